@@ -2,22 +2,26 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{format_err, Result};
-use aptos_gas_meter::{StandardGasAlgebra, StandardGasMeter};
+use aptos_gas_meter::{AptosGasMeter, StandardGasAlgebra, StandardGasMeter};
 use aptos_gas_profiling::{GasProfiler, TransactionGasLog};
-use aptos_gas_schedule::{MiscGasParameters, NativeGasParameters, LATEST_GAS_FEATURE_VERSION};
+use aptos_gas_schedule::{
+    AptosGasParameters, FromOnChainGasSchedule, MiscGasParameters, NativeGasParameters,
+    LATEST_GAS_FEATURE_VERSION,
+};
 use aptos_memory_usage_tracker::MemoryTrackedGasMeter;
 use aptos_resource_viewer::{AnnotatedAccountStateBlob, AptosValueAnnotator};
 use aptos_rest_client::Client;
 use aptos_types::{
     account_address::AccountAddress,
     chain_id::ChainId,
-    on_chain_config::{Features, OnChainConfig, TimedFeaturesBuilder},
+    on_chain_config::{FeatureFlag, Features, GasScheduleV2, OnChainConfig, TimedFeaturesBuilder},
     state_store::TStateView,
     transaction::{
-        signature_verified_transaction::SignatureVerifiedTransaction, SignedTransaction,
+        signature_verified_transaction::SignatureVerifiedTransaction, ChangeSet, SignedTransaction,
         Transaction, TransactionInfo, TransactionOutput, TransactionPayload, Version,
     },
     vm_status::VMStatus,
+    write_set::WriteSetMut,
 };
 use aptos_validator_interface::{
     AptosValidatorInterface, DBDebuggerInterface, DebuggerStateView, RestDebuggerInterface,
@@ -25,13 +29,18 @@ use aptos_validator_interface::{
 use aptos_vm::{
     data_cache::AsMoveResolver,
     move_vm_ext::{MoveVmExt, SessionExt, SessionId},
+    transaction_metadata::TransactionMetadata,
+    verifier::transaction_arg_validation::validate_combine_signer_and_txn_args,
     AptosVM, VMExecutor,
 };
 use aptos_vm_logging::log_schema::AdapterLogSchema;
 use aptos_vm_types::{
-    change_set::VMChangeSet, output::VMOutput, storage::change_set_configs::ChangeSetConfigs,
+    change_set::VMChangeSet,
+    output::VMOutput,
+    storage::{change_set_configs::ChangeSetConfigs, StorageGasParameters},
 };
 use move_binary_format::errors::VMResult;
+use move_core_types::{transaction_argument::convert_txn_args, vm_status::StatusCode};
 use std::{path::Path, sync::Arc};
 
 pub struct AptosDebugger {
@@ -122,6 +131,74 @@ impl AptosDebugger {
         Ok((status, output, gas_profiler.finish()))
     }
 
+    /// Executes the payload of a user transaction at `version`, with the VM and gas budget it
+    /// had on chain, and returns the status it stopped with and the events and writes it made
+    /// until then. Unlike when executing the whole transaction, these are kept when the payload
+    /// fails, so they show what the transaction did up to the failure. The prologue, epilogue,
+    /// fees and publishing of code are left out, and aggregator deltas are not included.
+    pub fn execute_transaction_payload_at_version_keeping_effects(
+        &self,
+        version: Version,
+        txn: &SignedTransaction,
+    ) -> Result<(VMStatus, ChangeSet)> {
+        let state_view = DebuggerStateView::new(self.debugger.clone(), version);
+        let resolver = state_view.as_move_resolver();
+        let vm = AptosVM::new(
+            &resolver,
+            /*override_is_delayed_field_optimization_capable=*/ Some(false),
+        );
+        let features = Features::fetch_config(&resolver).unwrap_or_default();
+        let gas_schedule = GasScheduleV2::fetch_config(&resolver)
+            .ok_or_else(|| format_err!("No gas schedule at version {}", version))?;
+        let gas_params = AptosGasParameters::from_on_chain_gas_schedule(
+            &gas_schedule.to_btree_map(),
+            gas_schedule.feature_version,
+        )
+        .map_err(|err| format_err!("Invalid gas schedule at version {}: {}", version, err))?;
+        let storage_gas_params = StorageGasParameters::new(
+            gas_schedule.feature_version,
+            &features,
+            &gas_params,
+            &resolver,
+        );
+
+        let txn_data = TransactionMetadata::new(txn);
+        let mut gas_meter =
+            MemoryTrackedGasMeter::new(StandardGasMeter::new(StandardGasAlgebra::new(
+                gas_schedule.feature_version,
+                gas_params.vm,
+                storage_gas_params.clone(),
+                txn_data.max_gas_amount(),
+            )));
+        let mut session = vm.new_session(&resolver, SessionId::txn_meta(&txn_data));
+        let status = match execute_payload(
+            &mut session,
+            &mut gas_meter,
+            &txn_data,
+            txn.payload(),
+            features.is_enabled(FeatureFlag::STRUCT_CONSTRUCTORS),
+        ) {
+            Ok(()) => VMStatus::Executed,
+            Err(status) => status,
+        };
+        let change_set = session
+            .finish(&storage_gas_params.change_set_configs)
+            .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))?;
+
+        let write_set = WriteSetMut::new(
+            change_set
+                .concrete_write_set_iter()
+                .filter_map(|(key, op)| Some((key.clone(), op?.clone()))),
+        )
+        .freeze()?;
+        let events = change_set
+            .events()
+            .iter()
+            .map(|(event, _)| event.clone())
+            .collect();
+        Ok((status, ChangeSet::new(write_set, events)))
+    }
+
     pub async fn execute_past_transactions(
         &self,
         mut begin: Version,
@@ -238,6 +315,14 @@ impl AptosDebugger {
             .await
     }
 
+    pub async fn get_committed_transactions(
+        &self,
+        begin: Version,
+        limit: u64,
+    ) -> Result<(Vec<Transaction>, Vec<TransactionInfo>)> {
+        self.debugger.get_committed_transactions(begin, limit).await
+    }
+
     pub async fn get_committed_transaction_at_version(
         &self,
         version: Version,
@@ -285,6 +370,69 @@ impl AptosDebugger {
     }
 }
 
+/// Executes a script or entry function the way the VM does for a user transaction, charging
+/// the intrinsic gas first.
+fn execute_payload(
+    session: &mut SessionExt,
+    gas_meter: &mut impl AptosGasMeter,
+    txn_data: &TransactionMetadata,
+    payload: &TransactionPayload,
+    struct_constructors_enabled: bool,
+) -> Result<(), VMStatus> {
+    gas_meter
+        .charge_intrinsic_gas_for_transaction(txn_data.transaction_size())
+        .map_err(|err| err.into_vm_status())?;
+    match payload {
+        TransactionPayload::Script(script) => {
+            let function = session
+                .load_script(script.code(), script.ty_args().to_vec())
+                .map_err(|err| err.into_vm_status())?;
+            let args = validate_combine_signer_and_txn_args(
+                session,
+                txn_data.senders(),
+                convert_txn_args(script.args()),
+                &function,
+                struct_constructors_enabled,
+            )?;
+            session
+                .execute_script(script.code(), script.ty_args().to_vec(), args, gas_meter)
+                .map_err(|err| err.into_vm_status())?;
+        },
+        TransactionPayload::EntryFunction(entry_function) => {
+            let function = session
+                .load_function(
+                    entry_function.module(),
+                    entry_function.function(),
+                    entry_function.ty_args(),
+                )
+                .map_err(|err| err.into_vm_status())?;
+            let args = validate_combine_signer_and_txn_args(
+                session,
+                txn_data.senders(),
+                entry_function.args().to_vec(),
+                &function,
+                struct_constructors_enabled,
+            )?;
+            session
+                .execute_entry_function(
+                    entry_function.module(),
+                    entry_function.function(),
+                    entry_function.ty_args().to_vec(),
+                    args,
+                    gas_meter,
+                )
+                .map_err(|err| err.into_vm_status())?;
+        },
+        TransactionPayload::Multisig(_) | TransactionPayload::ModuleBundle(_) => {
+            return Err(VMStatus::error(
+                StatusCode::FEATURE_UNDER_GATING,
+                Some("Only scripts and entry functions can be executed on their own".to_string()),
+            ))
+        },
+    }
+    Ok(())
+}
+
 fn is_reconfiguration(vm_output: &TransactionOutput) -> bool {
     let new_epoch_event_key = aptos_types::on_chain_config::new_epoch_event_key();
     vm_output
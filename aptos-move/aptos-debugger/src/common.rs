@@ -1,8 +1,9 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{execute_past_transactions, execute_pending_block};
+use crate::{diff_block, execute_past_transactions, execute_pending_block};
 use anyhow::Result;
+use aptos_types::{resource_usage::TransactionResourceUsage, transaction::TransactionOutput};
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -29,12 +30,30 @@ pub struct Opts {
 
     #[clap(long, default_value_t = 1)]
     pub(crate) concurrency_level: usize,
+
+    /// Print the resources used by the re-executed user transactions.
+    #[clap(long)]
+    pub(crate) report_resource_usage: bool,
+}
+
+pub(crate) fn print_resource_usage(txn_outputs: &[TransactionOutput]) {
+    let mut total = TransactionResourceUsage::default();
+    let mut num_txns = 0;
+    for usage in txn_outputs
+        .iter()
+        .filter_map(TransactionOutput::resource_usage)
+    {
+        total.accumulate(usage);
+        num_txns += 1;
+    }
+    println!("Resource usage of {num_txns} user transactions: {total:#?}");
 }
 
 #[derive(Parser)]
 pub enum Command {
     ExecutePastTransactions(execute_past_transactions::Command),
     ExecutePendingBlock(execute_pending_block::Command),
+    DiffBlock(diff_block::Command),
 }
 
 impl Command {
@@ -42,6 +61,7 @@ impl Command {
         match self {
             Command::ExecutePastTransactions(cmd) => cmd.run().await,
             Command::ExecutePendingBlock(cmd) => cmd.run().await,
+            Command::DiffBlock(cmd) => cmd.run().await,
         }
     }
 }
@@ -0,0 +1,676 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replays a single committed block under two VM configurations and reports every difference
+//! between the two sets of outputs. Used to qualify feature flag flips and gas schedule changes
+//! against real traffic before they are proposed on chain.
+
+use crate::{aptos_debugger::AptosDebugger, common::Target};
+use anyhow::{bail, format_err, Result};
+use aptos_backup_cli::{
+    coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    metadata::cache::MetadataCacheOpt,
+    storage::{
+        command_adapter::{CommandAdapter, CommandAdapterOpt},
+        local_fs::{LocalFs, LocalFsOpt},
+        BackupStorage,
+    },
+    utils::{
+        ConcurrentDownloadsOpt, GlobalRestoreOpt, ReplayConcurrencyLevelOpt, RocksdbOpt,
+        TrustedWaypointOpt,
+    },
+};
+use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
+use aptos_logger::warn;
+use aptos_rest_client::Client;
+use aptos_types::{
+    block_executor::config::BlockExecutorConfig,
+    on_chain_config::{FeatureFlag, Features, GasScheduleV2, OnChainConfig},
+    state_store::{
+        state_key::StateKey, state_storage_usage::StateStorageUsage, state_value::StateValue,
+        Result as StateViewResult, StateViewId, TStateView,
+    },
+    transaction::{
+        signature_verified_transaction::SignatureVerifiedTransaction, Transaction,
+        TransactionOutput, Version,
+    },
+    vm_status::VMStatus,
+};
+use aptos_vm::{
+    aptos_vm::RAYON_EXEC_POOL,
+    block_executor::{AptosTransactionOutput, BlockAptosVM},
+};
+use clap::Parser;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use url::Url;
+
+/// A VM configuration to replay the block with. Everything not mentioned is taken from the
+/// on-chain state at the version the block was executed at.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplayConfig {
+    /// Feature flags to turn on, on top of the on-chain `Features`, by name (e.g.
+    /// `VM_BINARY_FORMAT_V7`).
+    pub enable_features: Vec<FeatureFlag>,
+    /// Feature flags to turn off, on top of the on-chain `Features`, by name.
+    pub disable_features: Vec<FeatureFlag>,
+    /// Gas schedule entries to override, by on-chain name (e.g. `txn.min_transaction_gas_units`).
+    pub gas_params: BTreeMap<String, u64>,
+    /// Number of threads for the block executor; 1 executes the block sequentially.
+    pub concurrency_level: usize,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            enable_features: vec![],
+            disable_features: vec![],
+            gas_params: BTreeMap::new(),
+            concurrency_level: 1,
+        }
+    }
+}
+
+impl ReplayConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_yaml::from_str(&contents)
+            .map_err(|err| format_err!("Failed to parse replay config {:?}: {}", path, err))
+    }
+
+    /// Computes the state values this configuration overrides in `base`.
+    fn overrides<S: TStateView<Key = StateKey>>(
+        &self,
+        base: &S,
+    ) -> Result<HashMap<StateKey, StateValue>> {
+        let mut overrides = HashMap::new();
+
+        if !self.enable_features.is_empty() || !self.disable_features.is_empty() {
+            let mut features: Features =
+                fetch_config(base)?.ok_or_else(|| format_err!("Features are not set on chain"))?;
+            for flag in &self.enable_features {
+                features.enable(*flag);
+            }
+            for flag in &self.disable_features {
+                features.disable(*flag);
+            }
+            insert_config(&mut overrides, &features)?;
+        }
+
+        if !self.gas_params.is_empty() {
+            let mut gas_schedule: GasScheduleV2 = fetch_config(base)?
+                .ok_or_else(|| format_err!("GasScheduleV2 is not set on chain"))?;
+            for (name, value) in &self.gas_params {
+                match gas_schedule
+                    .entries
+                    .iter_mut()
+                    .find(|(entry, _)| entry == name)
+                {
+                    Some((_, entry_value)) => *entry_value = *value,
+                    None => bail!("Unknown gas parameter {}", name),
+                }
+            }
+            insert_config(&mut overrides, &gas_schedule)?;
+        }
+
+        Ok(overrides)
+    }
+}
+
+fn fetch_config<T: OnChainConfig, S: TStateView<Key = StateKey>>(base: &S) -> Result<Option<T>> {
+    let state_key = StateKey::access_path(T::access_path()?);
+    base.get_state_value(&state_key)?
+        .map(|value| T::deserialize_into_config(value.bytes()))
+        .transpose()
+}
+
+fn insert_config<T: OnChainConfig + serde::Serialize>(
+    overrides: &mut HashMap<StateKey, StateValue>,
+    config: &T,
+) -> Result<()> {
+    overrides.insert(
+        StateKey::access_path(T::access_path()?),
+        StateValue::new_legacy(bcs::to_bytes(config)?.into()),
+    );
+    Ok(())
+}
+
+/// State view that serves a fixed set of values on top of another state view.
+struct OverrideStateView<'a, S> {
+    base: &'a S,
+    overrides: HashMap<StateKey, StateValue>,
+}
+
+impl<'a, S: TStateView<Key = StateKey>> TStateView for OverrideStateView<'a, S> {
+    type Key = StateKey;
+
+    fn id(&self) -> StateViewId {
+        self.base.id()
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> StateViewResult<Option<StateValue>> {
+        match self.overrides.get(state_key) {
+            Some(value) => Ok(Some(value.clone())),
+            None => self.base.get_state_value(state_key),
+        }
+    }
+
+    fn get_usage(&self) -> StateViewResult<StateStorageUsage> {
+        self.base.get_usage()
+    }
+}
+
+/// A single difference between the baseline and candidate output of a transaction.
+#[derive(Debug)]
+pub enum OutputDiff {
+    Status { baseline: String, candidate: String },
+    GasUsed { baseline: u64, candidate: u64 },
+    WriteOnlyInBaseline(StateKey),
+    WriteOnlyInCandidate(StateKey),
+    WriteValue(StateKey),
+    NumEvents { baseline: usize, candidate: usize },
+    Event(usize),
+}
+
+impl fmt::Display for OutputDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputDiff::Status {
+                baseline,
+                candidate,
+            } => write!(f, "status: {} vs {}", baseline, candidate),
+            OutputDiff::GasUsed {
+                baseline,
+                candidate,
+            } => write!(f, "gas used: {} vs {}", baseline, candidate),
+            OutputDiff::WriteOnlyInBaseline(key) => {
+                write!(f, "write only in baseline: {:?}", key)
+            },
+            OutputDiff::WriteOnlyInCandidate(key) => {
+                write!(f, "write only in candidate: {:?}", key)
+            },
+            OutputDiff::WriteValue(key) => write!(f, "different write: {:?}", key),
+            OutputDiff::NumEvents {
+                baseline,
+                candidate,
+            } => write!(f, "number of events: {} vs {}", baseline, candidate),
+            OutputDiff::Event(idx) => write!(f, "different event #{}", idx),
+        }
+    }
+}
+
+/// Compares the outputs of the same transaction executed under the two configurations.
+pub fn diff_outputs(
+    baseline: &TransactionOutput,
+    candidate: &TransactionOutput,
+) -> Vec<OutputDiff> {
+    let mut diffs = vec![];
+
+    if baseline.status() != candidate.status() {
+        diffs.push(OutputDiff::Status {
+            baseline: format!("{:?}", baseline.status()),
+            candidate: format!("{:?}", candidate.status()),
+        });
+    }
+    if baseline.gas_used() != candidate.gas_used() {
+        diffs.push(OutputDiff::GasUsed {
+            baseline: baseline.gas_used(),
+            candidate: candidate.gas_used(),
+        });
+    }
+
+    let baseline_writes: BTreeMap<_, _> = baseline.write_set().iter().collect();
+    let candidate_writes: BTreeMap<_, _> = candidate.write_set().iter().collect();
+    let keys: BTreeSet<_> = baseline_writes
+        .keys()
+        .chain(candidate_writes.keys())
+        .collect();
+    for key in keys {
+        match (baseline_writes.get(*key), candidate_writes.get(*key)) {
+            (Some(_), None) => diffs.push(OutputDiff::WriteOnlyInBaseline((*key).clone())),
+            (None, Some(_)) => diffs.push(OutputDiff::WriteOnlyInCandidate((*key).clone())),
+            (Some(lhs), Some(rhs)) if lhs != rhs => {
+                diffs.push(OutputDiff::WriteValue((*key).clone()))
+            },
+            _ => (),
+        }
+    }
+
+    if baseline.events().len() != candidate.events().len() {
+        diffs.push(OutputDiff::NumEvents {
+            baseline: baseline.events().len(),
+            candidate: candidate.events().len(),
+        });
+    }
+    for (idx, (lhs, rhs)) in baseline.events().iter().zip(candidate.events()).enumerate() {
+        if lhs != rhs {
+            diffs.push(OutputDiff::Event(idx));
+        }
+    }
+
+    diffs
+}
+
+/// Backup storage to restore the block and the state it executed on from, for blocks that were
+/// pruned from the nodes at hand.  They are restored into `--db-path`, which is then replayed
+/// from as usual, so that a dir restored before is reused.
+#[derive(Parser)]
+#[clap(group(clap::ArgGroup::new("backup")
+        .multiple(false)
+        .requires("db_path")
+        .args(&["backup_local_fs_dir", "backup_command_adapter_config"]),
+))]
+pub struct BackupSource {
+    /// Restore from the backups in a local dir.
+    #[clap(long)]
+    backup_local_fs_dir: Option<LocalFsOpt>,
+
+    /// Restore from the backup storage the command adapter config talks to.
+    #[clap(long)]
+    backup_command_adapter_config: Option<CommandAdapterOpt>,
+
+    #[clap(flatten)]
+    metadata_cache_opt: MetadataCacheOpt,
+
+    #[clap(flatten)]
+    trusted_waypoints: TrustedWaypointOpt,
+
+    #[clap(flatten)]
+    rocksdb_opt: RocksdbOpt,
+
+    #[clap(flatten)]
+    concurrent_downloads: ConcurrentDownloadsOpt,
+
+    #[clap(flatten)]
+    replay_concurrency_level: ReplayConcurrencyLevelOpt,
+}
+
+impl BackupSource {
+    async fn init_storage(&self) -> Result<Option<Arc<dyn BackupStorage>>> {
+        Ok(if let Some(opt) = &self.backup_local_fs_dir {
+            Some(Arc::new(LocalFs::new_with_opt(opt.clone())))
+        } else if let Some(opt) = &self.backup_command_adapter_config {
+            Some(Arc::new(CommandAdapter::new_with_opt(opt.clone()).await?))
+        } else {
+            None
+        })
+    }
+
+    /// Restores the `num_versions` transactions from `begin_version` on into `db_dir`, along
+    /// with the state they executed on.  Does nothing without a backup storage.
+    async fn restore(
+        &self,
+        db_dir: &Path,
+        begin_version: Version,
+        num_versions: u64,
+    ) -> Result<()> {
+        let Some(storage) = self.init_storage().await? else {
+            return Ok(());
+        };
+        let global_opt = GlobalRestoreOpt {
+            dry_run: false,
+            db_dir: Some(db_dir.to_path_buf()),
+            target_version: Some(begin_version + num_versions.max(1) - 1),
+            trusted_waypoints: self.trusted_waypoints.clone(),
+            rocksdb_opt: self.rocksdb_opt.clone(),
+            concurrent_downloads: self.concurrent_downloads,
+            replay_concurrency_level: self.replay_concurrency_level,
+        };
+        let opt = RestoreCoordinatorOpt {
+            metadata_cache_opt: self.metadata_cache_opt.clone(),
+            replay_all: false,
+            // The block executes on the state of the version right before it.
+            ledger_history_start_version: Some(begin_version.saturating_sub(1)),
+            skip_epoch_endings: false,
+        };
+        RestoreCoordinator::new(opt, global_opt.try_into()?, storage)
+            .run()
+            .await
+    }
+}
+
+#[derive(Parser)]
+pub struct Command {
+    #[clap(flatten)]
+    target: Target,
+
+    #[clap(flatten)]
+    backup: BackupSource,
+
+    /// Version of the block metadata transaction that starts the block to replay.
+    #[clap(long)]
+    begin_version: Version,
+
+    /// Upper bound on the number of transactions fetched for the block.
+    #[clap(long, default_value_t = 10000)]
+    max_block_size: u64,
+
+    /// YAML `ReplayConfig` for the baseline run. Defaults to the on-chain configuration.
+    #[clap(long)]
+    baseline: Option<PathBuf>,
+
+    /// YAML `ReplayConfig` for the candidate run. Defaults to the on-chain configuration.
+    #[clap(long)]
+    candidate: Option<PathBuf>,
+
+    /// Exit with an error if the two runs produce different outputs.
+    #[clap(long)]
+    fail_on_diff: bool,
+}
+
+impl Command {
+    pub async fn run(self) -> Result<()> {
+        if let Some(db_path) = &self.target.db_path {
+            self.backup
+                .restore(db_path, self.begin_version, self.max_block_size)
+                .await?;
+        }
+        let debugger = if let Some(rest_endpoint) = self.target.rest_endpoint {
+            AptosDebugger::rest_client(Client::new(Url::parse(&rest_endpoint)?))?
+        } else if let Some(db_path) = self.target.db_path {
+            AptosDebugger::db(db_path)?
+        } else {
+            unreachable!("Must provide one target.");
+        };
+        let baseline = match &self.baseline {
+            Some(path) => ReplayConfig::load(path)?,
+            None => ReplayConfig::default(),
+        };
+        let candidate = match &self.candidate {
+            Some(path) => ReplayConfig::load(path)?,
+            None => ReplayConfig::default(),
+        };
+
+        let txns = fetch_block(&debugger, self.begin_version, self.max_block_size).await?;
+        println!(
+            "Replaying block at version {} with {} transactions",
+            self.begin_version,
+            txns.len()
+        );
+
+        let baseline_outputs = execute_block(&debugger, self.begin_version, &txns, &baseline)?;
+        let candidate_outputs = execute_block(&debugger, self.begin_version, &txns, &candidate)?;
+
+        let mut num_diverged = 0;
+        for (idx, (lhs, rhs)) in baseline_outputs
+            .iter()
+            .zip(candidate_outputs.iter())
+            .enumerate()
+        {
+            let diffs = diff_outputs(lhs, rhs);
+            if diffs.is_empty() {
+                continue;
+            }
+            num_diverged += 1;
+            println!("Transaction {}:", self.begin_version + idx as Version);
+            for diff in diffs {
+                println!("    {}", diff);
+            }
+        }
+        println!(
+            "{} out of {} transactions diverged",
+            num_diverged,
+            txns.len()
+        );
+
+        if self.fail_on_diff && num_diverged > 0 {
+            bail!("Baseline and candidate outputs differ");
+        }
+        Ok(())
+    }
+}
+
+/// Fetches the transactions of the block starting at `begin`, up to the next block start.
+async fn fetch_block(
+    debugger: &AptosDebugger,
+    begin: Version,
+    max_block_size: u64,
+) -> Result<Vec<Transaction>> {
+    let (txns, _) = debugger
+        .get_committed_transactions(begin, max_block_size)
+        .await?;
+    take_block(txns, begin, max_block_size)
+}
+
+/// Keeps the transactions of the block that starts at `begin`, out of the transactions fetched
+/// from there.
+fn take_block(
+    mut txns: Vec<Transaction>,
+    begin: Version,
+    max_block_size: u64,
+) -> Result<Vec<Transaction>> {
+    if !txns.first().map_or(false, is_block_start) {
+        bail!("Transaction at version {} does not start a block", begin);
+    }
+    match txns.iter().skip(1).position(is_block_start) {
+        Some(end) => txns.truncate(end + 1),
+        // Without the start of the next block, the block may go on past what was fetched.
+        None if txns.len() as u64 >= max_block_size => warn!(
+            "Block at version {} may have more than {} transactions, only the first {} are \
+             replayed. Increase --max-block-size to replay the whole block.",
+            begin,
+            max_block_size,
+            txns.len()
+        ),
+        None => (),
+    }
+    Ok(txns)
+}
+
+fn is_block_start(txn: &Transaction) -> bool {
+    matches!(
+        txn,
+        Transaction::BlockMetadata(_)
+            | Transaction::BlockMetadataExt(_)
+            | Transaction::GenesisTransaction(_)
+    )
+}
+
+fn execute_block(
+    debugger: &AptosDebugger,
+    version: Version,
+    txns: &[Transaction],
+    config: &ReplayConfig,
+) -> Result<Vec<TransactionOutput>> {
+    let base = debugger.state_view_at_version(version);
+    let state_view = OverrideStateView {
+        overrides: config.overrides(&base)?,
+        base: &base,
+    };
+    let txns: Vec<SignatureVerifiedTransaction> = txns.iter().cloned().map(Into::into).collect();
+
+    BlockAptosVM::execute_block::<_, NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>>(
+        Arc::clone(&RAYON_EXEC_POOL),
+        &txns,
+        &state_view,
+        BlockExecutorConfig::new_no_block_limit(config.concurrency_level),
+        None,
+    )
+    .map(|output| output.into_transaction_outputs_forced())
+    .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::HashValue;
+    use aptos_types::{
+        account_address::AccountAddress,
+        block_metadata::BlockMetadata,
+        contract_event::ContractEvent,
+        on_chain_config::FeatureFlag,
+        state_store::in_memory_state_view::InMemoryStateView,
+        transaction::{ExecutionStatus, TransactionStatus},
+        write_set::{WriteOp, WriteSetMut},
+    };
+    use move_core_types::language_storage::TypeTag;
+
+    fn key(idx: u8) -> StateKey {
+        StateKey::raw(vec![idx])
+    }
+
+    fn output(
+        writes: Vec<(StateKey, &'static [u8])>,
+        events: Vec<ContractEvent>,
+        gas_used: u64,
+        status: ExecutionStatus,
+    ) -> TransactionOutput {
+        let write_set = WriteSetMut::new(
+            writes
+                .into_iter()
+                .map(|(key, value)| (key, WriteOp::legacy_modification(value.into()))),
+        )
+        .freeze()
+        .unwrap();
+        TransactionOutput::new(write_set, events, gas_used, TransactionStatus::Keep(status))
+    }
+
+    fn event(data: u8) -> ContractEvent {
+        ContractEvent::new_v2(TypeTag::U8, vec![data])
+    }
+
+    fn block_start(round: u64) -> Transaction {
+        Transaction::BlockMetadata(BlockMetadata::new(
+            HashValue::zero(),
+            0,
+            round,
+            AccountAddress::ONE,
+            vec![],
+            vec![],
+            0,
+        ))
+    }
+
+    fn txn() -> Transaction {
+        Transaction::StateCheckpoint(HashValue::zero())
+    }
+
+    #[test]
+    fn test_diff_identical_outputs() {
+        let lhs = output(
+            vec![(key(0), b"a")],
+            vec![event(0)],
+            10,
+            ExecutionStatus::Success,
+        );
+        assert!(diff_outputs(&lhs, &lhs.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_outputs() {
+        let baseline = output(
+            vec![(key(0), b"a"), (key(1), b"b"), (key(2), b"c")],
+            vec![event(0), event(1)],
+            10,
+            ExecutionStatus::Success,
+        );
+        let candidate = output(
+            vec![(key(1), b"b"), (key(2), b"d"), (key(3), b"e")],
+            vec![event(0), event(2), event(3)],
+            12,
+            ExecutionStatus::OutOfGas,
+        );
+
+        let diffs: Vec<_> = diff_outputs(&baseline, &candidate)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            diffs,
+            vec![
+                "status: Keep(Success) vs Keep(OutOfGas)".to_string(),
+                "gas used: 10 vs 12".to_string(),
+                format!("write only in baseline: {:?}", key(0)),
+                format!("different write: {:?}", key(2)),
+                format!("write only in candidate: {:?}", key(3)),
+                "number of events: 2 vs 3".to_string(),
+                "different event #1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_take_block() {
+        let txns = vec![block_start(1), txn(), txn(), block_start(2), txn()];
+        assert_eq!(take_block(txns, 0, 10).unwrap().len(), 3);
+
+        // The last block of the fetched range is kept whole, whether truncated or not.
+        let txns = vec![block_start(1), txn(), txn()];
+        assert_eq!(take_block(txns.clone(), 0, 3).unwrap().len(), 3);
+        assert_eq!(take_block(txns, 0, 10).unwrap().len(), 3);
+
+        assert!(take_block(vec![txn(), block_start(1)], 0, 10).is_err());
+        assert!(take_block(vec![], 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut features = Features::default();
+        features.disable(FeatureFlag::VM_BINARY_FORMAT_V7);
+        let gas_schedule = GasScheduleV2 {
+            feature_version: 1,
+            entries: vec![("txn.min_transaction_gas_units".to_string(), 100)],
+        };
+        let mut state = HashMap::new();
+        insert_config(&mut state, &features).unwrap();
+        insert_config(&mut state, &gas_schedule).unwrap();
+        let base = InMemoryStateView::new(state);
+
+        let config = ReplayConfig {
+            enable_features: vec![FeatureFlag::VM_BINARY_FORMAT_V7],
+            disable_features: vec![FeatureFlag::CODE_DEPENDENCY_CHECK],
+            gas_params: BTreeMap::from([("txn.min_transaction_gas_units".to_string(), 7)]),
+            concurrency_level: 1,
+        };
+        let view = OverrideStateView {
+            overrides: config.overrides(&base).unwrap(),
+            base: &base,
+        };
+
+        let features: Features = fetch_config(&view).unwrap().unwrap();
+        assert!(features.is_enabled(FeatureFlag::VM_BINARY_FORMAT_V7));
+        assert!(!features.is_enabled(FeatureFlag::CODE_DEPENDENCY_CHECK));
+        let gas_schedule: GasScheduleV2 = fetch_config(&view).unwrap().unwrap();
+        assert_eq!(
+            gas_schedule.entries,
+            vec![("txn.min_transaction_gas_units".to_string(), 7)]
+        );
+
+        // Nothing is overridden by default.
+        assert!(ReplayConfig::default().overrides(&base).unwrap().is_empty());
+
+        let config = ReplayConfig {
+            gas_params: BTreeMap::from([("txn.unknown".to_string(), 7)]),
+            ..ReplayConfig::default()
+        };
+        assert!(config.overrides(&base).is_err());
+    }
+
+    #[test]
+    fn test_parse_replay_config() {
+        let config: ReplayConfig = serde_yaml::from_str(
+            "enable_features: [VM_BINARY_FORMAT_V7]\ndisable_features: [CODE_DEPENDENCY_CHECK]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.enable_features,
+            vec![FeatureFlag::VM_BINARY_FORMAT_V7]
+        );
+        assert_eq!(
+            config.disable_features,
+            vec![FeatureFlag::CODE_DEPENDENCY_CHECK]
+        );
+        assert_eq!(config.concurrency_level, 1);
+
+        assert!(
+            serde_yaml::from_str::<ReplayConfig>("enable_features: [UNKNOWN_FEATURE]").is_err()
+        );
+        assert!(serde_yaml::from_str::<ReplayConfig>("enable_features: [1]").is_err());
+    }
+}
@@ -1,7 +1,10 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{aptos_debugger::AptosDebugger, common::Opts};
+use crate::{
+    aptos_debugger::AptosDebugger,
+    common::{print_resource_usage, Opts},
+};
 use anyhow::Result;
 use aptos_rest_client::Client;
 use aptos_vm::AptosVM;
@@ -26,6 +29,7 @@ pub struct Command {
 impl Command {
     pub async fn run(self) -> Result<()> {
         AptosVM::set_concurrency_level_once(self.opts.concurrency_level);
+        AptosVM::set_resource_usage_tracking(self.opts.report_resource_usage);
 
         let debugger = if let Some(rest_endpoint) = self.opts.target.rest_endpoint {
             AptosDebugger::rest_client(Client::new(Url::parse(&rest_endpoint)?))?
@@ -42,6 +46,9 @@ impl Command {
         if !self.skip_result {
             println!("{result:#?}",);
         }
+        if self.opts.report_resource_usage {
+            print_resource_usage(&result);
+        }
 
         Ok(())
     }
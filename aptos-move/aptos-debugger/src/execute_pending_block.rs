@@ -1,7 +1,10 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{aptos_debugger::AptosDebugger, common::Opts};
+use crate::{
+    aptos_debugger::AptosDebugger,
+    common::{print_resource_usage, Opts},
+};
 use anyhow::Result;
 use aptos_crypto::HashValue;
 use aptos_logger::info;
@@ -40,6 +43,7 @@ pub struct Command {
 impl Command {
     pub async fn run(self) -> Result<()> {
         AptosVM::set_concurrency_level_once(self.opts.concurrency_level);
+        AptosVM::set_resource_usage_tracking(self.opts.report_resource_usage);
 
         let debugger = if let Some(rest_endpoint) = self.opts.target.rest_endpoint {
             AptosDebugger::rest_client(Client::new(Url::parse(&rest_endpoint)?))?
@@ -86,6 +90,9 @@ impl Command {
 
         let txn_outputs = debugger.execute_transactions_at_version(self.begin_version, block)?;
         println!("{txn_outputs:#?}");
+        if self.opts.report_resource_usage {
+            print_resource_usage(&txn_outputs);
+        }
 
         Ok(())
     }
@@ -4,5 +4,6 @@
 pub mod aptos_debugger;
 pub mod bcs_txn_decoder;
 pub mod common;
+pub mod diff_block;
 pub mod execute_past_transactions;
 pub mod execute_pending_block;
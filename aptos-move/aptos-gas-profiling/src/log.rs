@@ -42,7 +42,7 @@ pub enum ExecutionGasEvent {
 
 /// An enum representing the name of a call frame.
 /// Could be either a script or a function.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum FrameName {
     Script,
     Function {
@@ -122,6 +122,9 @@ pub struct StorageFees {
 pub struct TransactionGasLog {
     pub exec_io: ExecutionAndIOCosts,
     pub storage: StorageFees,
+    /// The frames that had not returned when execution ended, outermost first. If the
+    /// transaction aborted, this is the call stack at the abort.
+    pub call_stack_at_exit: Vec<FrameName>,
 }
 
 pub struct GasEventIter<'a> {
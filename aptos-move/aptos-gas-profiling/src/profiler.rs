@@ -605,6 +605,8 @@ where
     G: AptosGasMeter,
 {
     pub fn finish(mut self) -> TransactionGasLog {
+        let call_stack_at_exit = self.frames.iter().map(|frame| frame.name.clone()).collect();
+
         while self.frames.len() > 1 {
             let cur = self.frames.pop().expect("frame must exist");
             let last = self.frames.last_mut().expect("frame must exist");
@@ -630,6 +632,10 @@ where
         });
         storage.assert_consistency();
 
-        TransactionGasLog { exec_io, storage }
+        TransactionGasLog {
+            exec_io,
+            storage,
+            call_stack_at_exit,
+        }
     }
 }
@@ -7,6 +7,7 @@ use aptos_language_e2e_tests::{account::Account, executor::FakeExecutor};
 use aptos_types::{move_utils::MemberId, transaction::TransactionPayload};
 use move_binary_format::CompiledModule;
 use move_core_types::{account_address::AccountAddress, language_storage::ModuleId};
+use move_vm_runtime::move_vm::MoveVM;
 use std::{fs::ReadDir, path::PathBuf, string::String, time::Instant};
 
 // CONSTANTS
@@ -60,10 +61,27 @@ pub fn execute_module_txn(
 }
 
 // sign user transaction and only records the body of the transaction
-pub fn execute_user_txn(executor: &mut FakeExecutor, module_name: &ModuleId, function_name: &str) {
+pub fn execute_user_txn(
+    executor: &mut FakeExecutor,
+    module_name: &ModuleId,
+    function_name: &str,
+    flush_module_cache: bool,
+) {
+    if flush_module_cache {
+        MoveVM::flush_verified_module_cache();
+    }
+    let before = MoveVM::verified_module_cache_stats();
     let elapsed =
         executor.exec_func_record_running_time(module_name, function_name, vec![], vec![], 10);
+    let after = MoveVM::verified_module_cache_stats();
     println!("running time (microseconds): {}", elapsed);
+    println!(
+        "module cache: {} hits, {} misses ({} modules, {} bytes cached)",
+        after.hits - before.hits,
+        after.misses - before.misses,
+        after.num_modules,
+        after.size_bytes,
+    );
 }
 
 // publish module under user and sign user transaction
@@ -73,6 +91,7 @@ pub fn publish(
     func_identifiers: Vec<String>,
     address: AccountAddress,
     identifier: &String,
+    flush_module_cache: bool,
 ) {
     //// publish test-package under module address
     let creator = executor.new_account_at(address);
@@ -95,7 +114,7 @@ pub fn publish(
         //// send a txn that invokes the entry function 0x{address}::{name}::benchmark
         print!("Signing user txn... ");
         let module_name = get_module_name(address, identifier, &func_identifier);
-        execute_user_txn(executor, &module_name, &func_identifier);
+        execute_user_txn(executor, &module_name, &func_identifier, flush_module_cache);
     }
 }
 
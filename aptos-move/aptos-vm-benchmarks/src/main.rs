@@ -13,6 +13,10 @@ use std::{fs::read_dir, path::PathBuf};
 struct Cli {
     #[clap(default_value = "")]
     pattern: String,
+
+    /// Empty the cache of verified modules before each benchmark, to measure cold module loading.
+    #[clap(long)]
+    flush_module_cache: bool,
 }
 
 fn main() {
@@ -71,6 +75,7 @@ fn main() {
                 func_identifiers,
                 *address,
                 identifier,
+                args.flush_module_cache,
             )
         }
     }
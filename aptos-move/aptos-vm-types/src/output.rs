@@ -7,6 +7,7 @@ use aptos_types::{
     contract_event::ContractEvent, //contract_event::ContractEvent,
     delayed_fields::PanicError,
     fee_statement::FeeStatement,
+    resource_usage::TransactionResourceUsage,
     state_store::state_key::StateKey,
     transaction::{TransactionOutput, TransactionStatus},
     write_set::WriteOp,
@@ -22,6 +23,7 @@ pub struct VMOutput {
     change_set: VMChangeSet,
     fee_statement: FeeStatement,
     status: TransactionStatus,
+    resource_usage: Option<TransactionResourceUsage>,
 }
 
 impl VMOutput {
@@ -34,6 +36,7 @@ impl VMOutput {
             change_set,
             fee_statement,
             status,
+            resource_usage: None,
         }
     }
 
@@ -42,6 +45,7 @@ impl VMOutput {
             change_set: VMChangeSet::empty(),
             fee_statement: FeeStatement::zero(),
             status,
+            resource_usage: None,
         }
     }
 
@@ -73,6 +77,14 @@ impl VMOutput {
         &self.status
     }
 
+    pub fn resource_usage(&self) -> Option<&TransactionResourceUsage> {
+        self.resource_usage.as_ref()
+    }
+
+    pub fn set_resource_usage(&mut self, resource_usage: TransactionResourceUsage) {
+        self.resource_usage = Some(resource_usage);
+    }
+
     /// Materializes delta sets.
     /// Guarantees that if deltas are materialized successfully, the output
     /// has an empty delta set.
@@ -116,8 +128,10 @@ impl VMOutput {
 
     /// Constructs `TransactionOutput`, without doing `try_materialize`
     pub fn into_transaction_output(self) -> anyhow::Result<TransactionOutput, VMStatus> {
+        let resource_usage = self.resource_usage;
         let (change_set, fee_statement, status) = self.unpack_with_fee_statement();
-        let output = VMOutput::new(change_set, fee_statement, status);
+        let mut output = VMOutput::new(change_set, fee_statement, status);
+        output.resource_usage = resource_usage;
         Self::convert_to_transaction_output(output).map_err(|e| {
             VMStatus::error(
                 StatusCode::DELAYED_MATERIALIZATION_CODE_INVARIANT_ERROR,
@@ -129,9 +143,11 @@ impl VMOutput {
     fn convert_to_transaction_output(
         materialized_output: VMOutput,
     ) -> Result<TransactionOutput, PanicError> {
+        let resource_usage = materialized_output.resource_usage;
         let (vm_change_set, gas_used, status) = materialized_output.unpack();
         let (write_set, events) = vm_change_set.try_into_storage_change_set()?.into_inner();
-        Ok(TransactionOutput::new(write_set, events, gas_used, status))
+        Ok(TransactionOutput::new(write_set, events, gas_used, status)
+            .with_resource_usage(resource_usage))
     }
 
     /// Updates the VMChangeSet based on the input aggregator v1 deltas, patched resource write set,
@@ -13,6 +13,7 @@ use crate::{
         RespawnedSession, SessionExt, SessionId,
     },
     oidb_validation,
+    resource_usage::ResourceUsageTracker,
     sharded_block_executor::{executor_client::ExecutorClient, ShardedBlockExecutor},
     system_module_names::*,
     transaction_metadata::TransactionMetadata,
@@ -35,6 +36,7 @@ use aptos_types::{
     account_config::{new_block_event_key, AccountResource},
     block_executor::{
         config::{BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig},
+        conflict_history::ConflictHistory,
         partitioner::PartitionedTransactions,
     },
     block_metadata::BlockMetadata,
@@ -51,9 +53,9 @@ use aptos_types::{
     transaction::{
         authenticator::AnySignature, signature_verified_transaction::SignatureVerifiedTransaction,
         BlockOutput, EntryFunction, ExecutionError, ExecutionStatus, ModuleBundle, Multisig,
-        MultisigTransactionPayload, Script, SignatureCheckedTransaction, SignedTransaction,
-        Transaction, TransactionOutput, TransactionPayload, TransactionStatus, VMValidatorResult,
-        ViewFunctionOutput, WriteSetPayload,
+        MultisigTransactionPayload, ReplayProtector, Script, SignatureCheckedTransaction,
+        SignedTransaction, Transaction, TransactionOutput, TransactionPayload, TransactionStatus,
+        VMValidatorResult, ViewFunctionOutput, WriteSetPayload,
     },
     vm_status::{AbortLocation, StatusCode, VMStatus},
 };
@@ -93,7 +95,9 @@ use std::{
     cmp::{max, min},
     collections::{BTreeMap, BTreeSet},
     marker::Sync,
+    path::PathBuf,
     sync::Arc,
+    time::Instant,
 };
 
 static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
@@ -101,7 +105,10 @@ static NUM_EXECUTION_SHARD: OnceCell<usize> = OnceCell::new();
 static NUM_PROOF_READING_THREADS: OnceCell<usize> = OnceCell::new();
 static PARANOID_TYPE_CHECKS: OnceCell<bool> = OnceCell::new();
 static DISCARD_FAILED_BLOCKS: OnceCell<bool> = OnceCell::new();
+static EXECUTION_TRACE_DIR: OnceCell<PathBuf> = OnceCell::new();
+static CONFLICT_HISTORY: OnceCell<Arc<ConflictHistory>> = OnceCell::new();
 static PROCESSED_TRANSACTIONS_DETAILED_COUNTERS: OnceCell<bool> = OnceCell::new();
+static RESOURCE_USAGE_TRACKING: OnceCell<bool> = OnceCell::new();
 static TIMED_FEATURE_OVERRIDE: OnceCell<TimedFeatureOverride> = OnceCell::new();
 
 // TODO: Don't expose this in AptosVM, and use only in BlockAptosVM!
@@ -301,6 +308,29 @@ impl AptosVM {
         }
     }
 
+    /// Sets the directory that parallel execution traces are written to, when invoked the first time.
+    pub fn set_execution_trace_dir_once(dir: PathBuf) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        EXECUTION_TRACE_DIR.set(dir).ok();
+    }
+
+    /// Get the execution trace directory if already set, otherwise tracing is disabled.
+    pub fn get_execution_trace_dir() -> Option<PathBuf> {
+        EXECUTION_TRACE_DIR.get().cloned()
+    }
+
+    /// Sets the history that the conflicts of every executed block are recorded into, when
+    /// invoked the first time.
+    pub fn set_conflict_history_once(conflict_history: Arc<ConflictHistory>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        CONFLICT_HISTORY.set(conflict_history).ok();
+    }
+
+    /// Get the conflict history if already set, otherwise conflicts are not recorded.
+    pub fn get_conflict_history() -> Option<Arc<ConflictHistory>> {
+        CONFLICT_HISTORY.get().cloned()
+    }
+
     // Set the override profile for timed features.
     pub fn set_timed_feature_override(profile: TimedFeatureOverride) {
         TIMED_FEATURE_OVERRIDE.set(profile).ok();
@@ -341,6 +371,21 @@ impl AptosVM {
         }
     }
 
+    /// Sets whether user transaction outputs report their resource usage, when invoked the first
+    /// time.
+    pub fn set_resource_usage_tracking(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        RESOURCE_USAGE_TRACKING.set(enable).ok();
+    }
+
+    /// Get whether user transaction outputs report their resource usage.
+    pub fn get_resource_usage_tracking() -> bool {
+        match RESOURCE_USAGE_TRACKING.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
     /// Returns the internal gas schedule if it has been loaded, or an error if it hasn't.
     #[cfg(any(test, feature = "testing"))]
     pub fn gas_params(&self) -> Result<&AptosGasParameters, VMStatus> {
@@ -489,29 +534,54 @@ impl AptosVM {
 
         let is_account_init_for_sponsored_transaction =
             is_account_init_for_sponsored_transaction(txn_data, self.features(), resolver)?;
+        // The nonce of an orderless transaction is used up even if the transaction aborts, just
+        // like the sequence number of a regular one.
+        let orderless_nonce = match txn_data.replay_protector() {
+            ReplayProtector::Nonce(nonce) => Some(nonce),
+            ReplayProtector::SequenceNumber(_) => None,
+        };
 
-        if is_account_init_for_sponsored_transaction {
+        if is_account_init_for_sponsored_transaction || orderless_nonce.is_some() {
             let mut session = self.new_session(resolver, SessionId::run_on_abort(txn_data));
             let status = self.inject_abort_info_if_available(status);
 
-            create_account_if_does_not_exist(&mut session, gas_meter, txn_data.sender())
-                // if this fails, it is likely due to out of gas, so we try again without metering
-                // and then validate below that we charged sufficiently.
-                .or_else(|_err| {
-                    create_account_if_does_not_exist(
-                        &mut session,
-                        &mut UnmeteredGasMeter,
-                        txn_data.sender(),
-                    )
-                })
-                .map_err(expect_no_verification_errors)
-                .or_else(|err| {
-                    expect_only_successful_execution(
-                        err,
-                        &format!("{:?}::{}", ACCOUNT_MODULE, CREATE_ACCOUNT_IF_DOES_NOT_EXIST),
-                        log_context,
-                    )
-                })?;
+            if is_account_init_for_sponsored_transaction {
+                create_account_if_does_not_exist(&mut session, gas_meter, txn_data.sender())
+                    // if this fails, it is likely due to out of gas, so we try again without
+                    // metering and then validate below that we charged sufficiently.
+                    .or_else(|_err| {
+                        create_account_if_does_not_exist(
+                            &mut session,
+                            &mut UnmeteredGasMeter,
+                            txn_data.sender(),
+                        )
+                    })
+                    .map_err(expect_no_verification_errors)
+                    .or_else(|err| {
+                        expect_only_successful_execution(
+                            err,
+                            &format!("{:?}::{}", ACCOUNT_MODULE, CREATE_ACCOUNT_IF_DOES_NOT_EXIST),
+                            log_context,
+                        )
+                    })?;
+            }
+
+            if let Some(nonce) = orderless_nonce {
+                record_nonce(&mut session, gas_meter, txn_data, nonce)
+                    // As above, the transaction may be out of gas, in which case the storage fee
+                    // below is charged as far as the remaining gas allows.
+                    .or_else(|_err| {
+                        record_nonce(&mut session, &mut UnmeteredGasMeter, txn_data, nonce)
+                    })
+                    .map_err(expect_no_verification_errors)
+                    .or_else(|err| {
+                        expect_only_successful_execution(
+                            err,
+                            &format!("{:?}::{}", NONCE_VALIDATION_MODULE, RECORD_NONCE),
+                            log_context,
+                        )
+                    })?;
+            }
 
             let mut change_set = session.finish(change_set_configs)?;
             if let Err(err) = self.charge_change_set(&mut change_set, gas_meter, txn_data, resolver)
@@ -540,7 +610,7 @@ impl AptosVM {
             );
             // With the current gas schedule actual is expected to be 50_000 or higher, the cost
             // for the account slot.
-            if actual < expected {
+            if is_account_init_for_sponsored_transaction && actual < expected {
                 expect_only_successful_execution(
                     PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
                         .with_message(
@@ -1397,6 +1467,22 @@ impl AptosVM {
             log_context
         ));
 
+        // Orderless transactions remember their nonce as part of the user session, so that the
+        // storage it takes is charged like any other write of the transaction.
+        if let ReplayProtector::Nonce(nonce) = txn_data.replay_protector() {
+            if let Err(err) = record_nonce(&mut session, gas_meter, &txn_data, nonce) {
+                return self.on_user_transaction_execution_failure(
+                    err.into_vm_status(),
+                    resolver,
+                    &txn_data,
+                    log_context,
+                    gas_meter,
+                    &storage_gas_params,
+                    false,
+                );
+            }
+        }
+
         // We keep track of whether any newly published modules are loaded into the Vm's loader
         // cache as part of executing transactions. This would allow us to decide whether the cache
         // should be flushed later.
@@ -1478,6 +1564,15 @@ impl AptosVM {
         // TODO: would we end up having a diverging behavior by creating the gas meter at an earlier time?
         let mut gas_meter = unwrap_or_discard!(self.make_standard_gas_meter(balance, log_context));
 
+        if Self::get_resource_usage_tracking() {
+            let timer = Instant::now();
+            let mut gas_meter = ResourceUsageTracker::new(gas_meter);
+            let (status, mut output) =
+                self.execute_user_transaction_impl(resolver, txn, log_context, &mut gas_meter);
+            output.set_resource_usage(gas_meter.finish(timer.elapsed()));
+            return (status, output);
+        }
+
         self.execute_user_transaction_impl(resolver, txn, log_context, &mut gas_meter)
     }
 
@@ -1893,6 +1988,14 @@ impl AptosVM {
         }
     }
 
+    /// Marks the loader cache as invalid, so that it gets flushed before the next session
+    /// is created. The loader cache is not multi-versioned, hence the block executor uses
+    /// this after a transaction publishes modules, to avoid serving the published (possibly
+    /// speculative) modules from the cache to other transactions of the block.
+    pub(crate) fn mark_loader_cache_as_invalid(&self) {
+        self.move_vm.mark_loader_cache_as_invalid();
+    }
+
     pub fn should_restart_execution(vm_change_set: &VMChangeSet) -> bool {
         let new_epoch_event_key = new_epoch_event_key();
         vm_change_set
@@ -2068,6 +2171,8 @@ impl VMExecutor for AptosVM {
                     concurrency_level: Self::get_concurrency_level(),
                     allow_fallback: true,
                     discard_failed_blocks: Self::get_discard_failed_blocks(),
+                    execution_trace_dir: Self::get_execution_trace_dir(),
+                    conflict_history: Self::get_conflict_history(),
                 },
                 onchain: onchain_config,
             },
@@ -2244,6 +2349,28 @@ fn create_account_if_does_not_exist(
         .map(|_return_vals| ())
 }
 
+/// Remembers the nonce of an orderless transaction until the transaction expires.
+fn record_nonce(
+    session: &mut SessionExt,
+    gas_meter: &mut impl GasMeter,
+    txn_data: &TransactionMetadata,
+    nonce: u64,
+) -> VMResult<()> {
+    session
+        .execute_function_bypass_visibility(
+            &NONCE_VALIDATION_MODULE,
+            RECORD_NONCE,
+            vec![],
+            serialize_values(&vec![
+                MoveValue::Signer(txn_data.sender()),
+                MoveValue::U64(nonce),
+                MoveValue::U64(txn_data.expiration_timestamp_secs()),
+            ]),
+            gas_meter,
+        )
+        .map(|_return_vals| ())
+}
+
 /// Signals that the transaction should trigger the flow for creating an account as part of a
 /// sponsored transaction. This occurs when:
 /// * The feature gate is enabled SPONSORED_AUTOMATIC_ACCOUNT_V1_CREATION
@@ -9,15 +9,19 @@ use aptos_block_executor::task::{ExecutionStatus, ExecutorTask};
 use aptos_logger::{enabled, Level};
 use aptos_mvhashmap::types::TxnIndex;
 use aptos_types::{
-    state_store::StateView,
+    access_path::Path,
+    executable::ModulePath,
+    state_store::{state_key::StateKey, StateView},
     transaction::{
         signature_verified_transaction::SignatureVerifiedTransaction, Transaction, WriteSetPayload,
     },
+    write_set::WriteOp,
 };
 use aptos_vm_logging::{log_schema::AdapterLogSchema, prelude::*};
 use aptos_vm_types::resolver::{ExecutorView, ResourceGroupView};
 use fail::fail_point;
 use move_core_types::vm_status::{StatusCode, VMStatus};
+use move_vm_runtime::move_vm::MoveVM;
 
 pub(crate) struct AptosExecutorTask<'a, S> {
     vm: AptosVM,
@@ -65,6 +69,11 @@ impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
             .execute_single_transaction(txn, &resolver, &log_context)
         {
             Ok((vm_status, vm_output)) => {
+                if !vm_output.change_set().module_write_set().is_empty() {
+                    // Modules published by the transaction may have been loaded into the
+                    // loader cache, while other transactions must read them via the view.
+                    self.vm.mark_loader_cache_as_invalid();
+                }
                 if vm_output.status().is_discarded() {
                     speculative_trace!(
                         &log_context,
@@ -126,4 +135,10 @@ impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
         }
         true
     }
+
+    fn on_module_committed(key: &StateKey, write_op: &WriteOp) {
+        if let Some(Path::Code(module_id)) = key.module_path().map(|ap| ap.get_path()) {
+            MoveVM::invalidate_verified_module(&module_id, write_op.bytes().map(|b| b.as_ref()));
+        }
+    }
 }
@@ -35,7 +35,7 @@ use aptos_vm_types::{
     resource_group_adapter::ResourceGroupAdapter,
 };
 use bytes::Bytes;
-use move_binary_format::{deserializer::DeserializerConfig, errors::*, CompiledModule};
+use move_binary_format::{deserializer::DeserializerConfig, errors::*};
 use move_core_types::{
     account_address::AccountAddress,
     language_storage::{ModuleId, StructTag},
@@ -43,6 +43,7 @@ use move_core_types::{
     resolver::{resource_size, ModuleResolver, ResourceResolver},
     value::MoveTypeLayout,
 };
+use move_vm_runtime::move_vm::MoveVM;
 use move_vm_types::delayed_values::delayed_field_id::DelayedFieldID;
 use std::{
     cell::RefCell,
@@ -50,7 +51,7 @@ use std::{
     sync::Arc,
 };
 
-pub(crate) fn get_resource_group_from_metadata(
+pub fn get_resource_group_from_metadata(
     struct_tag: &StructTag,
     metadata: &[Metadata],
 ) -> Option<StructTag> {
@@ -206,13 +207,10 @@ impl<'e, E: ExecutorView> ModuleResolver for StorageAdapter<'e, E> {
             Ok(Some(bytes)) => bytes,
             _ => return vec![],
         };
-        let module =
-            match CompiledModule::deserialize_with_config(&module_bytes, &self.deserializer_config)
-            {
-                Ok(module) => module,
-                _ => return vec![],
-            };
-        module.metadata
+        match MoveVM::get_module_metadata(&module_bytes, &self.deserializer_config) {
+            Some(metadata) => metadata.as_ref().clone(),
+            None => vec![],
+        }
     }
 
     fn get_module(&self, module_id: &ModuleId) -> Result<Option<Bytes>, Self::Error> {
@@ -35,6 +35,10 @@ pub const ESEQUENCE_NUMBER_TOO_BIG: u64 = 1008;
 pub const ESECONDARY_KEYS_ADDRESSES_COUNT_MISMATCH: u64 = 1009;
 // Gas payer account missing in gas payer tx
 pub const EGAS_PAYER_ACCOUNT_MISSING: u64 = 1010;
+// Nonce of an orderless transaction was already used by an unexpired transaction.
+pub const ENONCE_ALREADY_USED: u64 = 1011;
+// Orderless transaction expires further in the future than its nonce is remembered.
+pub const EEXPIRATION_TOO_FAR_IN_FUTURE: u64 = 1012;
 
 // Specified account is not a multisig account.
 const EACCOUNT_NOT_MULTISIG: u64 = 2002;
@@ -123,6 +127,10 @@ pub fn convert_prologue_error(
                 (INVALID_ARGUMENT, EGAS_PAYER_ACCOUNT_MISSING) => {
                     StatusCode::GAS_PAYER_ACCOUNT_MISSING
                 },
+                (INVALID_ARGUMENT, ENONCE_ALREADY_USED) => StatusCode::SEQUENCE_NONCE_INVALID,
+                (INVALID_ARGUMENT, EEXPIRATION_TOO_FAR_IN_FUTURE) => {
+                    StatusCode::TRANSACTION_EXPIRATION_TOO_FAR_IN_FUTURE
+                },
                 (category, reason) => {
                     let err_msg = format!("[aptos_vm] Unexpected prologue Move abort: {:?}::{:?} (Category: {:?} Reason: {:?})",
                     location, code, category, reason);
@@ -112,6 +112,7 @@ mod gas;
 pub mod move_vm_ext;
 pub mod natives;
 mod oidb_validation;
+mod resource_usage;
 pub mod sharded_block_executor;
 pub mod system_module_names;
 pub mod testing;
@@ -0,0 +1,425 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_gas_algebra::{Fee, FeePerGasUnit, InternalGas, NumArgs, NumBytes, NumTypeNodes};
+use aptos_gas_meter::AptosGasMeter;
+use aptos_types::{
+    access_path::AccessPath,
+    resource_usage::TransactionResourceUsage,
+    state_store::state_key::{StateKey, StateKeyInner},
+    write_set::WriteOpSize,
+};
+use move_binary_format::{
+    errors::{PartialVMResult, VMResult},
+    file_format::CodeOffset,
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{ModuleId, TypeTag},
+};
+use move_vm_types::{
+    gas::{GasMeter as MoveGasMeter, SimpleInstruction},
+    views::{TypeView, ValueView},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+/// Gas meter that records the resources used by a transaction, on top of a base gas meter.
+///
+/// Storage accounting is restricted to Move resources, so that reads and writes are comparable:
+/// reads are observed when a resource is loaded from storage by the Move VM, and writes when the
+/// change set is charged. Table items and modules are not visible to the gas meter on reads, so
+/// they are skipped on writes too. If the change set is charged more than once, the last charge
+/// of a key wins.
+pub(crate) struct ResourceUsageTracker<G> {
+    base: G,
+
+    bytes_read: u64,
+    keys_read: HashSet<StateKey>,
+    bytes_written: HashMap<StateKey, u64>,
+    num_native_calls: u64,
+}
+
+impl<G> ResourceUsageTracker<G>
+where
+    G: AptosGasMeter,
+{
+    pub(crate) fn new(base: G) -> Self {
+        Self {
+            base,
+            bytes_read: 0,
+            keys_read: HashSet::new(),
+            bytes_written: HashMap::new(),
+            num_native_calls: 0,
+        }
+    }
+
+    pub(crate) fn finish(&self, execution_time: Duration) -> TransactionResourceUsage {
+        let num_state_keys_touched = self
+            .bytes_written
+            .keys()
+            .filter(|key| !self.keys_read.contains(*key))
+            .count()
+            + self.keys_read.len();
+        TransactionResourceUsage {
+            bytes_read: self.bytes_read,
+            bytes_written: self.bytes_written.values().sum(),
+            num_state_keys_touched: num_state_keys_touched as u64,
+            num_native_calls: self.num_native_calls,
+            execution_time,
+        }
+    }
+}
+
+fn is_resource(key: &StateKey) -> bool {
+    match key.inner() {
+        StateKeyInner::AccessPath(access_path) => !access_path.is_code(),
+        StateKeyInner::TableItem { .. } | StateKeyInner::Raw(_) => false,
+    }
+}
+
+macro_rules! delegate {
+    ($(
+        fn $fn: ident $(<$($lt: lifetime),*>)? (&self $(, $arg: ident : $ty: ty)* $(,)?) -> $ret_ty: ty;
+    )*) => {
+        $(fn $fn $(<$($lt)*>)? (&self, $($arg: $ty),*) -> $ret_ty {
+            self.base.$fn($($arg),*)
+        })*
+    };
+}
+
+macro_rules! delegate_mut {
+    ($(
+        fn $fn: ident $(<$($lt: lifetime),*>)? (&mut self $(, $arg: ident : $ty: ty)* $(,)?) -> $ret_ty: ty;
+    )*) => {
+        $(fn $fn $(<$($lt)*>)? (&mut self, $($arg: $ty),*) -> $ret_ty {
+            self.base.$fn($($arg),*)
+        })*
+    };
+}
+
+impl<G> MoveGasMeter for ResourceUsageTracker<G>
+where
+    G: AptosGasMeter,
+{
+    delegate! {
+        fn balance_internal(&self) -> InternalGas;
+    }
+
+    delegate_mut! {
+        fn charge_simple_instr(&mut self, instr: SimpleInstruction) -> PartialVMResult<()>;
+
+        fn charge_br_true(&mut self, target_offset: Option<CodeOffset>) -> PartialVMResult<()>;
+
+        fn charge_br_false(&mut self, target_offset: Option<CodeOffset>) -> PartialVMResult<()>;
+
+        fn charge_branch(&mut self, target_offset: CodeOffset) -> PartialVMResult<()>;
+
+        fn charge_pop(&mut self, popped_val: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_call(
+            &mut self,
+            module_id: &ModuleId,
+            func_name: &str,
+            args: impl ExactSizeIterator<Item = impl ValueView> + Clone,
+            num_locals: NumArgs,
+        ) -> PartialVMResult<()>;
+
+        fn charge_call_generic(
+            &mut self,
+            module_id: &ModuleId,
+            func_name: &str,
+            ty_args: impl ExactSizeIterator<Item = impl TypeView> + Clone,
+            args: impl ExactSizeIterator<Item = impl ValueView> + Clone,
+            num_locals: NumArgs,
+        ) -> PartialVMResult<()>;
+
+        fn charge_ld_const(&mut self, size: NumBytes) -> PartialVMResult<()>;
+
+        fn charge_ld_const_after_deserialization(&mut self, val: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_copy_loc(&mut self, val: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_move_loc(&mut self, val: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_store_loc(&mut self, val: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_pack(
+            &mut self,
+            is_generic: bool,
+            args: impl ExactSizeIterator<Item = impl ValueView> + Clone,
+        ) -> PartialVMResult<()>;
+
+        fn charge_unpack(
+            &mut self,
+            is_generic: bool,
+            args: impl ExactSizeIterator<Item = impl ValueView> + Clone,
+        ) -> PartialVMResult<()>;
+
+        fn charge_read_ref(&mut self, val: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_write_ref(
+            &mut self,
+            new_val: impl ValueView,
+            old_val: impl ValueView,
+        ) -> PartialVMResult<()>;
+
+        fn charge_eq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_neq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()>;
+
+        fn charge_borrow_global(
+            &mut self,
+            is_mut: bool,
+            is_generic: bool,
+            ty: impl TypeView,
+            is_success: bool,
+        ) -> PartialVMResult<()>;
+
+        fn charge_exists(
+            &mut self,
+            is_generic: bool,
+            ty: impl TypeView,
+            exists: bool,
+        ) -> PartialVMResult<()>;
+
+        fn charge_move_from(
+            &mut self,
+            is_generic: bool,
+            ty: impl TypeView,
+            val: Option<impl ValueView>,
+        ) -> PartialVMResult<()>;
+
+        fn charge_move_to(
+            &mut self,
+            is_generic: bool,
+            ty: impl TypeView,
+            val: impl ValueView,
+            is_success: bool,
+        ) -> PartialVMResult<()>;
+
+        fn charge_vec_len(&mut self, ty: impl TypeView) -> PartialVMResult<()>;
+
+        fn charge_vec_borrow(
+            &mut self,
+            is_mut: bool,
+            ty: impl TypeView,
+            is_success: bool,
+        ) -> PartialVMResult<()>;
+
+        fn charge_vec_push_back(
+            &mut self,
+            ty: impl TypeView,
+            val: impl ValueView,
+        ) -> PartialVMResult<()>;
+
+        fn charge_vec_pop_back(
+            &mut self,
+            ty: impl TypeView,
+            val: Option<impl ValueView>,
+        ) -> PartialVMResult<()>;
+
+        fn charge_vec_unpack(
+            &mut self,
+            ty: impl TypeView,
+            expect_num_elements: NumArgs,
+            elems: impl ExactSizeIterator<Item = impl ValueView> + Clone,
+        ) -> PartialVMResult<()>;
+
+        fn charge_vec_swap(&mut self, ty: impl TypeView) -> PartialVMResult<()>;
+
+        fn charge_native_function_before_execution(
+            &mut self,
+            ty_args: impl ExactSizeIterator<Item = impl TypeView> + Clone,
+            args: impl ExactSizeIterator<Item = impl ValueView> + Clone,
+        ) -> PartialVMResult<()>;
+
+        fn charge_drop_frame(
+            &mut self,
+            locals: impl Iterator<Item = impl ValueView> + Clone,
+        ) -> PartialVMResult<()>;
+
+        fn charge_create_ty(&mut self, num_nodes: NumTypeNodes) -> PartialVMResult<()>;
+    }
+
+    #[inline]
+    fn charge_vec_pack<'a>(
+        &mut self,
+        ty: impl TypeView + 'a,
+        args: impl ExactSizeIterator<Item = impl ValueView> + Clone,
+    ) -> PartialVMResult<()> {
+        self.base.charge_vec_pack(ty, args)
+    }
+
+    #[inline]
+    fn charge_load_resource(
+        &mut self,
+        addr: AccountAddress,
+        ty: impl TypeView,
+        val: Option<impl ValueView>,
+        bytes_loaded: NumBytes,
+    ) -> PartialVMResult<()> {
+        self.bytes_read += u64::from(bytes_loaded);
+        if let TypeTag::Struct(struct_tag) = ty.to_type_tag() {
+            if let Ok(access_path) = AccessPath::resource_access_path(addr, *struct_tag) {
+                self.keys_read.insert(StateKey::access_path(access_path));
+            }
+        }
+
+        self.base.charge_load_resource(addr, ty, val, bytes_loaded)
+    }
+
+    #[inline]
+    fn charge_native_function(
+        &mut self,
+        amount: InternalGas,
+        ret_vals: Option<impl ExactSizeIterator<Item = impl ValueView> + Clone>,
+    ) -> PartialVMResult<()> {
+        self.num_native_calls += 1;
+
+        self.base.charge_native_function(amount, ret_vals)
+    }
+}
+
+impl<G> AptosGasMeter for ResourceUsageTracker<G>
+where
+    G: AptosGasMeter,
+{
+    type Algebra = G::Algebra;
+
+    delegate! {
+        fn algebra(&self) -> &Self::Algebra;
+    }
+
+    delegate_mut! {
+        fn algebra_mut(&mut self) -> &mut Self::Algebra;
+
+        fn charge_storage_fee(
+            &mut self,
+            amount: Fee,
+            gas_unit_price: FeePerGasUnit,
+        ) -> PartialVMResult<()>;
+
+        fn charge_intrinsic_gas_for_transaction(&mut self, txn_size: NumBytes) -> VMResult<()>;
+    }
+
+    #[inline]
+    fn charge_io_gas_for_write(&mut self, key: &StateKey, op: &WriteOpSize) -> VMResult<()> {
+        if is_resource(key) {
+            let bytes_written = op
+                .write_len()
+                .map_or(0, |write_len| key.size() as u64 + write_len);
+            self.bytes_written.insert(key.clone(), bytes_written);
+        }
+
+        self.base.charge_io_gas_for_write(key, op)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_gas_algebra::Gas;
+    use aptos_gas_meter::{StandardGasAlgebra, StandardGasMeter};
+    use aptos_gas_schedule::{InitialGasSchedule, VMGasParameters, LATEST_GAS_FEATURE_VERSION};
+    use aptos_types::state_store::table::TableHandle;
+    use aptos_vm_types::storage::StorageGasParameters;
+    use move_core_types::{identifier::Identifier, language_storage::StructTag};
+    use move_vm_types::values::Value;
+
+    struct Tag(TypeTag);
+
+    impl TypeView for Tag {
+        fn to_type_tag(&self) -> TypeTag {
+            self.0.clone()
+        }
+    }
+
+    fn tracker() -> ResourceUsageTracker<StandardGasMeter<StandardGasAlgebra>> {
+        ResourceUsageTracker::new(StandardGasMeter::new(StandardGasAlgebra::new(
+            LATEST_GAS_FEATURE_VERSION,
+            VMGasParameters::initial(),
+            StorageGasParameters::unlimited(NumBytes::zero()),
+            Gas::new(1_000_000),
+        )))
+    }
+
+    fn struct_tag(name: &str) -> StructTag {
+        StructTag {
+            address: AccountAddress::ONE,
+            module: Identifier::new("test").unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_params: vec![],
+        }
+    }
+
+    fn resource_key(addr: AccountAddress, tag: StructTag) -> StateKey {
+        StateKey::access_path(AccessPath::resource_access_path(addr, tag).unwrap())
+    }
+
+    #[test]
+    fn test_reads_and_writes_cover_resources_only() {
+        let mut tracker = tracker();
+        let addr = AccountAddress::random();
+        tracker
+            .charge_load_resource(
+                addr,
+                Tag(TypeTag::Struct(Box::new(struct_tag("A")))),
+                Some(Value::u64(0)),
+                NumBytes::new(10),
+            )
+            .unwrap();
+        tracker
+            .charge_load_resource(
+                addr,
+                Tag(TypeTag::Struct(Box::new(struct_tag("B")))),
+                None::<Value>,
+                NumBytes::zero(),
+            )
+            .unwrap();
+
+        // The write of A overlaps with its read, B is only written, the table item and the
+        // module are not accounted for.
+        let a = resource_key(addr, struct_tag("A"));
+        let b = resource_key(addr, struct_tag("B"));
+        let table_item = StateKey::table_item(TableHandle(AccountAddress::random()), vec![1]);
+        let module = StateKey::access_path(AccessPath::code_access_path(ModuleId::new(
+            addr,
+            Identifier::new("test").unwrap(),
+        )));
+        for (key, write_len) in [(&a, 20), (&b, 30), (&table_item, 40), (&module, 50)] {
+            tracker
+                .charge_io_gas_for_write(key, &WriteOpSize::Modification { write_len })
+                .unwrap();
+        }
+
+        let usage = tracker.finish(Duration::from_micros(5));
+        assert_eq!(usage.bytes_read, 10);
+        assert_eq!(
+            usage.bytes_written,
+            a.size() as u64 + 20 + b.size() as u64 + 30
+        );
+        assert_eq!(usage.num_state_keys_touched, 2);
+        assert_eq!(usage.num_native_calls, 0);
+        assert_eq!(usage.execution_time, Duration::from_micros(5));
+    }
+
+    #[test]
+    fn test_last_write_charge_wins() {
+        let mut tracker = tracker();
+        let key = resource_key(AccountAddress::random(), struct_tag("A"));
+
+        tracker
+            .charge_io_gas_for_write(&key, &WriteOpSize::Creation { write_len: 100 })
+            .unwrap();
+        tracker
+            .charge_io_gas_for_write(&key, &WriteOpSize::Deletion)
+            .unwrap();
+
+        let usage = tracker.finish(Duration::ZERO);
+        assert_eq!(usage.bytes_written, 0);
+        assert_eq!(usage.num_state_keys_touched, 1);
+    }
+}
@@ -64,6 +64,8 @@ impl<S: StateView + Sync + Send + 'static> GlobalExecutor<S> {
                     concurrency_level: self.concurrency_level,
                     allow_fallback: true,
                     discard_failed_blocks: false,
+                    execution_trace_dir: None,
+                    conflict_history: None,
                 },
                 onchain: onchain_config,
             },
@@ -233,6 +233,8 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                                 concurrency_level: concurrency_level_per_shard,
                                 allow_fallback: true,
                                 discard_failed_blocks: false,
+                                execution_trace_dir: None,
+                                conflict_history: None,
                             },
                             onchain: onchain_config,
                         },
@@ -18,6 +18,15 @@ pub static ACCOUNT_MODULE: Lazy<ModuleId> = Lazy::new(|| {
 pub const CREATE_ACCOUNT_IF_DOES_NOT_EXIST: &IdentStr =
     ident_str!("create_account_if_does_not_exist");
 
+pub static NONCE_VALIDATION_MODULE: Lazy<ModuleId> = Lazy::new(|| {
+    ModuleId::new(
+        account_config::CORE_CODE_ADDRESS,
+        ident_str!("nonce_validation").to_owned(),
+    )
+});
+
+pub const RECORD_NONCE: &IdentStr = ident_str!("record_nonce");
+
 // Data to resolve basic account and transaction flow functions and structs
 /// The ModuleId for the aptos block module
 pub static BLOCK_MODULE: Lazy<ModuleId> = Lazy::new(|| {
@@ -7,7 +7,7 @@ use aptos_gas_algebra::{FeePerGasUnit, Gas, NumBytes};
 use aptos_types::{
     account_address::AccountAddress,
     chain_id::ChainId,
-    transaction::{SignedTransaction, TransactionPayload},
+    transaction::{ReplayProtector, SignedTransaction, TransactionPayload},
 };
 
 pub struct TransactionMetadata {
@@ -104,6 +104,10 @@ impl TransactionMetadata {
         self.sequence_number
     }
 
+    pub fn replay_protector(&self) -> ReplayProtector {
+        ReplayProtector::from_sequence_number_field(self.sequence_number)
+    }
+
     pub fn transaction_size(&self) -> NumBytes {
         self.transaction_size
     }
@@ -13,8 +13,10 @@ use crate::{
 };
 use aptos_gas_algebra::Gas;
 use aptos_types::{
-    account_config::constants::CORE_CODE_ADDRESS, fee_statement::FeeStatement,
-    on_chain_config::Features, transaction::Multisig,
+    account_config::constants::CORE_CODE_ADDRESS,
+    fee_statement::FeeStatement,
+    on_chain_config::Features,
+    transaction::{Multisig, ReplayProtector},
 };
 use aptos_vm_logging::log_schema::AdapterLogSchema;
 use fail::fail_point;
@@ -40,6 +42,7 @@ pub static APTOS_TRANSACTION_VALIDATION: Lazy<TransactionValidation> =
         multi_agent_prologue_name: Identifier::new("multi_agent_script_prologue").unwrap(),
         user_epilogue_name: Identifier::new("epilogue").unwrap(),
         user_epilogue_gas_payer_name: Identifier::new("epilogue_gas_payer").unwrap(),
+        orderless_epilogue_name: Identifier::new("orderless_epilogue").unwrap(),
     });
 
 /// On-chain functions used to validate transactions
@@ -52,6 +55,7 @@ pub struct TransactionValidation {
     pub multi_agent_prologue_name: Identifier,
     pub user_epilogue_name: Identifier,
     pub user_epilogue_gas_payer_name: Identifier,
+    pub orderless_epilogue_name: Identifier,
 }
 
 impl TransactionValidation {
@@ -193,9 +197,26 @@ fn run_epilogue(
     let txn_gas_price = txn_data.gas_unit_price();
     let txn_max_gas_units = txn_data.max_gas_amount();
 
-    // We can unconditionally do this as this condition can only be true if the prologue
-    // accepted it, in which case the gas payer feature is enabled.
-    if let Some(fee_payer) = txn_data.fee_payer() {
+    // Orderless transactions can only pass the prologue if the feature is enabled. Their nonce
+    // was already recorded during execution, so there is no sequence number to increment.
+    if matches!(txn_data.replay_protector(), ReplayProtector::Nonce(_)) {
+        session.execute_function_bypass_visibility(
+            &APTOS_TRANSACTION_VALIDATION.module_id(),
+            &APTOS_TRANSACTION_VALIDATION.orderless_epilogue_name,
+            vec![],
+            serialize_values(&vec![
+                MoveValue::Signer(txn_data.sender),
+                MoveValue::Address(txn_data.fee_payer().unwrap_or(txn_data.sender)),
+                MoveValue::U64(fee_statement.storage_fee_refund()),
+                MoveValue::U64(txn_gas_price.into()),
+                MoveValue::U64(txn_max_gas_units.into()),
+                MoveValue::U64(gas_remaining.into()),
+            ]),
+            &mut UnmeteredGasMeter,
+        )
+    } else if let Some(fee_payer) = txn_data.fee_payer() {
+        // We can unconditionally do this as this condition can only be true if the prologue
+        // accepted it, in which case the gas payer feature is enabled.
         session.execute_function_bypass_visibility(
             &APTOS_TRANSACTION_VALIDATION.module_id(),
             &APTOS_TRANSACTION_VALIDATION.user_epilogue_gas_payer_name,
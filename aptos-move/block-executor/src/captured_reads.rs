@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{types::InputOutputKey, value_exchange::filter_value_for_exchange};
+use crate::{trace::InvalidRead, types::InputOutputKey, value_exchange::filter_value_for_exchange};
 use anyhow::bail;
 use aptos_aggregator::{
     delta_math::DeltaHistory,
@@ -12,16 +12,21 @@ use aptos_aggregator::{
 };
 use aptos_mvhashmap::{
     types::{
-        MVDataError, MVDataOutput, MVDelayedFieldsError, MVGroupError, StorageVersion, TxnIndex,
-        ValueWithLayout, Version,
+        MVDataError, MVDataOutput, MVDelayedFieldsError, MVGroupError, MVModulesError,
+        MVModulesOutput, StorageVersion, TxnIndex, ValueWithLayout, Version,
     },
     versioned_data::VersionedData,
     versioned_delayed_fields::TVersionedDelayedFieldView,
     versioned_group_data::VersionedGroupData,
+    versioned_modules::VersionedModules,
+    MVHashMap,
 };
 use aptos_types::{
-    delayed_fields::PanicError, state_store::state_value::StateValueMetadata,
-    transaction::BlockExecutableTransaction as Transaction, write_set::TransactionWrite,
+    delayed_fields::PanicError,
+    executable::{Executable, ExecutableDescriptor},
+    state_store::state_value::StateValueMetadata,
+    transaction::BlockExecutableTransaction as Transaction,
+    write_set::TransactionWrite,
 };
 use aptos_vm_types::resolver::ResourceGroupSize;
 use derivative::Derivative;
@@ -34,6 +39,7 @@ use std::{
         },
         BTreeMap, HashMap, HashSet,
     },
+    fmt::Debug,
     sync::Arc,
 };
 
@@ -298,9 +304,9 @@ impl DelayedFieldRead {
 pub(crate) struct CapturedReads<T: Transaction> {
     data_reads: HashMap<T::Key, DataRead<T::Value>>,
     group_reads: HashMap<T::Key, GroupRead<T>>,
-    // Currently, we record paths for triggering module R/W fallback.
-    // TODO: implement a general functionality once the fallback is removed.
-    pub(crate) module_reads: Vec<T::Key>,
+    // Module reads are identified by the descriptor of the module that was read: either
+    // the storage version, or the hash of the module published earlier in the block.
+    module_reads: HashMap<T::Key, ExecutableDescriptor>,
 
     delayed_field_reads: HashMap<T::Identifier, DelayedFieldRead>,
 
@@ -549,6 +555,61 @@ impl<T: Transaction> CapturedReads<T> {
         self.incorrect_use
     }
 
+    /// Captures a module read. If the same module was already read by the transaction
+    /// and observed a different version, the speculative execution is inconsistent and
+    /// an error is returned (the caller must halt the execution).
+    pub(crate) fn capture_module_read(
+        &mut self,
+        key: T::Key,
+        descriptor: ExecutableDescriptor,
+    ) -> anyhow::Result<()> {
+        match self.module_reads.entry(key) {
+            Occupied(entry) => {
+                if entry.get() != &descriptor {
+                    self.speculative_failure = true;
+                    bail!("Inconsistent module reads (must be due to speculation)");
+                }
+            },
+            Vacant(entry) => {
+                entry.insert(descriptor);
+            },
+        }
+        Ok(())
+    }
+
+    pub(crate) fn module_reads(&self) -> impl Iterator<Item = &T::Key> {
+        self.module_reads.keys()
+    }
+
+    fn validate_data_read(
+        data_map: &VersionedData<T::Key, T::Value>,
+        key: &T::Key,
+        read: &DataRead<T::Value>,
+        idx_to_validate: TxnIndex,
+    ) -> bool {
+        use MVDataError::*;
+        use MVDataOutput::*;
+        match data_map.fetch_data(key, idx_to_validate) {
+            Ok(Versioned(version, v)) => {
+                matches!(
+                    DataRead::from_value_with_layout(version, v).contains(read),
+                    DataReadComparison::Contains
+                )
+            },
+            Ok(Resolved(value)) => matches!(
+                DataRead::Resolved(value).contains(read),
+                DataReadComparison::Contains
+            ),
+            // Dependency implies a validation failure, and if the original read were to
+            // observe an unresolved delta, it would set the aggregator base value in the
+            // multi-versioned data-structure, resolve, and record the resolved value.
+            Err(Dependency(_))
+            | Err(Unresolved(_))
+            | Err(DeltaApplicationFailure)
+            | Err(Uninitialized) => false,
+        }
+    }
+
     pub(crate) fn validate_data_reads(
         &self,
         data_map: &VersionedData<T::Key, T::Value>,
@@ -558,27 +619,49 @@ impl<T: Transaction> CapturedReads<T> {
             return false;
         }
 
-        use MVDataError::*;
-        use MVDataOutput::*;
-        self.data_reads.iter().all(|(k, r)| {
-            match data_map.fetch_data(k, idx_to_validate) {
-                Ok(Versioned(version, v)) => {
+        self.data_reads
+            .iter()
+            .all(|(k, r)| Self::validate_data_read(data_map, k, r, idx_to_validate))
+    }
+
+    fn validate_group_read(
+        group_map: &VersionedGroupData<T::Key, T::Tag, T::Value>,
+        key: &T::Key,
+        group: &GroupRead<T>,
+        idx_to_validate: TxnIndex,
+    ) -> bool {
+        use MVGroupError::*;
+
+        let mut ret = true;
+        if let Some(size) = group.collected_size {
+            ret &= Ok(size) == group_map.get_group_size(key, idx_to_validate);
+        }
+
+        ret && group.inner_reads.iter().all(|(tag, r)| {
+            match group_map.fetch_tagged_data(key, tag, idx_to_validate) {
+                Ok((version, v)) => {
                     matches!(
                         DataRead::from_value_with_layout(version, v).contains(r),
                         DataReadComparison::Contains
                     )
                 },
-                Ok(Resolved(value)) => matches!(
-                    DataRead::Resolved(value).contains(r),
-                    DataReadComparison::Contains
-                ),
-                // Dependency implies a validation failure, and if the original read were to
-                // observe an unresolved delta, it would set the aggregator base value in the
-                // multi-versioned data-structure, resolve, and record the resolved value.
-                Err(Dependency(_))
-                | Err(Unresolved(_))
-                | Err(DeltaApplicationFailure)
-                | Err(Uninitialized) => false,
+                Err(TagNotFound) => {
+                    let sentinel_deletion =
+                        Arc::<T::Value>::new(TransactionWrite::from_state_value(None));
+                    assert!(sentinel_deletion.is_deletion());
+                    matches!(
+                        DataRead::Versioned(Err(StorageVersion), sentinel_deletion, None)
+                            .contains(r),
+                        DataReadComparison::Contains
+                    )
+                },
+                Err(Dependency(_)) => false,
+                Err(Uninitialized) => {
+                    unreachable!("May not be uninitialized if captured for validation");
+                },
+                Err(TagSerializationError(_)) => {
+                    unreachable!("Should not require tag serialization");
+                },
             }
         })
     }
@@ -588,46 +671,137 @@ impl<T: Transaction> CapturedReads<T> {
         group_map: &VersionedGroupData<T::Key, T::Tag, T::Value>,
         idx_to_validate: TxnIndex,
     ) -> bool {
-        use MVGroupError::*;
+        if self.speculative_failure {
+            return false;
+        }
+
+        self.group_reads
+            .iter()
+            .all(|(key, group)| Self::validate_group_read(group_map, key, group, idx_to_validate))
+    }
+
+    fn validate_module_read<X: Executable>(
+        module_map: &VersionedModules<T::Key, T::Value, X>,
+        key: &T::Key,
+        descriptor: &ExecutableDescriptor,
+        idx_to_validate: TxnIndex,
+    ) -> bool {
+        use MVModulesError::*;
+        use MVModulesOutput::*;
+        match module_map.fetch_module(key, idx_to_validate) {
+            Ok(Executable((_, current))) => &current == descriptor,
+            Ok(Module((_, hash))) => descriptor == &ExecutableDescriptor::Published(hash),
+            // The module was not published in the block prior to the transaction.
+            Err(NotFound) => descriptor == &ExecutableDescriptor::Storage,
+            Err(Dependency(_)) => false,
+        }
+    }
 
+    pub(crate) fn validate_module_reads<X: Executable>(
+        &self,
+        module_map: &VersionedModules<T::Key, T::Value, X>,
+        idx_to_validate: TxnIndex,
+    ) -> bool {
         if self.speculative_failure {
             return false;
         }
 
-        self.group_reads.iter().all(|(key, group)| {
-            let mut ret = true;
-            if let Some(size) = group.collected_size {
-                ret &= Ok(size) == group_map.get_group_size(key, idx_to_validate);
-            }
+        self.module_reads.iter().all(|(k, descriptor)| {
+            Self::validate_module_read(module_map, k, descriptor, idx_to_validate)
+        })
+    }
 
-            ret && group.inner_reads.iter().all(|(tag, r)| {
-                match group_map.fetch_tagged_data(key, tag, idx_to_validate) {
-                    Ok((version, v)) => {
-                        matches!(
-                            DataRead::from_value_with_layout(version, v).contains(r),
-                            DataReadComparison::Contains
-                        )
-                    },
-                    Err(TagNotFound) => {
-                        let sentinel_deletion =
-                            Arc::<T::Value>::new(TransactionWrite::from_state_value(None));
-                        assert!(sentinel_deletion.is_deletion());
-                        matches!(
-                            DataRead::Versioned(Err(StorageVersion), sentinel_deletion, None)
-                                .contains(r),
-                            DataReadComparison::Contains
-                        )
-                    },
-                    Err(Dependency(_)) => false,
-                    Err(Uninitialized) => {
-                        unreachable!("May not be uninitialized if captured for validation");
-                    },
-                    Err(TagSerializationError(_)) => {
-                        unreachable!("Should not require tag serialization");
-                    },
-                }
+    /// Returns the data, group, module and delayed field reads that fail validation, along with
+    /// the index of the transaction that wrote the current value of the data, if known. Delayed
+    /// fields are checked against their latest committed values, as they are at commit time.
+    /// Only used for tracing, as it does not short-circuit on the first invalid read.
+    pub(crate) fn invalid_reads<X: Executable>(
+        &self,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        idx_to_validate: TxnIndex,
+    ) -> Result<Vec<InvalidRead>, PanicError> {
+        let invalid_read = |key: &dyn Debug, writer_txn_idx| InvalidRead {
+            key: format!("{:?}", key),
+            writer_txn_idx,
+        };
+        let mut invalid_reads: Vec<InvalidRead> = self
+            .data_reads
+            .iter()
+            .filter(|(k, r)| {
+                !Self::validate_data_read(versioned_cache.data(), k, r, idx_to_validate)
             })
-        })
+            .map(|(k, _)| {
+                let writer_idx = match versioned_cache.data().fetch_data(k, idx_to_validate) {
+                    Ok(MVDataOutput::Versioned(Ok((writer_idx, _)), _))
+                    | Err(MVDataError::Dependency(writer_idx)) => Some(writer_idx),
+                    _ => None,
+                };
+                invalid_read(k, writer_idx)
+            })
+            .collect();
+        invalid_reads.extend(
+            self.group_reads
+                .iter()
+                .filter(|(k, group)| {
+                    !Self::validate_group_read(
+                        versioned_cache.group_data(),
+                        k,
+                        group,
+                        idx_to_validate,
+                    )
+                })
+                .map(|(k, _)| invalid_read(k, None)),
+        );
+        invalid_reads.extend(
+            self.module_reads
+                .iter()
+                .filter(|(k, descriptor)| {
+                    !Self::validate_module_read(
+                        versioned_cache.modules(),
+                        k,
+                        descriptor,
+                        idx_to_validate,
+                    )
+                })
+                .map(|(k, _)| invalid_read(k, None)),
+        );
+        for (id, read_value) in &self.delayed_field_reads {
+            if !Self::validate_delayed_field_read(
+                versioned_cache.delayed_fields(),
+                id,
+                read_value,
+                idx_to_validate,
+            )? {
+                invalid_reads.push(invalid_read(id, None));
+            }
+        }
+        Ok(invalid_reads)
+    }
+
+    fn validate_delayed_field_read(
+        delayed_fields: &dyn TVersionedDelayedFieldView<T::Identifier>,
+        id: &T::Identifier,
+        read_value: &DelayedFieldRead,
+        idx_to_validate: TxnIndex,
+    ) -> Result<bool, PanicError> {
+        use MVDelayedFieldsError::*;
+        match delayed_fields.read_latest_committed_value(
+            id,
+            idx_to_validate,
+            ReadPosition::BeforeCurrentTxn,
+        ) {
+            Ok(current_value) => match read_value {
+                DelayedFieldRead::Value { value, .. } => Ok(value == &current_value),
+                DelayedFieldRead::HistoryBounded {
+                    restriction,
+                    max_value,
+                    ..
+                } => Ok(restriction
+                    .validate_against_base_value(current_value.into_aggregator_value()?, *max_value)
+                    .is_ok()),
+            },
+            Err(NotFound) | Err(Dependency(_)) | Err(DeltaApplicationFailure) => Ok(false),
+        }
     }
 
     // This validation needs to be called at commit time
@@ -641,36 +815,10 @@ impl<T: Transaction> CapturedReads<T> {
             return Ok(false);
         }
 
-        use MVDelayedFieldsError::*;
         for (id, read_value) in &self.delayed_field_reads {
-            match delayed_fields.read_latest_committed_value(
-                id,
-                idx_to_validate,
-                ReadPosition::BeforeCurrentTxn,
-            ) {
-                Ok(current_value) => match read_value {
-                    DelayedFieldRead::Value { value, .. } => {
-                        if value != &current_value {
-                            return Ok(false);
-                        }
-                    },
-                    DelayedFieldRead::HistoryBounded {
-                        restriction,
-                        max_value,
-                        ..
-                    } => match restriction.validate_against_base_value(
-                        current_value.into_aggregator_value()?,
-                        *max_value,
-                    ) {
-                        Ok(_) => {},
-                        Err(_) => {
-                            return Ok(false);
-                        },
-                    },
-                },
-                Err(NotFound) | Err(Dependency(_)) | Err(DeltaApplicationFailure) => {
-                    return Ok(false);
-                },
+            if !Self::validate_delayed_field_read(delayed_fields, id, read_value, idx_to_validate)?
+            {
+                return Ok(false);
             }
         }
         Ok(true)
@@ -694,7 +842,7 @@ impl<T: Transaction> CapturedReads<T> {
             }
         }
 
-        for key in &self.module_reads {
+        for key in self.module_reads.keys() {
             ret.insert(InputOutputKey::Resource(key.clone()));
         }
 
@@ -756,7 +904,8 @@ impl<T: Transaction> UnsyncReadSet<T> {
 mod test {
     use super::*;
     use crate::proptest_types::types::{raw_metadata, KeyType, MockEvent, ValueType};
-    use aptos_mvhashmap::types::StorageVersion;
+    use aptos_mvhashmap::{types::StorageVersion, MVHashMap};
+    use aptos_types::executable::ExecutableTestType;
     use claims::{assert_err, assert_gt, assert_matches, assert_none, assert_ok, assert_some_eq};
     use move_vm_types::delayed_values::delayed_field_id::DelayedFieldID;
     use test_case::test_case;
@@ -1294,4 +1443,54 @@ mod test {
         captured_reads.mark_failure();
         assert!(captured_reads.speculative_failure);
     }
+
+    #[test]
+    fn module_reads_validation() {
+        let map =
+            MVHashMap::<KeyType<u32>, u32, ValueType, ExecutableTestType, DelayedFieldID>::new();
+        let key = KeyType::<u32>(10, true);
+
+        let mut storage_reads = CapturedReads::<TestTransactionType>::new();
+        assert_ok!(storage_reads.capture_module_read(key, ExecutableDescriptor::Storage));
+        assert!(storage_reads.validate_module_reads(map.modules(), 5));
+
+        // Module published by a transaction prior to the one being validated.
+        map.modules().write(
+            key,
+            2,
+            ValueType::with_len_and_metadata(1, StateValueMetadata::none()),
+        );
+        assert!(!storage_reads.validate_module_reads(map.modules(), 5));
+        assert!(storage_reads.validate_module_reads(map.modules(), 2));
+
+        let hash = match map.modules().fetch_module(&key, 5) {
+            Ok(MVModulesOutput::Module((_, hash))) => hash,
+            _ => unreachable!("Module must be published"),
+        };
+        let mut published_reads = CapturedReads::<TestTransactionType>::new();
+        assert_ok!(published_reads.capture_module_read(key, ExecutableDescriptor::Published(hash)));
+        assert!(published_reads.validate_module_reads(map.modules(), 5));
+
+        // Re-publishing the same module by a later incarnation does not invalidate the read.
+        map.modules().write(
+            key,
+            2,
+            ValueType::with_len_and_metadata(1, StateValueMetadata::none()),
+        );
+        assert!(published_reads.validate_module_reads(map.modules(), 5));
+        map.modules().write(
+            key,
+            2,
+            ValueType::with_len_and_metadata(2, StateValueMetadata::none()),
+        );
+        assert!(!published_reads.validate_module_reads(map.modules(), 5));
+
+        map.modules().mark_estimate(&key, 2);
+        assert!(!storage_reads.validate_module_reads(map.modules(), 5));
+        assert!(!published_reads.validate_module_reads(map.modules(), 5));
+
+        // Reading a different version of the same module is inconsistent.
+        assert_err!(published_reads.capture_module_read(key, ExecutableDescriptor::Storage));
+        assert!(published_reads.speculative_failure);
+    }
 }
@@ -14,6 +14,7 @@ use crate::{
     limit_processor::BlockGasLimitProcessor,
     scheduler::{DependencyStatus, ExecutionTaskType, Scheduler, SchedulerTask, Wave},
     task::{ExecutionStatus, ExecutorTask, TransactionOutput},
+    trace::{AbortCause, ExecutionTracer, InvalidRead},
     txn_commit_hook::TransactionCommitHook,
     txn_last_input_output::{KeyKind, TxnLastInputOutput},
     types::ReadWriteSummary,
@@ -25,7 +26,7 @@ use aptos_aggregator::{
     types::{code_invariant_error, expect_ok, PanicOr},
 };
 use aptos_drop_helper::DEFAULT_DROPPER;
-use aptos_logger::{debug, error, info};
+use aptos_logger::{error, info};
 use aptos_mvhashmap::{
     types::{Incarnation, MVDelayedFieldsError, TxnIndex, ValueWithLayout},
     unsync_map::UnsyncMap,
@@ -58,6 +59,7 @@ use std::{
         atomic::{AtomicBool, AtomicU32, Ordering},
         Arc,
     },
+    time::Instant,
 };
 
 pub struct BlockExecutor<T, E, S, L, X> {
@@ -109,6 +111,8 @@ where
     ) -> Result<bool, PanicOr<ParallelBlockExecutionError>> {
         let _timer = TASK_EXECUTE_SECONDS.start_timer();
         let txn = &signature_verified_block[idx_to_execute as usize];
+        let tracer = latest_view.tracer();
+        let start_time = Instant::now();
 
         // VM execution.
         let sync_view = LatestView::new(base_view, ViewState::Sync(latest_view), idx_to_execute);
@@ -289,13 +293,48 @@ where
             versioned_cache.delayed_fields().remove(&id, idx_to_execute);
         }
 
-        if !last_input_output.record(idx_to_execute, read_set, result, resource_write_set) {
-            // Module R/W is an expected fallback behavior, no alert is required.
-            debug!("[Execution] At txn {}, Module read & write", idx_to_execute);
+        let traced_reads = tracer.map(|_| {
+            let status = match &result {
+                ExecutionStatus::Success(_) => "success",
+                ExecutionStatus::SkipRest(_) => "skip_rest",
+                ExecutionStatus::Abort(_) => "abort",
+                ExecutionStatus::SpeculativeExecutionAbortError(_) => "speculative_abort",
+                ExecutionStatus::DelayedFieldsCodeInvariantError(_) => "code_invariant_error",
+            };
+            let mut reads: Vec<String> = read_set
+                .get_read_summary()
+                .iter()
+                .map(|key| format!("{:?}", key))
+                .collect();
+            reads.sort();
+            (status, reads)
+        });
 
-            return Err(PanicOr::Or(
-                ParallelBlockExecutionError::ModulePathReadWriteError,
-            ));
+        last_input_output.record(idx_to_execute, read_set, result, resource_write_set);
+
+        if let (Some(tracer), Some((status, reads))) = (tracer, traced_reads) {
+            let mut writes: Vec<String> = last_input_output
+                .modified_keys(idx_to_execute)
+                .into_iter()
+                .flatten()
+                .map(|(key, _)| format!("{:?}", key))
+                .chain(
+                    last_input_output
+                        .delayed_field_keys(idx_to_execute)
+                        .into_iter()
+                        .flatten()
+                        .map(|id| format!("{:?}", id)),
+                )
+                .collect();
+            writes.sort();
+            tracer.record_execution(
+                idx_to_execute,
+                incarnation,
+                start_time,
+                status,
+                reads,
+                writes,
+            );
         }
         Ok(updates_outside)
     }
@@ -323,10 +362,10 @@ where
         // (i.e. not re-execute unless some other part of the validation fails or
         // until commit, but mark as estimates).
 
-        // TODO: validate modules when there is no r/w fallback.
         Ok(
             read_set.validate_data_reads(versioned_cache.data(), idx_to_validate)
-                && read_set.validate_group_reads(versioned_cache.group_data(), idx_to_validate),
+                && read_set.validate_group_reads(versioned_cache.group_data(), idx_to_validate)
+                && read_set.validate_module_reads(versioned_cache.modules(), idx_to_validate),
         )
     }
 
@@ -362,6 +401,18 @@ where
         }
     }
 
+    /// The reads of the transaction that are no longer valid, for the execution trace.
+    fn traced_invalid_reads(
+        txn_idx: TxnIndex,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+    ) -> Vec<InvalidRead> {
+        last_input_output
+            .read_set(txn_idx)
+            .and_then(|read_set| read_set.invalid_reads(versioned_cache, txn_idx).ok())
+            .unwrap_or_default()
+    }
+
     fn update_on_validation(
         txn_idx: TxnIndex,
         incarnation: Incarnation,
@@ -374,6 +425,15 @@ where
         let aborted = !valid && scheduler.try_abort(txn_idx, incarnation);
 
         if aborted {
+            if let Some(tracer) = scheduler.tracer() {
+                let invalid_reads =
+                    Self::traced_invalid_reads(txn_idx, last_input_output, versioned_cache);
+                tracer.record_abort(
+                    txn_idx,
+                    incarnation,
+                    AbortCause::ValidationFailure { invalid_reads },
+                );
+            }
             Self::update_transaction_on_abort(txn_idx, last_input_output, versioned_cache);
             scheduler.finish_abort(txn_idx, incarnation)
         } else {
@@ -446,6 +506,15 @@ where
         while let Some((txn_idx, incarnation)) = scheduler.try_commit() {
             if !Self::validate_commit_ready(txn_idx, versioned_cache, last_input_output)? {
                 // Transaction needs to be re-executed, one final time.
+                if let Some(tracer) = scheduler.tracer() {
+                    let invalid_reads =
+                        Self::traced_invalid_reads(txn_idx, last_input_output, versioned_cache);
+                    tracer.record_abort(
+                        txn_idx,
+                        incarnation,
+                        AbortCause::DelayedFieldCommitFailure { invalid_reads },
+                    );
+                }
 
                 Self::update_transaction_on_abort(txn_idx, last_input_output, versioned_cache);
                 // We are going to skip reducing validation index here, as we
@@ -494,6 +563,10 @@ where
             // Handle a potential vm error, then check invariants on the recorded outputs.
             last_input_output.check_execution_status_during_commit(txn_idx)?;
 
+            for (key, write_op) in last_input_output.module_write_set(txn_idx) {
+                E::on_module_committed(&key, &write_op);
+            }
+
             if let Some(fee_statement) = last_input_output.fee_statement(txn_idx) {
                 let approx_output_size = block_gas_limit_type.block_output_limit().and_then(|_| {
                     last_input_output
@@ -511,6 +584,12 @@ where
                     .conflict_penalty_window()
                     .map(|_| last_input_output.get_txn_read_write_summary(txn_idx));
 
+                // Module R/W conflicts are detected in the commit order, same as during the
+                // sequential execution, so that the block limit is deterministic.
+                if last_input_output.check_module_rw_conflict_during_commit(txn_idx) {
+                    block_limit_processor.process_module_rw_conflict();
+                }
+
                 // For committed txns with Success status, calculate the accumulated gas costs.
                 block_limit_processor.accumulate_fee_statement(
                     fee_statement,
@@ -781,7 +860,11 @@ where
 
             scheduler_task = match scheduler_task {
                 SchedulerTask::ValidationTask(txn_idx, incarnation, wave) => {
+                    let start_time = Instant::now();
                     let valid = Self::validate(txn_idx, last_input_output, versioned_cache)?;
+                    if let Some(tracer) = scheduler.tracer() {
+                        tracer.record_validation(txn_idx, incarnation, start_time, valid);
+                    }
                     Self::update_on_validation(
                         txn_idx,
                         incarnation,
@@ -862,6 +945,7 @@ where
         let shared_commit_state = ExplicitSyncWrapper::new(BlockGasLimitProcessor::new(
             self.config.onchain.block_gas_limit_type.clone(),
             num_txns,
+            self.config.local.conflict_history.clone(),
         ));
         let shared_maybe_error = AtomicBool::new(false);
 
@@ -876,7 +960,10 @@ where
         let num_txns = num_txns as u32;
 
         let last_input_output = TxnLastInputOutput::new(num_txns);
-        let scheduler = Scheduler::new(num_txns);
+        let mut scheduler = Scheduler::new(num_txns);
+        if self.config.local.execution_trace_dir.is_some() {
+            scheduler = scheduler.with_tracer(ExecutionTracer::new());
+        }
 
         let timer = RAYON_EXECUTION_SECONDS.start_timer();
         self.executor_thread_pool.scope(|s| {
@@ -895,7 +982,7 @@ where
                         &final_results,
                     ) {
                         // If there are multiple errors, they all get logged:
-                        // ModulePathReadWriteError and FatalVMErrorvariant is logged at construction,
+                        // FatalVMError variant is logged at construction,
                         // and below we log CodeInvariantErrors.
                         if let PanicOr::CodeInvariantError(err_msg) = err {
                             alert!("[BlockSTM] worker loop: CodeInvariantError({:?})", err_msg);
@@ -909,6 +996,17 @@ where
             }
         });
         drop(timer);
+
+        if let (Some(trace_dir), Some(tracer)) = (
+            &self.config.local.execution_trace_dir,
+            scheduler.take_tracer(),
+        ) {
+            match tracer.finish(num_txns as usize).write_to_dir(trace_dir) {
+                Ok(path) => info!("[BlockSTM]: Execution trace written to {:?}", path),
+                Err(err) => error!("[BlockSTM]: Failed to write execution trace: {:?}", err),
+            }
+        }
+
         // Explicit async drops.
         DEFAULT_DROPPER.schedule_drop((last_input_output, scheduler, versioned_cache));
 
@@ -941,6 +1039,7 @@ where
         }
 
         for (key, write_op) in output.module_write_set().into_iter() {
+            E::on_module_committed(&key, &write_op);
             unsync_map.write_module(key, write_op);
         }
 
@@ -1013,6 +1112,7 @@ where
         let mut block_limit_processor = BlockGasLimitProcessor::<T>::new(
             self.config.onchain.block_gas_limit_type.clone(),
             num_txns,
+            self.config.local.conflict_history.clone(),
         );
 
         let last_input_output: TxnLastInputOutput<T, E::Output, E::Error> =
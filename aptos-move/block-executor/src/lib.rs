@@ -150,6 +150,7 @@ mod limit_processor;
 pub mod proptest_types;
 mod scheduler;
 pub mod task;
+pub mod trace;
 pub mod txn_commit_hook;
 pub mod txn_last_input_output;
 pub mod types;
@@ -1,13 +1,17 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters, types::ReadWriteSummary};
+use crate::{
+    counters,
+    types::{InputOutputKey, ReadWriteSummary},
+};
 use aptos_logger::info;
 use aptos_types::{
-    fee_statement::FeeStatement, on_chain_config::BlockGasLimitType,
-    transaction::BlockExecutableTransaction as Transaction,
+    block_executor::conflict_history::ConflictHistory, fee_statement::FeeStatement,
+    on_chain_config::BlockGasLimitType, transaction::BlockExecutableTransaction as Transaction,
 };
 use claims::{assert_le, assert_none};
+use std::sync::Arc;
 
 pub struct BlockGasLimitProcessor<T: Transaction> {
     block_gas_limit_type: BlockGasLimitType,
@@ -18,10 +22,18 @@ pub struct BlockGasLimitProcessor<T: Transaction> {
     txn_read_write_summaries: Vec<ReadWriteSummary<T>>,
     block_limit_reached: bool,
     module_rw_conflict: bool,
+    // Hashes of the keys read by a txn and written by a txn in its conflict window,
+    // only collected when there is a conflict history to record the block into.
+    conflicting_key_hashes: Vec<u64>,
+    conflict_history: Option<Arc<ConflictHistory>>,
 }
 
 impl<T: Transaction> BlockGasLimitProcessor<T> {
-    pub fn new(block_gas_limit_type: BlockGasLimitType, init_size: usize) -> Self {
+    pub fn new(
+        block_gas_limit_type: BlockGasLimitType,
+        init_size: usize,
+        conflict_history: Option<Arc<ConflictHistory>>,
+    ) -> Self {
         Self {
             block_gas_limit_type,
             accumulated_effective_block_gas: 0,
@@ -31,6 +43,8 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
             txn_read_write_summaries: Vec::with_capacity(init_size),
             block_limit_reached: false,
             module_rw_conflict: false,
+            conflicting_key_hashes: Vec::new(),
+            conflict_history,
         }
     }
 
@@ -60,6 +74,9 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
                     txn_read_write_summary.collapse_resource_group_conflicts()
                 },
             );
+            if self.conflict_history.is_some() {
+                self.collect_conflicting_keys(conflict_overlap_length as usize);
+            }
             if self.module_rw_conflict {
                 conflict_overlap_length as u64
             } else {
@@ -190,12 +207,37 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
         (conflict_count + 1) as u64
     }
 
+    fn collect_conflicting_keys(&mut self, conflict_overlap_length: usize) {
+        let start = self
+            .txn_read_write_summaries
+            .len()
+            .saturating_sub(conflict_overlap_length);
+        let end = self.txn_read_write_summaries.len() - 1;
+
+        let current = &self.txn_read_write_summaries[end];
+        for prev in &self.txn_read_write_summaries[start..end] {
+            for key in current.conflicting_keys_with_previous(prev) {
+                match key {
+                    InputOutputKey::Resource(key) | InputOutputKey::Group(key, _) => self
+                        .conflicting_key_hashes
+                        .push(ConflictHistory::key_hash(key)),
+                    // Delayed field ids are not stable across blocks
+                    InputOutputKey::DelayedField(_) => {},
+                }
+            }
+        }
+    }
+
     fn finish_update_counters_and_log_info(
         &self,
         is_parallel: bool,
         num_committed: u32,
         num_total: u32,
     ) {
+        if let Some(conflict_history) = &self.conflict_history {
+            conflict_history.record_block(&self.conflicting_key_hashes);
+        }
+
         let accumulated_effective_block_gas = self.get_effective_accumulated_block_gas();
         let accumulated_approx_output_size = self.get_accumulated_approx_output_size();
 
@@ -282,7 +324,7 @@ mod test {
 
     #[test]
     fn test_output_limit_not_used() {
-        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(DEFAULT_COMPLEX_LIMIT, 10);
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(DEFAULT_COMPLEX_LIMIT, 10, None);
         // Assert passing none here doesn't panic.
         processor.accumulate_fee_statement(FeeStatement::zero(), None, None);
         assert!(!processor.should_end_block_parallel());
@@ -306,7 +348,7 @@ mod test {
             use_granular_resource_group_conflicts: false,
         };
 
-        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10);
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10, None);
 
         processor.accumulate_fee_statement(execution_fee(10), None, None);
         assert!(!processor.should_end_block_parallel());
@@ -330,7 +372,7 @@ mod test {
             use_granular_resource_group_conflicts: false,
         };
 
-        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10);
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10, None);
 
         processor.accumulate_fee_statement(FeeStatement::zero(), None, Some(10));
         assert_eq!(processor.accumulated_approx_output_size, 10);
@@ -370,7 +412,7 @@ mod test {
             use_granular_resource_group_conflicts: false,
         };
 
-        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10);
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10, None);
 
         processor.accumulate_fee_statement(
             execution_fee(10),
@@ -432,7 +474,7 @@ mod test {
             use_granular_resource_group_conflicts: true,
         };
 
-        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10);
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10, None);
 
         assert!(!processor.should_end_block_parallel());
         processor.accumulate_fee_statement(
@@ -474,7 +516,7 @@ mod test {
             use_granular_resource_group_conflicts: true,
         };
 
-        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10);
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10, None);
         processor.accumulate_fee_statement(
             execution_fee(10),
             Some(ReadWriteSummary::new(
@@ -513,4 +555,56 @@ mod test {
             55 * conflict_penalty_window as u64
         );
     }
+
+    #[test]
+    fn test_conflicts_recorded_into_history() {
+        let block_gas_limit = BlockGasLimitType::ComplexLimitV1 {
+            effective_block_gas_limit: 1000,
+            execution_gas_effective_multiplier: 1,
+            io_gas_effective_multiplier: 1,
+            conflict_penalty_window: 8,
+            use_module_publishing_block_conflict: false,
+            block_output_limit: None,
+            include_user_txn_size_in_block_output: true,
+            add_block_limit_outcome_onchain: false,
+            use_granular_resource_group_conflicts: false,
+        };
+        let conflict_history = Arc::new(ConflictHistory::new());
+
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(
+            block_gas_limit.clone(),
+            10,
+            Some(conflict_history.clone()),
+        );
+        for _ in 0..3 {
+            processor.accumulate_fee_statement(
+                execution_fee(10),
+                Some(ReadWriteSummary::new(
+                    to_map(&[InputOutputKey::Resource(1), InputOutputKey::Resource(2)]),
+                    to_map(&[InputOutputKey::Resource(1)]),
+                )),
+                None,
+            );
+        }
+        // The history is only updated once the block is finished.
+        assert_eq!(conflict_history.num_tracked_keys(), 0);
+        processor.finish_sequential_update_counters_and_log_info(3, 3);
+
+        // The second txn conflicts with the first one, the third with both previous ones.
+        let hot_key = ConflictHistory::key_hash(&KeyType(1u64, false));
+        assert_eq!(conflict_history.conflict_score(hot_key), 3.0);
+        assert_eq!(conflict_history.num_tracked_keys(), 1);
+
+        // Without a history, the conflicting keys are not collected.
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10, None);
+        processor.accumulate_fee_statement(
+            execution_fee(10),
+            Some(ReadWriteSummary::new(
+                to_map(&[InputOutputKey::Resource(1)]),
+                to_map(&[InputOutputKey::Resource(1)]),
+            )),
+            None,
+        );
+        assert!(processor.conflicting_key_hashes.is_empty());
+    }
 }
@@ -19,7 +19,7 @@ use aptos_types::{
     block_executor::config::BlockExecutorConfig, contract_event::TransactionEvent,
     executable::ExecutableTestType,
 };
-use claims::{assert_matches, assert_ok};
+use claims::assert_ok;
 use num_cpus;
 use proptest::{
     collection::vec,
@@ -84,11 +84,6 @@ fn run_transactions<K, V, E>(
         )
         .execute_transactions_parallel((), &transactions, &data_view);
 
-        if module_access.0 && module_access.1 {
-            assert_matches!(output, Err(()));
-            continue;
-        }
-
         BaselineOutput::generate(&transactions, maybe_block_gas_limit)
             .assert_parallel_output(&output);
     }
@@ -126,6 +121,16 @@ proptest! {
         run_transactions::<[u8; 32], [u8; 32], MockEvent>(&universe, transaction_gen, abort_transactions, skip_rest_transactions, 1, (false, false), None);
     }
 
+    #[test]
+    fn module_publish_and_read(
+        universe in vec(any::<[u8; 32]>(), 10),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 200).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 2),
+        skip_rest_transactions in vec(any::<Index>(), 0),
+    ) {
+        run_transactions::<[u8; 32], [u8; 32], MockEvent>(&universe, transaction_gen, abort_transactions, skip_rest_transactions, 2, (true, true), None);
+    }
+
     #[test]
     fn mixed_transactions(
         universe in vec(any::<[u8; 32]>(), 100),
@@ -305,10 +310,7 @@ fn dynamic_read_writes_contended_with_block_gas_limit(
     );
 }
 
-fn module_publishing_fallback_with_block_gas_limit(
-    num_txns: usize,
-    maybe_block_gas_limit: Option<u64>,
-) {
+fn module_publishing_with_block_gas_limit(num_txns: usize, maybe_block_gas_limit: Option<u64>) {
     let mut runner = TestRunner::default();
 
     let universe = vec(any::<[u8; 32]>(), 100)
@@ -454,6 +456,8 @@ fn publishing_fixed_params_with_block_gas_limit(
             .unwrap(),
     );
 
+    let block_gas_limit = Some(max(w_index, r_index) as u64 * MAX_GAS_PER_TXN + 1);
+
     for _ in 0..200 {
         let output = BlockExecutor::<
             MockTransaction<KeyType<[u8; 32]>, MockEvent>,
@@ -462,16 +466,17 @@ fn publishing_fixed_params_with_block_gas_limit(
             NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
             ExecutableTestType,
         >::new(
-            BlockExecutorConfig::new_maybe_block_limit(
-                num_cpus::get(),
-                Some(max(w_index, r_index) as u64 * MAX_GAS_PER_TXN + 1),
-            ),
+            // Ensure enough gas limit to commit the module txns (4 is maximum gas per txn)
+            BlockExecutorConfig::new_maybe_block_limit(num_cpus::get(), block_gas_limit),
             executor_thread_pool.clone(),
             None,
-        ) // Ensure enough gas limit to commit the module txns (4 is maximum gas per txn)
+        )
         .execute_transactions_parallel((), &transactions, &data_view);
 
-        assert_matches!(output, Err(()));
+        // The module read is validated against the versioned module write, so the
+        // parallel execution must succeed (no fallback) and match the baseline.
+        assert_ok!(&output);
+        BaselineOutput::generate(&transactions, block_gas_limit).assert_parallel_output(&output);
     }
 }
 
@@ -598,8 +603,8 @@ fn dynamic_read_writes_contended() {
 }
 
 #[test]
-fn module_publishing_fallback() {
-    module_publishing_fallback_with_block_gas_limit(3000, None);
+fn module_publishing() {
+    module_publishing_with_block_gas_limit(3000, None);
 }
 
 #[test]
@@ -703,10 +708,10 @@ fn dynamic_read_writes_contended_with_block_gas_limit_test() {
 }
 
 #[test]
-fn module_publishing_fallback_with_block_gas_limit_test() {
-    module_publishing_fallback_with_block_gas_limit(
+fn module_publishing_with_block_gas_limit_test() {
+    module_publishing_with_block_gas_limit(
         3000,
-        // Need to execute at least 2 txns to trigger module publishing fallback
+        // Need to execute at least 2 txns to have module read & write conflicts
         Some(rand::thread_rng().gen_range(1, 3000 * MAX_GAS_PER_TXN / 2)),
     );
 }
@@ -147,9 +147,8 @@ pub(crate) struct KeyType<K: Hash + Clone + Debug + PartialOrd + Ord + Eq>(
     /// Wrapping the types used for testing to add ModulePath trait implementation (below).
     pub K,
     /// The bool field determines for testing purposes, whether the key will be interpreted
-    /// as a module access path. In this case, if a module path is both read and written
-    /// during parallel execution, ModulePathReadWrite must be returned and the
-    /// block execution must fall back to the sequential execution.
+    /// as a module access path. In this case, reads and writes go through the versioned
+    /// modules in the multi-version data-structure during parallel execution.
     pub bool,
 );
 
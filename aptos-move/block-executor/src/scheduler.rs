@@ -2,7 +2,7 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::explicit_sync_wrapper::ExplicitSyncWrapper;
+use crate::{explicit_sync_wrapper::ExplicitSyncWrapper, trace::ExecutionTracer};
 use aptos_infallible::Mutex;
 use aptos_mvhashmap::types::{Incarnation, TxnIndex};
 use concurrent_queue::{ConcurrentQueue, PopError};
@@ -10,10 +10,12 @@ use crossbeam::utils::CachePadded;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 use std::{
     cmp::{max, min},
+    fmt::Debug,
     sync::{
         atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
         Arc, Condvar,
     },
+    time::Instant,
 };
 
 const TXN_IDX_MASK: u64 = (1 << 32) - 1;
@@ -229,6 +231,17 @@ impl ValidationStatus {
 
 pub trait TWaitForDependency {
     fn wait_for_dependency(&self, txn_idx: TxnIndex, dep_txn_idx: TxnIndex) -> DependencyResult;
+
+    /// Called once txn_idx is resumed after waiting on dep_txn_idx since wait_start, due to
+    /// reading the given key.
+    fn record_dependency_wait(
+        &self,
+        _txn_idx: TxnIndex,
+        _dep_txn_idx: TxnIndex,
+        _key: &dyn Debug,
+        _wait_start: Instant,
+    ) {
+    }
 }
 
 pub struct Scheduler {
@@ -277,6 +290,9 @@ pub struct Scheduler {
     queueing_commits_lock: CachePadded<ArmedLock>,
 
     commit_queue: ConcurrentQueue<u32>,
+
+    /// Records the execution trace of the block, if tracing is enabled.
+    tracer: Option<ExecutionTracer>,
 }
 
 /// Public Interfaces for the Scheduler
@@ -305,9 +321,23 @@ impl Scheduler {
             has_halted: CachePadded::new(AtomicBool::new(false)),
             queueing_commits_lock: CachePadded::new(ArmedLock::new()),
             commit_queue: ConcurrentQueue::<u32>::bounded(num_txns as usize),
+            tracer: None,
         }
     }
 
+    pub fn with_tracer(mut self, tracer: ExecutionTracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    pub fn tracer(&self) -> Option<&ExecutionTracer> {
+        self.tracer.as_ref()
+    }
+
+    pub fn take_tracer(&mut self) -> Option<ExecutionTracer> {
+        self.tracer.take()
+    }
+
     pub fn num_txns(&self) -> TxnIndex {
         self.num_txns
     }
@@ -366,6 +396,10 @@ impl Scheduler {
                         // Can commit.
                         *status_write = ExecutionStatus::Committed(incarnation);
 
+                        if let Some(tracer) = &self.tracer {
+                            tracer.record_commit(*commit_idx, incarnation);
+                        }
+
                         *commit_idx += 1;
                         if *commit_idx == self.num_txns {
                             // All txns have been committed, the parallel execution can finish.
@@ -658,6 +692,18 @@ impl TWaitForDependency for Scheduler {
 
         DependencyResult::Dependency(dep_condvar)
     }
+
+    fn record_dependency_wait(
+        &self,
+        txn_idx: TxnIndex,
+        dep_txn_idx: TxnIndex,
+        key: &dyn Debug,
+        wait_start: Instant,
+    ) {
+        if let Some(tracer) = &self.tracer {
+            tracer.record_dependency_wait(txn_idx, dep_txn_idx, key, wait_start);
+        }
+    }
 }
 
 /// Private functions of the Scheduler
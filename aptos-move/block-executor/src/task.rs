@@ -83,6 +83,15 @@ pub trait ExecutorTask: Sync {
     ) -> ExecutionStatus<Self::Output, Self::Error>;
 
     fn is_transaction_dynamic_change_set_capable(txn: &Self::Txn) -> bool;
+
+    /// Called when the module write of a committed transaction is applied, so that code caches
+    /// kept across blocks can drop the versions of the module it replaces. Module writes of
+    /// speculative incarnations are not reported.
+    fn on_module_committed(
+        _key: &<Self::Txn as Transaction>::Key,
+        _value: &<Self::Txn as Transaction>::Value,
+    ) {
+    }
 }
 
 /// Trait for execution result of a single transaction.
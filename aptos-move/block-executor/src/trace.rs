@@ -0,0 +1,442 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in tracing of parallel execution, recording for each incarnation of every transaction
+//! what it read and wrote, which reads failed validation and what it waited on, to find the
+//! keys that make a workload sequential. Tracing serializes all workers on a single lock, so
+//! it is only meant for analysis and not for production.
+
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+static NUM_TRACED_BLOCKS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InvalidRead {
+    pub key: String,
+    /// Transaction that wrote the value observed during validation, if known.
+    pub writer_txn_idx: Option<TxnIndex>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum AbortCause {
+    /// Some reads were invalidated by the writes of lower transactions.
+    ValidationFailure { invalid_reads: Vec<InvalidRead> },
+    /// Delayed field reads were invalidated at commit time, or the delayed field changes could
+    /// not be committed.
+    DelayedFieldCommitFailure { invalid_reads: Vec<InvalidRead> },
+}
+
+impl AbortCause {
+    pub fn invalid_reads(&self) -> &[InvalidRead] {
+        match self {
+            AbortCause::ValidationFailure { invalid_reads }
+            | AbortCause::DelayedFieldCommitFailure { invalid_reads } => invalid_reads,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DependencyWait {
+    pub dep_txn_idx: TxnIndex,
+    pub key: String,
+    pub worker: usize,
+    pub start_us: u64,
+    pub duration_us: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidationTrace {
+    pub worker: usize,
+    pub start_us: u64,
+    pub duration_us: u64,
+    pub valid: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AbortTrace {
+    pub worker: usize,
+    pub time_us: u64,
+    pub cause: AbortCause,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IncarnationTrace {
+    pub txn_idx: TxnIndex,
+    pub incarnation: Incarnation,
+    pub worker: usize,
+    pub start_us: u64,
+    pub duration_us: u64,
+    pub status: String,
+    pub reads: Vec<String>,
+    pub writes: Vec<String>,
+    pub dependency_waits: Vec<DependencyWait>,
+    pub validations: Vec<ValidationTrace>,
+    pub abort: Option<AbortTrace>,
+    pub committed_at_us: Option<u64>,
+}
+
+/// Per key statistics of the aborts and dependency waits caused by the key.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct KeyConflictStats {
+    pub key: String,
+    pub num_aborts: usize,
+    pub num_dependency_waits: usize,
+    pub dependency_wait_us: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExecutionTrace {
+    pub num_txns: usize,
+    pub duration_us: u64,
+    pub incarnations: Vec<IncarnationTrace>,
+    /// Keys sorted by the number of aborts and dependency waits they caused.
+    pub key_conflicts: Vec<KeyConflictStats>,
+}
+
+enum TraceEvent {
+    Execution(IncarnationTrace),
+    DependencyWait(TxnIndex, DependencyWait),
+    Validation(TxnIndex, Incarnation, ValidationTrace),
+    Abort(TxnIndex, Incarnation, AbortTrace),
+    Commit(TxnIndex, Incarnation, u64),
+}
+
+pub struct ExecutionTracer {
+    start: Instant,
+    events: Mutex<Vec<TraceEvent>>,
+}
+
+fn current_worker() -> usize {
+    rayon::current_thread_index().unwrap_or(0)
+}
+
+impl ExecutionTracer {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn offset_us(&self, time: Instant) -> u64 {
+        time.saturating_duration_since(self.start).as_micros() as u64
+    }
+
+    pub(crate) fn record_execution(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        start: Instant,
+        status: &str,
+        reads: Vec<String>,
+        writes: Vec<String>,
+    ) {
+        let trace = IncarnationTrace {
+            txn_idx,
+            incarnation,
+            worker: current_worker(),
+            start_us: self.offset_us(start),
+            duration_us: start.elapsed().as_micros() as u64,
+            status: status.to_string(),
+            reads,
+            writes,
+            dependency_waits: vec![],
+            validations: vec![],
+            abort: None,
+            committed_at_us: None,
+        };
+        self.events.lock().push(TraceEvent::Execution(trace));
+    }
+
+    pub(crate) fn record_dependency_wait(
+        &self,
+        txn_idx: TxnIndex,
+        dep_txn_idx: TxnIndex,
+        key: &dyn Debug,
+        start: Instant,
+    ) {
+        let wait = DependencyWait {
+            dep_txn_idx,
+            key: format!("{:?}", key),
+            worker: current_worker(),
+            start_us: self.offset_us(start),
+            duration_us: start.elapsed().as_micros() as u64,
+        };
+        self.events
+            .lock()
+            .push(TraceEvent::DependencyWait(txn_idx, wait));
+    }
+
+    pub(crate) fn record_validation(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        start: Instant,
+        valid: bool,
+    ) {
+        let validation = ValidationTrace {
+            worker: current_worker(),
+            start_us: self.offset_us(start),
+            duration_us: start.elapsed().as_micros() as u64,
+            valid,
+        };
+        self.events
+            .lock()
+            .push(TraceEvent::Validation(txn_idx, incarnation, validation));
+    }
+
+    pub(crate) fn record_abort(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        cause: AbortCause,
+    ) {
+        let abort = AbortTrace {
+            worker: current_worker(),
+            time_us: self.offset_us(Instant::now()),
+            cause,
+        };
+        self.events
+            .lock()
+            .push(TraceEvent::Abort(txn_idx, incarnation, abort));
+    }
+
+    pub(crate) fn record_commit(&self, txn_idx: TxnIndex, incarnation: Incarnation) {
+        let time_us = self.offset_us(Instant::now());
+        self.events
+            .lock()
+            .push(TraceEvent::Commit(txn_idx, incarnation, time_us));
+    }
+
+    /// Assembles the recorded events into a trace, grouped by transaction incarnation.
+    pub fn finish(self, num_txns: usize) -> ExecutionTrace {
+        let duration_us = self.start.elapsed().as_micros() as u64;
+        let events = self.events.into_inner();
+
+        let mut incarnations: BTreeMap<(TxnIndex, Incarnation), IncarnationTrace> = BTreeMap::new();
+        let mut other_events = vec![];
+        for event in events {
+            match event {
+                TraceEvent::Execution(trace) => {
+                    incarnations.insert((trace.txn_idx, trace.incarnation), trace);
+                },
+                event => other_events.push(event),
+            }
+        }
+
+        let mut key_conflicts: HashMap<String, KeyConflictStats> = HashMap::new();
+        for event in other_events {
+            match event {
+                TraceEvent::DependencyWait(txn_idx, wait) => {
+                    let stats = key_conflicts.entry(wait.key.clone()).or_default();
+                    stats.num_dependency_waits += 1;
+                    stats.dependency_wait_us += wait.duration_us;
+
+                    // Incarnations of a transaction never execute concurrently, so the wait
+                    // belongs to the one that was executing at the time.
+                    if let Some(trace) = incarnations
+                        .range_mut((txn_idx, 0)..=(txn_idx, Incarnation::MAX))
+                        .map(|(_, trace)| trace)
+                        .find(|trace| {
+                            trace.start_us <= wait.start_us
+                                && wait.start_us <= trace.start_us + trace.duration_us
+                        })
+                    {
+                        trace.dependency_waits.push(wait);
+                    }
+                },
+                TraceEvent::Validation(txn_idx, incarnation, validation) => {
+                    if let Some(trace) = incarnations.get_mut(&(txn_idx, incarnation)) {
+                        trace.validations.push(validation);
+                    }
+                },
+                TraceEvent::Abort(txn_idx, incarnation, abort) => {
+                    for read in abort.cause.invalid_reads() {
+                        key_conflicts
+                            .entry(read.key.clone())
+                            .or_default()
+                            .num_aborts += 1;
+                    }
+                    if let Some(trace) = incarnations.get_mut(&(txn_idx, incarnation)) {
+                        trace.abort = Some(abort);
+                    }
+                },
+                TraceEvent::Commit(txn_idx, incarnation, time_us) => {
+                    if let Some(trace) = incarnations.get_mut(&(txn_idx, incarnation)) {
+                        trace.committed_at_us = Some(time_us);
+                    }
+                },
+                TraceEvent::Execution(_) => unreachable!("Executions are already grouped"),
+            }
+        }
+
+        let mut key_conflicts: Vec<KeyConflictStats> = key_conflicts
+            .into_iter()
+            .map(|(key, stats)| KeyConflictStats { key, ..stats })
+            .collect();
+        key_conflicts.sort_by(|a, b| {
+            (b.num_aborts + b.num_dependency_waits)
+                .cmp(&(a.num_aborts + a.num_dependency_waits))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+
+        ExecutionTrace {
+            num_txns,
+            duration_us,
+            incarnations: incarnations.into_values().collect(),
+            key_conflicts,
+        }
+    }
+}
+
+impl Default for ExecutionTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionTrace {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Exports the trace in the Chrome trace event format, which can be loaded in
+    /// chrome://tracing or Perfetto, with one row per worker thread.
+    pub fn to_chrome_trace(&self) -> serde_json::Result<String> {
+        let mut events = vec![];
+        for trace in &self.incarnations {
+            events.push(json!({
+                "name": format!("txn {} #{}", trace.txn_idx, trace.incarnation),
+                "cat": "execution",
+                "ph": "X",
+                "pid": 0,
+                "tid": trace.worker,
+                "ts": trace.start_us,
+                "dur": trace.duration_us,
+                "args": {
+                    "status": trace.status,
+                    "reads": trace.reads,
+                    "writes": trace.writes,
+                },
+            }));
+            for wait in &trace.dependency_waits {
+                events.push(json!({
+                    "name": format!("txn {} waits on txn {}", trace.txn_idx, wait.dep_txn_idx),
+                    "cat": "dependency_wait",
+                    "ph": "X",
+                    "pid": 0,
+                    "tid": wait.worker,
+                    "ts": wait.start_us,
+                    "dur": wait.duration_us,
+                    "args": { "key": wait.key },
+                }));
+            }
+            for validation in &trace.validations {
+                events.push(json!({
+                    "name": format!("validate txn {} #{}", trace.txn_idx, trace.incarnation),
+                    "cat": "validation",
+                    "ph": "X",
+                    "pid": 0,
+                    "tid": validation.worker,
+                    "ts": validation.start_us,
+                    "dur": validation.duration_us,
+                    "args": { "valid": validation.valid },
+                }));
+            }
+            if let Some(abort) = &trace.abort {
+                events.push(json!({
+                    "name": format!("abort txn {} #{}", trace.txn_idx, trace.incarnation),
+                    "cat": "abort",
+                    "ph": "i",
+                    "s": "t",
+                    "pid": 0,
+                    "tid": abort.worker,
+                    "ts": abort.time_us,
+                    "args": { "cause": abort.cause },
+                }));
+            }
+        }
+        serde_json::to_string(&json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms",
+        }))
+    }
+
+    /// Writes the trace as `block_<n>.json` and `block_<n>.chrome.json` into the directory,
+    /// numbering the blocks traced by this process, and returns the path of the JSON trace.
+    pub fn write_to_dir(&self, dir: &Path) -> anyhow::Result<PathBuf> {
+        let block_num = NUM_TRACED_BLOCKS.fetch_add(1, Ordering::Relaxed);
+        fs::create_dir_all(dir)?;
+        let json_path = dir.join(format!("block_{}.json", block_num));
+        fs::write(&json_path, self.to_json()?)?;
+        fs::write(
+            dir.join(format!("block_{}.chrome.json", block_num)),
+            self.to_chrome_trace()?,
+        )?;
+        Ok(json_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_assembly() {
+        let tracer = ExecutionTracer::new();
+        let start = Instant::now();
+
+        tracer.record_execution(1, 0, start, "success", vec!["A".to_string()], vec![]);
+        tracer.record_execution(0, 0, start, "success", vec![], vec!["A".to_string()]);
+        tracer.record_validation(1, 0, start, false);
+        tracer.record_abort(
+            1,
+            0,
+            AbortCause::ValidationFailure {
+                invalid_reads: vec![InvalidRead {
+                    key: "A".to_string(),
+                    writer_txn_idx: Some(0),
+                }],
+            },
+        );
+        tracer.record_commit(0, 0);
+
+        let trace_start = Instant::now();
+        tracer.record_execution(1, 1, trace_start, "success", vec!["A".to_string()], vec![]);
+        tracer.record_commit(1, 1);
+
+        let trace = tracer.finish(2);
+        let versions: Vec<(TxnIndex, Incarnation)> = trace
+            .incarnations
+            .iter()
+            .map(|trace| (trace.txn_idx, trace.incarnation))
+            .collect();
+        assert_eq!(versions, vec![(0, 0), (1, 0), (1, 1)]);
+        assert!(trace.incarnations[0].committed_at_us.is_some());
+        assert_eq!(trace.incarnations[1].validations.len(), 1);
+        assert!(trace.incarnations[1].abort.is_some());
+        assert!(trace.incarnations[1].committed_at_us.is_none());
+        assert!(trace.incarnations[2].committed_at_us.is_some());
+
+        assert_eq!(trace.key_conflicts.len(), 1);
+        assert_eq!(trace.key_conflicts[0].key, "A");
+        assert_eq!(trace.key_conflicts[0].num_aborts, 1);
+
+        let chrome_trace: serde_json::Value =
+            serde_json::from_str(&trace.to_chrome_trace().unwrap()).unwrap();
+        // Three executions, one validation and one abort
+        assert_eq!(chrome_trace["traceEvents"].as_array().unwrap().len(), 5);
+    }
+}
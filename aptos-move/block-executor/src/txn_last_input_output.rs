@@ -68,9 +68,9 @@ pub struct TxnLastInputOutput<T: Transaction, O: TransactionOutput<Txn = T>, E:
         CachePadded<ExplicitSyncWrapper<Vec<(T::Key, Arc<T::Value>, Option<Arc<MoveTypeLayout>>)>>>,
     >,
 
-    // Record all writes and reads to access paths corresponding to modules (code) in any
-    // (speculative) executions. Used to avoid a potential race with module publishing and
-    // Move-VM loader cache - see 'record' function comment for more information.
+    // Record all writes and reads to access paths corresponding to modules (code) by the
+    // committed transactions, in the commit order. Used to detect module R/W conflicts for
+    // the block limit - see 'check_module_rw_conflict_during_commit' for more information.
     module_writes: DashSet<T::Key>,
     module_reads: DashSet<T::Key>,
 }
@@ -113,26 +113,32 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         false
     }
 
-    /// Returns false on an error - if a module path that was read was previously written to, and vice versa.
-    /// Since parallel executor is instantiated per block, any module that is in the Move-VM loader
-    /// cache must previously be read and would be recorded in the 'module_reads' set. Any module
-    /// that is written (published or re-published) goes through transaction output write-set and
-    /// gets recorded in the 'module_writes' set. If these sets have an intersection, it is currently
-    /// possible that Move-VM loader cache loads a module and incorrectly uses it for another
-    /// transaction (e.g. a smaller transaction, or if the speculative execution of the publishing
-    /// transaction later aborts). The intersection is guaranteed to be found because we first
-    /// record the paths then check the other set (flags principle), and in this case we return an
-    /// error that ensures a fallback to a correct sequential execution.
-    /// When the sets do not have an intersection, it is impossible for the race to occur as any
-    /// module in the loader cache may not be published by a transaction in the ongoing block.
     pub(crate) fn record(
         &self,
         txn_idx: TxnIndex,
         input: CapturedReads<T>,
         output: ExecutionStatus<O, E>,
         arced_resource_writes: Vec<(T::Key, Arc<T::Value>, Option<Arc<MoveTypeLayout>>)>,
-    ) -> bool {
-        let written_modules = match &output {
+    ) {
+        *self.arced_resource_writes[txn_idx as usize].acquire() = arced_resource_writes;
+        self.inputs[txn_idx as usize].store(Some(Arc::new(input)));
+        self.outputs[txn_idx as usize].store(Some(Arc::new(output)));
+    }
+
+    /// Returns true if a module path read by the committed transaction was written by a
+    /// previously committed transaction, or vice versa. Module reads and writes are versioned
+    /// and validated by Block-STM, so conflicts do not affect correctness, but they are still
+    /// accounted for by the block limit. Must be called in the commit order, so that the
+    /// outcome is the same as during the sequential execution.
+    pub(crate) fn check_module_rw_conflict_during_commit(&self, txn_idx: TxnIndex) -> bool {
+        let input = self.inputs[txn_idx as usize]
+            .load_full()
+            .expect("[BlockSTM]: Input must be recorded for committed txn");
+        let written_modules = match self.outputs[txn_idx as usize]
+            .load_full()
+            .expect("[BlockSTM]: Execution output must be recorded after execution")
+            .as_ref()
+        {
             ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
                 output.module_write_set()
             },
@@ -141,17 +147,7 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             | ExecutionStatus::DelayedFieldsCodeInvariantError(_) => BTreeMap::new(),
         };
 
-        if self
-            .check_and_append_module_rw_conflict(input.module_reads.iter(), written_modules.keys())
-        {
-            return false;
-        }
-
-        *self.arced_resource_writes[txn_idx as usize].acquire() = arced_resource_writes;
-        self.inputs[txn_idx as usize].store(Some(Arc::new(input)));
-        self.outputs[txn_idx as usize].store(Some(Arc::new(output)));
-
-        true
+        self.check_and_append_module_rw_conflict(input.module_reads(), written_modules.keys())
     }
 
     pub(crate) fn check_and_append_module_rw_conflict<'a>(
@@ -345,6 +341,18 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             .collect()
     }
 
+    pub(crate) fn module_write_set(&self, txn_idx: TxnIndex) -> BTreeMap<T::Key, T::Value> {
+        self.outputs[txn_idx as usize]
+            .load()
+            .as_ref()
+            .map_or(BTreeMap::new(), |txn_output| match txn_output.as_ref() {
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => t.module_write_set(),
+                ExecutionStatus::Abort(_)
+                | ExecutionStatus::SpeculativeExecutionAbortError(_)
+                | ExecutionStatus::DelayedFieldsCodeInvariantError(_) => BTreeMap::new(),
+            })
+    }
+
     pub(crate) fn resource_group_metadata_ops(&self, txn_idx: TxnIndex) -> Vec<(T::Key, T::Value)> {
         forward_on_success_or_skip_rest!(self, txn_idx, resource_group_metadata_ops)
     }
@@ -28,6 +28,13 @@ impl<T: Transaction> ReadWriteSummary<T> {
         !self.reads.is_disjoint(&previous.writes)
     }
 
+    pub fn conflicting_keys_with_previous<'a>(
+        &'a self,
+        previous: &'a Self,
+    ) -> impl Iterator<Item = &'a InputOutputKey<T::Key, T::Tag, T::Identifier>> {
+        self.reads.intersection(&previous.writes)
+    }
+
     pub fn collapse_resource_group_conflicts(self) -> Self {
         let collapse = |k: InputOutputKey<T::Key, T::Tag, T::Identifier>| match k {
             InputOutputKey::Resource(k) => InputOutputKey::Resource(k),
@@ -230,6 +230,108 @@ fn skip_rest_gas_limit() {
     let _ = block_executor.execute_transactions_parallel((), &transactions, &data_view);
 }
 
+#[test]
+fn execution_trace_with_reexecutions() {
+    // Every transaction reads and writes the same key, so that transactions executed before the
+    // previous one commits fail validation and are executed again.
+    let key = KeyType::<u32>(0, false);
+    let num_txns = 100;
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![key],
+                vec![(key, ValueType::from_value(vec![i as u8], true))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let concurrency_level = 4;
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(concurrency_level)
+            .build()
+            .unwrap(),
+    );
+
+    // Re-executions depend on how the workers interleave, so a few blocks may be needed
+    let mut reexecuted_trace = None;
+    for _ in 0..10 {
+        let trace_dir = tempfile::tempdir().unwrap();
+        let mut config = BlockExecutorConfig::new_no_block_limit(concurrency_level);
+        config.local.execution_trace_dir = Some(trace_dir.path().to_path_buf());
+        let output = BlockExecutor::<
+            MockTransaction<KeyType<u32>, MockEvent>,
+            MockTask<KeyType<u32>, MockEvent>,
+            DeltaDataView<KeyType<u32>>,
+            NoOpTransactionCommitHook<MockOutput<KeyType<u32>, MockEvent>, usize>,
+            ExecutableTestType,
+        >::new(config, executor_thread_pool.clone(), None)
+        .execute_transactions_parallel((), &transactions, &data_view);
+        BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
+
+        let trace_paths: Vec<_> = std::fs::read_dir(trace_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(trace_paths.len(), 2, "Expected a JSON and a Chrome trace");
+        let json_path = trace_paths
+            .iter()
+            .find(|path| !path.to_string_lossy().ends_with(".chrome.json"))
+            .unwrap();
+        let trace: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(json_path).unwrap()).unwrap();
+        let reexecuted = trace["incarnations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|incarnation| incarnation["incarnation"].as_u64().unwrap() > 0);
+        if reexecuted {
+            reexecuted_trace = Some(trace);
+            break;
+        }
+    }
+    let trace = reexecuted_trace.expect("No block was executed with re-executions");
+
+    assert_eq!(trace["num_txns"].as_u64(), Some(num_txns));
+    let incarnations = trace["incarnations"].as_array().unwrap();
+    // Exactly one incarnation of each transaction is committed
+    let committed: Vec<u64> = incarnations
+        .iter()
+        .filter(|incarnation| !incarnation["committed_at_us"].is_null())
+        .map(|incarnation| incarnation["txn_idx"].as_u64().unwrap())
+        .collect();
+    assert_eq!(committed, (0..num_txns).collect::<Vec<_>>());
+
+    // Every re-execution follows an abort of the previous incarnation, blamed on the shared key
+    let key = format!("{:?}", key);
+    for incarnation in incarnations {
+        if incarnation["incarnation"].as_u64().unwrap() == 0 {
+            continue;
+        }
+        let previous = incarnations
+            .iter()
+            .find(|previous| {
+                previous["txn_idx"] == incarnation["txn_idx"]
+                    && previous["incarnation"].as_u64().unwrap() + 1
+                        == incarnation["incarnation"].as_u64().unwrap()
+            })
+            .expect("The previous incarnation must be traced");
+        let abort = &previous["abort"];
+        assert_eq!(abort["cause"]["type"], "validation_failure");
+        let invalid_reads = abort["cause"]["invalid_reads"].as_array().unwrap();
+        assert!(invalid_reads
+            .iter()
+            .all(|invalid_read| invalid_read["key"] == key.as_str()));
+    }
+    assert_eq!(trace["key_conflicts"][0]["key"], key.as_str());
+    assert!(trace["key_conflicts"][0]["num_aborts"].as_u64().unwrap() > 0);
+}
+
 // TODO: add unit test for block gas limit!
 fn run_and_assert<K, E>(transactions: Vec<MockTransaction<K, E>>)
 where
@@ -10,6 +10,7 @@ use crate::{
     },
     counters,
     scheduler::{DependencyResult, DependencyStatus, Scheduler, TWaitForDependency},
+    trace::ExecutionTracer,
     value_exchange::{
         does_value_need_exchange, filter_value_for_exchange, TemporaryValueToIdentifierMapping,
     },
@@ -37,7 +38,7 @@ use aptos_mvhashmap::{
 };
 use aptos_types::{
     delayed_fields::PanicError,
-    executable::{Executable, ModulePath},
+    executable::{Executable, ExecutableDescriptor, ModulePath},
     state_store::{
         errors::StateviewError,
         state_storage_usage::StateStorageUsage,
@@ -70,6 +71,7 @@ use std::{
         atomic::{AtomicU32, Ordering},
         Arc,
     },
+    time::Instant,
 };
 
 /// A struct which describes the result of the read from the proxy. The client
@@ -204,7 +206,7 @@ fn get_delayed_field_value_impl<T: Transaction>(
                 return Ok(value);
             },
             Err(PanicOr::Or(MVDelayedFieldsError::Dependency(dep_idx))) => {
-                if !wait_for_dependency(wait_for, txn_idx, dep_idx) {
+                if !wait_for_dependency(wait_for, txn_idx, dep_idx, id) {
                     // TODO[agg_v2](cleanup): think of correct return type
                     return Err(PanicOr::Or(DelayedFieldsSpeculativeError::InconsistentRead));
                 }
@@ -370,7 +372,7 @@ fn delayed_field_try_add_delta_outcome_impl<T: Transaction>(
                 ) {
                     Ok(v) => break v,
                     Err(MVDelayedFieldsError::Dependency(dep_idx)) => {
-                        if !wait_for_dependency(wait_for, txn_idx, dep_idx) {
+                        if !wait_for_dependency(wait_for, txn_idx, dep_idx, id) {
                             // TODO[agg_v2](cleanup): think of correct return type
                             return Err(PanicOr::Or(
                                 DelayedFieldsSpeculativeError::InconsistentRead,
@@ -406,10 +408,12 @@ fn wait_for_dependency(
     wait_for: &dyn TWaitForDependency,
     txn_idx: TxnIndex,
     dep_idx: TxnIndex,
+    key: &dyn Debug,
 ) -> bool {
     match wait_for.wait_for_dependency(txn_idx, dep_idx) {
         DependencyResult::Dependency(dep_condition) => {
             let _timer = counters::DEPENDENCY_WAIT_SECONDS.start_timer();
+            let wait_start = Instant::now();
             // Wait on a condition variable corresponding to the encountered
             // read dependency. Once the dep_idx finishes re-execution, scheduler
             // will mark the dependency as resolved, and then the txn_idx will be
@@ -429,6 +433,7 @@ fn wait_for_dependency(
             while let DependencyStatus::Unresolved = *dep_resolved {
                 dep_resolved = cvar.wait(dep_resolved).unwrap();
             }
+            wait_for.record_dependency_wait(txn_idx, dep_idx, key, wait_start);
             // dep resolved status is either resolved or execution halted.
             matches!(*dep_resolved, DependencyStatus::Resolved)
         },
@@ -453,6 +458,10 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
         }
     }
 
+    pub(crate) fn tracer(&self) -> Option<&'a ExecutionTracer> {
+        self.scheduler.tracer()
+    }
+
     pub(crate) fn set_delayed_field_value(&self, id: T::Identifier, base_value: DelayedFieldValue) {
         self.versioned_map
             .delayed_fields()
@@ -464,14 +473,49 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
         &self,
         key: &T::Key,
         txn_idx: TxnIndex,
-    ) -> anyhow::Result<MVModulesOutput<T::Value, X>, MVModulesError> {
-        // Record for the R/W path intersection fallback for modules.
-        self.captured_reads
-            .borrow_mut()
-            .module_reads
-            .push(key.clone());
+    ) -> PartialVMResult<Option<MVModulesOutput<T::Value, X>>> {
+        use MVModulesError::*;
+        use MVModulesOutput::*;
+
+        loop {
+            let (output, descriptor) = match self.versioned_map.modules().fetch_module(key, txn_idx)
+            {
+                Ok(Executable((x, descriptor))) => {
+                    (Some(Executable((x, descriptor.clone()))), descriptor)
+                },
+                Ok(Module((module, hash))) => (
+                    Some(Module((module, hash))),
+                    ExecutableDescriptor::Published(hash),
+                ),
+                Err(NotFound) => (None, ExecutableDescriptor::Storage),
+                Err(Dependency(dep_idx)) => {
+                    // Wait for the publishing transaction to be re-executed, same as for data.
+                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx, key) {
+                        return Err(PartialVMError::new(
+                            StatusCode::SPECULATIVE_EXECUTION_ABORT_ERROR,
+                        )
+                        .with_message("Interrupted as block execution was halted".to_string()));
+                    }
+                    continue;
+                },
+            };
 
-        self.versioned_map.modules().fetch_module(key, txn_idx)
+            if self
+                .captured_reads
+                .borrow_mut()
+                .capture_module_read(key.clone(), descriptor)
+                .is_err()
+            {
+                return Err(
+                    PartialVMError::new(StatusCode::SPECULATIVE_EXECUTION_ABORT_ERROR)
+                        .with_message(
+                            "Inconsistency in module reads (must be due to speculation)"
+                                .to_string(),
+                        ),
+                );
+            }
+            return Ok(output);
+        }
     }
 
     fn read_group_size(
@@ -508,7 +552,7 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
                     unreachable!("Reading group size does not require a specific tag look-up");
                 },
                 Err(Dependency(dep_idx)) => {
-                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx) {
+                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx, group_key) {
                         return Err(PartialVMError::new(
                             StatusCode::SPECULATIVE_EXECUTION_ABORT_ERROR,
                         )
@@ -633,7 +677,7 @@ impl<'a, T: Transaction, X: Executable> ResourceState<T> for ParallelState<'a, T
                     return ReadResult::Uninitialized;
                 },
                 Err(Dependency(dep_idx)) => {
-                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx) {
+                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx, key) {
                         return ReadResult::HaltSpeculativeExecution(
                             "Interrupted as block execution was halted".to_string(),
                         );
@@ -737,7 +781,7 @@ impl<'a, T: Transaction, X: Executable> ResourceGroupState<T> for ParallelState<
                     return Ok(GroupReadResult::Value(None, None));
                 },
                 Err(Dependency(dep_idx)) => {
-                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx) {
+                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx, group_key) {
                         // TODO[agg_v2](cleanup): consider changing from PartialVMResult<GroupReadResult> to GroupReadResult
                         // like in ReadResult for resources.
                         return Err(PartialVMError::new(
@@ -1537,18 +1581,12 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TModuleView
 
         match &self.latest_view {
             ViewState::Sync(state) => {
-                use MVModulesError::*;
                 use MVModulesOutput::*;
 
-                match state.fetch_module(state_key, self.txn_idx) {
-                    Ok(Executable(_)) => unreachable!("Versioned executable not implemented"),
-                    Ok(Module((v, _))) => Ok(v.as_state_value()),
-                    Err(Dependency(_)) => {
-                        // Return anything (e.g. module does not exist) to avoid waiting,
-                        // because parallel execution will fall back to sequential anyway.
-                        Ok(None)
-                    },
-                    Err(NotFound) => self.get_raw_base_value(state_key),
+                match state.fetch_module(state_key, self.txn_idx)? {
+                    Some(Executable(_)) => unreachable!("Versioned executable not implemented"),
+                    Some(Module((v, _))) => Ok(v.as_state_value()),
+                    None => self.get_raw_base_value(state_key),
                 }
             },
             ViewState::Unsync(state) => {
@@ -32,6 +32,7 @@ mod nft_dao;
 mod object_code_deployment;
 mod offer_rotation_capability;
 mod offer_signer_capability;
+mod orderless_transactions;
 mod per_category_gas_limits;
 mod resource_groups;
 mod rotate_auth_key;
@@ -0,0 +1,183 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{assert_success, MoveHarness};
+use aptos_cached_packages::aptos_stdlib;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    on_chain_config::FeatureFlag,
+    transaction::{
+        ExecutionStatus, ReplayProtector, SignedTransaction, TransactionOutput, TransactionStatus,
+    },
+};
+use move_core_types::{identifier::Identifier, language_storage::StructTag, vm_status::StatusCode};
+
+fn orderless_transfer(
+    h: &mut MoveHarness,
+    sender: &Account,
+    nonce: u64,
+    amount: u64,
+    expiration_secs: u64,
+) -> SignedTransaction {
+    let expiration_timestamp_secs = h.executor.get_block_time_seconds() + expiration_secs;
+    sender
+        .transaction()
+        .sequence_number(ReplayProtector::Nonce(nonce).to_sequence_number_field())
+        .payload(aptos_stdlib::aptos_coin_transfer(
+            AccountAddress::from_hex_literal("0xb0b").unwrap(),
+            amount,
+        ))
+        .gas_unit_price(100)
+        .ttl(expiration_timestamp_secs)
+        .sign()
+}
+
+fn is_nonce_used(h: &mut MoveHarness, addr: AccountAddress, nonce: u64) -> bool {
+    let used = h
+        .execute_view_function(
+            str::parse("0x1::nonce_validation::is_nonce_used").unwrap(),
+            vec![],
+            vec![
+                bcs::to_bytes(&addr).unwrap(),
+                bcs::to_bytes(&nonce).unwrap(),
+            ],
+        )
+        .values
+        .unwrap()
+        .pop()
+        .unwrap();
+    bcs::from_bytes::<bool>(&used).unwrap()
+}
+
+fn storage_fee_used(output: &TransactionOutput) -> u64 {
+    output
+        .try_extract_fee_statement()
+        .unwrap()
+        .unwrap()
+        .storage_fee_used()
+}
+
+fn setup() -> (MoveHarness, Account) {
+    let mut h = MoveHarness::new_with_features(vec![FeatureFlag::ORDERLESS_TRANSACTIONS], vec![]);
+    h.new_account_at(AccountAddress::from_hex_literal("0xb0b").unwrap());
+    let alice = h.new_account_with_key_pair();
+    // Puts the block time on chain, the prologue compares expiration times against it.
+    h.new_epoch();
+    (h, alice)
+}
+
+#[test]
+fn test_orderless_transaction_records_nonce() {
+    let (mut h, alice) = setup();
+    let nonce_history = StructTag {
+        address: AccountAddress::ONE,
+        module: Identifier::new("nonce_validation").unwrap(),
+        name: Identifier::new("NonceHistory").unwrap(),
+        type_args: vec![],
+    };
+    assert!(!h.exists_resource(alice.address(), nonce_history.clone()));
+    let balance = h.read_aptos_balance(alice.address());
+
+    let txn = orderless_transfer(&mut h, &alice, 7, 1000, 30);
+    let output = h.run_raw(txn);
+    assert_success!(output.status().clone());
+
+    // Orderless transactions leave the sequence number alone.
+    assert_eq!(h.sequence_number(alice.address()), 0);
+    assert!(is_nonce_used(&mut h, *alice.address(), 7));
+    assert!(!is_nonce_used(&mut h, *alice.address(), 8));
+    // The nonce history is created by, and charged to, the transaction.
+    assert!(h.exists_resource(alice.address(), nonce_history));
+    assert!(storage_fee_used(&output) > 0);
+    assert_eq!(
+        h.read_aptos_balance(alice.address()),
+        balance - 1000 - output.gas_used() * 100
+    );
+}
+
+#[test]
+fn test_replayed_nonce_is_rejected() {
+    let (mut h, alice) = setup();
+
+    let txn = orderless_transfer(&mut h, &alice, 7, 1000, 30);
+    assert_success!(h.run_raw(txn.clone()).status().clone());
+
+    // Neither the same transaction nor a different one may reuse the nonce.
+    let output = h.run_raw(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(StatusCode::SEQUENCE_NONCE_INVALID)
+    );
+    let txn = orderless_transfer(&mut h, &alice, 7, 2000, 10);
+    let output = h.run_raw(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(StatusCode::SEQUENCE_NONCE_INVALID)
+    );
+
+    // Other nonces are unaffected.
+    let txn = orderless_transfer(&mut h, &alice, 8, 1000, 30);
+    assert_success!(h.run_raw(txn).status().clone());
+
+    // Once the first transaction expired, its nonce can be used again.
+    h.fast_forward(31);
+    h.executor.new_block();
+    assert!(!is_nonce_used(&mut h, *alice.address(), 7));
+    let txn = orderless_transfer(&mut h, &alice, 7, 1000, 30);
+    assert_success!(h.run_raw(txn).status().clone());
+}
+
+#[test]
+fn test_expiration_too_far_in_future() {
+    let (mut h, alice) = setup();
+
+    let txn = orderless_transfer(&mut h, &alice, 7, 1000, 61);
+    let output = h.run_raw(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(StatusCode::TRANSACTION_EXPIRATION_TOO_FAR_IN_FUTURE)
+    );
+    assert!(!is_nonce_used(&mut h, *alice.address(), 7));
+
+    let txn = orderless_transfer(&mut h, &alice, 7, 1000, 60);
+    assert_success!(h.run_raw(txn).status().clone());
+}
+
+#[test]
+fn test_nonce_is_recorded_when_transaction_aborts() {
+    let (mut h, alice) = setup();
+    let balance = h.read_aptos_balance(alice.address());
+
+    // Transferring more than the balance aborts in the payload.
+    let txn = orderless_transfer(&mut h, &alice, 7, balance + 1, 30);
+    let output = h.run_raw(txn.clone());
+    assert!(matches!(
+        output.status(),
+        TransactionStatus::Keep(ExecutionStatus::MoveAbort { .. })
+    ));
+    assert!(storage_fee_used(&output) > 0);
+    assert_eq!(h.sequence_number(alice.address()), 0);
+    assert!(is_nonce_used(&mut h, *alice.address(), 7));
+
+    let output = h.run_raw(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(StatusCode::SEQUENCE_NONCE_INVALID)
+    );
+}
+
+#[test]
+fn test_orderless_transactions_disabled() {
+    let mut h = MoveHarness::new_with_features(vec![], vec![FeatureFlag::ORDERLESS_TRANSACTIONS]);
+    h.new_account_at(AccountAddress::from_hex_literal("0xb0b").unwrap());
+    let alice = h.new_account_with_key_pair();
+    h.new_epoch();
+
+    let txn = orderless_transfer(&mut h, &alice, 7, 1000, 30);
+    let output = h.run_raw(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(StatusCode::SEQUENCE_NUMBER_TOO_BIG)
+    );
+}
@@ -490,6 +490,8 @@ impl FakeExecutor {
                 },
                 allow_fallback: self.allow_block_executor_fallback,
                 discard_failed_blocks: false,
+                execution_trace_dir: None,
+                conflict_history: None,
             },
             onchain: onchain_config,
         };
@@ -0,0 +1,181 @@
+/// Replay protection for orderless transactions.
+///
+/// Instead of a sequence number, an orderless transaction carries a nonce chosen by its sender.
+/// The nonce is remembered until the transaction expires, and a transaction reusing a remembered
+/// nonce is rejected by the prologue. Because orderless transactions may only expire a bounded
+/// amount of time into the future, each account only needs to remember a bounded set of nonces.
+module aptos_framework::nonce_validation {
+    use std::signer;
+    use std::vector;
+    use aptos_std::table::{Self, Table};
+    use aptos_framework::timestamp;
+
+    /// How far into the future an orderless transaction may expire, in seconds.
+    const MAX_EXPIRATION_WINDOW_SECS: u64 = 60;
+
+    /// How many seconds of expired nonces a single transaction garbage collects at most.
+    const MAX_GC_SECS_PER_TXN: u64 = 16;
+
+    /// How many expired nonces a single transaction garbage collects at most.
+    const MAX_GC_NONCES_PER_TXN: u64 = 32;
+
+    /// The nonces an account used for orderless transactions that may not have expired yet.
+    struct NonceHistory has key {
+        /// Nonce to the expiration time of the transaction that used it.
+        nonce_to_expiration: Table<u64, u64>,
+        /// Expiration time to the nonces of the transactions expiring at that time.
+        expiration_buckets: Table<u64, vector<u64>>,
+        /// All buckets before this time have been garbage collected.
+        next_gc_time: u64,
+        /// The latest expiration time that has a bucket.
+        max_expiration_time: u64,
+    }
+
+    public fun max_expiration_window_secs(): u64 {
+        MAX_EXPIRATION_WINDOW_SECS
+    }
+
+    #[view]
+    /// Returns whether `nonce` was used by an orderless transaction of `addr` that has not
+    /// expired yet.
+    public fun is_nonce_used(addr: address, nonce: u64): bool acquires NonceHistory {
+        if (!exists<NonceHistory>(addr)) {
+            return false
+        };
+        let history = borrow_global<NonceHistory>(addr);
+        table::contains(&history.nonce_to_expiration, nonce)
+            && *table::borrow(&history.nonce_to_expiration, nonce) > timestamp::now_seconds()
+    }
+
+    /// Remembers `nonce` until `expiration_time`. Called by the Adapter while executing an
+    /// orderless transaction, after the prologue checked that the nonce is not in use, so that
+    /// the transaction pays for the storage it takes.
+    fun record_nonce(
+        account: &signer,
+        nonce: u64,
+        expiration_time: u64,
+    ) acquires NonceHistory {
+        let addr = signer::address_of(account);
+        let now = timestamp::now_seconds();
+        if (!exists<NonceHistory>(addr)) {
+            move_to(account, NonceHistory {
+                nonce_to_expiration: table::new(),
+                expiration_buckets: table::new(),
+                next_gc_time: now,
+                max_expiration_time: 0,
+            });
+        };
+
+        let history = borrow_global_mut<NonceHistory>(addr);
+        garbage_collect(history, now);
+
+        table::upsert(&mut history.nonce_to_expiration, nonce, expiration_time);
+        if (!table::contains(&history.expiration_buckets, expiration_time)) {
+            table::add(&mut history.expiration_buckets, expiration_time, vector::empty());
+        };
+        vector::push_back(table::borrow_mut(&mut history.expiration_buckets, expiration_time), nonce);
+        if (expiration_time > history.max_expiration_time) {
+            history.max_expiration_time = expiration_time;
+        };
+    }
+
+    /// Forgets the nonces of transactions that expired at or before `now`, a bounded number of
+    /// seconds and nonces at a time.
+    fun garbage_collect(history: &mut NonceHistory, now: u64) {
+        if (history.next_gc_time > history.max_expiration_time) {
+            // Every bucket has been collected, skip the seconds in which nothing expires.
+            if (now > history.next_gc_time) {
+                history.next_gc_time = now;
+            };
+            return
+        };
+
+        let collected_secs = 0;
+        let collected_nonces = 0;
+        while (history.next_gc_time <= now
+            && collected_secs < MAX_GC_SECS_PER_TXN
+            && collected_nonces < MAX_GC_NONCES_PER_TXN) {
+            let time = history.next_gc_time;
+            if (table::contains(&history.expiration_buckets, time)) {
+                let nonces = table::borrow_mut(&mut history.expiration_buckets, time);
+                while (!vector::is_empty(nonces) && collected_nonces < MAX_GC_NONCES_PER_TXN) {
+                    let nonce = vector::pop_back(nonces);
+                    // The nonce may have been reused since, by a transaction expiring later.
+                    if (table::contains(&history.nonce_to_expiration, nonce)
+                        && *table::borrow(&history.nonce_to_expiration, nonce) == time) {
+                        table::remove(&mut history.nonce_to_expiration, nonce);
+                    };
+                    collected_nonces = collected_nonces + 1;
+                };
+                if (!vector::is_empty(nonces)) {
+                    // The rest of this bucket is collected by later transactions.
+                    return
+                };
+                table::remove(&mut history.expiration_buckets, time);
+            };
+            history.next_gc_time = time + 1;
+            collected_secs = collected_secs + 1;
+        };
+    }
+
+    #[test_only]
+    public fun nonce_count(addr: address): u64 acquires NonceHistory {
+        table::length(&borrow_global<NonceHistory>(addr).nonce_to_expiration)
+    }
+
+    #[test(aptos_framework = @aptos_framework, account = @0x123)]
+    fun test_nonces_are_forgotten_after_expiration(
+        aptos_framework: &signer,
+        account: &signer,
+    ) acquires NonceHistory {
+        timestamp::set_time_has_started_for_testing(aptos_framework);
+        let addr = signer::address_of(account);
+
+        record_nonce(account, 1, 10);
+        record_nonce(account, 2, 20);
+        assert!(is_nonce_used(addr, 1), 0);
+        assert!(is_nonce_used(addr, 2), 1);
+        assert!(!is_nonce_used(addr, 3), 2);
+
+        // Nonce 1 expired, so it is free again even before being garbage collected.
+        timestamp::update_global_time_for_test_secs(10);
+        assert!(!is_nonce_used(addr, 1), 3);
+        assert!(is_nonce_used(addr, 2), 4);
+
+        // Recording another nonce collects the expired one.
+        record_nonce(account, 3, 30);
+        assert!(nonce_count(addr) == 2, 5);
+
+        // Reusing an expired nonce keeps it until its new expiration.
+        record_nonce(account, 1, 40);
+        timestamp::update_global_time_for_test_secs(35);
+        record_nonce(account, 4, 50);
+        assert!(is_nonce_used(addr, 1), 6);
+        assert!(!is_nonce_used(addr, 2), 7);
+        assert!(!is_nonce_used(addr, 3), 8);
+    }
+
+    #[test(aptos_framework = @aptos_framework, account = @0x123)]
+    fun test_garbage_collection_is_bounded(
+        aptos_framework: &signer,
+        account: &signer,
+    ) acquires NonceHistory {
+        timestamp::set_time_has_started_for_testing(aptos_framework);
+        let addr = signer::address_of(account);
+
+        let nonce = 0;
+        while (nonce < MAX_GC_NONCES_PER_TXN + 8) {
+            record_nonce(account, nonce, 10);
+            nonce = nonce + 1;
+        };
+
+        // A single transaction only collects part of the expired bucket.
+        timestamp::update_global_time_for_test_secs(10);
+        record_nonce(account, nonce, 20);
+        assert!(nonce_count(addr) == 9, 0);
+
+        // The next one collects the rest of it.
+        record_nonce(account, nonce + 1, 20);
+        assert!(nonce_count(addr) == 2, 1);
+    }
+}
@@ -0,0 +1,41 @@
+spec aptos_framework::nonce_validation {
+    spec module {
+        pragma verify = true;
+        pragma aborts_if_is_strict;
+    }
+
+    spec is_nonce_used(addr: address, nonce: u64): bool {
+        use aptos_framework::timestamp::{CurrentTimeMicroseconds};
+
+        let used = exists<NonceHistory>(addr)
+            && table::spec_contains(global<NonceHistory>(addr).nonce_to_expiration, nonce);
+        aborts_if used && !exists<CurrentTimeMicroseconds>(@aptos_framework);
+        ensures result == (used
+            && table::spec_get(global<NonceHistory>(addr).nonce_to_expiration, nonce) > timestamp::spec_now_seconds());
+    }
+
+    /// The nonce is remembered until `expiration_time`, whatever garbage collection did before.
+    spec record_nonce(
+        account: &signer,
+        nonce: u64,
+        expiration_time: u64,
+    ) {
+        use aptos_framework::timestamp::{CurrentTimeMicroseconds};
+
+        let addr = signer::address_of(account);
+        aborts_if !exists<CurrentTimeMicroseconds>(@aptos_framework);
+        ensures exists<NonceHistory>(addr);
+        ensures table::spec_contains(global<NonceHistory>(addr).nonce_to_expiration, nonce);
+        ensures table::spec_get(global<NonceHistory>(addr).nonce_to_expiration, nonce) == expiration_time;
+        ensures global<NonceHistory>(addr).max_expiration_time >= expiration_time;
+    }
+
+    /// The loops are bounded by `MAX_GC_SECS_PER_TXN` and `MAX_GC_NONCES_PER_TXN`, but the prover
+    /// can't infer the invariants needed to verify them.
+    spec garbage_collect(history: &mut NonceHistory, now: u64) {
+        pragma opaque;
+        pragma verify = false;
+        aborts_if false;
+        ensures history.max_expiration_time == old(history.max_expiration_time);
+    }
+}
@@ -9,6 +9,7 @@ module aptos_framework::transaction_validation {
     use aptos_framework::aptos_coin::AptosCoin;
     use aptos_framework::chain_id;
     use aptos_framework::coin;
+    use aptos_framework::nonce_validation;
     use aptos_framework::system_addresses;
     use aptos_framework::timestamp;
     use aptos_framework::transaction_fee;
@@ -46,6 +47,11 @@ module aptos_framework::transaction_validation {
     const PROLOGUE_ESEQUENCE_NUMBER_TOO_BIG: u64 = 1008;
     const PROLOGUE_ESECONDARY_KEYS_ADDRESSES_COUNT_MISMATCH: u64 = 1009;
     const PROLOGUE_EFEE_PAYER_NOT_ENABLED: u64 = 1010;
+    const PROLOGUE_ENONCE_ALREADY_USED: u64 = 1011;
+    const PROLOGUE_EEXPIRATION_TOO_FAR_IN_FUTURE: u64 = 1012;
+
+    /// Sequence numbers at or above this value carry the nonce of an orderless transaction.
+    const ORDERLESS_SEQUENCE_NUMBER_START: u64 = 9223372036854775808;
 
 
     /// Only called during genesis to initialize system resources for this module.
@@ -100,21 +106,37 @@ module aptos_framework::transaction_validation {
                 error::invalid_argument(PROLOGUE_EINVALID_ACCOUNT_AUTH_KEY),
             );
 
-            let account_sequence_number = account::get_sequence_number(transaction_sender);
-            assert!(
-                txn_sequence_number < (1u64 << 63),
-                error::out_of_range(PROLOGUE_ESEQUENCE_NUMBER_TOO_BIG)
-            );
+            if (is_orderless(txn_sequence_number)) {
+                // Orderless transaction: the nonce must not be in use until the transaction
+                // expires, and it expires soon enough for the nonce to be remembered that long.
+                assert!(
+                    txn_expiration_time <= timestamp::now_seconds() + nonce_validation::max_expiration_window_secs(),
+                    error::invalid_argument(PROLOGUE_EEXPIRATION_TOO_FAR_IN_FUTURE),
+                );
+                assert!(
+                    !nonce_validation::is_nonce_used(
+                        transaction_sender,
+                        txn_sequence_number - ORDERLESS_SEQUENCE_NUMBER_START
+                    ),
+                    error::invalid_argument(PROLOGUE_ENONCE_ALREADY_USED),
+                );
+            } else {
+                let account_sequence_number = account::get_sequence_number(transaction_sender);
+                assert!(
+                    txn_sequence_number < (1u64 << 63),
+                    error::out_of_range(PROLOGUE_ESEQUENCE_NUMBER_TOO_BIG)
+                );
 
-            assert!(
-                txn_sequence_number >= account_sequence_number,
-                error::invalid_argument(PROLOGUE_ESEQUENCE_NUMBER_TOO_OLD)
-            );
+                assert!(
+                    txn_sequence_number >= account_sequence_number,
+                    error::invalid_argument(PROLOGUE_ESEQUENCE_NUMBER_TOO_OLD)
+                );
 
-            assert!(
-                txn_sequence_number == account_sequence_number,
-                error::invalid_argument(PROLOGUE_ESEQUENCE_NUMBER_TOO_NEW)
-            );
+                assert!(
+                    txn_sequence_number == account_sequence_number,
+                    error::invalid_argument(PROLOGUE_ESEQUENCE_NUMBER_TOO_NEW)
+                );
+            };
         } else {
             // In this case, the transaction is sponsored and the account does not exist, so ensure
             // the default values match.
@@ -263,6 +285,35 @@ module aptos_framework::transaction_validation {
         txn_gas_price: u64,
         txn_max_gas_units: u64,
         gas_units_remaining: u64
+    ) {
+        charge_transaction_fee(gas_payer, storage_fee_refunded, txn_gas_price, txn_max_gas_units, gas_units_remaining);
+
+        // Increment sequence number
+        let addr = signer::address_of(&account);
+        account::increment_sequence_number(addr);
+    }
+
+    /// Epilogue function of an orderless transaction, run instead of `epilogue_gas_payer`.
+    /// There is no sequence number to increment: the Adapter already recorded the nonce with
+    /// `nonce_validation::record_nonce` while executing the transaction, so that its storage is
+    /// charged. Called by the Adapter
+    fun orderless_epilogue(
+        _account: signer,
+        gas_payer: address,
+        storage_fee_refunded: u64,
+        txn_gas_price: u64,
+        txn_max_gas_units: u64,
+        gas_units_remaining: u64,
+    ) {
+        charge_transaction_fee(gas_payer, storage_fee_refunded, txn_gas_price, txn_max_gas_units, gas_units_remaining);
+    }
+
+    fun charge_transaction_fee(
+        gas_payer: address,
+        storage_fee_refunded: u64,
+        txn_gas_price: u64,
+        txn_max_gas_units: u64,
+        gas_units_remaining: u64
     ) {
         assert!(txn_max_gas_units >= gas_units_remaining, error::invalid_argument(EOUT_OF_GAS));
         let gas_used = txn_max_gas_units - gas_units_remaining;
@@ -301,9 +352,9 @@ module aptos_framework::transaction_validation {
             let mint_amount = storage_fee_refunded - amount_to_burn;
             transaction_fee::mint_and_refund(gas_payer, mint_amount)
         };
+    }
 
-        // Increment sequence number
-        let addr = signer::address_of(&account);
-        account::increment_sequence_number(addr);
+    fun is_orderless(txn_sequence_number: u64): bool {
+        txn_sequence_number >= ORDERLESS_SEQUENCE_NUMBER_START && features::orderless_transactions_enabled()
     }
 }
@@ -53,6 +53,7 @@ spec aptos_framework::transaction_validation {
         use aptos_framework::chain_id::{ChainId};
         use aptos_framework::account::{Account};
         use aptos_framework::coin::{CoinStore};
+        use aptos_framework::nonce_validation;
         sender: signer;
         gas_payer: address;
         txn_sequence_number: u64;
@@ -69,17 +70,28 @@ spec aptos_framework::transaction_validation {
         aborts_if !(chain_id::get() == chain_id);
         let transaction_sender = signer::address_of(sender);
 
-        aborts_if (
+        let is_orderless = txn_sequence_number >= ORDERLESS_SEQUENCE_NUMBER_START
+            && features::spec_is_enabled(features::ORDERLESS_TRANSACTIONS);
+        let requires_account = (
             !features::spec_is_enabled(features::SPONSORED_AUTOMATIC_ACCOUNT_CREATION)
             || account::exists_at(transaction_sender)
             || transaction_sender == gas_payer
             || txn_sequence_number > 0
-        ) && (
-            !(txn_sequence_number >= global<Account>(transaction_sender).sequence_number)
-            || !(txn_authentication_key == global<Account>(transaction_sender).authentication_key)
+        );
+
+        aborts_if requires_account && (
+            !(txn_authentication_key == global<Account>(transaction_sender).authentication_key)
             || !account::exists_at(transaction_sender)
+        );
+        aborts_if requires_account && !is_orderless && (
+            !(txn_sequence_number >= global<Account>(transaction_sender).sequence_number)
             || !(txn_sequence_number == global<Account>(transaction_sender).sequence_number)
         );
+        aborts_if requires_account && is_orderless && (
+            timestamp::now_seconds() + nonce_validation::max_expiration_window_secs() > MAX_U64
+            || txn_expiration_time > timestamp::now_seconds() + nonce_validation::max_expiration_window_secs()
+            || nonce_validation::is_nonce_used(transaction_sender, txn_sequence_number - ORDERLESS_SEQUENCE_NUMBER_START)
+        );
 
         aborts_if features::spec_is_enabled(features::SPONSORED_AUTOMATIC_ACCOUNT_CREATION)
             && transaction_sender != gas_payer
@@ -87,7 +99,7 @@ spec aptos_framework::transaction_validation {
             && !account::exists_at(transaction_sender)
             && txn_authentication_key != bcs::to_bytes(transaction_sender);
 
-        aborts_if !(txn_sequence_number < (1u64 << 63));
+        aborts_if !is_orderless && !(txn_sequence_number < (1u64 << 63));
 
         let max_transaction_fee = txn_gas_price * txn_max_gas_units;
         aborts_if max_transaction_fee > MAX_U64;
@@ -245,10 +257,63 @@ spec aptos_framework::transaction_validation {
         include EpilogueGasPayerAbortsIf;
     }
 
+    /// Abort according to the conditions.
+    /// The nonce is recorded before the epilogue runs, so only the fee is charged and the
+    /// sequence number of the sender stays the same.
+    spec orderless_epilogue(
+        _account: signer,
+        gas_payer: address,
+        storage_fee_refunded: u64,
+        txn_gas_price: u64,
+        txn_max_gas_units: u64,
+        gas_units_remaining: u64,
+    ) {
+        use aptos_framework::account::{Account};
+
+        include ChargeTransactionFeeAbortsIf;
+        let addr = signer::address_of(_account);
+        ensures exists<Account>(addr) ==>
+            global<Account>(addr).sequence_number == old(global<Account>(addr)).sequence_number;
+    }
+
+    /// Abort according to the conditions.
+    /// `AptosCoinCapabilities` and `CoinInfo` should exist.
+    /// Skip transaction_fee::burn_fee verification.
+    spec charge_transaction_fee(
+        gas_payer: address,
+        storage_fee_refunded: u64,
+        txn_gas_price: u64,
+        txn_max_gas_units: u64,
+        gas_units_remaining: u64
+    ) {
+        include ChargeTransactionFeeAbortsIf;
+    }
+
     spec schema EpilogueGasPayerAbortsIf {
+        use aptos_framework::account::{Account};
+
+        account: signer;
+        gas_payer: address;
+        storage_fee_refunded: u64;
+        txn_gas_price: u64;
+        txn_max_gas_units: u64;
+        gas_units_remaining: u64;
+
+        include ChargeTransactionFeeAbortsIf;
+
+        // Check account invariants.
+        let addr = signer::address_of(account);
+        let pre_account = global<account::Account>(addr);
+        let post account = global<account::Account>(addr);
+
+        aborts_if !exists<Account>(addr);
+        aborts_if !(global<Account>(addr).sequence_number < MAX_U64);
+        ensures account.sequence_number == pre_account.sequence_number + 1;
+    }
+
+    spec schema ChargeTransactionFeeAbortsIf {
         use std::option;
         use aptos_std::type_info;
-        use aptos_framework::account::{Account};
         use aptos_framework::aggregator;
         use aptos_framework::aptos_coin::{AptosCoin};
         use aptos_framework::coin;
@@ -256,7 +321,6 @@ spec aptos_framework::transaction_validation {
         use aptos_framework::optional_aggregator;
         use aptos_framework::transaction_fee::{AptosCoinCapabilities, AptosCoinMintCapability, CollectedFeesPerBlock};
 
-        account: signer;
         gas_payer: address;
         storage_fee_refunded: u64;
         txn_gas_price: u64;
@@ -269,19 +333,13 @@ spec aptos_framework::transaction_validation {
         aborts_if !(txn_gas_price * gas_used <= MAX_U64);
         let transaction_fee_amount = txn_gas_price * gas_used;
 
-        // Check account invariants.
-        let addr = signer::address_of(account);
+        // Check gas payer invariants.
         let pre_balance = global<coin::CoinStore<AptosCoin>>(gas_payer).coin.value;
         let post balance = global<coin::CoinStore<AptosCoin>>(gas_payer).coin.value;
-        let pre_account = global<account::Account>(addr);
-        let post account = global<account::Account>(addr);
 
         aborts_if !exists<CoinStore<AptosCoin>>(gas_payer);
-        aborts_if !exists<Account>(addr);
-        aborts_if !(global<Account>(addr).sequence_number < MAX_U64);
         aborts_if pre_balance < transaction_fee_amount;
         ensures balance == pre_balance - transaction_fee_amount + storage_fee_refunded;
-        ensures account.sequence_number == pre_account.sequence_number + 1;
 
 
         // Check fee collection.
@@ -325,7 +383,7 @@ spec aptos_framework::transaction_validation {
         let total_supply = coin::supply<AptosCoin>;
         let post post_total_supply = coin::supply<AptosCoin>;
 
-        aborts_if amount_to_mint > 0 && !exists<CoinStore<AptosCoin>>(addr);
+        aborts_if amount_to_mint > 0 && !exists<CoinStore<AptosCoin>>(gas_payer);
         aborts_if amount_to_mint > 0 && !exists<AptosCoinMintCapability>(@aptos_framework);
         aborts_if amount_to_mint > 0 && total_supply + amount_to_mint > MAX_U128;
         ensures amount_to_mint > 0 ==> post_total_supply == total_supply + amount_to_mint;
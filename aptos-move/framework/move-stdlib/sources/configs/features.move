@@ -408,6 +408,17 @@ module std::features {
         is_enabled(MAX_OBJECT_NESTING_CHECK)
     }
 
+    /// Whether transactions can use a nonce instead of a sequence number for replay protection,
+    /// so that they do not need to execute in order.
+    /// Lifetime: transient
+    const ORDERLESS_TRANSACTIONS: u64 = 54;
+
+    public fun get_orderless_transactions_feature(): u64 { ORDERLESS_TRANSACTIONS }
+
+    public fun orderless_transactions_enabled(): bool acquires Features {
+        is_enabled(ORDERLESS_TRANSACTIONS)
+    }
+
     // ============================================================================================
     // Feature Flag Implementation
 
@@ -0,0 +1,644 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Forking a single node test environment from an existing `AptosDB`
+//!
+//! The DB is copied into the node's storage directory, and a genesis transaction is applied on
+//! top of its latest state, the same way a network is recovered after losing quorum.  Its write
+//! set is produced by calling the framework stored in the DB from a Move session: the local
+//! validator takes over the validator with the most voting power, the other validators are
+//! removed, and a new epoch is started.  The root account gets the mint key of the test
+//! environment and the capability to mint, so that the faucet works, and the chain id is set to
+//! the test chain id.  All the other on-chain state is kept.
+
+use crate::utils::copy_dir;
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_config::config::{IdentityBlob, InitialSafetyRulesConfig, NodeConfig, WaypointConfig};
+use aptos_crypto::{
+    bls12381,
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    PrivateKey,
+};
+use aptos_db::AptosDB;
+use aptos_executor::db_bootstrapper::generate_waypoint;
+use aptos_gas_schedule::{MiscGasParameters, NativeGasParameters, LATEST_GAS_FEATURE_VERSION};
+use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
+use aptos_types::{
+    account_address::AccountAddress,
+    account_config::{aptos_test_root_address, reserved_vm_address, CORE_CODE_ADDRESS},
+    chain_id::ChainId,
+    dkg::DKGState,
+    on_chain_config::{
+        access_path_for_config, FeatureFlag, Features, OnChainConfig, TimedFeaturesBuilder,
+        ValidatorSet,
+    },
+    state_store::state_key::StateKey,
+    transaction::{authenticator::AuthenticationKey, ChangeSet, Transaction, WriteSetPayload},
+    utility_coin::APTOS_COIN_TYPE,
+    validator_config::ValidatorConfig,
+    write_set::WriteOp,
+};
+use aptos_vm::{
+    data_cache::AsMoveResolver,
+    move_vm_ext::{MoveVmExt, SessionExt, SessionId},
+    AptosVM,
+};
+use aptos_vm_types::storage::change_set_configs::ChangeSetConfigs;
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, TypeTag},
+    value::{serialize_values, MoveStruct, MoveValue},
+};
+use move_vm_types::gas::UnmeteredGasMeter;
+use serde::de::DeserializeOwned;
+use std::{fs, path::Path};
+
+/// The balance given to the root account, to pay for the gas of minting
+const ROOT_ACCOUNT_BALANCE: u64 = 1_000_000_000 * 100_000_000;
+
+/// Copies the DB at `source_db` into the storage of the freshly created `node_config` of the test
+/// environment in `test_dir`, and sets it up to start from a fork of that DB.  The updated config
+/// and waypoint are saved to `test_dir`, so restarting the environment keeps the fork.
+pub fn fork_test_environment_from_db(
+    node_config: &mut NodeConfig,
+    test_dir: &Path,
+    source_db: &Path,
+) -> Result<()> {
+    let mint_key: Ed25519PrivateKey = bcs::from_bytes(&fs::read(test_dir.join("mint.key"))?)?;
+    let db_dir = node_config.storage.dir();
+    copy_dir(source_db, &db_dir).with_context(|| {
+        format!(
+            "Failed to copy the DB from {} to {}",
+            source_db.display(),
+            db_dir.display()
+        )
+    })?;
+
+    let local_validator = local_validator_config(node_config)?;
+    let identity_blob_path = match &node_config
+        .consensus
+        .safety_rules
+        .initial_safety_rules_config
+    {
+        InitialSafetyRulesConfig::FromFile {
+            identity_blob_path, ..
+        } => identity_blob_path.clone(),
+        InitialSafetyRulesConfig::None => bail!("The test config has no validator identity"),
+    };
+    let mut identity = IdentityBlob::from_file(&identity_blob_path)?;
+
+    // The DB is only open while the fork is computed, the node opens it again on start
+    let (genesis, waypoint, validator_address) = {
+        let db = AptosDB::open(
+            node_config.storage.get_dir_paths(),
+            /*readonly=*/ false,
+            node_config.storage.storage_pruner_config,
+            node_config.storage.rocksdb_configs,
+            node_config.storage.enable_indexer,
+            node_config.storage.buffered_state_target_items,
+            node_config.storage.max_num_nodes_per_lru_cache_shard,
+        )
+        .map_err(|err| anyhow!("Failed to open the copied DB: {}", err))?;
+        let db_rw = DbReaderWriter::new(db);
+
+        let consensus_private_key = identity
+            .consensus_private_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("The validator identity has no consensus key"))?;
+        let (change_set, validator_address) = fork_change_set(
+            &db_rw,
+            &local_validator,
+            consensus_private_key,
+            &mint_key.public_key(),
+        )?;
+        let genesis = Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set));
+        let waypoint = generate_waypoint::<AptosVM>(&db_rw, &genesis)?;
+        (genesis, waypoint, validator_address)
+    };
+
+    // The local validator takes over the address of the validator it replaces
+    identity.account_address = Some(validator_address);
+    fs::write(&identity_blob_path, serde_yaml::to_string(&identity)?)?;
+
+    let waypoint_config = WaypointConfig::FromConfig(waypoint);
+    node_config
+        .consensus
+        .safety_rules
+        .initial_safety_rules_config =
+        InitialSafetyRulesConfig::from_file(identity_blob_path, waypoint_config.clone());
+    node_config.base.waypoint = waypoint_config;
+    node_config.execution.genesis = Some(genesis);
+    node_config.execution.genesis_waypoint = None;
+    // The validator builder puts the first node in the 0 directory
+    node_config.save_to_path(test_dir.join("0").join("node.yaml"))?;
+    fs::write(test_dir.join("waypoint.txt"), waypoint.to_string())?;
+
+    println!(
+        "\tForked from DB {:?} at version {}, as validator {}",
+        source_db,
+        waypoint.version() - 1,
+        validator_address
+    );
+    Ok(())
+}
+
+/// Builds the write set of the fork, and returns it with the address of the validator that the
+/// local validator replaces
+fn fork_change_set(
+    db_rw: &DbReaderWriter,
+    local_validator: &ValidatorConfig,
+    consensus_private_key: &bls12381::PrivateKey,
+    mint_public_key: &Ed25519PublicKey,
+) -> Result<(ChangeSet, AccountAddress)> {
+    let state_view = db_rw.reader.latest_state_checkpoint_view()?;
+    let resolver = state_view.as_move_resolver();
+    let validator_set = ValidatorSet::fetch_config(&state_view)
+        .ok_or_else(|| anyhow!("The DB has no validator set"))?;
+    let replaced = validator_set
+        .active_validators
+        .iter()
+        .max_by_key(|validator| validator.consensus_voting_power())
+        .ok_or_else(|| anyhow!("The validator set of the DB is empty"))?;
+    let validator_address = replaced.account_address;
+
+    let move_vm = MoveVmExt::new(
+        NativeGasParameters::zeros(),
+        MiscGasParameters::zeros(),
+        LATEST_GAS_FEATURE_VERSION,
+        ChainId::test().id(),
+        Features::fetch_config(&state_view).unwrap_or_default(),
+        TimedFeaturesBuilder::enable_all().build(),
+        &resolver,
+        false,
+    )?;
+    // Tables created by the fork must not collide with the ones of the original genesis
+    let ledger_info = db_rw.reader.get_latest_ledger_info()?;
+    let session_id = SessionId::genesis(ledger_info.ledger_info().transaction_accumulator_hash());
+    let mut session = ForkSession(move_vm.new_session(&resolver, session_id));
+
+    // The local validator has no randomness keys for the epoch the fork starts, so a DKG session
+    // of the original validators is dropped, and epoch changes don't wait for one anymore
+    if DKGState::fetch_config(&state_view).is_some_and(|state| state.in_progress.is_some()) {
+        session.exec(
+            "dkg",
+            "try_clear_incomplete_session",
+            vec![],
+            vec![MoveValue::Signer(CORE_CODE_ADDRESS)],
+        )?;
+    }
+    session.exec(
+        "features",
+        "change_feature_flags",
+        vec![],
+        vec![
+            MoveValue::Signer(CORE_CODE_ADDRESS),
+            MoveValue::Vector(vec![]),
+            MoveValue::Vector(vec![MoveValue::U64(
+                FeatureFlag::RECONFIGURE_WITH_DKG as u64,
+            )]),
+        ],
+    )?;
+
+    // The local validator takes over the keys and addresses of the replaced validator, which
+    // apply from the next epoch on, the same as if its operator rotated them
+    let operator: AccountAddress = session.view(
+        "stake",
+        "get_operator",
+        vec![],
+        vec![MoveValue::Address(validator_address)],
+    )?;
+    session.exec(
+        "stake",
+        "rotate_consensus_key",
+        vec![],
+        vec![
+            MoveValue::Signer(operator),
+            MoveValue::Address(validator_address),
+            MoveValue::vector_u8(consensus_private_key.public_key().to_bytes().to_vec()),
+            MoveValue::vector_u8(
+                bls12381::ProofOfPossession::create(consensus_private_key)
+                    .to_bytes()
+                    .to_vec(),
+            ),
+        ],
+    )?;
+    session.exec(
+        "stake",
+        "update_network_and_fullnode_addresses",
+        vec![],
+        vec![
+            MoveValue::Signer(operator),
+            MoveValue::Address(validator_address),
+            MoveValue::vector_u8(local_validator.validator_network_addresses.clone()),
+            MoveValue::vector_u8(local_validator.fullnode_network_addresses.clone()),
+        ],
+    )?;
+
+    // All the other validators leave the validator set at the new epoch
+    let others = validator_set
+        .active_validators
+        .iter()
+        .map(|validator| validator.account_address)
+        .filter(|address| *address != validator_address)
+        .map(MoveValue::Address)
+        .collect();
+    session.exec(
+        "stake",
+        "remove_validators",
+        vec![],
+        vec![
+            MoveValue::Signer(CORE_CODE_ADDRESS),
+            MoveValue::Vector(others),
+        ],
+    )?;
+    for pending in &validator_set.pending_active {
+        let operator: AccountAddress = session.view(
+            "stake",
+            "get_operator",
+            vec![],
+            vec![MoveValue::Address(pending.account_address)],
+        )?;
+        session.exec(
+            "stake",
+            "leave_validator_set",
+            vec![],
+            vec![
+                MoveValue::Signer(operator),
+                MoveValue::Address(pending.account_address),
+            ],
+        )?;
+    }
+
+    setup_root_account(&mut session, mint_public_key)?;
+
+    // A reconfiguration in the same microsecond as the last one is skipped, so time moves on by
+    // one microsecond, as if the replaced validator proposed a block
+    let now: u64 = session.view("timestamp", "now_microseconds", vec![], vec![])?;
+    session.exec(
+        "timestamp",
+        "update_global_time",
+        vec![],
+        vec![
+            MoveValue::Signer(reserved_vm_address()),
+            MoveValue::Address(validator_address),
+            MoveValue::U64(now + 1),
+        ],
+    )?;
+    session.exec("reconfiguration", "reconfigure", vec![], vec![])?;
+    // As any genesis, the fork is its own block
+    session.exec(
+        "block",
+        "emit_writeset_block_event",
+        vec![],
+        vec![
+            MoveValue::Signer(reserved_vm_address()),
+            MoveValue::Address(AccountAddress::ZERO),
+        ],
+    )?;
+
+    let configs = ChangeSetConfigs::unlimited_at_gas_feature_version(LATEST_GAS_FEATURE_VERSION);
+    let mut change_set = session.0.finish(&configs)?;
+    change_set.try_materialize_aggregator_v1_delta_set(&resolver)?;
+    let (write_set, events) = change_set
+        .try_into_storage_change_set()
+        .map_err(|err| anyhow!("Failed to build the write set of the fork: {:?}", err))?
+        .into_inner();
+
+    // The framework can't change the chain id after genesis
+    let mut write_set = write_set.into_mut();
+    write_set.insert((
+        StateKey::access_path(access_path_for_config(ChainId::CONFIG_ID)?),
+        WriteOp::legacy_modification(bcs::to_bytes(&ChainId::test())?.into()),
+    ));
+    Ok((
+        ChangeSet::new(write_set.freeze()?, events),
+        validator_address,
+    ))
+}
+
+/// Gives the root account the mint key of the test environment, the capability to mint, and a
+/// balance to pay for the gas of minting
+fn setup_root_account(session: &mut ForkSession, mint_public_key: &Ed25519PublicKey) -> Result<()> {
+    let root = aptos_test_root_address();
+    let exists: bool = session.view(
+        "account",
+        "exists_at",
+        vec![],
+        vec![MoveValue::Address(root)],
+    )?;
+    if !exists {
+        session.exec(
+            "aptos_account",
+            "create_account",
+            vec![],
+            vec![MoveValue::Address(root)],
+        )?;
+    }
+    session.exec(
+        "account",
+        "rotate_authentication_key_internal",
+        vec![],
+        vec![
+            MoveValue::Signer(root),
+            MoveValue::vector_u8(AuthenticationKey::ed25519(mint_public_key).to_vec()),
+        ],
+    )?;
+
+    let has_mint_capability: bool = session.view(
+        "aptos_coin",
+        "has_mint_capability",
+        vec![],
+        vec![MoveValue::Signer(root)],
+    )?;
+    if has_mint_capability {
+        session.exec(
+            "coin",
+            "register",
+            vec![APTOS_COIN_TYPE.clone()],
+            vec![MoveValue::Signer(root)],
+        )?;
+        let balance: u64 = session.view(
+            "coin",
+            "balance",
+            vec![APTOS_COIN_TYPE.clone()],
+            vec![MoveValue::Address(root)],
+        )?;
+        if balance < ROOT_ACCOUNT_BALANCE {
+            session.exec(
+                "aptos_coin",
+                "mint",
+                vec![],
+                vec![
+                    MoveValue::Signer(root),
+                    MoveValue::Address(root),
+                    MoveValue::U64(ROOT_ACCOUNT_BALANCE - balance),
+                ],
+            )?;
+        }
+    } else {
+        // Sets up the root account the same way as the genesis of a test network does, with a
+        // new mint capability, an empty struct holding only the dummy field added by the compiler
+        session.exec(
+            "aptos_coin",
+            "configure_accounts_for_test",
+            vec![],
+            vec![
+                MoveValue::Signer(CORE_CODE_ADDRESS),
+                MoveValue::Signer(root),
+                MoveValue::Struct(MoveStruct::new(vec![MoveValue::Bool(false)])),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// A session calling the framework stored in the DB, without checking visibility or charging gas
+struct ForkSession<'r, 'l>(SessionExt<'r, 'l>);
+
+impl ForkSession<'_, '_> {
+    fn exec(
+        &mut self,
+        module_name: &str,
+        function_name: &str,
+        ty_args: Vec<TypeTag>,
+        args: Vec<MoveValue>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let return_values = self
+            .0
+            .execute_function_bypass_visibility(
+                &ModuleId::new(CORE_CODE_ADDRESS, Identifier::new(module_name)?),
+                &Identifier::new(function_name)?,
+                ty_args,
+                serialize_values(&args),
+                &mut UnmeteredGasMeter,
+            )
+            .map_err(|err| {
+                anyhow!(
+                    "Error calling {}::{}: ({:#x}) {}",
+                    module_name,
+                    function_name,
+                    err.sub_status().unwrap_or_default(),
+                    err
+                )
+            })?
+            .return_values;
+        Ok(return_values.into_iter().map(|(bytes, _)| bytes).collect())
+    }
+
+    fn view<T: DeserializeOwned>(
+        &mut self,
+        module_name: &str,
+        function_name: &str,
+        ty_args: Vec<TypeTag>,
+        args: Vec<MoveValue>,
+    ) -> Result<T> {
+        let return_values = self.exec(module_name, function_name, ty_args, args)?;
+        let bytes = return_values
+            .first()
+            .ok_or_else(|| anyhow!("{}::{} returned no value", module_name, function_name))?;
+        Ok(bcs::from_bytes(bytes)?)
+    }
+}
+
+/// Returns the config of the validator of the freshly created test environment
+fn local_validator_config(node_config: &NodeConfig) -> Result<ValidatorConfig> {
+    let change_set = match &node_config.execution.genesis {
+        Some(Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set))) => change_set,
+        _ => bail!("The test config has no genesis"),
+    };
+    let validator_set_key = StateKey::access_path(access_path_for_config(ValidatorSet::CONFIG_ID)?);
+    let bytes = change_set
+        .write_set()
+        .iter()
+        .find(|(key, _)| **key == validator_set_key)
+        .and_then(|(_, write_op)| write_op.bytes())
+        .ok_or_else(|| anyhow!("The test genesis has no validator set"))?;
+    let validator_set: ValidatorSet = bcs::from_bytes(bytes)?;
+    let validator = validator_set
+        .payload()
+        .next()
+        .ok_or_else(|| anyhow!("The test genesis has no validator"))?;
+    Ok(validator.config().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_cached_packages::aptos_stdlib;
+    use aptos_crypto::{HashValue, Uniform};
+    use aptos_executor::{block_executor::BlockExecutor, db_bootstrapper::maybe_bootstrap};
+    use aptos_executor_test_helpers::{
+        bootstrap_genesis, gen_ledger_info_with_sigs, get_test_signed_transaction,
+    };
+    use aptos_executor_types::BlockExecutorTrait;
+    use aptos_temppath::TempPath;
+    use aptos_types::{
+        account_view::AccountView,
+        state_store::account_with_state_view::AsAccountWithStateView,
+        test_helpers::transaction_test_helpers::{block, TEST_BLOCK_EXECUTOR_ONCHAIN_CONFIG},
+        transaction::{ExecutionStatus, TransactionStatus},
+        trusted_state::TrustedState,
+        validator_signer::ValidatorSigner,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn execute_and_commit(
+        txns: Vec<Transaction>,
+        db: &DbReaderWriter,
+        signers: &[ValidatorSigner],
+    ) {
+        let block_id = HashValue::random();
+        let epoch = db
+            .reader
+            .get_latest_ledger_info()
+            .unwrap()
+            .ledger_info()
+            .next_block_epoch();
+        let executor = BlockExecutor::<AptosVM>::new(db.clone());
+        let output = executor
+            .execute_block(
+                (block_id, block(txns)).into(),
+                executor.committed_block_id(),
+                TEST_BLOCK_EXECUTOR_ONCHAIN_CONFIG,
+            )
+            .unwrap();
+        for status in output.compute_status_for_input_txns() {
+            assert_eq!(status, &TransactionStatus::Keep(ExecutionStatus::Success));
+        }
+        let ledger_info_with_sigs = gen_ledger_info_with_sigs(epoch, &output, block_id, signers);
+        executor
+            .commit_blocks(vec![block_id], ledger_info_with_sigs)
+            .unwrap();
+    }
+
+    fn transaction(
+        sender: AccountAddress,
+        sequence_number: u64,
+        key: &Ed25519PrivateKey,
+        payload: aptos_types::transaction::TransactionPayload,
+    ) -> Transaction {
+        get_test_signed_transaction(
+            sender,
+            sequence_number,
+            key.clone(),
+            key.public_key(),
+            Some(payload),
+        )
+    }
+
+    fn balance(db: &DbReaderWriter, account: &AccountAddress) -> u64 {
+        let state_view = db.reader.latest_state_checkpoint_view().unwrap();
+        state_view
+            .as_account_with_state_view(account)
+            .get_coin_store_resource()
+            .unwrap()
+            .unwrap()
+            .coin()
+    }
+
+    #[test]
+    fn test_fork_produces_blocks() {
+        let mut rng = StdRng::from_seed([7u8; 32]);
+        let (genesis, validators) =
+            aptos_vm_genesis::test_genesis_change_set_and_validators(Some(3));
+        let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+        let tmp_dir = TempPath::new();
+        let db = DbReaderWriter::new(AptosDB::new_for_test(&tmp_dir));
+        bootstrap_genesis::<AptosVM>(&db, &genesis_txn).unwrap();
+
+        // A block of the original network, signed by its validators
+        let signers: Vec<_> = validators
+            .iter()
+            .map(|validator| {
+                ValidatorSigner::new(
+                    validator.data.owner_address,
+                    validator.consensus_key.clone(),
+                )
+            })
+            .collect();
+        let root = aptos_test_root_address();
+        let genesis_key = &aptos_vm_genesis::GENESIS_KEYPAIR.0;
+        let account_key = Ed25519PrivateKey::generate(&mut rng);
+        let account = AuthenticationKey::ed25519(&account_key.public_key()).account_address();
+        execute_and_commit(
+            vec![transaction(
+                root,
+                0,
+                genesis_key,
+                aptos_stdlib::aptos_account_transfer(account, 100_000_000),
+            )],
+            &db,
+            &signers,
+        );
+
+        // Fork the DB for a new validator and mint key
+        let consensus_key = bls12381::PrivateKey::generate(&mut rng);
+        let local_validator = ValidatorConfig::new(
+            consensus_key.public_key(),
+            validators[0].data.network_addresses.clone(),
+            validators[0].data.full_node_network_addresses.clone(),
+            0,
+        );
+        let mint_key = Ed25519PrivateKey::generate(&mut rng);
+        let (change_set, validator_address) = fork_change_set(
+            &db,
+            &local_validator,
+            &consensus_key,
+            &mint_key.public_key(),
+        )
+        .unwrap();
+        let fork_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set));
+        let waypoint = generate_waypoint::<AptosVM>(&db, &fork_txn).unwrap();
+        assert!(maybe_bootstrap::<AptosVM>(&db, &fork_txn, waypoint)
+            .unwrap()
+            .is_some());
+
+        // The fork starts a new epoch, with the local validator as the only validator
+        let trusted_state = TrustedState::from_epoch_waypoint(waypoint);
+        let state_proof = db.reader.get_state_proof(trusted_state.version()).unwrap();
+        assert!(trusted_state
+            .verify_and_ratchet(&state_proof)
+            .unwrap()
+            .is_epoch_change());
+        let state_view = db.reader.latest_state_checkpoint_view().unwrap();
+        let validator_set = ValidatorSet::fetch_config(&state_view).unwrap();
+        assert_eq!(validator_set.active_validators.len(), 1);
+        let validator = &validator_set.active_validators[0];
+        assert_eq!(validator.account_address, validator_address);
+        assert_eq!(
+            validator.consensus_public_key(),
+            &consensus_key.public_key()
+        );
+        assert_eq!(ChainId::fetch_config(&state_view), Some(ChainId::test()));
+        assert!(!Features::fetch_config(&state_view)
+            .unwrap()
+            .is_enabled(FeatureFlag::RECONFIGURE_WITH_DKG));
+        assert!(balance(&db, &root) >= ROOT_ACCOUNT_BALANCE);
+
+        // The local validator alone produces blocks, where the root account mints with the mint
+        // key, and the state from before the fork is kept
+        let signer = ValidatorSigner::new(validator_address, consensus_key);
+        execute_and_commit(
+            vec![transaction(
+                root,
+                1,
+                &mint_key,
+                aptos_stdlib::aptos_coin_mint(account, 200_000_000),
+            )],
+            &db,
+            &[signer.clone()],
+        );
+        assert_eq!(balance(&db, &account), 300_000_000);
+        execute_and_commit(
+            vec![transaction(
+                account,
+                0,
+                &account_key,
+                aptos_stdlib::aptos_coin_transfer(root, 100_000_000),
+            )],
+            &db,
+            &[signer],
+        );
+        assert!(balance(&db, &account) < 200_000_000);
+    }
+}
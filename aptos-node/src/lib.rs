@@ -4,6 +4,7 @@
 
 #![forbid(unsafe_code)]
 
+pub mod fork;
 mod indexer;
 mod logger;
 mod network;
@@ -23,6 +24,7 @@ use aptos_build_info::build_information;
 use aptos_config::config::{
     merge_node_config, InitialSafetyRulesConfig, NodeConfig, PersistableConfig,
 };
+use aptos_consensus::util::block_production::BlockProductionControl;
 use aptos_dkg_runtime::start_dkg_runtime;
 use aptos_framework::ReleaseBundle;
 use aptos_jwk_consensus::start_jwk_consensus_runtime;
@@ -210,6 +212,30 @@ pub fn start(
     log_file: Option<PathBuf>,
     create_global_rayon_pool: bool,
 ) -> anyhow::Result<()> {
+    let (remote_log_receiver, logger_filter_update) =
+        setup_process(&config, log_file, create_global_rayon_pool)?;
+
+    // Set up the node environment and start it
+    let _node_handle = setup_environment_and_start_node(
+        config,
+        remote_log_receiver,
+        Some(logger_filter_update),
+        None,
+    )?;
+    let term = Arc::new(AtomicBool::new(false));
+    while !term.load(Ordering::Acquire) {
+        thread::park();
+    }
+
+    Ok(())
+}
+
+/// Sets up what is global to the process running the node, which may only happen once
+fn setup_process(
+    config: &NodeConfig,
+    log_file: Option<PathBuf>,
+    create_global_rayon_pool: bool,
+) -> anyhow::Result<(Option<mpsc::Receiver<TelemetryLog>>, LoggerFilterUpdater)> {
     // Setup panic handler
     aptos_crash_handler::setup_panic_handler();
 
@@ -220,7 +246,7 @@ pub fn start(
     aptos_node_identity::init(config.get_peer_id())?;
 
     // Instantiate the global logger
-    let (remote_log_receiver, logger_filter_update) = logger::create_logger(&config, log_file);
+    let (remote_log_receiver, logger_filter_update) = logger::create_logger(config, log_file);
 
     assert!(
         !cfg!(feature = "testing") && !cfg!(feature = "fuzzing"),
@@ -246,15 +272,7 @@ pub fn start(
         warn!("Failpoints is set in the node config, but the binary didn't compile with this feature!");
     }
 
-    // Set up the node environment and start it
-    let _node_handle =
-        setup_environment_and_start_node(config, remote_log_receiver, Some(logger_filter_update))?;
-    let term = Arc::new(AtomicBool::new(false));
-    while !term.load(Ordering::Acquire) {
-        thread::park();
-    }
-
-    Ok(())
+    Ok((remote_log_receiver, logger_filter_update))
 }
 
 /// Load a config based on a variety of different ways to provide config options. For
@@ -310,6 +328,46 @@ pub fn start_test_environment_node(
     test_dir: PathBuf,
     enable_lazy_mode: bool,
 ) -> anyhow::Result<()> {
+    let log_file = print_test_environment(&config, &test_dir, enable_lazy_mode);
+    start(config, Some(log_file), false)
+}
+
+/// Print details about a node config configured for a test environment, and run it with its
+/// block production controlled by `block_production` until `stop` receives a message or its
+/// sender is dropped. The runtimes of the node are dropped once it stops, which closes its DB,
+/// so the node can be started again in the same process, e.g. after replacing its data dir.
+pub fn run_test_environment_node_until(
+    config: NodeConfig,
+    test_dir: PathBuf,
+    block_production: BlockProductionControl,
+    stop: std::sync::mpsc::Receiver<()>,
+) -> anyhow::Result<()> {
+    static PROCESS_SET_UP: AtomicBool = AtomicBool::new(false);
+
+    let log_file = print_test_environment(&config, &test_dir, false);
+    let (remote_log_receiver, logger_filter_update) = if PROCESS_SET_UP.swap(true, Ordering::AcqRel)
+    {
+        (None, None)
+    } else {
+        let (remote_log_receiver, logger_filter_update) =
+            setup_process(&config, Some(log_file), false)?;
+        (remote_log_receiver, Some(logger_filter_update))
+    };
+
+    let node_handle = setup_environment_and_start_node(
+        config,
+        remote_log_receiver,
+        logger_filter_update,
+        Some(block_production),
+    )?;
+    // Either a stop was requested, or whoever could request it is gone
+    let _ = stop.recv();
+    drop(node_handle);
+    Ok(())
+}
+
+/// Prints the details of a test environment, and returns the log file of its node.
+fn print_test_environment(config: &NodeConfig, test_dir: &Path, enable_lazy_mode: bool) -> PathBuf {
     let aptos_root_key_path = test_dir.join("mint.key");
 
     // Prepare log file since we cannot automatically route logs to stderr
@@ -342,7 +400,7 @@ pub fn start_test_environment_node(
     }
     println!("\nAptos is running, press ctrl-c to exit\n");
 
-    start(config, Some(log_file), false)
+    log_file
 }
 
 /// Creates a simple test environment and starts the node.
@@ -560,6 +618,7 @@ pub fn setup_environment_and_start_node(
     mut node_config: NodeConfig,
     remote_log_rx: Option<mpsc::Receiver<TelemetryLog>>,
     logger_filter_update_job: Option<LoggerFilterUpdater>,
+    block_production: Option<BlockProductionControl>,
 ) -> anyhow::Result<AptosHandle> {
     // Log the node config at node startup
     node_config.log_all_configs();
@@ -746,6 +805,7 @@ pub fn setup_environment_and_start_node(
             consensus_notifier,
             consensus_to_mempool_sender,
             vtxn_pool,
+            block_production,
         );
         admin_service.set_consensus_dbs(consensus_db, quorum_store_db);
         runtime
@@ -7,7 +7,7 @@ use aptos_build_info::build_information;
 use aptos_config::config::NodeConfig;
 use aptos_consensus::{
     network_interface::ConsensusMsg, persistent_liveness_storage::StorageWriteProxy,
-    quorum_store::quorum_store_db::QuorumStoreDB,
+    quorum_store::quorum_store_db::QuorumStoreDB, util::block_production::BlockProductionControl,
 };
 use aptos_consensus_notifications::ConsensusNotifier;
 use aptos_data_client::client::AptosDataClient;
@@ -115,6 +115,7 @@ pub fn start_consensus_runtime(
     consensus_notifier: ConsensusNotifier,
     consensus_to_mempool_sender: Sender<QuorumStoreRequest>,
     vtxn_pool: VTxnPoolState,
+    block_production: Option<BlockProductionControl>,
 ) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
     let instant = Instant::now();
     let consensus = aptos_consensus::consensus_provider::start_consensus(
@@ -127,6 +128,7 @@ pub fn start_consensus_runtime(
         consensus_reconfig_subscription
             .expect("Consensus requires a reconfiguration subscription!"),
         vtxn_pool,
+        block_production,
     );
     debug!("Consensus started in {} ms", instant.elapsed().as_millis());
     consensus
@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::anyhow;
+use anyhow::{anyhow, ensure};
 use aptos_config::config::{NodeConfig, DEFAULT_CONCURRENCY_LEVEL};
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
 use aptos_types::{
@@ -9,7 +9,7 @@ use aptos_types::{
     state_store::account_with_state_view::AsAccountWithStateView,
 };
 use aptos_vm::AptosVM;
-use std::cmp::min;
+use std::{cmp::min, fs, path::Path};
 
 /// Error message to display when non-production features are enabled
 pub const ERROR_MSG_BAD_FEATURE_FLAGS: &str = r#"
@@ -68,3 +68,20 @@ pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
         AptosVM::set_processed_transactions_detailed_counters();
     }
 }
+
+/// Copies the directory at `source` and everything in it to `destination`, which is created if
+/// it doesn't exist. The node must not be running on either directory.
+pub fn copy_dir(source: &Path, destination: &Path) -> anyhow::Result<()> {
+    ensure!(source.is_dir(), "{} is not a directory", source.display());
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let destination = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), destination)?;
+        }
+    }
+    Ok(())
+}
@@ -67,6 +67,15 @@ pub struct ConsensusConfig {
     pub max_blocks_per_sending_request_quorum_store_override: u64,
     pub max_blocks_per_receiving_request: u64,
     pub max_blocks_per_receiving_request_quorum_store_override: u64,
+    // Start executing blocks as soon as they are inserted into the block tree, on top of the
+    // (possibly not yet ordered) parent, instead of waiting for them to be ordered. The result
+    // is reused once the block is ordered, and dropped if another fork is ordered instead.
+    pub enable_speculative_execution: bool,
+    // Let a local test network pause block production, produce blocks on demand and move the
+    // on-chain time forward, through the `BlockProductionControl` the node is started with.
+    // This is only meant for networks with a single validator, and can't be enabled on mainnet
+    // or testnet.
+    pub enable_block_production_control: bool,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -279,6 +288,8 @@ impl Default for ConsensusConfig {
             max_blocks_per_sending_request_quorum_store_override: 10,
             max_blocks_per_receiving_request: 10,
             max_blocks_per_receiving_request_quorum_store_override: 100,
+            enable_speculative_execution: false,
+            enable_block_production_control: false,
         }
     }
 }
@@ -417,6 +428,17 @@ impl ConfigSanitizer for ConsensusConfig {
                     "consensus-only-perf-test should not be enabled in mainnet!".to_string(),
                 ));
             }
+
+            // Verify that block production control is not enabled in mainnet or testnet
+            if (chain_id.is_mainnet() || chain_id.is_testnet())
+                && node_config.consensus.enable_block_production_control
+            {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "enable_block_production_control should not be enabled in mainnet or testnet!"
+                        .to_string(),
+                ));
+            }
         }
 
         // Sender block limits must be <= receiver block limits
@@ -452,6 +474,35 @@ mod test {
         serde_yaml::from_str::<ConsensusConfig>(&s).unwrap();
     }
 
+    #[test]
+    fn test_block_production_control_on_mainnet() {
+        // Create a node config with block production control enabled
+        let node_config = NodeConfig {
+            consensus: ConsensusConfig {
+                enable_block_production_control: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config for mainnet and verify that it fails
+        let error = ConsensusConfig::sanitize(
+            &node_config,
+            NodeType::ValidatorFullnode,
+            Some(ChainId::mainnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Sanitize the config for a test chain and verify that it succeeds
+        ConsensusConfig::sanitize(
+            &node_config,
+            NodeType::ValidatorFullnode,
+            Some(ChainId::test()),
+        )
+        .unwrap();
+    }
+
     #[test]
     fn test_send_recv_block_txn_limits() {
         // Create a node config with invalid block txn limits
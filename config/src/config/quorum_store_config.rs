@@ -63,6 +63,8 @@ pub struct QuorumStoreConfig {
     pub batch_request_retry_limit: usize,
     pub batch_request_retry_interval_ms: usize,
     pub batch_request_rpc_timeout_ms: usize,
+    /// Maximum number of missing batches fetched concurrently
+    pub batch_request_max_concurrent_fetches: usize,
     /// Used when setting up the expiration time for the batch initation.
     pub batch_expiry_gap_when_init_usecs: u64,
     pub memory_quota: usize,
@@ -99,6 +101,7 @@ impl Default for QuorumStoreConfig {
             batch_request_retry_limit: 10,
             batch_request_retry_interval_ms: 1000,
             batch_request_rpc_timeout_ms: 5000,
+            batch_request_max_concurrent_fetches: 100,
             batch_expiry_gap_when_init_usecs: Duration::from_secs(60).as_micros() as u64,
             memory_quota: 120_000_000,
             db_quota: 300_000_000,
@@ -367,6 +367,49 @@ impl Default for AptosDataMultiFetchConfig {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AptosDataThroughputConfig {
+    /// Whether or not to use measured peer throughput when selecting
+    /// peers and sizing chunks for data client requests.
+    pub enable_throughput_aware_requests: bool,
+    /// The percentage of peer selection slots reserved for peers without
+    /// throughput measurements (so that new and unmeasured peers are tried).
+    /// A value of zero disables exploration entirely.
+    pub exploration_percentage: u64,
+    /// The percentage by which the target chunk sizes must exceed the
+    /// current chunk sizes before the chunk sizes are increased again. This
+    /// avoids oscillating between small and large chunks.
+    pub chunk_size_hysteresis_percentage: u64,
+    /// The minimum percentage of the optimal chunk sizes that adaptive
+    /// chunk sizing can reduce requests to (e.g., 10 means 10%).
+    pub min_chunk_size_percentage: u64,
+    /// The minimum number of measured responses before a peer's
+    /// throughput and response time are used for scheduling.
+    pub min_samples_for_throughput: u64,
+    /// The weight (percentage) given to each new sample when updating
+    /// the moving averages of peer throughput and response time.
+    pub sample_weight_percentage: u64,
+    /// The target time (ms) within which a peer should be able to respond
+    /// to a single data request. Chunk sizes are reduced if the measured
+    /// response times of the peers exceed this target.
+    pub target_response_time_ms: u64,
+}
+
+impl Default for AptosDataThroughputConfig {
+    fn default() -> Self {
+        Self {
+            enable_throughput_aware_requests: false,
+            exploration_percentage: 10, // Reserve 10% of the selection slots for unmeasured peers
+            chunk_size_hysteresis_percentage: 20, // Only grow chunks if the target is 20% larger
+            min_chunk_size_percentage: 10, // Never reduce chunks to less than 10% of the optimal size
+            min_samples_for_throughput: 5, // Only use peers with at least 5 measured responses
+            sample_weight_percentage: 20,  // New samples account for 20% of the moving average
+            target_response_time_ms: 5000, // 5 seconds (half of the default response timeout)
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct AptosLatencyFilteringConfig {
@@ -395,6 +438,8 @@ pub struct AptosDataClientConfig {
     pub data_poller_config: AptosDataPollerConfig,
     /// The aptos data multi-fetch config for the data client
     pub data_multi_fetch_config: AptosDataMultiFetchConfig,
+    /// The aptos data throughput config for the data client
+    pub data_throughput_config: AptosDataThroughputConfig,
     /// The aptos latency filtering config for the data client
     pub latency_filtering_config: AptosLatencyFilteringConfig,
     /// The interval (milliseconds) at which to refresh the latency monitor
@@ -430,6 +475,7 @@ impl Default for AptosDataClientConfig {
         Self {
             data_poller_config: AptosDataPollerConfig::default(),
             data_multi_fetch_config: AptosDataMultiFetchConfig::default(),
+            data_throughput_config: AptosDataThroughputConfig::default(),
             latency_filtering_config: AptosLatencyFilteringConfig::default(),
             latency_monitor_loop_interval_ms: 100,
             max_epoch_chunk_size: MAX_EPOCH_CHUNK_SIZE,
@@ -339,7 +339,11 @@ impl BlockStore {
         self.storage
             .save_tree(vec![pipelined_block.block().clone()], vec![])
             .context("Insert block failed when saving block")?;
-        self.inner.write().insert_block(pipelined_block)
+        let pipelined_block = self.inner.write().insert_block(pipelined_block)?;
+        self.execution_client
+            .speculate_execution(&pipelined_block)
+            .await;
+        Ok(pipelined_block)
     }
 
     /// Validates quorum certificates and inserts it into block tree assuming dependencies exist.
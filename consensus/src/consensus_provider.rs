@@ -13,7 +13,7 @@ use crate::{
     state_computer::ExecutionProxy,
     transaction_filter::TransactionFilter,
     txn_notifier::MempoolNotifier,
-    util::time_service::ClockTimeService,
+    util::{block_production::BlockProductionControl, time_service::ClockTimeService},
 };
 use aptos_bounded_executor::BoundedExecutor;
 use aptos_config::config::NodeConfig;
@@ -40,8 +40,14 @@ pub fn start_consensus(
     aptos_db: DbReaderWriter,
     reconfig_events: ReconfigNotificationListener<DbBackedOnChainConfig>,
     vtxn_pool: VTxnPoolState,
+    block_production: Option<BlockProductionControl>,
 ) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
     let runtime = aptos_runtimes::spawn_named_runtime("consensus".into(), None);
+    // A node started without a controller gets one of its own, which never pauses blocks
+    let block_production = node_config
+        .consensus
+        .enable_block_production_control
+        .then(|| block_production.unwrap_or_default());
     let storage = Arc::new(StorageWriteProxy::new(node_config, aptos_db.reader.clone()));
     let quorum_store_db = Arc::new(QuorumStoreDB::new(node_config.storage.dir()));
 
@@ -56,6 +62,7 @@ pub fn start_consensus(
         state_sync_notifier,
         runtime.handle(),
         TransactionFilter::new(node_config.execution.transaction_filter.clone()),
+        block_production.clone(),
     );
 
     let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
@@ -73,6 +80,7 @@ pub fn start_consensus(
         self_sender.clone(),
         consensus_network_client.clone(),
         bounded_executor.clone(),
+        node_config.consensus.enable_speculative_execution,
     ));
 
     let epoch_mgr = EpochManager::new(
@@ -89,6 +97,7 @@ pub fn start_consensus(
         bounded_executor,
         aptos_time_service::TimeService::real(),
         vtxn_pool,
+        block_production,
     );
 
     let (network_task, network_receiver) = NetworkTask::new(network_service_events, self_receiver);
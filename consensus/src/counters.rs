@@ -657,6 +657,16 @@ pub static PENDING_ROUND_TIMEOUTS: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counters(started,skipped,reused,discarded) related to blocks executed before being ordered
+pub static SPECULATIVE_EXECUTION_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_speculative_execution_count",
+        "Counters(started,skipped,reused,discarded) related to blocks executed before being ordered",
+        &["state"]
+    )
+    .unwrap()
+});
+
 /// Counter of pending network events to Consensus
 pub static PENDING_CONSENSUS_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
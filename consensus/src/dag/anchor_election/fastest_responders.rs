@@ -0,0 +1,143 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::CommitHistory;
+use crate::{
+    dag::{anchor_election::AnchorElection, health::AnchorLatencyHistory, storage::CommitEvent},
+    liveness::{leader_reputation::VotingPowerRatio, proposer_election::choose_index},
+};
+use aptos_consensus_types::common::{Author, Round};
+use aptos_infallible::Mutex;
+
+struct ResponderState {
+    latency_history: AnchorLatencyHistory,
+    // Voting powers of the eligible anchors, zero for ineligible validators
+    eligible_weights: Vec<u128>,
+    participation_ratio: VotingPowerRatio,
+}
+
+/// Elects anchors among the validators that responded the fastest in the
+/// recent history, using the anchor latencies of `AnchorLatencyHistory`. As the
+/// latencies are derived from the committed anchors, all validators compute the
+/// same set of eligible anchors.
+///
+/// Validators without a measured latency are always eligible, so that they are
+/// measured. The other validators are taken from the lowest latency until the
+/// configured percentage of voting power is reached. Anchors are then elected
+/// pseudo-randomly among the eligible validators, with probability proportional
+/// to voting power.
+pub struct FastestRespondersAnchorElection {
+    epoch: u64,
+    validators: Vec<Author>,
+    voting_powers: Vec<u128>,
+    eligible_voting_power_percent: u64,
+    state: Mutex<ResponderState>,
+}
+
+impl FastestRespondersAnchorElection {
+    pub fn new(
+        epoch: u64,
+        validators: Vec<Author>,
+        voting_powers: Vec<u64>,
+        window_size: usize,
+        eligible_voting_power_percent: u64,
+    ) -> Self {
+        assert_eq!(validators.len(), voting_powers.len());
+        let voting_powers: Vec<u128> = voting_powers.into_iter().map(u128::from).collect();
+        Self {
+            epoch,
+            validators: validators.clone(),
+            voting_powers: voting_powers.clone(),
+            eligible_voting_power_percent: eligible_voting_power_percent.clamp(1, 100),
+            state: Mutex::new(ResponderState {
+                latency_history: AnchorLatencyHistory::new(&validators, window_size),
+                eligible_weights: voting_powers,
+                participation_ratio: 1.0,
+            }),
+        }
+    }
+
+    fn compute_eligible_weights(&self, latencies: &[Option<u64>]) -> Vec<u128> {
+        let total_voting_power: u128 = self.voting_powers.iter().sum();
+        let target_voting_power =
+            (total_voting_power * self.eligible_voting_power_percent as u128).div_ceil(100);
+
+        // Validators without a measured latency are always eligible
+        let mut eligible_weights = vec![0; self.validators.len()];
+        let mut eligible_voting_power = 0;
+        for (index, latency) in latencies.iter().enumerate() {
+            if latency.is_none() {
+                eligible_weights[index] = self.voting_powers[index];
+                eligible_voting_power += self.voting_powers[index];
+            }
+        }
+
+        // Sort the other validators by latency, breaking ties by validator index
+        let mut indexes: Vec<_> = latencies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, latency)| latency.map(|latency| (latency, index)))
+            .collect();
+        indexes.sort();
+
+        for (_, index) in indexes {
+            if eligible_voting_power >= target_voting_power {
+                break;
+            }
+            eligible_weights[index] = self.voting_powers[index];
+            eligible_voting_power += self.voting_powers[index];
+        }
+        eligible_weights
+    }
+
+    /// Returns the ratio of the voting power of the validators that were linked by
+    /// at least one of the committed anchors
+    fn compute_participation_ratio(
+        &self,
+        latency_history: &AnchorLatencyHistory,
+    ) -> VotingPowerRatio {
+        let total_voting_power: u128 = self.voting_powers.iter().sum();
+        if total_voting_power == 0 {
+            return 1.0;
+        }
+        let participating_voting_power: u128 = self
+            .validators
+            .iter()
+            .zip(self.voting_powers.iter())
+            .filter(|(author, _)| {
+                latency_history
+                    .events()
+                    .any(|event| event.parents().contains(author))
+            })
+            .map(|(_, voting_power)| *voting_power)
+            .sum();
+        participating_voting_power as f64 / total_voting_power as f64
+    }
+}
+
+impl AnchorElection for FastestRespondersAnchorElection {
+    fn get_anchor(&self, round: Round) -> Author {
+        let eligible_weights = self.state.lock().eligible_weights.clone();
+        let state = [self.epoch.to_le_bytes(), round.to_le_bytes()].concat();
+        self.validators[choose_index(eligible_weights, state)]
+    }
+
+    fn update_reputation(&self, commit_event: CommitEvent) {
+        // Ignore events from other epochs, as the validator set may differ
+        if commit_event.epoch() != self.epoch {
+            return;
+        }
+
+        let mut state = self.state.lock();
+        state.latency_history.push(commit_event);
+        let latencies = state.latency_history.average_latencies();
+        state.eligible_weights = self.compute_eligible_weights(&latencies);
+        state.participation_ratio = self.compute_participation_ratio(&state.latency_history);
+    }
+}
+
+impl CommitHistory for FastestRespondersAnchorElection {
+    fn get_voting_power_participation_ratio(&self, _round: Round) -> VotingPowerRatio {
+        self.state.lock().participation_ratio
+    }
+}
@@ -14,8 +14,12 @@ pub trait CommitHistory: Send + Sync {
     fn get_voting_power_participation_ratio(&self, round: Round) -> VotingPowerRatio;
 }
 
+mod fastest_responders;
 mod leader_reputation_adapter;
 mod round_robin;
+mod stake_weighted;
 
+pub use fastest_responders::FastestRespondersAnchorElection;
 pub use leader_reputation_adapter::{LeaderReputationAdapter, MetadataBackendAdapter};
 pub use round_robin::RoundRobinAnchorElection;
+pub use stake_weighted::StakeWeightedAnchorElection;
@@ -0,0 +1,44 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::CommitHistory;
+use crate::{
+    dag::{anchor_election::AnchorElection, storage::CommitEvent},
+    liveness::{leader_reputation::VotingPowerRatio, proposer_election::choose_index},
+};
+use aptos_consensus_types::common::{Author, Round};
+
+/// Elects anchors pseudo-randomly per round, with probability proportional
+/// to the voting power of each validator. The election only depends on the
+/// epoch and round, so all validators agree on the anchors without any history.
+pub struct StakeWeightedAnchorElection {
+    epoch: u64,
+    validators: Vec<Author>,
+    voting_powers: Vec<u128>,
+}
+
+impl StakeWeightedAnchorElection {
+    pub fn new(epoch: u64, validators: Vec<Author>, voting_powers: Vec<u64>) -> Self {
+        assert_eq!(validators.len(), voting_powers.len());
+        Self {
+            epoch,
+            validators,
+            voting_powers: voting_powers.into_iter().map(u128::from).collect(),
+        }
+    }
+}
+
+impl AnchorElection for StakeWeightedAnchorElection {
+    fn get_anchor(&self, round: Round) -> Author {
+        let state = [self.epoch.to_le_bytes(), round.to_le_bytes()].concat();
+        self.validators[choose_index(self.voting_powers.clone(), state)]
+    }
+
+    fn update_reputation(&self, _event: CommitEvent) {}
+}
+
+impl CommitHistory for StakeWeightedAnchorElection {
+    fn get_voting_power_participation_ratio(&self, _round: Round) -> VotingPowerRatio {
+        1.0
+    }
+}
@@ -2,7 +2,10 @@
 
 use super::{
     adapter::{OrderedNotifierAdapter, TLedgerInfoProvider},
-    anchor_election::{AnchorElection, CommitHistory, RoundRobinAnchorElection},
+    anchor_election::{
+        AnchorElection, CommitHistory, FastestRespondersAnchorElection, RoundRobinAnchorElection,
+        StakeWeightedAnchorElection,
+    },
     dag_driver::DagDriver,
     dag_fetcher::{DagFetcher, DagFetcherService, FetchRequestHandler},
     dag_handler::NetworkHandler,
@@ -317,6 +320,123 @@ impl SyncMode {
     }
 }
 
+fn build_leader_reputation_components(
+    self_peer: Author,
+    epoch_state: &EpochState,
+    config: &ProposerAndVoterConfig,
+) -> Arc<LeaderReputationAdapter> {
+    let num_validators = epoch_state.verifier.len();
+    // TODO: support multiple epochs
+    let metadata_adapter = Arc::new(MetadataBackendAdapter::new(
+        num_validators * 10,
+        HashMap::from([(
+            epoch_state.epoch,
+            epoch_state.verifier.address_to_validator_index().clone(),
+        )]),
+    ));
+    // TODO: use onchain config
+    let heuristic: Box<dyn ReputationHeuristic> = Box::new(ProposerAndVoterHeuristic::new(
+        self_peer,
+        config.active_weight,
+        config.inactive_weight,
+        config.failed_weight,
+        config.failure_threshold_percent,
+        num_validators * config.voter_window_num_validators_multiplier,
+        num_validators * config.proposer_window_num_validators_multiplier,
+        false,
+    ));
+
+    let voting_power = ordered_voting_powers(epoch_state);
+
+    Arc::new(LeaderReputationAdapter::new(
+        epoch_state.epoch,
+        HashMap::from([(
+            epoch_state.epoch,
+            epoch_state.verifier.get_ordered_account_addresses(),
+        )]),
+        voting_power,
+        metadata_adapter,
+        heuristic,
+        100,
+    ))
+}
+
+fn ordered_voting_powers(epoch_state: &EpochState) -> Vec<u64> {
+    epoch_state
+        .verifier
+        .get_ordered_account_addresses_iter()
+        .map(|p| epoch_state.verifier.get_voting_power(&p).unwrap())
+        .collect()
+}
+
+/// Builds the anchor election of the epoch, along with the commit events to replay into it.
+/// The commit events are read from storage, so that a restarted validator elects the same
+/// anchors as the other validators.
+pub(super) fn build_anchor_election(
+    self_peer: Author,
+    epoch_state: &EpochState,
+    anchor_election_mode: &AnchorElectionMode,
+    storage: &dyn DAGStorage,
+) -> (
+    Arc<dyn AnchorElection>,
+    Arc<dyn CommitHistory>,
+    Option<Vec<CommitEvent>>,
+) {
+    match anchor_election_mode {
+        AnchorElectionMode::RoundRobin => {
+            let election = Arc::new(RoundRobinAnchorElection::new(
+                epoch_state.verifier.get_ordered_account_addresses(),
+            ));
+            (election.clone(), election, None)
+        },
+        AnchorElectionMode::StakeWeighted => {
+            let election = Arc::new(StakeWeightedAnchorElection::new(
+                epoch_state.epoch,
+                epoch_state.verifier.get_ordered_account_addresses(),
+                ordered_voting_powers(epoch_state),
+            ));
+            (election.clone(), election, None)
+        },
+        AnchorElectionMode::FastestResponders(config) => {
+            let window_size = config.window_num_validators_multiplier * epoch_state.verifier.len();
+            let commit_events = storage
+                .get_latest_k_committed_events(window_size as u64)
+                .expect("Failed to read commit events from storage");
+            let election = Arc::new(FastestRespondersAnchorElection::new(
+                epoch_state.epoch,
+                epoch_state.verifier.get_ordered_account_addresses(),
+                ordered_voting_powers(epoch_state),
+                window_size,
+                config.eligible_voting_power_percent,
+            ));
+            (election.clone(), election, Some(commit_events))
+        },
+        AnchorElectionMode::LeaderReputation(reputation_type) => {
+            let (commit_events, leader_reputation) = match reputation_type {
+                ProposerAndVoterV2(config) => {
+                    let commit_events = storage
+                        .get_latest_k_committed_events(
+                            config.voter_window_num_validators_multiplier as u64
+                                * epoch_state.verifier.len() as u64,
+                        )
+                        .expect("Failed to read commit events from storage");
+                    (
+                        commit_events,
+                        build_leader_reputation_components(self_peer, epoch_state, config),
+                    )
+                },
+                ProposerAndVoter(_) => unreachable!("unsupported mode"),
+            };
+
+            (
+                leader_reputation.clone(),
+                leader_reputation,
+                Some(commit_events),
+            )
+        },
+    }
+}
+
 pub struct DagBootstrapper {
     self_peer: Author,
     config: DagConsensusConfig,
@@ -382,95 +502,6 @@ impl DagBootstrapper {
         }
     }
 
-    fn build_leader_reputation_components(
-        &self,
-        config: &ProposerAndVoterConfig,
-    ) -> Arc<LeaderReputationAdapter> {
-        let num_validators = self.epoch_state.verifier.len();
-        // TODO: support multiple epochs
-        let metadata_adapter = Arc::new(MetadataBackendAdapter::new(
-            num_validators * 10,
-            HashMap::from([(
-                self.epoch_state.epoch,
-                self.epoch_state
-                    .verifier
-                    .address_to_validator_index()
-                    .clone(),
-            )]),
-        ));
-        // TODO: use onchain config
-        let heuristic: Box<dyn ReputationHeuristic> = Box::new(ProposerAndVoterHeuristic::new(
-            self.self_peer,
-            config.active_weight,
-            config.inactive_weight,
-            config.failed_weight,
-            config.failure_threshold_percent,
-            num_validators * config.voter_window_num_validators_multiplier,
-            num_validators * config.proposer_window_num_validators_multiplier,
-            false,
-        ));
-
-        let voting_power: Vec<u64> = self
-            .epoch_state
-            .verifier
-            .get_ordered_account_addresses_iter()
-            .map(|p| self.epoch_state.verifier.get_voting_power(&p).unwrap())
-            .collect();
-
-        Arc::new(LeaderReputationAdapter::new(
-            self.epoch_state.epoch,
-            HashMap::from([(
-                self.epoch_state.epoch,
-                self.epoch_state.verifier.get_ordered_account_addresses(),
-            )]),
-            voting_power,
-            metadata_adapter,
-            heuristic,
-            100,
-        ))
-    }
-
-    fn build_anchor_election(
-        &self,
-    ) -> (
-        Arc<dyn AnchorElection>,
-        Arc<dyn CommitHistory>,
-        Option<Vec<CommitEvent>>,
-    ) {
-        match &self.onchain_config.anchor_election_mode {
-            AnchorElectionMode::RoundRobin => {
-                let election = Arc::new(RoundRobinAnchorElection::new(
-                    self.epoch_state.verifier.get_ordered_account_addresses(),
-                ));
-                (election.clone(), election, None)
-            },
-            AnchorElectionMode::LeaderReputation(reputation_type) => {
-                let (commit_events, leader_reputation) = match reputation_type {
-                    ProposerAndVoterV2(config) => {
-                        let commit_events = self
-                            .storage
-                            .get_latest_k_committed_events(
-                                config.voter_window_num_validators_multiplier as u64
-                                    * self.epoch_state.verifier.len() as u64,
-                            )
-                            .expect("Failed to read commit events from storage");
-                        (
-                            commit_events,
-                            self.build_leader_reputation_components(config),
-                        )
-                    },
-                    ProposerAndVoter(_) => unreachable!("unsupported mode"),
-                };
-
-                (
-                    leader_reputation.clone(),
-                    leader_reputation,
-                    Some(commit_events),
-                )
-            },
-        }
-    }
-
     fn bootstrap_dag_store(
         &self,
         anchor_election: Arc<dyn AnchorElection>,
@@ -652,7 +683,12 @@ impl DagBootstrapper {
     }
 
     fn full_bootstrap(&self) -> (BootstrapBaseState, NetworkHandler, DagFetcherService) {
-        let (anchor_election, commit_history, commit_events) = self.build_anchor_election();
+        let (anchor_election, commit_history, commit_events) = build_anchor_election(
+            self.self_peer,
+            &self.epoch_state,
+            &self.onchain_config.anchor_election_mode,
+            self.storage.as_ref(),
+        );
 
         let base_state = self.bootstrap_dag_store(
             anchor_election.clone(),
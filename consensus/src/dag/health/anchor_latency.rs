@@ -0,0 +1,79 @@
+// Copyright © Aptos Foundation
+
+use crate::dag::storage::CommitEvent;
+use aptos_collections::BoundedVecDeque;
+use aptos_consensus_types::common::Author;
+use std::collections::HashMap;
+
+/// Keeps the recently committed anchors of an epoch, to measure how fast the DAG
+/// makes progress with each validator as the anchor.
+///
+/// The latency of a committed anchor is the time since the previously committed anchor,
+/// divided by the number of anchor rounds in between. The failed anchors in between are
+/// charged the whole time, as the DAG had to wait for them. The latencies only depend on
+/// the timestamps of the committed anchors, which are also recorded in the ledger, so all
+/// validators compute the same latencies, including after a restart.
+pub struct AnchorLatencyHistory {
+    validator_indexes: HashMap<Author, usize>,
+    sliding_window: BoundedVecDeque<CommitEvent>,
+}
+
+impl AnchorLatencyHistory {
+    pub fn new(validators: &[Author], window_size: usize) -> Self {
+        Self {
+            validator_indexes: validators
+                .iter()
+                .enumerate()
+                .map(|(index, author)| (*author, index))
+                .collect(),
+            sliding_window: BoundedVecDeque::new(window_size.max(1)),
+        }
+    }
+
+    pub fn push(&mut self, event: CommitEvent) {
+        self.sliding_window.push_front(event);
+    }
+
+    /// The committed anchors in the window, from the most recent one
+    pub fn events(&self) -> impl Iterator<Item = &CommitEvent> {
+        self.sliding_window.iter()
+    }
+
+    /// Returns the average anchor latency, in microseconds, of every validator (by index),
+    /// or None for the validators without a committed or failed anchor in the window.
+    pub fn average_latencies(&self) -> Vec<Option<u64>> {
+        let mut total_latencies = vec![0u128; self.validator_indexes.len()];
+        let mut num_latencies = vec![0u128; self.validator_indexes.len()];
+        let mut record = |author: &Author, latency: u64| {
+            if let Some(index) = self.validator_indexes.get(author) {
+                total_latencies[*index] += latency as u128;
+                num_latencies[*index] += 1;
+            }
+        };
+
+        // Blocks are committed with strictly increasing timestamps, so the timestamps
+        // are adjusted in the same way as the ones in the ledger
+        let mut previous_timestamp = None;
+        for event in self.sliding_window.iter().rev() {
+            let timestamp = match previous_timestamp {
+                Some(previous_timestamp) => event.timestamp().max(previous_timestamp + 1),
+                None => event.timestamp(),
+            };
+            if let Some(previous_timestamp) = previous_timestamp {
+                let elapsed = timestamp - previous_timestamp;
+                let num_anchor_rounds = event.failed_authors().len() as u64 + 1;
+                record(event.author(), elapsed / num_anchor_rounds);
+                for failed_author in event.failed_authors() {
+                    record(failed_author, elapsed);
+                }
+            }
+            previous_timestamp = Some(timestamp);
+        }
+
+        total_latencies
+            .into_iter()
+            .zip(num_latencies)
+            .map(|(total, num)| (num > 0).then(|| (total / num) as u64))
+            .collect()
+    }
+}
@@ -1,9 +1,11 @@
 // Copyright © Aptos Foundation
 
+mod anchor_latency;
 mod backoff;
 mod chain_health;
 mod pipeline_health;
 
+pub use anchor_latency::AnchorLatencyHistory;
 pub use backoff::HealthBackoff;
 #[cfg(test)]
 pub use chain_health::NoChainHealth;
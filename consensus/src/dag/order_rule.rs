@@ -190,6 +190,7 @@ impl OrderRule {
                 .iter()
                 .map(|(_, author)| *author)
                 .collect(),
+            anchor.timestamp(),
         );
         self.anchor_election.update_reputation(event);
 
@@ -0,0 +1,143 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::dag::{
+    anchor_election::{
+        AnchorElection, CommitHistory, FastestRespondersAnchorElection, StakeWeightedAnchorElection,
+    },
+    health::AnchorLatencyHistory,
+    storage::CommitEvent,
+    NodeId,
+};
+use aptos_consensus_types::common::{Author, Round};
+use std::collections::HashMap;
+
+fn count_anchors(election: &dyn AnchorElection, rounds: Round) -> HashMap<Author, usize> {
+    let mut counts = HashMap::new();
+    for round in 1..=rounds {
+        *counts.entry(election.get_anchor(round)).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[test]
+fn test_stake_weighted_anchor_election() {
+    let validators: Vec<_> = (0..4).map(|_| Author::random()).collect();
+    let election = StakeWeightedAnchorElection::new(1, validators.clone(), vec![1000, 100, 10, 0]);
+
+    // the election is deterministic
+    let other_election =
+        StakeWeightedAnchorElection::new(1, validators.clone(), vec![1000, 100, 10, 0]);
+    for round in 1..100 {
+        assert_eq!(election.get_anchor(round), other_election.get_anchor(round));
+    }
+
+    // anchors are elected proportionally to the voting power
+    let counts = count_anchors(&election, 10_000);
+    let count = |index: usize| *counts.get(&validators[index]).unwrap_or(&0);
+    assert!(count(0) > count(1));
+    assert!(count(1) > count(2));
+    assert_eq!(count(3), 0);
+    assert_eq!(election.get_voting_power_participation_ratio(1), 1.0);
+}
+
+#[test]
+fn test_anchor_latency_history() {
+    let epoch = 1;
+    let validators: Vec<_> = (0..4).map(|_| Author::random()).collect();
+    let mut history = AnchorLatencyHistory::new(&validators, 4);
+    let commit_event = |round: Round, author: usize, failed: &[usize], timestamp: u64| {
+        CommitEvent::new(
+            NodeId::new(epoch, round, validators[author]),
+            vec![],
+            failed.iter().map(|index| validators[*index]).collect(),
+            timestamp,
+        )
+    };
+
+    // the first anchor has no previous anchor to measure from
+    history.push(commit_event(2, 0, &[], 1_000));
+    assert_eq!(history.average_latencies(), vec![None; 4]);
+
+    // failed anchors are charged the whole time, committed ones the time per anchor round
+    history.push(commit_event(6, 1, &[2], 3_000));
+    assert_eq!(
+        history.average_latencies(),
+        vec![None, Some(1_000), Some(2_000), None]
+    );
+
+    // timestamps are adjusted to be strictly increasing, as in the ledger
+    history.push(commit_event(8, 3, &[], 2_000));
+    history.push(commit_event(10, 1, &[], 3_101));
+    assert_eq!(
+        history.average_latencies(),
+        vec![None, Some(550), Some(2_000), Some(1)]
+    );
+
+    // the window only keeps the most recent events
+    history.push(commit_event(12, 0, &[], 3_201));
+    assert_eq!(
+        history.average_latencies(),
+        vec![Some(100), Some(100), None, Some(1)]
+    );
+}
+
+#[test]
+fn test_fastest_responders_anchor_election() {
+    let epoch = 1;
+    let validators: Vec<_> = (0..4).map(|_| Author::random()).collect();
+    let election =
+        FastestRespondersAnchorElection::new(epoch, validators.clone(), vec![1; 4], 8, 50);
+
+    // without history, all validators are eligible
+    let counts = count_anchors(&election, 1_000);
+    assert_eq!(counts.len(), 4);
+
+    let commit_event = |round: Round, author: usize, parents: &[usize], timestamp: u64| {
+        CommitEvent::new(
+            NodeId::new(epoch, round, validators[author]),
+            parents.iter().map(|index| validators[*index]).collect(),
+            vec![],
+            timestamp,
+        )
+    };
+
+    // validators without a measured latency stay eligible, only validator 1 is measured
+    election.update_reputation(commit_event(2, 0, &[0, 1, 2, 3], 0));
+    election.update_reputation(commit_event(4, 1, &[0, 1, 2, 3], 200));
+    let counts = count_anchors(&election, 1_000);
+    assert_eq!(counts.len(), 3);
+    assert!(!counts.contains_key(&validators[1]));
+
+    // validators 0 and 1 have the lowest latencies
+    election.update_reputation(commit_event(6, 2, &[0, 1, 2, 3], 1_200));
+    election.update_reputation(commit_event(8, 3, &[0, 1, 2, 3], 3_200));
+    election.update_reputation(commit_event(10, 0, &[0, 1, 2, 3], 3_300));
+    let counts = count_anchors(&election, 1_000);
+    assert_eq!(counts.len(), 2);
+    assert!(counts.contains_key(&validators[0]));
+    assert!(counts.contains_key(&validators[1]));
+    assert_eq!(election.get_voting_power_participation_ratio(10), 1.0);
+
+    // events from other epochs are ignored
+    election.update_reputation(CommitEvent::new(
+        NodeId::new(epoch + 1, 12, validators[2]),
+        vec![],
+        vec![],
+        3_301,
+    ));
+    assert_eq!(count_anchors(&election, 1_000), counts);
+
+    // the window only keeps the most recent events, where validators 2 and 3 are faster
+    let mut timestamp = 3_300;
+    for (index, author) in [2, 3, 0, 1].into_iter().cycle().take(8).enumerate() {
+        timestamp += if author < 2 { 1_000 } else { 10 };
+        let round = 12 + 2 * index as Round;
+        election.update_reputation(commit_event(round, author, &[2, 3], timestamp));
+    }
+    let counts = count_anchors(&election, 1_000);
+    assert_eq!(counts.len(), 2);
+    assert!(counts.contains_key(&validators[2]));
+    assert!(counts.contains_key(&validators[3]));
+    assert_eq!(election.get_voting_power_participation_ratio(26), 0.5);
+}
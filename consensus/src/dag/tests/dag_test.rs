@@ -22,6 +22,7 @@ pub struct MockStorage {
     vote_data: Mutex<HashMap<NodeId, Vote>>,
     certified_node_data: Mutex<HashMap<HashValue, CertifiedNode>>,
     latest_ledger_info: Option<LedgerInfoWithSignatures>,
+    commit_events: Mutex<Vec<CommitEvent>>,
 }
 
 impl MockStorage {
@@ -31,6 +32,7 @@ impl MockStorage {
             vote_data: Mutex::new(HashMap::new()),
             certified_node_data: Mutex::new(HashMap::new()),
             latest_ledger_info: None,
+            commit_events: Mutex::new(vec![]),
         }
     }
 
@@ -40,8 +42,14 @@ impl MockStorage {
             vote_data: Mutex::new(HashMap::new()),
             certified_node_data: Mutex::new(HashMap::new()),
             latest_ledger_info: Some(ledger_info),
+            commit_events: Mutex::new(vec![]),
         }
     }
+
+    /// Sets the committed events, from the oldest one
+    pub fn set_commit_events(&self, commit_events: Vec<CommitEvent>) {
+        *self.commit_events.lock() = commit_events;
+    }
 }
 
 impl DAGStorage for MockStorage {
@@ -98,8 +106,10 @@ impl DAGStorage for MockStorage {
         Ok(())
     }
 
-    fn get_latest_k_committed_events(&self, _k: u64) -> anyhow::Result<Vec<CommitEvent>> {
-        Ok(vec![])
+    fn get_latest_k_committed_events(&self, k: u64) -> anyhow::Result<Vec<CommitEvent>> {
+        let commit_events = self.commit_events.lock();
+        let start = commit_events.len().saturating_sub(k as usize);
+        Ok(commit_events[start..].to_vec())
     }
 
     fn get_latest_ledger_info(&self) -> anyhow::Result<LedgerInfoWithSignatures> {
@@ -30,12 +30,22 @@ pub(crate) fn new_certified_node(
     round: Round,
     author: Author,
     parents: Vec<NodeCertificate>,
+) -> CertifiedNode {
+    new_certified_node_with_timestamp(1, round, 0, author, parents)
+}
+
+pub(crate) fn new_certified_node_with_timestamp(
+    epoch: u64,
+    round: Round,
+    timestamp: u64,
+    author: Author,
+    parents: Vec<NodeCertificate>,
 ) -> CertifiedNode {
     let node = Node::new(
-        1,
+        epoch,
         round,
         author,
-        0,
+        timestamp,
         vec![],
         Payload::empty(false),
         parents,
@@ -66,6 +76,17 @@ pub(crate) fn new_node(
 pub(crate) fn generate_dag_nodes(
     dag: &[Vec<Option<Vec<bool>>>],
     validators: &[Author],
+) -> Vec<Vec<Option<CertifiedNode>>> {
+    generate_dag_nodes_with_timestamps(dag, validators, 1, |_, _| 0)
+}
+
+/// Generate certified nodes of the given epoch for dag given the virtual dag, with the
+/// timestamps given by round and validator index
+pub(crate) fn generate_dag_nodes_with_timestamps(
+    dag: &[Vec<Option<Vec<bool>>>],
+    validators: &[Author],
+    epoch: u64,
+    timestamp: impl Fn(Round, usize) -> u64,
 ) -> Vec<Vec<Option<CertifiedNode>>> {
     let mut nodes = vec![];
     let mut previous_round: Vec<Option<CertifiedNode>> = vec![];
@@ -87,8 +108,11 @@ pub(crate) fn generate_dag_nodes(
                 if round > 1 {
                     assert_eq!(parents.len(), validators.len() * 2 / 3 + 1);
                 }
-                nodes_at_round.push(Some(new_certified_node(
-                    (round + 1) as u64,
+                let round = (round + 1) as Round;
+                nodes_at_round.push(Some(new_certified_node_with_timestamp(
+                    epoch,
+                    round,
+                    timestamp(round, idx),
                     *author,
                     parents,
                 )));
@@ -5,7 +5,7 @@ use crate::{
     dag::{bootstrap::bootstrap_dag_for_test, dag_state_sync::SyncOutcome},
     network::{IncomingDAGRequest, NetworkSender, RpcResponder},
     network_interface::{ConsensusMsg, ConsensusNetworkClient, DIRECT_SEND, RPC},
-    network_tests::{NetworkPlayground, TwinId},
+    network_tests::{LatencyDistribution, LinkFaults, NetworkPlayground, TwinId},
     payload_manager::PayloadManager,
     pipeline::{buffer_manager::OrderedBlocks, execution_client::DummyExecutionClient},
     test_utils::{consensus_runtime, MockPayloadManager, MockStorage},
@@ -38,7 +38,7 @@ use futures::{
 };
 use futures_channel::mpsc::UnboundedReceiver;
 use maplit::hashmap;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio::task::JoinHandle;
 
 struct DagBootstrapUnit {
@@ -226,9 +226,78 @@ async fn test_dag_e2e() {
         .collect();
     runtime.spawn(playground.start());
 
-    for _ in 1..10 {
+    verify_ordered_blocks(&mut ordered_node_receivers, 9).await;
+    for task in tasks {
+        task.abort();
+        let _ = task.await;
+    }
+    runtime.shutdown_background();
+}
+
+#[tokio::test]
+async fn test_dag_e2e_with_link_faults() {
+    let num_nodes = 7;
+    let runtime = consensus_runtime();
+    let time_service = TimeService::mock();
+    let mut playground =
+        NetworkPlayground::new_with_time_service(runtime.handle().clone(), time_service);
+    let (signers, validators) = random_validator_verifier(num_nodes, None, false);
+    let twin_ids: Vec<_> = signers
+        .iter()
+        .enumerate()
+        .map(|(id, signer)| TwinId {
+            id,
+            author: signer.author(),
+        })
+        .collect();
+
+    // Messages sent by the first two nodes are delayed, while messages sent
+    // to them are lossy
+    playground.set_fault_injection_seed(42);
+    for slow_node in &twin_ids[..2] {
+        for node in &twin_ids {
+            if node == slow_node {
+                continue;
+            }
+            playground.inject_link_faults(*slow_node, *node, LinkFaults {
+                latency: LatencyDistribution::Uniform {
+                    min: Duration::from_millis(10),
+                    max: Duration::from_millis(100),
+                },
+                drop_percentage: 0,
+            });
+            playground.inject_link_faults(*node, *slow_node, LinkFaults {
+                latency: LatencyDistribution::None,
+                drop_percentage: 10,
+            });
+        }
+    }
+
+    let (nodes, mut ordered_node_receivers) = bootstrap_nodes(&mut playground, signers, validators);
+    let tasks: Vec<_> = nodes
+        .into_iter()
+        .map(|node| runtime.spawn(node.start()))
+        .collect();
+    let clock_task = runtime.spawn(playground.deliver_delayed_messages());
+    runtime.spawn(playground.start());
+
+    verify_ordered_blocks(&mut ordered_node_receivers, 5).await;
+    clock_task.abort();
+    for task in tasks {
+        task.abort();
+        let _ = task.await;
+    }
+    runtime.shutdown_background();
+}
+
+/// Verifies that all nodes order the same blocks for the given number of batches
+async fn verify_ordered_blocks(
+    ordered_node_receivers: &mut [UnboundedReceiver<OrderedBlocks>],
+    num_batches: usize,
+) {
+    for _ in 0..num_batches {
         let mut all_ordered = vec![];
-        for receiver in &mut ordered_node_receivers {
+        for receiver in ordered_node_receivers.iter_mut() {
             let block = receiver.next().await.unwrap();
             all_ordered.push(block.ordered_blocks)
         }
@@ -239,9 +308,4 @@ async fn test_dag_e2e() {
             assert_eq!(a, first);
         }
     }
-    for task in tasks {
-        task.abort();
-        let _ = task.await;
-    }
-    runtime.shutdown_background();
 }
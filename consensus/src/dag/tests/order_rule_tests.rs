@@ -3,19 +3,28 @@
 
 use crate::dag::{
     adapter::OrderedNotifier,
-    anchor_election::RoundRobinAnchorElection,
+    anchor_election::{AnchorElection, RoundRobinAnchorElection},
+    bootstrap::build_anchor_election,
     dag_store::{DagStore, InMemDag},
     order_rule::OrderRule,
+    storage::CommitEvent,
     tests::{
         dag_test::MockStorage,
-        helpers::{generate_dag_nodes, MockPayloadManager, TEST_DAG_WINDOW},
+        helpers::{
+            generate_dag_nodes, generate_dag_nodes_with_timestamps, MockPayloadManager,
+            TEST_DAG_WINDOW,
+        },
     },
     types::NodeMetadata,
     CertifiedNode,
 };
 use aptos_consensus_types::common::{Author, Round};
 use aptos_infallible::Mutex;
-use aptos_types::{epoch_state::EpochState, validator_verifier::random_validator_verifier};
+use aptos_types::{
+    epoch_state::EpochState,
+    on_chain_config::{AnchorElectionMode, FastestRespondersConfig},
+    validator_verifier::{random_validator_verifier, ValidatorVerifier},
+};
 use async_trait::async_trait;
 use futures_channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use proptest::prelude::*;
@@ -178,6 +187,176 @@ proptest! {
     }
 }
 
+/// Records the commit events passed to the anchor election it wraps
+struct RecordingAnchorElection {
+    anchor_election: Arc<dyn AnchorElection>,
+    commit_events: Mutex<Vec<CommitEvent>>,
+}
+
+impl AnchorElection for RecordingAnchorElection {
+    fn get_anchor(&self, round: Round) -> Author {
+        self.anchor_election.get_anchor(round)
+    }
+
+    fn update_reputation(&self, commit_event: CommitEvent) {
+        self.commit_events.lock().push(commit_event.clone());
+        self.anchor_election.update_reputation(commit_event);
+    }
+}
+
+/// Builds the dag of the epoch, where the timestamps of the nodes depend on their authors
+fn create_dag_with_timestamps(
+    virtual_dag: &[Vec<Option<Vec<bool>>>],
+    epoch_state: Arc<EpochState>,
+) -> InMemDag {
+    let validators = epoch_state.verifier.get_ordered_account_addresses();
+    let nodes = generate_dag_nodes_with_timestamps(
+        virtual_dag,
+        &validators,
+        epoch_state.epoch,
+        |round, index| round * 1_000_000 + index as u64 * 100_000,
+    );
+    let mut dag = InMemDag::new_empty(epoch_state, 0, TEST_DAG_WINDOW);
+    for node in nodes.into_iter().flatten().flatten() {
+        dag.add_node_for_test(node).unwrap();
+    }
+    dag
+}
+
+/// Starts the order rule of the epoch the way the bootstrapper does, with the given commit
+/// events in storage, and orders the whole dag. Returns the ordered batches, and the commit
+/// events of the epoch.
+fn order_from_storage(
+    epoch_state: Arc<EpochState>,
+    dag: &InMemDag,
+    anchor_election_mode: &AnchorElectionMode,
+    committed_events: Vec<CommitEvent>,
+) -> (Vec<Vec<(Round, Author)>>, Vec<CommitEvent>) {
+    let lowest_unordered_anchor_round = committed_events
+        .last()
+        .filter(|event| event.epoch() == epoch_state.epoch)
+        .map_or(1, |event| event.round() + 1);
+    let storage = MockStorage::new();
+    storage.set_commit_events(committed_events);
+    let (anchor_election, _, commit_events) = build_anchor_election(
+        epoch_state.verifier.get_ordered_account_addresses()[0],
+        &epoch_state,
+        anchor_election_mode,
+        &storage,
+    );
+    let anchor_election = Arc::new(RecordingAnchorElection {
+        anchor_election,
+        commit_events: Mutex::new(vec![]),
+    });
+    let dag = Arc::new(DagStore::new_for_test(
+        dag.clone(),
+        Arc::new(MockStorage::new()),
+        Arc::new(MockPayloadManager {}),
+    ));
+    let (tx, mut receiver) = unbounded();
+    let mut order_rule = OrderRule::new(
+        epoch_state.clone(),
+        lowest_unordered_anchor_round,
+        dag,
+        anchor_election.clone(),
+        Arc::new(TestNotifier { tx }),
+        TEST_DAG_WINDOW as Round,
+        commit_events,
+    );
+    order_rule.process_all();
+
+    let mut ordered = vec![];
+    while let Ok(Some(ordered_nodes)) = receiver.try_next() {
+        ordered.push(
+            ordered_nodes
+                .iter()
+                .map(|node| (node.metadata().round(), *node.metadata().author()))
+                .collect(),
+        );
+    }
+    let commit_events = anchor_election
+        .commit_events
+        .lock()
+        .iter()
+        .filter(|event| event.epoch() == epoch_state.epoch)
+        .cloned()
+        .collect();
+    (ordered, commit_events)
+}
+
+fn create_epoch_state(epoch: u64, verifier: &ValidatorVerifier) -> Arc<EpochState> {
+    Arc::new(EpochState {
+        epoch,
+        verifier: verifier.clone(),
+    })
+}
+
+const NUM_SWITCH_ROUNDS: u64 = 30;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    /// Validators switch to the fastest responders anchor election at the epoch boundary, and
+    /// then restart at any point of the new epoch. The anchor election is rebuilt from the
+    /// commit events in storage, which include the events of the previous epoch, and must
+    /// order the rest of the dag exactly like the validators that did not restart.
+    #[test]
+    fn test_order_rule_restart_with_anchor_election_switch(
+        mut dag_with_holes in generate_virtual_dag(NUM_VALIDATORS, NUM_HOLES, NUM_SWITCH_ROUNDS),
+        mut virtual_dag in generate_virtual_dag(NUM_VALIDATORS, 0, NUM_SWITCH_ROUNDS),
+    ) {
+        let (_, validator_verifier) = random_validator_verifier(NUM_VALIDATORS, None, false);
+        virtual_dag.append(&mut dag_with_holes);
+        let fastest_responders = AnchorElectionMode::FastestResponders(FastestRespondersConfig {
+            window_num_validators_multiplier: 2,
+            eligible_voting_power_percent: 50,
+        });
+
+        for previous_mode in [
+            AnchorElectionMode::RoundRobin,
+            AnchorElectionMode::StakeWeighted,
+            fastest_responders.clone(),
+        ] {
+            let previous_epoch_state = create_epoch_state(1, &validator_verifier);
+            let previous_dag = create_dag_with_timestamps(&virtual_dag, previous_epoch_state.clone());
+            let (_, previous_events) =
+                order_from_storage(previous_epoch_state, &previous_dag, &previous_mode, vec![]);
+
+            let epoch_state = create_epoch_state(2, &validator_verifier);
+            let dag = create_dag_with_timestamps(&virtual_dag, epoch_state.clone());
+            let (expected_order, events) = order_from_storage(
+                epoch_state.clone(),
+                &dag,
+                &fastest_responders,
+                previous_events.clone(),
+            );
+            prop_assert_eq!(expected_order.len(), events.len());
+
+            // the commit history changes the elected anchors, compared to an election without it
+            let (no_history_election, _, _) = build_anchor_election(
+                validator_verifier.get_ordered_account_addresses()[0],
+                &epoch_state,
+                &fastest_responders,
+                &MockStorage::new(),
+            );
+            prop_assert!(events
+                .iter()
+                .any(|event| no_history_election.get_anchor(event.round()) != *event.author()));
+
+            for num_restored in 0..=events.len() {
+                let committed_events = previous_events
+                    .iter()
+                    .chain(&events[..num_restored])
+                    .cloned()
+                    .collect();
+                let (ordered, _) =
+                    order_from_storage(epoch_state.clone(), &dag, &fastest_responders, committed_events);
+                prop_assert_eq!(&ordered, &expected_order[num_restored..].to_vec());
+            }
+        }
+    }
+}
+
 #[test]
 fn test_order_rule_basic() {
     let dag = vec![
@@ -45,7 +45,7 @@ use crate::{
     },
     recovery_manager::RecoveryManager,
     round_manager::{RoundManager, UnverifiedEvent, VerifiedEvent},
-    util::time_service::TimeService,
+    util::{block_production::BlockProductionControl, time_service::TimeService},
 };
 use anyhow::{bail, ensure, Context};
 use aptos_bounded_executor::BoundedExecutor;
@@ -151,6 +151,8 @@ pub struct EpochManager<P: OnChainConfigProvider> {
     dag_shutdown_tx: Option<oneshot::Sender<oneshot::Sender<()>>>,
     dag_config: DagConsensusConfig,
     payload_manager: Arc<PayloadManager>,
+    // Only set when block production control is enabled
+    block_production: Option<BlockProductionControl>,
 }
 
 impl<P: OnChainConfigProvider> EpochManager<P> {
@@ -168,6 +170,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         bounded_executor: BoundedExecutor,
         aptos_time_service: aptos_time_service::TimeService,
         vtxn_pool: VTxnPoolState,
+        block_production: Option<BlockProductionControl>,
     ) -> Self {
         let author = node_config.validator_network.as_ref().unwrap().peer_id();
         let config = node_config.consensus.clone();
@@ -207,6 +210,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             aptos_time_service,
             dag_config,
             payload_manager: Arc::new(PayloadManager::DirectMempool),
+            block_production,
         }
     }
 
@@ -815,6 +819,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             buffered_proposal_tx,
             self.config.clone(),
             features,
+            self.block_production.clone(),
         );
 
         round_manager.init(last_vote).await;
@@ -108,6 +108,11 @@ impl ExecutionPipeline {
         } = command;
 
         debug!("prepare_block received block {}.", block.id());
+        // Nobody awaits the result anymore, e.g. a discarded speculative execution.
+        if result_tx.is_closed() {
+            debug!("prepare_block skipped block {}.", block.id());
+            return;
+        }
         let input_txns = block_preparer.prepare_block(&block).await;
         if let Err(e) = input_txns {
             result_tx.send(Err(e)).unwrap_or_else(|err| {
@@ -176,6 +181,10 @@ impl ExecutionPipeline {
         {
             let block_id = block.block_id;
             debug!("execute_stage received block {}.", block_id);
+            if result_tx.is_closed() {
+                debug!("execute_stage skipped block {}.", block_id);
+                continue;
+            }
             let executor = executor.clone();
             let state_checkpoint_output = monitor!(
                 "execute_block",
@@ -221,6 +230,10 @@ impl ExecutionPipeline {
         }) = block_rx.recv().await
         {
             debug!("ledger_apply stage received block {}.", block_id);
+            if result_tx.is_closed() {
+                debug!("ledger_apply stage skipped block {}.", block_id);
+                continue;
+            }
             let res = async {
                 let executor = executor.clone();
                 monitor!(
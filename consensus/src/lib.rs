@@ -69,6 +69,8 @@ pub use consensusdb::CONSENSUS_DB_NAME;
 pub use quorum_store::quorum_store_db::QUORUM_STORE_DB_NAME;
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
+/// Required by the executor benchmark
+pub use transaction_shuffler::{ConflictAwareShuffler, TransactionShuffler};
 
 struct IntGaugeGuard {
     gauge: IntGauge,
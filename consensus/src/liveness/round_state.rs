@@ -330,6 +330,13 @@ impl RoundState {
         self.setup_timeout(2);
     }
 
+    /// Cancels the timeout of the current round, until it is set up again.
+    pub fn cancel_timeout(&mut self) {
+        if let Some(handle) = self.abort_handle.take() {
+            handle.abort();
+        }
+    }
+
     /// Setup the timeout task and return the duration of the current timeout
     fn setup_timeout(&mut self, multiplier: u32) -> Duration {
         let timeout_sender = self.timeout_sender.clone();
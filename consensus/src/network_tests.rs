@@ -32,15 +32,17 @@ use aptos_network::{
     },
     ProtocolId,
 };
+use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{block_info::BlockInfo, PeerId};
-use futures::{channel::mpsc, SinkExt, StreamExt};
+use futures::{channel::mpsc, Future, SinkExt, StreamExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
     collections::{HashMap, HashSet},
     iter::FromIterator,
     sync::Arc,
     time::Duration,
 };
-use tokio::runtime::Handle;
+use tokio::{runtime::Handle, sync::Notify};
 
 /// `TwinId` is used by the NetworkPlayground to uniquely identify
 /// nodes, even if they have the same `AccountAddress` (e.g. for Twins)
@@ -60,6 +62,11 @@ pub struct TwinId {
 /// Currently, RPC messages are delivered immediately and are not controlled by
 /// `wait_for_messages` or `deliver_messages` for delivery. They are also not
 /// currently dropped according to the `NetworkPlayground`'s drop config.
+///
+/// Test code can also inject seeded faults (latency and message loss) per link,
+/// see `inject_link_faults`. These faults apply to all the messages, rpc or
+/// direct-send. Delayed messages wait on the playground's time service, which
+/// tests can mock to control the delivery.
 pub struct NetworkPlayground {
     /// Maps each Author to a Sender of their inbound network notifications.
     /// These events will usually be handled by the event loop spawned in
@@ -79,6 +86,10 @@ pub struct NetworkPlayground {
     drop_config: Arc<RwLock<DropConfig>>,
     /// Allow test code to drop direct-send messages between peers per round.
     drop_config_round: DropConfigRound,
+    /// Allow test code to inject latency and message loss per link.
+    fault_injector: Arc<Mutex<FaultInjector>>,
+    /// Delivers the messages of faulty links once their latency has passed
+    delayed_delivery: DelayedDelivery,
     /// An executor for spawning node outbound network event handlers
     executor: Handle,
     /// Maps authors to twins IDs
@@ -90,6 +101,10 @@ pub struct NetworkPlayground {
 
 impl NetworkPlayground {
     pub fn new(executor: Handle) -> Self {
+        Self::new_with_time_service(executor, TimeService::real())
+    }
+
+    pub fn new_with_time_service(executor: Handle, time_service: TimeService) -> Self {
         let (outbound_msgs_tx, outbound_msgs_rx) = mpsc::channel(1_024);
 
         NetworkPlayground {
@@ -98,6 +113,12 @@ impl NetworkPlayground {
             outbound_msgs_rx,
             drop_config: Arc::new(RwLock::new(DropConfig::default())),
             drop_config_round: DropConfigRound::default(),
+            fault_injector: Arc::new(Mutex::new(FaultInjector::default())),
+            delayed_delivery: DelayedDelivery {
+                time_service,
+                executor: executor.clone(),
+                delayed_messages: Arc::new(Notify::new()),
+            },
             executor,
             author_to_twin_ids: Arc::new(RwLock::new(AuthorToTwinIds::default())),
             peers_and_metadata: PeersAndMetadata::new(&[NetworkId::Validator]),
@@ -123,6 +144,8 @@ impl NetworkPlayground {
     /// they don't block.
     async fn start_node_outbound_handler(
         drop_config: Arc<RwLock<DropConfig>>,
+        fault_injector: Arc<Mutex<FaultInjector>>,
+        delayed_delivery: DelayedDelivery,
         src_twin_id: TwinId,
         mut network_reqs_rx: aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
         mut outbound_msgs_tx: mpsc::Sender<(TwinId, PeerManagerRequest)>,
@@ -160,6 +183,11 @@ impl NetworkPlayground {
                         None => continue, // drop rpc
                     };
 
+                    let delay = match fault_injector.lock().sample(&src_twin_id, dst_twin_id) {
+                        FaultDecision::Drop => continue, // drop rpc
+                        FaultDecision::Deliver(delay) => delay,
+                    };
+
                     let node_consensus_tx =
                         node_consensus_txs.lock().get(dst_twin_id).unwrap().clone();
 
@@ -168,13 +196,15 @@ impl NetworkPlayground {
                         data: outbound_req.data,
                         res_tx: outbound_req.res_tx,
                     };
+                    let key = (src_twin_id.author, ProtocolId::ConsensusRpcBcs);
+                    let msg_notif =
+                        PeerManagerNotification::RecvRpc(src_twin_id.author, inbound_req);
 
-                    node_consensus_tx
-                        .push(
-                            (src_twin_id.author, ProtocolId::ConsensusRpcBcs),
-                            PeerManagerNotification::RecvRpc(src_twin_id.author, inbound_req),
-                        )
-                        .unwrap();
+                    if delay.is_zero() {
+                        node_consensus_tx.push(key, msg_notif).unwrap();
+                    } else {
+                        delayed_delivery.push_after(delay, node_consensus_tx, key, msg_notif);
+                    }
                 },
                 // Other PeerManagerRequest get buffered for `deliver_messages` to
                 // synchronously drain.
@@ -200,6 +230,8 @@ impl NetworkPlayground {
 
         let fut1 = NetworkPlayground::start_node_outbound_handler(
             Arc::clone(&self.drop_config),
+            Arc::clone(&self.fault_injector),
+            self.delayed_delivery.clone(),
             twin_id,
             network_reqs_rx,
             self.outbound_msgs_tx.clone(),
@@ -210,15 +242,24 @@ impl NetworkPlayground {
         self.executor.spawn(futures::future::join(fut1, fut2));
     }
 
-    /// Deliver a `PeerManagerRequest` from peer `src` to the destination peer.
-    /// Returns a copy of the delivered message and the sending peer id, and
-    /// whether the message was successfully delivered
+    /// Deliver a `PeerManagerRequest` from peer `src` to the destination peer,
+    /// with the faults injected on the link. Returns a copy of the delivered
+    /// message and the sending peer id, or None if the message was dropped.
+    /// Delayed messages are delivered in the background.
     async fn deliver_message(
         &mut self,
         src_twin_id: TwinId,
         dst_twin_id: TwinId,
         msg_notif: PeerManagerNotification,
-    ) -> (Author, ConsensusMsg) {
+    ) -> Option<(Author, ConsensusMsg)> {
+        let delay = match self
+            .fault_injector
+            .lock()
+            .sample(&src_twin_id, &dst_twin_id)
+        {
+            FaultDecision::Drop => return None,
+            FaultDecision::Deliver(delay) => delay,
+        };
         let node_consensus_tx = self
             .node_consensus_txs
             .lock()
@@ -237,17 +278,21 @@ impl NetworkPlayground {
                 msg_notif
             ),
         };
-        let _ = node_consensus_tx.push(
-            (src_twin_id.author, ProtocolId::ConsensusDirectSendBcs),
-            msg_notif,
-        );
-        msg_copy
+        let key = (src_twin_id.author, ProtocolId::ConsensusDirectSendBcs);
+        if delay.is_zero() {
+            let _ = node_consensus_tx.push(key, msg_notif);
+        } else {
+            self.delayed_delivery
+                .push_after(delay, node_consensus_tx, key, msg_notif);
+        }
+        Some(msg_copy)
     }
 
     /// Wait for exactly `num_messages` to be enqueued and delivered. Return a
     /// copy of all messages for verification.
     /// While all the sent messages are delivered (except those configured to be dropped),
-    /// only the messages that satisfy the given msg inspector are counted.
+    /// only the messages that satisfy the given msg inspector are counted. Delayed
+    /// messages are counted when they are sent, not when they reach the destination.
     pub async fn wait_for_messages<F>(
         &mut self,
         num_messages: usize,
@@ -287,8 +332,10 @@ impl NetworkPlayground {
                         .await;
 
                     // Only insert msg_copy once for twins (if delivered)
-                    if idx == 0 && msg_inspector(&msg_copy) {
-                        msg_copies.push(msg_copy);
+                    if let Some(msg_copy) = msg_copy {
+                        if idx == 0 && msg_inspector(&msg_copy) {
+                            msg_copies.push(msg_copy);
+                        }
                     }
                 }
             }
@@ -374,6 +421,46 @@ impl NetworkPlayground {
         ret
     }
 
+    /// Sets the seed from which the random generators of the faulty links are
+    /// derived. The links whose faults are already injected are reseeded too.
+    pub fn set_fault_injection_seed(&self, seed: u64) {
+        self.fault_injector.lock().set_seed(seed);
+    }
+
+    /// Injects faults on the messages sent from `src` to `dst`. Links are
+    /// directional, so asymmetric faults can be configured for each direction.
+    pub fn inject_link_faults(&self, src: TwinId, dst: TwinId, faults: LinkFaults) {
+        self.fault_injector.lock().set_link_faults(src, dst, faults);
+    }
+
+    /// Injects the same faults on all the links between the two groups of nodes,
+    /// in both directions.
+    pub fn inject_faults_between(&self, first: &[TwinId], second: &[TwinId], faults: LinkFaults) {
+        let mut fault_injector = self.fault_injector.lock();
+        for n1 in first {
+            for n2 in second {
+                fault_injector.set_link_faults(*n1, *n2, faults);
+                fault_injector.set_link_faults(*n2, *n1, faults);
+            }
+        }
+    }
+
+    /// Returns a future that advances the mock time service of the playground
+    /// whenever messages are delayed, so the delayed messages are delivered in
+    /// the order of their sampled latencies without waiting for them. It waits
+    /// for the next delayed message once no message is pending. Panics if the
+    /// playground does not use a mock time service.
+    pub fn deliver_delayed_messages(&self) -> impl Future<Output = ()> + Send + 'static {
+        let time_service = self.delayed_delivery.time_service.clone().into_mock();
+        let delayed_messages = self.delayed_delivery.delayed_messages.clone();
+        async move {
+            loop {
+                while time_service.advance_next_async().await.is_some() {}
+                delayed_messages.notified().await;
+            }
+        }
+    }
+
     pub async fn start(mut self) {
         // Take the next queued message
         while let Some((src_twin_id, net_req)) = self.outbound_msgs_rx.next().await {
@@ -477,6 +564,143 @@ impl DropConfigRound {
     }
 }
 
+/// Distribution of the latency added to the messages of a link
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatencyDistribution {
+    /// No latency is added
+    None,
+    /// The same latency is added to all messages
+    Fixed(Duration),
+    /// The latency is sampled uniformly from `[min, max]`, which reorders messages
+    Uniform { min: Duration, max: Duration },
+    /// The `base` latency, plus a `spike` for the given percentage of messages
+    Spiky {
+        base: Duration,
+        spike: Duration,
+        spike_percentage: u8,
+    },
+}
+
+impl LatencyDistribution {
+    fn sample(&self, rng: &mut StdRng) -> Duration {
+        match *self {
+            LatencyDistribution::None => Duration::ZERO,
+            LatencyDistribution::Fixed(latency) => latency,
+            LatencyDistribution::Uniform { min, max } => {
+                if max <= min {
+                    min
+                } else {
+                    let micros = rng.gen_range(min.as_micros() as u64, max.as_micros() as u64 + 1);
+                    Duration::from_micros(micros)
+                }
+            },
+            LatencyDistribution::Spiky {
+                base,
+                spike,
+                spike_percentage,
+            } => {
+                if rng.gen_range(0, 100) < spike_percentage {
+                    base + spike
+                } else {
+                    base
+                }
+            },
+        }
+    }
+}
+
+/// Faults injected on the messages sent over a link
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkFaults {
+    pub latency: LatencyDistribution,
+    /// Percentage of messages that are dropped
+    pub drop_percentage: u8,
+}
+
+impl Default for LinkFaults {
+    fn default() -> Self {
+        Self {
+            latency: LatencyDistribution::None,
+            drop_percentage: 0,
+        }
+    }
+}
+
+/// Pushes the messages of faulty links to their destination once their
+/// latency has passed on the time service
+#[derive(Clone)]
+struct DelayedDelivery {
+    time_service: TimeService,
+    executor: Handle,
+    /// Notified whenever a message is delayed
+    delayed_messages: Arc<Notify>,
+}
+
+impl DelayedDelivery {
+    fn push_after(
+        &self,
+        delay: Duration,
+        node_consensus_tx: aptos_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+        key: (PeerId, ProtocolId),
+        msg_notif: PeerManagerNotification,
+    ) {
+        // The sleep is registered with the time service before the notification,
+        // so advancing the time on notification delivers the message
+        let sleep = self.time_service.sleep(delay);
+        self.executor.spawn(async move {
+            sleep.await;
+            let _ = node_consensus_tx.push(key, msg_notif);
+        });
+        self.delayed_messages.notify_one();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FaultDecision {
+    Drop,
+    Deliver(Duration),
+}
+
+/// Seeded fault injection for the links between nodes. Each link has its own
+/// random generator derived from the seed and the IDs of the nodes, so the
+/// injected faults only depend on the sequence of messages sent over the link.
+#[derive(Default)]
+struct FaultInjector {
+    seed: u64,
+    links: HashMap<(TwinId, TwinId), (LinkFaults, StdRng)>,
+}
+
+impl FaultInjector {
+    fn link_rng(seed: u64, src: &TwinId, dst: &TwinId) -> StdRng {
+        StdRng::seed_from_u64(seed ^ ((src.id as u64) << 32 | dst.id as u64))
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        for ((src, dst), (_, rng)) in self.links.iter_mut() {
+            *rng = Self::link_rng(seed, src, dst);
+        }
+    }
+
+    fn set_link_faults(&mut self, src: TwinId, dst: TwinId, faults: LinkFaults) {
+        let rng = Self::link_rng(self.seed, &src, &dst);
+        self.links.insert((src, dst), (faults, rng));
+    }
+
+    fn sample(&mut self, src: &TwinId, dst: &TwinId) -> FaultDecision {
+        match self.links.get_mut(&(*src, *dst)) {
+            Some((faults, rng)) => {
+                if faults.drop_percentage > 0 && rng.gen_range(0, 100) < faults.drop_percentage {
+                    FaultDecision::Drop
+                } else {
+                    FaultDecision::Deliver(faults.latency.sample(rng))
+                }
+            },
+            None => FaultDecision::Deliver(Duration::ZERO),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -504,7 +728,7 @@ mod tests {
     };
     use aptos_types::validator_verifier::random_validator_verifier;
     use bytes::Bytes;
-    use futures::{channel::oneshot, future};
+    use futures::{channel::oneshot, future, FutureExt};
     use maplit::hashmap;
 
     #[test]
@@ -554,6 +778,149 @@ mod tests {
         assert!(!playground.is_message_dropped_round(&nodes[0], &nodes[3], 2));
     }
 
+    #[test]
+    fn test_link_faults() {
+        let nodes: Vec<TwinId> = (0..2)
+            .map(|id| TwinId {
+                id,
+                author: Author::random(),
+            })
+            .collect();
+        let faults = LinkFaults {
+            latency: LatencyDistribution::Uniform {
+                min: Duration::from_millis(10),
+                max: Duration::from_millis(50),
+            },
+            drop_percentage: 20,
+        };
+        let sample_decisions = |seed| {
+            let mut fault_injector = FaultInjector {
+                seed,
+                ..Default::default()
+            };
+            fault_injector.set_link_faults(nodes[0], nodes[1], faults);
+            (0..1000)
+                .map(|_| fault_injector.sample(&nodes[0], &nodes[1]))
+                .collect::<Vec<_>>()
+        };
+
+        // The same seed reproduces the same faults
+        let decisions = sample_decisions(1);
+        assert_eq!(decisions, sample_decisions(1));
+        assert_ne!(decisions, sample_decisions(2));
+
+        // Verify the drop rate and the sampled latencies
+        let num_dropped = decisions
+            .iter()
+            .filter(|decision| **decision == FaultDecision::Drop)
+            .count();
+        assert!(num_dropped > 100 && num_dropped < 300);
+        for decision in decisions {
+            if let FaultDecision::Deliver(delay) = decision {
+                assert!(delay >= Duration::from_millis(10) && delay <= Duration::from_millis(50));
+            }
+        }
+
+        // Changing the seed reseeds the existing links
+        let mut fault_injector = FaultInjector::default();
+        fault_injector.set_link_faults(nodes[0], nodes[1], faults);
+        fault_injector.set_seed(1);
+        let reseeded: Vec<_> = (0..1000)
+            .map(|_| fault_injector.sample(&nodes[0], &nodes[1]))
+            .collect();
+        assert_eq!(reseeded, decisions);
+
+        // Links are directional, so the reverse link has no faults
+        let mut fault_injector = FaultInjector::default();
+        fault_injector.set_link_faults(nodes[0], nodes[1], faults);
+        assert_eq!(
+            fault_injector.sample(&nodes[1], &nodes[0]),
+            FaultDecision::Deliver(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_link_latency_with_mock_clock() {
+        let runtime = consensus_runtime();
+        let _entered_runtime = runtime.enter();
+
+        let time_service = TimeService::mock();
+        let mut playground = NetworkPlayground::new_with_time_service(
+            runtime.handle().clone(),
+            time_service.clone(),
+        );
+        let mut network_reqs_txs = vec![];
+        let mut consensus_rxs = vec![];
+        let mut conn_mgr_reqs_txs = vec![];
+        let nodes: Vec<TwinId> = (0..2)
+            .map(|id| TwinId {
+                id,
+                author: Author::random(),
+            })
+            .collect();
+        for node in &nodes {
+            let (network_reqs_tx, network_reqs_rx) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+            let (consensus_tx, consensus_rx) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+            let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = aptos_channels::new_test(1024);
+            playground.add_node(*node, consensus_tx, network_reqs_rx, conn_mgr_reqs_rx);
+            network_reqs_txs.push(network_reqs_tx);
+            consensus_rxs.push(consensus_rx);
+            conn_mgr_reqs_txs.push(conn_mgr_reqs_tx);
+        }
+        playground.inject_link_faults(
+            nodes[0],
+            nodes[1],
+            LinkFaults {
+                latency: LatencyDistribution::Fixed(Duration::from_millis(100)),
+                drop_percentage: 0,
+            },
+        );
+
+        let previous_qc = certificate_for_genesis();
+        let msg = ConsensusMsg::SyncInfo(Box::new(SyncInfo::new(
+            previous_qc.clone(),
+            previous_qc,
+            None,
+        )));
+        let protocol_id = ProtocolId::ConsensusDirectSendBcs;
+        let send = |src: usize, dst: usize| {
+            let msg = Message {
+                protocol_id,
+                mdata: Bytes::from(protocol_id.to_bytes(&msg).unwrap()),
+            };
+            network_reqs_txs[src]
+                .push(
+                    (nodes[dst].author, protocol_id),
+                    PeerManagerRequest::SendDirectSend(nodes[dst].author, msg),
+                )
+                .unwrap();
+        };
+
+        let mock_time_service = time_service.into_mock();
+        timed_block_on(&runtime, async {
+            // The message to node 1 is delayed until the clock advances
+            send(0, 1);
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            mock_time_service
+                .advance_async(Duration::from_millis(99))
+                .await;
+            assert!(consensus_rxs[1].next().now_or_never().is_none());
+            mock_time_service
+                .advance_async(Duration::from_millis(1))
+                .await;
+            assert!(consensus_rxs[1].next().await.is_some());
+
+            // The reverse link has no faults
+            send(1, 0);
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            assert!(consensus_rxs[0].next().now_or_never().is_some());
+        });
+    }
+
     fn add_peer_to_storage(
         peers_and_metadata: &PeersAndMetadata,
         peer: &PeerId,
@@ -3,7 +3,10 @@
 
 use crate::{
     counters,
-    quorum_store::{batch_store::BatchReader, quorum_store_coordinator::CoordinatorCommand},
+    quorum_store::{
+        batch_requester::BatchRequestPriority, batch_store::BatchReader,
+        quorum_store_coordinator::CoordinatorCommand,
+    },
 };
 use aptos_consensus_types::{
     block::Block,
@@ -45,7 +48,7 @@ impl PayloadManager {
         oneshot::Receiver<ExecutorResult<Vec<SignedTransaction>>>,
     )> {
         let mut receivers = Vec::new();
-        for pos in proofs {
+        for (position, pos) in proofs.into_iter().enumerate() {
             trace!(
                 "QSE: requesting pos {:?}, digest {}, time = {}",
                 pos,
@@ -53,7 +56,8 @@ impl PayloadManager {
                 block_timestamp
             );
             if block_timestamp <= pos.expiration() {
-                receivers.push((*pos.digest(), batch_reader.get_batch(pos)));
+                let priority = BatchRequestPriority::new(block_timestamp, position);
+                receivers.push((*pos.digest(), batch_reader.get_batch(pos, priority)));
             } else {
                 debug!("QSE: skipped expired pos {}", pos.digest());
             }
@@ -131,6 +135,35 @@ impl PayloadManager {
         }
     }
 
+    /// Returns true if the transactions of the block can be extracted without fetching any
+    /// batch from other validators.
+    pub fn is_payload_available(&self, block: &Block) -> bool {
+        let payload = match block.payload() {
+            Some(p) => p,
+            None => return true,
+        };
+
+        match self {
+            PayloadManager::DirectMempool => true,
+            PayloadManager::InQuorumStore(batch_reader, _) => {
+                let proofs = match payload {
+                    Payload::InQuorumStore(proof_with_data) => &proof_with_data.proofs,
+                    Payload::InQuorumStoreWithLimit(proof_with_data) => {
+                        &proof_with_data.proof_with_data.proofs
+                    },
+                    Payload::DirectMempool(_) => {
+                        unreachable!("InQuorumStore should be used");
+                    },
+                };
+                // Expired batches are skipped when extracting the transactions.
+                proofs.iter().all(|proof| {
+                    block.timestamp_usecs() > proof.expiration()
+                        || batch_reader.exists(proof.digest()).is_some()
+                })
+            },
+        }
+    }
+
     /// Extract transaction from a given block
     /// Assumes it is never called for the same block concurrently. Otherwise status can be None.
     pub async fn get_transactions(
@@ -56,6 +56,9 @@ pub trait TExecutionClient: Send + Sync {
     /// This is needed for some DAG tests. Clean this up as a TODO.
     fn get_execution_channel(&self) -> Option<UnboundedSender<OrderedBlocks>>;
 
+    /// Start executing a block before it is ordered, if speculative execution is enabled.
+    async fn speculate_execution(&self, _block: &PipelinedBlock) {}
+
     /// Send ordered blocks to the real execution phase through the channel.
     async fn finalize_order(
         &self,
@@ -118,6 +121,7 @@ pub struct ExecutionProxyClient {
     self_sender: aptos_channels::UnboundedSender<Event<ConsensusMsg>>,
     network_sender: ConsensusNetworkClient<NetworkClient<ConsensusMsg>>,
     bounded_executor: BoundedExecutor,
+    enable_speculative_execution: bool,
     // channels to buffer manager
     handle: Arc<RwLock<BufferManagerHandle>>,
 }
@@ -129,6 +133,7 @@ impl ExecutionProxyClient {
         self_sender: aptos_channels::UnboundedSender<Event<ConsensusMsg>>,
         network_sender: ConsensusNetworkClient<NetworkClient<ConsensusMsg>>,
         bounded_executor: BoundedExecutor,
+        enable_speculative_execution: bool,
     ) -> Self {
         Self {
             execution_proxy,
@@ -136,6 +141,7 @@ impl ExecutionProxyClient {
             self_sender,
             network_sender,
             bounded_executor,
+            enable_speculative_execution,
             handle: Arc::new(RwLock::new(BufferManagerHandle::new())),
         }
     }
@@ -223,6 +229,13 @@ impl TExecutionClient for ExecutionProxyClient {
         self.handle.read().execute_tx.clone()
     }
 
+    async fn speculate_execution(&self, block: &PipelinedBlock) {
+        // Without a running buffer manager, the result would never be reused.
+        if self.enable_speculative_execution && self.handle.read().execute_tx.is_some() {
+            self.execution_proxy.speculate(block.block()).await;
+        }
+    }
+
     async fn finalize_order(
         &self,
         blocks: &[Arc<PipelinedBlock>],
@@ -12,13 +12,156 @@ use crate::{
 use aptos_consensus_types::proof_of_store::{BatchInfo, ProofOfStore};
 use aptos_crypto::HashValue;
 use aptos_executor_types::*;
+use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_types::{transaction::SignedTransaction, validator_verifier::ValidatorVerifier, PeerId};
 use futures::{stream::FuturesUnordered, StreamExt};
 use rand::Rng;
-use std::{sync::Arc, time::Duration};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::{sync::oneshot, time};
 
+/// The position of a batch in the blocks that need it. When the number of
+/// concurrent fetches is limited, batches needed by older blocks, and earlier
+/// in the block, are fetched first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BatchRequestPriority {
+    block_timestamp: u64,
+    position: usize,
+}
+
+impl BatchRequestPriority {
+    pub fn new(block_timestamp: u64, position: usize) -> Self {
+        Self {
+            block_timestamp,
+            position,
+        }
+    }
+}
+
+struct PendingFetch {
+    priority: BatchRequestPriority,
+    // Breaks ties between equal priorities in arrival order
+    sequence_number: u64,
+    permit_tx: oneshot::Sender<FetchPermit>,
+}
+
+impl PendingFetch {
+    fn key(&self) -> (BatchRequestPriority, u64) {
+        (self.priority, self.sequence_number)
+    }
+}
+
+impl PartialEq for PendingFetch {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for PendingFetch {}
+
+impl PartialOrd for PendingFetch {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingFetch {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so the max-heap pops the lowest (i.e., most urgent) priority first
+        other.key().cmp(&self.key())
+    }
+}
+
+struct FetchSchedulerState {
+    num_active: usize,
+    pending: BinaryHeap<PendingFetch>,
+    next_sequence_number: u64,
+}
+
+/// Limits the number of batches fetched concurrently, and starts the pending
+/// fetches in priority order as the active ones complete.
+pub(crate) struct FetchScheduler {
+    max_concurrent_fetches: usize,
+    state: Mutex<FetchSchedulerState>,
+}
+
+impl FetchScheduler {
+    pub(crate) fn new(max_concurrent_fetches: usize) -> Self {
+        Self {
+            max_concurrent_fetches: max_concurrent_fetches.max(1),
+            state: Mutex::new(FetchSchedulerState {
+                num_active: 0,
+                pending: BinaryHeap::new(),
+                next_sequence_number: 0,
+            }),
+        }
+    }
+
+    pub(crate) async fn acquire(self: &Arc<Self>, priority: BatchRequestPriority) -> FetchPermit {
+        let permit_rx = {
+            let mut state = self.state.lock();
+            if state.num_active < self.max_concurrent_fetches {
+                state.num_active += 1;
+                return FetchPermit {
+                    scheduler: Some(self.clone()),
+                };
+            }
+            let (permit_tx, permit_rx) = oneshot::channel();
+            let sequence_number = state.next_sequence_number;
+            state.next_sequence_number += 1;
+            state.pending.push(PendingFetch {
+                priority,
+                sequence_number,
+                permit_tx,
+            });
+            permit_rx
+        };
+        // The permit is handed over by the fetch that releases it. If this future is
+        // dropped after the hand over, the permit is dropped with the channel, which
+        // releases the slot again.
+        permit_rx
+            .await
+            .expect("pending fetches are never dropped by the scheduler")
+    }
+
+    pub(crate) fn num_active(&self) -> usize {
+        self.state.lock().num_active
+    }
+
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock();
+        // Hand over the slot to the most urgent pending fetch that is still waiting
+        while let Some(pending) = state.pending.pop() {
+            let permit = FetchPermit {
+                scheduler: Some(self.clone()),
+            };
+            match pending.permit_tx.send(permit) {
+                Ok(()) => return,
+                // The fetch stopped waiting, so the permit must not release the slot
+                Err(mut permit) => permit.scheduler = None,
+            }
+        }
+        state.num_active -= 1;
+    }
+}
+
+pub(crate) struct FetchPermit {
+    scheduler: Option<Arc<FetchScheduler>>,
+}
+
+impl Drop for FetchPermit {
+    fn drop(&mut self) {
+        if let Some(scheduler) = self.scheduler.take() {
+            scheduler.release();
+        }
+    }
+}
+
 struct BatchRequesterState {
     signers: Vec<PeerId>,
     next_index: usize,
@@ -42,7 +185,17 @@ impl BatchRequesterState {
         }
     }
 
+    /// Returns the signers to request the batch from next. Each retry, the batch is
+    /// requested from twice as many signers as the previous time (up to all the
+    /// signers), while the previous requests are still in flight.
     fn next_request_peers(&mut self, num_peers: usize) -> Option<Vec<PeerId>> {
+        let num_peers = num_peers
+            .saturating_mul(
+                1usize
+                    .checked_shl(self.num_retries as u32)
+                    .unwrap_or(usize::MAX),
+            )
+            .clamp(1, self.signers.len().max(1));
         if self.num_retries == 0 {
             let mut rng = rand::thread_rng();
             // make sure nodes request from the different set of nodes
@@ -103,6 +256,7 @@ pub(crate) struct BatchRequester<T> {
     rpc_timeout_ms: usize,
     network_sender: T,
     validator_verifier: Arc<ValidatorVerifier>,
+    fetch_scheduler: Arc<FetchScheduler>,
 }
 
 impl<T: QuorumStoreSender + Sync + 'static> BatchRequester<T> {
@@ -113,6 +267,7 @@ impl<T: QuorumStoreSender + Sync + 'static> BatchRequester<T> {
         retry_limit: usize,
         retry_interval_ms: usize,
         rpc_timeout_ms: usize,
+        max_concurrent_fetches: usize,
         network_sender: T,
         validator_verifier: ValidatorVerifier,
     ) -> Self {
@@ -125,14 +280,25 @@ impl<T: QuorumStoreSender + Sync + 'static> BatchRequester<T> {
             rpc_timeout_ms,
             network_sender,
             validator_verifier: Arc::new(validator_verifier),
+            fetch_scheduler: Arc::new(FetchScheduler::new(max_concurrent_fetches)),
         }
     }
 
+    /// Fetches the batch from its signers. Each retry interval, the request is
+    /// sent to a larger set of new signers while the previous requests are still
+    /// in flight. Once a signer returns the batch, the remaining requests are
+    /// cancelled.
     pub(crate) async fn request_batch(
         &self,
         proof: ProofOfStore,
+        priority: BatchRequestPriority,
         ret_tx: oneshot::Sender<ExecutorResult<Vec<SignedTransaction>>>,
     ) -> Option<(BatchInfo, Vec<SignedTransaction>)> {
+        let queue_start = Instant::now();
+        let _permit = self.fetch_scheduler.acquire(priority).await;
+        counters::BATCH_FETCH_QUEUE_DURATION.observe_duration(queue_start.elapsed());
+        let request_start = Instant::now();
+
         let digest = *proof.digest();
         let expiration = proof.expiration();
         let signers = proof.shuffled_signers(&self.validator_verifier);
@@ -170,6 +336,13 @@ impl<T: QuorumStoreSender + Sync + 'static> BatchRequester<T> {
                                 let batch_info = batch.batch_info().clone();
                                 let payload = batch.into_transactions();
                                 request_state.serve_request(digest, Some(payload.clone()));
+                                // Cancel the requests to the other signers
+                                counters::CANCELLED_BATCH_REQUEST_COUNT.inc_by(futures.len() as u64);
+                                drop(futures);
+                                counters::batch_fetch_duration(
+                                    counters::REQUEST_SUCCESS_LABEL,
+                                    request_start.elapsed(),
+                                );
                                 return Some((batch_info, payload));
                             }
                             // Short-circuit if the chain has moved beyond expiration
@@ -182,6 +355,10 @@ impl<T: QuorumStoreSender + Sync + 'static> BatchRequester<T> {
                                     counters::RECEIVED_BATCH_EXPIRED_COUNT.inc();
                                     debug!("QS: batch request expired, digest:{}", digest);
                                     request_state.serve_request(digest, None);
+                                    counters::batch_fetch_duration(
+                                        counters::REQUEST_FAIL_LABEL,
+                                        request_start.elapsed(),
+                                    );
                                     return None;
                                 }
                             }
@@ -196,6 +373,7 @@ impl<T: QuorumStoreSender + Sync + 'static> BatchRequester<T> {
             counters::RECEIVED_BATCH_REQUEST_TIMEOUT_COUNT.inc();
             debug!("QS: batch request timed out, digest:{}", digest);
             request_state.serve_request(digest, None);
+            counters::batch_fetch_duration(counters::REQUEST_FAIL_LABEL, request_start.elapsed());
             None
         })
     }
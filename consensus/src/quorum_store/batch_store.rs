@@ -4,7 +4,7 @@
 use crate::{
     network::QuorumStoreSender,
     quorum_store::{
-        batch_requester::BatchRequester,
+        batch_requester::{BatchRequestPriority, BatchRequester},
         counters,
         quorum_store_db::QuorumStoreStorage,
         types::{PersistedValue, StorageMode},
@@ -388,9 +388,12 @@ pub trait BatchReader: Send + Sync {
     /// Check if the batch corresponding to the digest exists, return the batch author if true
     fn exists(&self, digest: &HashValue) -> Option<PeerId>;
 
+    /// Returns the payload of the batch, fetching it from the signers if it
+    /// is not available locally
     fn get_batch(
         &self,
         proof: ProofOfStore,
+        priority: BatchRequestPriority,
     ) -> oneshot::Receiver<ExecutorResult<Vec<SignedTransaction>>>;
 
     fn update_certified_timestamp(&self, certified_time: u64);
@@ -421,6 +424,7 @@ impl<T: QuorumStoreSender + Clone + Send + Sync + 'static> BatchReader for Batch
     fn get_batch(
         &self,
         proof: ProofOfStore,
+        priority: BatchRequestPriority,
     ) -> oneshot::Receiver<ExecutorResult<Vec<SignedTransaction>>> {
         let (tx, rx) = oneshot::channel();
 
@@ -433,7 +437,8 @@ impl<T: QuorumStoreSender + Clone + Send + Sync + 'static> BatchReader for Batch
             let batch_store = self.batch_store.clone();
             let batch_requester = self.batch_requester.clone();
             tokio::spawn(async move {
-                if let Some((batch_info, payload)) = batch_requester.request_batch(proof, tx).await
+                if let Some((batch_info, payload)) =
+                    batch_requester.request_batch(proof, priority, tx).await
                 {
                     batch_store.persist(vec![PersistedValue::new(batch_info, Some(payload))]);
                 }
@@ -496,6 +496,44 @@ pub static SENT_BATCH_REQUEST_RETRY_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the number of in-flight batch requests dropped once the batch was received.
+pub static CANCELLED_BATCH_REQUEST_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "quorum_store_cancelled_batch_request_count",
+        "Count of the number of in-flight batch requests dropped once the batch was received."
+    )
+    .unwrap()
+});
+
+/// Histogram of the time durations for fetching a missing batch from other nodes, by result.
+static BATCH_FETCH_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "quorum_store_batch_fetch_duration",
+        "Histogram of the time durations for fetching a missing batch from other nodes, by result.",
+        &["result"],
+        QUORUM_STORE_LATENCY_BUCKETS.to_vec()
+    )
+    .unwrap()
+});
+
+pub fn batch_fetch_duration(result: &str, duration: Duration) {
+    BATCH_FETCH_DURATION
+        .with_label_values(&[result])
+        .observe(duration.as_secs_f64());
+}
+
+/// Histogram of the time durations a batch fetch waits for a free slot.
+pub static BATCH_FETCH_QUEUE_DURATION: Lazy<DurationHistogram> = Lazy::new(|| {
+    DurationHistogram::new(
+        register_histogram!(
+            "quorum_store_batch_fetch_queue_duration",
+            "Histogram of the time durations a batch fetch waits for a free slot.",
+            QUORUM_STORE_LATENCY_BUCKETS.to_vec()
+        )
+        .unwrap(),
+    )
+});
+
 /// Counters(queued,dequeued,dropped) related to batch retrieval per epoch task
 pub static BATCH_RETRIEVAL_TASK_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
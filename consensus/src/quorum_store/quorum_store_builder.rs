@@ -240,6 +240,7 @@ impl InnerBuilder {
             self.config.batch_request_retry_limit,
             self.config.batch_request_retry_interval_ms,
             self.config.batch_request_rpc_timeout_ms,
+            self.config.batch_request_max_concurrent_fetches,
             self.network_sender.clone(),
             self.verifier.clone(),
         );
@@ -4,33 +4,80 @@
 use crate::{
     network::QuorumStoreSender,
     quorum_store::{
-        batch_requester::BatchRequester,
+        batch_requester::{BatchRequestPriority, BatchRequester, FetchScheduler},
         types::{Batch, BatchRequest, BatchResponse},
     },
 };
 use aptos_consensus_types::{
     common::Author,
-    proof_of_store::{BatchId, ProofOfStore, SignedBatchInfo},
+    proof_of_store::{BatchId, BatchInfo, ProofOfStore, SignedBatchInfo},
 };
 use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
 use aptos_types::{
     aggregate_signature::{AggregateSignature, PartialSignatures},
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     validator_signer::ValidatorSigner,
-    validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
+    validator_verifier::{random_validator_verifier, ValidatorConsensusInfo, ValidatorVerifier},
 };
+use futures::FutureExt;
 use move_core_types::account_address::AccountAddress;
-use std::time::{Duration, Instant};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 #[derive(Clone)]
 struct MockBatchRequester {
     return_value: BatchResponse,
+    response_delay: Duration,
+    // If set, only this peer responds, and the requests to the others never complete
+    responding_peer: Option<Author>,
+    requested_digests: Arc<Mutex<Vec<HashValue>>>,
+    requested_peers: Arc<Mutex<Vec<Author>>>,
+    num_in_flight: Arc<AtomicUsize>,
 }
 
 impl MockBatchRequester {
     fn new(return_value: BatchResponse) -> Self {
-        Self { return_value }
+        Self {
+            return_value,
+            response_delay: Duration::ZERO,
+            responding_peer: None,
+            requested_digests: Arc::new(Mutex::new(vec![])),
+            requested_peers: Arc::new(Mutex::new(vec![])),
+            num_in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn with_response_delay(mut self, response_delay: Duration) -> Self {
+        self.response_delay = response_delay;
+        self
+    }
+
+    fn with_responding_peer(mut self, responding_peer: Author) -> Self {
+        self.responding_peer = Some(responding_peer);
+        self
+    }
+}
+
+/// Tracks the requests in flight, including the ones dropped before completing
+struct InFlightRequest(Arc<AtomicUsize>);
+
+impl InFlightRequest {
+    fn new(num_in_flight: Arc<AtomicUsize>) -> Self {
+        num_in_flight.fetch_add(1, Ordering::SeqCst);
+        Self(num_in_flight)
+    }
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
     }
 }
 
@@ -42,10 +89,20 @@ impl QuorumStoreSender for MockBatchRequester {
 
     async fn request_batch(
         &self,
-        _request: BatchRequest,
-        _recipient: Author,
+        request: BatchRequest,
+        recipient: Author,
         _timeout: Duration,
     ) -> anyhow::Result<BatchResponse> {
+        self.requested_digests.lock().push(request.digest());
+        self.requested_peers.lock().push(recipient);
+        let _in_flight = InFlightRequest::new(self.num_in_flight.clone());
+        if self
+            .responding_peer
+            .is_some_and(|responding_peer| responding_peer != recipient)
+        {
+            futures::future::pending::<()>().await;
+        }
+        tokio::time::sleep(self.response_delay).await;
         Ok(self.return_value.clone())
     }
 
@@ -95,6 +152,7 @@ async fn test_batch_request_exists() {
         2,
         1_000,
         1_000,
+        100,
         MockBatchRequester::new(batch_response),
         ValidatorVerifier::new_single(validator_signer.author(), validator_signer.public_key()),
     );
@@ -105,6 +163,7 @@ async fn test_batch_request_exists() {
                 batch.batch_info().clone(),
                 AggregateSignature::new(vec![u8::MAX].into(), None),
             ),
+            BatchRequestPriority::new(0, 0),
             tx,
         )
         .await;
@@ -189,6 +248,7 @@ async fn test_batch_request_not_exists_not_expired() {
         2,
         retry_interval_ms,
         1_000,
+        100,
         MockBatchRequester::new(batch_response),
         validator_verifier,
     );
@@ -200,6 +260,7 @@ async fn test_batch_request_not_exists_not_expired() {
                 batch.batch_info().clone(),
                 AggregateSignature::new(vec![u8::MAX].into(), None),
             ),
+            BatchRequestPriority::new(0, 0),
             tx,
         )
         .await;
@@ -236,6 +297,7 @@ async fn test_batch_request_not_exists_expired() {
         2,
         retry_interval_ms,
         1_000,
+        100,
         MockBatchRequester::new(batch_response),
         validator_verifier,
     );
@@ -247,6 +309,7 @@ async fn test_batch_request_not_exists_expired() {
                 batch.batch_info().clone(),
                 AggregateSignature::new(vec![u8::MAX].into(), None),
             ),
+            BatchRequestPriority::new(0, 0),
             tx,
         )
         .await;
@@ -256,3 +319,150 @@ async fn test_batch_request_not_exists_expired() {
     // No retry because of short-circuiting of expired batch
     assert!(request_duration < Duration::from_millis(retry_interval_ms as u64));
 }
+
+#[tokio::test]
+async fn test_batch_request_priority() {
+    let batch = Batch::new(
+        BatchId::new_for_test(1),
+        vec![],
+        1,
+        1,
+        AccountAddress::random(),
+        0,
+    );
+    let batch_response = BatchResponse::Batch(batch.clone());
+    let sender =
+        MockBatchRequester::new(batch_response).with_response_delay(Duration::from_millis(100));
+    let requested_digests = sender.requested_digests.clone();
+
+    let validator_signer = ValidatorSigner::random(None);
+    // Only a single batch can be fetched at a time
+    let batch_requester = Arc::new(BatchRequester::new(
+        1,
+        AccountAddress::random(),
+        1,
+        2,
+        1_000,
+        1_000,
+        1,
+        sender,
+        ValidatorVerifier::new_single(validator_signer.author(), validator_signer.public_key()),
+    ));
+
+    let request = |digest: HashValue, priority: BatchRequestPriority| {
+        let batch_requester = batch_requester.clone();
+        let batch_info = BatchInfo::new(
+            AccountAddress::random(),
+            BatchId::new_for_test(1),
+            1,
+            1,
+            digest,
+            0,
+            0,
+            0,
+        );
+        tokio::spawn(async move {
+            let (tx, _rx) = tokio::sync::oneshot::channel();
+            let proof = ProofOfStore::new(
+                batch_info,
+                AggregateSignature::new(vec![u8::MAX].into(), None),
+            );
+            batch_requester.request_batch(proof, priority, tx).await
+        })
+    };
+
+    // The first request occupies the only slot, while the others wait for it
+    let digests: Vec<_> = (0..4).map(|_| HashValue::random()).collect();
+    let mut handles = vec![request(digests[0], BatchRequestPriority::new(10, 0))];
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    handles.push(request(digests[1], BatchRequestPriority::new(30, 0)));
+    handles.push(request(digests[2], BatchRequestPriority::new(20, 1)));
+    handles.push(request(digests[3], BatchRequestPriority::new(20, 0)));
+    for handle in handles {
+        assert!(handle.await.unwrap().is_some());
+    }
+
+    // Pending batches are fetched by block timestamp, then by position in the block
+    assert_eq!(*requested_digests.lock(), vec![
+        digests[0], digests[3], digests[2], digests[1]
+    ]);
+}
+
+#[tokio::test]
+async fn test_batch_request_parallel_signers() {
+    let batch = Batch::new(
+        BatchId::new_for_test(1),
+        vec![],
+        1,
+        1,
+        AccountAddress::random(),
+        0,
+    );
+    let batch_response = BatchResponse::Batch(batch.clone());
+    let (_, validator_verifier) = random_validator_verifier(8, None, false);
+    let signers = validator_verifier.get_ordered_account_addresses();
+    // Only the last signer has the batch, the others never respond
+    let sender = MockBatchRequester::new(batch_response).with_responding_peer(signers[7]);
+    let requested_peers = sender.requested_peers.clone();
+    let num_in_flight = sender.num_in_flight.clone();
+
+    let retry_interval_ms = 50;
+    let batch_requester = BatchRequester::new(
+        1,
+        AccountAddress::random(),
+        1,
+        10,
+        retry_interval_ms,
+        60_000,
+        100,
+        sender,
+        validator_verifier,
+    );
+
+    let (tx, mut rx) = tokio::sync::oneshot::channel();
+    let start = Instant::now();
+    let result = batch_requester
+        .request_batch(
+            ProofOfStore::new(
+                batch.batch_info().clone(),
+                AggregateSignature::new(vec![u8::MAX].into(), None),
+            ),
+            BatchRequestPriority::new(0, 0),
+            tx,
+        )
+        .await;
+    assert!(result.is_some());
+    assert!(rx.try_recv().is_ok());
+
+    // The number of signers requested doubles every retry (1, 2, 4, then all 8), so
+    // the responding signer is reached by the fourth attempt at the latest
+    let requested_peers = requested_peers.lock().clone();
+    assert!(requested_peers.contains(&signers[7]));
+    assert!(requested_peers.len() <= 15);
+    assert!(start.elapsed() < Duration::from_millis(4 * retry_interval_ms as u64));
+
+    // The requests still in flight are cancelled once the batch is received
+    assert_eq!(num_in_flight.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_fetch_scheduler_hand_over_to_dropped_fetch() {
+    let scheduler = Arc::new(FetchScheduler::new(1));
+    let priority = BatchRequestPriority::new(0, 0);
+
+    let permit = scheduler.acquire(priority).now_or_never().unwrap();
+    let mut waiting = Box::pin(scheduler.acquire(priority));
+    assert!((&mut waiting).now_or_never().is_none());
+
+    // The slot is handed over to the waiting fetch, which is then dropped before
+    // taking the permit
+    drop(permit);
+    drop(waiting);
+    assert_eq!(scheduler.num_active(), 0);
+
+    // The slot is available again
+    let permit = scheduler.acquire(priority).now_or_never().unwrap();
+    assert_eq!(scheduler.num_active(), 1);
+    drop(permit);
+    assert_eq!(scheduler.num_active(), 0);
+}
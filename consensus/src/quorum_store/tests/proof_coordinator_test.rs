@@ -4,6 +4,7 @@
 use crate::{
     network_interface::ConsensusMsg,
     quorum_store::{
+        batch_requester::BatchRequestPriority,
         batch_store::BatchReader,
         proof_coordinator::{ProofCoordinator, ProofCoordinatorCommand},
         types::Batch,
@@ -30,7 +31,11 @@ impl BatchReader for MockBatchReader {
         Some(self.peer)
     }
 
-    fn get_batch(&self, _proof: ProofOfStore) -> Receiver<ExecutorResult<Vec<SignedTransaction>>> {
+    fn get_batch(
+        &self,
+        _proof: ProofOfStore,
+        _priority: BatchRequestPriority,
+    ) -> Receiver<ExecutorResult<Vec<SignedTransaction>>> {
         unimplemented!()
     }
 
@@ -24,7 +24,7 @@ use crate::{
     pending_votes::VoteReceptionResult,
     persistent_liveness_storage::PersistentLivenessStorage,
     quorum_store::types::BatchMsg,
-    util::is_vtxn_expected,
+    util::{block_production::BlockProductionControl, is_vtxn_expected},
 };
 use anyhow::{bail, ensure, Context};
 use aptos_channels::aptos_channel;
@@ -196,6 +196,10 @@ pub struct RoundManager {
     buffered_proposal_tx: aptos_channel::Sender<Author, VerifiedEvent>,
     local_config: ConsensusConfig,
     features: Features,
+    // Only set when block production control is enabled
+    block_production: Option<BlockProductionControl>,
+    // The proposal for the current round, held back while block production is paused
+    paused_proposal: Option<NewRoundEvent>,
 }
 
 impl RoundManager {
@@ -212,6 +216,7 @@ impl RoundManager {
         buffered_proposal_tx: aptos_channel::Sender<Author, VerifiedEvent>,
         local_config: ConsensusConfig,
         features: Features,
+        block_production: Option<BlockProductionControl>,
     ) -> Self {
         // when decoupled execution is false,
         // the counter is still static.
@@ -237,6 +242,8 @@ impl RoundManager {
             buffered_proposal_tx,
             local_config,
             features,
+            block_production,
+            paused_proposal: None,
         }
     }
 
@@ -284,24 +291,51 @@ impl RoundManager {
             self.new_log(LogEvent::NewRound),
             reason = new_round_event.reason
         );
+        self.paused_proposal = None;
 
         if self
             .proposer_election
             .is_valid_proposer(self.proposal_generator.author(), new_round_event.round)
         {
             self.log_collected_vote_stats(&new_round_event);
-            self.round_state.setup_leader_timeout();
-            let proposal_msg = self.generate_proposal(new_round_event).await?;
-            #[cfg(feature = "failpoints")]
+            if self
+                .block_production
+                .as_ref()
+                .is_some_and(|block_production| !block_production.try_take_turn())
             {
-                if self.check_whether_to_inject_reconfiguration_error() {
-                    self.attempt_to_inject_reconfiguration_error(&proposal_msg)
-                        .await?;
-                }
+                // Block production is paused, so the proposal is made once a block is requested
+                // and the round must not time out meanwhile
+                self.round_state.cancel_timeout();
+                self.paused_proposal = Some(new_round_event);
+                return Ok(());
+            }
+            self.propose(new_round_event).await?;
+        }
+        Ok(())
+    }
+
+    /// Makes the proposal held back while block production is paused, once a block is requested.
+    async fn process_paused_proposal(&mut self) -> anyhow::Result<()> {
+        if let Some(new_round_event) = self.paused_proposal.take() {
+            if new_round_event.round == self.round_state.current_round() {
+                self.propose(new_round_event).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn propose(&mut self, new_round_event: NewRoundEvent) -> anyhow::Result<()> {
+        self.round_state.setup_leader_timeout();
+        let proposal_msg = self.generate_proposal(new_round_event).await?;
+        #[cfg(feature = "failpoints")]
+        {
+            if self.check_whether_to_inject_reconfiguration_error() {
+                self.attempt_to_inject_reconfiguration_error(&proposal_msg)
+                    .await?;
             }
-            self.network.broadcast_proposal(proposal_msg).await;
-            counters::PROPOSALS_COUNT.inc();
         }
+        self.network.broadcast_proposal(proposal_msg).await;
+        counters::PROPOSALS_COUNT.inc();
         Ok(())
     }
 
@@ -1052,6 +1086,8 @@ impl RoundManager {
     ) {
         info!(epoch = self.epoch_state().epoch, "RoundManager started");
         let mut close_rx = close_rx.into_stream();
+        // Proposals are only held back when block production is controlled
+        let block_production = self.block_production.clone().unwrap_or_default();
         loop {
             tokio::select! {
                 biased;
@@ -1113,6 +1149,19 @@ impl RoundManager {
                         }
                     }
                 },
+                _ = block_production.wait_for_turn(), if self.paused_proposal.is_some() => {
+                    let result = monitor!(
+                        "process_paused_proposal",
+                        self.process_paused_proposal().await
+                    );
+                    match result {
+                        Ok(_) => trace!(RoundStateLogSchema::new(self.round_state())),
+                        Err(e) => {
+                            counters::ERROR_COUNT.inc();
+                            warn!(error = ?e, kind = error_kind(&e), RoundStateLogSchema::new(self.round_state()));
+                        }
+                    }
+                },
                 (peer_id, event) = event_rx.select_next_some() => {
                     let result = match event {
                         VerifiedEvent::VoteMsg(vote_msg) => {
@@ -15,7 +15,7 @@ use crate::{
     metrics_safety_rules::MetricsSafetyRules,
     network::{IncomingBlockRetrievalRequest, NetworkSender},
     network_interface::{CommitMessage, ConsensusMsg, ConsensusNetworkClient, DIRECT_SEND, RPC},
-    network_tests::{NetworkPlayground, TwinId},
+    network_tests::{LatencyDistribution, LinkFaults, NetworkPlayground, TwinId},
     payload_manager::PayloadManager,
     persistent_liveness_storage::RecoveryData,
     pipeline::buffer_manager::OrderedBlocks,
@@ -309,6 +309,7 @@ impl NodeSetup {
             round_manager_tx,
             local_consensus_config.clone(),
             features.clone(),
+            None,
         );
         block_on(round_manager.init(last_vote_sent));
         Self {
@@ -350,6 +351,13 @@ impl NodeSetup {
         )
     }
 
+    fn twin_id(&self) -> TwinId {
+        TwinId {
+            id: self.id,
+            author: self.signer.author(),
+        }
+    }
+
     pub fn identity_desc(&self) -> String {
         format!("{} [{}]", self.id, self.signer.author())
     }
@@ -1672,6 +1680,115 @@ fn no_next_test() {
     });
 }
 
+#[test]
+/// Proposals and votes that reach the nodes after latency spikes still let the proposer
+/// make progress.
+fn progress_with_latency_spikes() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new_with_time_service(
+        runtime.handle().clone(),
+        aptos_time_service::TimeService::mock(),
+    );
+    let mut nodes = NodeSetup::create_nodes(
+        &mut playground,
+        runtime.handle().clone(),
+        4,
+        None,
+        None,
+        None,
+        None,
+    );
+    // All the messages between the proposer and the other nodes are delayed, some of them a lot
+    // more than the others
+    let twin_ids: Vec<_> = nodes.iter().map(NodeSetup::twin_id).collect();
+    playground.set_fault_injection_seed(7);
+    playground.inject_faults_between(
+        &twin_ids[..1],
+        &twin_ids[1..],
+        LinkFaults {
+            latency: LatencyDistribution::Spiky {
+                base: Duration::from_millis(10),
+                spike: Duration::from_secs(1),
+                spike_percentage: 30,
+            },
+            drop_percentage: 0,
+        },
+    );
+    runtime.spawn(playground.deliver_delayed_messages());
+    runtime.spawn(playground.start());
+
+    for i in 0..4 {
+        process_and_vote_on_proposal(
+            &runtime,
+            &mut nodes,
+            0,
+            &[],
+            true,
+            None,
+            true,
+            i + 1,
+            i.saturating_sub(1),
+            0,
+        );
+    }
+}
+
+#[test]
+/// Votes lost on the way to the proposer don't keep it from forming a QC, as long as a quorum
+/// of votes gets through.
+fn quorum_despite_lossy_link() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(
+        &mut playground,
+        runtime.handle().clone(),
+        4,
+        None,
+        None,
+        None,
+        None,
+    );
+    // Nothing sent by the last node reaches the proposer
+    playground.inject_link_faults(
+        nodes[3].twin_id(),
+        nodes[0].twin_id(),
+        LinkFaults {
+            latency: LatencyDistribution::None,
+            drop_percentage: 100,
+        },
+    );
+    runtime.spawn(playground.start());
+
+    let lossy_author = nodes[3].signer.author();
+    timed_block_on(&runtime, async {
+        for node in nodes.iter_mut() {
+            let proposal_msg = node.next_proposal().await;
+            assert_eq!(proposal_msg.proposal().round(), 1);
+            node.round_manager
+                .process_proposal_msg(proposal_msg)
+                .await
+                .unwrap();
+        }
+
+        // The vote of the proposer and the votes of the two other nodes form a quorum
+        let proposer = &mut nodes[0];
+        for _ in 0..3 {
+            let vote_msg = proposer.next_vote().await;
+            assert_ne!(vote_msg.vote().author(), lossy_author);
+            proposer
+                .round_manager
+                .process_vote_msg(vote_msg)
+                .await
+                .unwrap();
+        }
+
+        // Round 2 starts on every node, including the one whose vote was lost
+        for node in nodes.iter_mut() {
+            assert_eq!(node.next_proposal().await.proposal().round(), 2);
+        }
+    });
+}
+
 #[test]
 fn commit_pipeline_test() {
     let runtime = consensus_runtime();
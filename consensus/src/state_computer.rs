@@ -15,17 +15,20 @@ use crate::{
     transaction_filter::TransactionFilter,
     transaction_shuffler::TransactionShuffler,
     txn_notifier::TxnNotifier,
+    util::block_production::BlockProductionControl,
 };
 use anyhow::Result;
 use aptos_consensus_notifications::ConsensusNotificationSender;
 use aptos_consensus_types::{block::Block, common::Round, pipelined_block::PipelinedBlock};
 use aptos_crypto::HashValue;
 use aptos_executor_types::{BlockExecutorTrait, ExecutorResult, StateComputeResult};
-use aptos_infallible::RwLock;
+use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
 use aptos_types::{
     account_address::AccountAddress,
     block_executor::config::BlockExecutorConfigFromOnchain,
+    block_metadata::BlockMetadata,
+    block_metadata_ext::{BlockMetadataExt, BlockMetadataWithRandomness},
     contract_event::ContractEvent,
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
@@ -34,7 +37,11 @@ use aptos_types::{
 };
 use fail::fail_point;
 use futures::{future::BoxFuture, SinkExt, StreamExt};
-use std::{boxed::Box, sync::Arc};
+use std::{
+    boxed::Box,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use tokio::sync::Mutex as AsyncMutex;
 
 pub type StateComputeResultFut = BoxFuture<'static, ExecutorResult<PipelineExecutionResult>>;
@@ -79,6 +86,70 @@ struct MutableState {
     is_randomness_enabled: bool,
 }
 
+/// Blocks sent to the execution pipeline before being ordered, see `ExecutionProxy::speculate`.
+#[derive(Default)]
+struct SpeculativeExecutions {
+    /// Round and parent of the blocks queued in the execution pipeline since the last reset. A
+    /// block is only executed speculatively if its parent is queued ahead of it.
+    queued: HashMap<HashValue, (Round, HashValue)>,
+    /// Results of the blocks executed speculatively, that are not ordered yet. Dropping a result
+    /// makes the execution pipeline skip the block, if it hasn't been executed yet.
+    pending: HashMap<HashValue, StateComputeResultFut>,
+}
+
+impl SpeculativeExecutions {
+    fn queue(&mut self, block: &Block) {
+        self.queued
+            .insert(block.id(), (block.round(), block.parent_id()));
+    }
+
+    /// Returns the given block and its queued descendants.
+    fn subtree(&self, block_id: HashValue) -> HashSet<HashValue> {
+        let mut blocks: Vec<_> = self
+            .queued
+            .iter()
+            .map(|(id, (round, parent_id))| (*round, *id, *parent_id))
+            .collect();
+        // Parents have lower rounds than their children.
+        blocks.sort();
+
+        let mut subtree = HashSet::from([block_id]);
+        for (_, id, parent_id) in blocks {
+            if subtree.contains(&parent_id) {
+                subtree.insert(id);
+            }
+        }
+        subtree
+    }
+
+    fn retain(&mut self, keep: impl Fn(&HashValue) -> bool) {
+        self.queued.retain(|id, _| keep(id));
+        let num_pending = self.pending.len();
+        self.pending.retain(|id, _| keep(id));
+        counters::SPECULATIVE_EXECUTION_COUNT
+            .with_label_values(&["discarded"])
+            .inc_by((num_pending - self.pending.len()) as u64);
+    }
+
+    /// Drops the blocks that don't extend the committed block, as they can no longer be ordered.
+    fn prune(&mut self, committed_block_id: HashValue) {
+        let extending = self.subtree(committed_block_id);
+        self.retain(|id| extending.contains(id));
+    }
+
+    /// Drops the descendants of a block that is executed again, as they were executed on top of
+    /// its previous result.
+    fn discard_descendants(&mut self, block_id: HashValue) {
+        let mut descendants = self.subtree(block_id);
+        descendants.remove(&block_id);
+        self.retain(|id| !descendants.contains(id));
+    }
+
+    fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+}
+
 /// Basic communication with the Execution module;
 /// implements StateComputer traits.
 pub struct ExecutionProxy {
@@ -90,6 +161,9 @@ pub struct ExecutionProxy {
     transaction_filter: Arc<TransactionFilter>,
     execution_pipeline: ExecutionPipeline,
     state: RwLock<Option<MutableState>>,
+    speculative_executions: Mutex<SpeculativeExecutions>,
+    // Only set when block production control is enabled
+    block_production: Option<BlockProductionControl>,
 }
 
 impl ExecutionProxy {
@@ -99,6 +173,7 @@ impl ExecutionProxy {
         state_sync_notifier: Arc<dyn ConsensusNotificationSender>,
         handle: &tokio::runtime::Handle,
         txn_filter: TransactionFilter,
+        block_production: Option<BlockProductionControl>,
     ) -> Self {
         let (tx, mut rx) =
             aptos_channels::new::<NotificationType>(10, &counters::PENDING_STATE_SYNC_NOTIFICATION);
@@ -125,9 +200,92 @@ impl ExecutionProxy {
             transaction_filter: Arc::new(txn_filter),
             execution_pipeline,
             state: RwLock::new(None),
+            speculative_executions: Mutex::new(SpeculativeExecutions::default()),
+            block_production,
         }
     }
 
+    /// Starts executing a block that is not ordered yet, on top of its parent's speculative
+    /// result, so that the result is ready by the time the block is ordered. The result is reused
+    /// by `schedule_compute` if the block gets ordered, and dropped if another fork gets ordered,
+    /// the parent is executed again or the executor is reset.
+    pub async fn speculate(&self, block: &Block) {
+        let state = match self.state.read().as_ref() {
+            Some(state) => state.clone(),
+            None => return,
+        };
+        // The randomness of a block is only known once it is ordered.
+        if state.is_randomness_enabled {
+            return;
+        }
+        {
+            let speculative_executions = self.speculative_executions.lock();
+            if speculative_executions.queued.contains_key(&block.id())
+                || !speculative_executions
+                    .queued
+                    .contains_key(&block.parent_id())
+            {
+                return;
+            }
+        }
+        // Speculative blocks go through the same pipeline as the ordered ones, so blocks that
+        // need to fetch batches are not queued, to not delay the ordered blocks behind them.
+        if !state.payload_manager.is_payload_available(block) {
+            counters::SPECULATIVE_EXECUTION_COUNT
+                .with_label_values(&["skipped"])
+                .inc();
+            return;
+        }
+
+        debug!(block = %block, "Speculatively executing block");
+        let fut = self
+            .queue_block(block, block.parent_id(), None, state)
+            .await;
+        let mut speculative_executions = self.speculative_executions.lock();
+        speculative_executions.queue(block);
+        speculative_executions.pending.insert(block.id(), fut);
+        counters::SPECULATIVE_EXECUTION_COUNT
+            .with_label_values(&["started"])
+            .inc();
+    }
+
+    async fn queue_block(
+        &self,
+        block: &Block,
+        parent_block_id: HashValue,
+        randomness: Option<Randomness>,
+        state: MutableState,
+    ) -> StateComputeResultFut {
+        let MutableState {
+            validators,
+            payload_manager,
+            transaction_shuffler,
+            block_executor_onchain_config,
+            transaction_deduper,
+            is_randomness_enabled,
+        } = state;
+
+        let transaction_generator = BlockPreparer::new(
+            payload_manager,
+            self.transaction_filter.clone(),
+            transaction_deduper,
+            transaction_shuffler,
+        );
+
+        let metadata =
+            self.new_block_metadata(block, &validators, randomness, is_randomness_enabled);
+
+        self.execution_pipeline
+            .queue(
+                block.clone(),
+                metadata,
+                parent_block_id,
+                transaction_generator,
+                block_executor_onchain_config,
+            )
+            .await
+    }
+
     fn transactions_to_commit(
         &self,
         executed_block: &PipelinedBlock,
@@ -141,13 +299,12 @@ impl ExecutionProxy {
 
         let user_txns = executed_block.input_transactions().clone();
         let validator_txns = executed_block.validator_txns().cloned().unwrap_or_default();
-        let metadata = if randomness_enabled {
-            executed_block
-                .block()
-                .new_metadata_with_randomness(validators, executed_block.randomness().cloned())
-        } else {
-            executed_block.block().new_block_metadata(validators).into()
-        };
+        let metadata = self.new_block_metadata(
+            executed_block.block(),
+            validators,
+            executed_block.randomness().cloned(),
+            randomness_enabled,
+        );
 
         let input_txns = Block::combine_to_input_transactions(validator_txns, user_txns, metadata);
 
@@ -156,6 +313,42 @@ impl ExecutionProxy {
             .compute_result()
             .transactions_to_commit(input_txns, executed_block.id())
     }
+
+    /// Executing and committing a block must agree on its metadata, so both build it here.
+    fn new_block_metadata(
+        &self,
+        block: &Block,
+        validators: &[AccountAddress],
+        randomness: Option<Randomness>,
+        randomness_enabled: bool,
+    ) -> BlockMetadataExt {
+        let metadata = if randomness_enabled {
+            block.new_metadata_with_randomness(validators, randomness)
+        } else {
+            block.new_block_metadata(validators).into()
+        };
+        let Some(block_production) = &self.block_production else {
+            return metadata;
+        };
+
+        // A local test network may have moved the on-chain time forward
+        let timestamp_usecs = block_production.block_timestamp_usecs(block.timestamp_usecs());
+        match metadata {
+            BlockMetadataExt::V0(metadata) => BlockMetadataExt::V0(BlockMetadata::new(
+                metadata.id(),
+                metadata.epoch(),
+                metadata.round(),
+                metadata.proposer(),
+                metadata.previous_block_votes_bitvec().clone(),
+                metadata.failed_proposer_indices().clone(),
+                timestamp_usecs,
+            )),
+            BlockMetadataExt::V1(metadata) => BlockMetadataExt::V1(BlockMetadataWithRandomness {
+                timestamp_usecs,
+                ..metadata
+            }),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -174,14 +367,7 @@ impl StateComputer for ExecutionProxy {
             parent_id = parent_block_id,
             "Executing block",
         );
-        let MutableState {
-            validators,
-            payload_manager,
-            transaction_shuffler,
-            block_executor_onchain_config,
-            transaction_deduper,
-            is_randomness_enabled,
-        } = self
+        let state = self
             .state
             .read()
             .as_ref()
@@ -189,32 +375,32 @@ impl StateComputer for ExecutionProxy {
             .expect("must be set within an epoch");
 
         let txn_notifier = self.txn_notifier.clone();
-        let transaction_generator = BlockPreparer::new(
-            payload_manager.clone(),
-            self.transaction_filter.clone(),
-            transaction_deduper.clone(),
-            transaction_shuffler.clone(),
-        );
-
-        let block_executor_onchain_config = block_executor_onchain_config.clone();
-
         let timestamp = block.timestamp_usecs();
-        let metadata = if is_randomness_enabled {
-            block.new_metadata_with_randomness(&validators, randomness)
-        } else {
-            block.new_block_metadata(&validators).into()
-        };
 
-        let fut = self
-            .execution_pipeline
-            .queue(
-                block.clone(),
-                metadata,
-                parent_block_id,
-                transaction_generator,
-                block_executor_onchain_config,
-            )
-            .await;
+        // A block always has the same parent, so a speculative result can be reused as is.
+        let speculative_result = {
+            let mut speculative_executions = self.speculative_executions.lock();
+            let speculative_result = speculative_executions.pending.remove(&block_id);
+            if speculative_result.is_none() && speculative_executions.queued.contains_key(&block_id)
+            {
+                // The block is executed again, e.g. after its execution failed.
+                speculative_executions.discard_descendants(block_id);
+            }
+            speculative_result
+        };
+        let fut = match speculative_result {
+            Some(fut) => {
+                counters::SPECULATIVE_EXECUTION_COUNT
+                    .with_label_values(&["reused"])
+                    .inc();
+                fut
+            },
+            None => {
+                self.queue_block(block, parent_block_id, randomness, state)
+                    .await
+            },
+        };
+        self.speculative_executions.lock().queue(block);
 
         Box::pin(async move {
             debug!(
@@ -254,6 +440,7 @@ impl StateComputer for ExecutionProxy {
             finality_proof.ledger_info().round(),
         );
         let block_timestamp = finality_proof.commit_info().timestamp_usecs();
+        let committed_block_id = finality_proof.commit_info().id();
 
         let MutableState {
             payload_manager,
@@ -301,6 +488,7 @@ impl StateComputer for ExecutionProxy {
             .expect("Failed to send async state sync notification");
 
         *latest_logical_time = logical_time;
+        self.speculative_executions.lock().prune(committed_block_id);
         payload_manager.notify_commit(block_timestamp, payloads);
         Ok(())
     }
@@ -315,6 +503,7 @@ impl StateComputer for ExecutionProxy {
         // Before the state synchronization, we have to call finish() to free the in-memory SMT
         // held by BlockExecutor to prevent memory leak.
         self.executor.finish();
+        self.speculative_executions.lock().clear();
 
         // The pipeline phase already committed beyond the target block timestamp, just return.
         if *latest_logical_time >= logical_time {
@@ -379,12 +568,14 @@ impl StateComputer for ExecutionProxy {
             transaction_deduper,
             is_randomness_enabled: randomness_enabled,
         });
+        self.speculative_executions.lock().clear();
     }
 
     // Clears the epoch-specific state. Only a sync_to call is expected before calling new_epoch
     // on the next epoch.
     fn end_epoch(&self) {
         self.state.write().take();
+        self.speculative_executions.lock().clear();
     }
 }
 
@@ -397,7 +588,6 @@ async fn test_commit_sync_race() {
     use aptos_config::config::transaction_filter_type::Filter;
     use aptos_consensus_notifications::Error;
     use aptos_executor_types::state_checkpoint_output::StateCheckpointOutput;
-    use aptos_infallible::Mutex;
     use aptos_types::{
         aggregate_signature::AggregateSignature,
         block_executor::partitioner::ExecutableBlock,
@@ -519,6 +709,7 @@ async fn test_commit_sync_race() {
         recorded_commit.clone(),
         &tokio::runtime::Handle::current(),
         TransactionFilter::new(Filter::empty()),
+        None,
     );
 
     executor.new_epoch(
@@ -8,7 +8,12 @@ use crate::{
 };
 use aptos_config::config::transaction_filter_type::Filter;
 use aptos_consensus_notifications::{ConsensusNotificationSender, Error};
-use aptos_consensus_types::{block::Block, block_data::BlockData, pipelined_block::PipelinedBlock};
+use aptos_consensus_types::{
+    block::{block_test_utils::placeholder_certificate_for_block, Block},
+    block_data::BlockData,
+    common::{Author, Payload, Round},
+    pipelined_block::PipelinedBlock,
+};
 use aptos_crypto::HashValue;
 use aptos_executor_types::{
     state_checkpoint_output::StateCheckpointOutput, BlockExecutorTrait, ExecutorResult,
@@ -22,10 +27,11 @@ use aptos_types::{
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     transaction::{ExecutionStatus, SignedTransaction, Transaction, TransactionStatus},
+    validator_signer::ValidatorSigner,
     validator_txn::ValidatorTransaction,
 };
 use futures_channel::oneshot;
-use std::sync::Arc;
+use std::sync::{Arc, Condvar, Mutex as StdMutex};
 use tokio::runtime::Handle;
 
 struct DummyStateSyncNotifier {
@@ -73,14 +79,34 @@ impl TxnNotifier for DummyTxnNotifier {
 
 struct DummyBlockExecutor {
     blocks_received: Mutex<Vec<ExecutableBlock>>,
+    // Blocks wait for the execution to be resumed while it is paused.
+    paused: (StdMutex<bool>, Condvar),
 }
 
 impl DummyBlockExecutor {
     fn new() -> Self {
         Self {
             blocks_received: Mutex::new(vec![]),
+            paused: (StdMutex::new(false), Condvar::new()),
         }
     }
+
+    fn pause(&self) {
+        *self.paused.0.lock().unwrap() = true;
+    }
+
+    fn resume(&self) {
+        *self.paused.0.lock().unwrap() = false;
+        self.paused.1.notify_all();
+    }
+
+    fn executed_block_ids(&self) -> Vec<HashValue> {
+        self.blocks_received
+            .lock()
+            .iter()
+            .map(|executable_block| executable_block.block_id)
+            .collect()
+    }
 }
 
 impl BlockExecutorTrait for DummyBlockExecutor {
@@ -107,6 +133,12 @@ impl BlockExecutorTrait for DummyBlockExecutor {
         _parent_block_id: HashValue,
         _onchain_config: BlockExecutorConfigFromOnchain,
     ) -> ExecutorResult<StateCheckpointOutput> {
+        let (paused, resumed) = &self.paused;
+        drop(
+            resumed
+                .wait_while(paused.lock().unwrap(), |paused| *paused)
+                .unwrap(),
+        );
         self.blocks_received.lock().push(block);
         Ok(StateCheckpointOutput::default())
     }
@@ -143,6 +175,7 @@ async fn schedule_compute_should_discover_validator_txns() {
         Arc::new(DummyStateSyncNotifier::new()),
         &Handle::current(),
         TransactionFilter::new(Filter::empty()),
+        None,
     );
 
     let validator_txn_0 = ValidatorTransaction::dummy(vec![0xFF; 99]);
@@ -196,6 +229,7 @@ async fn commit_should_discover_validator_txns() {
         state_sync_notifier.clone(),
         &tokio::runtime::Handle::current(),
         TransactionFilter::new(Filter::empty()),
+        None,
     );
 
     let validator_txn_0 = ValidatorTransaction::dummy(vec![0xFF; 99]);
@@ -261,3 +295,241 @@ async fn commit_should_discover_validator_txns() {
     assert_eq!(&validator_txn_0, supposed_validator_txn_0);
     assert_eq!(&validator_txn_1, supposed_validator_txn_1);
 }
+
+#[tokio::test]
+async fn schedule_compute_should_reuse_speculative_execution() {
+    let executor = Arc::new(DummyBlockExecutor::new());
+
+    let execution_policy = ExecutionProxy::new(
+        executor.clone(),
+        Arc::new(DummyTxnNotifier {}),
+        Arc::new(DummyStateSyncNotifier::new()),
+        &Handle::current(),
+        TransactionFilter::new(Filter::empty()),
+        None,
+    );
+    execution_policy.new_epoch(
+        &EpochState::empty(),
+        Arc::new(PayloadManager::DirectMempool),
+        Arc::new(NoOpShuffler {}),
+        BlockExecutorConfigFromOnchain::new_no_block_limit(),
+        Arc::new(NoOpDeduper {}),
+        false,
+    );
+
+    let block = Block::new_for_testing(
+        HashValue::random(),
+        BlockData::dummy_with_validator_txns(vec![]),
+        None,
+    );
+    let parent = Block::new_for_testing(
+        block.parent_id(),
+        BlockData::dummy_with_validator_txns(vec![]),
+        None,
+    );
+
+    // The parent is not queued for execution yet, so the block is not executed speculatively.
+    execution_policy.speculate(&block).await;
+
+    let parent_fut = execution_policy
+        .schedule_compute(&parent, HashValue::zero(), None)
+        .await;
+    execution_policy.speculate(&block).await;
+    let block_fut = execution_policy
+        .schedule_compute(&block, parent.id(), None)
+        .await;
+    assert!(parent_fut.await.is_ok());
+    assert!(block_fut.await.is_ok());
+
+    // Each block is executed once.
+    let executed_block_ids: Vec<_> = executor
+        .blocks_received
+        .lock()
+        .iter()
+        .map(|executable_block| executable_block.block_id)
+        .collect();
+    assert_eq!(executed_block_ids, vec![parent.id(), block.id()]);
+}
+
+fn create_execution_proxy(executor: Arc<DummyBlockExecutor>) -> ExecutionProxy {
+    let execution_proxy = ExecutionProxy::new(
+        executor,
+        Arc::new(DummyTxnNotifier {}),
+        Arc::new(DummyStateSyncNotifier::new()),
+        &Handle::current(),
+        TransactionFilter::new(Filter::empty()),
+        None,
+    );
+    execution_proxy.new_epoch(
+        &EpochState::empty(),
+        Arc::new(PayloadManager::DirectMempool),
+        Arc::new(NoOpShuffler {}),
+        BlockExecutorConfigFromOnchain::new_no_block_limit(),
+        Arc::new(NoOpDeduper {}),
+        false,
+    );
+    execution_proxy
+}
+
+fn create_child_block(parent: &Block, round: Round) -> Block {
+    let quorum_cert = placeholder_certificate_for_block(
+        &[ValidatorSigner::random(None)],
+        parent.id(),
+        parent.round(),
+        parent.parent_id(),
+        parent.round().saturating_sub(1),
+    );
+    Block::new_for_testing(
+        HashValue::random(),
+        BlockData::new_proposal(
+            Payload::empty(false),
+            Author::ONE,
+            vec![],
+            round,
+            round,
+            quorum_cert,
+        ),
+        None,
+    )
+}
+
+#[tokio::test]
+async fn speculative_execution_should_be_discarded_on_fork() {
+    let executor = Arc::new(DummyBlockExecutor::new());
+    let execution_policy = create_execution_proxy(executor.clone());
+
+    let genesis = Block::new_for_testing(
+        HashValue::random(),
+        BlockData::dummy_with_validator_txns(vec![]),
+        None,
+    );
+    let parent = create_child_block(&genesis, 2);
+    let block = create_child_block(&parent, 3);
+    let fork = create_child_block(&parent, 4);
+    let fork_child = create_child_block(&fork, 5);
+
+    // Keep the speculative blocks queued behind the parent.
+    executor.pause();
+    let parent_fut = execution_policy
+        .schedule_compute(&parent, parent.parent_id(), None)
+        .await;
+    for speculative_block in [&block, &fork, &fork_child] {
+        execution_policy.speculate(speculative_block).await;
+    }
+
+    // Committing the block makes the fork unreachable.
+    let _ = execution_policy
+        .commit(
+            &[Arc::new(PipelinedBlock::new(
+                block.clone(),
+                vec![],
+                StateComputeResult::new_dummy_with_compute_status(vec![
+                    TransactionStatus::Keep(
+                        ExecutionStatus::Success
+                    );
+                    1
+                ]),
+            ))],
+            LedgerInfoWithSignatures::new(
+                LedgerInfo::new(
+                    block.gen_block_info(HashValue::zero(), 0, None),
+                    HashValue::zero(),
+                ),
+                AggregateSignature::empty(),
+            ),
+            Box::new(|_: &[Arc<PipelinedBlock>], _: LedgerInfoWithSignatures| {}),
+        )
+        .await;
+    executor.resume();
+
+    let block_fut = execution_policy
+        .schedule_compute(&block, parent.id(), None)
+        .await;
+    assert!(parent_fut.await.is_ok());
+    assert!(block_fut.await.is_ok());
+
+    // The fork is never executed.
+    assert_eq!(executor.executed_block_ids(), vec![parent.id(), block.id()]);
+}
+
+#[tokio::test]
+async fn speculative_execution_should_be_discarded_on_reset() {
+    let executor = Arc::new(DummyBlockExecutor::new());
+    let execution_policy = create_execution_proxy(executor.clone());
+
+    let genesis = Block::new_for_testing(
+        HashValue::random(),
+        BlockData::dummy_with_validator_txns(vec![]),
+        None,
+    );
+    let parent = create_child_block(&genesis, 2);
+    let block = create_child_block(&parent, 3);
+
+    executor.pause();
+    let parent_fut = execution_policy
+        .schedule_compute(&parent, parent.parent_id(), None)
+        .await;
+    execution_policy.speculate(&block).await;
+
+    // Syncing to an already committed target only resets the executor.
+    execution_policy
+        .sync_to(LedgerInfoWithSignatures::new(
+            LedgerInfo::dummy(),
+            AggregateSignature::empty(),
+        ))
+        .await
+        .unwrap();
+    executor.resume();
+    assert!(parent_fut.await.is_ok());
+
+    // The speculative result was dropped, so the block is executed again once ordered.
+    let block_fut = execution_policy
+        .schedule_compute(&block, parent.id(), None)
+        .await;
+    assert!(block_fut.await.is_ok());
+    assert_eq!(executor.executed_block_ids(), vec![parent.id(), block.id()]);
+}
+
+#[tokio::test]
+async fn speculative_execution_should_be_discarded_on_retry() {
+    let executor = Arc::new(DummyBlockExecutor::new());
+    let execution_policy = create_execution_proxy(executor.clone());
+
+    let genesis = Block::new_for_testing(
+        HashValue::random(),
+        BlockData::dummy_with_validator_txns(vec![]),
+        None,
+    );
+    let parent = create_child_block(&genesis, 2);
+    let block = create_child_block(&parent, 3);
+    let child = create_child_block(&block, 4);
+
+    executor.pause();
+    let parent_fut = execution_policy
+        .schedule_compute(&parent, parent.parent_id(), None)
+        .await;
+    execution_policy.speculate(&block).await;
+    execution_policy.speculate(&child).await;
+
+    // The speculative result of the block is reused, then the block is executed again, so the
+    // result of the child, which was executed on top of the previous execution, is dropped.
+    let block_fut = execution_policy
+        .schedule_compute(&block, parent.id(), None)
+        .await;
+    let retried_block_fut = execution_policy
+        .schedule_compute(&block, parent.id(), None)
+        .await;
+    let child_fut = execution_policy
+        .schedule_compute(&child, block.id(), None)
+        .await;
+    executor.resume();
+
+    assert!(parent_fut.await.is_ok());
+    assert!(block_fut.await.is_ok());
+    assert!(retried_block_fut.await.is_ok());
+    assert!(child_fut.await.is_ok());
+    assert_eq!(
+        executor.executed_block_ids(),
+        vec![parent.id(), block.id(), block.id(), child.id()]
+    );
+}
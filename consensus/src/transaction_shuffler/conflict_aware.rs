@@ -0,0 +1,429 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::transaction_shuffler::TransactionShuffler;
+use aptos_types::{
+    block_executor::conflict_history::ConflictHistory,
+    state_store::state_key::StateKey,
+    transaction::{
+        analyzed_transaction::{
+            account_resource_location, try_get_read_write_hints, StorageLocation,
+        },
+        SignedTransaction, TransactionPayload,
+    },
+};
+use move_core_types::language_storage::ModuleId;
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
+
+/// Read hints with at least this many (decayed) conflicts per block in the conflict history
+/// are considered to be written by some transaction in the block.
+const HOT_KEY_CONFLICT_SCORE: f64 = 1.0;
+
+type TxnIdx = usize;
+
+#[derive(Eq, Hash, PartialEq)]
+enum ConflictAwareKey {
+    State(StateKey),
+    // Entry function module of a transaction without read/write hints
+    Module(ModuleId),
+}
+
+/// An implementation of transaction shuffler, which clusters transactions that are expected to
+/// conflict during execution and spreads each cluster evenly across the block, so that conflicting
+/// transactions are separated by as much non-conflicting work as possible.
+///
+/// Conflicts are derived from the read/write hints of `AnalyzedTransaction`. Two transactions
+/// conflict if they access the same key and at least one of them writes it, and clusters are the
+/// connected components of the conflict graph. As the hints are only available for a few framework
+/// entry functions, transactions without hints are assumed to write their sender's account and,
+/// unless it is a framework module, the module of their entry function. This way, all transactions
+/// calling into a popular contract, i.e. a DEX pool, form a single cluster.
+///
+/// The i-th transaction of a cluster of size n is placed at the relative position (2i + 1) / 2n in
+/// the block, and transactions not conflicting with any other one are spread the same way, as if
+/// they were a cluster of their own. Ties are broken by the original order. This maintains the
+/// following invariants:
+/// 1. Relative ordering of all transactions in the same cluster is preserved, which includes all
+/// transactions from the same sender.
+/// 2. If no transactions conflict, or all of them do, the ordering is unchanged.
+///
+/// Optionally, a `ConflictHistory` of the recently executed blocks can be provided, in which case
+/// read hints that have been frequently conflicting are treated as writes, accounting for writers
+/// that have no hints. As the history depends on the blocks executed locally, the resulting order
+/// is not deterministic across nodes, and the history must not be used to order blocks in consensus.
+pub struct ConflictAwareShuffler {
+    conflict_history: Option<Arc<ConflictHistory>>,
+}
+
+impl ConflictAwareShuffler {
+    pub fn new() -> Self {
+        Self {
+            conflict_history: None,
+        }
+    }
+
+    pub fn with_conflict_history(conflict_history: Arc<ConflictHistory>) -> Self {
+        Self {
+            conflict_history: Some(conflict_history),
+        }
+    }
+
+    fn is_hot(&self, state_key: &StateKey) -> bool {
+        self.conflict_history.as_ref().map_or(false, |history| {
+            history.conflict_score(ConflictHistory::key_hash(state_key)) >= HOT_KEY_CONFLICT_SCORE
+        })
+    }
+
+    /// Returns the keys read and written by the transaction
+    fn conflict_keys(
+        &self,
+        txn: &SignedTransaction,
+    ) -> (Vec<ConflictAwareKey>, Vec<ConflictAwareKey>) {
+        let mut reads = vec![];
+        let mut writes = vec![ConflictAwareKey::State(
+            account_resource_location(txn.sender()).into_state_key(),
+        )];
+
+        match try_get_read_write_hints(txn) {
+            Some((read_hints, write_hints)) => {
+                for hint in write_hints {
+                    if let StorageLocation::Specific(state_key) = hint {
+                        writes.push(ConflictAwareKey::State(state_key));
+                    }
+                }
+                for hint in read_hints {
+                    if let StorageLocation::Specific(state_key) = hint {
+                        if self.is_hot(&state_key) {
+                            writes.push(ConflictAwareKey::State(state_key));
+                        } else {
+                            reads.push(ConflictAwareKey::State(state_key));
+                        }
+                    }
+                }
+            },
+            None => {
+                if let TransactionPayload::EntryFunction(entry_fun) = txn.payload() {
+                    if !entry_fun.module().address().is_special() {
+                        writes.push(ConflictAwareKey::Module(entry_fun.module().clone()));
+                    }
+                }
+            },
+        }
+        (reads, writes)
+    }
+
+    /// Returns the cluster of each transaction, identified by its first transaction
+    fn clusters(&self, txns: &[SignedTransaction]) -> Vec<TxnIdx> {
+        // For each key, the transactions accessing it and whether any of them writes it
+        let mut accesses: HashMap<ConflictAwareKey, (Vec<TxnIdx>, bool)> = HashMap::new();
+        for (txn_idx, txn) in txns.iter().enumerate() {
+            let (reads, writes) = self.conflict_keys(txn);
+            for (key, is_write) in reads
+                .into_iter()
+                .map(|key| (key, false))
+                .chain(writes.into_iter().map(|key| (key, true)))
+            {
+                let (accessors, written) = accesses.entry(key).or_default();
+                if accessors.last() != Some(&txn_idx) {
+                    accessors.push(txn_idx);
+                }
+                *written |= is_write;
+            }
+        }
+
+        let mut clusters = UnionFind::new(txns.len());
+        for (accessors, written) in accesses.values() {
+            if *written {
+                for txn_idx in &accessors[1..] {
+                    clusters.union(accessors[0], *txn_idx);
+                }
+            }
+        }
+        (0..txns.len()).map(|idx| clusters.find(idx)).collect()
+    }
+
+    fn shuffled_order(&self, txns: &[SignedTransaction]) -> Vec<TxnIdx> {
+        let clusters = self.clusters(txns);
+        let mut cluster_sizes: HashMap<TxnIdx, usize> = HashMap::new();
+        for cluster in &clusters {
+            *cluster_sizes.entry(*cluster).or_default() += 1;
+        }
+
+        // Transactions without conflicts are spread as a single cluster
+        let num_unconflicted = cluster_sizes.values().filter(|size| **size == 1).count();
+        let mut num_placed: HashMap<Option<TxnIdx>, usize> = HashMap::new();
+        let positions: Vec<(usize, usize)> = clusters
+            .iter()
+            .map(|cluster| {
+                let (group, group_size) = match cluster_sizes[cluster] {
+                    1 => (None, num_unconflicted),
+                    size => (Some(*cluster), size),
+                };
+                let rank = num_placed.entry(group).or_default();
+                *rank += 1;
+                (2 * *rank - 1, 2 * group_size)
+            })
+            .collect();
+
+        let mut order: Vec<TxnIdx> = (0..txns.len()).collect();
+        order.sort_by(|a, b| {
+            let (numerator_a, denominator_a) = positions[*a];
+            let (numerator_b, denominator_b) = positions[*b];
+            match (numerator_a as u128 * denominator_b as u128)
+                .cmp(&(numerator_b as u128 * denominator_a as u128))
+            {
+                Ordering::Equal => a.cmp(b),
+                ordering => ordering,
+            }
+        });
+        order
+    }
+}
+
+impl Default for ConflictAwareShuffler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransactionShuffler for ConflictAwareShuffler {
+    fn shuffle(&self, txns: Vec<SignedTransaction>) -> Vec<SignedTransaction> {
+        // Early return for performance reason if there are no transactions to shuffle
+        if txns.len() <= 1 {
+            return txns;
+        }
+
+        let order = self.shuffled_order(&txns);
+        let mut txns: Vec<Option<SignedTransaction>> = txns.into_iter().map(Some).collect();
+        order
+            .into_iter()
+            .map(|idx| txns[idx].take().expect("Each transaction is placed once"))
+            .collect()
+    }
+}
+
+/// Union-find over transaction indices, where the root of each set is its smallest index
+struct UnionFind {
+    parents: Vec<TxnIdx>,
+}
+
+impl UnionFind {
+    fn new(num_txns: usize) -> Self {
+        Self {
+            parents: (0..num_txns).collect(),
+        }
+    }
+
+    fn find(&mut self, mut idx: TxnIdx) -> TxnIdx {
+        while self.parents[idx] != idx {
+            self.parents[idx] = self.parents[self.parents[idx]];
+            idx = self.parents[idx];
+        }
+        idx
+    }
+
+    fn union(&mut self, a: TxnIdx, b: TxnIdx) {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a < root_b {
+            self.parents[root_b] = root_a;
+        } else {
+            self.parents[root_a] = root_b;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction_shuffler::{conflict_aware::ConflictAwareShuffler, TransactionShuffler};
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, SigningKey, Uniform};
+    use aptos_types::{
+        block_executor::conflict_history::ConflictHistory,
+        chain_id::ChainId,
+        transaction::{
+            analyzed_transaction::aptos_coin_info_location, EntryFunction, RawTransaction,
+            SignedTransaction, TransactionPayload,
+        },
+    };
+    use move_core_types::{
+        account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+    };
+    use rand::{rngs::OsRng, Rng};
+    use std::{collections::HashMap, sync::Arc};
+
+    fn entry_fun_txn(
+        sender: AccountAddress,
+        sequence_number: u64,
+        module: (AccountAddress, &str),
+        function: &str,
+        args: Vec<Vec<u8>>,
+    ) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let raw_transaction = RawTransaction::new(
+            sender,
+            sequence_number,
+            TransactionPayload::EntryFunction(EntryFunction::new(
+                ModuleId::new(module.0, Identifier::new(module.1).unwrap()),
+                Identifier::new(function).unwrap(),
+                vec![],
+                args,
+            )),
+            0,
+            0,
+            0,
+            ChainId::new(10),
+        );
+        SignedTransaction::new(
+            raw_transaction.clone(),
+            private_key.public_key(),
+            private_key.sign(&raw_transaction).unwrap(),
+        )
+    }
+
+    fn transfer_txn(sender: AccountAddress, receiver: AccountAddress) -> SignedTransaction {
+        entry_fun_txn(
+            sender,
+            0,
+            (AccountAddress::ONE, "aptos_account"),
+            "transfer",
+            vec![
+                bcs::to_bytes(&receiver).unwrap(),
+                bcs::to_bytes(&1u64).unwrap(),
+            ],
+        )
+    }
+
+    fn swap_txn(sender: AccountAddress, sequence_number: u64) -> SignedTransaction {
+        entry_fun_txn(
+            sender,
+            sequence_number,
+            (AccountAddress::from_hex_literal("0xcafe").unwrap(), "dex"),
+            "swap",
+            vec![],
+        )
+    }
+
+    fn independent_transfers(num_txns: usize) -> Vec<SignedTransaction> {
+        (0..num_txns)
+            .map(|_| transfer_txn(AccountAddress::random(), AccountAddress::random()))
+            .collect()
+    }
+
+    fn assert_order(
+        shuffler: &ConflictAwareShuffler,
+        txns: &[SignedTransaction],
+        expected_order: &[usize],
+    ) {
+        let shuffled_txns = shuffler.shuffle(txns.to_vec());
+        let expected_txns: Vec<SignedTransaction> = expected_order
+            .iter()
+            .map(|idx| txns[*idx].clone())
+            .collect();
+        assert_eq!(shuffled_txns, expected_txns);
+    }
+
+    #[test]
+    fn test_no_conflicts() {
+        let txns = independent_transfers(10);
+        assert_order(
+            &ConflictAwareShuffler::new(),
+            &txns,
+            &(0..10).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_full_conflict() {
+        let receiver = AccountAddress::random();
+        let txns: Vec<SignedTransaction> = (0..10)
+            .map(|_| transfer_txn(AccountAddress::random(), receiver))
+            .collect();
+        assert_order(
+            &ConflictAwareShuffler::new(),
+            &txns,
+            &(0..10).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_hot_spot_spread() {
+        // [S0, S1, S2, S3, T0, T1, T2, T3], where swaps S* all call into the same module
+        let mut txns: Vec<SignedTransaction> = (0..4)
+            .map(|_| swap_txn(AccountAddress::random(), 0))
+            .collect();
+        txns.extend(independent_transfers(4));
+        assert_order(
+            &ConflictAwareShuffler::new(),
+            &txns,
+            &[0, 4, 1, 5, 2, 6, 3, 7],
+        );
+    }
+
+    #[test]
+    fn test_multiple_clusters() {
+        // [S0, S1, S2, S3, R0, R1, T0], where transfers R* have the same receiver
+        let receiver = AccountAddress::random();
+        let mut txns: Vec<SignedTransaction> = (0..4)
+            .map(|_| swap_txn(AccountAddress::random(), 0))
+            .collect();
+        txns.extend((0..2).map(|_| transfer_txn(AccountAddress::random(), receiver)));
+        txns.extend(independent_transfers(1));
+        // Positions: S* at 1/8, 3/8, 5/8, 7/8, R* at 1/4, 3/4 and T0 at 1/2
+        assert_order(&ConflictAwareShuffler::new(), &txns, &[0, 4, 1, 6, 2, 5, 3]);
+    }
+
+    #[test]
+    fn test_conflict_history() {
+        // [T0, T1, T2, T3, S0], where all transfers read the coin info
+        let mut txns = independent_transfers(4);
+        txns.push(swap_txn(AccountAddress::random(), 0));
+        assert_order(
+            &ConflictAwareShuffler::new(),
+            &txns,
+            &(0..5).collect::<Vec<_>>(),
+        );
+
+        // Once the coin info is known to be contended, the transfers form a cluster
+        let conflict_history = Arc::new(ConflictHistory::new());
+        let coin_info_hash = ConflictHistory::key_hash(aptos_coin_info_location().state_key());
+        conflict_history.record_block(&[coin_info_hash, coin_info_hash]);
+        assert_order(
+            &ConflictAwareShuffler::with_conflict_history(conflict_history),
+            &txns,
+            &[0, 1, 4, 2, 3],
+        );
+    }
+
+    #[test]
+    fn test_sender_order_preserved() {
+        let mut rng = OsRng;
+        let senders: Vec<AccountAddress> = (0..10).map(|_| AccountAddress::random()).collect();
+        let mut sequence_numbers: HashMap<AccountAddress, u64> = HashMap::new();
+        let txns: Vec<SignedTransaction> = (0..200)
+            .map(|_| {
+                let sender = senders[rng.gen_range(0, senders.len())];
+                let sequence_number = sequence_numbers.entry(sender).or_default();
+                *sequence_number += 1;
+                if rng.gen_bool(0.5) {
+                    swap_txn(sender, *sequence_number)
+                } else {
+                    entry_fun_txn(
+                        sender,
+                        *sequence_number,
+                        (AccountAddress::random(), "module"),
+                        "function",
+                        vec![],
+                    )
+                }
+            })
+            .collect();
+
+        let shuffled_txns = ConflictAwareShuffler::new().shuffle(txns.clone());
+        assert_eq!(shuffled_txns.len(), txns.len());
+        let mut last_sequence_numbers: HashMap<AccountAddress, u64> = HashMap::new();
+        for txn in shuffled_txns {
+            let last_sequence_number = last_sequence_numbers.entry(txn.sender()).or_default();
+            assert!(txn.sequence_number() > *last_sequence_number);
+            *last_sequence_number = txn.sequence_number();
+        }
+    }
+}
@@ -5,13 +5,17 @@ use aptos_logger::info;
 use aptos_types::{
     on_chain_config::{
         TransactionShufflerType,
-        TransactionShufflerType::{DeprecatedSenderAwareV1, NoShuffling, SenderAwareV2},
+        TransactionShufflerType::{
+            ConflictAware, DeprecatedSenderAwareV1, NoShuffling, SenderAwareV2,
+        },
     },
     transaction::SignedTransaction,
 };
+pub use conflict_aware::ConflictAwareShuffler;
 use sender_aware::SenderAwareShuffler;
 use std::sync::Arc;
 
+mod conflict_aware;
 mod fairness;
 mod sender_aware;
 
@@ -65,5 +69,11 @@ pub fn create_transaction_shuffler(
                 entry_fun_conflict_window_size: entry_fun_conflict_window_size as usize,
             })
         },
+        ConflictAware => {
+            info!("Using conflict aware transaction shuffling");
+            // The conflict history is local to each node, so it can't be used to order blocks
+            // in consensus, which needs to be deterministic.
+            Arc::new(ConflictAwareShuffler::new())
+        },
     }
 }
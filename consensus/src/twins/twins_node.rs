@@ -162,6 +162,7 @@ impl SMRNode {
             bounded_executor,
             aptos_time_service::TimeService::real(),
             vtxn_pool,
+            None,
         );
         let (network_task, network_receiver) =
             NetworkTask::new(network_service_events, self_receiver);
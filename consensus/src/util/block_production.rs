@@ -0,0 +1,151 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Control over when this node proposes blocks and over the on-chain time, which lets a local
+//! test network pause block production, produce blocks on demand and skip through time.  The
+//! [`BlockProductionControl`] is handed to consensus when the node starts, and is only used when
+//! `ConsensusConfig::enable_block_production_control` is set.  Blocks are produced as usual
+//! unless paused.
+//!
+//! A block is committed once the block after it is certified, so the last block produced
+//! while paused only gets committed by the next produced block.
+
+use aptos_infallible::{duration_since_epoch, Mutex};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Notify;
+
+/// The state of block production, as reported to the controller
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct BlockProductionStatus {
+    /// Whether blocks are only produced on demand
+    pub paused: bool,
+    /// Number of blocks requested while paused, that are yet to be proposed
+    pub pending_blocks: u64,
+    /// Number of blocks proposed by this node since it started
+    pub proposed_blocks: u64,
+}
+
+/// A move of the on-chain time, which applies to the blocks with a later timestamp
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TimeAdvance {
+    /// Time of the advance, no block proposed before it has a later timestamp
+    pub after_usecs: u64,
+    pub by_usecs: u64,
+}
+
+/// Handle shared by consensus and the controller of block production, e.g. the admin server of
+/// a local test network.  It outlives the node, so that a restarted node carries on with the
+/// same state.
+#[derive(Clone, Debug, Default)]
+pub struct BlockProductionControl {
+    inner: Arc<BlockProduction>,
+}
+
+#[derive(Debug, Default)]
+struct BlockProduction {
+    status: Mutex<BlockProductionStatus>,
+    notify: Notify,
+    time_advances: Mutex<Vec<TimeAdvance>>,
+}
+
+impl BlockProductionControl {
+    /// Stops proposing blocks, except for the ones requested with [`Self::produce_blocks`].
+    pub fn pause(&self) {
+        let mut status = self.inner.status.lock();
+        status.paused = true;
+    }
+
+    /// Proposes blocks as usual again, dropping any blocks still pending.
+    pub fn resume(&self) {
+        let mut status = self.inner.status.lock();
+        status.paused = false;
+        status.pending_blocks = 0;
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Requests `count` more blocks while block production is paused.
+    pub fn produce_blocks(&self, count: u64) {
+        let mut status = self.inner.status.lock();
+        status.pending_blocks = status.pending_blocks.saturating_add(count);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn status(&self) -> BlockProductionStatus {
+        *self.inner.status.lock()
+    }
+
+    /// Takes the turn to propose a block, returns false if block production is paused and no
+    /// block is pending.  The proposer calls this before each proposal.
+    pub fn try_take_turn(&self) -> bool {
+        let mut status = self.inner.status.lock();
+        if status.paused {
+            if status.pending_blocks == 0 {
+                return false;
+            }
+            status.pending_blocks -= 1;
+        }
+        status.proposed_blocks += 1;
+        true
+    }
+
+    /// Waits until a block may be proposed, and takes the turn to propose it.
+    pub(crate) async fn wait_for_turn(&self) {
+        loop {
+            // Created before checking, so that a request made in between isn't missed
+            let notified = self.inner.notify.notified();
+            if self.try_take_turn() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Moves the on-chain time forward by `duration`, from the next proposed block on.  The
+    /// time of consensus itself is left alone, only the timestamp the blocks are executed with
+    /// moves.
+    pub fn advance_time(&self, duration: Duration) -> TimeAdvance {
+        let advance = TimeAdvance {
+            after_usecs: duration_since_epoch().as_micros() as u64,
+            by_usecs: duration.as_micros() as u64,
+        };
+        self.inner.time_advances.lock().push(advance);
+        advance
+    }
+
+    /// Gives the time advances so far, oldest first.
+    pub fn time_advances(&self) -> Vec<TimeAdvance> {
+        self.inner.time_advances.lock().clone()
+    }
+
+    /// Restores the time advances of a previous run, which must happen before blocks are
+    /// executed, so that blocks executed again on restart get the same timestamps.
+    pub fn restore_time_advances(&self, time_advances: Vec<TimeAdvance>) {
+        *self.inner.time_advances.lock() = time_advances;
+    }
+
+    /// Gives how far the on-chain time has been moved forward in total.
+    pub fn time_offset(&self) -> Duration {
+        Duration::from_micros(
+            self.inner
+                .time_advances
+                .lock()
+                .iter()
+                .map(|advance| advance.by_usecs)
+                .sum(),
+        )
+    }
+
+    /// Gives the on-chain timestamp of a block with the given timestamp.  This only depends on
+    /// the block, so that executing and committing a block agree.
+    pub(crate) fn block_timestamp_usecs(&self, timestamp_usecs: u64) -> u64 {
+        self.inner
+            .time_advances
+            .lock()
+            .iter()
+            .filter(|advance| advance.after_usecs < timestamp_usecs)
+            .fold(timestamp_usecs, |timestamp_usecs, advance| {
+                timestamp_usecs + advance.by_usecs
+            })
+    }
+}
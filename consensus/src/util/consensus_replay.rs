@@ -0,0 +1,539 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensusdb::ConsensusDB,
+    quorum_store::{
+        quorum_store_db::{QuorumStoreDB, QuorumStoreStorage},
+        types::PersistedValue,
+    },
+    util::db_tool::extract_txns_from_block,
+};
+use anyhow::Result;
+use aptos_consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    quorum_cert::QuorumCert,
+    timeout_2chain::TwoChainTimeoutCertificate,
+    vote::Vote,
+};
+use aptos_crypto::HashValue;
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    path::PathBuf,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Dot,
+    Html,
+}
+
+#[derive(Parser)]
+#[clap(about = "Replay the consensus db of a stopped node and export the block tree.")]
+pub struct Command {
+    #[clap(long, value_parser)]
+    pub db_dir: PathBuf,
+
+    #[clap(long, value_enum, default_value = "json")]
+    pub format: OutputFormat,
+
+    // If None, will print to stdout.
+    #[clap(long, value_parser)]
+    pub output: Option<PathBuf>,
+}
+
+impl Command {
+    pub async fn run(self) -> Result<()> {
+        let replay = self.load_replay()?;
+        let output = match self.format {
+            OutputFormat::Json => replay.to_json()?,
+            OutputFormat::Dot => replay.to_dot(),
+            OutputFormat::Html => replay.to_html(),
+        };
+
+        match &self.output {
+            Some(path) => std::fs::write(path, output)?,
+            None => println!("{output}"),
+        }
+
+        Ok(())
+    }
+
+    pub fn load_replay(&self) -> Result<ConsensusReplay> {
+        let quorum_store_db = QuorumStoreDB::new(self.db_dir.clone());
+        let all_batches = quorum_store_db.get_all_batches()?;
+
+        let consensus_db = ConsensusDB::new(self.db_dir.clone());
+        let (last_vote, highest_2chain_tc, blocks, qcs) = consensus_db.get_data()?;
+        let last_vote = last_vote
+            .map(|bytes| bcs::from_bytes::<Vote>(&bytes))
+            .transpose()?;
+        let highest_2chain_tc = highest_2chain_tc
+            .map(|bytes| bcs::from_bytes::<TwoChainTimeoutCertificate>(&bytes))
+            .transpose()?;
+
+        Ok(ConsensusReplay::new(
+            blocks,
+            qcs,
+            last_vote,
+            highest_2chain_tc,
+            Some(&all_batches),
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplayedBlock {
+    pub id: HashValue,
+    pub parent_id: HashValue,
+    pub epoch: u64,
+    pub round: Round,
+    pub author: Option<Author>,
+    pub timestamp_usecs: u64,
+    // None if the payload batches are missing from the quorum store db
+    pub num_txns: Option<usize>,
+    pub is_nil: bool,
+    pub children: Vec<HashValue>,
+    pub certified: bool,
+    pub committed: bool,
+    // Timestamp of the first block carrying a QC that committed this block
+    pub commit_timestamp_usecs: Option<u64>,
+    pub commit_latency_ms: Option<u64>,
+}
+
+impl ReplayedBlock {
+    pub fn is_fork(&self) -> bool {
+        self.children.len() > 1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundOutcome {
+    /// A block proposed in the round was certified
+    Certified,
+    /// A block was proposed in the round, but was never certified
+    NotCertified,
+    /// No proposal for the round was persisted
+    NoProposal,
+}
+
+impl RoundOutcome {
+    pub fn is_timeout(&self) -> bool {
+        *self != RoundOutcome::Certified
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RoundTransition {
+    pub epoch: u64,
+    pub round: Round,
+    pub proposals: Vec<HashValue>,
+    pub outcome: RoundOutcome,
+    // Proposers reported as failed for this round by later proposals
+    pub failed_authors: Vec<Author>,
+    // Whether the highest persisted timeout certificate is for this round
+    pub has_timeout_certificate: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReplaySummary {
+    pub num_blocks: usize,
+    pub num_committed_blocks: usize,
+    pub num_forks: usize,
+    pub num_timeout_rounds: usize,
+    pub highest_certified_round: Option<Round>,
+    pub highest_committed_round: Option<Round>,
+    pub last_voted_round: Option<Round>,
+    pub last_vote_is_timeout: bool,
+    pub highest_timeout_certificate_round: Option<Round>,
+    pub average_commit_latency_ms: Option<u64>,
+    pub max_commit_latency_ms: Option<u64>,
+}
+
+/// The block tree, round transitions and commit latencies reconstructed from
+/// the persisted consensus data of a node.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConsensusReplay {
+    pub summary: ReplaySummary,
+    pub rounds: Vec<RoundTransition>,
+    // Sorted by (epoch, round)
+    pub blocks: Vec<ReplayedBlock>,
+}
+
+impl ConsensusReplay {
+    pub fn new(
+        mut blocks: Vec<Block>,
+        qcs: Vec<QuorumCert>,
+        last_vote: Option<Vote>,
+        highest_2chain_tc: Option<TwoChainTimeoutCertificate>,
+        all_batches: Option<&HashMap<HashValue, PersistedValue>>,
+    ) -> Self {
+        blocks.sort_by_key(|block| (block.epoch(), block.round(), block.id()));
+        let indexes: HashMap<HashValue, usize> = blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (block.id(), index))
+            .collect();
+
+        let mut replayed_blocks: Vec<ReplayedBlock> = blocks
+            .iter()
+            .map(|block| ReplayedBlock {
+                id: block.id(),
+                parent_id: block.parent_id(),
+                epoch: block.epoch(),
+                round: block.round(),
+                author: block.author(),
+                timestamp_usecs: block.timestamp_usecs(),
+                num_txns: match all_batches {
+                    Some(all_batches) => extract_txns_from_block(block, all_batches)
+                        .ok()
+                        .map(|txns| txns.len()),
+                    None => Some(block.payload().map_or(0, |payload| payload.len())),
+                },
+                is_nil: block.is_nil_block(),
+                children: vec![],
+                certified: false,
+                committed: false,
+                commit_timestamp_usecs: None,
+                commit_latency_ms: None,
+            })
+            .collect();
+
+        // Link the children, blocks are already sorted so children are sorted by round
+        for block in &blocks {
+            if let Some(parent_index) = indexes.get(&block.parent_id()) {
+                replayed_blocks[*parent_index].children.push(block.id());
+            }
+        }
+
+        // Blocks are certified by the QCs carried by their descendants or persisted on their own
+        let all_qcs = blocks
+            .iter()
+            .map(|block| block.quorum_cert())
+            .chain(qcs.iter());
+        for qc in all_qcs {
+            if let Some(index) = indexes.get(&qc.certified_block().id()) {
+                replayed_blocks[*index].certified = true;
+            }
+        }
+
+        // The commit time of a block is approximated by the timestamp of the first
+        // block carrying a QC that commits it. Persisted QCs are only used to mark
+        // blocks as committed, as no block timestamp is known for them.
+        for block in &blocks {
+            let commit_id = block.quorum_cert().commit_info().id();
+            mark_committed(
+                &mut replayed_blocks,
+                &indexes,
+                commit_id,
+                Some(block.timestamp_usecs()),
+            );
+        }
+        for qc in &qcs {
+            mark_committed(&mut replayed_blocks, &indexes, qc.commit_info().id(), None);
+        }
+
+        let rounds = compute_round_transitions(&blocks, &replayed_blocks, &highest_2chain_tc);
+        let summary = compute_summary(&replayed_blocks, &rounds, &last_vote, &highest_2chain_tc);
+
+        Self {
+            summary,
+            rounds,
+            blocks: replayed_blocks,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Exports the block tree in the Graphviz DOT format. Committed blocks are
+    /// green, certified blocks are blue and blocks that never got certified are
+    /// grey. Blocks with multiple children (forks) are drawn with a double border.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph consensus {{").unwrap();
+        writeln!(dot, "  rankdir=LR;").unwrap();
+        writeln!(dot, "  node [shape=box, style=filled, fontname=monospace];").unwrap();
+
+        for block in &self.blocks {
+            let color = if block.committed {
+                "palegreen"
+            } else if block.certified {
+                "lightblue"
+            } else {
+                "lightgrey"
+            };
+            let mut label = format!(
+                "e{} r{}\\n{}\\nauthor: {}",
+                block.epoch,
+                block.round,
+                block.id,
+                block
+                    .author
+                    .map_or("none".to_string(), |author| author.short_str_lossless()),
+            );
+            if let Some(num_txns) = block.num_txns {
+                write!(label, "\\ntxns: {}", num_txns).unwrap();
+            }
+            if let Some(commit_latency_ms) = block.commit_latency_ms {
+                write!(label, "\\ncommit: {} ms", commit_latency_ms).unwrap();
+            }
+            writeln!(
+                dot,
+                "  \"{:x}\" [label=\"{}\", fillcolor={}, peripheries={}];",
+                block.id,
+                label,
+                color,
+                if block.is_fork() { 2 } else { 1 },
+            )
+            .unwrap();
+        }
+
+        for block in &self.blocks {
+            for child in &block.children {
+                writeln!(dot, "  \"{:x}\" -> \"{:x}\";", block.id, child).unwrap();
+            }
+        }
+
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    /// Exports a self-contained HTML report with the summary, the round timeline,
+    /// the blocks and the DOT source of the block tree.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>").unwrap();
+        writeln!(html, "<html><head><meta charset=\"utf-8\">").unwrap();
+        writeln!(html, "<title>Consensus replay</title>").unwrap();
+        writeln!(
+            html,
+            "<style>body {{ font-family: monospace; }} \
+             table {{ border-collapse: collapse; margin-bottom: 2em; }} \
+             td, th {{ border: 1px solid #ccc; padding: 2px 8px; }} \
+             .certified {{ background: #d4f7d4; }} \
+             .not_certified {{ background: #fde2c4; }} \
+             .no_proposal {{ background: #f7d4d4; }}</style>"
+        )
+        .unwrap();
+        writeln!(html, "</head><body>").unwrap();
+
+        writeln!(html, "<h2>Summary</h2><pre>").unwrap();
+        writeln!(
+            html,
+            "{}",
+            serde_json::to_string_pretty(&self.summary).unwrap_or_default()
+        )
+        .unwrap();
+        writeln!(html, "</pre>").unwrap();
+
+        writeln!(html, "<h2>Rounds</h2><table>").unwrap();
+        writeln!(
+            html,
+            "<tr><th>epoch</th><th>round</th><th>outcome</th><th>proposals</th>\
+             <th>failed authors</th><th>timeout certificate</th></tr>"
+        )
+        .unwrap();
+        for round in &self.rounds {
+            let outcome = serde_json::to_value(round.outcome)
+                .ok()
+                .and_then(|value| value.as_str().map(str::to_string))
+                .unwrap_or_default();
+            writeln!(
+                html,
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                outcome,
+                round.epoch,
+                round.round,
+                outcome,
+                join(round.proposals.iter().map(|id| id.to_string())),
+                join(round.failed_authors.iter().map(|a| a.short_str_lossless())),
+                round.has_timeout_certificate,
+            )
+            .unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+
+        writeln!(html, "<h2>Blocks</h2><table>").unwrap();
+        writeln!(
+            html,
+            "<tr><th>epoch</th><th>round</th><th>id</th><th>parent</th><th>author</th>\
+             <th>txns</th><th>certified</th><th>committed</th><th>commit latency (ms)</th>\
+             <th>children</th></tr>"
+        )
+        .unwrap();
+        for block in &self.blocks {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                block.epoch,
+                block.round,
+                block.id,
+                block.parent_id,
+                block
+                    .author
+                    .map_or(String::new(), |author| author.short_str_lossless()),
+                block
+                    .num_txns
+                    .map_or("missing".to_string(), |n| n.to_string()),
+                block.certified,
+                block.committed,
+                block
+                    .commit_latency_ms
+                    .map_or(String::new(), |latency| latency.to_string()),
+                block.children.len(),
+            )
+            .unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+
+        writeln!(
+            html,
+            "<h2>Block tree</h2><p>Render with <code>dot -Tsvg</code>.</p><pre>"
+        )
+        .unwrap();
+        write!(
+            html,
+            "{}",
+            self.to_dot().replace('<', "&lt;").replace('>', "&gt;")
+        )
+        .unwrap();
+        writeln!(html, "</pre></body></html>").unwrap();
+        html
+    }
+}
+
+/// Marks the given block and all of its uncommitted ancestors as committed
+fn mark_committed(
+    blocks: &mut [ReplayedBlock],
+    indexes: &HashMap<HashValue, usize>,
+    commit_id: HashValue,
+    commit_timestamp_usecs: Option<u64>,
+) {
+    let mut next_id = commit_id;
+    while let Some(index) = indexes.get(&next_id) {
+        let block = &mut blocks[*index];
+        if block.committed {
+            break;
+        }
+        block.committed = true;
+        block.commit_timestamp_usecs = commit_timestamp_usecs;
+        block.commit_latency_ms = commit_timestamp_usecs
+            .map(|timestamp| timestamp.saturating_sub(block.timestamp_usecs) / 1000);
+        next_id = block.parent_id;
+    }
+}
+
+fn compute_round_transitions(
+    blocks: &[Block],
+    replayed_blocks: &[ReplayedBlock],
+    highest_2chain_tc: &Option<TwoChainTimeoutCertificate>,
+) -> Vec<RoundTransition> {
+    // Round ranges and proposals per epoch
+    let mut round_ranges: BTreeMap<u64, (Round, Round)> = BTreeMap::new();
+    let mut proposals: HashMap<(u64, Round), Vec<&ReplayedBlock>> = HashMap::new();
+    for block in replayed_blocks {
+        let range = round_ranges
+            .entry(block.epoch)
+            .or_insert((block.round, block.round));
+        range.1 = range.1.max(block.round);
+        proposals
+            .entry((block.epoch, block.round))
+            .or_default()
+            .push(block);
+    }
+    if let Some(tc) = highest_2chain_tc {
+        if let Some(range) = round_ranges.get_mut(&tc.epoch()) {
+            range.1 = range.1.max(tc.round());
+        }
+    }
+
+    let mut failed_authors: HashMap<(u64, Round), Vec<Author>> = HashMap::new();
+    for block in blocks {
+        for (round, author) in block.block_data().failed_authors().into_iter().flatten() {
+            let authors = failed_authors.entry((block.epoch(), *round)).or_default();
+            if !authors.contains(author) {
+                authors.push(*author);
+            }
+        }
+    }
+
+    let mut rounds = vec![];
+    for (epoch, (first_round, last_round)) in round_ranges {
+        for round in first_round..=last_round {
+            let round_proposals = proposals.remove(&(epoch, round)).unwrap_or_default();
+            let outcome = if round_proposals.is_empty() {
+                RoundOutcome::NoProposal
+            } else if round_proposals.iter().any(|block| block.certified) {
+                RoundOutcome::Certified
+            } else {
+                RoundOutcome::NotCertified
+            };
+            rounds.push(RoundTransition {
+                epoch,
+                round,
+                proposals: round_proposals.iter().map(|block| block.id).collect(),
+                outcome,
+                failed_authors: failed_authors.remove(&(epoch, round)).unwrap_or_default(),
+                has_timeout_certificate: highest_2chain_tc
+                    .as_ref()
+                    .map_or(false, |tc| tc.epoch() == epoch && tc.round() == round),
+            });
+        }
+    }
+    rounds
+}
+
+fn compute_summary(
+    blocks: &[ReplayedBlock],
+    rounds: &[RoundTransition],
+    last_vote: &Option<Vote>,
+    highest_2chain_tc: &Option<TwoChainTimeoutCertificate>,
+) -> ReplaySummary {
+    let commit_latencies: Vec<u64> = blocks
+        .iter()
+        .filter_map(|block| block.commit_latency_ms)
+        .collect();
+    ReplaySummary {
+        num_blocks: blocks.len(),
+        num_committed_blocks: blocks.iter().filter(|block| block.committed).count(),
+        num_forks: blocks.iter().filter(|block| block.is_fork()).count(),
+        num_timeout_rounds: rounds
+            .iter()
+            .filter(|round| round.outcome.is_timeout())
+            .count(),
+        highest_certified_round: blocks
+            .iter()
+            .filter(|block| block.certified)
+            .map(|block| block.round)
+            .max(),
+        highest_committed_round: blocks
+            .iter()
+            .filter(|block| block.committed)
+            .map(|block| block.round)
+            .max(),
+        last_voted_round: last_vote
+            .as_ref()
+            .map(|vote| vote.vote_data().proposed().round()),
+        last_vote_is_timeout: last_vote.as_ref().map_or(false, |vote| vote.is_timeout()),
+        highest_timeout_certificate_round: highest_2chain_tc.as_ref().map(|tc| tc.round()),
+        average_commit_latency_ms: if commit_latencies.is_empty() {
+            None
+        } else {
+            Some(commit_latencies.iter().sum::<u64>() / commit_latencies.len() as u64)
+        },
+        max_commit_latency_ms: commit_latencies.iter().max().copied(),
+    }
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,164 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::util::consensus_replay::{ConsensusReplay, RoundOutcome};
+use aptos_consensus_types::{
+    block::{
+        block_test_utils::{certificate_for_genesis, gen_test_certificate},
+        Block,
+    },
+    common::{Payload, Round},
+    quorum_cert::QuorumCert,
+    timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
+};
+use aptos_crypto::HashValue;
+use aptos_types::{block_info::BlockInfo, validator_signer::ValidatorSigner};
+
+fn block_info(block: &Block) -> BlockInfo {
+    block.gen_block_info(HashValue::zero(), 0, None)
+}
+
+fn certificate(
+    signer: &ValidatorSigner,
+    block: &Block,
+    parent: &Block,
+    committed: Option<&Block>,
+) -> QuorumCert {
+    gen_test_certificate(
+        &[signer.clone()],
+        block_info(block),
+        block_info(parent),
+        committed.map(block_info),
+    )
+}
+
+fn proposal(
+    signer: &ValidatorSigner,
+    round: Round,
+    quorum_cert: QuorumCert,
+    failed_rounds: Vec<Round>,
+) -> Block {
+    Block::new_proposal(
+        Payload::empty(false),
+        round,
+        round * 1_000_000,
+        quorum_cert,
+        signer,
+        failed_rounds
+            .into_iter()
+            .map(|round| (round, signer.author()))
+            .collect(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_replay_block_tree() {
+    let signer = ValidatorSigner::random(None);
+
+    // Round 3 is proposed but never certified, round 4 has no proposal, and
+    // round 5 forks off the certified block of round 2.
+    let genesis = Block::make_genesis_block();
+    let b1 = proposal(&signer, 1, certificate_for_genesis(), vec![]);
+    let b2 = proposal(
+        &signer,
+        2,
+        certificate(&signer, &b1, &genesis, None),
+        vec![],
+    );
+    let b3 = proposal(
+        &signer,
+        3,
+        certificate(&signer, &b2, &b1, Some(&b1)),
+        vec![],
+    );
+    let b4 = proposal(
+        &signer,
+        5,
+        certificate(&signer, &b2, &b1, Some(&b1)),
+        vec![3, 4],
+    );
+    let b5 = proposal(&signer, 6, certificate(&signer, &b4, &b2, None), vec![]);
+    let b6 = proposal(
+        &signer,
+        7,
+        certificate(&signer, &b5, &b4, Some(&b4)),
+        vec![],
+    );
+    let tc = TwoChainTimeoutCertificate::new(TwoChainTimeout::new(
+        b4.epoch(),
+        4,
+        b2.quorum_cert().clone(),
+    ));
+
+    let blocks = vec![
+        b6.clone(),
+        b4.clone(),
+        genesis.clone(),
+        b3.clone(),
+        b1.clone(),
+        b5.clone(),
+        b2.clone(),
+    ];
+    let replay = ConsensusReplay::new(blocks, vec![], None, Some(tc), None);
+
+    // Verify the blocks are sorted by round and linked to their children
+    let rounds: Vec<Round> = replay.blocks.iter().map(|block| block.round).collect();
+    assert_eq!(rounds, vec![0, 1, 2, 3, 5, 6, 7]);
+    let replayed_b2 = &replay.blocks[2];
+    assert_eq!(replayed_b2.children, vec![b3.id(), b4.id()]);
+    assert!(replayed_b2.is_fork());
+
+    // Verify certification and commits
+    let certified: Vec<bool> = replay.blocks.iter().map(|block| block.certified).collect();
+    assert_eq!(certified, vec![true, true, true, false, true, true, false]);
+    let committed: Vec<bool> = replay.blocks.iter().map(|block| block.committed).collect();
+    assert_eq!(committed, vec![true, true, true, false, true, false, false]);
+
+    // b1 is committed by the QC carried by b3, b2 and b4 by the QC carried by b6
+    let commit_latencies: Vec<Option<u64>> = replay.blocks[1..]
+        .iter()
+        .map(|block| block.commit_latency_ms)
+        .collect();
+    assert_eq!(
+        commit_latencies,
+        vec![Some(2000), Some(5000), None, Some(2000), None, None]
+    );
+
+    // Verify the round transitions
+    let outcomes: Vec<RoundOutcome> = replay.rounds.iter().map(|round| round.outcome).collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            RoundOutcome::Certified,
+            RoundOutcome::Certified,
+            RoundOutcome::Certified,
+            RoundOutcome::NotCertified,
+            RoundOutcome::NoProposal,
+            RoundOutcome::Certified,
+            RoundOutcome::Certified,
+            RoundOutcome::NotCertified,
+        ]
+    );
+    assert_eq!(replay.rounds[3].failed_authors, vec![signer.author()]);
+    assert_eq!(replay.rounds[4].failed_authors, vec![signer.author()]);
+    assert!(replay.rounds[4].has_timeout_certificate);
+
+    // Verify the summary
+    let summary = &replay.summary;
+    assert_eq!(summary.num_blocks, 7);
+    assert_eq!(summary.num_committed_blocks, 4);
+    assert_eq!(summary.num_forks, 1);
+    assert_eq!(summary.num_timeout_rounds, 3);
+    assert_eq!(summary.highest_certified_round, Some(6));
+    assert_eq!(summary.highest_committed_round, Some(5));
+    assert_eq!(summary.highest_timeout_certificate_round, Some(4));
+    assert_eq!(summary.max_commit_latency_ms, Some(5000));
+
+    // Verify the exports
+    let dot = replay.to_dot();
+    assert_eq!(dot.matches(" -> ").count(), 6);
+    assert_eq!(dot.matches("peripheries=2").count(), 1);
+    assert!(replay.to_html().contains("no_proposal"));
+    assert!(replay.to_json().unwrap().contains("\"not_certified\""));
+}
@@ -7,6 +7,10 @@ use aptos_types::{
     validator_txn::ValidatorTransaction,
 };
 
+pub mod block_production;
+pub mod consensus_replay;
+#[cfg(test)]
+mod consensus_replay_test;
 pub mod db_tool;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod mock_time_service;
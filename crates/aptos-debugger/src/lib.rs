@@ -13,6 +13,8 @@ pub enum Cmd {
 
     DumpPendingTxns(aptos_consensus::util::db_tool::Command),
 
+    ReplayConsensus(aptos_consensus::util::consensus_replay::Command),
+
     #[clap(subcommand)]
     Move(aptos_move_debugger::common::Command),
 }
@@ -23,6 +25,7 @@ impl Cmd {
             Cmd::AptosDb(cmd) => cmd.run().await,
             Cmd::Decode(cmd) => cmd.run().await,
             Cmd::DumpPendingTxns(cmd) => cmd.run().await,
+            Cmd::ReplayConsensus(cmd) => cmd.run().await,
             Cmd::Move(cmd) => cmd.run().await,
         }
     }
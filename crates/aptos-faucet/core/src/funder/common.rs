@@ -4,6 +4,7 @@
 
 use crate::{
     endpoints::{AptosTapError, AptosTapErrorCode},
+    helpers::get_current_time_secs,
     middleware::NUM_OUTSTANDING_TRANSACTIONS,
 };
 use anyhow::{anyhow, Context, Result};
@@ -16,9 +17,10 @@ use aptos_logger::{
 use aptos_sdk::{
     crypto::ed25519::Ed25519PrivateKey,
     rest_client::Client,
+    transaction_builder::TransactionFactory,
     types::{
-        account_address::AccountAddress, chain_id::ChainId, transaction::SignedTransaction,
-        LocalAccount,
+        account_address::AccountAddress, chain_id::ChainId, timestamp::TimestampResource,
+        transaction::SignedTransaction, LocalAccount,
     },
 };
 use clap::Parser;
@@ -345,6 +347,28 @@ async fn get_sequence_numbers(
     Ok((funder_seq_num, receiver_seq_num))
 }
 
+/// Makes the transactions of the factory expire relative to the on-chain time, when that is
+/// ahead of the local time. This is only the case on a local testnet whose on-chain time was
+/// moved forward, where transactions expiring relative to the local time would be rejected.
+pub async fn expire_after_on_chain_time(
+    client: &Client,
+    transaction_factory: TransactionFactory,
+) -> Result<TransactionFactory, AptosTapError> {
+    let timestamp = client
+        .get_account_resource_bcs::<TimestampResource>(
+            AccountAddress::ONE,
+            "0x1::timestamp::CurrentTimeMicroseconds",
+        )
+        .await
+        .map_err(|e| AptosTapError::new_with_error_code(e, AptosTapErrorCode::AptosApiError))?
+        .into_inner();
+    let on_chain_now = Duration::from_micros(timestamp.timestamp.microseconds).as_secs();
+    let lead_secs = on_chain_now.saturating_sub(get_current_time_secs());
+    let transaction_expiration_secs = transaction_factory.get_transaction_expiration_time();
+    Ok(transaction_factory
+        .with_transaction_expiration_time(transaction_expiration_secs + lead_secs))
+}
+
 /// Submit a transaction, potentially wait for it depending on `wait_for_transactions`
 pub async fn submit_transaction(
     client: &Client,
@@ -29,8 +29,8 @@ static MINTER_SCRIPT: &[u8] = include_bytes!(
 );
 
 use super::common::{
-    submit_transaction, update_sequence_numbers, ApiConnectionConfig, GasUnitPriceManager,
-    TransactionSubmissionConfig,
+    expire_after_on_chain_time, submit_transaction, update_sequence_numbers, ApiConnectionConfig,
+    GasUnitPriceManager, TransactionSubmissionConfig,
 };
 
 /// explain these contain additional args for the mint funder.
@@ -134,11 +134,17 @@ impl MintFunder {
         }
     }
 
-    async fn get_transaction_factory(&self) -> Result<TransactionFactory, AptosTapError> {
-        Ok(self
-            .transaction_factory
-            .clone()
-            .with_gas_unit_price(self.get_gas_unit_price().await?))
+    async fn get_transaction_factory(
+        &self,
+        client: &Client,
+    ) -> Result<TransactionFactory, AptosTapError> {
+        expire_after_on_chain_time(
+            client,
+            self.transaction_factory
+                .clone()
+                .with_gas_unit_price(self.get_gas_unit_price().await?),
+        )
+        .await
     }
 
     /// todo explain / rename
@@ -166,7 +172,7 @@ impl MintFunder {
         // Build a transaction factory using the gas unit price from the
         // GasUnitPriceManager. This mostly ensures that we will build a
         // transaction with a gas unit price that will be accepted.
-        let transaction_factory = self.get_transaction_factory().await?;
+        let transaction_factory = self.get_transaction_factory(&client).await?;
 
         // Delegate minting to the account
         {
@@ -238,17 +244,18 @@ impl MintFunder {
             return Ok(vec![]);
         }
 
-        let txn =
-            {
-                let faucet_account = self.faucet_account.write().await;
-                let transaction_factory = self.get_transaction_factory().await?;
-                faucet_account.sign_with_transaction_builder(transaction_factory.script(
-                    Script::new(MINTER_SCRIPT.to_vec(), vec![], vec![
-                        TransactionArgument::Address(receiver_address),
-                        TransactionArgument::U64(amount),
-                    ]),
-                ))
-            };
+        let txn = {
+            let faucet_account = self.faucet_account.write().await;
+            let transaction_factory = self.get_transaction_factory(client).await?;
+            faucet_account.sign_with_transaction_builder(transaction_factory.script(Script::new(
+                MINTER_SCRIPT.to_vec(),
+                vec![],
+                vec![
+                    TransactionArgument::Address(receiver_address),
+                    TransactionArgument::U64(amount),
+                ],
+            )))
+        };
 
         Ok(vec![
             submit_transaction(
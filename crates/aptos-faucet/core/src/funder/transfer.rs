@@ -3,7 +3,8 @@
 
 use super::{
     common::{
-        submit_transaction, ApiConnectionConfig, GasUnitPriceManager, TransactionSubmissionConfig,
+        expire_after_on_chain_time, submit_transaction, ApiConnectionConfig, GasUnitPriceManager,
+        TransactionSubmissionConfig,
     },
     FunderHealthMessage, FunderTrait,
 };
@@ -177,10 +178,13 @@ impl TransferFunder {
         // Build a transaction factory using the gas unit price from the
         // GasUnitPriceManager. This mostly ensures that we will build a
         // transaction with a gas unit price that will be accepted.
-        let transaction_factory = self
-            .transaction_factory
-            .clone()
-            .with_gas_unit_price(self.get_gas_unit_price().await?);
+        let transaction_factory = expire_after_on_chain_time(
+            client,
+            self.transaction_factory
+                .clone()
+                .with_gas_unit_price(self.get_gas_unit_price().await?),
+        )
+        .await?;
 
         let transaction_builder = transaction_factory.payload(payload);
 
@@ -0,0 +1,129 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::{
+    signer::PartialTransaction,
+    types::{
+        CliCommand, CliError, CliTypedResult, EncodingOptions, PrivateKeyInputOptions,
+        ProfileOptions, RestOptions, TransactionSummary,
+    },
+};
+use async_trait::async_trait;
+use clap::Parser;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Add signatures to a partially signed MultiEd25519 transaction
+///
+/// Signs with the private key given on the command line, or else with the keys of the profile.
+/// No network access is needed, so co-signers can sign on an offline machine.  The partially
+/// signed transaction is written by commands run with `--partial-output-file`.
+#[derive(Debug, Parser)]
+pub struct SignPartial {
+    /// Partially signed transaction file
+    #[clap(long, value_parser)]
+    pub(crate) partial_file: PathBuf,
+
+    /// File to write the transaction with the added signatures to
+    ///
+    /// Defaults to overwriting `--partial-file`
+    #[clap(long, value_parser)]
+    pub(crate) output_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) private_key_options: PrivateKeyInputOptions,
+    #[clap(flatten)]
+    pub(crate) encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignPartialSummary {
+    output_file: PathBuf,
+    signatures: usize,
+    missing_signatures: usize,
+}
+
+#[async_trait]
+impl CliCommand<SignPartialSummary> for SignPartial {
+    fn command_name(&self) -> &'static str {
+        "SignPartial"
+    }
+
+    async fn execute(self) -> CliTypedResult<SignPartialSummary> {
+        let mut partial_txn = PartialTransaction::load(&self.partial_file)?;
+
+        let private_keys = if let Some(private_key) = self
+            .private_key_options
+            .extract_private_key_cli(self.encoding_options.encoding)?
+        {
+            vec![private_key]
+        } else {
+            let mut profile = self.profile_options.profile()?;
+            if let Some(multi_ed25519) = profile.multi_ed25519.take() {
+                multi_ed25519.unlock_private_keys()?
+            } else {
+                profile.unlock_private_key()?.into_iter().collect()
+            }
+        };
+
+        if partial_txn.sign(&private_keys)? == 0 {
+            return Err(CliError::CommandArgumentError(
+                "None of the private keys belong to the key set of the transaction".to_string(),
+            ));
+        }
+
+        let output_file = self.output_file.unwrap_or(self.partial_file);
+        partial_txn.save(&output_file)?;
+        Ok(SignPartialSummary {
+            output_file,
+            signatures: partial_txn.signatures.len(),
+            missing_signatures: partial_txn.missing_signatures(),
+        })
+    }
+}
+
+/// Submit a MultiEd25519 transaction once enough co-signers signed it
+///
+/// The signatures of all the given files are combined, so each co-signer can sign their own
+/// copy of the partially signed transaction.
+#[derive(Debug, Parser)]
+pub struct SubmitPartial {
+    /// Partially signed transaction files, all for the same transaction
+    #[clap(long = "partial-file", value_parser, required = true, num_args = 1..)]
+    pub(crate) partial_files: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[async_trait]
+impl CliCommand<TransactionSummary> for SubmitPartial {
+    fn command_name(&self) -> &'static str {
+        "SubmitPartial"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionSummary> {
+        let mut partial_files = self.partial_files.iter();
+        let mut partial_txn = PartialTransaction::load(
+            partial_files
+                .next()
+                .expect("At least one partial file is required"),
+        )?;
+        partial_txn.verify_signatures()?;
+        for partial_file in partial_files {
+            partial_txn.merge(PartialTransaction::load(partial_file)?)?;
+        }
+
+        let transaction = partial_txn.into_signed_transaction()?;
+        let client = self.rest_options.client(&self.profile_options)?;
+        let response = client
+            .submit_and_wait(&transaction)
+            .await
+            .map_err(|err| CliError::ApiError(err.to_string()))?;
+        Ok(TransactionSummary::from(&response.into_inner()))
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::common::{
+    signer::AccountSigner,
     types::{
         account_address_from_auth_key, account_address_from_public_key,
         AuthenticationKeyInputOptions, CliCommand, CliConfig, CliError, CliTypedResult,
@@ -14,6 +15,7 @@ use aptos_cached_packages::aptos_stdlib;
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     encoding_type::EncodingType,
+    multi_ed25519::MultiEd25519Signature,
     PrivateKey, SigningKey,
 };
 use aptos_rest_client::{
@@ -31,6 +33,10 @@ use clap::Parser;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, path::PathBuf};
 
+/// Signature schemes of `0x1::account::rotate_authentication_key`
+const ED25519_SCHEME: u8 = 0;
+const MULTI_ED25519_SCHEME: u8 = 1;
+
 /// Rotate an account's authentication key
 ///
 /// Rotating the account's authentication key allows you to use a new
@@ -103,12 +109,14 @@ impl CliCommand<RotateSummary> for RotateKey {
                 )
             })?;
 
-        let (current_private_key, sender_address) = self.txn_options.get_key_and_address()?;
+        let (current_signer, sender_address) = self.txn_options.get_signer_and_address()?;
 
-        if new_private_key == current_private_key {
-            return Err(CliError::CommandArgumentError(
-                "New private key cannot be the same as the current private key".to_string(),
-            ));
+        if let AccountSigner::Ed25519(ref current_private_key) = current_signer {
+            if new_private_key == *current_private_key {
+                return Err(CliError::CommandArgumentError(
+                    "New private key cannot be the same as the current private key".to_string(),
+                ));
+            }
         }
 
         // Get sequence number for account
@@ -129,24 +137,72 @@ impl CliCommand<RotateSummary> for RotateKey {
         let rotation_msg =
             bcs::to_bytes(&rotation_proof).map_err(|err| CliError::BCS("rotation_proof", err))?;
 
-        // Signs the struct using both the current private key and the next private key
-        let rotation_proof_signed_by_current_private_key =
-            current_private_key.sign_arbitrary_message(&rotation_msg.clone());
+        // Signs the struct using both the current key and the next private key
+        let (from_scheme, current_public_key, rotation_proof_signed_by_current_key) =
+            match current_signer {
+                AccountSigner::Ed25519(ref current_private_key) => (
+                    ED25519_SCHEME,
+                    current_private_key.public_key().to_bytes().to_vec(),
+                    current_private_key
+                        .sign_arbitrary_message(&rotation_msg)
+                        .to_bytes()
+                        .to_vec(),
+                ),
+                AccountSigner::MultiEd25519 {
+                    ref public_key,
+                    ref private_keys,
+                } => {
+                    let signatures =
+                        private_keys
+                            .iter()
+                            .filter_map(|private_key| {
+                                let key = private_key.public_key();
+                                public_key.public_keys().iter().position(|k| *k == key).map(
+                                    |index| {
+                                        (
+                                            private_key.sign_arbitrary_message(&rotation_msg),
+                                            index as u8,
+                                        )
+                                    },
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                    if signatures.len() < *public_key.threshold() as usize {
+                        return Err(CliError::CommandArgumentError(format!(
+                            "Rotating a MultiEd25519 key set requires {} of its private keys \
+                            in the profile, but only {} are present",
+                            public_key.threshold(),
+                            signatures.len()
+                        )));
+                    }
+                    let signature = MultiEd25519Signature::new(signatures)
+                        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                    (
+                        MULTI_ED25519_SCHEME,
+                        public_key.to_bytes(),
+                        signature.to_bytes(),
+                    )
+                },
+                AccountSigner::Secp256k1Ecdsa(_) => {
+                    return Err(CliError::CommandArgumentError(
+                        "Secp256k1 ECDSA accounts can't rotate their authentication key"
+                            .to_string(),
+                    ))
+                },
+            };
         let rotation_proof_signed_by_new_private_key =
             new_private_key.sign_arbitrary_message(&rotation_msg);
 
         let txn_summary = self
             .txn_options
             .submit_transaction(aptos_stdlib::account_rotate_authentication_key(
-                0,
+                from_scheme,
                 // Existing public key
-                current_private_key.public_key().to_bytes().to_vec(),
-                0,
+                current_public_key,
+                ED25519_SCHEME,
                 // New public key
                 new_private_key.public_key().to_bytes().to_vec(),
-                rotation_proof_signed_by_current_private_key
-                    .to_bytes()
-                    .to_vec(),
+                rotation_proof_signed_by_current_key,
                 rotation_proof_signed_by_new_private_key.to_bytes().to_vec(),
             ))
             .await
@@ -225,6 +281,9 @@ impl CliCommand<RotateSummary> for RotateKey {
 
         let mut profile_config = ProfileConfig {
             private_key: Some(new_private_key.clone()),
+            keystore: None,
+            secp256k1_private_key: None,
+            multi_ed25519: None,
             public_key: Some(new_private_key.public_key()),
             account: Some(sender_address),
             ..self.txn_options.profile_options.profile()?
@@ -4,6 +4,7 @@
 use crate::common::types::{CliCommand, CliResult};
 use clap::Subcommand;
 
+pub mod co_sign;
 pub mod create;
 pub mod create_resource_account;
 pub mod derive_resource_account;
@@ -26,6 +27,8 @@ pub enum AccountTool {
     List(list::ListAccount),
     LookupAddress(key_rotation::LookupAddress),
     RotateKey(key_rotation::RotateKey),
+    SignPartial(co_sign::SignPartial),
+    SubmitPartial(co_sign::SubmitPartial),
     Transfer(transfer::TransferCoins),
 }
 
@@ -39,6 +42,8 @@ impl AccountTool {
             AccountTool::List(tool) => tool.execute_serialized().await,
             AccountTool::LookupAddress(tool) => tool.execute_serialized().await,
             AccountTool::RotateKey(tool) => tool.execute_serialized().await,
+            AccountTool::SignPartial(tool) => tool.execute_serialized().await,
+            AccountTool::SubmitPartial(tool) => tool.execute_serialized().await,
             AccountTool::Transfer(tool) => tool.execute_serialized().await,
         }
     }
@@ -4,6 +4,8 @@
 use crate::{
     account::key_rotation::lookup_address,
     common::{
+        keystore::{EncryptedKeystore, KeystoreKeyScheme},
+        signer::{KeyScheme, MultiEd25519Config},
         types::{
             account_address_from_public_key, CliCommand, CliConfig, CliError, CliTypedResult,
             ConfigSearchMode, EncodingOptions, HardwareWalletOptions, PrivateKeyInputOptions,
@@ -12,12 +14,19 @@ use crate::{
         utils::{fund_account, prompt_yes_with_override, read_line},
     },
 };
-use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, ValidCryptoMaterialStringExt};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    secp256k1_ecdsa, PrivateKey, ValidCryptoMaterialStringExt,
+};
 use aptos_ledger;
 use aptos_rest_client::{
     aptos_api_types::{AptosError, AptosErrorCode},
     error::{AptosErrorResponse, RestError},
 };
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::authenticator::{AnyPublicKey, AuthenticationKey},
+};
 use async_trait::async_trait;
 use clap::Parser;
 use reqwest::Url;
@@ -65,6 +74,23 @@ pub struct InitTool {
     #[clap(flatten)]
     pub(crate) hardware_wallet_options: HardwareWalletOptions,
 
+    /// Signature scheme of the account key
+    ///
+    /// A `multi-ed25519` key set requires `--multi-ed25519-public-keys` and
+    /// `--multi-ed25519-threshold`.  The profile holds the private key given with
+    /// `--private-key`, which must belong to the set, and signatures of the other keys are
+    /// collected from co-signers.
+    #[clap(long, value_enum, default_value_t = KeyScheme::Ed25519)]
+    pub(crate) key_scheme: KeyScheme,
+
+    /// Public keys of the MultiEd25519 key set, in order, as hex literals (0x...)
+    #[clap(long, num_args = 1..)]
+    pub(crate) multi_ed25519_public_keys: Vec<String>,
+
+    /// Number of signatures required by the MultiEd25519 key set
+    #[clap(long)]
+    pub(crate) multi_ed25519_threshold: Option<u8>,
+
     #[clap(flatten)]
     pub rng_args: RngArgs,
     #[clap(flatten)]
@@ -185,62 +211,16 @@ impl CliCommand<()> for InitTool {
         // Set the derivation_path to the one user chose
         profile_config.derivation_path = derivation_path.clone();
 
-        // Private key
-        let private_key = if self.is_hardware_wallet() {
-            // Private key stays in ledger
-            None
-        } else {
-            let ed25519_private_key = if let Some(key) = self
-                .private_key_options
-                .extract_private_key_cli(self.encoding_options.encoding)?
-            {
-                eprintln!("Using command line argument for private key");
-                key
-            } else {
-                eprintln!("Enter your private key as a hex literal (0x...) [Current: {} | No input: Generate new key (or keep one if present)]", profile_config.private_key.as_ref().map(|_| "Redacted").unwrap_or("None"));
-                let input = read_line("Private key")?;
-                let input = input.trim();
-                if input.is_empty() {
-                    if let Some(key) = profile_config.private_key {
-                        eprintln!("No key given, keeping existing key...");
-                        key
-                    } else {
-                        eprintln!("No key given, generating key...");
-                        self.rng_args
-                            .key_generator()?
-                            .generate_ed25519_private_key()
-                    }
-                } else {
-                    Ed25519PrivateKey::from_encoded_string(input).map_err(|err| {
-                        CliError::UnableToParse("Ed25519PrivateKey", err.to_string())
-                    })?
-                }
-            };
-
-            Some(ed25519_private_key)
-        };
-
-        // Public key
-        let public_key = if self.is_hardware_wallet() {
-            let pub_key = match aptos_ledger::get_public_key(
-                derivation_path
-                    .ok_or(CliError::UnexpectedError(
-                        "Invalid derivation path".to_string(),
-                    ))?
-                    .as_str(),
-                false,
-            ) {
-                Ok(pub_key_str) => pub_key_str,
-                Err(err) => {
-                    return Err(CliError::UnexpectedError(format!(
-                        "Unexpected Ledger Error: {:?}",
-                        err.to_string()
-                    )))
-                },
-            };
-            pub_key
-        } else {
-            private_key.clone().unwrap().public_key()
+        // Keys, depending on the scheme
+        if self.is_hardware_wallet() && self.key_scheme != KeyScheme::Ed25519 {
+            return Err(CliError::CommandArgumentError(
+                "Hardware wallets only support the ed25519 key scheme".to_string(),
+            ));
+        }
+        let derived_address = match self.key_scheme {
+            KeyScheme::Ed25519 => self.init_ed25519(&mut profile_config, derivation_path)?,
+            KeyScheme::Secp256k1Ecdsa => self.init_secp256k1_ecdsa(&mut profile_config)?,
+            KeyScheme::MultiEd25519 => self.init_multi_ed25519(&mut profile_config)?,
         };
 
         let rest_url = Url::parse(
@@ -254,11 +234,7 @@ impl CliCommand<()> for InitTool {
 
         // lookup the address from onchain instead of deriving it
         // if this is the rotated key, deriving it will outputs an incorrect address
-        let derived_address = account_address_from_public_key(&public_key);
         let address = lookup_address(&client, derived_address, false).await?;
-
-        profile_config.private_key = private_key;
-        profile_config.public_key = Some(public_key);
         profile_config.account = Some(address);
 
         // Create account if it doesn't exist (and there's a faucet)
@@ -414,11 +390,229 @@ impl InitTool {
         Ok(())
     }
 
+    /// Sets up an Ed25519 key, from the command line, the prompt, or a hardware wallet, and
+    /// returns the address derived from it
+    fn init_ed25519(
+        &self,
+        profile_config: &mut ProfileConfig,
+        derivation_path: Option<String>,
+    ) -> CliTypedResult<AccountAddress> {
+        // Private key
+        let mut keep_keystore = false;
+        let private_key = if self.is_hardware_wallet() {
+            // Private key stays in ledger
+            None
+        } else {
+            let ed25519_private_key = if let Some(key) = self
+                .private_key_options
+                .extract_private_key_cli(self.encoding_options.encoding)?
+            {
+                eprintln!("Using command line argument for private key");
+                key
+            } else {
+                let has_keystore = has_keystore(profile_config, KeystoreKeyScheme::Ed25519)?;
+                let current = if profile_config.private_key.is_some() {
+                    "Redacted"
+                } else if has_keystore {
+                    "Encrypted"
+                } else {
+                    "None"
+                };
+                eprintln!("Enter your private key as a hex literal (0x...) [Current: {} | No input: Generate new key (or keep one if present)]", current);
+                let input = read_line("Private key")?;
+                let input = input.trim();
+                if input.is_empty() {
+                    if let Some(key) = profile_config.private_key.take() {
+                        eprintln!("No key given, keeping existing key...");
+                        key
+                    } else if let (true, Some(keystore)) = (has_keystore, &profile_config.keystore)
+                    {
+                        eprintln!("No key given, keeping existing encrypted key...");
+                        keep_keystore = true;
+                        EncryptedKeystore::unlock(keystore)?.into_ed25519()?
+                    } else {
+                        eprintln!("No key given, generating key...");
+                        self.rng_args
+                            .key_generator()?
+                            .generate_ed25519_private_key()
+                    }
+                } else {
+                    Ed25519PrivateKey::from_encoded_string(input).map_err(|err| {
+                        CliError::UnableToParse("Ed25519PrivateKey", err.to_string())
+                    })?
+                }
+            };
+
+            Some(ed25519_private_key)
+        };
+
+        // Public key
+        let public_key = if self.is_hardware_wallet() {
+            let pub_key = match aptos_ledger::get_public_key(
+                derivation_path
+                    .ok_or(CliError::UnexpectedError(
+                        "Invalid derivation path".to_string(),
+                    ))?
+                    .as_str(),
+                false,
+            ) {
+                Ok(pub_key_str) => pub_key_str,
+                Err(err) => {
+                    return Err(CliError::UnexpectedError(format!(
+                        "Unexpected Ledger Error: {:?}",
+                        err.to_string()
+                    )))
+                },
+            };
+            pub_key
+        } else {
+            private_key.clone().unwrap().public_key()
+        };
+
+        if keep_keystore {
+            // The key stays encrypted, only the keystore is referenced by the profile
+            profile_config.private_key = None;
+        } else {
+            profile_config.private_key = private_key;
+            profile_config.keystore = None;
+        }
+        profile_config.secp256k1_private_key = None;
+        profile_config.multi_ed25519 = None;
+        profile_config.public_key = Some(public_key.clone());
+        Ok(account_address_from_public_key(&public_key))
+    }
+
+    /// Sets up a Secp256k1 ECDSA key, and returns the address derived from it
+    fn init_secp256k1_ecdsa(
+        &self,
+        profile_config: &mut ProfileConfig,
+    ) -> CliTypedResult<AccountAddress> {
+        let mut keep_keystore = false;
+        let private_key = if let Some(key) = self
+            .private_key_options
+            .extract_secp256k1_private_key_cli(self.encoding_options.encoding)?
+        {
+            eprintln!("Using command line argument for private key");
+            key
+        } else {
+            let has_keystore = has_keystore(profile_config, KeystoreKeyScheme::Secp256k1Ecdsa)?;
+            let current = if profile_config.secp256k1_private_key.is_some() {
+                "Redacted"
+            } else if has_keystore {
+                "Encrypted"
+            } else {
+                "None"
+            };
+            eprintln!("Enter your private key as a hex literal (0x...) [Current: {} | No input: Generate new key (or keep one if present)]", current);
+            let input = read_line("Private key")?;
+            let input = input.trim();
+            if input.is_empty() {
+                if let Some(key) = profile_config.secp256k1_private_key.take() {
+                    eprintln!("No key given, keeping existing key...");
+                    key
+                } else if let (true, Some(keystore)) = (has_keystore, &profile_config.keystore) {
+                    eprintln!("No key given, keeping existing encrypted key...");
+                    keep_keystore = true;
+                    EncryptedKeystore::unlock(keystore)?.into_secp256k1_ecdsa()?
+                } else {
+                    eprintln!("No key given, generating key...");
+                    self.rng_args
+                        .key_generator()?
+                        .generate_secp256k1_ecdsa_private_key()
+                }
+            } else {
+                secp256k1_ecdsa::PrivateKey::from_encoded_string(input).map_err(|err| {
+                    CliError::UnableToParse("Secp256k1 ECDSA private key", err.to_string())
+                })?
+            }
+        };
+
+        let derived_address =
+            AuthenticationKey::any_key(AnyPublicKey::secp256k1_ecdsa(private_key.public_key()))
+                .account_address();
+        if keep_keystore {
+            // The key stays encrypted, only the keystore is referenced by the profile
+            profile_config.secp256k1_private_key = None;
+        } else {
+            profile_config.secp256k1_private_key = Some(private_key);
+            profile_config.keystore = None;
+        }
+        profile_config.private_key = None;
+        profile_config.public_key = None;
+        profile_config.multi_ed25519 = None;
+        Ok(derived_address)
+    }
+
+    /// Sets up a MultiEd25519 key set, and returns the address derived from it
+    fn init_multi_ed25519(
+        &self,
+        profile_config: &mut ProfileConfig,
+    ) -> CliTypedResult<AccountAddress> {
+        let threshold = self.multi_ed25519_threshold.ok_or_else(|| {
+            CliError::CommandArgumentError(
+                "'--multi-ed25519-threshold' is required for the multi-ed25519 key scheme"
+                    .to_string(),
+            )
+        })?;
+        let public_keys = self
+            .multi_ed25519_public_keys
+            .iter()
+            .map(|key| {
+                Ed25519PublicKey::from_encoded_string(key).map_err(|err| {
+                    CliError::UnableToParse("--multi-ed25519-public-keys", err.to_string())
+                })
+            })
+            .collect::<CliTypedResult<Vec<_>>>()?;
+
+        let mut private_keys = vec![];
+        if let Some(private_key) = self
+            .private_key_options
+            .extract_private_key_cli(self.encoding_options.encoding)?
+        {
+            if !public_keys.contains(&private_key.public_key()) {
+                return Err(CliError::CommandArgumentError(
+                    "The private key doesn't belong to the MultiEd25519 key set".to_string(),
+                ));
+            }
+            private_keys.push(private_key);
+        }
+
+        let multi_ed25519 = MultiEd25519Config {
+            public_keys,
+            threshold,
+            private_keys,
+            keystores: vec![],
+        };
+        let derived_address =
+            AuthenticationKey::multi_ed25519(&multi_ed25519.public_key()?).account_address();
+        eprintln!(
+            "Using a {}-of-{} MultiEd25519 key set, holding {} of its private keys",
+            threshold,
+            multi_ed25519.public_keys.len(),
+            multi_ed25519.num_private_keys()
+        );
+
+        profile_config.multi_ed25519 = Some(multi_ed25519);
+        profile_config.private_key = None;
+        profile_config.keystore = None;
+        profile_config.public_key = None;
+        profile_config.secp256k1_private_key = None;
+        Ok(derived_address)
+    }
+
     fn is_hardware_wallet(&self) -> bool {
         self.hardware_wallet_options.is_hardware_wallet() || self.ledger
     }
 }
 
+/// Whether the profile references a keystore holding a key of the scheme
+fn has_keystore(profile_config: &ProfileConfig, scheme: KeystoreKeyScheme) -> CliTypedResult<bool> {
+    match profile_config.keystore {
+        Some(ref keystore) => Ok(EncryptedKeystore::load(keystore)?.key_scheme == scheme),
+        None => Ok(false),
+    }
+}
+
 /// A simplified list of all networks supported by the CLI
 ///
 /// Any command using this, will be simpler to setup as profiles
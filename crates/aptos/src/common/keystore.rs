@@ -0,0 +1,509 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Passphrase protected storage of private keys
+//!
+//! A keystore holds an Ed25519 or Secp256k1 ECDSA private key encrypted with AES-256-GCM, under a
+//! key derived from a passphrase with Argon2id.  Profiles can reference keystores instead of
+//! embedding private keys in plaintext in the config file.
+
+use crate::common::{
+    types::{CliError, CliTypedResult},
+    utils::{read_from_file, write_to_user_only_file},
+};
+use aptos_crypto::{
+    ed25519::Ed25519PrivateKey, secp256k1_ecdsa, PrivateKey, ValidCryptoMaterial,
+    ValidCryptoMaterialStringExt,
+};
+use aptos_infallible::Mutex;
+use argon2::{Algorithm, Argon2, Params, Version};
+use once_cell::sync::Lazy;
+use rand::{rngs::OsRng, RngCore};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroizing;
+
+/// Environment variable holding the passphrase of a keystore, for non-interactive use
+pub const KEYSTORE_PASSPHRASE_ENV: &str = "APTOS_KEYSTORE_PASSPHRASE";
+/// Environment variable holding the new passphrase when changing the passphrase of a keystore
+pub const KEYSTORE_NEW_PASSPHRASE_ENV: &str = "APTOS_KEYSTORE_NEW_PASSPHRASE";
+
+const KEYSTORE_VERSION: u32 = 1;
+const KDF_ALGORITHM: &str = "argon2id";
+const CIPHER_ALGORITHM: &str = "aes-256-gcm";
+const SALT_LENGTH: usize = 16;
+const DERIVED_KEY_LENGTH: usize = 32;
+
+/// Upper bounds of the KDF parameters read from a keystore, so a tampered keystore can't make
+/// unlocking it exhaust the memory or spin forever.  The defaults are well below them.
+const MAX_KDF_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 64;
+const MAX_KDF_PARALLELISM: u32 = 64;
+
+/// Keys already unlocked by this process, so signing multiple times only prompts once
+static UNLOCKED_KEYS: Lazy<Mutex<HashMap<PathBuf, UnlockedKey>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The bytes of an unlocked private key, wiped from memory when dropped
+struct UnlockedKey {
+    scheme: KeystoreKeyScheme,
+    bytes: Zeroizing<Vec<u8>>,
+}
+
+impl UnlockedKey {
+    fn new(private_key: &KeystorePrivateKey) -> Self {
+        Self {
+            scheme: private_key.scheme(),
+            bytes: Zeroizing::new(private_key.to_bytes()),
+        }
+    }
+
+    fn private_key(&self) -> CliTypedResult<KeystorePrivateKey> {
+        KeystorePrivateKey::from_bytes(self.scheme, &self.bytes)
+    }
+}
+
+/// Signature scheme of the key held by a keystore
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeystoreKeyScheme {
+    /// Keystores written before other schemes were supported hold Ed25519 keys
+    #[default]
+    Ed25519,
+    Secp256k1Ecdsa,
+}
+
+/// A private key that can be held by a keystore
+#[derive(Clone, Debug)]
+pub enum KeystorePrivateKey {
+    Ed25519(Ed25519PrivateKey),
+    Secp256k1Ecdsa(secp256k1_ecdsa::PrivateKey),
+}
+
+impl KeystorePrivateKey {
+    pub fn scheme(&self) -> KeystoreKeyScheme {
+        match self {
+            KeystorePrivateKey::Ed25519(_) => KeystoreKeyScheme::Ed25519,
+            KeystorePrivateKey::Secp256k1Ecdsa(_) => KeystoreKeyScheme::Secp256k1Ecdsa,
+        }
+    }
+
+    /// Encoded public key, as stored in the clear in the keystore
+    pub fn encoded_public_key(&self) -> CliTypedResult<String> {
+        match self {
+            KeystorePrivateKey::Ed25519(private_key) => {
+                private_key.public_key().to_encoded_string()
+            },
+            KeystorePrivateKey::Secp256k1Ecdsa(private_key) => {
+                private_key.public_key().to_encoded_string()
+            },
+        }
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            KeystorePrivateKey::Ed25519(private_key) => private_key.to_bytes().to_vec(),
+            KeystorePrivateKey::Secp256k1Ecdsa(private_key) => private_key.to_bytes(),
+        }
+    }
+
+    fn from_bytes(scheme: KeystoreKeyScheme, bytes: &[u8]) -> CliTypedResult<Self> {
+        match scheme {
+            KeystoreKeyScheme::Ed25519 => {
+                Ed25519PrivateKey::try_from(bytes).map(KeystorePrivateKey::Ed25519)
+            },
+            KeystoreKeyScheme::Secp256k1Ecdsa => {
+                secp256k1_ecdsa::PrivateKey::try_from(bytes).map(KeystorePrivateKey::Secp256k1Ecdsa)
+            },
+        }
+        .map_err(|err| {
+            CliError::UnexpectedError(format!("Keystore contains an invalid key: {}", err))
+        })
+    }
+
+    /// Returns the Ed25519 key, failing for keys of other schemes
+    pub fn into_ed25519(self) -> CliTypedResult<Ed25519PrivateKey> {
+        match self {
+            KeystorePrivateKey::Ed25519(private_key) => Ok(private_key),
+            KeystorePrivateKey::Secp256k1Ecdsa(_) => Err(CliError::CommandArgumentError(
+                "The keystore holds a Secp256k1 ECDSA key, but an Ed25519 key is required"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Returns the Secp256k1 ECDSA key, failing for keys of other schemes
+    pub fn into_secp256k1_ecdsa(self) -> CliTypedResult<secp256k1_ecdsa::PrivateKey> {
+        match self {
+            KeystorePrivateKey::Secp256k1Ecdsa(private_key) => Ok(private_key),
+            KeystorePrivateKey::Ed25519(_) => Err(CliError::CommandArgumentError(
+                "The keystore holds an Ed25519 key, but a Secp256k1 ECDSA key is required"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+impl From<Ed25519PrivateKey> for KeystorePrivateKey {
+    fn from(private_key: Ed25519PrivateKey) -> Self {
+        KeystorePrivateKey::Ed25519(private_key)
+    }
+}
+
+impl From<secp256k1_ecdsa::PrivateKey> for KeystorePrivateKey {
+    fn from(private_key: secp256k1_ecdsa::PrivateKey) -> Self {
+        KeystorePrivateKey::Secp256k1Ecdsa(private_key)
+    }
+}
+
+/// Parameters of the key derivation function
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Hex encoded salt
+    pub salt: String,
+}
+
+impl KdfParams {
+    /// Defaults recommended for interactive use, with a fresh random salt
+    pub fn new_random() -> Self {
+        Self::with_cost(64 * 1024, 3, 1)
+    }
+
+    pub fn with_cost(memory_kib: u32, iterations: u32, parallelism: u32) -> Self {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        KdfParams {
+            algorithm: KDF_ALGORITHM.to_string(),
+            memory_kib,
+            iterations,
+            parallelism,
+            salt: hex::encode(salt),
+        }
+    }
+
+    /// Derives the encryption key from the passphrase
+    fn derive_key(&self, passphrase: &str) -> CliTypedResult<Zeroizing<[u8; DERIVED_KEY_LENGTH]>> {
+        if self.algorithm != KDF_ALGORITHM {
+            return Err(CliError::UnexpectedError(format!(
+                "Unsupported keystore KDF {}, expected {}",
+                self.algorithm, KDF_ALGORITHM
+            )));
+        }
+        self.check_cost()?;
+        let salt = hex::decode(&self.salt)
+            .map_err(|err| CliError::UnexpectedError(format!("Invalid keystore salt: {}", err)))?;
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(DERIVED_KEY_LENGTH),
+        )
+        .map_err(|err| {
+            CliError::UnexpectedError(format!("Invalid keystore KDF params: {}", err))
+        })?;
+
+        let mut key = Zeroizing::new([0u8; DERIVED_KEY_LENGTH]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key[..])
+            .map_err(|err| CliError::UnexpectedError(format!("Failed to derive key: {}", err)))?;
+        Ok(key)
+    }
+
+    /// Rejects costs above the maximums, before any memory is allocated for the derivation
+    fn check_cost(&self) -> CliTypedResult<()> {
+        for (name, value, max) in [
+            ("memory_kib", self.memory_kib, MAX_KDF_MEMORY_KIB),
+            ("iterations", self.iterations, MAX_KDF_ITERATIONS),
+            ("parallelism", self.parallelism, MAX_KDF_PARALLELISM),
+        ] {
+            if value > max {
+                return Err(CliError::UnexpectedError(format!(
+                    "Keystore KDF {} of {} exceeds the maximum of {}",
+                    name, value, max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parameters of the cipher
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CipherParams {
+    pub algorithm: String,
+    /// Hex encoded nonce
+    pub nonce: String,
+}
+
+/// An encrypted private key, as stored on disk
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EncryptedKeystore {
+    pub version: u32,
+    #[serde(default)]
+    pub key_scheme: KeystoreKeyScheme,
+    /// Encoded public key of the encrypted private key, readable without the passphrase
+    pub public_key: String,
+    pub kdf: KdfParams,
+    pub cipher: CipherParams,
+    /// Hex encoded encrypted private key, followed by the authentication tag
+    pub ciphertext: String,
+}
+
+impl EncryptedKeystore {
+    /// Encrypts the private key with the default KDF parameters
+    pub fn encrypt(private_key: &KeystorePrivateKey, passphrase: &str) -> CliTypedResult<Self> {
+        Self::encrypt_with_params(private_key, passphrase, KdfParams::new_random())
+    }
+
+    pub fn encrypt_with_params(
+        private_key: &KeystorePrivateKey,
+        passphrase: &str,
+        kdf: KdfParams,
+    ) -> CliTypedResult<Self> {
+        let public_key = private_key.encoded_public_key()?;
+        let cipher_key = cipher_key(&kdf.derive_key(passphrase)?[..])?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let mut in_out = Zeroizing::new(private_key.to_bytes());
+        cipher_key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(public_key_bytes(&public_key)?),
+                &mut *in_out,
+            )
+            .map_err(|_| CliError::UnexpectedError("Failed to encrypt private key".to_string()))?;
+
+        Ok(EncryptedKeystore {
+            version: KEYSTORE_VERSION,
+            key_scheme: private_key.scheme(),
+            public_key,
+            kdf,
+            cipher: CipherParams {
+                algorithm: CIPHER_ALGORITHM.to_string(),
+                nonce: hex::encode(nonce),
+            },
+            ciphertext: hex::encode(&*in_out),
+        })
+    }
+
+    /// Decrypts the private key, failing if the passphrase is wrong or the keystore was tampered with
+    pub fn decrypt(&self, passphrase: &str) -> CliTypedResult<KeystorePrivateKey> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(CliError::UnexpectedError(format!(
+                "Unsupported keystore version {}, expected {}",
+                self.version, KEYSTORE_VERSION
+            )));
+        }
+        if self.cipher.algorithm != CIPHER_ALGORITHM {
+            return Err(CliError::UnexpectedError(format!(
+                "Unsupported keystore cipher {}, expected {}",
+                self.cipher.algorithm, CIPHER_ALGORITHM
+            )));
+        }
+        let nonce = hex::decode(&self.cipher.nonce)
+            .ok()
+            .and_then(|nonce| Nonce::try_assume_unique_for_key(&nonce).ok())
+            .ok_or_else(|| CliError::UnexpectedError("Invalid keystore nonce".to_string()))?;
+        let mut in_out = Zeroizing::new(hex::decode(&self.ciphertext).map_err(|err| {
+            CliError::UnexpectedError(format!("Invalid keystore ciphertext: {}", err))
+        })?);
+
+        let cipher_key = cipher_key(&self.kdf.derive_key(passphrase)?[..])?;
+        let plaintext = cipher_key
+            .open_in_place(
+                nonce,
+                Aad::from(public_key_bytes(&self.public_key)?),
+                &mut *in_out,
+            )
+            .map_err(|_| {
+                CliError::CommandArgumentError(
+                    "Unable to decrypt keystore, the passphrase is incorrect".to_string(),
+                )
+            })?;
+        let private_key = KeystorePrivateKey::from_bytes(self.key_scheme, plaintext)?;
+
+        if private_key.encoded_public_key()? != self.public_key {
+            return Err(CliError::UnexpectedError(
+                "Keystore private key does not match its public key".to_string(),
+            ));
+        }
+        Ok(private_key)
+    }
+
+    pub fn load(path: &Path) -> CliTypedResult<Self> {
+        serde_json::from_slice(&read_from_file(path)?).map_err(|err| {
+            CliError::UnableToParse("keystore", format!("{}: {}", path.display(), err))
+        })
+    }
+
+    /// Saves the keystore readable only by the current user
+    pub fn save(&self, path: &Path) -> CliTypedResult<()> {
+        let bytes = serde_json::to_vec_pretty(self)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        write_to_user_only_file(path, "keystore", &bytes)
+    }
+
+    /// Loads and decrypts a keystore, prompting for its passphrase unless
+    /// `APTOS_KEYSTORE_PASSPHRASE` is set
+    pub fn unlock(path: &Path) -> CliTypedResult<KeystorePrivateKey> {
+        let mut unlocked_keys = UNLOCKED_KEYS.lock();
+        if let Some(unlocked_key) = unlocked_keys.get(path) {
+            return unlocked_key.private_key();
+        }
+
+        let keystore = Self::load(path)?;
+        let passphrase = read_passphrase(
+            &format!("Enter passphrase for keystore {}: ", path.display()),
+            KEYSTORE_PASSPHRASE_ENV,
+        )?;
+        let private_key = keystore.decrypt(&passphrase)?;
+        unlocked_keys.insert(path.to_path_buf(), UnlockedKey::new(&private_key));
+        Ok(private_key)
+    }
+}
+
+/// The public key authenticates the ciphertext, so it can't be swapped for another one
+fn public_key_bytes(encoded_public_key: &str) -> CliTypedResult<Vec<u8>> {
+    hex::decode(encoded_public_key.trim_start_matches("0x"))
+        .map_err(|err| CliError::UnexpectedError(format!("Invalid keystore public key: {}", err)))
+}
+
+fn cipher_key(key: &[u8]) -> CliTypedResult<LessSafeKey> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| CliError::UnexpectedError("Invalid keystore cipher key".to_string()))
+}
+
+/// Reads a passphrase from the environment variable, or prompts for it without echoing
+pub fn read_passphrase(prompt: &str, env: &str) -> CliTypedResult<String> {
+    if let Ok(passphrase) = std::env::var(env) {
+        return Ok(passphrase);
+    }
+    rpassword::prompt_password(prompt)
+        .map_err(|err| CliError::IO("Failed to read passphrase".to_string(), err))
+}
+
+/// Reads a new passphrase from the environment variable, or prompts for it twice to confirm it
+pub fn read_new_passphrase(env: &str) -> CliTypedResult<String> {
+    let passphrase = if let Ok(passphrase) = std::env::var(env) {
+        passphrase
+    } else {
+        let passphrase = read_passphrase("Enter new keystore passphrase: ", env)?;
+        let confirmation = read_passphrase("Confirm new keystore passphrase: ", env)?;
+        if passphrase != confirmation {
+            return Err(CliError::CommandArgumentError(
+                "Passphrases do not match".to_string(),
+            ));
+        }
+        passphrase
+    };
+
+    if passphrase.is_empty() {
+        return Err(CliError::CommandArgumentError(
+            "Keystore passphrase must not be empty".to_string(),
+        ));
+    }
+    Ok(passphrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::Uniform;
+
+    fn test_keystore(private_key: &KeystorePrivateKey) -> EncryptedKeystore {
+        // Cheap parameters keep the test fast, the format is the same
+        EncryptedKeystore::encrypt_with_params(
+            private_key,
+            "correct horse",
+            KdfParams::with_cost(1024, 1, 1),
+        )
+        .unwrap()
+    }
+
+    fn assert_round_trip(private_key: KeystorePrivateKey) {
+        let keystore = test_keystore(&private_key);
+        assert_eq!(keystore.key_scheme, private_key.scheme());
+        assert_eq!(
+            keystore.public_key,
+            private_key.encoded_public_key().unwrap()
+        );
+
+        let serialized = serde_json::to_string(&keystore).unwrap();
+        let deserialized: EncryptedKeystore = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, keystore);
+
+        let decrypted = deserialized.decrypt("correct horse").unwrap();
+        assert_eq!(decrypted.scheme(), private_key.scheme());
+        assert_eq!(decrypted.to_bytes(), private_key.to_bytes());
+        assert!(deserialized.decrypt("battery staple").is_err());
+    }
+
+    #[test]
+    fn test_keystore_round_trip() {
+        assert_round_trip(Ed25519PrivateKey::generate(&mut rand::rngs::OsRng).into());
+        assert_round_trip(secp256k1_ecdsa::PrivateKey::generate(&mut rand::rngs::OsRng).into());
+    }
+
+    #[test]
+    fn test_keystore_without_scheme_holds_ed25519_key() {
+        let private_key: KeystorePrivateKey =
+            Ed25519PrivateKey::generate(&mut rand::rngs::OsRng).into();
+        let mut keystore = serde_json::to_value(test_keystore(&private_key)).unwrap();
+        keystore.as_object_mut().unwrap().remove("key_scheme");
+
+        let keystore: EncryptedKeystore = serde_json::from_value(keystore).unwrap();
+        let decrypted = keystore.decrypt("correct horse").unwrap();
+        assert_eq!(
+            decrypted.into_ed25519().unwrap().to_bytes(),
+            private_key.into_ed25519().unwrap().to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_keystore_detects_tampering() {
+        let private_key = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng).into();
+        let mut keystore = test_keystore(&private_key);
+        keystore.public_key = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng)
+            .public_key()
+            .to_encoded_string()
+            .unwrap();
+        assert!(keystore.decrypt("correct horse").is_err());
+
+        // A key of one scheme can't be passed off as a key of another
+        let mut keystore = test_keystore(&private_key);
+        keystore.key_scheme = KeystoreKeyScheme::Secp256k1Ecdsa;
+        assert!(keystore.decrypt("correct horse").is_err());
+    }
+
+    #[test]
+    fn test_keystore_rejects_excessive_kdf_cost() {
+        let private_key = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng).into();
+        for (memory_kib, iterations, parallelism) in [
+            (MAX_KDF_MEMORY_KIB + 1, 1, 1),
+            (1024, MAX_KDF_ITERATIONS + 1, 1),
+            (1024, 1, MAX_KDF_PARALLELISM + 1),
+        ] {
+            let mut keystore = test_keystore(&private_key);
+            keystore.kdf.memory_kib = memory_kib;
+            keystore.kdf.iterations = iterations;
+            keystore.kdf.parallelism = parallelism;
+            assert!(matches!(
+                keystore.decrypt("correct horse"),
+                Err(CliError::UnexpectedError(err)) if err.contains("exceeds the maximum")
+            ));
+        }
+    }
+}
@@ -2,5 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod init;
+pub mod keystore;
+pub mod signer;
 pub mod types;
 pub mod utils;
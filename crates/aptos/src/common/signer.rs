@@ -0,0 +1,468 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signing transactions with the different kinds of keys a profile can hold
+//!
+//! Besides a single Ed25519 key, a profile can hold a Secp256k1 ECDSA key, or a k-of-n
+//! MultiEd25519 key set of which it holds only some of the private keys.  Signatures of the other
+//! keys of the set are collected from co-signers through [`PartialTransaction`] files.
+
+use crate::common::{
+    keystore::EncryptedKeystore,
+    types::{CliError, CliTypedResult},
+    utils::{read_from_file, write_to_file},
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1_ecdsa, PrivateKey, Signature, SigningKey,
+};
+use aptos_types::transaction::{
+    authenticator::{AnyPublicKey, AuthenticationKey},
+    RawTransaction, SignedTransaction,
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
+
+/// Signature scheme of the key held by a profile
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum KeyScheme {
+    #[default]
+    Ed25519,
+    Secp256k1Ecdsa,
+    MultiEd25519,
+}
+
+/// A k-of-n MultiEd25519 key set, of which only some private keys may be held locally
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MultiEd25519Config {
+    /// Public keys of the set, in order
+    pub public_keys: Vec<Ed25519PublicKey>,
+    /// Number of signatures required
+    pub threshold: u8,
+    /// Private keys of the set held by this profile
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub private_keys: Vec<Ed25519PrivateKey>,
+    /// Encrypted keystores holding private keys of the set, used like `private_keys`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keystores: Vec<PathBuf>,
+}
+
+impl MultiEd25519Config {
+    pub fn public_key(&self) -> CliTypedResult<MultiEd25519PublicKey> {
+        MultiEd25519PublicKey::new(self.public_keys.clone(), self.threshold).map_err(|err| {
+            CliError::CommandArgumentError(format!("Invalid MultiEd25519 key set: {}", err))
+        })
+    }
+
+    /// Number of private keys of the set held by this profile, encrypted or not
+    pub fn num_private_keys(&self) -> usize {
+        self.private_keys.len() + self.keystores.len()
+    }
+
+    /// Returns the private keys held by this profile, unlocking its keystores
+    pub fn unlock_private_keys(self) -> CliTypedResult<Vec<Ed25519PrivateKey>> {
+        let mut private_keys = self.private_keys;
+        for keystore in &self.keystores {
+            private_keys.push(EncryptedKeystore::unlock(keystore)?.into_ed25519()?);
+        }
+        Ok(private_keys)
+    }
+}
+
+/// Public key of an account, enough to build its transactions without unlocking its private key
+#[derive(Clone, Debug)]
+pub enum AccountPublicKey {
+    Ed25519(Ed25519PublicKey),
+    Secp256k1Ecdsa(secp256k1_ecdsa::PublicKey),
+    MultiEd25519(MultiEd25519PublicKey),
+}
+
+impl AccountPublicKey {
+    pub fn authentication_key(&self) -> AuthenticationKey {
+        match self {
+            AccountPublicKey::Ed25519(public_key) => AuthenticationKey::ed25519(public_key),
+            AccountPublicKey::Secp256k1Ecdsa(public_key) => {
+                AuthenticationKey::any_key(AnyPublicKey::secp256k1_ecdsa(public_key.clone()))
+            },
+            AccountPublicKey::MultiEd25519(public_key) => {
+                AuthenticationKey::multi_ed25519(public_key)
+            },
+        }
+    }
+
+    /// Builds a transaction with well formed but invalid signatures, as simulations require
+    pub fn simulation_transaction(
+        &self,
+        raw_txn: RawTransaction,
+    ) -> CliTypedResult<SignedTransaction> {
+        match self {
+            AccountPublicKey::Ed25519(public_key) => Ok(SignedTransaction::new(
+                raw_txn,
+                public_key.clone(),
+                invalid_signature(),
+            )),
+            AccountPublicKey::Secp256k1Ecdsa(public_key) => {
+                // r = s = 1 is a well formed signature, which doesn't verify for any message
+                let mut bytes = [0u8; 64];
+                bytes[31] = 1;
+                bytes[63] = 1;
+                let signature = secp256k1_ecdsa::Signature::try_from(bytes.as_ref())
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                Ok(SignedTransaction::new_secp256k1_ecdsa(
+                    raw_txn,
+                    public_key.clone(),
+                    signature,
+                ))
+            },
+            AccountPublicKey::MultiEd25519(public_key) => {
+                let signatures = (0..*public_key.threshold())
+                    .map(|index| (invalid_signature(), index))
+                    .collect();
+                let signature = MultiEd25519Signature::new(signatures)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                Ok(SignedTransaction::new_multisig(
+                    raw_txn,
+                    public_key.clone(),
+                    signature,
+                ))
+            },
+        }
+    }
+}
+
+/// Signs transactions for an account, with the authenticator matching its key
+#[derive(Debug)]
+pub enum AccountSigner {
+    Ed25519(Ed25519PrivateKey),
+    Secp256k1Ecdsa(secp256k1_ecdsa::PrivateKey),
+    MultiEd25519 {
+        public_key: MultiEd25519PublicKey,
+        /// The private keys of the set held locally
+        private_keys: Vec<Ed25519PrivateKey>,
+    },
+}
+
+impl AccountSigner {
+    pub fn public_key(&self) -> AccountPublicKey {
+        match self {
+            AccountSigner::Ed25519(private_key) => {
+                AccountPublicKey::Ed25519(private_key.public_key())
+            },
+            AccountSigner::Secp256k1Ecdsa(private_key) => {
+                AccountPublicKey::Secp256k1Ecdsa(private_key.public_key())
+            },
+            AccountSigner::MultiEd25519 { public_key, .. } => {
+                AccountPublicKey::MultiEd25519(public_key.clone())
+            },
+        }
+    }
+
+    pub fn authentication_key(&self) -> AuthenticationKey {
+        self.public_key().authentication_key()
+    }
+
+    /// Signs the transaction, failing if a MultiEd25519 key set doesn't hold enough private keys
+    pub fn sign(&self, raw_txn: RawTransaction) -> CliTypedResult<SignedTransaction> {
+        match self {
+            AccountSigner::Ed25519(private_key) => raw_txn
+                .sign(private_key, private_key.public_key())
+                .map(|txn| txn.into_inner())
+                .map_err(|err| CliError::UnexpectedError(err.to_string())),
+            AccountSigner::Secp256k1Ecdsa(private_key) => raw_txn
+                .sign_secp256k1_ecdsa(private_key, private_key.public_key())
+                .map(|txn| txn.into_inner())
+                .map_err(|err| CliError::UnexpectedError(err.to_string())),
+            AccountSigner::MultiEd25519 {
+                public_key,
+                private_keys,
+            } => {
+                let mut partial_txn = PartialTransaction::new(&raw_txn, public_key.clone())?;
+                partial_txn.sign(private_keys)?;
+                partial_txn.into_signed_transaction()
+            },
+        }
+    }
+
+    /// Builds a transaction with well formed but invalid signatures, as simulations require
+    pub fn simulation_transaction(
+        &self,
+        raw_txn: RawTransaction,
+    ) -> CliTypedResult<SignedTransaction> {
+        self.public_key().simulation_transaction(raw_txn)
+    }
+}
+
+fn invalid_signature() -> Ed25519Signature {
+    Ed25519Signature::try_from([0u8; 64].as_ref()).unwrap()
+}
+
+/// A MultiEd25519 transaction being co-signed, as passed between co-signers in a file
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PartialTransaction {
+    /// Hex encoded BCS of the `RawTransaction`
+    pub raw_transaction: String,
+    pub public_key: MultiEd25519PublicKey,
+    /// Signatures collected so far, by the index of their key in `public_key`
+    pub signatures: BTreeMap<u8, Ed25519Signature>,
+}
+
+impl PartialTransaction {
+    pub fn new(
+        raw_txn: &RawTransaction,
+        public_key: MultiEd25519PublicKey,
+    ) -> CliTypedResult<Self> {
+        let bytes = bcs::to_bytes(raw_txn).map_err(|err| CliError::BCS("raw_transaction", err))?;
+        Ok(PartialTransaction {
+            raw_transaction: hex::encode(bytes),
+            public_key,
+            signatures: BTreeMap::new(),
+        })
+    }
+
+    pub fn raw_transaction(&self) -> CliTypedResult<RawTransaction> {
+        let bytes = hex::decode(&self.raw_transaction)
+            .map_err(|err| CliError::UnableToParse("raw_transaction", err.to_string()))?;
+        bcs::from_bytes(&bytes).map_err(|err| CliError::BCS("raw_transaction", err))
+    }
+
+    /// Checks that each collected signature is a signature of the transaction by its key
+    pub fn verify_signatures(&self) -> CliTypedResult<()> {
+        let raw_txn = self.raw_transaction()?;
+        for (index, signature) in &self.signatures {
+            let public_key = self
+                .public_key
+                .public_keys()
+                .get(*index as usize)
+                .ok_or_else(|| {
+                    CliError::CommandArgumentError(format!(
+                        "Signature of key {} doesn't belong to the MultiEd25519 key set",
+                        index
+                    ))
+                })?;
+            signature.verify(&raw_txn, public_key).map_err(|_| {
+                CliError::CommandArgumentError(format!(
+                    "Invalid signature of key {} of the MultiEd25519 key set",
+                    index
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Signs with each of the private keys belonging to the key set, returning how many signed
+    ///
+    /// The signatures already collected are verified first, so a bad one isn't passed on.
+    pub fn sign(&mut self, private_keys: &[Ed25519PrivateKey]) -> CliTypedResult<usize> {
+        self.verify_signatures()?;
+        let raw_txn = self.raw_transaction()?;
+        let mut signed = 0;
+        for private_key in private_keys {
+            let public_key = private_key.public_key();
+            if let Some(index) = self
+                .public_key
+                .public_keys()
+                .iter()
+                .position(|key| *key == public_key)
+            {
+                let signature = private_key
+                    .sign(&raw_txn)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                self.signatures.insert(index as u8, signature);
+                signed += 1;
+            }
+        }
+        Ok(signed)
+    }
+
+    /// Adds the signatures collected in another copy of the same transaction
+    pub fn merge(&mut self, other: PartialTransaction) -> CliTypedResult<()> {
+        if other.raw_transaction != self.raw_transaction || other.public_key != self.public_key {
+            return Err(CliError::CommandArgumentError(
+                "Partial transactions are for different transactions or key sets".to_string(),
+            ));
+        }
+        other.verify_signatures()?;
+        self.signatures.extend(other.signatures);
+        Ok(())
+    }
+
+    /// Number of signatures still needed to reach the threshold
+    pub fn missing_signatures(&self) -> usize {
+        (*self.public_key.threshold() as usize).saturating_sub(self.signatures.len())
+    }
+
+    pub fn into_signed_transaction(self) -> CliTypedResult<SignedTransaction> {
+        let missing_signatures = self.missing_signatures();
+        if missing_signatures > 0 {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} more signatures of the MultiEd25519 key set are required, \
+                use '--partial-output-file' to collect them from co-signers",
+                missing_signatures
+            )));
+        }
+        let raw_txn = self.raw_transaction()?;
+        let signature = MultiEd25519Signature::new(
+            self.signatures
+                .into_iter()
+                .map(|(index, signature)| (signature, index))
+                .collect(),
+        )
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        Ok(SignedTransaction::new_multisig(
+            raw_txn,
+            self.public_key,
+            signature,
+        ))
+    }
+
+    pub fn load(path: &Path) -> CliTypedResult<Self> {
+        serde_json::from_slice(&read_from_file(path)?).map_err(|err| {
+            CliError::UnableToParse(
+                "partial transaction",
+                format!("{}: {}", path.display(), err),
+            )
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> CliTypedResult<()> {
+        let bytes = serde_json::to_vec_pretty(self)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        write_to_file(path, "partial transaction", &bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::Uniform;
+    use aptos_types::{
+        account_address::AccountAddress, chain_id::ChainId, transaction::TransactionPayload,
+    };
+
+    fn raw_txn() -> RawTransaction {
+        raw_txn_at(0)
+    }
+
+    fn raw_txn_at(sequence_number: u64) -> RawTransaction {
+        RawTransaction::new(
+            AccountAddress::ONE,
+            sequence_number,
+            TransactionPayload::Script(aptos_types::transaction::Script::new(
+                vec![],
+                vec![],
+                vec![],
+            )),
+            1000,
+            100,
+            u64::MAX,
+            ChainId::test(),
+        )
+    }
+
+    #[test]
+    fn test_simulation_transactions_are_well_formed_and_invalid() {
+        let mut rng = rand::rngs::OsRng;
+        let private_keys: Vec<_> = (0..3)
+            .map(|_| Ed25519PrivateKey::generate(&mut rng))
+            .collect();
+        let signers = vec![
+            AccountSigner::Ed25519(Ed25519PrivateKey::generate(&mut rng)),
+            AccountSigner::Secp256k1Ecdsa(secp256k1_ecdsa::PrivateKey::generate(&mut rng)),
+            AccountSigner::MultiEd25519 {
+                public_key: MultiEd25519PublicKey::new(
+                    private_keys.iter().map(|key| key.public_key()).collect(),
+                    2,
+                )
+                .unwrap(),
+                private_keys,
+            },
+        ];
+        for signer in signers {
+            let signed_txn = signer.sign(raw_txn()).unwrap();
+            assert!(signed_txn.verify_signature().is_ok());
+
+            let simulation_txn = signer.simulation_transaction(raw_txn()).unwrap();
+            assert!(simulation_txn.verify_signature().is_err());
+            let bytes = bcs::to_bytes(&simulation_txn).unwrap();
+            assert_eq!(
+                bcs::from_bytes::<SignedTransaction>(&bytes).unwrap(),
+                simulation_txn
+            );
+            assert_eq!(
+                simulation_txn.authenticator().sender().authentication_key(),
+                signer.authentication_key()
+            );
+        }
+    }
+
+    #[test]
+    fn test_partial_transaction_co_signing() {
+        let mut rng = rand::rngs::OsRng;
+        let private_keys: Vec<_> = (0..3)
+            .map(|_| Ed25519PrivateKey::generate(&mut rng))
+            .collect();
+        let public_key = MultiEd25519PublicKey::new(
+            private_keys.iter().map(|key| key.public_key()).collect(),
+            2,
+        )
+        .unwrap();
+
+        // Each co-signer signs their own copy of the transaction
+        let mut first = PartialTransaction::new(&raw_txn(), public_key.clone()).unwrap();
+        assert_eq!(first.sign(&private_keys[..1]).unwrap(), 1);
+        assert!(first.into_signed_transaction().is_err());
+
+        let mut first = PartialTransaction::new(&raw_txn(), public_key.clone()).unwrap();
+        first.sign(&private_keys[..1]).unwrap();
+        let serialized = serde_json::to_vec(&first).unwrap();
+        let mut second: PartialTransaction = serde_json::from_slice(&serialized).unwrap();
+        second.signatures.clear();
+        assert_eq!(second.sign(&private_keys[2..]).unwrap(), 1);
+
+        first.merge(second).unwrap();
+        assert_eq!(first.missing_signatures(), 0);
+        let signed_txn = first.into_signed_transaction().unwrap();
+        assert!(signed_txn.verify_signature().is_ok());
+    }
+
+    #[test]
+    fn test_partial_transaction_rejects_invalid_signatures() {
+        let mut rng = rand::rngs::OsRng;
+        let private_keys: Vec<_> = (0..3)
+            .map(|_| Ed25519PrivateKey::generate(&mut rng))
+            .collect();
+        let public_key = MultiEd25519PublicKey::new(
+            private_keys.iter().map(|key| key.public_key()).collect(),
+            2,
+        )
+        .unwrap();
+        let mut signed = PartialTransaction::new(&raw_txn(), public_key.clone()).unwrap();
+        signed.sign(&private_keys[..1]).unwrap();
+        let signature = signed.signatures[&0].clone();
+
+        // A signature filed under the index of another key
+        let mut forged = PartialTransaction::new(&raw_txn(), public_key.clone()).unwrap();
+        forged.signatures.insert(1, signature.clone());
+        let mut first = PartialTransaction::new(&raw_txn(), public_key.clone()).unwrap();
+        assert!(first.merge(forged).is_err());
+
+        // A signature of a key outside of the set
+        let mut forged = PartialTransaction::new(&raw_txn(), public_key.clone()).unwrap();
+        forged.signatures.insert(3, signature);
+        assert!(forged.sign(&private_keys[1..2]).is_err());
+
+        // A signature of another transaction
+        let mut other = PartialTransaction::new(&raw_txn_at(1), public_key.clone()).unwrap();
+        other.sign(&private_keys[..1]).unwrap();
+        let mut forged = PartialTransaction::new(&raw_txn(), public_key).unwrap();
+        forged.signatures = other.signatures;
+        assert!(forged.sign(&private_keys[1..2]).is_err());
+        assert!(first.merge(forged).is_err());
+    }
+}
@@ -5,11 +5,13 @@ use super::utils::fund_account;
 use crate::{
     common::{
         init::Network,
+        keystore::{EncryptedKeystore, KeystoreKeyScheme, KeystorePrivateKey},
+        signer::{AccountPublicKey, AccountSigner, MultiEd25519Config, PartialTransaction},
         utils::{
             check_if_file_exists, create_dir_if_not_exist, dir_default_to_current,
-            get_account_with_state, get_auth_key, get_sequence_number, parse_json_file,
-            prompt_yes_with_override, read_from_file, start_logger, to_common_result,
-            to_common_success_result, write_to_file, write_to_file_with_opts,
+            get_account_with_state, get_auth_key, get_expiration_time_secs, get_sequence_number,
+            parse_json_file, prompt_yes_with_override, read_from_file, start_logger,
+            to_common_result, to_common_success_result, write_to_file, write_to_file_with_opts,
             write_to_user_only_file,
         },
     },
@@ -19,9 +21,10 @@ use crate::{
 };
 use anyhow::Context;
 use aptos_crypto::{
-    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     encoding_type::{EncodingError, EncodingType},
-    x25519, PrivateKey, ValidCryptoMaterialStringExt,
+    hash::CryptoHash,
+    secp256k1_ecdsa, x25519, PrivateKey, ValidCryptoMaterialStringExt,
 };
 use aptos_gas_profiling::FrameName;
 use aptos_global_constants::adjust_gas_headroom;
@@ -35,13 +38,14 @@ use aptos_rest_client::{
 };
 use aptos_sdk::{
     transaction_builder::TransactionFactory,
-    types::{HardwareWalletAccount, HardwareWalletType, LocalAccount, TransactionSigner},
+    types::{HardwareWalletAccount, HardwareWalletType, TransactionSigner},
 };
 use aptos_types::{
     chain_id::ChainId,
     transaction::{
-        authenticator::AuthenticationKey, EntryFunction, MultisigTransactionPayload, Script,
-        SignedTransaction, TransactionArgument, TransactionPayload, TransactionStatus,
+        authenticator::AuthenticationKey, EntryFunction, MultisigTransactionPayload,
+        RawTransaction, Script, SignedTransaction, TransactionArgument, TransactionPayload,
+        TransactionStatus,
     },
 };
 use async_trait::async_trait;
@@ -67,6 +71,7 @@ pub const USER_AGENT: &str = concat!("aptos-cli/", env!("CARGO_PKG_VERSION"));
 const US_IN_SECS: u64 = 1_000_000;
 const ACCEPTED_CLOCK_SKEW_US: u64 = 5 * US_IN_SECS;
 pub const DEFAULT_EXPIRATION_SECS: u64 = 30;
+const HARDWARE_WALLET_EXPIRATION_SECS: u64 = 2 * 60;
 pub const DEFAULT_PROFILE: &str = "default";
 
 // Custom header value to identify the client
@@ -223,6 +228,7 @@ pub struct CliConfig {
 const CONFIG_FILE: &str = "config.yaml";
 const LEGACY_CONFIG_FILE: &str = "config.yml";
 pub const CONFIG_FOLDER: &str = ".aptos";
+const KEYSTORE_FOLDER: &str = "keystores";
 
 /// An individual profile
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -232,6 +238,16 @@ pub struct ProfileConfig {
     /// Private key for commands.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub private_key: Option<Ed25519PrivateKey>,
+    /// Encrypted keystore holding the Ed25519 or Secp256k1 ECDSA private key, used instead of
+    /// `private_key` or `secp256k1_private_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keystore: Option<PathBuf>,
+    /// Secp256k1 ECDSA private key for commands, used instead of `private_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secp256k1_private_key: Option<secp256k1_ecdsa::PrivateKey>,
+    /// MultiEd25519 key set for commands, used instead of `private_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multi_ed25519: Option<MultiEd25519Config>,
     /// Public key for commands
     #[serde(skip_serializing_if = "Option::is_none")]
     pub public_key: Option<Ed25519PublicKey>,
@@ -266,7 +282,7 @@ pub struct ProfileSummary {
 impl From<&ProfileConfig> for ProfileSummary {
     fn from(config: &ProfileConfig) -> Self {
         ProfileSummary {
-            has_private_key: config.private_key.is_some(),
+            has_private_key: config.has_private_key(),
             public_key: config.public_key.clone(),
             account: config.account,
             rest_url: config.rest_url.clone(),
@@ -275,6 +291,82 @@ impl From<&ProfileConfig> for ProfileSummary {
     }
 }
 
+impl ProfileConfig {
+    /// Whether the profile can sign, either with an embedded or an encrypted private key
+    pub fn has_private_key(&self) -> bool {
+        self.private_key.is_some()
+            || self.keystore.is_some()
+            || self.secp256k1_private_key.is_some()
+            || self.multi_ed25519.is_some()
+    }
+
+    /// Returns the Ed25519 private key of the profile, unlocking its keystore if it has one
+    pub fn unlock_private_key(self) -> CliTypedResult<Option<Ed25519PrivateKey>> {
+        if let Some(private_key) = self.private_key {
+            Ok(Some(private_key))
+        } else if let Some(keystore) = self.keystore {
+            EncryptedKeystore::unlock(&keystore)?
+                .into_ed25519()
+                .map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the public key of the profile without unlocking its private key, whichever kind
+    /// it is
+    pub fn account_public_key(&self) -> CliTypedResult<Option<AccountPublicKey>> {
+        if let Some(ref multi_ed25519) = self.multi_ed25519 {
+            Ok(Some(AccountPublicKey::MultiEd25519(
+                multi_ed25519.public_key()?,
+            )))
+        } else if let Some(ref private_key) = self.secp256k1_private_key {
+            Ok(Some(AccountPublicKey::Secp256k1Ecdsa(
+                private_key.public_key(),
+            )))
+        } else if let Some(ref keystore) = self.keystore {
+            let keystore = EncryptedKeystore::load(keystore)?;
+            let public_key = match keystore.key_scheme {
+                KeystoreKeyScheme::Ed25519 => {
+                    Ed25519PublicKey::from_encoded_string(&keystore.public_key)
+                        .map(AccountPublicKey::Ed25519)
+                },
+                KeystoreKeyScheme::Secp256k1Ecdsa => {
+                    secp256k1_ecdsa::PublicKey::from_encoded_string(&keystore.public_key)
+                        .map(AccountPublicKey::Secp256k1Ecdsa)
+                },
+            }
+            .map_err(|err| CliError::UnableToParse("keystore public key", err.to_string()))?;
+            Ok(Some(public_key))
+        } else if let Some(ref private_key) = self.private_key {
+            Ok(Some(AccountPublicKey::Ed25519(private_key.public_key())))
+        } else {
+            Ok(self.public_key.clone().map(AccountPublicKey::Ed25519))
+        }
+    }
+
+    /// Returns the signer for the key of the profile, whichever kind it is
+    pub fn signer(self) -> CliTypedResult<Option<AccountSigner>> {
+        if let Some(multi_ed25519) = self.multi_ed25519 {
+            Ok(Some(AccountSigner::MultiEd25519 {
+                public_key: multi_ed25519.public_key()?,
+                private_keys: multi_ed25519.unlock_private_keys()?,
+            }))
+        } else if let Some(private_key) = self.secp256k1_private_key {
+            Ok(Some(AccountSigner::Secp256k1Ecdsa(private_key)))
+        } else if let Some(keystore) = self.keystore {
+            Ok(Some(match EncryptedKeystore::unlock(&keystore)? {
+                KeystorePrivateKey::Ed25519(private_key) => AccountSigner::Ed25519(private_key),
+                KeystorePrivateKey::Secp256k1Ecdsa(private_key) => {
+                    AccountSigner::Secp256k1Ecdsa(private_key)
+                },
+            }))
+        } else {
+            Ok(self.private_key.map(AccountSigner::Ed25519))
+        }
+    }
+}
+
 impl Default for CliConfig {
     fn default() -> Self {
         CliConfig {
@@ -377,6 +469,13 @@ impl CliConfig {
         Ok(())
     }
 
+    /// Default location of a profile's keystore, in the keystores folder next to the config
+    pub fn default_keystore_path(profile: &str) -> CliTypedResult<PathBuf> {
+        Ok(Self::aptos_folder(ConfigSearchMode::CurrentDirAndParents)?
+            .join(KEYSTORE_FOLDER)
+            .join(format!("{}.json", profile)))
+    }
+
     /// Finds the current directory's .aptos folder
     fn aptos_folder(mode: ConfigSearchMode) -> CliTypedResult<PathBuf> {
         let global_config = GlobalConfig::load()?;
@@ -393,6 +492,8 @@ pub enum KeyType {
     X25519,
     /// A BLS12381 key for consensus
     Bls12381,
+    /// Secp256k1 ECDSA key used for signing
+    Secp256k1Ecdsa,
 }
 
 impl Display for KeyType {
@@ -401,6 +502,7 @@ impl Display for KeyType {
             KeyType::Ed25519 => "ed25519",
             KeyType::X25519 => "x25519",
             KeyType::Bls12381 => "bls12381",
+            KeyType::Secp256k1Ecdsa => "secp256k1-ecdsa",
         };
         write!(f, "{}", str)
     }
@@ -414,7 +516,10 @@ impl FromStr for KeyType {
             "ed25519" => Ok(KeyType::Ed25519),
             "x25519" => Ok(KeyType::X25519),
             "bls12381" => Ok(KeyType::Bls12381),
-            _ => Err("Invalid key type: Must be one of [ed25519, x25519]"),
+            "secp256k1-ecdsa" | "secp256k1_ecdsa" => Ok(KeyType::Secp256k1Ecdsa),
+            _ => {
+                Err("Invalid key type: Must be one of [ed25519, x25519, bls12381, secp256k1-ecdsa]")
+            },
         }
     }
 }
@@ -817,7 +922,11 @@ impl PrivateKeyInputOptions {
             profile.profile_name(),
             ConfigSearchMode::CurrentDirAndParents,
         )?
-        .map(|p| (p.private_key, p.account))
+        .map(|p| {
+            let account = p.account;
+            p.unlock_private_key().map(|key| (key, account))
+        })
+        .transpose()?
         {
             match (maybe_address, maybe_config_address) {
                 (Some(address), _) => Ok((key, address)),
@@ -846,7 +955,8 @@ impl PrivateKeyInputOptions {
             profile.profile_name(),
             ConfigSearchMode::CurrentDirAndParents,
         )?
-        .map(|p| p.private_key)
+        .map(ProfileConfig::unlock_private_key)
+        .transpose()?
         {
             Ok(private_key)
         } else {
@@ -867,6 +977,23 @@ impl PrivateKeyInputOptions {
             self.private_key.clone(),
         )
     }
+
+    /// Extract a Secp256k1 ECDSA private key from CLI args
+    pub fn extract_secp256k1_private_key_cli(
+        &self,
+        encoding: EncodingType,
+    ) -> CliTypedResult<Option<secp256k1_ecdsa::PrivateKey>> {
+        if let Some(ref file) = self.private_key_file {
+            Ok(Some(
+                encoding.load_key("--private-key-file", file.as_path())?,
+            ))
+        } else if let Some(ref key) = self.private_key {
+            let key = key.as_bytes().to_vec();
+            Ok(Some(encoding.decode_key("--private-key", key)?))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 // Extract the public key by deriving private key, fall back to public key from profile
@@ -1505,6 +1632,14 @@ pub struct TransactionOptions {
     /// flamegraphs that reflect the gas usage.
     #[clap(long)]
     pub(crate) profile_gas: bool,
+
+    /// Instead of submitting, sign with the locally held keys of a MultiEd25519 profile and
+    /// write the partially signed transaction to this file
+    ///
+    /// Co-signers add their signatures with `aptos account sign-partial`, and the transaction is
+    /// submitted with `aptos account submit-partial`.
+    #[clap(long, value_parser)]
+    pub(crate) partial_output_file: Option<PathBuf>,
 }
 
 impl TransactionOptions {
@@ -1522,7 +1657,7 @@ impl TransactionOptions {
             self.profile_options.profile_name(),
             ConfigSearchMode::CurrentDirAndParents,
         )? {
-            if profile.private_key.is_some() {
+            if profile.has_private_key() {
                 Ok(AccountType::Local)
             } else {
                 Ok(AccountType::HardwareWallet)
@@ -1553,8 +1688,46 @@ impl TransactionOptions {
         )
     }
 
+    /// Retrieves the signer for the key of the sender, whichever kind it is, and its address
+    pub fn get_signer_and_address(&self) -> CliTypedResult<(AccountSigner, AccountAddress)> {
+        if let Some(private_key) = self
+            .private_key_options
+            .extract_private_key_cli(self.encoding_options.encoding)?
+        {
+            let address = self
+                .sender_account
+                .unwrap_or_else(|| account_address_from_public_key(&private_key.public_key()));
+            return Ok((AccountSigner::Ed25519(private_key), address));
+        }
+
+        let profile = CliConfig::load_profile(
+            self.profile_options.profile_name(),
+            ConfigSearchMode::CurrentDirAndParents,
+        )?;
+        let (signer, profile_address) = match profile {
+            Some(profile) => {
+                let address = profile.account;
+                (profile.signer()?, address)
+            },
+            None => (None, None),
+        };
+        let signer = signer.ok_or_else(|| {
+            CliError::CommandArgumentError(
+                "One of ['--private-key', '--private-key-file'] must be used".to_string(),
+            )
+        })?;
+        let address = self
+            .sender_account
+            .or(profile_address)
+            .unwrap_or_else(|| signer.authentication_key().account_address());
+        Ok((signer, address))
+    }
+
     pub fn sender_address(&self) -> CliTypedResult<AccountAddress> {
-        Ok(self.get_key_and_address()?.1)
+        match self.get_transaction_account_type()? {
+            AccountType::Local => Ok(self.get_signer_and_address()?.1),
+            AccountType::HardwareWallet => Ok(self.get_public_key_and_address()?.1),
+        }
     }
 
     pub fn get_public_key(&self) -> CliTypedResult<Ed25519PublicKey> {
@@ -1588,8 +1761,165 @@ impl TransactionOptions {
         payload: TransactionPayload,
     ) -> CliTypedResult<Transaction> {
         let client = self.rest_client()?;
-        let (sender_public_key, sender_address) = self.get_public_key_and_address()?;
+        let (raw_txn, signer) = self.build_transaction(&client, payload).await?;
 
+        // Sign and submit transaction
+        let transaction = match signer {
+            Some(signer) => signer.sign(raw_txn)?,
+            None => HardwareWalletAccount::new(
+                raw_txn.sender(),
+                self.get_public_key()?,
+                self.profile_options
+                    .derivation_path()
+                    .expect("derivative path is missing from profile")
+                    .unwrap(),
+                HardwareWalletType::Ledger,
+                // Only used when building transactions, the raw transaction is already built
+                0,
+            )
+            .sign_transaction(raw_txn)?,
+        };
+        let response = client
+            .submit_and_wait(&transaction)
+            .await
+            .map_err(|err| CliError::ApiError(err.to_string()))?;
+
+        Ok(response.into_inner())
+    }
+
+    /// Signs with the locally held keys of a MultiEd25519 profile, and writes the partially
+    /// signed transaction to `output_file` for co-signers.
+    pub async fn write_partial_transaction(
+        &self,
+        payload: TransactionPayload,
+        output_file: &Path,
+    ) -> CliTypedResult<TransactionSummary> {
+        let client = self.rest_client()?;
+        let (raw_txn, signer) = self.build_transaction(&client, payload).await?;
+        let (public_key, private_keys) = match signer {
+            Some(AccountSigner::MultiEd25519 {
+                public_key,
+                private_keys,
+            }) => (public_key, private_keys),
+            _ => {
+                return Err(CliError::CommandArgumentError(
+                    "'--partial-output-file' can only be used with a MultiEd25519 profile"
+                        .to_string(),
+                ))
+            },
+        };
+
+        let sender = raw_txn.sender();
+        let transaction_hash = raw_txn.hash();
+        let mut partial_txn = PartialTransaction::new(&raw_txn, public_key)?;
+        partial_txn.sign(&private_keys)?;
+        partial_txn.save(output_file)?;
+        eprintln!(
+            "Transaction signed with {} local keys, {} more signatures are required",
+            partial_txn.signatures.len(),
+            partial_txn.missing_signatures()
+        );
+
+        // The transaction isn't signed yet, so its hash is the one of the raw transaction
+        Ok(TransactionSummary {
+            transaction_hash: transaction_hash.into(),
+            gas_used: None,
+            gas_unit_price: None,
+            pending: Some(true),
+            sender: Some(sender),
+            sequence_number: None,
+            success: None,
+            timestamp_us: None,
+            version: None,
+            vm_status: None,
+        })
+    }
+
+    /// Builds the transaction for the payload without signing it, to be signed offline
+    ///
+    /// Only the public key of the sender is needed, so nothing is unlocked and the private key
+    /// doesn't have to be on this machine.  The transaction expires `--expiration-secs` from
+    /// now whatever the kind of account, as it has to be carried to the signing machine and back
+    /// in that time.
+    pub async fn build_unsigned_transaction(
+        &self,
+        payload: TransactionPayload,
+    ) -> CliTypedResult<RawTransaction> {
+        let client = self.rest_client()?;
+        let profile = CliConfig::load_profile(
+            self.profile_options.profile_name(),
+            ConfigSearchMode::CurrentDirAndParents,
+        )?;
+        let (public_key, profile_address) = match profile {
+            Some(profile) => (profile.account_public_key()?, profile.account),
+            None => (None, None),
+        };
+        let public_key = public_key.ok_or_else(|| {
+            CliError::CommandArgumentError(
+                "The profile has no key to build the transaction with".to_string(),
+            )
+        })?;
+        let sender_address = self
+            .sender_account
+            .or(profile_address)
+            .unwrap_or_else(|| public_key.authentication_key().account_address());
+        self.build_raw_transaction(
+            &client,
+            payload,
+            sender_address,
+            &public_key,
+            self.gas_options.expiration_secs,
+        )
+        .await
+    }
+
+    /// Builds the transaction for the payload, and returns it with the signer of the sender.
+    /// There is no signer for hardware wallets.
+    async fn build_transaction(
+        &self,
+        client: &Client,
+        payload: TransactionPayload,
+    ) -> CliTypedResult<(RawTransaction, Option<AccountSigner>)> {
+        let (signer, sender_address, public_key) = match self.get_transaction_account_type()? {
+            AccountType::Local => {
+                let (signer, sender_address) = self.get_signer_and_address()?;
+                let public_key = signer.public_key();
+                (Some(signer), sender_address, public_key)
+            },
+            AccountType::HardwareWallet => {
+                let (public_key, sender_address) = self.get_public_key_and_address()?;
+                (None, sender_address, AccountPublicKey::Ed25519(public_key))
+            },
+        };
+
+        // Hardware wallets need time for the user to confirm the transaction on the device
+        let expiration_secs = if signer.is_some() {
+            self.gas_options.expiration_secs
+        } else {
+            HARDWARE_WALLET_EXPIRATION_SECS
+        };
+        let raw_txn = self
+            .build_raw_transaction(
+                client,
+                payload,
+                sender_address,
+                &public_key,
+                expiration_secs,
+            )
+            .await?;
+        Ok((raw_txn, signer))
+    }
+
+    /// Builds the transaction for the payload, estimating the gas with a simulation unless
+    /// `--max-gas` is given
+    async fn build_raw_transaction(
+        &self,
+        client: &Client,
+        payload: TransactionPayload,
+        sender_address: AccountAddress,
+        public_key: &AccountPublicKey,
+        expiration_secs: u64,
+    ) -> CliTypedResult<RawTransaction> {
         // Ask to confirm price if the gas unit price is estimated above the lowest value when
         // it is automatically estimated
         let ask_to_confirm_price;
@@ -1604,7 +1934,7 @@ impl TransactionOptions {
         };
 
         // Get sequence number for account
-        let (account, state) = get_account_with_state(&client, sender_address).await?;
+        let (account, state) = get_account_with_state(client, sender_address).await?;
         let sequence_number = account.sequence_number;
 
         // Retrieve local time, and ensure it's within an expected skew of the blockchain
@@ -1619,7 +1949,7 @@ impl TransactionOptions {
         if now_usecs < state.timestamp_usecs - ACCEPTED_CLOCK_SKEW_US {
             eprintln!("Local clock is is skewed from blockchain clock.  Clock is more than {} seconds behind the blockchain {}", ACCEPTED_CLOCK_SKEW_US, state.timestamp_usecs / US_IN_SECS );
         }
-        let expiration_time_secs = now + self.gas_options.expiration_secs;
+        let expiration_time_secs = get_expiration_time_secs(client, now, expiration_secs).await?;
 
         let chain_id = ChainId::new(state.chain_id);
         // TODO: Check auth key against current private key and provide a better message
@@ -1642,11 +1972,7 @@ impl TransactionOptions {
                 .expiration_timestamp_secs(expiration_time_secs)
                 .build();
 
-            let signed_transaction = SignedTransaction::new(
-                unsigned_transaction,
-                sender_public_key.clone(),
-                Ed25519Signature::try_from([0u8; 64].as_ref()).unwrap(),
-            );
+            let signed_transaction = public_key.simulation_transaction(unsigned_transaction)?;
 
             let txns = client
                 .simulate_with_gas_estimation(&signed_transaction, true, false)
@@ -1678,48 +2004,14 @@ impl TransactionOptions {
             adjusted_max_gas
         };
 
-        // Sign and submit transaction
-        let transaction_factory = TransactionFactory::new(chain_id)
+        Ok(TransactionFactory::new(chain_id)
             .with_gas_unit_price(gas_unit_price)
             .with_max_gas_amount(max_gas)
-            .with_transaction_expiration_time(self.gas_options.expiration_secs);
-
-        match self.get_transaction_account_type() {
-            Ok(AccountType::Local) => {
-                let (private_key, _) = self.get_key_and_address()?;
-                let sender_account =
-                    &mut LocalAccount::new(sender_address, private_key, sequence_number);
-                let transaction = sender_account
-                    .sign_with_transaction_builder(transaction_factory.payload(payload));
-                let response = client
-                    .submit_and_wait(&transaction)
-                    .await
-                    .map_err(|err| CliError::ApiError(err.to_string()))?;
-
-                Ok(response.into_inner())
-            },
-            Ok(AccountType::HardwareWallet) => {
-                let sender_account = &mut HardwareWalletAccount::new(
-                    sender_address,
-                    sender_public_key,
-                    self.profile_options
-                        .derivation_path()
-                        .expect("derivative path is missing from profile")
-                        .unwrap(),
-                    HardwareWalletType::Ledger,
-                    sequence_number,
-                );
-                let transaction = sender_account
-                    .sign_with_transaction_builder(transaction_factory.payload(payload))?;
-                let response = client
-                    .submit_and_wait(&transaction)
-                    .await
-                    .map_err(|err| CliError::ApiError(err.to_string()))?;
-
-                Ok(response.into_inner())
-            },
-            Err(err) => Err(err),
-        }
+            .payload(payload)
+            .sender(sender_address)
+            .sequence_number(sequence_number)
+            .expiration_timestamp_secs(expiration_time_secs)
+            .build())
     }
 
     /// Simulate the transaction locally using the debugger, with the gas profiler enabled.
@@ -1737,7 +2029,7 @@ impl TransactionOptions {
         const DEFAULT_GAS_UNIT_PRICE: u64 = 100;
         const DEFAULT_MAX_GAS: u64 = 2_000_000;
 
-        let (sender_key, sender_address) = self.get_key_and_address()?;
+        let (signer, sender_address) = self.get_signer_and_address()?;
         let gas_unit_price = self
             .gas_options
             .gas_unit_price
@@ -1762,13 +2054,23 @@ impl TransactionOptions {
         });
 
         // Create and sign the transaction
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+            .as_secs();
+        let expiration_time_secs =
+            get_expiration_time_secs(&client, now, self.gas_options.expiration_secs).await?;
         let transaction_factory = TransactionFactory::new(chain_id)
             .with_gas_unit_price(gas_unit_price)
-            .with_max_gas_amount(max_gas)
-            .with_transaction_expiration_time(self.gas_options.expiration_secs);
-        let sender_account = &mut LocalAccount::new(sender_address, sender_key, sequence_number);
-        let transaction =
-            sender_account.sign_with_transaction_builder(transaction_factory.payload(payload));
+            .with_max_gas_amount(max_gas);
+        let transaction = signer.sign(
+            transaction_factory
+                .payload(payload)
+                .sender(sender_address)
+                .sequence_number(sequence_number)
+                .expiration_timestamp_secs(expiration_time_secs)
+                .build(),
+        )?;
         let hash = transaction.clone().committed_hash();
 
         // Execute the transaction using the debugger
@@ -19,6 +19,7 @@ use aptos_types::{
     account_address::create_multisig_account_address,
     chain_id::ChainId,
     on_chain_config::{FeatureFlag, Features},
+    timestamp::TimestampResource,
     transaction::{authenticator::AuthenticationKey, TransactionPayload},
 };
 use itertools::Itertools;
@@ -28,6 +29,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
 use std::{
+    cmp::max,
     collections::BTreeMap,
     env,
     fs::OpenOptions,
@@ -303,6 +305,24 @@ pub async fn get_feature_flag(client: &Client, flag: FeatureFlag) -> CliTypedRes
     Ok(features.is_enabled(flag))
 }
 
+/// Gives the expiration time of a transaction built at `now`, counted from the on-chain time
+/// when that is later, since the on-chain time of a local testnet may have been moved forward
+pub async fn get_expiration_time_secs(
+    client: &Client,
+    now: u64,
+    expiration_secs: u64,
+) -> CliTypedResult<u64> {
+    let timestamp = client
+        .get_account_resource_bcs::<TimestampResource>(
+            CORE_CODE_ADDRESS,
+            "0x1::timestamp::CurrentTimeMicroseconds",
+        )
+        .await?
+        .into_inner();
+    let on_chain_now = Duration::from_micros(timestamp.timestamp.microseconds).as_secs();
+    Ok(max(now, on_chain_now) + expiration_secs)
+}
+
 /// Retrieves the chain id from the rest client
 pub async fn chain_id(rest_client: &Client) -> CliTypedResult<ChainId> {
     let state = rest_client
@@ -476,7 +496,8 @@ pub fn start_logger(level: Level) {
     logger.build();
 }
 
-/// For transaction payload and options, either get gas profile or submit for execution.
+/// For transaction payload and options, either get gas profile, write it for co-signers, or
+/// submit for execution.
 pub async fn profile_or_submit(
     payload: TransactionPayload,
     txn_options_ref: &TransactionOptions,
@@ -484,6 +505,11 @@ pub async fn profile_or_submit(
     // Profile gas if needed.
     if txn_options_ref.profile_gas {
         txn_options_ref.profile_gas(payload).await
+    } else if let Some(output_file) = &txn_options_ref.partial_output_file {
+        // Leave the transaction for co-signers to sign.
+        txn_options_ref
+            .write_partial_transaction(payload, output_file)
+            .await
     } else {
         // Otherwise submit the transaction.
         txn_options_ref
@@ -15,6 +15,7 @@ pub mod op;
 pub mod stake;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod test;
+pub mod txn;
 pub mod update;
 
 use crate::common::{
@@ -49,6 +50,8 @@ pub enum Tool {
     Node(node::NodeTool),
     #[clap(subcommand)]
     Stake(stake::StakeTool),
+    #[clap(subcommand)]
+    Txn(txn::TxnTool),
     Update(update::UpdateTool),
 }
 
@@ -68,6 +71,7 @@ impl Tool {
             Multisig(tool) => tool.execute().await,
             Node(tool) => tool.execute().await,
             Stake(tool) => tool.execute().await,
+            Txn(tool) => tool.execute().await,
             Update(tool) => tool.execute_serialized().await,
         }
     }
@@ -8,6 +8,8 @@ mod manifest;
 pub mod package_hooks;
 mod show;
 pub mod stored_package;
+mod test_gas_schedule;
+mod watch;
 
 use crate::{
     account::derive_resource_account::ResourceAccountSeed,
@@ -29,6 +31,7 @@ use crate::{
         coverage::SummaryCoverage,
         disassembler::Disassemble,
         manifest::{Dependency, ManifestNamedAddress, MovePackageManifest, PackageInfo},
+        test_gas_schedule::AptosTestGasSchedule,
     },
     CliCommand, CliResult,
 };
@@ -37,7 +40,7 @@ use aptos_framework::{
     docgen::DocgenOptions, extended_checks, natives::code::UpgradePolicy, prover::ProverOptions,
     BuildOptions, BuiltPackage,
 };
-use aptos_gas_schedule::{MiscGasParameters, NativeGasParameters};
+use aptos_gas_schedule::{InitialGasSchedule, MiscGasParameters, NativeGasParameters};
 use aptos_rest_client::aptos_api_types::{
     EntryFunctionId, HexEncodedBytes, IdentifierWrapper, MoveModuleId,
 };
@@ -53,17 +56,21 @@ use move_cli::{self, base::test::UnitTestResult};
 use move_command_line_common::env::MOVE_HOME;
 use move_core_types::{identifier::Identifier, language_storage::ModuleId, u256::U256};
 use move_package::{
-    source_package::layout::SourcePackageLayout, BuildConfig, CompilerConfig, CompilerVersion,
+    resolution::lock_file::LockedPackage, source_package::layout::SourcePackageLayout, BuildConfig,
+    CompilerConfig, CompilerVersion,
 };
-use move_unit_test::UnitTestingConfig;
+use move_unit_test::{gas_meter::TestGasSchedule, UnitTestingConfig};
+use move_vm_runtime::native_functions::NativeFunctionTable;
 pub use package_hooks::*;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{
     collections::BTreeMap,
     fmt::{Display, Formatter},
+    io::Write,
     path::{Path, PathBuf},
     str::FromStr,
+    sync::Arc,
 };
 pub use stored_package::*;
 use tokio::task;
@@ -89,6 +96,7 @@ pub enum MoveTool {
     Download(DownloadPackage),
     Init(InitPackage),
     List(ListPackage),
+    Lock(LockPackage),
     Prove(ProvePackage),
     Publish(PublishPackage),
     Run(RunFunction),
@@ -96,6 +104,7 @@ pub enum MoveTool {
     #[clap(subcommand, hide = true)]
     Show(show::ShowTool),
     Test(TestPackage),
+    Vendor(VendorPackage),
     VerifyPackage(VerifyPackage),
     View(ViewFunction),
 }
@@ -120,12 +129,14 @@ impl MoveTool {
             MoveTool::Download(tool) => tool.execute_serialized().await,
             MoveTool::Init(tool) => tool.execute_serialized_success().await,
             MoveTool::List(tool) => tool.execute_serialized().await,
+            MoveTool::Lock(tool) => tool.execute_serialized().await,
             MoveTool::Prove(tool) => tool.execute_serialized().await,
             MoveTool::Publish(tool) => tool.execute_serialized().await,
             MoveTool::Run(tool) => tool.execute_serialized().await,
             MoveTool::RunScript(tool) => tool.execute_serialized().await,
             MoveTool::Show(tool) => tool.execute_serialized().await,
             MoveTool::Test(tool) => tool.execute_serialized().await,
+            MoveTool::Vendor(tool) => tool.execute_serialized().await,
             MoveTool::VerifyPackage(tool) => tool.execute_serialized().await,
             MoveTool::View(tool) => tool.execute_serialized().await,
         }
@@ -296,20 +307,24 @@ pub struct CompilePackage {
     #[clap(long)]
     pub(crate) save_metadata: bool,
 
+    /// Keep watching the package and its dependencies, and compile again on every change
+    ///
+    /// The whole package is built on start and when a manifest or a dependency changes.
+    /// Otherwise, only the changed modules and the modules depending on them are compiled again,
+    /// unless `--save-metadata` is set.  After each compilation, the modules that changed since
+    /// the previous one are listed.  Stop watching with Ctrl-C.
+    #[clap(long)]
+    pub(crate) watch: bool,
+
     #[clap(flatten)]
     pub(crate) included_artifacts_args: IncludedArtifactsArgs,
     #[clap(flatten)]
     pub(crate) move_options: MovePackageDir,
 }
 
-#[async_trait]
-impl CliCommand<Vec<String>> for CompilePackage {
-    fn command_name(&self) -> &'static str {
-        "CompilePackage"
-    }
-
-    async fn execute(self) -> CliTypedResult<Vec<String>> {
-        let build_options = BuildOptions {
+impl CompilePackage {
+    pub(crate) fn build_options(&self) -> BuildOptions {
+        BuildOptions {
             install_dir: self.move_options.output_dir.clone(),
             ..self
                 .included_artifacts_args
@@ -323,8 +338,21 @@ impl CliCommand<Vec<String>> for CompilePackage {
                     self.move_options.skip_attribute_checks,
                     self.move_options.check_test_code,
                 )
-        };
-        let pack = BuiltPackage::build(self.move_options.get_package_path()?, build_options)
+        }
+    }
+}
+
+#[async_trait]
+impl CliCommand<Vec<String>> for CompilePackage {
+    fn command_name(&self) -> &'static str {
+        "CompilePackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<String>> {
+        if self.watch {
+            return watch::watch_compile(self).await;
+        }
+        let pack = BuiltPackage::build(self.move_options.get_package_path()?, self.build_options())
             .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
         if self.save_metadata {
             pack.extract_metadata_and_save()?;
@@ -447,17 +475,29 @@ pub struct TestPackage {
     /// Dump storage state on failure.
     #[clap(long = "dump")]
     pub dump_state: bool,
-}
 
-#[async_trait]
-impl CliCommand<&'static str> for TestPackage {
-    fn command_name(&self) -> &'static str {
-        "TestPackage"
-    }
+    /// Keep watching the package and its dependencies, and run the tests again on every change
+    ///
+    /// Dependencies are only compiled again when they change.  Only the tests of modules that
+    /// depend, directly or not, on a changed module are run again, and the results are compared
+    /// with the previous run.  Compiler warnings are shown but don't block the tests in this
+    /// mode.  Stop watching with Ctrl-C.
+    #[clap(long, conflicts_with = "compute_coverage")]
+    pub watch: bool,
+
+    /// Run the tests with the Aptos gas schedule, and report the execution, IO and storage gas
+    /// used by each test
+    ///
+    /// Tests are charged like transactions running them would be.  Tests with an
+    /// `#[expected_gas_below=<gas>]` attribute fail if they use at least that much gas.  Without
+    /// this flag, gas isn't measured and these budgets aren't checked.
+    #[clap(long)]
+    pub gas_report: bool,
+}
 
-    async fn execute(self) -> CliTypedResult<&'static str> {
-        let known_attributes = extended_checks::get_all_attribute_names();
-        let mut config = BuildConfig {
+impl TestPackage {
+    pub(crate) fn build_config(&self) -> BuildConfig {
+        BuildConfig {
             dev_mode: self.move_options.dev,
             additional_named_addresses: self.move_options.named_addresses(),
             test_mode: true,
@@ -465,35 +505,76 @@ impl CliCommand<&'static str> for TestPackage {
             install_dir: self.move_options.output_dir.clone(),
             skip_fetch_latest_git_deps: self.move_options.skip_fetch_latest_git_deps,
             compiler_config: CompilerConfig {
-                known_attributes: known_attributes.clone(),
+                known_attributes: extended_checks::get_all_attribute_names().clone(),
                 skip_attribute_checks: self.move_options.skip_attribute_checks,
                 compiler_version: self.move_options.compiler_version,
                 ..Default::default()
             },
             ..Default::default()
+        }
+    }
+
+    pub(crate) fn unit_testing_config(&self) -> UnitTestingConfig {
+        UnitTestingConfig {
+            filter: self.filter.clone(),
+            report_stacktrace_on_abort: true,
+            report_storage_on_error: self.dump_state,
+            ignore_compile_warnings: self.ignore_compile_warnings,
+            gas_schedule: self
+                .gas_report
+                .then(|| Arc::new(AptosTestGasSchedule::latest()) as Arc<dyn TestGasSchedule>),
+            ..UnitTestingConfig::default_with_bound(None)
+        }
+    }
+
+    pub(crate) fn natives(&self) -> NativeFunctionTable {
+        let (native_gas_params, misc_gas_params) = if self.gas_report {
+            (NativeGasParameters::initial(), MiscGasParameters::initial())
+        } else {
+            // TODO(Gas): we may want to switch to non-zero costs in the future
+            (NativeGasParameters::zeros(), MiscGasParameters::zeros())
         };
+        aptos_debug_natives::aptos_debug_natives(native_gas_params, misc_gas_params)
+    }
+
+    pub(crate) fn run_tests<W: Write + Send>(
+        &self,
+        path: &Path,
+        config: BuildConfig,
+        unit_testing_config: UnitTestingConfig,
+        writer: &mut W,
+    ) -> CliTypedResult<UnitTestResult> {
+        move_cli::base::test::run_move_unit_tests(
+            path,
+            config,
+            unit_testing_config,
+            self.natives(),
+            None,
+            self.compute_coverage,
+            writer,
+        )
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to run tests: {:#}", err)))
+    }
+}
 
+#[async_trait]
+impl CliCommand<&'static str> for TestPackage {
+    fn command_name(&self) -> &'static str {
+        "TestPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<&'static str> {
+        if self.watch {
+            return watch::watch_tests(self).await;
+        }
+        let mut config = self.build_config();
         let path = self.move_options.get_package_path()?;
-        let result = move_cli::base::test::run_move_unit_tests(
+        let result = self.run_tests(
             path.as_path(),
             config.clone(),
-            UnitTestingConfig {
-                filter: self.filter.clone(),
-                report_stacktrace_on_abort: true,
-                report_storage_on_error: self.dump_state,
-                ignore_compile_warnings: self.ignore_compile_warnings,
-                ..UnitTestingConfig::default_with_bound(None)
-            },
-            // TODO(Gas): we may want to switch to non-zero costs in the future
-            aptos_debug_natives::aptos_debug_natives(
-                NativeGasParameters::zeros(),
-                MiscGasParameters::zeros(),
-            ),
-            None,
-            self.compute_coverage,
+            self.unit_testing_config(),
             &mut std::io::stdout(),
-        )
-        .map_err(|err| CliError::UnexpectedError(format!("Failed to run tests: {:#}", err)))?;
+        )?;
 
         // Print coverage summary if --coverage is set
         if self.compute_coverage {
@@ -885,7 +966,7 @@ impl CliCommand<TransactionSummary> for CreateObjectAndPublishPackage {
     }
 
     async fn execute(mut self) -> CliTypedResult<TransactionSummary> {
-        let sender_address = self.txn_options.get_public_key_and_address()?.1;
+        let sender_address = self.txn_options.sender_address()?;
         let sequence_number = self.txn_options.sequence_number(sender_address).await? + 1;
         let object_address = create_object_code_deployment_address(sender_address, sequence_number);
 
@@ -1383,6 +1464,72 @@ impl CliCommand<&'static str> for CleanPackage {
     }
 }
 
+/// Pins the dependencies of a package in its `Move.lock`
+///
+/// Resolves the dependencies of the package again, including dev dependencies, and writes a
+/// `Move.lock` pinning each git dependency to its exact commit, and each git and on-chain
+/// dependency to the digest of its sources.  A package with a `Move.lock` is always built against
+/// it, so this needs to be run again after changing the dependencies.
+#[derive(Parser)]
+pub struct LockPackage {
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<Vec<LockedPackage>> for LockPackage {
+    fn command_name(&self) -> &'static str {
+        "LockPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<LockedPackage>> {
+        let lock_file = dependency_build_config(&self.move_options)
+            .update_lock_file(
+                self.move_options.get_package_path()?.as_path(),
+                &mut std::io::stderr(),
+            )
+            .map_err(|err| CliError::MoveCompilationError(format!("{:#}", err)))?;
+        Ok(lock_file.packages)
+    }
+}
+
+/// Copies the dependencies of a package into its `vendor` directory
+///
+/// Vendored dependencies are used instead of downloading git and on-chain dependencies, so
+/// that the package builds without network access.  The package is locked first if it has no
+/// `Move.lock` yet, and vendored dependencies are checked against the lock on every build.
+#[derive(Parser)]
+pub struct VendorPackage {
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<Vec<PathBuf>> for VendorPackage {
+    fn command_name(&self) -> &'static str {
+        "VendorPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<PathBuf>> {
+        dependency_build_config(&self.move_options)
+            .vendor_dependencies(
+                self.move_options.get_package_path()?.as_path(),
+                &mut std::io::stderr(),
+            )
+            .map_err(|err| CliError::MoveCompilationError(format!("{:#}", err)))
+    }
+}
+
+/// Configuration to resolve the dependencies of a package with
+fn dependency_build_config(move_options: &MovePackageDir) -> BuildConfig {
+    BuildConfig {
+        additional_named_addresses: move_options.named_addresses(),
+        skip_fetch_latest_git_deps: move_options.skip_fetch_latest_git_deps,
+        install_dir: move_options.output_dir.clone(),
+        ..Default::default()
+    }
+}
+
 /// Run a Move function
 #[derive(Parser)]
 pub struct RunFunction {
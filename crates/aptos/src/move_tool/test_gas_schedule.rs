@@ -0,0 +1,415 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metering of Move unit tests with the Aptos gas schedule, for `aptos move test --gas-report`
+
+use aptos_framework::natives::event::NativeEventContext;
+use aptos_gas_meter::{AptosGasMeter, GasAlgebra, StandardGasAlgebra, StandardGasMeter};
+use aptos_gas_schedule::{InitialGasSchedule, VMGasParameters, LATEST_GAS_FEATURE_VERSION};
+use aptos_table_natives::{NativeTableContext, TableChangeSet};
+use aptos_types::{
+    access_path::AccessPath,
+    contract_event::ContractEvent,
+    on_chain_config::CurrentTimeMicroseconds,
+    state_store::{
+        in_memory_state_view::InMemoryStateView, state_key::StateKey,
+        state_value::StateValueMetadata,
+    },
+    write_set::WriteOp,
+};
+use aptos_vm::data_cache::get_resource_group_from_metadata;
+use aptos_vm_types::{
+    abstract_write_op::GroupWrite,
+    change_set::VMChangeSet,
+    resource_group_adapter::group_size_as_sum,
+    storage::{
+        change_set_configs::ChangeSetConfigs,
+        io_pricing::{IoPricing, IoPricingV4},
+        space_pricing::DiskSpacePricing,
+        StorageGasParameters,
+    },
+};
+use bytes::Bytes;
+use move_binary_format::errors::{Location, PartialVMError, PartialVMResult, VMResult};
+use move_core_types::{
+    effects::{ChangeSet, Op},
+    identifier::IdentStr,
+    language_storage::{ModuleId, StructTag},
+    value::MoveTypeLayout,
+    vm_status::StatusCode,
+};
+use move_unit_test::gas_meter::{GasUsage, TestGasMeter, TestGasSchedule};
+use move_vm_runtime::{
+    move_vm::MoveVM,
+    native_extensions::NativeContextExtensions,
+    session::{SerializedReturnValues, Session},
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// The latest gas schedule, with the storage pricing of the latest gas feature version.
+///
+/// Tests are charged like a transaction running them, without the intrinsic gas of the
+/// transaction, and can use up to the maximum amount of gas of a transaction.  Storage fees are
+/// converted into gas at the minimum gas unit price.
+#[derive(Debug)]
+pub struct AptosTestGasSchedule {
+    vm_gas_params: VMGasParameters,
+    storage_gas_params: StorageGasParameters,
+}
+
+struct AptosTestGasMeter {
+    gas_meter: StandardGasMeter<StandardGasAlgebra>,
+    change_set_configs: ChangeSetConfigs,
+}
+
+impl AptosTestGasSchedule {
+    pub fn latest() -> Self {
+        Self {
+            vm_gas_params: VMGasParameters::initial(),
+            storage_gas_params: StorageGasParameters {
+                io_pricing: IoPricing::V4(IoPricingV4),
+                space_pricing: DiskSpacePricing::latest(),
+                change_set_configs: ChangeSetConfigs::unlimited_at_gas_feature_version(
+                    LATEST_GAS_FEATURE_VERSION,
+                ),
+            },
+        }
+    }
+
+    fn meter(&self) -> AptosTestGasMeter {
+        AptosTestGasMeter {
+            gas_meter: StandardGasMeter::new(StandardGasAlgebra::new(
+                LATEST_GAS_FEATURE_VERSION,
+                self.vm_gas_params.clone(),
+                self.storage_gas_params.clone(),
+                self.vm_gas_params.txn.maximum_number_of_gas_units,
+            )),
+            change_set_configs: self.storage_gas_params.change_set_configs.clone(),
+        }
+    }
+}
+
+impl TestGasSchedule for AptosTestGasSchedule {
+    fn new_meter(&self) -> Box<dyn TestGasMeter> {
+        Box::new(self.meter())
+    }
+}
+
+impl AptosTestGasMeter {
+    /// Converts the changes made by a test into the change set of a transaction making them.
+    ///
+    /// Tests start from storage without any resources or table items, so every write creates a
+    /// new slot, and every resource group is created with its members.
+    fn vm_change_set(
+        &self,
+        change_set: &ChangeSet,
+        resource_group_of: impl Fn(&StructTag) -> Option<StructTag>,
+        table_change_set: TableChangeSet,
+        events: Vec<(ContractEvent, Option<MoveTypeLayout>)>,
+    ) -> PartialVMResult<VMChangeSet> {
+        let mut resource_write_set = BTreeMap::new();
+        let mut resource_groups: BTreeMap<StateKey, BTreeMap<StructTag, Bytes>> = BTreeMap::new();
+        for (address, struct_tag, op) in change_set.resources() {
+            let data = created_data(op.map(Bytes::clone))?;
+            match resource_group_of(struct_tag) {
+                Some(group_tag) => {
+                    let state_key = StateKey::access_path(AccessPath::resource_group_access_path(
+                        address, group_tag,
+                    ));
+                    resource_groups
+                        .entry(state_key)
+                        .or_default()
+                        .insert(struct_tag.clone(), data);
+                },
+                None => {
+                    let access_path = AccessPath::resource_access_path(address, struct_tag.clone())
+                        .map_err(|err| {
+                            PartialVMError::new(StatusCode::VALUE_SERIALIZATION_ERROR)
+                                .with_message(err.to_string())
+                        })?;
+                    resource_write_set
+                        .insert(StateKey::access_path(access_path), (creation(data), None));
+                },
+            }
+        }
+
+        let mut resource_group_write_set = BTreeMap::new();
+        for (state_key, members) in resource_groups {
+            let group_size =
+                group_size_as_sum(members.iter().map(|(tag, data)| (tag, data.len())))?;
+            let inner_ops = members
+                .into_iter()
+                .map(|(tag, data)| (tag, (WriteOp::legacy_creation(data), None)))
+                .collect();
+            resource_group_write_set.insert(
+                state_key,
+                GroupWrite::new(creation(Bytes::new()), inner_ops, group_size.get(), 0),
+            );
+        }
+
+        for (handle, change) in table_change_set.changes {
+            for (key, op) in change.entries {
+                let data = created_data(op.map(|(data, _layout)| data))?;
+                resource_write_set.insert(
+                    StateKey::table_item(handle.into(), key),
+                    (creation(data), None),
+                );
+            }
+        }
+
+        VMChangeSet::new_expanded(
+            resource_write_set,
+            resource_group_write_set,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            events,
+            &self.change_set_configs,
+        )
+    }
+
+    /// Charges IO gas and storage fees for a change set, like they are charged for a transaction.
+    fn charge_vm_change_set(&mut self, mut change_set: VMChangeSet) -> VMResult<()> {
+        for (key, op_size) in change_set.write_set_size_iter() {
+            self.gas_meter.charge_io_gas_for_write(key, &op_size)?;
+        }
+
+        let gas_unit_price = self.gas_meter.vm_gas_params().txn.min_price_per_gas_unit;
+        self.gas_meter.process_storage_fee_for_all(
+            &mut change_set,
+            0.into(),
+            gas_unit_price,
+            &InMemoryStateView::new(HashMap::new()),
+        )?;
+        Ok(())
+    }
+}
+
+impl TestGasMeter for AptosTestGasMeter {
+    fn execute_function_bypass_visibility(
+        &mut self,
+        session: &mut Session,
+        module_id: &ModuleId,
+        function_name: &IdentStr,
+        args: Vec<Vec<u8>>,
+    ) -> VMResult<SerializedReturnValues> {
+        session.execute_function_bypass_visibility(
+            module_id,
+            function_name,
+            vec![],
+            args,
+            &mut self.gas_meter,
+        )
+    }
+
+    fn charge_change_set(
+        &mut self,
+        move_vm: &MoveVM,
+        change_set: &ChangeSet,
+        extensions: &mut NativeContextExtensions,
+    ) -> VMResult<()> {
+        let table_change_set = extensions
+            .remove::<NativeTableContext>()
+            .into_change_set()
+            .map_err(|err| err.finish(Location::Undefined))?;
+        let events = extensions.remove::<NativeEventContext>().into_events();
+        let resource_group_of = |struct_tag: &StructTag| {
+            move_vm.with_module_metadata(&struct_tag.module_id(), |metadata| {
+                get_resource_group_from_metadata(struct_tag, metadata)
+            })
+        };
+
+        let change_set = self
+            .vm_change_set(change_set, resource_group_of, table_change_set, events)
+            .map_err(|err| err.finish(Location::Undefined))?;
+        self.charge_vm_change_set(change_set)
+    }
+
+    fn gas_used(&self) -> GasUsage {
+        let txn_params = &self.gas_meter.vm_gas_params().txn;
+        GasUsage {
+            execution: self.gas_meter.execution_gas_used().into(),
+            io: self.gas_meter.io_gas_used().into(),
+            storage: self
+                .gas_meter
+                .algebra()
+                .storage_fee_used_in_gas_units()
+                .to_unit_round_up_with_params(txn_params)
+                .into(),
+        }
+    }
+}
+
+/// Returns the data written by an op creating a value.
+fn created_data(op: Op<Bytes>) -> PartialVMResult<Bytes> {
+    op.ok().ok_or_else(|| {
+        PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+            .with_message("Test deleted a value which did not exist before it".to_string())
+    })
+}
+
+/// Creates a value with storage slot metadata, whose deposits are set when storage fees are
+/// charged.
+fn creation(data: Bytes) -> WriteOp {
+    WriteOp::Creation {
+        data,
+        metadata: StateValueMetadata::placeholder(&CurrentTimeMicroseconds { microseconds: 0 }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_table_natives::{TableChange, TableHandle};
+    use move_core_types::{
+        account_address::AccountAddress, identifier::Identifier, language_storage::TypeTag,
+    };
+    use std::str::FromStr;
+
+    fn struct_tag(name: &str) -> StructTag {
+        StructTag {
+            address: AccountAddress::ONE,
+            module: Identifier::new("m").unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_params: vec![],
+        }
+    }
+
+    fn resources(resources: &[(&str, usize)]) -> ChangeSet {
+        let mut change_set = ChangeSet::new();
+        for (name, len) in resources {
+            change_set
+                .add_resource_op(
+                    AccountAddress::ONE,
+                    struct_tag(name),
+                    Op::New(vec![0; *len].into()),
+                )
+                .unwrap();
+        }
+        change_set
+    }
+
+    fn table_items(items: &[(u8, usize)]) -> TableChangeSet {
+        let entries = items
+            .iter()
+            .map(|(key, len)| (vec![*key], Op::New((vec![0; *len].into(), None))))
+            .collect();
+        TableChangeSet {
+            changes: BTreeMap::from([(TableHandle(AccountAddress::TWO), TableChange { entries })]),
+            ..TableChangeSet::default()
+        }
+    }
+
+    fn not_in_group(_: &StructTag) -> Option<StructTag> {
+        None
+    }
+
+    fn in_group(_: &StructTag) -> Option<StructTag> {
+        Some(struct_tag("Group"))
+    }
+
+    fn charge(
+        change_set: &ChangeSet,
+        resource_group_of: impl Fn(&StructTag) -> Option<StructTag>,
+        table_change_set: TableChangeSet,
+        events: Vec<(ContractEvent, Option<MoveTypeLayout>)>,
+    ) -> GasUsage {
+        let mut meter = AptosTestGasSchedule::latest().meter();
+        let change_set = meter
+            .vm_change_set(change_set, resource_group_of, table_change_set, events)
+            .unwrap();
+        meter.charge_vm_change_set(change_set).unwrap();
+        meter.gas_used()
+    }
+
+    #[test]
+    fn new_meters_have_not_used_gas() {
+        let meter = AptosTestGasSchedule::latest().new_meter();
+        assert_eq!(meter.gas_used(), GasUsage::default());
+    }
+
+    #[test]
+    fn writes_are_charged_io_and_storage() {
+        let resource = charge(
+            &resources(&[("R", 8)]),
+            not_in_group,
+            TableChangeSet::default(),
+            vec![],
+        );
+        let group = charge(
+            &resources(&[("R", 8)]),
+            in_group,
+            TableChangeSet::default(),
+            vec![],
+        );
+        let table_item = charge(
+            &ChangeSet::new(),
+            not_in_group,
+            table_items(&[(0, 8)]),
+            vec![],
+        );
+        for gas_used in [resource, group, table_item] {
+            assert_eq!(gas_used.execution, 0);
+            assert!(gas_used.io > 0);
+            assert!(gas_used.storage > 0);
+        }
+    }
+
+    #[test]
+    fn larger_writes_cost_more() {
+        let small = charge(
+            &ChangeSet::new(),
+            not_in_group,
+            table_items(&[(0, 8)]),
+            vec![],
+        );
+        let large = charge(
+            &ChangeSet::new(),
+            not_in_group,
+            table_items(&[(0, 100_000)]),
+            vec![],
+        );
+        assert!(large.io > small.io);
+        assert!(large.storage > small.storage);
+
+        let one = charge(
+            &ChangeSet::new(),
+            not_in_group,
+            table_items(&[(0, 8)]),
+            vec![],
+        );
+        let two = charge(
+            &ChangeSet::new(),
+            not_in_group,
+            table_items(&[(0, 8), (1, 8)]),
+            vec![],
+        );
+        assert!(two.total() > one.total());
+    }
+
+    #[test]
+    fn resource_group_members_share_a_slot() {
+        let change_set = resources(&[("R", 8), ("S", 8)]);
+        let separate = charge(&change_set, not_in_group, TableChangeSet::default(), vec![]);
+        let grouped = charge(&change_set, in_group, TableChangeSet::default(), vec![]);
+        assert!(grouped.total() < separate.total());
+    }
+
+    #[test]
+    fn events_are_kept_in_the_change_set() {
+        let meter = AptosTestGasSchedule::latest().meter();
+        let event = ContractEvent::new_v2(TypeTag::from_str("0x1::m::E").unwrap(), vec![0; 8]);
+        let change_set = meter
+            .vm_change_set(
+                &ChangeSet::new(),
+                not_in_group,
+                TableChangeSet::default(),
+                vec![(event, None)],
+            )
+            .unwrap();
+        assert_eq!(change_set.events().len(), 1);
+    }
+}
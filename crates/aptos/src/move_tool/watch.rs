@@ -0,0 +1,1235 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Watch mode for `aptos move compile` and `aptos move test`
+//!
+//! The sources and manifests of the package and all of its dependencies are watched for changes
+//! in their content.  When compiling, the whole package is built on start and whenever a
+//! manifest or a dependency changes; otherwise only the changed modules of the package, and the
+//! modules depending on them, are compiled again on top of the previous build.  When testing,
+//! the dependencies are compiled once and kept until one of them changes, so that only the
+//! sources of the package itself are compiled again.  The bytecode and test attributes of each
+//! module are compared with the previous build to find out which modules really changed, and
+//! only the tests of modules depending on those are run again.
+
+use crate::{
+    common::types::{CliError, CliTypedResult},
+    move_tool::{CompilePackage, TestPackage},
+};
+use aptos_crypto::HashValue;
+use aptos_framework::{BuildOptions, BuiltPackage};
+use itertools::{Either, Itertools};
+use move_binary_format::{access::ModuleAccess, CompiledModule};
+use move_compiler::{
+    compiled_unit::{AnnotatedCompiledUnit, CompiledUnitEnum},
+    construct_pre_compiled_lib,
+    diagnostics::{
+        report_diagnostics_to_color_buffer, report_warnings, Diagnostics, FilesSourceText,
+    },
+    shared::PackagePaths,
+    unit_test::{plan_builder::construct_test_plan, TestCase, TestName, TestPlan},
+    Compiler, Flags, FullyCompiledProgram, PASS_CFGIR,
+};
+use move_core_types::language_storage::ModuleId;
+use move_package::{
+    compilation::build_plan::BuildPlan, source_package::parsed_manifest::PackageName, BuildConfig,
+    CompilerConfig, CompilerVersion,
+};
+use move_unit_test::test_reporter::{FailureReason, TestResults as UnitTestResults};
+use move_vm_runtime::native_functions::NativeFunctionTable;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use walkdir::WalkDir;
+
+/// How long to wait for more file system events after one, so that a save is handled once
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Compiles the package on every change, until stopped with Ctrl-C.
+pub(crate) async fn watch_compile(command: CompilePackage) -> CliTypedResult<Vec<String>> {
+    let path = command.move_options.get_package_path()?;
+    let mut build_options = command.build_options();
+    let config = BuildConfig {
+        dev_mode: build_options.dev,
+        additional_named_addresses: build_options.named_addresses.clone(),
+        skip_fetch_latest_git_deps: build_options.skip_fetch_latest_git_deps,
+        compiler_config: CompilerConfig {
+            bytecode_version: build_options.bytecode_version,
+            compiler_version: build_options.compiler_version,
+            skip_attribute_checks: build_options.skip_attribute_checks,
+            known_attributes: build_options.known_attributes.clone(),
+        },
+        ..Default::default()
+    };
+    let mut watcher = SourceWatcher::new(package_roots(&config, &path)?)?;
+    let mut compiler = ModuleCompiler::new(config, path);
+    let mut previous: Option<BTreeMap<ModuleId, Vec<u8>>> = None;
+    let mut changed = vec![];
+
+    loop {
+        // Saving the metadata needs the whole package to be built
+        let recompiled = if command.save_metadata {
+            None
+        } else {
+            compiler.recompile(&changed)
+        };
+        let modules = match recompiled {
+            Some(modules) => Some(modules),
+            None => build_package(&command, &build_options, &mut compiler),
+        };
+        if let Some(modules) = modules {
+            if let Some(previous) = &previous {
+                report_compilation(previous, &modules);
+            } else {
+                eprintln!("Compiled {} modules", modules.len());
+            }
+            previous = Some(modules);
+        }
+
+        // Dependencies are fetched by the first build, there is no need to do it every time
+        build_options.skip_fetch_latest_git_deps = true;
+        compiler.config.skip_fetch_latest_git_deps = true;
+        changed = wait_for_changes(&mut watcher, &compiler.path, &compiler.config).await;
+    }
+}
+
+/// Builds the whole package, with the same checks as `aptos move compile`, and compiles it ahead
+/// for the changes to come.
+fn build_package(
+    command: &CompilePackage,
+    build_options: &BuildOptions,
+    compiler: &mut ModuleCompiler,
+) -> Option<BTreeMap<ModuleId, Vec<u8>>> {
+    compiler.compiled = None;
+    let pack = match BuiltPackage::build(compiler.path.clone(), build_options.clone()) {
+        Ok(pack) => pack,
+        Err(err) => {
+            eprintln!("Compilation failed: {:#}", err);
+            return None;
+        },
+    };
+    if command.save_metadata {
+        if let Err(err) = pack.extract_metadata_and_save() {
+            eprintln!("Failed to save the package metadata: {:#}", err);
+        }
+    } else if let Err(err) = compiler.compile_ahead() {
+        eprintln!("Changes will build the whole package: {}", err);
+    }
+    Some(
+        pack.modules()
+            .map(|module| {
+                let bytes = serialize_module(module, build_options.bytecode_version);
+                (module.self_id(), bytes)
+            })
+            .collect(),
+    )
+}
+
+/// Runs the tests affected by every change, until stopped with Ctrl-C.
+pub(crate) async fn watch_tests(command: TestPackage) -> CliTypedResult<&'static str> {
+    let path = command.move_options.get_package_path()?;
+    let mut config = command.build_config();
+    // The test runner always builds in dev mode
+    config.dev_mode = true;
+    if config.compiler_config.compiler_version.unwrap_or_default() != CompilerVersion::V1 {
+        return Err(CliError::CommandArgumentError(
+            "Watching tests is only supported with the V1 compiler".to_string(),
+        ));
+    }
+    let natives = command.natives();
+    let mut watcher = SourceWatcher::new(package_roots(&config, &path)?)?;
+    let mut compiler = TestCompiler::new(config, path);
+    let mut previous_modules: Option<BTreeMap<ModuleId, BuiltModule>> = None;
+    let mut previous_results = TestResults::default();
+
+    loop {
+        match compiler.compile() {
+            Ok((root_package, test_plan)) => {
+                let modules = built_modules(&test_plan, root_package);
+                let affected = previous_modules
+                    .as_ref()
+                    .map(|previous| affected_modules(previous, &modules));
+                if affected
+                    .as_ref()
+                    .map_or(false, |affected| affected.is_empty())
+                {
+                    eprintln!("No module depending on the changes has tests to run");
+                } else {
+                    match run_tests(&command, test_plan, natives.clone(), affected.clone()) {
+                        Ok(unit_test_results) => {
+                            let results = previous_results.merge(
+                                TestResults::new(&unit_test_results),
+                                affected.as_ref(),
+                                &modules,
+                            );
+                            report_tests(&previous_results, &results, affected.as_ref());
+                            print_failures(&unit_test_results);
+                            previous_results = results;
+                        },
+                        Err(err) => eprintln!("{}", err),
+                    }
+                }
+                previous_modules = Some(modules);
+            },
+            Err(err) => eprintln!("{}", err),
+        }
+
+        // Dependencies are fetched by the first build, there is no need to do it every time
+        compiler.config.skip_fetch_latest_git_deps = true;
+        let changed = wait_for_changes(&mut watcher, &compiler.path, &compiler.config).await;
+        compiler.forget_changed_dependencies(&changed);
+    }
+}
+
+/// Waits for sources to change, and looks for the dependencies again if a manifest changed.
+async fn wait_for_changes(
+    watcher: &mut SourceWatcher,
+    path: &Path,
+    config: &BuildConfig,
+) -> Vec<PathBuf> {
+    let changed = watcher.wait_for_changes().await;
+    eprintln!(
+        "\nChanged: {}",
+        changed.iter().map(|path| path.display()).join(", ")
+    );
+    if changed.iter().any(|path| is_manifest(path)) {
+        match package_roots(config, path) {
+            Ok(roots) => watcher.set_roots(roots),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    changed
+}
+
+fn is_manifest(path: &Path) -> bool {
+    path.file_name().map_or(false, |name| name == "Move.toml")
+}
+
+/// The directories of the package and all of its dependencies.
+fn package_roots(config: &BuildConfig, path: &Path) -> CliTypedResult<Vec<PathBuf>> {
+    let graph = config
+        .clone()
+        .resolution_graph_for_package(path, &mut Vec::new())
+        .map_err(|err| CliError::MoveCompilationError(format!("{:#}", err)))?;
+    Ok(graph
+        .package_table
+        .values()
+        .map(|package| package.package_path.clone())
+        .collect())
+}
+
+/// Watches the Move sources and manifests under a set of directories for changes in their
+/// content, with the file system notifications of the platform.
+struct SourceWatcher {
+    watcher: RecommendedWatcher,
+    events: UnboundedReceiver<notify::Result<Event>>,
+    roots: Vec<PathBuf>,
+    /// The hash of each file, as events don't tell whether the content changed
+    files: BTreeMap<PathBuf, HashValue>,
+}
+
+impl SourceWatcher {
+    fn new(roots: Vec<PathBuf>) -> CliTypedResult<Self> {
+        let (sender, events) = unbounded_channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            // Events are only dropped when the watcher itself is
+            let _ = sender.send(event);
+        })
+        .map_err(|err| {
+            CliError::UnexpectedError(format!("Failed to watch the sources: {:#}", err))
+        })?;
+        let mut watcher = Self {
+            watcher,
+            events,
+            roots: vec![],
+            files: BTreeMap::new(),
+        };
+        watcher.set_roots(roots);
+        Ok(watcher)
+    }
+
+    fn set_roots(&mut self, roots: Vec<PathBuf>) {
+        for root in &self.roots {
+            let _ = self.watcher.unwatch(root);
+        }
+        for root in &roots {
+            if let Err(err) = self.watcher.watch(root, RecursiveMode::Recursive) {
+                eprintln!("Failed to watch {}: {:#}", root.display(), err);
+            }
+        }
+        self.files = roots
+            .iter()
+            .flat_map(|root| source_files_under(root))
+            .filter_map(|path| hash_file(&path).map(|hash| (path, hash)))
+            .collect();
+        self.roots = roots;
+    }
+
+    /// Waits until a file is added, removed, or has its content changed, and returns those files.
+    async fn wait_for_changes(&mut self) -> Vec<PathBuf> {
+        loop {
+            let mut paths = BTreeSet::new();
+            match self.events.recv().await {
+                Some(event) => self.collect(event, &mut paths),
+                // The sender lives as long as the watcher
+                None => std::future::pending().await,
+            }
+            // Editors often write a file in several steps, which are handled together
+            while let Ok(Some(event)) =
+                tokio::time::timeout(DEBOUNCE_INTERVAL, self.events.recv()).await
+            {
+                self.collect(event, &mut paths);
+            }
+
+            let mut changed = vec![];
+            for path in paths {
+                let hash = hash_file(&path);
+                if self.files.get(&path) != hash.as_ref() {
+                    match hash {
+                        Some(hash) => self.files.insert(path.clone(), hash),
+                        None => self.files.remove(&path),
+                    };
+                    changed.push(path);
+                }
+            }
+            if !changed.is_empty() {
+                return changed;
+            }
+        }
+    }
+
+    /// Adds the sources an event is about, including those under directories added or removed.
+    fn collect(&self, event: notify::Result<Event>, paths: &mut BTreeSet<PathBuf>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                eprintln!("Failed to watch the sources: {:#}", err);
+                return;
+            },
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths {
+            if !self.is_watched(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                paths.extend(source_files_under(&path));
+            }
+            paths.extend(
+                self.files
+                    .keys()
+                    .filter(|file| file.starts_with(&path))
+                    .cloned(),
+            );
+            if is_source_file(&path) {
+                paths.insert(path);
+            }
+        }
+    }
+
+    /// Whether the path is under one of the roots, outside of build outputs and hidden
+    /// directories, e.g. `.git`.
+    fn is_watched(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| {
+            path.strip_prefix(root).map_or(false, |relative| {
+                relative.components().all(|component| {
+                    let name = component.as_os_str().to_string_lossy();
+                    name != "build" && !name.starts_with('.')
+                })
+            })
+        })
+    }
+}
+
+fn is_source_file(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "move") || is_manifest(path)
+}
+
+/// The Move sources and manifests under a directory, skipping build outputs and hidden
+/// directories.
+fn source_files_under(dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || (name != "build" && !name.starts_with('.'))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_source_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn hash_file(path: &Path) -> Option<HashValue> {
+    std::fs::read(path)
+        .ok()
+        .map(|bytes| HashValue::sha3_256_of(&bytes))
+}
+
+fn serialize_module(module: &CompiledModule, bytecode_version: Option<u32>) -> Vec<u8> {
+    let mut bytes = vec![];
+    module
+        .serialize_for_version(bytecode_version, &mut bytes)
+        .expect("Compiled modules must serialize");
+    bytes
+}
+
+/// Compiles the changed modules of the root package again, with the modules depending on them,
+/// on top of the whole package compiled ahead.  Modules compiled from the targets take the place
+/// of those compiled ahead.
+struct ModuleCompiler {
+    config: BuildConfig,
+    path: PathBuf,
+    compiled: Option<CompiledAhead>,
+}
+
+/// The package and its dependencies, as compiled ahead, and how the root package changed since
+struct CompiledAhead {
+    program: FullyCompiledProgram,
+    /// The sources of the root package, with its named addresses
+    sources: PackagePaths,
+    modules: BTreeMap<ModuleId, SourceModule>,
+    /// The root sources changed since the package was compiled ahead.  The modules compiled
+    /// ahead from those are outdated, so they are compiled again on every change.
+    changed: BTreeSet<PathBuf>,
+}
+
+/// A module of the root package, with the source it was compiled from
+struct SourceModule {
+    source: PathBuf,
+    bytes: Vec<u8>,
+    dependencies: Vec<ModuleId>,
+}
+
+impl ModuleCompiler {
+    fn new(config: BuildConfig, path: PathBuf) -> Self {
+        Self {
+            config,
+            path,
+            compiled: None,
+        }
+    }
+
+    fn flags(&self) -> Flags {
+        Flags::empty().set_skip_attribute_checks(self.config.compiler_config.skip_attribute_checks)
+    }
+
+    /// Compiles the package and its dependencies, to compile the changes on top of them.  With
+    /// the V2 compiler, or bytecode dependencies, changes build the whole package instead.
+    fn compile_ahead(&mut self) -> CliTypedResult<()> {
+        self.compiled = None;
+        if self
+            .config
+            .compiler_config
+            .compiler_version
+            .unwrap_or_default()
+            != CompilerVersion::V1
+        {
+            return Ok(());
+        }
+        let graph = self
+            .config
+            .clone()
+            .resolution_graph_for_package(&self.path, &mut Vec::new())
+            .map_err(compilation_error)?;
+        let (sources, dependencies) = BuildPlan::create(graph)
+            .and_then(|plan| plan.compiler_package_paths())
+            .map_err(compilation_error)?;
+        // Programs compiled ahead can't depend on bytecode
+        if dependencies
+            .iter()
+            .any(|(_, source_available)| !source_available)
+        {
+            return Ok(());
+        }
+        let targets = dependencies
+            .into_iter()
+            .map(|(paths, _)| paths)
+            .chain([sources.clone()])
+            .collect();
+        let program = construct_pre_compiled_lib(
+            targets,
+            None,
+            self.flags(),
+            &self.config.compiler_config.known_attributes,
+        )
+        .map_err(compilation_error)?
+        .map_err(|(files, diags)| diagnostics_error(&files, diags))?;
+        let modules = source_modules(
+            &program.files,
+            &program.compiled,
+            &root_sources(&sources),
+            self.config.compiler_config.bytecode_version,
+        );
+        self.compiled = Some(CompiledAhead {
+            program,
+            sources,
+            modules,
+            changed: BTreeSet::new(),
+        });
+        Ok(())
+    }
+
+    /// Compiles the modules of the changed root sources, and of the root modules depending on
+    /// them, and returns all root modules.  Returns `None` when the whole package must be built
+    /// instead: on the first build, after changes to other files, when files or modules are
+    /// added or removed, and on compilation errors, which the full build reports.
+    fn recompile(&mut self, changed: &[PathBuf]) -> Option<BTreeMap<ModuleId, Vec<u8>>> {
+        let mut compiled = self.compiled.take()?;
+        let root_sources = root_sources(&compiled.sources);
+        for path in changed {
+            compiled.changed.insert(
+                path.canonicalize()
+                    .ok()
+                    .filter(|path| root_sources.contains(path))?,
+            );
+        }
+        let targets = recompiled_sources(&compiled.modules, &compiled.changed);
+        let sources = PackagePaths {
+            name: compiled.sources.name,
+            paths: targets
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect(),
+            named_address_map: compiled.sources.named_address_map.clone(),
+        };
+
+        let (files, result) = Compiler::from_package_paths(
+            vec![sources],
+            vec![],
+            self.flags(),
+            &self.config.compiler_config.known_attributes,
+        )
+        .set_pre_compiled_lib(&compiled.program)
+        .build()
+        .ok()?;
+        let (units, warnings) = result.ok()?;
+        let recompiled = source_modules(
+            &files,
+            &units,
+            &root_sources,
+            self.config.compiler_config.bytecode_version,
+        );
+        let added_or_removed = recompiled
+            .keys()
+            .any(|id| !compiled.modules.contains_key(id))
+            || compiled.modules.iter().any(|(id, module)| {
+                targets.contains(&module.source) && !recompiled.contains_key(id)
+            });
+        if added_or_removed {
+            return None;
+        }
+        report_warnings(&files, warnings);
+
+        compiled.modules.extend(recompiled);
+        let modules = compiled
+            .modules
+            .iter()
+            .map(|(id, module)| (id.clone(), module.bytes.clone()))
+            .collect();
+        self.compiled = Some(compiled);
+        Some(modules)
+    }
+}
+
+/// The changed root sources, and the sources of the root modules depending on their modules,
+/// directly or not.
+fn recompiled_sources(
+    modules: &BTreeMap<ModuleId, SourceModule>,
+    changed: &BTreeSet<PathBuf>,
+) -> BTreeSet<PathBuf> {
+    let changed_modules: BTreeSet<&ModuleId> = modules
+        .iter()
+        .filter(|(_, module)| changed.contains(&module.source))
+        .map(|(id, _)| id)
+        .collect();
+    let mut sources = changed.clone();
+    sources.extend(
+        modules
+            .iter()
+            .filter(|(id, _)| {
+                depends_on(id, &changed_modules, |id| {
+                    modules.get(id).map(|module| &module.dependencies)
+                })
+            })
+            .map(|(_, module)| module.source.clone()),
+    );
+    sources
+}
+
+/// The canonical paths of the sources of a package
+fn root_sources(sources: &PackagePaths) -> BTreeSet<PathBuf> {
+    sources
+        .paths
+        .iter()
+        .filter_map(|path| Path::new(path.as_str()).canonicalize().ok())
+        .collect()
+}
+
+/// The modules compiled from the given sources, by ID
+fn source_modules(
+    files: &FilesSourceText,
+    units: &[AnnotatedCompiledUnit],
+    sources: &BTreeSet<PathBuf>,
+    bytecode_version: Option<u32>,
+) -> BTreeMap<ModuleId, SourceModule> {
+    units
+        .iter()
+        .filter_map(|unit| {
+            let module = match unit {
+                CompiledUnitEnum::Module(module) => &module.named_module.module,
+                CompiledUnitEnum::Script(_) => return None,
+            };
+            let (file_name, _) = files.get(&unit.loc().file_hash())?;
+            let source = Path::new(file_name.as_str()).canonicalize().ok()?;
+            if !sources.contains(&source) {
+                return None;
+            }
+            let source_module = SourceModule {
+                source,
+                bytes: serialize_module(module, bytecode_version),
+                dependencies: module.immediate_dependencies(),
+            };
+            Some((module.self_id(), source_module))
+        })
+        .collect()
+}
+
+/// Whether the module is one of the changed modules, or depends on one of them, directly or not.
+fn depends_on<'a>(
+    id: &'a ModuleId,
+    changed: &BTreeSet<&ModuleId>,
+    dependencies: impl Fn(&ModuleId) -> Option<&'a Vec<ModuleId>>,
+) -> bool {
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![id];
+    while let Some(id) = pending.pop() {
+        if changed.contains(id) {
+            return true;
+        }
+        if visited.insert(id) {
+            if let Some(module_dependencies) = dependencies(id) {
+                pending.extend(module_dependencies.iter());
+            }
+        }
+    }
+    false
+}
+
+/// Lists the root modules added, removed and changed since the previous compilation.
+fn report_compilation(
+    previous: &BTreeMap<ModuleId, Vec<u8>>,
+    current: &BTreeMap<ModuleId, Vec<u8>>,
+) {
+    let added: Vec<_> = current
+        .keys()
+        .filter(|id| !previous.contains_key(*id))
+        .collect();
+    let removed: Vec<_> = previous
+        .keys()
+        .filter(|id| !current.contains_key(*id))
+        .collect();
+    let changed: Vec<_> = current
+        .iter()
+        .filter(|(id, bytes)| previous.get(*id).map_or(false, |old| old != *bytes))
+        .map(|(id, _)| id)
+        .collect();
+    eprintln!(
+        "Compiled {} modules: {} changed, {} added, {} removed, {} unchanged",
+        current.len(),
+        changed.len(),
+        added.len(),
+        removed.len(),
+        current.len() - changed.len() - added.len()
+    );
+    for id in changed {
+        eprintln!("  changed  {}", id);
+    }
+    for id in added {
+        eprintln!("  added    {}", id);
+    }
+    for id in removed {
+        eprintln!("  removed  {}", id);
+    }
+}
+
+/// Compiles a package in test mode, without exiting on errors like the test runner does.
+///
+/// Dependencies are compiled once and kept, so that changes to the package itself only compile
+/// its own sources again, until a dependency or a manifest changes.
+struct TestCompiler {
+    config: BuildConfig,
+    path: PathBuf,
+    /// The directory of each package, and whether it is the root package
+    package_dirs: Vec<(PathBuf, bool)>,
+    dependencies: Option<FullyCompiledProgram>,
+}
+
+impl TestCompiler {
+    fn new(config: BuildConfig, path: PathBuf) -> Self {
+        Self {
+            config,
+            path,
+            package_dirs: vec![],
+            dependencies: None,
+        }
+    }
+
+    /// Compiles the package, and returns its name and its test plan.
+    fn compile(&mut self) -> CliTypedResult<(PackageName, TestPlan)> {
+        let graph = self
+            .config
+            .clone()
+            .resolution_graph_for_package(&self.path, &mut Vec::new())
+            .map_err(compilation_error)?;
+        let root_package = graph.root_package.package.name;
+        self.package_dirs = graph
+            .package_table
+            .iter()
+            .map(|(name, package)| (package.package_path.clone(), *name == root_package))
+            .collect();
+        let (sources, dependencies) = BuildPlan::create(graph)
+            .and_then(|plan| plan.compiler_package_paths())
+            .map_err(compilation_error)?;
+        let (source_dependencies, bytecode_dependencies): (Vec<_>, Vec<_>) = dependencies
+            .into_iter()
+            .partition_map(|(paths, source_available)| {
+                if source_available {
+                    Either::Left(paths)
+                } else {
+                    Either::Right(paths)
+                }
+            });
+
+        let flags = Flags::testing()
+            .set_skip_attribute_checks(self.config.compiler_config.skip_attribute_checks);
+        let known_attributes = &self.config.compiler_config.known_attributes;
+        // Dependencies compiled ahead can't depend on bytecode, those are compiled every time
+        if self.dependencies.is_none()
+            && bytecode_dependencies.is_empty()
+            && !source_dependencies.is_empty()
+        {
+            let dependencies = construct_pre_compiled_lib(
+                source_dependencies.clone(),
+                None,
+                flags.clone(),
+                known_attributes,
+            )
+            .map_err(compilation_error)?
+            .map_err(|(files, diags)| diagnostics_error(&files, diags))?;
+            self.dependencies = Some(dependencies);
+        }
+        let targets = match &self.dependencies {
+            Some(_) => vec![sources],
+            None => source_dependencies.into_iter().chain([sources]).collect(),
+        };
+
+        let (mut files, result) =
+            Compiler::from_package_paths(targets, bytecode_dependencies, flags, known_attributes)
+                .set_pre_compiled_lib_opt(self.dependencies.as_ref())
+                .run::<PASS_CFGIR>()
+                .map_err(compilation_error)?;
+        if let Some(dependencies) = &self.dependencies {
+            files.extend(dependencies.files.clone());
+        }
+        let (_, compiler) = result.map_err(|diags| diagnostics_error(&files, diags))?;
+        let (mut compiler, cfgir) = compiler.into_ast();
+        let tests = construct_test_plan(compiler.compilation_env(), Some(root_package), &cfgir);
+        let (mut units, warnings) = compiler
+            .at_cfgir(cfgir)
+            .build()
+            .map_err(|diags| diagnostics_error(&files, diags))?;
+        // Warnings are shown, but don't block the tests
+        report_warnings(&files, warnings);
+        if let Some(dependencies) = &self.dependencies {
+            units.extend(dependencies.compiled.iter().cloned());
+        }
+        Ok((
+            root_package,
+            TestPlan::new(tests.unwrap_or_default(), files, units),
+        ))
+    }
+
+    /// Drops the compiled dependencies if one of them, or a manifest, changed.
+    fn forget_changed_dependencies(&mut self, changed: &[PathBuf]) {
+        if changed
+            .iter()
+            .any(|path| is_manifest(path) || self.is_dependency_file(path))
+        {
+            self.dependencies = None;
+        }
+    }
+
+    /// Whether the file belongs to a dependency, i.e. the closest package directory holding it
+    /// isn't the root package's.
+    fn is_dependency_file(&self, path: &Path) -> bool {
+        self.package_dirs
+            .iter()
+            .filter(|(dir, _)| path.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count())
+            .map_or(false, |(_, is_root)| !is_root)
+    }
+}
+
+fn compilation_error(err: anyhow::Error) -> CliError {
+    CliError::MoveCompilationError(format!("{:#}", err))
+}
+
+fn diagnostics_error(files: &FilesSourceText, diags: Diagnostics) -> CliError {
+    let buffer = report_diagnostics_to_color_buffer(files, diags);
+    CliError::MoveCompilationError(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// A module of the package or of one of its dependencies, as built in test mode
+struct BuiltModule {
+    bytes: Vec<u8>,
+    dependencies: Vec<ModuleId>,
+    /// The tests of the module, with their attributes, which aren't part of the bytecode
+    tests: BTreeMap<TestName, TestCase>,
+    is_root: bool,
+}
+
+fn built_modules(
+    test_plan: &TestPlan,
+    root_package: PackageName,
+) -> BTreeMap<ModuleId, BuiltModule> {
+    test_plan
+        .module_info
+        .iter()
+        .map(|(id, module)| {
+            let built = BuiltModule {
+                bytes: serialize_module(&module.module, None),
+                dependencies: module.module.immediate_dependencies(),
+                tests: test_plan
+                    .module_tests
+                    .get(id)
+                    .map(|module_tests| module_tests.tests.clone())
+                    .unwrap_or_default(),
+                is_root: module.package_name == Some(root_package),
+            };
+            (id.clone(), built)
+        })
+        .collect()
+}
+
+/// The root modules with tests depending, directly or not, on a module added or changed since
+/// the previous build, including the changed modules themselves.  A module changed if its
+/// bytecode or the attributes of its tests, e.g. `#[expected_failure]`, did.
+fn affected_modules(
+    previous: &BTreeMap<ModuleId, BuiltModule>,
+    current: &BTreeMap<ModuleId, BuiltModule>,
+) -> BTreeSet<ModuleId> {
+    let changed: BTreeSet<&ModuleId> = current
+        .iter()
+        .filter(|(id, module)| {
+            previous.get(*id).map_or(true, |old| {
+                old.bytes != module.bytes || old.tests != module.tests
+            })
+        })
+        .map(|(id, _)| id)
+        .collect();
+    current
+        .iter()
+        .filter(|(_, module)| module.is_root && !module.tests.is_empty())
+        .filter(|(id, _)| {
+            depends_on(id, &changed, |id| {
+                current.get(id).map(|module| &module.dependencies)
+            })
+        })
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+/// Runs the tests of the given modules, or all tests, from an already compiled test plan.
+fn run_tests(
+    command: &TestPackage,
+    test_plan: TestPlan,
+    natives: NativeFunctionTable,
+    modules: Option<BTreeSet<ModuleId>>,
+) -> CliTypedResult<UnitTestResults> {
+    let mut unit_testing_config = command.unit_testing_config();
+    unit_testing_config.module_filter = modules;
+
+    // The outcome of each test is reported from the results instead of the runner's output
+    let (_, results) = unit_testing_config
+        .run_and_report_unit_tests_with_results(test_plan, Some(natives), None, std::io::sink())
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to run tests: {:#}", err)))?;
+    results.ok_or_else(|| CliError::UnexpectedError("The tests were only listed".to_string()))
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TestOutcome {
+    Pass,
+    Fail,
+    Timeout,
+}
+
+impl Display for TestOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TestOutcome::Pass => "PASS",
+            TestOutcome::Fail => "FAIL",
+            TestOutcome::Timeout => "TIMEOUT",
+        })
+    }
+}
+
+/// Outcome of each test, by module and test name
+#[derive(Clone, Debug, Default, PartialEq)]
+struct TestResults(BTreeMap<ModuleId, BTreeMap<TestName, TestOutcome>>);
+
+impl TestResults {
+    fn new(results: &UnitTestResults) -> Self {
+        let mut outcomes: BTreeMap<ModuleId, BTreeMap<TestName, TestOutcome>> = BTreeMap::new();
+        for (id, passed) in results.passed() {
+            let tests = outcomes.entry(id.clone()).or_default();
+            for info in passed {
+                tests.insert(info.function_ident.clone(), TestOutcome::Pass);
+            }
+        }
+        for (id, failed) in results.failed() {
+            let tests = outcomes.entry(id.clone()).or_default();
+            for failure in failed {
+                let outcome = match failure.failure_reason {
+                    FailureReason::Timeout(_) => TestOutcome::Timeout,
+                    _ => TestOutcome::Fail,
+                };
+                tests.insert(failure.test_run_info.function_ident.clone(), outcome);
+            }
+        }
+        Self(outcomes)
+    }
+
+    /// Replaces the results of the modules that were run again, keeping those of the other
+    /// modules that still have tests.
+    fn merge(
+        &self,
+        results: TestResults,
+        rerun: Option<&BTreeSet<ModuleId>>,
+        modules: &BTreeMap<ModuleId, BuiltModule>,
+    ) -> TestResults {
+        let rerun = match rerun {
+            Some(rerun) => rerun,
+            None => return results,
+        };
+        let mut merged: BTreeMap<_, _> = self
+            .0
+            .iter()
+            .filter(|(id, _)| {
+                !rerun.contains(*id)
+                    && modules
+                        .get(*id)
+                        .map_or(false, |module| !module.tests.is_empty())
+            })
+            .map(|(id, tests)| (id.clone(), tests.clone()))
+            .collect();
+        merged.extend(results.0);
+        TestResults(merged)
+    }
+
+    /// The outcome of each test, by fully qualified name
+    fn outcomes(&self) -> BTreeMap<String, TestOutcome> {
+        self.0
+            .iter()
+            .flat_map(|(id, tests)| {
+                tests
+                    .iter()
+                    .map(move |(name, outcome)| (qualified_test_name(id, name), *outcome))
+            })
+            .collect()
+    }
+
+    fn count(&self, outcome: TestOutcome) -> usize {
+        self.0
+            .values()
+            .flat_map(|tests| tests.values())
+            .filter(|value| **value == outcome)
+            .count()
+    }
+
+    fn len(&self) -> usize {
+        self.0.values().map(|tests| tests.len()).sum()
+    }
+}
+
+/// How the test runner prints a test
+fn qualified_test_name(id: &ModuleId, name: &str) -> String {
+    format!(
+        "0x{}::{}::{}",
+        id.address().short_str_lossless(),
+        id.name(),
+        name
+    )
+}
+
+/// Prints the tests whose outcome differs from the previous run, and totals.
+fn report_tests(
+    previous: &TestResults,
+    current: &TestResults,
+    affected: Option<&BTreeSet<ModuleId>>,
+) {
+    let previous_outcomes = previous.outcomes();
+    let current_outcomes = current.outcomes();
+    let mut differences = vec![];
+    for (name, outcome) in &current_outcomes {
+        match previous_outcomes.get(name) {
+            None => differences.push(format!("  new      {:<7} {}", outcome, name)),
+            Some(old) if old != outcome => {
+                differences.push(format!("  {:<7}→ {:<7} {}", old, outcome, name))
+            },
+            Some(_) => (),
+        }
+    }
+    for name in previous_outcomes
+        .keys()
+        .filter(|name| !current_outcomes.contains_key(*name))
+    {
+        differences.push(format!("  removed          {}", name));
+    }
+
+    if affected.is_some() {
+        if differences.is_empty() {
+            eprintln!("No test changed its outcome");
+        } else {
+            eprintln!("Changes since the previous run:");
+            for difference in differences {
+                eprintln!("{}", difference);
+            }
+        }
+    }
+    let rerun = match affected {
+        Some(modules) => modules
+            .iter()
+            .filter_map(|id| current.0.get(id))
+            .map(|tests| tests.len())
+            .sum(),
+        None => current.len(),
+    };
+    eprintln!(
+        "Ran {} tests, {} not affected by the changes. Passed: {}; failed: {}; timed out: {}",
+        rerun,
+        current.len() - rerun,
+        current.count(TestOutcome::Pass),
+        current.count(TestOutcome::Fail),
+        current.count(TestOutcome::Timeout),
+    );
+}
+
+/// Prints the details of the tests that failed in this run.
+fn print_failures(results: &UnitTestResults) {
+    for (id, failures) in results.failed() {
+        for failure in failures {
+            eprintln!(
+                "\n┌── {} ──────",
+                qualified_test_name(id, &failure.test_run_info.function_ident)
+            );
+            eprintln!(
+                "│ {}",
+                failure
+                    .render_error(results.test_plan())
+                    .replace('\n', "\n│ ")
+            );
+            eprintln!("└──────────────────");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_compiler::unit_test::ExpectedFailure;
+    use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+
+    fn module_id(name: &str) -> ModuleId {
+        ModuleId::new(AccountAddress::ONE, Identifier::new(name).unwrap())
+    }
+
+    fn test_case(name: &str, expected_failure: Option<ExpectedFailure>) -> (TestName, TestCase) {
+        let test_case = TestCase {
+            test_name: name.to_string(),
+            arguments: vec![],
+            expected_failure,
+            expected_gas_below: None,
+        };
+        (name.to_string(), test_case)
+    }
+
+    /// A root module with a `test` function, unless it is a dependency
+    fn built(code: u8, dependencies: &[&str], is_root: bool) -> BuiltModule {
+        BuiltModule {
+            bytes: vec![code],
+            dependencies: dependencies.iter().map(|name| module_id(name)).collect(),
+            tests: if is_root {
+                [test_case("test", None)].into_iter().collect()
+            } else {
+                BTreeMap::new()
+            },
+            is_root,
+        }
+    }
+
+    fn modules(list: Vec<(&str, BuiltModule)>) -> BTreeMap<ModuleId, BuiltModule> {
+        list.into_iter()
+            .map(|(name, module)| (module_id(name), module))
+            .collect()
+    }
+
+    fn ids(names: &[&str]) -> BTreeSet<ModuleId> {
+        names.iter().map(|name| module_id(name)).collect()
+    }
+
+    fn package(dep_code: u8, a_code: u8) -> BTreeMap<ModuleId, BuiltModule> {
+        modules(vec![
+            ("dep", built(dep_code, &[], false)),
+            ("a", built(a_code, &["dep"], true)),
+            ("b", built(0, &["a"], true)),
+            ("c", built(0, &[], true)),
+        ])
+    }
+
+    #[test]
+    fn test_affected_modules() {
+        // Nothing changed
+        assert!(affected_modules(&package(0, 0), &package(0, 0)).is_empty());
+        // Modules depending on a changed dependency, directly or not
+        assert_eq!(
+            affected_modules(&package(0, 0), &package(1, 0)),
+            ids(&["a", "b"])
+        );
+        // A changed module, and the modules depending on it
+        assert_eq!(
+            affected_modules(&package(0, 0), &package(0, 1)),
+            ids(&["a", "b"])
+        );
+
+        // New modules are affected
+        let mut current = package(0, 0);
+        current.insert(module_id("d"), built(0, &[], true));
+        assert_eq!(affected_modules(&package(0, 0), &current), ids(&["d"]));
+
+        // Modules without tests are left out
+        let mut current = package(0, 1);
+        current.get_mut(&module_id("b")).unwrap().tests.clear();
+        assert_eq!(affected_modules(&package(0, 0), &current), ids(&["a"]));
+    }
+
+    #[test]
+    fn test_affected_modules_with_changed_test_attributes() {
+        // Adding `#[expected_failure]` doesn't change the bytecode
+        let mut current = package(0, 0);
+        current.get_mut(&module_id("c")).unwrap().tests =
+            [test_case("test", Some(ExpectedFailure::Expected))]
+                .into_iter()
+                .collect();
+        assert_eq!(affected_modules(&package(0, 0), &current), ids(&["c"]));
+    }
+
+    fn source_module(source: &str, dependencies: &[&str]) -> SourceModule {
+        SourceModule {
+            source: PathBuf::from(source),
+            bytes: vec![],
+            dependencies: dependencies.iter().map(|name| module_id(name)).collect(),
+        }
+    }
+
+    fn paths(names: &[&str]) -> BTreeSet<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_recompiled_sources() {
+        let modules: BTreeMap<_, _> = [
+            ("a", source_module("a.move", &[])),
+            ("b", source_module("b.move", &["a"])),
+            ("c", source_module("c.move", &["b"])),
+            ("d", source_module("b.move", &[])),
+            ("e", source_module("e.move", &["d"])),
+            ("f", source_module("f.move", &[])),
+        ]
+        .into_iter()
+        .map(|(name, module)| (module_id(name), module))
+        .collect();
+
+        // Nothing changed
+        assert!(recompiled_sources(&modules, &BTreeSet::new()).is_empty());
+        // Sources of modules depending on a changed module, directly or not
+        assert_eq!(
+            recompiled_sources(&modules, &paths(&["a.move"])),
+            paths(&["a.move", "b.move", "c.move"])
+        );
+        // All modules of a changed source are changed
+        assert_eq!(
+            recompiled_sources(&modules, &paths(&["b.move"])),
+            paths(&["b.move", "c.move", "e.move"])
+        );
+        assert_eq!(
+            recompiled_sources(&modules, &paths(&["c.move", "f.move"])),
+            paths(&["c.move", "f.move"])
+        );
+    }
+
+    fn results(list: &[(&str, &str, TestOutcome)]) -> TestResults {
+        let mut results = TestResults::default();
+        for (module, test, outcome) in list {
+            results
+                .0
+                .entry(module_id(module))
+                .or_default()
+                .insert(test.to_string(), *outcome);
+        }
+        results
+    }
+
+    #[test]
+    fn test_merge_results() {
+        let previous = results(&[
+            ("a", "test", TestOutcome::Pass),
+            ("a", "removed", TestOutcome::Pass),
+            ("b", "test", TestOutcome::Fail),
+            ("c", "test", TestOutcome::Pass),
+        ]);
+        let rerun = results(&[
+            ("a", "test", TestOutcome::Fail),
+            ("a", "added", TestOutcome::Timeout),
+        ]);
+
+        // Only the modules run again are replaced
+        let merged = previous.merge(rerun.clone(), Some(&ids(&["a"])), &package(0, 1));
+        assert_eq!(
+            merged,
+            results(&[
+                ("a", "test", TestOutcome::Fail),
+                ("a", "added", TestOutcome::Timeout),
+                ("b", "test", TestOutcome::Fail),
+                ("c", "test", TestOutcome::Pass),
+            ])
+        );
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged.count(TestOutcome::Fail), 2);
+        assert_eq!(
+            merged.outcomes().get("0x1::a::added"),
+            Some(&TestOutcome::Timeout)
+        );
+
+        // Modules that no longer exist, or have no tests, are dropped
+        let mut current = package(0, 1);
+        current.remove(&module_id("b"));
+        current.get_mut(&module_id("c")).unwrap().tests.clear();
+        assert_eq!(
+            previous.merge(rerun.clone(), Some(&ids(&["a"])), &current),
+            rerun
+        );
+
+        // All results are replaced when all tests were run
+        assert_eq!(previous.merge(rerun.clone(), None, &package(0, 1)), rerun);
+    }
+}
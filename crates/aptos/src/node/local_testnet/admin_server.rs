@@ -0,0 +1,629 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    health_checker::HealthChecker, node::NodeRestarter, traits::ServiceManager, RunLocalTestnet,
+};
+use anyhow::{anyhow, Context, Result};
+use aptos_consensus::util::block_production::{
+    BlockProductionControl, BlockProductionStatus, TimeAdvance,
+};
+use aptos_node::utils::copy_dir;
+use aptos_rest_client::Client;
+use aptos_types::{account_address::AccountAddress, timestamp::TimestampResource};
+use async_trait::async_trait;
+use clap::Parser;
+use maplit::hashset;
+use poem::{
+    get, handler,
+    http::StatusCode,
+    listener::TcpListener,
+    middleware::Tracing,
+    post,
+    web::{Data, Json, Path as UrlPath, Query},
+    Endpoint, EndpointExt, Route, Server,
+};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs,
+    future::Future,
+    net::{Ipv4Addr, SocketAddrV4},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::time::{sleep, timeout};
+use tracing::info;
+
+/// Directory in the test dir where snapshots of the node's data dir are kept.
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// File in the snapshots dir with the snapshot operation to apply on restart.
+const PENDING_OPERATION_FILE: &str = "pending.json";
+
+/// File in the node's data dir that stores how the on-chain time was moved forward, so that it
+/// survives restarts and is reverted along with the rest of the data dir.
+const TIME_ADVANCES_FILE: &str = "time_advances.json";
+
+/// How long to wait for requested blocks to be committed.
+const PRODUCE_BLOCKS_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Args related to running an admin server in the local testnet. The admin server lets
+/// tests control block production and time, and snapshot and revert the node's data.
+#[derive(Debug, Clone, Parser)]
+pub struct AdminServerArgs {
+    /// Do not run the admin server.
+    #[clap(long)]
+    pub no_admin_server: bool,
+
+    /// The port to run the admin server. Its endpoints are:
+    ///
+    /// - `GET /`: The state of block production, time and snapshots.
+    ///
+    /// - `POST /blocks/pause`, `POST /blocks/resume`: Pause and resume block production.
+    ///
+    /// - `POST /blocks/produce?count=N`: Produce exactly N blocks while paused, and wait for
+    ///   them to be committed. A block is only committed once the next block is produced, so
+    ///   transactions submitted while paused are committed after producing 2 blocks.
+    ///
+    /// - `POST /time/advance?seconds=N`: Move the on-chain time forward, i.e.
+    ///   `timestamp::now_seconds` from the next block on. The CLI and the faucet count the
+    ///   expiration of their transactions from the on-chain time, so they keep working.
+    ///
+    /// - `POST /snapshots/<name>`, `POST /snapshots/<name>/revert`: Snapshot the node's DB,
+    ///   or revert it to a snapshot. Both restart the node and the faucet, so wait for the
+    ///   ready server before using them again. Not supported with the indexer API.
+    #[clap(long, default_value_t = 8071)]
+    pub admin_server_listen_port: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct AdminServerManager {
+    config: AdminServerArgs,
+    bind_to: Ipv4Addr,
+    context: AdminContext,
+    prerequisite_health_checkers: HashSet<HealthChecker>,
+}
+
+impl AdminServerManager {
+    pub fn new(
+        args: &RunLocalTestnet,
+        bind_to: Ipv4Addr,
+        test_dir: PathBuf,
+        data_dir: PathBuf,
+        node_api_url: Url,
+        block_production: BlockProductionControl,
+        node_restarter: NodeRestarter,
+        prerequisite_health_checkers: HashSet<HealthChecker>,
+    ) -> Result<Self> {
+        Ok(AdminServerManager {
+            config: args.admin_server_args.clone(),
+            bind_to,
+            context: AdminContext {
+                test_dir,
+                data_dir,
+                node_api_url,
+                block_production,
+                node_restarter,
+                snapshots_supported: !args.indexer_api_args.with_indexer_api,
+            },
+            prerequisite_health_checkers,
+        })
+    }
+}
+
+#[async_trait]
+impl ServiceManager for AdminServerManager {
+    fn get_name(&self) -> String {
+        "Admin Server".to_string()
+    }
+
+    fn get_health_checkers(&self) -> HashSet<HealthChecker> {
+        hashset! {}
+    }
+
+    fn get_prerequisite_health_checkers(&self) -> HashSet<&HealthChecker> {
+        self.prerequisite_health_checkers.iter().collect()
+    }
+
+    async fn run_service(self: Box<Self>) -> Result<()> {
+        let app = admin_app(self.context).with(Tracing);
+        Server::new(TcpListener::bind(SocketAddrV4::new(
+            self.bind_to,
+            self.config.admin_server_listen_port,
+        )))
+        .name("admin-server")
+        .run(app)
+        .await?;
+        Err(anyhow!("Admin server exited unexpectedly"))
+    }
+}
+
+fn admin_app(context: AdminContext) -> impl Endpoint {
+    Route::new()
+        .at("/", get(root))
+        .at("/blocks/pause", post(pause))
+        .at("/blocks/resume", post(resume))
+        .at("/blocks/produce", post(produce))
+        .at("/time/advance", post(advance_time))
+        .at("/snapshots/:name", post(take_snapshot))
+        .at("/snapshots/:name/revert", post(revert_to_snapshot))
+        .data(context)
+}
+
+/// Restores the state the admin server keeps across restarts of the node. This must run before
+/// the node starts, as it may replace the node's data dir.
+pub fn prepare_node_data_dir(
+    test_dir: &Path,
+    data_dir: &Path,
+    block_production: &BlockProductionControl,
+) -> Result<()> {
+    let pending_path = test_dir.join(SNAPSHOTS_DIR).join(PENDING_OPERATION_FILE);
+    if pending_path.exists() {
+        let pending: PendingOperation = serde_json::from_slice(&fs::read(&pending_path)?)
+            .context("Failed to read the pending snapshot operation")?;
+        let snapshot_dir = snapshot_dir(test_dir, &pending.name);
+        match pending.operation {
+            SnapshotOperation::Take => {
+                if snapshot_dir.exists() {
+                    fs::remove_dir_all(&snapshot_dir)?;
+                }
+                copy_dir(data_dir, &snapshot_dir).context("Failed to take the snapshot")?;
+                eprintln!("Took snapshot {}", pending.name);
+            },
+            SnapshotOperation::Revert => {
+                fs::remove_dir_all(data_dir)?;
+                copy_dir(&snapshot_dir, data_dir).context("Failed to revert to the snapshot")?;
+                eprintln!("Reverted to snapshot {}", pending.name);
+            },
+        }
+        fs::remove_file(&pending_path)?;
+    }
+
+    let time_advances_path = data_dir.join(TIME_ADVANCES_FILE);
+    let time_advances: Vec<TimeAdvance> = if time_advances_path.exists() {
+        serde_json::from_slice(&fs::read(&time_advances_path)?)
+            .context("Failed to read the time advances")?
+    } else {
+        vec![]
+    };
+    block_production.restore_time_advances(time_advances);
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+struct AdminContext {
+    test_dir: PathBuf,
+    data_dir: PathBuf,
+    node_api_url: Url,
+    block_production: BlockProductionControl,
+    node_restarter: NodeRestarter,
+    snapshots_supported: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SnapshotOperation {
+    Take,
+    Revert,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PendingOperation {
+    operation: SnapshotOperation,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct AdminStatus {
+    block_production: BlockProductionStatus,
+    time_offset_secs: u64,
+    snapshots: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LedgerStatus {
+    block_height: u64,
+    ledger_version: u64,
+    ledger_timestamp_usecs: u64,
+}
+
+#[derive(Serialize)]
+struct RestartResponse {
+    restarting: bool,
+}
+
+#[derive(Deserialize)]
+struct ProduceParams {
+    count: u64,
+}
+
+#[derive(Deserialize)]
+struct AdvanceTimeParams {
+    seconds: u64,
+}
+
+#[handler]
+async fn root(context: Data<&AdminContext>) -> poem::Result<Json<AdminStatus>> {
+    admin_status(&context).map(Json).map_err(internal_error)
+}
+
+#[handler]
+async fn pause(context: Data<&AdminContext>) -> poem::Result<Json<AdminStatus>> {
+    context.block_production.pause();
+    admin_status(&context).map(Json).map_err(internal_error)
+}
+
+#[handler]
+async fn resume(context: Data<&AdminContext>) -> poem::Result<Json<AdminStatus>> {
+    context.block_production.resume();
+    admin_status(&context).map(Json).map_err(internal_error)
+}
+
+#[handler]
+async fn produce(
+    context: Data<&AdminContext>,
+    Query(params): Query<ProduceParams>,
+) -> poem::Result<Json<LedgerStatus>> {
+    if !context.block_production.status().paused {
+        return Err(poem::Error::from_string(
+            "Block production is not paused",
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    produce_blocks(&context, params.count)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+#[handler]
+async fn advance_time(
+    context: Data<&AdminContext>,
+    Query(params): Query<AdvanceTimeParams>,
+) -> poem::Result<Json<AdminStatus>> {
+    context
+        .block_production
+        .advance_time(Duration::from_secs(params.seconds));
+    fs::write(
+        context.data_dir.join(TIME_ADVANCES_FILE),
+        serde_json::to_vec(&context.block_production.time_advances()).map_err(internal_error)?,
+    )
+    .map_err(internal_error)?;
+    info!(
+        "Moved the on-chain time forward by {} seconds",
+        params.seconds
+    );
+    admin_status(&context).map(Json).map_err(internal_error)
+}
+
+#[handler]
+async fn take_snapshot(
+    context: Data<&AdminContext>,
+    UrlPath(name): UrlPath<String>,
+) -> poem::Result<Json<RestartResponse>> {
+    restart_for_snapshot(&context, SnapshotOperation::Take, name)
+}
+
+#[handler]
+async fn revert_to_snapshot(
+    context: Data<&AdminContext>,
+    UrlPath(name): UrlPath<String>,
+) -> poem::Result<Json<RestartResponse>> {
+    if !snapshot_dir(&context.test_dir, &name).exists() {
+        return Err(poem::Error::from_string(
+            format!("There is no snapshot {}", name),
+            StatusCode::NOT_FOUND,
+        ));
+    }
+    restart_for_snapshot(&context, SnapshotOperation::Revert, name)
+}
+
+fn admin_status(context: &AdminContext) -> Result<AdminStatus> {
+    let mut snapshots = vec![];
+    let snapshots_dir = context.test_dir.join(SNAPSHOTS_DIR);
+    if snapshots_dir.exists() {
+        for entry in fs::read_dir(snapshots_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                snapshots.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    snapshots.sort();
+    Ok(AdminStatus {
+        block_production: context.block_production.status(),
+        time_offset_secs: context.block_production.time_offset().as_secs(),
+        snapshots,
+    })
+}
+
+async fn produce_blocks(context: &AdminContext, count: u64) -> Result<LedgerStatus> {
+    let client = Client::new(context.node_api_url.clone());
+    request_blocks(&context.block_production, count, || ledger_status(&client)).await
+}
+
+/// Requests `count` blocks and waits until as many more blocks are committed. Each produced
+/// block commits the one before it, so this is exactly `count` blocks.
+async fn request_blocks<F, Fut>(
+    block_production: &BlockProductionControl,
+    count: u64,
+    ledger_status: F,
+) -> Result<LedgerStatus>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<LedgerStatus>>,
+{
+    let target_height = ledger_status().await?.block_height + count;
+    block_production.produce_blocks(count);
+
+    timeout(PRODUCE_BLOCKS_TIMEOUT, async {
+        loop {
+            let status = ledger_status().await?;
+            if status.block_height >= target_height {
+                return Ok(status);
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .map_err(|_| {
+        anyhow!(
+            "Timed out waiting for block {} to be committed",
+            target_height
+        )
+    })?
+}
+
+async fn ledger_status(client: &Client) -> Result<LedgerStatus> {
+    let index = client.get_index().await?.into_inner();
+    // The ledger info has the timestamp of consensus, which doesn't include the time advances
+    let timestamp: TimestampResource = client
+        .get_account_resource_at_version_bcs(
+            AccountAddress::ONE,
+            "0x1::timestamp::CurrentTimeMicroseconds",
+            index.ledger_version.0,
+        )
+        .await?
+        .into_inner();
+    Ok(LedgerStatus {
+        block_height: index.block_height.0,
+        ledger_version: index.ledger_version.0,
+        ledger_timestamp_usecs: timestamp.timestamp.microseconds,
+    })
+}
+
+/// Records the snapshot operation and restarts the node to apply it, since the node must not be
+/// running while its data dir is copied.
+fn restart_for_snapshot(
+    context: &AdminContext,
+    operation: SnapshotOperation,
+    name: String,
+) -> poem::Result<Json<RestartResponse>> {
+    if !context.snapshots_supported {
+        return Err(poem::Error::from_string(
+            "Snapshots are not supported with the indexer API, as its database would not be \
+            reverted along with the node",
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(poem::Error::from_string(
+            "Snapshot names may only contain letters, digits, '-' and '_'",
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    record_pending_operation(&context.test_dir, operation, name).map_err(internal_error)?;
+    context.node_restarter.request_restart();
+    Ok(Json(RestartResponse { restarting: true }))
+}
+
+/// Records the snapshot operation for [`prepare_node_data_dir`] to apply on restart.
+fn record_pending_operation(
+    test_dir: &Path,
+    operation: SnapshotOperation,
+    name: String,
+) -> Result<()> {
+    let pending = PendingOperation { operation, name };
+    let snapshots_dir = test_dir.join(SNAPSHOTS_DIR);
+    fs::create_dir_all(&snapshots_dir)?;
+    fs::write(
+        snapshots_dir.join(PENDING_OPERATION_FILE),
+        serde_json::to_vec(&pending)?,
+    )?;
+    Ok(())
+}
+
+fn snapshot_dir(test_dir: &Path, name: &str) -> PathBuf {
+    test_dir.join(SNAPSHOTS_DIR).join(name)
+}
+
+fn internal_error(error: impl std::fmt::Display) -> poem::Error {
+    poem::Error::from_string(error.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{
+        http::{Method, Uri},
+        Request,
+    };
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+    use tokio::sync::{watch, Notify};
+
+    fn test_context(test_dir: &Path) -> AdminContext {
+        AdminContext {
+            test_dir: test_dir.to_path_buf(),
+            data_dir: test_dir.join("0"),
+            node_api_url: Url::parse("http://127.0.0.1:8080").unwrap(),
+            block_production: BlockProductionControl::default(),
+            node_restarter: NodeRestarter {
+                requests: Arc::new(Notify::new()),
+                restarts: watch::channel(0).1,
+            },
+            snapshots_supported: true,
+        }
+    }
+
+    async fn request(
+        app: &impl Endpoint,
+        method: Method,
+        uri: &'static str,
+    ) -> (StatusCode, String) {
+        let response = app
+            .get_response(
+                Request::builder()
+                    .method(method)
+                    .uri(Uri::from_static(uri))
+                    .finish(),
+            )
+            .await;
+        let status = response.status();
+        (status, response.into_body().into_string().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pause_produce_resume() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let context = test_context(test_dir.path());
+        let block_production = context.block_production.clone();
+        let app = admin_app(context);
+
+        // Blocks are only produced on demand while paused
+        let (status, _) = request(&app, Method::POST, "/blocks/produce?count=1").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = request(&app, Method::POST, "/blocks/pause").await;
+        assert_eq!(status, StatusCode::OK);
+        let admin_status: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(admin_status["block_production"]["paused"], true);
+
+        // A node that commits every block it gets to propose
+        let block_height = Arc::new(AtomicU64::new(10));
+        let node = tokio::spawn({
+            let block_height = block_height.clone();
+            let block_production = block_production.clone();
+            async move {
+                loop {
+                    if block_production.try_take_turn() {
+                        block_height.fetch_add(1, Ordering::SeqCst);
+                    }
+                    sleep(Duration::from_millis(10)).await;
+                }
+            }
+        });
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(block_height.load(Ordering::SeqCst), 10);
+
+        let ledger_status = request_blocks(&block_production, 3, || {
+            let block_height = block_height.load(Ordering::SeqCst);
+            async move {
+                Ok(LedgerStatus {
+                    block_height,
+                    ledger_version: 0,
+                    ledger_timestamp_usecs: 0,
+                })
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(ledger_status.block_height, 13);
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(block_height.load(Ordering::SeqCst), 13);
+        assert_eq!(block_production.status().pending_blocks, 0);
+
+        let (status, body) = request(&app, Method::POST, "/blocks/resume").await;
+        assert_eq!(status, StatusCode::OK);
+        let admin_status: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(admin_status["block_production"]["paused"], false);
+        sleep(Duration::from_millis(100)).await;
+        assert!(block_height.load(Ordering::SeqCst) > 13);
+        node.abort();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_revert() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let context = test_context(test_dir.path());
+        let db_file = context.data_dir.join("db").join("state");
+        fs::create_dir_all(db_file.parent().unwrap()).unwrap();
+        fs::write(&db_file, "before").unwrap();
+        let time_advances = vec![TimeAdvance {
+            after_usecs: 1,
+            by_usecs: 60_000_000,
+        }];
+        fs::write(
+            context.data_dir.join(TIME_ADVANCES_FILE),
+            serde_json::to_vec(&time_advances).unwrap(),
+        )
+        .unwrap();
+
+        // Reverting to a snapshot that doesn't exist doesn't restart
+        let app = admin_app(context.clone());
+        let (status, _) = request(&app, Method::POST, "/snapshots/before/revert").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Taking a snapshot records it and restarts the node, which applies it
+        let pending_path = test_dir
+            .path()
+            .join(SNAPSHOTS_DIR)
+            .join(PENDING_OPERATION_FILE);
+        let (status, _) = request(&app, Method::POST, "/snapshots/before").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(pending_path.exists());
+        timeout(
+            Duration::from_secs(1),
+            context.node_restarter.requests.notified(),
+        )
+        .await
+        .unwrap();
+        prepare_node_data_dir(
+            test_dir.path(),
+            &context.data_dir,
+            &context.block_production,
+        )
+        .unwrap();
+        assert!(!pending_path.exists());
+        assert_eq!(admin_status(&context).unwrap().snapshots, vec!["before"]);
+
+        // Changes made after the snapshot, including to the time, are reverted
+        fs::write(&db_file, "after").unwrap();
+        fs::write(
+            context.data_dir.join(TIME_ADVANCES_FILE),
+            serde_json::to_vec(&[
+                time_advances[0],
+                TimeAdvance {
+                    after_usecs: 2,
+                    by_usecs: 60_000_000,
+                },
+            ])
+            .unwrap(),
+        )
+        .unwrap();
+        record_pending_operation(
+            test_dir.path(),
+            SnapshotOperation::Revert,
+            "before".to_string(),
+        )
+        .unwrap();
+        prepare_node_data_dir(
+            test_dir.path(),
+            &context.data_dir,
+            &context.block_production,
+        )
+        .unwrap();
+        assert!(!pending_path.exists());
+        assert_eq!(fs::read_to_string(&db_file).unwrap(), "before");
+        assert_eq!(context.block_production.time_advances(), time_advances);
+    }
+}
@@ -1,8 +1,10 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{health_checker::HealthChecker, traits::ServiceManager, RunLocalTestnet};
-use anyhow::Result;
+use super::{
+    health_checker::HealthChecker, node::NodeRestarter, traits::ServiceManager, RunLocalTestnet,
+};
+use anyhow::{Context, Result};
 use aptos_faucet_core::server::{FunderKeyEnum, RunConfig};
 use async_trait::async_trait;
 use clap::Parser;
@@ -42,6 +44,7 @@ pub struct FaucetArgs {
 pub struct FaucetManager {
     config: RunConfig,
     prerequisite_health_checkers: HashSet<HealthChecker>,
+    node_restarter: NodeRestarter,
 }
 
 impl FaucetManager {
@@ -51,6 +54,7 @@ impl FaucetManager {
         bind_to: Ipv4Addr,
         test_dir: PathBuf,
         node_api_url: Url,
+        node_restarter: NodeRestarter,
     ) -> Result<Self> {
         Ok(Self {
             config: RunConfig::build_for_cli(
@@ -62,6 +66,7 @@ impl FaucetManager {
                 None,
             ),
             prerequisite_health_checkers,
+            node_restarter,
         })
     }
 }
@@ -83,7 +88,21 @@ impl ServiceManager for FaucetManager {
         self.prerequisite_health_checkers.iter().collect()
     }
 
+    /// Runs the faucet, and runs it anew whenever the node restarts, since the faucet keeps
+    /// track of the sequence numbers of its accounts, which a reverted DB takes back.
     async fn run_service(self: Box<Self>) -> Result<()> {
-        self.config.run().await
+        let mut node_restarter = self.node_restarter.clone();
+        loop {
+            tokio::select! {
+                result = self.config.clone().run() => return result,
+                _ = node_restarter.restarted() => {},
+            }
+            for health_checker in &self.prerequisite_health_checkers {
+                health_checker
+                    .wait(Some(&self.get_name()))
+                    .await
+                    .context("Node did not come back up after restarting")?;
+            }
+        }
     }
 }
@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+mod admin_server;
 mod docker;
 mod faucet;
 mod health_checker;
@@ -10,10 +11,12 @@ mod node;
 mod postgres;
 mod processors;
 mod ready_server;
+mod seed;
 mod traits;
 mod utils;
 
 use self::{
+    admin_server::{AdminServerArgs, AdminServerManager},
     faucet::FaucetArgs,
     health_checker::HealthChecker,
     indexer_api::IndexerApiArgs,
@@ -22,6 +25,7 @@ use self::{
     postgres::PostgresArgs,
     processors::ProcessorArgs,
     ready_server::ReadyServerArgs,
+    seed::SeedArgs,
     traits::{PostHealthyStep, ServiceManager},
 };
 use crate::{
@@ -91,6 +95,12 @@ pub struct RunLocalTestnet {
     #[clap(flatten)]
     ready_server_args: ReadyServerArgs,
 
+    #[clap(flatten)]
+    admin_server_args: AdminServerArgs,
+
+    #[clap(flatten)]
+    seed_args: SeedArgs,
+
     #[clap(flatten)]
     prompt_options: PromptOptions,
 
@@ -261,11 +271,28 @@ impl CliCommand<()> for RunLocalTestnet {
                 bind_to,
                 test_dir.clone(),
                 node_manager.get_node_api_url(),
+                node_manager.get_restarter(),
             )
             .context("Failed to build faucet service manager")?;
             managers.push(Box::new(faucet_manager));
         }
 
+        // If configured to do so, build the admin server manager.
+        if !self.admin_server_args.no_admin_server {
+            let admin_server_manager = AdminServerManager::new(
+                &self,
+                bind_to,
+                test_dir.clone(),
+                node_manager.get_data_dir(),
+                node_manager.get_node_api_url(),
+                node_manager.get_block_production(),
+                node_manager.get_restarter(),
+                node_health_checkers.clone(),
+            )
+            .context("Failed to build admin server manager")?;
+            managers.push(Box::new(admin_server_manager));
+        }
+
         if self.indexer_api_args.with_indexer_api {
             let postgres_manager = postgres::PostgresManager::new(&self, test_dir.clone())
                 .context("Failed to build postgres service manager")?;
@@ -345,6 +372,13 @@ impl CliCommand<()> for RunLocalTestnet {
             bind_to, self.ready_server_args.ready_server_listen_port,
         );
 
+        if !self.admin_server_args.no_admin_server {
+            eprintln!(
+                "Admin endpoint: http://{}:{}/\n",
+                bind_to, self.admin_server_args.admin_server_listen_port,
+            );
+        }
+
         // Collect post healthy steps to run after the services start.
         let post_healthy_steps: Vec<Box<dyn PostHealthyStep>> = managers
             .iter()
@@ -1,11 +1,18 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{health_checker::HealthChecker, traits::ServiceManager, RunLocalTestnet};
+use super::{
+    admin_server::prepare_node_data_dir,
+    health_checker::HealthChecker,
+    seed::{SeedArgs, SeedPostHealthyStep},
+    traits::{PostHealthyStep, ServiceManager},
+    RunLocalTestnet,
+};
 use crate::node::local_testnet::utils::socket_addr_to_url;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use aptos_config::config::{NodeConfig, DEFAULT_GRPC_STREAM_PORT};
-use aptos_node::{load_node_config, start_test_environment_node};
+use aptos_consensus::util::block_production::BlockProductionControl;
+use aptos_node::{load_node_config, run_test_environment_node_until};
 use async_trait::async_trait;
 use clap::Parser;
 use maplit::hashset;
@@ -13,11 +20,17 @@ use rand::{rngs::StdRng, SeedableRng};
 use reqwest::Url;
 use std::{
     collections::HashSet,
+    future::pending,
     net::{IpAddr, Ipv4Addr},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
     thread,
     time::Duration,
 };
+use tokio::sync::{watch, Notify};
+
+/// File in the test dir that records the DB the local testnet was forked from.
+const FORKED_FROM_FILE: &str = "forked_from";
 
 /// Args specific to running a node (and its components, e.g. the txn stream) in the
 /// local testnet.
@@ -43,6 +56,22 @@ pub struct NodeArgs {
     #[clap(long, value_parser = aptos_node::load_seed)]
     pub seed: Option<[u8; 32]>,
 
+    /// Path to an existing AptosDB to fork the local testnet from
+    ///
+    /// The DB, e.g. a checkpoint made with `aptos-db-tool debug checkpoint`, is copied into
+    /// `--test-dir` instead of running a fresh genesis.  All on-chain state is kept, but the
+    /// validator set is replaced with the single local validator, the chain id is set to the
+    /// test chain id, and the root account can mint funds for the faucet.  The fork is only
+    /// made when the test dir has no DB yet, use `--force-restart` to fork again.
+    ///
+    /// Reconfiguration with DKG is turned off on the fork, since the local validator has no
+    /// randomness keys for the epochs of the DB.
+    ///
+    /// The transaction stream is not run on a fork, since the indexer tables of the DB may be
+    /// missing.
+    #[clap(long, value_parser, conflicts_with_all = &["config_path", "with_indexer_api"])]
+    pub fork_from_db: Option<PathBuf>,
+
     /// Do not run a transaction stream service alongside the node.
     ///
     /// Note: In reality this is not the same as running a Transaction Stream Service,
@@ -82,6 +111,35 @@ pub struct NodeManager {
     config: NodeConfig,
     test_dir: PathBuf,
     no_node: bool,
+    seed_args: SeedArgs,
+    block_production: BlockProductionControl,
+    restart_requests: Arc<Notify>,
+    // Counts the restarts of the node
+    restarts: Arc<watch::Sender<u64>>,
+}
+
+/// Lets other services restart the node, e.g. to revert its data dir to a snapshot, and follow
+/// its restarts. The data dir is prepared again by [`prepare_node_data_dir`] while the node is
+/// stopped.
+#[derive(Clone, Debug)]
+pub struct NodeRestarter {
+    pub(super) requests: Arc<Notify>,
+    pub(super) restarts: watch::Receiver<u64>,
+}
+
+impl NodeRestarter {
+    /// Asks the node to restart, which happens in the background.
+    pub fn request_restart(&self) {
+        self.requests.notify_one();
+    }
+
+    /// Waits until the node restarted, since the last time this returned.
+    pub async fn restarted(&mut self) {
+        if self.restarts.changed().await.is_err() {
+            // The node is gone, so it won't restart again
+            pending::<()>().await;
+        }
+    }
 }
 
 impl NodeManager {
@@ -106,10 +164,19 @@ impl NodeManager {
         )
         .context("Failed to load / create config for node")?;
 
+        // This may revert the data dir to a snapshot, so it must come before anything reads it.
+        let block_production = BlockProductionControl::default();
+        prepare_node_data_dir(&test_dir, &node_config.base.data_dir, &block_production)
+            .context("Failed to prepare the node's data dir")?;
+
+        if let Some(source_db) = &args.node_args.fork_from_db {
+            fork_from_db(&mut node_config, &test_dir, source_db)?;
+        }
+
         eprintln!();
 
         // Enable the grpc stream on the node if we will run a txn stream service.
-        let run_txn_stream = !args.node_args.no_txn_stream;
+        let run_txn_stream = !args.node_args.no_txn_stream && args.node_args.fork_from_db.is_none();
         node_config.indexer_grpc.enabled = run_txn_stream;
         node_config.indexer_grpc.use_data_service_interface = run_txn_stream;
         node_config
@@ -121,6 +188,10 @@ impl NodeManager {
         // for the indexer GRPC stream on the node to work.
         node_config.storage.enable_indexer = run_txn_stream;
 
+        // Let the admin server control block production and time.
+        node_config.consensus.enable_block_production_control =
+            !args.admin_server_args.no_admin_server;
+
         // Bind to the requested address.
         node_config.api.address.set_ip(IpAddr::V4(bind_to));
         node_config.indexer_grpc.address.set_ip(IpAddr::V4(bind_to));
@@ -131,18 +202,70 @@ impl NodeManager {
             config: node_config,
             test_dir,
             no_node: args.node_args.no_node,
+            seed_args: args.seed_args.clone(),
+            block_production,
+            restart_requests: Arc::new(Notify::new()),
+            restarts: Arc::new(watch::channel(0).0),
         })
     }
 
+    /// Gives the control over the block production of the node, which is kept across restarts.
+    pub fn get_block_production(&self) -> BlockProductionControl {
+        self.block_production.clone()
+    }
+
+    pub fn get_restarter(&self) -> NodeRestarter {
+        NodeRestarter {
+            requests: self.restart_requests.clone(),
+            restarts: self.restarts.subscribe(),
+        }
+    }
+
     pub fn get_node_api_url(&self) -> Url {
         socket_addr_to_url(&self.config.api.address, "http").unwrap()
     }
 
+    pub fn get_data_dir(&self) -> PathBuf {
+        self.config.base.data_dir.clone()
+    }
+
     pub fn get_data_service_url(&self) -> Url {
         socket_addr_to_url(&self.config.indexer_grpc.address, "http").unwrap()
     }
 }
 
+/// Forks the test dir from `source_db` if it has no DB yet. The source of the fork is recorded
+/// in the test dir, so that restarting the local testnet continues the same fork rather than
+/// silently using a DB it was not asked for.
+fn fork_from_db(node_config: &mut NodeConfig, test_dir: &Path, source_db: &Path) -> Result<()> {
+    let marker_path = test_dir.join(FORKED_FROM_FILE);
+    let source_db = source_db
+        .canonicalize()
+        .with_context(|| format!("Failed to find the DB to fork from at {:?}", source_db))?;
+
+    if node_config.storage.dir().exists() {
+        let forked_from = std::fs::read_to_string(&marker_path)
+            .map(PathBuf::from)
+            .ok();
+        if forked_from.as_ref() != Some(&source_db) {
+            bail!(
+                "The test dir {:?} already has a DB that was not forked from {:?}, use \
+                --force-restart to replace it with a new fork",
+                test_dir,
+                source_db
+            );
+        }
+        eprintln!("Continuing the fork of {:?} in the test dir", source_db);
+        return Ok(());
+    }
+
+    aptos_node::fork::fork_test_environment_from_db(node_config, test_dir, &source_db)
+        .context("Failed to fork the local testnet from the DB")?;
+    std::fs::write(&marker_path, source_db.to_string_lossy().as_bytes())
+        .context("Failed to record the source of the fork")?;
+    Ok(())
+}
+
 #[async_trait]
 impl ServiceManager for NodeManager {
     fn get_name(&self) -> String {
@@ -169,9 +292,23 @@ impl ServiceManager for NodeManager {
         hashset! {}
     }
 
+    /// Seeds the chain once the node is up, if a seed file was given.
+    fn get_post_healthy_steps(&self) -> Vec<Box<dyn PostHealthyStep>> {
+        SeedPostHealthyStep::new(
+            &self.seed_args,
+            self.test_dir.clone(),
+            self.get_data_dir(),
+            self.get_node_api_url(),
+        )
+        .into_iter()
+        .map(|step| Box::new(step) as Box<dyn PostHealthyStep>)
+        .collect()
+    }
+
     /// Spawn the node on a thread and then create a future that just waits for it to
     /// exit (which should never happen) forever. This is necessary because there is
-    /// no async function we can use to run the node.
+    /// no async function we can use to run the node. When a restart is requested, the
+    /// node is stopped, its data dir is prepared again and it is spawned anew.
     async fn run_service(self: Box<Self>) -> Result<()> {
         // Don't actually run the node, just idle.
         if self.no_node {
@@ -180,17 +317,48 @@ impl ServiceManager for NodeManager {
             }
         }
 
-        let node_thread_handle = thread::spawn(move || {
-            let result = start_test_environment_node(self.config, self.test_dir, false);
-            eprintln!("Node stopped unexpectedly {:#?}", result);
-        });
-
-        // This just waits for the node thread forever.
         loop {
-            if node_thread_handle.is_finished() {
-                return Err(anyhow!("Node thread finished unexpectedly"));
+            let (stop_sender, stop_receiver) = mpsc::channel();
+            let node_thread_handle = thread::spawn({
+                let config = self.config.clone();
+                let test_dir = self.test_dir.clone();
+                let block_production = self.block_production.clone();
+                move || {
+                    run_test_environment_node_until(
+                        config,
+                        test_dir,
+                        block_production,
+                        stop_receiver,
+                    )
+                }
+            });
+
+            // This just waits for the node thread forever, unless a restart is requested.
+            loop {
+                if node_thread_handle.is_finished() {
+                    let result = node_thread_handle.join();
+                    eprintln!("Node stopped unexpectedly {:#?}", result);
+                    return Err(anyhow!("Node thread finished unexpectedly"));
+                }
+                tokio::select! {
+                    _ = self.restart_requests.notified() => break,
+                    _ = tokio::time::sleep(Duration::from_millis(500)) => {},
+                }
             }
-            tokio::time::sleep(Duration::from_millis(500)).await;
+
+            eprintln!("\nRestarting the node...");
+            drop(stop_sender);
+            tokio::task::spawn_blocking(move || node_thread_handle.join())
+                .await?
+                .map_err(|_| anyhow!("Node thread panicked while stopping"))?
+                .context("Node failed while stopping")?;
+            prepare_node_data_dir(
+                &self.test_dir,
+                &self.config.base.data_dir,
+                &self.block_production,
+            )
+            .context("Failed to prepare the node's data dir")?;
+            self.restarts.send_modify(|restarts| *restarts += 1);
         }
     }
 }
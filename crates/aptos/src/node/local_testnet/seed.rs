@@ -0,0 +1,777 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::traits::PostHealthyStep;
+use crate::common::types::{ArgWithTypeJSON, EntryFunctionArguments, EntryFunctionArgumentsJSON};
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_api_types::{AptosError, AptosErrorCode};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    HashValue, PrivateKey,
+};
+use aptos_framework::{BuildOptions, BuiltPackage};
+use aptos_genesis::{config::AccountBalanceMap, keys::generate_key_objects};
+use aptos_keygen::KeyGen;
+use aptos_rest_client::{
+    error::{AptosErrorResponse, RestError},
+    Client,
+};
+use aptos_sdk::{
+    transaction_builder::TransactionFactory,
+    types::{
+        account_address::AccountAddress,
+        account_config::aptos_test_root_address,
+        chain_id::ChainId,
+        transaction::{
+            authenticator::AuthenticationKey, EntryFunction, SignedTransaction, TransactionPayload,
+        },
+        LocalAccount,
+    },
+};
+use aptos_vm_genesis::AccountBalance;
+use async_trait::async_trait;
+use clap::Parser;
+use regex::{Captures, Regex};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tracing::info;
+
+/// File in the node's data dir with the progress of applying the seed file. It lives in the
+/// data dir so that it is reverted along with the chain state when reverting to a snapshot.
+const SEED_PROGRESS_FILE: &str = "seed_progress.json";
+
+/// Default name of the file in the test dir that the seeded accounts are written to.
+const DEFAULT_SEED_OUTPUT_FILE: &str = "seed-output.json";
+
+/// Args for seeding the local testnet with accounts, Move packages and transactions.
+#[derive(Clone, Debug, Parser)]
+pub struct SeedArgs {
+    /// A YAML file declaring accounts, Move packages and entry function calls to set up on
+    /// the local testnet once it has started, for example:
+    ///
+    /// ```yaml
+    /// accounts:
+    ///   alice:
+    ///     balance: 1000000000
+    ///   bob:
+    ///     private_key: "0x..."
+    ///     balance: 500000000
+    /// packages:
+    ///   - publisher: alice
+    ///     path: move/hello
+    ///     named_addresses:
+    ///       hello: alice
+    /// calls:
+    ///   - sender: alice
+    ///     function_id: alice::hello::set_message
+    ///     type_args: []
+    ///     args:
+    ///       - type: address
+    ///         value: bob
+    /// ```
+    ///
+    /// Accounts without a `private_key` get a key generated the same way as by
+    /// `aptos genesis generate-keys`, seeded from their name, so they have the same address on
+    /// every run.  Balances are in octas, and publishing and calling pay gas
+    /// from them.  Account names can be used in place of addresses in named addresses,
+    /// function ids, type args and address args.  Package paths are relative to the seed
+    /// file.  Packages are published and functions called in the order they are declared.
+    ///
+    /// The seed is applied once per chain, restarting the local testnet doesn't apply it
+    /// again.  If applying it fails, the next start resumes after the last transaction that
+    /// was committed.  Use `--force-restart` to apply a changed seed file.
+    #[clap(long, value_parser)]
+    pub seed_file: Option<PathBuf>,
+
+    /// The file to write the addresses and keys of the seeded accounts to, as JSON
+    ///
+    /// Defaults to seed-output.json in `--test-dir`
+    #[clap(long, value_parser, requires = "seed_file")]
+    pub seed_output_file: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedFile {
+    #[serde(default)]
+    accounts: BTreeMap<String, SeedAccount>,
+    #[serde(default)]
+    packages: Vec<SeedPackage>,
+    #[serde(default)]
+    calls: Vec<SeedCall>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedAccount {
+    private_key: Option<Ed25519PrivateKey>,
+    #[serde(default)]
+    balance: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedPackage {
+    publisher: String,
+    path: PathBuf,
+    #[serde(default)]
+    named_addresses: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedCall {
+    sender: String,
+    function_id: String,
+    #[serde(default)]
+    type_args: Vec<String>,
+    #[serde(default)]
+    args: Vec<ArgWithTypeJSON>,
+}
+
+/// The seeded accounts, as written to the seed output file.
+#[derive(Serialize)]
+struct SeedOutput<'a> {
+    accounts: BTreeMap<&'a str, SeededAccount<'a>>,
+}
+
+#[derive(Serialize)]
+struct SeededAccount<'a> {
+    address: AccountAddress,
+    public_key: &'a Ed25519PublicKey,
+    private_key: &'a Ed25519PrivateKey,
+}
+
+/// Seeds the local testnet from a seed file once the node is up.
+#[derive(Debug)]
+pub struct SeedPostHealthyStep {
+    seed_file: PathBuf,
+    output_file: PathBuf,
+    test_dir: PathBuf,
+    data_dir: PathBuf,
+    node_api_url: Url,
+}
+
+impl SeedPostHealthyStep {
+    /// Returns the step to run if the args ask for seeding.
+    pub fn new(
+        args: &SeedArgs,
+        test_dir: PathBuf,
+        data_dir: PathBuf,
+        node_api_url: Url,
+    ) -> Option<Self> {
+        let seed_file = args.seed_file.clone()?;
+        let output_file = args
+            .seed_output_file
+            .clone()
+            .unwrap_or_else(|| test_dir.join(DEFAULT_SEED_OUTPUT_FILE));
+        Some(Self {
+            seed_file,
+            output_file,
+            test_dir,
+            data_dir,
+            node_api_url,
+        })
+    }
+}
+
+#[async_trait]
+impl PostHealthyStep for SeedPostHealthyStep {
+    async fn run(self: Box<Self>) -> Result<()> {
+        let contents = fs::read(&self.seed_file)
+            .with_context(|| format!("Failed to read the seed file at {:?}", self.seed_file))?;
+        let seed_file: SeedFile = serde_yaml::from_slice(&contents)
+            .with_context(|| format!("Failed to parse the seed file at {:?}", self.seed_file))?;
+        let accounts = seed_accounts(&seed_file.accounts)?;
+        let steps = seed_steps(&seed_file)?;
+
+        let seed_hash = HashValue::sha3_256_of(&contents).to_hex();
+        let progress_path = self.data_dir.join(SEED_PROGRESS_FILE);
+        let progress = read_progress(&progress_path, &seed_hash)
+            .with_context(|| format!("Failed to read {:?}", progress_path))?
+            .unwrap_or_else(|| SeedProgress {
+                seed_hash,
+                applied_steps: 0,
+                pending_transaction: None,
+            });
+        if progress.applied_steps == steps.len() {
+            eprintln!("Seed file was already applied to the local testnet");
+        } else {
+            let seeder = Seeder::new(
+                &accounts,
+                self.seed_file.parent().unwrap_or_else(|| Path::new(".")),
+                &self.test_dir,
+                &self.node_api_url,
+            )
+            .await?;
+            seeder
+                .apply(&steps, progress, &progress_path)
+                .await
+                .context("Failed to apply the seed file")?;
+            eprintln!("Applied the seed file to the local testnet");
+        }
+
+        let output = SeedOutput {
+            accounts: accounts
+                .iter()
+                .map(|(name, account)| {
+                    let seeded_account = SeededAccount {
+                        address: account.address(),
+                        public_key: account.public_key(),
+                        private_key: account.private_key(),
+                    };
+                    (name.as_str(), seeded_account)
+                })
+                .collect(),
+        };
+        fs::write(&self.output_file, serde_json::to_string_pretty(&output)?)
+            .with_context(|| format!("Failed to write {:?}", self.output_file))?;
+        eprintln!("Seeded accounts written to {}", self.output_file.display());
+        Ok(())
+    }
+}
+
+/// Returns each account with its key, generating the keys not given like genesis does, from a
+/// seed derived from the account name, so that they never change.
+fn seed_accounts(
+    accounts: &BTreeMap<String, SeedAccount>,
+) -> Result<BTreeMap<String, LocalAccount>> {
+    let seeded_accounts: BTreeMap<String, LocalAccount> = accounts
+        .iter()
+        .map(|(name, account)| {
+            let key = match &account.private_key {
+                Some(key) => Ed25519PrivateKey::try_from(key.to_bytes().as_slice())
+                    .expect("Ed25519PrivateKey bytes are valid"),
+                None => {
+                    let mut keygen = KeyGen::from_seed(*HashValue::sha3_256_of(name.as_bytes()));
+                    let (_, _, private_identity, _) = generate_key_objects(&mut keygen)?;
+                    private_identity.account_private_key
+                },
+            };
+            let address = AuthenticationKey::ed25519(&key.public_key()).account_address();
+            Ok((name.clone(), LocalAccount::new(address, key, 0)))
+        })
+        .collect::<Result<_>>()?;
+
+    // Accounts sharing a key would be funded twice, genesis rejects them the same way
+    let balances: Vec<AccountBalance> = accounts
+        .iter()
+        .map(|(name, account)| AccountBalance {
+            account_address: seeded_accounts[name].address(),
+            balance: account.balance,
+        })
+        .collect();
+    AccountBalanceMap::try_from(balances)
+        .context("Accounts in the seed file must have different keys")?;
+    Ok(seeded_accounts)
+}
+
+/// A transaction of the seed, applied in order.
+enum SeedStep<'a> {
+    /// Mints the total balance of the seeded accounts to the root account.
+    Mint(u64),
+    CreateAccount(&'a str, &'a SeedAccount),
+    Publish(&'a SeedPackage),
+    Call(&'a SeedCall),
+}
+
+fn seed_steps(seed_file: &SeedFile) -> Result<Vec<SeedStep<'_>>> {
+    let total_balance = seed_file
+        .accounts
+        .values()
+        .try_fold(0u64, |total, account| total.checked_add(account.balance))
+        .ok_or_else(|| anyhow!("The total balance of the seeded accounts overflows"))?;
+
+    let mut steps = vec![];
+    if total_balance > 0 {
+        steps.push(SeedStep::Mint(total_balance));
+    }
+    steps.extend(
+        seed_file
+            .accounts
+            .iter()
+            .map(|(name, account)| SeedStep::CreateAccount(name, account)),
+    );
+    steps.extend(seed_file.packages.iter().map(SeedStep::Publish));
+    steps.extend(seed_file.calls.iter().map(SeedStep::Call));
+    Ok(steps)
+}
+
+/// How far the seed was applied, written before and after each transaction.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct SeedProgress {
+    seed_hash: String,
+    applied_steps: usize,
+    /// The transaction of the next step, which may have been committed if seeding stopped
+    /// before it was recorded as applied.
+    pending_transaction: Option<HashValue>,
+}
+
+/// Reads the progress of applying the seed with the given hash, if it was applied before.
+fn read_progress(path: &Path, seed_hash: &str) -> Result<Option<SeedProgress>> {
+    let progress: SeedProgress = match fs::read(path) {
+        Ok(contents) => serde_json::from_slice(&contents)?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    if progress.seed_hash != seed_hash {
+        bail!(
+            "A different seed file was applied to the local testnet, use --force-restart to \
+            apply it to a new chain"
+        );
+    }
+    Ok(Some(progress))
+}
+
+fn write_progress(path: &Path, progress: &SeedProgress) -> Result<()> {
+    fs::write(path, serde_json::to_vec(progress)?)
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Resolves account names to addresses in Move source text, where they are used as the address
+/// of a module, e.g. `alice::hello::set_message`.  Only the leading segment of a path is an
+/// address, so a module named like an account, as in `0x1::alice::f`, is left alone.
+struct NameResolver {
+    addresses: BTreeMap<String, AccountAddress>,
+    module_address: Regex,
+}
+
+impl NameResolver {
+    fn new(accounts: &BTreeMap<String, LocalAccount>) -> Self {
+        Self {
+            addresses: accounts
+                .iter()
+                .map(|(name, account)| (name.clone(), account.address()))
+                .collect(),
+            // A path starts the text or a type arg
+            module_address: Regex::new(r"(^|[<,\s])([A-Za-z_][A-Za-z0-9_]*)::").unwrap(),
+        }
+    }
+
+    /// Returns the address of an account name, or parses a literal address.
+    fn address(&self, name_or_address: &str) -> Result<AccountAddress> {
+        match self.addresses.get(name_or_address) {
+            Some(address) => Ok(*address),
+            None => AccountAddress::from_str(name_or_address).map_err(|_| {
+                anyhow!(
+                    "{} is neither an account in the seed file nor an address",
+                    name_or_address
+                )
+            }),
+        }
+    }
+
+    fn resolve_module_addresses(&self, text: &str) -> String {
+        self.module_address
+            .replace_all(text, |captures: &Captures| {
+                match self.addresses.get(&captures[2]) {
+                    Some(address) => format!("{}{}::", &captures[1], address.to_hex_literal()),
+                    None => captures[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+
+    /// Resolves account names in the string values of address args, including nested vectors.
+    fn resolve_arg(&self, arg: &ArgWithTypeJSON) -> ArgWithTypeJSON {
+        let value = if arg.arg_type.contains("address") {
+            self.resolve_value(arg.value.clone())
+        } else {
+            arg.value.clone()
+        };
+        ArgWithTypeJSON {
+            arg_type: arg.arg_type.clone(),
+            value,
+        }
+    }
+
+    fn resolve_value(&self, value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::String(name) => match self.addresses.get(&name) {
+                Some(address) => serde_json::Value::String(address.to_hex_literal()),
+                None => serde_json::Value::String(name),
+            },
+            serde_json::Value::Array(values) => serde_json::Value::Array(
+                values.into_iter().map(|v| self.resolve_value(v)).collect(),
+            ),
+            value => value,
+        }
+    }
+}
+
+/// Applies the steps of a seed file to the local testnet.
+struct Seeder<'a> {
+    client: Client,
+    transaction_factory: TransactionFactory,
+    root: LocalAccount,
+    accounts: &'a BTreeMap<String, LocalAccount>,
+    resolver: NameResolver,
+    base_dir: &'a Path,
+}
+
+impl<'a> Seeder<'a> {
+    async fn new(
+        accounts: &'a BTreeMap<String, LocalAccount>,
+        base_dir: &'a Path,
+        test_dir: &Path,
+        node_api_url: &Url,
+    ) -> Result<Seeder<'a>> {
+        let client = Client::new(node_api_url.clone());
+        let chain_id = ChainId::new(client.get_index().await?.into_inner().chain_id);
+
+        // Funds come from the root account, the same as for the faucet.
+        let mint_key_path = test_dir.join("mint.key");
+        let mint_key: Ed25519PrivateKey = bcs::from_bytes(
+            &fs::read(&mint_key_path)
+                .with_context(|| format!("Failed to read the mint key at {:?}", mint_key_path))?,
+        )?;
+
+        Ok(Seeder {
+            client,
+            transaction_factory: TransactionFactory::new(chain_id),
+            root: LocalAccount::new(aptos_test_root_address(), mint_key, 0),
+            accounts,
+            resolver: NameResolver::new(accounts),
+            base_dir,
+        })
+    }
+
+    /// Applies the steps that were not applied yet, recording the progress after each one.
+    async fn apply(
+        &self,
+        steps: &[SeedStep<'_>],
+        mut progress: SeedProgress,
+        progress_path: &Path,
+    ) -> Result<()> {
+        // Seeding may have stopped between committing a transaction and recording it
+        if let Some(hash) = progress.pending_transaction.take() {
+            if self.is_committed(hash).await? {
+                progress.applied_steps += 1;
+            }
+            write_progress(progress_path, &progress)?;
+        }
+
+        for step in &steps[progress.applied_steps..] {
+            let transaction = self
+                .sign(step)
+                .await
+                .with_context(|| format!("Failed to prepare {}", step.description()))?;
+            progress.pending_transaction = Some(transaction.committed_hash());
+            write_progress(progress_path, &progress)?;
+
+            self.client
+                .submit_and_wait(&transaction)
+                .await
+                .with_context(|| format!("Failed to {}", step.description()))?;
+            progress.applied_steps += 1;
+            progress.pending_transaction = None;
+            write_progress(progress_path, &progress)?;
+            info!("Seeding: {}", step.description());
+        }
+        Ok(())
+    }
+
+    /// Builds and signs the transaction of a step, with the current sequence number of the
+    /// sender.
+    async fn sign(&self, step: &SeedStep<'_>) -> Result<SignedTransaction> {
+        let (sender, payload) = match step {
+            SeedStep::Mint(total_balance) => (
+                &self.root,
+                aptos_cached_packages::aptos_stdlib::aptos_coin_mint(
+                    self.root.address(),
+                    *total_balance,
+                ),
+            ),
+            SeedStep::CreateAccount(name, account) => {
+                let address = self.resolver.address(name)?;
+                let payload = if account.balance > 0 {
+                    aptos_cached_packages::aptos_stdlib::aptos_account_transfer(
+                        address,
+                        account.balance,
+                    )
+                } else {
+                    aptos_cached_packages::aptos_stdlib::aptos_account_create_account(address)
+                };
+                (&self.root, payload)
+            },
+            SeedStep::Publish(package) => {
+                let named_addresses = package
+                    .named_addresses
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), self.resolver.address(value)?)))
+                    .collect::<Result<_>>()?;
+                let path = self.base_dir.join(&package.path);
+                let payload = build_publish_payload(&path, named_addresses)
+                    .with_context(|| format!("Failed to build the package at {:?}", path))?;
+                (self.sender(&package.publisher)?, payload)
+            },
+            SeedStep::Call(call) => {
+                let function_id = self.resolver.resolve_module_addresses(&call.function_id);
+                let entry_function_args = EntryFunctionArgumentsJSON {
+                    function_id: function_id.clone(),
+                    type_args: call
+                        .type_args
+                        .iter()
+                        .map(|type_arg| self.resolver.resolve_module_addresses(type_arg))
+                        .collect(),
+                    args: call
+                        .args
+                        .iter()
+                        .map(|arg| self.resolver.resolve_arg(arg))
+                        .collect(),
+                };
+                let entry_function: EntryFunction = entry_function_args
+                    .try_into()
+                    .and_then(|args: EntryFunctionArguments| args.try_into())
+                    .map_err(|err| anyhow!("Invalid call to {}: {}", function_id, err))?;
+                (
+                    self.sender(&call.sender)?,
+                    TransactionPayload::EntryFunction(entry_function),
+                )
+            },
+        };
+
+        sender.set_sequence_number(get_sequence_number(&self.client, sender.address()).await?);
+        Ok(sender.sign_with_transaction_builder(self.transaction_factory.payload(payload)))
+    }
+
+    fn sender(&self, name: &str) -> Result<&LocalAccount> {
+        self.accounts
+            .get(name)
+            .ok_or_else(|| anyhow!("{} is not an account in the seed file", name))
+    }
+
+    /// Returns whether a transaction was committed successfully.  The node runs in this
+    /// process, so a transaction that isn't committed was dropped along with the mempool.
+    async fn is_committed(&self, hash: HashValue) -> Result<bool> {
+        match self.client.get_transaction_by_hash(hash).await {
+            Ok(transaction) => Ok(transaction.into_inner().success()),
+            Err(error) if is_not_found(&error) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl SeedStep<'_> {
+    fn description(&self) -> String {
+        match self {
+            SeedStep::Mint(total_balance) => format!("mint {} octas", total_balance),
+            SeedStep::CreateAccount(name, _) => format!("create account {}", name),
+            SeedStep::Publish(package) => format!(
+                "publish the package at {:?} from {}",
+                package.path, package.publisher
+            ),
+            SeedStep::Call(call) => format!("call {} from {}", call.function_id, call.sender),
+        }
+    }
+}
+
+/// Builds a package, and returns the payload that publishes it.
+fn build_publish_payload(
+    path: &Path,
+    named_addresses: BTreeMap<String, AccountAddress>,
+) -> Result<TransactionPayload> {
+    let package = BuiltPackage::build(
+        path.to_path_buf(),
+        BuildOptions {
+            named_addresses,
+            ..BuildOptions::default()
+        },
+    )?;
+    Ok(
+        aptos_cached_packages::aptos_stdlib::code_publish_package_txn(
+            bcs::to_bytes(&package.extract_metadata()?).expect("PackageMetadata has BCS"),
+            package.extract_code(),
+        ),
+    )
+}
+
+/// Returns the sequence number of an account, which is 0 if it doesn't exist yet.
+async fn get_sequence_number(client: &Client, address: AccountAddress) -> Result<u64> {
+    match client.get_account(address).await {
+        Ok(account) => Ok(account.into_inner().sequence_number),
+        Err(error) if is_not_found(&error) => Ok(0),
+        Err(error) => {
+            Err(error).with_context(|| format!("Failed to get the sequence number of {}", address))
+        },
+    }
+}
+
+fn is_not_found(error: &RestError) -> bool {
+    matches!(
+        error,
+        RestError::Api(AptosErrorResponse {
+            error: AptosError {
+                error_code: AptosErrorCode::AccountNotFound
+                    | AptosErrorCode::ResourceNotFound
+                    | AptosErrorCode::TransactionNotFound,
+                ..
+            },
+            ..
+        })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    const SEED_FILE: &str = r#"
+accounts:
+  alice:
+    balance: 1000
+  bob: {}
+packages:
+  - publisher: alice
+    path: move/hello
+    named_addresses:
+      hello: alice
+calls:
+  - sender: bob
+    function_id: alice::hello::set_message
+"#;
+
+    fn parse(seed_file: &str) -> SeedFile {
+        serde_yaml::from_str(seed_file).unwrap()
+    }
+
+    #[test]
+    fn test_seed_accounts_are_deterministic() {
+        let seed_file = parse(SEED_FILE);
+        let accounts = seed_accounts(&seed_file.accounts).unwrap();
+        let again = seed_accounts(&seed_file.accounts).unwrap();
+        assert_eq!(accounts["alice"].address(), again["alice"].address());
+        assert_ne!(accounts["alice"].address(), accounts["bob"].address());
+
+        // A given key is used as is
+        let key = accounts["alice"].private_key().to_bytes();
+        let seed_file = parse(&format!(
+            "accounts:\n  carol:\n    private_key: \"0x{}\"\n",
+            hex::encode(key)
+        ));
+        let carol = seed_accounts(&seed_file.accounts).unwrap();
+        assert_eq!(carol["carol"].address(), accounts["alice"].address());
+
+        // Accounts must not share a key
+        let seed_file = parse(&format!(
+            "accounts:\n  alice: {{}}\n  carol:\n    private_key: \"0x{}\"\n",
+            hex::encode(key)
+        ));
+        assert!(seed_accounts(&seed_file.accounts).is_err());
+    }
+
+    #[test]
+    fn test_seed_steps() {
+        let seed_file = parse(SEED_FILE);
+        let steps = seed_steps(&seed_file).unwrap();
+        let descriptions: Vec<_> = steps.iter().map(|step| step.description()).collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "mint 1000 octas",
+                "create account alice",
+                "create account bob",
+                "publish the package at \"move/hello\" from alice",
+                "call alice::hello::set_message from bob",
+            ]
+        );
+
+        let seed_file = parse(&format!(
+            "accounts:\n  alice:\n    balance: {}\n  bob:\n    balance: 1\n",
+            u64::MAX
+        ));
+        assert!(seed_steps(&seed_file).is_err());
+    }
+
+    #[test]
+    fn test_name_resolver() {
+        let seed_file = parse(SEED_FILE);
+        let accounts = seed_accounts(&seed_file.accounts).unwrap();
+        let resolver = NameResolver::new(&accounts);
+        let alice = accounts["alice"].address().to_hex_literal();
+        let bob = accounts["bob"].address().to_hex_literal();
+
+        assert_eq!(
+            resolver.resolve_module_addresses("alice::hello::set_message"),
+            format!("{}::hello::set_message", alice)
+        );
+        // Only the address of a path is resolved, not modules or structs named like accounts
+        assert_eq!(
+            resolver.resolve_module_addresses("0x1::bob::alice"),
+            "0x1::bob::alice"
+        );
+        assert_eq!(
+            resolver.resolve_module_addresses("0x1::coin::CoinStore<alice::coin::T>"),
+            format!("0x1::coin::CoinStore<{}::coin::T>", alice)
+        );
+        assert_eq!(
+            resolver.resolve_module_addresses("alice::pair::Pair<bob::a::A, alice::b::B>"),
+            format!("{}::pair::Pair<{}::a::A, {}::b::B>", alice, bob, alice)
+        );
+        assert_eq!(
+            resolver.resolve_module_addresses("carol::hello::f"),
+            "carol::hello::f"
+        );
+
+        assert_eq!(
+            resolver.address("alice").unwrap(),
+            accounts["alice"].address()
+        );
+        assert_eq!(resolver.address("0x1").unwrap(), AccountAddress::ONE);
+        assert!(resolver.address("carol").is_err());
+
+        let arg = resolver.resolve_arg(&ArgWithTypeJSON {
+            arg_type: "vector<address>".to_string(),
+            value: serde_json::json!(["alice", "0x1", "carol"]),
+        });
+        assert_eq!(arg.value, serde_json::json!([alice, "0x1", "carol"]));
+        let arg = resolver.resolve_arg(&ArgWithTypeJSON {
+            arg_type: "string".to_string(),
+            value: serde_json::json!("alice"),
+        });
+        assert_eq!(arg.value, serde_json::json!("alice"));
+    }
+
+    #[test]
+    fn test_progress() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let path = data_dir.path().join(SEED_PROGRESS_FILE);
+        assert_eq!(read_progress(&path, "hash").unwrap(), None);
+
+        let progress = SeedProgress {
+            seed_hash: "hash".to_string(),
+            applied_steps: 2,
+            pending_transaction: Some(HashValue::random()),
+        };
+        write_progress(&path, &progress).unwrap();
+        assert_eq!(read_progress(&path, "hash").unwrap(), Some(progress));
+        assert!(read_progress(&path, "other hash").is_err());
+
+        fs::write(&path, "not json").unwrap();
+        assert!(read_progress(&path, "hash").is_err());
+    }
+
+    #[test]
+    fn test_is_not_found() {
+        let api_error = |error_code| {
+            RestError::Api(AptosErrorResponse {
+                error: AptosError::new_with_error_code("error", error_code),
+                state: None,
+                status_code: StatusCode::NOT_FOUND,
+            })
+        };
+        assert!(is_not_found(&api_error(AptosErrorCode::AccountNotFound)));
+        assert!(is_not_found(&api_error(
+            AptosErrorCode::TransactionNotFound
+        )));
+        assert!(!is_not_found(&api_error(AptosErrorCode::InternalError)));
+        assert!(!is_not_found(&RestError::Timeout("timed out")));
+    }
+}
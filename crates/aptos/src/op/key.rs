@@ -3,13 +3,19 @@
 
 use crate::{
     common::{
+        keystore::{
+            read_new_passphrase, read_passphrase, EncryptedKeystore, KeystorePrivateKey,
+            KEYSTORE_NEW_PASSPHRASE_ENV, KEYSTORE_PASSPHRASE_ENV,
+        },
+        signer::KeyScheme,
         types::{
-            account_address_from_public_key, CliError, CliTypedResult, EncodingOptions, KeyType,
-            RngArgs, SaveFile,
+            account_address_from_public_key, CliConfig, CliError, CliTypedResult, ConfigSearchMode,
+            EncodingOptions, KeyType, PrivateKeyInputOptions, ProfileOptions, PromptOptions,
+            RngArgs, SaveFile, DEFAULT_PROFILE,
         },
         utils::{
-            append_file_extension, check_if_file_exists, generate_vanity_account_ed25519,
-            write_to_file,
+            append_file_extension, check_if_file_exists, create_dir_if_not_exist,
+            generate_vanity_account_ed25519, write_to_file,
         },
     },
     CliCommand, CliResult,
@@ -39,6 +45,9 @@ pub const PUBLIC_KEY_EXTENSION: &str = "pub";
 pub enum KeyTool {
     Generate(GenerateKey),
     ExtractPeer(ExtractPeer),
+    Encrypt(EncryptKey),
+    Decrypt(DecryptKey),
+    ChangePassphrase(ChangeKeyPassphrase),
 }
 
 impl KeyTool {
@@ -46,6 +55,9 @@ impl KeyTool {
         match self {
             KeyTool::Generate(tool) => tool.execute_serialized().await,
             KeyTool::ExtractPeer(tool) => tool.execute_serialized().await,
+            KeyTool::Encrypt(tool) => tool.execute_serialized().await,
+            KeyTool::Decrypt(tool) => tool.execute_serialized().await,
+            KeyTool::ChangePassphrase(tool) => tool.execute_serialized().await,
         }
     }
 }
@@ -171,7 +183,7 @@ impl NetworkKeyInputOptions {
 /// key encoded with the `encoding`.
 #[derive(Debug, Parser)]
 pub struct GenerateKey {
-    /// Key type to generate. Must be one of [x25519, ed25519, bls12381]
+    /// Key type to generate. Must be one of [x25519, ed25519, bls12381, secp256k1-ecdsa]
     #[clap(long, default_value_t = KeyType::Ed25519)]
     pub(crate) key_type: KeyType,
     /// Vanity prefix that resultant account address should start with, e.g. 0xaceface or d00d. Each
@@ -258,6 +270,10 @@ impl CliCommand<HashMap<&'static str, PathBuf>> for GenerateKey {
                 let private_key = keygen.generate_bls12381_private_key();
                 self.save_params.save_bls_key(&private_key, "bls12381")
             },
+            KeyType::Secp256k1Ecdsa => {
+                let private_key = keygen.generate_secp256k1_ecdsa_private_key();
+                self.save_params.save_key(&private_key, "secp256k1_ecdsa")
+            },
         }
     }
 }
@@ -308,6 +324,292 @@ impl GenerateKey {
     }
 }
 
+/// Encrypts private keys into passphrase protected keystores
+///
+/// The key is read from `--private-key` or `--private-key-file`, or else from the profile.  When
+/// it comes from the profile, the profile is updated to reference the keystore and its plaintext
+/// private key is removed from the config.  The private keys of a MultiEd25519 profile are each
+/// encrypted into their own keystore, named after their index in the key set, e.g.
+/// `.aptos/keystores/<profile>.0.json`.  The passphrase is prompted for, unless it is set in the
+/// `APTOS_KEYSTORE_NEW_PASSPHRASE` environment variable.
+#[derive(Debug, Parser)]
+pub struct EncryptKey {
+    /// Keystore file to create
+    ///
+    /// Defaults to `.aptos/keystores/<profile>.json`
+    #[clap(long, value_parser)]
+    pub(crate) output_file: Option<PathBuf>,
+
+    /// Scheme of the key given with `--private-key` or `--private-key-file`
+    #[clap(long, value_enum, default_value_t = KeyScheme::Ed25519)]
+    pub(crate) key_scheme: KeyScheme,
+
+    #[clap(flatten)]
+    pub(crate) private_key_options: PrivateKeyInputOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<Vec<PathBuf>> for EncryptKey {
+    fn command_name(&self) -> &'static str {
+        "EncryptKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<PathBuf>> {
+        let profile_name = self
+            .profile_options
+            .profile_name()
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string();
+        let output_file = match self.output_file {
+            Some(output_file) => output_file,
+            None => CliConfig::default_keystore_path(&profile_name)?,
+        };
+
+        let encoding = self.encoding_options.encoding;
+        let private_key: Option<KeystorePrivateKey> = match self.key_scheme {
+            KeyScheme::Ed25519 => self
+                .private_key_options
+                .extract_private_key_cli(encoding)?
+                .map(Into::into),
+            KeyScheme::Secp256k1Ecdsa => self
+                .private_key_options
+                .extract_secp256k1_private_key_cli(encoding)?
+                .map(Into::into),
+            KeyScheme::MultiEd25519 => {
+                return Err(CliError::CommandArgumentError(
+                    "Keys of a MultiEd25519 key set are encrypted one at a time, \
+                    use '--key-scheme ed25519'"
+                        .to_string(),
+                ))
+            },
+        };
+        if let Some(private_key) = private_key {
+            let passphrase = read_new_passphrase(KEYSTORE_NEW_PASSPHRASE_ENV)?;
+            return Ok(vec![save_keystore(
+                &private_key,
+                &passphrase,
+                &output_file,
+                self.prompt_options,
+            )?]);
+        }
+
+        let mut config = CliConfig::load(ConfigSearchMode::CurrentDirAndParents)?;
+        let profile = config
+            .profiles
+            .as_mut()
+            .and_then(|profiles| profiles.get_mut(&profile_name))
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(format!("Profile {} not found", profile_name))
+            })?;
+
+        let keystores = if let Some(private_key) = profile.private_key.take() {
+            let passphrase = read_new_passphrase(KEYSTORE_NEW_PASSPHRASE_ENV)?;
+            let keystore = save_keystore(
+                &private_key.into(),
+                &passphrase,
+                &output_file,
+                self.prompt_options,
+            )?;
+            profile.keystore = Some(keystore.clone());
+            vec![keystore]
+        } else if let Some(private_key) = profile.secp256k1_private_key.take() {
+            let passphrase = read_new_passphrase(KEYSTORE_NEW_PASSPHRASE_ENV)?;
+            let keystore = save_keystore(
+                &private_key.into(),
+                &passphrase,
+                &output_file,
+                self.prompt_options,
+            )?;
+            profile.keystore = Some(keystore.clone());
+            vec![keystore]
+        } else if let Some(multi_ed25519) = profile
+            .multi_ed25519
+            .as_mut()
+            .filter(|multi_ed25519| !multi_ed25519.private_keys.is_empty())
+        {
+            let passphrase = read_new_passphrase(KEYSTORE_NEW_PASSPHRASE_ENV)?;
+            let mut keystores = vec![];
+            for private_key in std::mem::take(&mut multi_ed25519.private_keys) {
+                let public_key = private_key.public_key();
+                let index = multi_ed25519
+                    .public_keys
+                    .iter()
+                    .position(|key| *key == public_key)
+                    .ok_or_else(|| {
+                        CliError::UnexpectedError(format!(
+                            "Private key of {} doesn't belong to the MultiEd25519 key set",
+                            public_key
+                        ))
+                    })?;
+                let keystore = save_keystore(
+                    &private_key.into(),
+                    &passphrase,
+                    &output_file.with_extension(format!("{}.json", index)),
+                    self.prompt_options,
+                )?;
+                multi_ed25519.keystores.push(keystore.clone());
+                keystores.push(keystore);
+            }
+            keystores
+        } else if profile.keystore.is_some()
+            || profile
+                .multi_ed25519
+                .as_ref()
+                .map_or(false, |multi_ed25519| !multi_ed25519.keystores.is_empty())
+        {
+            return Err(CliError::CommandArgumentError(format!(
+                "Profile {} is already encrypted, use `aptos key change-passphrase` instead",
+                profile_name
+            )));
+        } else {
+            return Err(CliError::CommandArgumentError(format!(
+                "Profile {} has no private key, use one of ['--private-key', '--private-key-file']",
+                profile_name
+            )));
+        };
+
+        config.save()?;
+        eprintln!(
+            "Profile {} now uses {} keystore(s), its plaintext private keys were removed",
+            profile_name,
+            keystores.len()
+        );
+        Ok(keystores)
+    }
+}
+
+/// Encrypts the key into a new keystore, and returns the absolute path of the keystore
+fn save_keystore(
+    private_key: &KeystorePrivateKey,
+    passphrase: &str,
+    output_file: &Path,
+    prompt_options: PromptOptions,
+) -> CliTypedResult<PathBuf> {
+    check_if_file_exists(output_file, prompt_options)?;
+    let keystore = EncryptedKeystore::encrypt(private_key, passphrase)?;
+    if let Some(parent) = output_file.parent() {
+        if !parent.as_os_str().is_empty() {
+            create_dir_if_not_exist(parent)?;
+        }
+    }
+    keystore.save(output_file)?;
+    // The profile may be used from another directory, so reference the keystore absolutely
+    output_file
+        .canonicalize()
+        .map_err(|err| CliError::IO(output_file.display().to_string(), err))
+}
+
+/// Decrypts a keystore, and saves the private key in plaintext
+///
+/// Two files will be created `output_file` and `output_file.pub`, like `aptos key generate`.
+/// The passphrase is prompted for, unless it is set in the `APTOS_KEYSTORE_PASSPHRASE`
+/// environment variable.
+#[derive(Debug, Parser)]
+pub struct DecryptKey {
+    /// Keystore file to decrypt
+    ///
+    /// Defaults to the keystore of the profile
+    #[clap(long, value_parser)]
+    pub(crate) keystore_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) save_params: SaveKey,
+}
+
+#[async_trait]
+impl CliCommand<HashMap<&'static str, PathBuf>> for DecryptKey {
+    fn command_name(&self) -> &'static str {
+        "DecryptKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let keystore_file = keystore_file(self.keystore_file, &self.profile_options)?;
+        self.save_params.check_key_file()?;
+
+        let keystore = EncryptedKeystore::load(&keystore_file)?;
+        let passphrase = read_passphrase(
+            &format!(
+                "Enter passphrase for keystore {}: ",
+                keystore_file.display()
+            ),
+            KEYSTORE_PASSPHRASE_ENV,
+        )?;
+        match keystore.decrypt(&passphrase)? {
+            KeystorePrivateKey::Ed25519(private_key) => {
+                self.save_params.save_key(&private_key, "ed25519")
+            },
+            KeystorePrivateKey::Secp256k1Ecdsa(private_key) => {
+                self.save_params.save_key(&private_key, "secp256k1_ecdsa")
+            },
+        }
+    }
+}
+
+/// Changes the passphrase of a keystore
+///
+/// The keystore is re-encrypted with a fresh salt and nonce.  The current and new passphrases
+/// are prompted for, unless they are set in the `APTOS_KEYSTORE_PASSPHRASE` and
+/// `APTOS_KEYSTORE_NEW_PASSPHRASE` environment variables.
+#[derive(Debug, Parser)]
+pub struct ChangeKeyPassphrase {
+    /// Keystore file to change the passphrase of
+    ///
+    /// Defaults to the keystore of the profile
+    #[clap(long, value_parser)]
+    pub(crate) keystore_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[async_trait]
+impl CliCommand<PathBuf> for ChangeKeyPassphrase {
+    fn command_name(&self) -> &'static str {
+        "ChangeKeyPassphrase"
+    }
+
+    async fn execute(self) -> CliTypedResult<PathBuf> {
+        let keystore_file = keystore_file(self.keystore_file, &self.profile_options)?;
+        let keystore = EncryptedKeystore::load(&keystore_file)?;
+        let passphrase = read_passphrase(
+            &format!(
+                "Enter current passphrase for keystore {}: ",
+                keystore_file.display()
+            ),
+            KEYSTORE_PASSPHRASE_ENV,
+        )?;
+        let private_key = keystore.decrypt(&passphrase)?;
+
+        let new_passphrase = read_new_passphrase(KEYSTORE_NEW_PASSPHRASE_ENV)?;
+        EncryptedKeystore::encrypt(&private_key, &new_passphrase)?.save(&keystore_file)?;
+        Ok(keystore_file)
+    }
+}
+
+/// Uses the given keystore file, or else the keystore of the profile
+fn keystore_file(
+    keystore_file: Option<PathBuf>,
+    profile_options: &ProfileOptions,
+) -> CliTypedResult<PathBuf> {
+    if let Some(keystore_file) = keystore_file {
+        return Ok(keystore_file);
+    }
+    profile_options.profile()?.keystore.ok_or_else(|| {
+        CliError::CommandArgumentError(format!(
+            "Profile {} has no keystore, use '--keystore-file'",
+            profile_options.profile_name().unwrap_or(DEFAULT_PROFILE)
+        ))
+    })
+}
+
 #[derive(Debug, Parser)]
 pub struct SaveKey {
     #[clap(flatten)]
@@ -11,6 +11,7 @@ use crate::{
     },
     common::{
         init::{InitTool, Network},
+        signer::KeyScheme,
         types::{
             account_address_from_public_key, AccountAddressWrapper, ArgWithTypeVec,
             AuthenticationKeyInputOptions, CliError, CliTypedResult, EncodingOptions,
@@ -547,6 +548,9 @@ impl CliTestFramework {
             skip_faucet: false,
             ledger: false,
             hardware_wallet_options: Default::default(),
+            key_scheme: KeyScheme::Ed25519,
+            multi_ed25519_public_keys: vec![],
+            multi_ed25519_threshold: None,
         }
         .execute()
         .await
@@ -833,6 +837,7 @@ impl CliTestFramework {
             included_artifacts_args: IncludedArtifactsArgs {
                 included_artifacts: included_artifacts.unwrap_or(IncludedArtifacts::Sparse),
             },
+            watch: false,
         }
         .execute()
         .await
@@ -850,6 +855,8 @@ impl CliTestFramework {
             ignore_compile_warnings: false,
             compute_coverage: false,
             dump_state: false,
+            watch: false,
+            gas_report: false,
         }
         .execute()
         .await
@@ -0,0 +1,462 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Explaining what a committed transaction did, and why it failed
+//!
+//! The transaction is replayed with the debugger against the state it was executed on, which
+//! gives the Move call stack when execution stopped.  Its payload is also run on its own, with
+//! the same gas budget, so that the events and writes made before a failure, which the chain
+//! discards, can be shown.  Both replays are checked to end the way the transaction did on
+//! chain, and what can't be replayed faithfully is left out with the reason.
+
+use crate::common::types::{CliCommand, CliError, CliTypedResult, ProfileOptions, RestOptions};
+use aptos_move_debugger::aptos_debugger::AptosDebugger;
+use aptos_resource_viewer::AptosValueAnnotator;
+use aptos_rest_client::aptos_api_types::{HashValue, TransactionData};
+use aptos_types::{
+    access_path::Path,
+    account_address::AccountAddress,
+    contract_event::ContractEvent,
+    state_store::state_key::{StateKey, StateKeyInner},
+    transaction::{AbortInfo, ExecutionStatus, Transaction, TransactionStatus},
+    vm_status::{AbortLocation, VMStatus},
+    write_set::WriteOp,
+};
+use aptos_vm::data_cache::AsMoveResolver;
+use async_trait::async_trait;
+use clap::Parser;
+use move_binary_format::CompiledModule;
+use move_core_types::{
+    language_storage::StructTag, resolver::ModuleResolver, vm_status::StatusCode,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Categories of abort codes, as defined in `std::error`.
+const ERROR_CATEGORIES: [&str; 13] = [
+    "INVALID_ARGUMENT",
+    "OUT_OF_RANGE",
+    "INVALID_STATE",
+    "UNAUTHENTICATED",
+    "PERMISSION_DENIED",
+    "NOT_FOUND",
+    "ABORTED",
+    "ALREADY_EXISTS",
+    "RESOURCE_EXHAUSTED",
+    "CANCELLED",
+    "INTERNAL",
+    "NOT_IMPLEMENTED",
+    "UNAVAILABLE",
+];
+
+/// Explain what a committed transaction did, and why it failed
+///
+/// Replays the transaction at its version to find the Move call stack when it stopped, decodes
+/// the abort code with the error map in the module's metadata, and lists the events and state
+/// changes its payload made up to the failure.  The chain discards those on failure, so they
+/// are found by running the payload on its own, without the prologue, epilogue and fees.
+#[derive(Debug, Parser)]
+pub struct ExplainTransaction {
+    /// Hash of the transaction
+    #[clap(value_parser)]
+    pub(crate) transaction_hash: HashValue,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+/// What a transaction did, and why it failed
+#[derive(Debug, Serialize)]
+pub struct TransactionExplanation {
+    transaction_hash: HashValue,
+    version: u64,
+    sender: AccountAddress,
+    success: bool,
+    status: ExecutionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    abort: Option<AbortExplanation>,
+    /// The Move functions that had not returned when execution stopped, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    call_stack: Vec<String>,
+    /// Events emitted by the payload, up to the failure
+    events: Vec<ExplainedEvent>,
+    /// State changed by the payload, up to the failure
+    changes: Vec<ExplainedChange>,
+    /// Why the call stack, events and changes are missing, if the replay didn't end the way the
+    /// transaction did on chain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replay_error: Option<String>,
+}
+
+/// An abort code, split into its `std::error` category and reason
+#[derive(Debug, Serialize)]
+pub struct AbortExplanation {
+    location: String,
+    code: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<&'static str>,
+    reason: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainedEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainedChange {
+    state_key: String,
+    write: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+#[async_trait]
+impl CliCommand<TransactionExplanation> for ExplainTransaction {
+    fn command_name(&self) -> &'static str {
+        "ExplainTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionExplanation> {
+        let client = self.rest_options.client(&self.profile_options)?;
+        let data = client
+            .get_transaction_by_hash_bcs(self.transaction_hash.into())
+            .await
+            .map_err(|err| CliError::ApiError(err.to_string()))?
+            .into_inner();
+        let data = match data {
+            TransactionData::OnChain(data) => data,
+            TransactionData::Pending(_) => {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Transaction {} is not committed yet",
+                    self.transaction_hash
+                )))
+            },
+        };
+        let signed_txn = match data.transaction {
+            Transaction::UserTransaction(signed_txn) => signed_txn,
+            _ => {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Transaction {} is not a user transaction",
+                    self.transaction_hash
+                )))
+            },
+        };
+        let version = data.version;
+        let status = data.info.status().clone();
+
+        let debugger = AptosDebugger::rest_client(client).map_err(debugger_error)?;
+        let (vm_status, output, gas_log) = debugger
+            .execute_transaction_at_version_with_gas_profiler(version, signed_txn.clone())
+            .map_err(debugger_error)?;
+        // Nothing replayed can be trusted if the replay ended differently than on chain.
+        let replayed = check_replay(&status, output.status());
+        let call_stack = if replayed.is_ok() && !status.is_success() {
+            gas_log
+                .call_stack_at_exit
+                .iter()
+                .map(|frame| frame.to_string())
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let state_view = debugger.state_view_at_version(version);
+        let resolver = state_view.as_move_resolver();
+        let annotator = AptosValueAnnotator::new(&resolver);
+
+        let abort = match &status {
+            ExecutionStatus::MoveAbort { location, code, .. } => {
+                let info = match location {
+                    AbortLocation::Module(module_id) => resolver
+                        .get_module(module_id)
+                        .ok()
+                        .flatten()
+                        .and_then(|bytes| CompiledModule::deserialize(&bytes).ok())
+                        .and_then(|module| {
+                            aptos_framework::get_metadata_from_compiled_module(&module)
+                        })
+                        .and_then(|metadata| metadata.extract_abort_info(*code)),
+                    AbortLocation::Script => None,
+                };
+                Some(explain_abort(location, *code, info))
+            },
+            _ => None,
+        };
+
+        let effects = replayed
+            .and_then(|()| {
+                debugger
+                    .execute_transaction_payload_at_version_keeping_effects(version, &signed_txn)
+                    .map_err(|err| format!("Failed to replay the payload: {:#}", err))
+            })
+            .and_then(|(payload_status, change_set)| {
+                check_payload_replay(&status, &vm_status, &payload_status)?;
+                Ok(change_set)
+            });
+        let mut replay_error = None;
+        let (events, changes) = match effects {
+            Ok(change_set) => {
+                let (write_set, events) = change_set.into_inner();
+                let events = events
+                    .iter()
+                    .map(|event| explain_event(&annotator, event))
+                    .collect();
+                let changes = write_set
+                    .iter()
+                    .map(|(state_key, write_op)| explain_change(&annotator, state_key, write_op))
+                    .collect();
+                (events, changes)
+            },
+            Err(err) => {
+                replay_error = Some(err);
+                (vec![], vec![])
+            },
+        };
+
+        Ok(TransactionExplanation {
+            transaction_hash: self.transaction_hash,
+            version,
+            sender: signed_txn.sender(),
+            success: status.is_success(),
+            status,
+            abort,
+            call_stack,
+            events,
+            changes,
+            replay_error,
+        })
+    }
+}
+
+fn debugger_error(err: anyhow::Error) -> CliError {
+    CliError::UnexpectedError(format!("Failed to replay the transaction: {:#}", err))
+}
+
+/// Checks that replaying the transaction ended the way it did on chain, otherwise the state
+/// it was replayed on differs and the call stack can't be trusted.
+fn check_replay(on_chain: &ExecutionStatus, replayed: &TransactionStatus) -> Result<(), String> {
+    match replayed {
+        TransactionStatus::Keep(replayed) if replayed == on_chain => Ok(()),
+        _ => Err(format!(
+            "Replaying the transaction ended with {:?} instead of {:?}",
+            replayed, on_chain
+        )),
+    }
+}
+
+/// Checks that the payload on its own stopped where the whole transaction did.  The payload
+/// succeeds on its own if the transaction only failed after it, e.g. running out of gas for
+/// storage fees in the epilogue, and then all it did is shown.
+fn check_payload_replay(
+    on_chain: &ExecutionStatus,
+    replayed: &VMStatus,
+    payload_status: &VMStatus,
+) -> Result<(), String> {
+    let failed_after_payload = matches!(
+        on_chain,
+        ExecutionStatus::OutOfGas | ExecutionStatus::MiscellaneousError(_)
+    ) && payload_status.status_code() == StatusCode::EXECUTED;
+    if payload_status == replayed || failed_after_payload {
+        Ok(())
+    } else {
+        Err(format!(
+            "Replaying the payload on its own ended with {} instead of {}",
+            payload_status, replayed
+        ))
+    }
+}
+
+/// Splits an abort code into its category and reason, if it follows the `std::error`
+/// convention, and adds the name and description of the reason from the module's error map.
+fn explain_abort(location: &AbortLocation, code: u64, info: Option<AbortInfo>) -> AbortExplanation {
+    let category = match code >> 16 {
+        category @ 1..=13 => Some(ERROR_CATEGORIES[category as usize - 1]),
+        _ => None,
+    };
+    let (reason_name, description) = match info {
+        Some(info) => (Some(info.reason_name), Some(info.description)),
+        None => (None, None),
+    };
+    AbortExplanation {
+        location: location.to_string(),
+        code,
+        category,
+        reason: if category.is_some() {
+            code & 0xFFFF
+        } else {
+            code
+        },
+        reason_name,
+        description,
+    }
+}
+
+fn explain_event<T: ModuleResolver>(
+    annotator: &AptosValueAnnotator<T>,
+    event: &ContractEvent,
+) -> ExplainedEvent {
+    let data = match annotator.view_contract_event(event) {
+        Ok(value) => value.to_string(),
+        Err(_) => hex::encode(event.event_data()),
+    };
+    ExplainedEvent {
+        event_type: event.type_tag().to_string(),
+        data,
+    }
+}
+
+fn explain_change<T: ModuleResolver>(
+    annotator: &AptosValueAnnotator<T>,
+    state_key: &StateKey,
+    write_op: &WriteOp,
+) -> ExplainedChange {
+    let write = match write_op {
+        WriteOp::Creation { .. } => "creation",
+        WriteOp::Modification { .. } => "modification",
+        WriteOp::Deletion { .. } => "deletion",
+    };
+    let (state_key, value) = match state_key.inner() {
+        StateKeyInner::AccessPath(access_path) => {
+            let path = access_path.get_path();
+            let value = write_op
+                .bytes()
+                .and_then(|bytes| view_path(annotator, &path, bytes));
+            (format!("{}/{}", access_path.address, path), value)
+        },
+        StateKeyInner::TableItem { handle, key } => (
+            format!("table {}/{}", handle.0, hex::encode(key)),
+            write_op.bytes().map(hex::encode),
+        ),
+        StateKeyInner::Raw(key) => (hex::encode(key), write_op.bytes().map(hex::encode)),
+    };
+    ExplainedChange {
+        state_key,
+        write,
+        value,
+    }
+}
+
+/// Annotates a resource, or each resource of a resource group. Code is left out.
+fn view_path<T: ModuleResolver>(
+    annotator: &AptosValueAnnotator<T>,
+    path: &Path,
+    bytes: &[u8],
+) -> Option<String> {
+    match path {
+        Path::Code(_) => None,
+        Path::Resource(tag) => Some(match annotator.view_resource(tag, bytes) {
+            Ok(value) => value.to_string(),
+            Err(_) => hex::encode(bytes),
+        }),
+        Path::ResourceGroup(_) => {
+            let group = bcs::from_bytes::<BTreeMap<StructTag, Vec<u8>>>(bytes).ok()?;
+            let members: Vec<String> = group
+                .iter()
+                .map(|(tag, bytes)| match annotator.view_resource(tag, bytes) {
+                    Ok(value) => value.to_string(),
+                    Err(_) => format!("{} {}", tag, hex::encode(bytes)),
+                })
+                .collect();
+            Some(members.join("\n"))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+
+    fn module_location() -> AbortLocation {
+        AbortLocation::Module(ModuleId::new(
+            AccountAddress::ONE,
+            Identifier::new("coin").unwrap(),
+        ))
+    }
+
+    fn move_abort(code: u64) -> ExecutionStatus {
+        ExecutionStatus::MoveAbort {
+            location: module_location(),
+            code,
+            info: None,
+        }
+    }
+
+    #[test]
+    fn test_explain_abort() {
+        // `error::invalid_argument(6)` from a module with an error map
+        let info = AbortInfo {
+            reason_name: "EINSUFFICIENT_BALANCE".to_string(),
+            description: "Not enough coins to complete transaction".to_string(),
+        };
+        let abort = explain_abort(&module_location(), 0x1_0006, Some(info));
+        assert_eq!(abort.location, "0x1::coin");
+        assert_eq!(abort.code, 0x1_0006);
+        assert_eq!(abort.category, Some("INVALID_ARGUMENT"));
+        assert_eq!(abort.reason, 6);
+        assert_eq!(abort.reason_name.as_deref(), Some("EINSUFFICIENT_BALANCE"));
+        assert!(abort.description.is_some());
+
+        // The last category
+        let abort = explain_abort(&module_location(), 0xD_0001, None);
+        assert_eq!(abort.category, Some("UNAVAILABLE"));
+        assert_eq!(abort.reason, 1);
+        assert_eq!(abort.reason_name, None);
+    }
+
+    #[test]
+    fn test_explain_abort_without_category() {
+        // Codes that don't follow the `std::error` convention are kept whole
+        for code in [0, 7, 0xE_0001, 1 << 32] {
+            let abort = explain_abort(&AbortLocation::Script, code, None);
+            assert_eq!(abort.location, "Script");
+            assert_eq!(abort.category, None);
+            assert_eq!(abort.reason, code);
+        }
+    }
+
+    #[test]
+    fn test_check_replay() {
+        let status = move_abort(0x1_0006);
+        assert!(check_replay(&status, &TransactionStatus::Keep(status.clone())).is_ok());
+        assert!(check_replay(&status, &TransactionStatus::Keep(move_abort(0x1_0007))).is_err());
+        assert!(check_replay(
+            &status,
+            &TransactionStatus::Discard(StatusCode::SEQUENCE_NUMBER_TOO_OLD)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_check_payload_replay() {
+        let abort = VMStatus::MoveAbort(module_location(), 0x1_0006);
+        let out_of_gas = VMStatus::error(StatusCode::OUT_OF_GAS, None);
+
+        // The payload stopped where the transaction did
+        assert!(check_payload_replay(&move_abort(0x1_0006), &abort, &abort).is_ok());
+        assert!(check_payload_replay(
+            &ExecutionStatus::Success,
+            &VMStatus::Executed,
+            &VMStatus::Executed
+        )
+        .is_ok());
+        // The transaction ran out of gas after the payload, in the epilogue
+        assert!(
+            check_payload_replay(&ExecutionStatus::OutOfGas, &out_of_gas, &VMStatus::Executed)
+                .is_ok()
+        );
+
+        // The payload must not succeed on its own if the transaction aborted in it
+        assert!(check_payload_replay(&move_abort(0x1_0006), &abort, &VMStatus::Executed).is_err());
+        assert!(check_payload_replay(&ExecutionStatus::OutOfGas, &out_of_gas, &abort).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::types::{CliCommand, CliResult};
+use clap::Subcommand;
+
+pub mod explain;
+pub mod offline;
+
+/// Tool for building, signing and submitting transactions separately, and explaining them
+///
+/// This allows signing transactions on an offline machine: the transaction is built on an
+/// online machine, carried to the offline machine to be signed, and carried back to be
+/// submitted.
+#[derive(Subcommand)]
+pub enum TxnTool {
+    Build(offline::BuildTransaction),
+    Explain(explain::ExplainTransaction),
+    Sign(offline::SignTransaction),
+    Submit(offline::SubmitTransaction),
+}
+
+impl TxnTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            TxnTool::Build(tool) => tool.execute_serialized().await,
+            TxnTool::Explain(tool) => tool.execute_serialized().await,
+            TxnTool::Sign(tool) => tool.execute_serialized().await,
+            TxnTool::Submit(tool) => tool.execute_serialized().await,
+        }
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Building, signing and submitting a transaction in separate steps
+//!
+//! Transactions are carried between the steps in JSON files holding the BCS of the transaction,
+//! along with its fields decoded for review.  The decoded fields are checked against the BCS
+//! when a file is loaded, so that what is reviewed is what gets signed.
+
+use crate::common::{
+    signer::{AccountSigner, KeyScheme},
+    types::{
+        CliCommand, CliError, CliTypedResult, EncodingOptions, EntryFunctionArguments, GasOptions,
+        PrivateKeyInputOptions, ProfileOptions, PromptOptions, RestOptions, TransactionOptions,
+        TransactionSummary,
+    },
+    utils::{read_from_file, write_to_file},
+};
+use aptos_crypto::HashValue;
+use aptos_rest_client::aptos_api_types::{Event, Transaction};
+use aptos_sdk::types::{HardwareWalletAccount, HardwareWalletType, TransactionSigner};
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{RawTransaction, SignedTransaction, TransactionPayload},
+};
+use async_trait::async_trait;
+use clap::Parser;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Build an unsigned transaction, to be signed offline with `aptos txn sign`
+///
+/// The sequence number, gas and expiration are filled in from the network, so the transaction
+/// must be signed and submitted within `--expiration-secs`.  Gas is estimated by simulating
+/// the transaction, which needs the public key of the sender: it comes from the profile, whatever
+/// its key scheme, so the online machine doesn't need the private key and no passphrase is
+/// asked for.  Payloads written by `aptos move build-publish-payload` can be given with
+/// `--json-file`.
+#[derive(Parser)]
+pub struct BuildTransaction {
+    #[clap(flatten)]
+    pub(crate) entry_function_args: EntryFunctionArguments,
+
+    /// Sender account address
+    ///
+    /// Defaults to the account of the profile
+    #[clap(long, value_parser = crate::common::types::load_account_arg)]
+    pub(crate) sender_account: Option<AccountAddress>,
+
+    /// Number of seconds from now until the transaction expires
+    ///
+    /// This has to leave enough time to carry the transaction to the offline machine, sign it,
+    /// and carry it back to submit it.
+    #[clap(long)]
+    pub(crate) expiration_secs: u64,
+
+    /// Gas unit price in Octas
+    ///
+    /// Without a value, it is estimated from the network
+    #[clap(long)]
+    pub(crate) gas_unit_price: Option<u64>,
+
+    /// Maximum amount of gas units to be used by the transaction
+    ///
+    /// Without a value, it is estimated by simulating the transaction
+    #[clap(long)]
+    pub(crate) max_gas: Option<u64>,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+
+    /// File to write the unsigned transaction to
+    #[clap(long, value_parser)]
+    pub(crate) output_file: PathBuf,
+}
+
+#[async_trait]
+impl CliCommand<TransactionDetails> for BuildTransaction {
+    fn command_name(&self) -> &'static str {
+        "BuildTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionDetails> {
+        let payload = TransactionPayload::EntryFunction(self.entry_function_args.try_into()?);
+        let txn_options = TransactionOptions {
+            sender_account: self.sender_account,
+            profile_options: self.profile_options,
+            rest_options: self.rest_options,
+            gas_options: GasOptions {
+                gas_unit_price: self.gas_unit_price,
+                max_gas: self.max_gas,
+                expiration_secs: self.expiration_secs,
+            },
+            prompt_options: self.prompt_options,
+            ..Default::default()
+        };
+        let raw_txn = txn_options.build_unsigned_transaction(payload).await?;
+
+        let file = UnsignedTransactionFile::new(&raw_txn)?;
+        save_json(&self.output_file, "unsigned transaction", &file)?;
+        Ok(file.details)
+    }
+}
+
+/// Sign a transaction built with `aptos txn build`
+///
+/// Signs with the private key given on the command line, or else with the key of the profile,
+/// which may be encrypted, of any key scheme, or on a hardware wallet.  No network access is
+/// needed, so this can run on an offline machine.
+#[derive(Debug, Parser)]
+pub struct SignTransaction {
+    /// Unsigned transaction file, as written by `aptos txn build`
+    #[clap(long, value_parser)]
+    pub(crate) unsigned_file: PathBuf,
+
+    /// File to write the signed transaction to
+    #[clap(long, value_parser)]
+    pub(crate) output_file: PathBuf,
+
+    /// Scheme of the key given with `--private-key` or `--private-key-file`
+    #[clap(long, value_enum, default_value_t = KeyScheme::Ed25519)]
+    pub(crate) key_scheme: KeyScheme,
+
+    #[clap(flatten)]
+    pub(crate) private_key_options: PrivateKeyInputOptions,
+    #[clap(flatten)]
+    pub(crate) encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[async_trait]
+impl CliCommand<SignedTransactionFile> for SignTransaction {
+    fn command_name(&self) -> &'static str {
+        "SignTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<SignedTransactionFile> {
+        let unsigned: UnsignedTransactionFile = load_json(&self.unsigned_file)?;
+        let raw_txn = unsigned.raw_transaction()?;
+
+        let encoding = self.encoding_options.encoding;
+        let signer = match self.key_scheme {
+            KeyScheme::Ed25519 => self
+                .private_key_options
+                .extract_private_key_cli(encoding)?
+                .map(AccountSigner::Ed25519),
+            KeyScheme::Secp256k1Ecdsa => self
+                .private_key_options
+                .extract_secp256k1_private_key_cli(encoding)?
+                .map(AccountSigner::Secp256k1Ecdsa),
+            KeyScheme::MultiEd25519 => {
+                return Err(CliError::CommandArgumentError(
+                    "MultiEd25519 transactions are signed with the keys of the profile, or \
+                    co-signed with `aptos account sign-partial`"
+                        .to_string(),
+                ))
+            },
+        };
+        let signed_txn =
+            if let Some(signer) = signer {
+                signer.sign(raw_txn)?
+            } else {
+                let profile = self.profile_options.profile()?;
+                let derivation_path = profile.derivation_path.clone();
+                let public_key = profile.public_key.clone();
+                match (profile.signer()?, derivation_path, public_key) {
+                    (Some(signer), _, _) => signer.sign(raw_txn)?,
+                    (None, Some(derivation_path), Some(public_key)) => HardwareWalletAccount::new(
+                        raw_txn.sender(),
+                        public_key,
+                        derivation_path,
+                        HardwareWalletType::Ledger,
+                        // Only used when building transactions, the raw transaction is already built
+                        0,
+                    )
+                    .sign_transaction(raw_txn)?,
+                    _ => return Err(CliError::CommandArgumentError(
+                        "One of ['--private-key', '--private-key-file'] or a profile with a key \
+                        must be used"
+                            .to_string(),
+                    )),
+                }
+            };
+
+        let file = SignedTransactionFile::new(&signed_txn)?;
+        save_json(&self.output_file, "signed transaction", &file)?;
+        Ok(file)
+    }
+}
+
+/// Submit a transaction signed with `aptos txn sign`, and wait for its result
+#[derive(Debug, Parser)]
+pub struct SubmitTransaction {
+    /// Signed transaction file, as written by `aptos txn sign`
+    #[clap(long, value_parser)]
+    pub(crate) signed_file: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+/// The result of a submitted transaction, with the events it emitted
+#[derive(Debug, Serialize)]
+pub struct SubmitSummary {
+    #[serde(flatten)]
+    transaction: TransactionSummary,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    events: Vec<Event>,
+}
+
+#[async_trait]
+impl CliCommand<SubmitSummary> for SubmitTransaction {
+    fn command_name(&self) -> &'static str {
+        "SubmitTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<SubmitSummary> {
+        let signed: SignedTransactionFile = load_json(&self.signed_file)?;
+        let signed_txn = signed.signed_transaction()?;
+
+        let client = self.rest_options.client(&self.profile_options)?;
+        let response = client
+            .submit_and_wait(&signed_txn)
+            .await
+            .map_err(|err| CliError::ApiError(err.to_string()))?
+            .into_inner();
+
+        let events = match &response {
+            Transaction::UserTransaction(txn) => txn.events.clone(),
+            _ => vec![],
+        };
+        Ok(SubmitSummary {
+            transaction: TransactionSummary::from(&response),
+            events,
+        })
+    }
+}
+
+/// The fields of a transaction, decoded for review
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionDetails {
+    sender: AccountAddress,
+    sequence_number: u64,
+    /// The function called, or the kind of payload for other payloads
+    payload: String,
+    max_gas_amount: u64,
+    gas_unit_price: u64,
+    expiration_timestamp_secs: u64,
+    chain_id: u8,
+}
+
+impl TransactionDetails {
+    fn new(raw_txn: &RawTransaction) -> Self {
+        let payload = match raw_txn.payload() {
+            TransactionPayload::EntryFunction(entry_function) => {
+                format!("{}::{}", entry_function.module(), entry_function.function())
+            },
+            TransactionPayload::Script(_) => "script".to_string(),
+            TransactionPayload::Multisig(multisig) => {
+                format!("multisig of {}", multisig.multisig_address)
+            },
+            TransactionPayload::ModuleBundle(_) => "module bundle".to_string(),
+        };
+        TransactionDetails {
+            sender: raw_txn.sender(),
+            sequence_number: raw_txn.sequence_number(),
+            payload,
+            max_gas_amount: raw_txn.max_gas_amount(),
+            gas_unit_price: raw_txn.gas_unit_price(),
+            expiration_timestamp_secs: raw_txn.expiration_timestamp_secs(),
+            chain_id: raw_txn.chain_id().id(),
+        }
+    }
+}
+
+/// An unsigned transaction, as written by `aptos txn build`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UnsignedTransactionFile {
+    /// Hex encoded BCS of the `RawTransaction`
+    raw_transaction: String,
+    #[serde(flatten)]
+    details: TransactionDetails,
+}
+
+impl UnsignedTransactionFile {
+    fn new(raw_txn: &RawTransaction) -> CliTypedResult<Self> {
+        Ok(UnsignedTransactionFile {
+            raw_transaction: encode_bcs("raw_transaction", raw_txn)?,
+            details: TransactionDetails::new(raw_txn),
+        })
+    }
+
+    fn raw_transaction(&self) -> CliTypedResult<RawTransaction> {
+        let raw_txn: RawTransaction = decode_bcs("raw_transaction", &self.raw_transaction)?;
+        check_details(&self.details, &TransactionDetails::new(&raw_txn))?;
+        Ok(raw_txn)
+    }
+}
+
+/// A signed transaction, as written by `aptos txn sign`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SignedTransactionFile {
+    /// Hex encoded BCS of the `SignedTransaction`
+    signed_transaction: String,
+    transaction_hash: HashValue,
+    #[serde(flatten)]
+    details: TransactionDetails,
+}
+
+impl SignedTransactionFile {
+    fn new(signed_txn: &SignedTransaction) -> CliTypedResult<Self> {
+        Ok(SignedTransactionFile {
+            signed_transaction: encode_bcs("signed_transaction", signed_txn)?,
+            transaction_hash: signed_txn.clone().committed_hash(),
+            details: TransactionDetails::new(signed_txn.raw_transaction_ref()),
+        })
+    }
+
+    fn signed_transaction(&self) -> CliTypedResult<SignedTransaction> {
+        let signed_txn: SignedTransaction =
+            decode_bcs("signed_transaction", &self.signed_transaction)?;
+        check_details(
+            &self.details,
+            &TransactionDetails::new(signed_txn.raw_transaction_ref()),
+        )?;
+        signed_txn.verify_signature().map_err(|err| {
+            CliError::CommandArgumentError(format!("Invalid transaction signature: {}", err))
+        })?;
+        Ok(signed_txn)
+    }
+}
+
+fn check_details(
+    file_details: &TransactionDetails,
+    details: &TransactionDetails,
+) -> CliTypedResult<()> {
+    if file_details != details {
+        return Err(CliError::CommandArgumentError(format!(
+            "The transaction fields of the file don't match its BCS, which decodes to {:?}",
+            details
+        )));
+    }
+    Ok(())
+}
+
+fn encode_bcs<T: Serialize>(name: &'static str, value: &T) -> CliTypedResult<String> {
+    Ok(hex::encode(
+        bcs::to_bytes(value).map_err(|err| CliError::BCS(name, err))?,
+    ))
+}
+
+fn decode_bcs<T: DeserializeOwned>(name: &'static str, hex_bytes: &str) -> CliTypedResult<T> {
+    let bytes =
+        hex::decode(hex_bytes).map_err(|err| CliError::UnableToParse(name, err.to_string()))?;
+    bcs::from_bytes(&bytes).map_err(|err| CliError::BCS(name, err))
+}
+
+fn load_json<T: DeserializeOwned>(path: &Path) -> CliTypedResult<T> {
+    serde_json::from_slice(&read_from_file(path)?).map_err(|err| {
+        CliError::UnableToParse("transaction file", format!("{}: {}", path.display(), err))
+    })
+}
+
+fn save_json<T: Serialize>(path: &Path, name: &str, value: &T) -> CliTypedResult<()> {
+    let bytes = serde_json::to_vec_pretty(value)
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    write_to_file(path, name, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use aptos_types::{chain_id::ChainId, transaction::Script};
+
+    fn raw_txn(sequence_number: u64) -> RawTransaction {
+        RawTransaction::new(
+            AccountAddress::ONE,
+            sequence_number,
+            TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+            1000,
+            100,
+            u64::MAX,
+            ChainId::test(),
+        )
+    }
+
+    #[test]
+    fn test_offline_transaction_files() {
+        let unsigned = UnsignedTransactionFile::new(&raw_txn(5)).unwrap();
+        let unsigned: UnsignedTransactionFile =
+            serde_json::from_slice(&serde_json::to_vec(&unsigned).unwrap()).unwrap();
+        let raw_txn = unsigned.raw_transaction().unwrap();
+        assert_eq!(raw_txn.sequence_number(), 5);
+
+        let private_key = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+        let signed_txn = raw_txn
+            .sign(&private_key, private_key.public_key())
+            .unwrap()
+            .into_inner();
+        let signed = SignedTransactionFile::new(&signed_txn).unwrap();
+        assert_eq!(signed.signed_transaction().unwrap(), signed_txn);
+    }
+
+    #[test]
+    fn test_tampered_details_are_rejected() {
+        let mut unsigned = UnsignedTransactionFile::new(&raw_txn(5)).unwrap();
+        unsigned.details.sequence_number = 6;
+        assert!(unsigned.raw_transaction().is_err());
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    pre_partition::uniform_partitioner::config::UniformPartitionerConfig,
+    v2::config::PartitionerV2Config, BlockPartitioner, PartitionerConfig,
+};
+use aptos_types::{
+    block_executor::partitioner::PartitionedTransactions,
+    transaction::{
+        analyzed_transaction::{
+            empty_rw_set, try_get_read_write_hints, AnalyzedTransaction, StorageLocation,
+        },
+        Transaction,
+    },
+    write_set::{WriteSet, TOTAL_SUPPLY_STATE_KEY},
+};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The partitioner configurations compared by default.
+pub fn default_candidates() -> Vec<(String, Box<dyn PartitionerConfig>)> {
+    vec![
+        (
+            "v2-connected-component".to_string(),
+            Box::<PartitionerV2Config>::default(),
+        ),
+        (
+            "v2-uniform".to_string(),
+            Box::new(
+                PartitionerV2Config::default()
+                    .pre_partitioner_config(Box::new(UniformPartitionerConfig {})),
+            ),
+        ),
+        (
+            "v2-partition-last-round".to_string(),
+            Box::new(PartitionerV2Config::default().partition_last_round(true)),
+        ),
+    ]
+}
+
+/// Builds an `AnalyzedTransaction` out of a committed transaction.
+///
+/// Hints are derived from the payload if supported (see `try_get_read_write_hints`). Otherwise
+/// the keys in the write set are used as write hints. Reads are not persisted, so the read
+/// hints are left empty in that case, which underestimates the conflicts. The total supply is
+/// skipped, as it is updated by every transaction but handled as an aggregator by the VM.
+pub fn analyze_committed_transaction(
+    txn: Transaction,
+    write_set: &WriteSet,
+) -> AnalyzedTransaction {
+    let hints = match &txn {
+        Transaction::UserTransaction(signed_txn) => try_get_read_write_hints(signed_txn),
+        _ => Some(empty_rw_set()),
+    };
+    let (read_hints, write_hints) = hints.unwrap_or_else(|| {
+        let write_hints = write_set
+            .iter()
+            .filter(|(state_key, _)| *state_key != &*TOTAL_SUPPLY_STATE_KEY)
+            .map(|(state_key, _)| StorageLocation::Specific(state_key.clone()))
+            .collect();
+        (vec![], write_hints)
+    });
+    AnalyzedTransaction::new_with_hints(txn.into(), read_hints, write_hints)
+}
+
+/// Splits a stream of committed transactions into blocks, at the block metadata transactions.
+/// Only the user transactions are kept, as these are the ones that get partitioned.
+#[derive(Default)]
+pub struct BlockSplitter {
+    current_block: Vec<AnalyzedTransaction>,
+}
+
+impl BlockSplitter {
+    /// Returns the previous block once the next one starts.
+    pub fn push(
+        &mut self,
+        txn: Transaction,
+        write_set: &WriteSet,
+    ) -> Option<Vec<AnalyzedTransaction>> {
+        match txn {
+            Transaction::BlockMetadata(_) | Transaction::BlockMetadataExt(_) => self.finish(),
+            Transaction::UserTransaction(_) => {
+                self.current_block
+                    .push(analyze_committed_transaction(txn, write_set));
+                None
+            },
+            _ => None,
+        }
+    }
+
+    pub fn finish(&mut self) -> Option<Vec<AnalyzedTransaction>> {
+        if self.current_block.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.current_block))
+        }
+    }
+}
+
+/// Quality metrics of a single partitioned block.
+#[derive(Clone, Debug, Default)]
+pub struct PartitionQuality {
+    pub num_txns: usize,
+    pub num_global_txns: usize,
+    /// Number of rounds with at least one transaction, not counting the global round.
+    pub num_rounds: usize,
+    /// Number of (transaction, required transaction from another shard or round) pairs.
+    pub num_cross_shard_edges: usize,
+    /// Sum over the rounds of the largest sub-block, relative to a perfectly balanced split of the
+    /// sharded transactions. 1.0 means that no shard ever waits for another.
+    pub load_imbalance: f64,
+    pub partitioning_time: Duration,
+}
+
+impl PartitionQuality {
+    pub fn measure(partitioned: &PartitionedTransactions, partitioning_time: Duration) -> Self {
+        let num_shards = partitioned.num_shards();
+        let num_sharded_txns = partitioned.num_sharded_txns();
+
+        let mut round_sizes: Vec<usize> = vec![];
+        let mut num_cross_shard_edges = 0;
+        for sub_blocks in partitioned.sharded_txns() {
+            for (round_id, sub_block) in sub_blocks.sub_block_iter().enumerate() {
+                if round_sizes.len() <= round_id {
+                    round_sizes.resize(round_id + 1, 0);
+                }
+                round_sizes[round_id] = round_sizes[round_id].max(sub_block.num_txns());
+                num_cross_shard_edges += sub_block
+                    .transactions_with_deps()
+                    .iter()
+                    .map(|txn| txn.cross_shard_dependencies().num_required_edges())
+                    .sum::<usize>();
+            }
+        }
+        num_cross_shard_edges += partitioned
+            .global_txns
+            .iter()
+            .map(|txn| txn.cross_shard_dependencies().num_required_edges())
+            .sum::<usize>();
+
+        let load_imbalance = if num_sharded_txns == 0 {
+            1.0
+        } else {
+            round_sizes.iter().sum::<usize>() as f64 * num_shards as f64 / num_sharded_txns as f64
+        };
+
+        Self {
+            num_txns: partitioned.num_txns(),
+            num_global_txns: partitioned.num_txns() - num_sharded_txns,
+            num_rounds: round_sizes.iter().filter(|size| **size > 0).count(),
+            num_cross_shard_edges,
+            load_imbalance,
+            partitioning_time,
+        }
+    }
+}
+
+/// Runs every candidate partitioner on the same blocks and aggregates the quality metrics.
+pub struct PartitionerEvaluator {
+    num_shards: usize,
+    candidates: Vec<(String, Box<dyn BlockPartitioner>)>,
+    results: Vec<Vec<PartitionQuality>>,
+}
+
+impl PartitionerEvaluator {
+    pub fn new(num_shards: usize, candidates: Vec<(String, Box<dyn PartitionerConfig>)>) -> Self {
+        let results = vec![vec![]; candidates.len()];
+        Self {
+            num_shards,
+            candidates: candidates
+                .into_iter()
+                .map(|(name, config)| (name, config.build()))
+                .collect(),
+            results,
+        }
+    }
+
+    pub fn evaluate_block(&mut self, txns: Vec<AnalyzedTransaction>) {
+        for ((_, partitioner), results) in self.candidates.iter().zip(self.results.iter_mut()) {
+            let timer = Instant::now();
+            let partitioned = partitioner.partition(txns.clone(), self.num_shards);
+            results.push(PartitionQuality::measure(&partitioned, timer.elapsed()));
+        }
+    }
+
+    pub fn num_blocks(&self) -> usize {
+        self.results.first().map_or(0, Vec::len)
+    }
+
+    pub fn report(&self) -> EvaluationReport {
+        EvaluationReport {
+            num_shards: self.num_shards,
+            summaries: self
+                .candidates
+                .iter()
+                .zip(self.results.iter())
+                .map(|((name, _), results)| QualitySummary::new(name.clone(), results))
+                .collect(),
+        }
+    }
+}
+
+/// Quality metrics of a partitioner, aggregated over all evaluated blocks.
+#[derive(Clone, Debug)]
+pub struct QualitySummary {
+    pub name: String,
+    pub num_blocks: usize,
+    pub num_txns: usize,
+    pub num_global_txns: usize,
+    pub avg_rounds: f64,
+    pub max_rounds: usize,
+    pub num_cross_shard_edges: usize,
+    /// Average over the blocks, weighted by the number of transactions.
+    pub avg_load_imbalance: f64,
+    pub total_partitioning_time: Duration,
+    pub max_partitioning_time: Duration,
+}
+
+impl QualitySummary {
+    fn new(name: String, results: &[PartitionQuality]) -> Self {
+        let num_blocks = results.len();
+        let num_txns = results.iter().map(|r| r.num_txns).sum();
+        let weighted_imbalance: f64 = results
+            .iter()
+            .map(|r| r.load_imbalance * r.num_txns as f64)
+            .sum();
+        Self {
+            name,
+            num_blocks,
+            num_txns,
+            num_global_txns: results.iter().map(|r| r.num_global_txns).sum(),
+            avg_rounds: results.iter().map(|r| r.num_rounds).sum::<usize>() as f64
+                / num_blocks.max(1) as f64,
+            max_rounds: results.iter().map(|r| r.num_rounds).max().unwrap_or(0),
+            num_cross_shard_edges: results.iter().map(|r| r.num_cross_shard_edges).sum(),
+            avg_load_imbalance: if num_txns == 0 {
+                1.0
+            } else {
+                weighted_imbalance / num_txns as f64
+            },
+            total_partitioning_time: results.iter().map(|r| r.partitioning_time).sum(),
+            max_partitioning_time: results
+                .iter()
+                .map(|r| r.partitioning_time)
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+pub struct EvaluationReport {
+    pub num_shards: usize,
+    pub summaries: Vec<QualitySummary>,
+}
+
+impl fmt::Display for EvaluationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Partitioner quality with {} shards:", self.num_shards)?;
+        for s in &self.summaries {
+            writeln!(
+                f,
+                "  {}: blocks: {}, txns: {}, global txns: {}, rounds: avg {:.2} max {}, \
+                cross-shard edges: {} ({:.3} per txn), load imbalance: {:.3}, \
+                partitioning time: total {:?} max {:?}",
+                s.name,
+                s.num_blocks,
+                s.num_txns,
+                s.num_global_txns,
+                s.avg_rounds,
+                s.max_rounds,
+                s.num_cross_shard_edges,
+                s.num_cross_shard_edges as f64 / s.num_txns.max(1) as f64,
+                s.avg_load_imbalance,
+                s.total_partitioning_time,
+                s.max_partitioning_time,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@
 
 pub mod v2;
 
+pub mod evaluation;
 pub mod test_utils;
 
 use aptos_types::{
@@ -1,7 +1,10 @@
 // Copyright © Aptos Foundation
 
 use aptos_block_partitioner::{
-    test_utils::P2PBlockGenerator, v2::config::PartitionerV2Config, PartitionerConfig,
+    evaluation::{default_candidates, PartitionerEvaluator},
+    test_utils::P2PBlockGenerator,
+    v2::config::PartitionerV2Config,
+    PartitionerConfig,
 };
 use aptos_logger::info;
 use clap::Parser;
@@ -25,6 +28,12 @@ struct Args {
 
     #[clap(long, default_value_t = 48)]
     pub num_shards: usize,
+
+    /// Compare the quality of the default partitioner configurations on the generated blocks,
+    /// instead of only timing the partitioning.
+    /// To evaluate on historical blocks, see `aptos-db-tool evaluate-partitioners`.
+    #[clap(long)]
+    pub evaluate: bool,
 }
 
 fn main() {
@@ -32,6 +41,15 @@ fn main() {
     info!("Starting the block partitioning benchmark");
     let args = Args::parse();
     let block_gen = P2PBlockGenerator::new(args.num_accounts);
+    let mut rng = thread_rng();
+    if args.evaluate {
+        let mut evaluator = PartitionerEvaluator::new(args.num_shards, default_candidates());
+        for _ in 0..args.num_blocks {
+            evaluator.evaluate_block(block_gen.rand_block(&mut rng, args.block_size));
+        }
+        info!("{}", evaluator.report());
+        return;
+    }
     let partitioner = PartitionerV2Config::default()
         .max_partitioning_rounds(4)
         .num_threads(8)
@@ -39,7 +57,6 @@ fn main() {
         .dashmap_num_shards(64)
         .partition_last_round(false)
         .build();
-    let mut rng = thread_rng();
     for _ in 0..args.num_blocks {
         let transactions = block_gen.rand_block(&mut rng, args.block_size);
         info!("Starting to partition");
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    evaluation::{PartitionQuality, PartitionerEvaluator},
     test_utils::{
         create_non_conflicting_p2p_transaction, create_signed_p2p_transaction,
         generate_test_account, verify_partitioner_output,
@@ -12,7 +13,7 @@ use crate::{
 use aptos_types::{block_executor::partitioner::SubBlocksForShard, transaction::Transaction};
 use move_core_types::account_address::AccountAddress;
 use rand::{rngs::OsRng, Rng};
-use std::{collections::HashMap, sync::Mutex};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
 
 #[test]
 // Test that the partitioner works correctly for no conflict transactions. In this case, the
@@ -39,6 +40,39 @@ fn test_non_conflicting_txns() {
     }
 }
 
+#[test]
+fn test_partition_quality() {
+    let num_shards = 2;
+    let transactions: Vec<_> = (0..4)
+        .map(|_| create_non_conflicting_p2p_transaction())
+        .collect();
+    let partitioner = PartitionerV2Config::default().build();
+    let partitioned_txns = partitioner.partition(transactions.clone(), num_shards);
+    let quality = PartitionQuality::measure(&partitioned_txns, Duration::ZERO);
+    assert_eq!(quality.num_txns, 4);
+    assert_eq!(quality.num_global_txns, 0);
+    assert_eq!(quality.num_rounds, 1);
+    assert_eq!(quality.num_cross_shard_edges, 0);
+    assert_eq!(quality.load_imbalance, 1.0);
+
+    // All the transactions of a single sender conflict with each other.
+    let mut sender = generate_test_account();
+    let receiver = generate_test_account();
+    let conflicting_txns: Vec<_> = (0..4)
+        .map(|_| create_signed_p2p_transaction(&mut sender, vec![&receiver]).remove(0))
+        .collect();
+    let mut evaluator = PartitionerEvaluator::new(
+        num_shards,
+        vec![("v2".to_string(), Box::<PartitionerV2Config>::default())],
+    );
+    evaluator.evaluate_block(transactions);
+    evaluator.evaluate_block(conflicting_txns);
+    assert_eq!(evaluator.num_blocks(), 2);
+    let summary = &evaluator.report().summaries[0];
+    assert_eq!(summary.num_txns, 8);
+    assert!(summary.avg_load_imbalance > 1.0);
+}
+
 fn get_account_seq_number(txn: &Transaction) -> (AccountAddress, u64) {
     match txn {
         Transaction::UserTransaction(txn) => (txn.sender(), txn.sequence_number()),
@@ -2,6 +2,7 @@
 
 use crate::{metrics::TIMER, pipeline::ExecuteBlockMessage};
 use aptos_block_partitioner::{BlockPartitioner, PartitionerConfig};
+use aptos_consensus::TransactionShuffler;
 use aptos_crypto::HashValue;
 use aptos_experimental_runtimes::thread_manager::optimal_min_len;
 use aptos_logger::info;
@@ -27,10 +28,15 @@ pub(crate) struct BlockPreparationStage {
     num_executor_shards: usize,
     num_blocks_processed: usize,
     maybe_partitioner: Option<Box<dyn BlockPartitioner>>,
+    maybe_shuffler: Option<Arc<dyn TransactionShuffler>>,
 }
 
 impl BlockPreparationStage {
-    pub fn new(num_shards: usize, partitioner_config: &dyn PartitionerConfig) -> Self {
+    pub fn new(
+        num_shards: usize,
+        partitioner_config: &dyn PartitionerConfig,
+        maybe_shuffler: Option<Arc<dyn TransactionShuffler>>,
+    ) -> Self {
         let maybe_partitioner = if num_shards == 0 {
             None
         } else {
@@ -42,9 +48,39 @@ impl BlockPreparationStage {
             num_executor_shards: num_shards,
             num_blocks_processed: 0,
             maybe_partitioner,
+            maybe_shuffler,
         }
     }
 
+    /// Reorders the user transactions of the block, other transactions keep their position.
+    fn shuffle(shuffler: &dyn TransactionShuffler, txns: Vec<Transaction>) -> Vec<Transaction> {
+        let mut user_txns = vec![];
+        let slots: Vec<Option<Transaction>> = txns
+            .into_iter()
+            .map(|txn| match txn {
+                Transaction::UserTransaction(user_txn) => {
+                    user_txns.push(user_txn);
+                    None
+                },
+                txn => Some(txn),
+            })
+            .collect();
+
+        let mut shuffled_user_txns = shuffler.shuffle(user_txns).into_iter();
+        slots
+            .into_iter()
+            .map(|slot| {
+                slot.unwrap_or_else(|| {
+                    Transaction::UserTransaction(
+                        shuffled_user_txns
+                            .next()
+                            .expect("Shuffler must return all transactions"),
+                    )
+                })
+            })
+            .collect()
+    }
+
     pub fn process(&mut self, txns: Vec<Transaction>) -> ExecuteBlockMessage {
         let current_block_start_time = Instant::now();
         info!(
@@ -52,6 +88,15 @@ impl BlockPreparationStage {
             self.num_blocks_processed,
             txns.len()
         );
+        let txns = match &self.maybe_shuffler {
+            None => txns,
+            Some(shuffler) => {
+                let timer = TIMER.with_label_values(&["shuffle"]).start_timer();
+                let shuffled_txns = Self::shuffle(shuffler.as_ref(), txns);
+                timer.stop_and_record();
+                shuffled_txns
+            },
+        };
         let block_id = HashValue::random();
         let sig_verified_txns: Vec<SignatureVerifiedTransaction> = SIG_VERIFY_POOL.install(|| {
             let num_txns = txns.len();
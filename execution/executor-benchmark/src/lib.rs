@@ -11,6 +11,7 @@ mod ledger_update_stage;
 mod metrics;
 pub mod native_executor;
 pub mod pipeline;
+pub mod shard_processes;
 pub mod transaction_committer;
 pub mod transaction_executor;
 pub mod transaction_generator;
@@ -29,6 +30,7 @@ use aptos_executor::{
         APTOS_EXECUTOR_COMMIT_BLOCKS_SECONDS, APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS,
         APTOS_EXECUTOR_LEDGER_UPDATE_SECONDS, APTOS_EXECUTOR_OTHER_TIMERS_SECONDS,
         APTOS_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS, APTOS_PROCESSED_TXNS_OUTPUT_SIZE,
+        APTOS_PROCESSED_TXNS_RESOURCE_USAGE,
     },
 };
 use aptos_jellyfish_merkle::metrics::{
@@ -493,9 +495,19 @@ static OTHER_LABELS: &[(&str, bool, &str)] = &[
     ("2.2.4.", false, "make_checkpoint"),
 ];
 
+static RESOURCE_USAGE_LABELS: &[&str] = &[
+    "txns",
+    "bytes_read",
+    "bytes_written",
+    "state_keys_touched",
+    "native_calls",
+    "execution_micros",
+];
+
 #[derive(Debug, Clone)]
 struct ExecutionTimeMeasurement {
     output_size: f64,
+    resource_usage: HashMap<&'static str, u64>,
 
     partitioning_total: f64,
     execution_total: f64,
@@ -510,6 +522,17 @@ struct ExecutionTimeMeasurement {
 impl ExecutionTimeMeasurement {
     pub fn now() -> Self {
         let output_size = APTOS_PROCESSED_TXNS_OUTPUT_SIZE.get_sample_sum();
+        let resource_usage = RESOURCE_USAGE_LABELS
+            .iter()
+            .map(|resource| {
+                (
+                    *resource,
+                    APTOS_PROCESSED_TXNS_RESOURCE_USAGE
+                        .with_label_values(&["execution", resource])
+                        .get(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
 
         let partitioning_total = BLOCK_PARTITIONING_SECONDS.get_sample_sum();
         let execution_total = APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS.get_sample_sum();
@@ -533,6 +556,7 @@ impl ExecutionTimeMeasurement {
 
         Self {
             output_size,
+            resource_usage,
             partitioning_total,
             execution_total,
             vm_only,
@@ -548,6 +572,11 @@ impl ExecutionTimeMeasurement {
 
         Self {
             output_size: end.output_size - self.output_size,
+            resource_usage: end
+                .resource_usage
+                .into_iter()
+                .map(|(k, v)| (k, v - self.resource_usage.get(&k).unwrap()))
+                .collect::<HashMap<_, _>>(),
             partitioning_total: end.partitioning_total - self.partitioning_total,
             execution_total: end.execution_total - self.execution_total,
             vm_only: end.vm_only - self.vm_only,
@@ -635,6 +664,20 @@ impl OverallMeasuring {
             prefix,
             delta_execution.output_size / elapsed
         );
+        let tracked_txns = delta_execution.resource_usage["txns"];
+        if tracked_txns > 0 {
+            for resource in &RESOURCE_USAGE_LABELS[1..] {
+                let total = delta_execution.resource_usage[resource];
+                info!(
+                    "{} resource usage: {} {}/txn ({} over {} user txns)",
+                    prefix,
+                    total as f64 / tracked_txns as f64,
+                    resource,
+                    total,
+                    tracked_txns
+                );
+            }
+        }
 
         info!(
             "{} fraction of total: {:.3} in partitioning (component TPS: {})",
@@ -703,6 +746,7 @@ mod tests {
     fn test_generic_benchmark<E>(
         transaction_type: Option<TransactionTypeArg>,
         verify_sequence_numbers: bool,
+        pipeline_config: PipelineConfig,
     ) where
         E: TransactionBlockExecutor + 'static,
     {
@@ -742,13 +786,13 @@ mod tests {
             verify_sequence_numbers,
             NO_OP_STORAGE_PRUNER_CONFIG,
             false,
-            PipelineConfig::default(),
+            pipeline_config,
         );
     }
 
     #[test]
     fn test_benchmark_default() {
-        test_generic_benchmark::<AptosVM>(None, true);
+        test_generic_benchmark::<AptosVM>(None, true, PipelineConfig::default());
     }
 
     #[test]
@@ -760,12 +804,30 @@ mod tests {
         test_generic_benchmark::<AptosVM>(
             Some(TransactionTypeArg::ResourceGroupsGlobalWriteTag1KB),
             true,
+            PipelineConfig::default(),
         );
     }
 
     #[test]
     fn test_native_benchmark() {
         // correct execution not yet implemented, so cannot be checked for validity
-        test_generic_benchmark::<NativeExecutor>(None, false);
+        test_generic_benchmark::<NativeExecutor>(None, false, PipelineConfig::default());
+    }
+
+    #[test]
+    fn test_benchmark_verify_against_unsharded() {
+        // Blocks are partitioned and executed by the in-process shards, and every block is
+        // executed again unsharded to check that both reach the same state root.
+        AptosVM::set_num_shards_once(1);
+        AptosVM::set_concurrency_level_once(4);
+        test_generic_benchmark::<AptosVM>(
+            None,
+            true,
+            PipelineConfig {
+                num_executor_shards: 1,
+                verify_against_unsharded: true,
+                ..Default::default()
+            },
+        );
     }
 }
@@ -14,7 +14,9 @@ use aptos_config::config::{
     EpochSnapshotPrunerConfig, LedgerPrunerConfig, PrunerConfig, StateMerklePrunerConfig,
 };
 use aptos_executor::block_executor::TransactionBlockExecutor;
-use aptos_executor_benchmark::{native_executor::NativeExecutor, pipeline::PipelineConfig};
+use aptos_executor_benchmark::{
+    native_executor::NativeExecutor, pipeline::PipelineConfig, shard_processes::ShardProcesses,
+};
 use aptos_executor_service::remote_executor_client;
 use aptos_experimental_ptx_executor::PtxBlockExecutor;
 #[cfg(target_os = "linux")]
@@ -23,12 +25,14 @@ use aptos_metrics_core::{register_int_gauge, IntGauge};
 use aptos_profiler::{ProfilerConfig, ProfilerHandler};
 use aptos_push_metrics::MetricsPusher;
 use aptos_transaction_generator_lib::args::TransactionTypeArg;
+use aptos_types::block_executor::conflict_history::ConflictHistory;
 use aptos_vm::AptosVM;
 use clap::{ArgGroup, Parser, Subcommand};
 use once_cell::sync::Lazy;
 use std::{
     net::SocketAddr,
     path::PathBuf,
+    sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -110,6 +114,15 @@ pub struct PipelineOpt {
     allow_retries: bool,
     #[clap(long, default_value = "4")]
     num_generator_workers: usize,
+    /// Reorders the transactions of each block before execution, to spread conflicting ones.
+    #[clap(long)]
+    conflict_aware_shuffling: bool,
+    /// Also uses the conflicts observed while executing the previous blocks for reordering.
+    #[clap(long, requires = "conflict_aware_shuffling")]
+    use_conflict_history: bool,
+    /// Also executes every sharded block unsharded, and checks that the state roots match.
+    #[clap(long)]
+    verify_against_unsharded: bool,
     #[clap(flatten)]
     sharding_opt: ShardingOpt,
 }
@@ -126,6 +139,11 @@ impl PipelineOpt {
             num_executor_shards: self.sharding_opt.num_executor_shards,
             use_global_executor: self.sharding_opt.use_global_executor,
             num_generator_workers: self.num_generator_workers,
+            conflict_aware_shuffling: self.conflict_aware_shuffling,
+            conflict_history: self
+                .use_conflict_history
+                .then(|| Arc::new(ConflictHistory::new())),
+            verify_against_unsharded: self.verify_against_unsharded,
             partitioner_config: self.sharding_opt.partitioner_config(),
         }
     }
@@ -144,6 +162,14 @@ struct ShardingOpt {
     remote_executor_addresses: Option<Vec<SocketAddr>>,
     #[clap(long)]
     coordinator_address: Option<SocketAddr>,
+    /// Launches 'num_executor_shards' executor service processes on localhost, and executes
+    /// through them as remote shards.
+    #[clap(long, conflicts_with_all = &["remote_executor_addresses", "coordinator_address"])]
+    spawn_local_shards: bool,
+    /// Path of the executor service binary used by '--spawn-local-shards', defaults to the
+    /// aptos-executor-service binary next to this one.
+    #[clap(long, requires = "spawn_local_shards")]
+    executor_service_binary: Option<PathBuf>,
     #[clap(long, default_value = "4")]
     max_partitioning_rounds: usize,
     #[clap(long, default_value = "0.90")]
@@ -238,6 +264,16 @@ struct Opt {
     )]
     execution_threads: Option<usize>,
 
+    /// Records a trace of the parallel execution of every block (incarnations, read/write sets,
+    /// dependency waits, aborts), written to this directory in JSON and Chrome trace formats.
+    #[clap(long)]
+    execution_trace_dir: Option<PathBuf>,
+
+    /// Reports the resources used by user transactions (bytes read and written, state keys
+    /// touched, native calls and VM time), aggregated over the run.
+    #[clap(long)]
+    report_resource_usage: bool,
+
     #[clap(flatten)]
     pruner_opt: PrunerOpt,
 
@@ -337,7 +373,7 @@ enum Command {
     },
 }
 
-fn run<E>(opt: Opt)
+fn run<E>(opt: Opt, pipeline_config: PipelineConfig)
 where
     E: TransactionBlockExecutor + 'static,
 {
@@ -355,7 +391,7 @@ where
                 opt.pruner_opt.pruner_config(),
                 opt.verify_sequence_numbers,
                 opt.enable_storage_sharding,
-                opt.pipeline_opt.pipeline_config(),
+                pipeline_config,
             );
         },
         Command::RunExecutor {
@@ -404,7 +440,7 @@ where
                 opt.verify_sequence_numbers,
                 opt.pruner_opt.pruner_config(),
                 opt.enable_storage_sharding,
-                opt.pipeline_opt.pipeline_config(),
+                pipeline_config,
             );
         },
         Command::AddAccounts {
@@ -422,7 +458,7 @@ where
                 opt.pruner_opt.pruner_config(),
                 opt.verify_sequence_numbers,
                 opt.enable_storage_sharding,
-                opt.pipeline_opt.pipeline_config(),
+                pipeline_config,
             );
         },
     }
@@ -486,10 +522,43 @@ fn main() {
         execution_threads_per_shard = execution_threads;
     }
 
+    let maybe_shard_processes = opt.pipeline_opt.sharding_opt.spawn_local_shards.then(|| {
+        assert!(
+            execution_shards > 1,
+            "--spawn-local-shards requires more than one execution shard"
+        );
+        let executor_service_binary = opt
+            .pipeline_opt
+            .sharding_opt
+            .executor_service_binary
+            .clone()
+            .unwrap_or_else(ShardProcesses::default_executor_service_binary);
+        let shard_processes = ShardProcesses::spawn(
+            &executor_service_binary,
+            execution_shards,
+            execution_threads_per_shard,
+        );
+        remote_executor_client::set_remote_addresses(shard_processes.shard_addresses());
+        remote_executor_client::set_coordinator_address(shard_processes.coordinator_address());
+        shard_processes
+    });
+    if maybe_shard_processes.is_some() {
+        // Shards use their own threads, the local ones are only used for unsharded verification.
+        execution_threads_per_shard = execution_threads;
+    }
+
     AptosVM::set_num_shards_once(execution_shards);
     AptosVM::set_concurrency_level_once(execution_threads_per_shard);
     NativeExecutor::set_concurrency_level_once(execution_threads_per_shard);
     AptosVM::set_processed_transactions_detailed_counters();
+    if let Some(execution_trace_dir) = opt.execution_trace_dir.clone() {
+        AptosVM::set_execution_trace_dir_once(execution_trace_dir);
+    }
+    AptosVM::set_resource_usage_tracking(opt.report_resource_usage);
+    let pipeline_config = opt.pipeline_opt.pipeline_config();
+    if let Some(conflict_history) = &pipeline_config.conflict_history {
+        AptosVM::set_conflict_history_once(conflict_history.clone());
+    }
 
     let config = ProfilerConfig::new_with_defaults();
     let handler = ProfilerHandler::new(config);
@@ -508,13 +577,17 @@ fn main() {
     }
 
     if opt.vm_selection_opt.use_native_executor {
-        run::<NativeExecutor>(opt);
+        run::<NativeExecutor>(opt, pipeline_config);
     } else if opt.vm_selection_opt.use_ptx_executor {
         #[cfg(target_os = "linux")]
         ThreadManagerBuilder::set_thread_config_strategy(ThreadConfigStrategy::ThreadsPriority(48));
-        run::<PtxBlockExecutor>(opt);
+        run::<PtxBlockExecutor>(opt, pipeline_config);
     } else {
-        run::<AptosVM>(opt);
+        run::<AptosVM>(opt, pipeline_config);
+    }
+
+    if let Some(shard_processes) = maybe_shard_processes {
+        shard_processes.print_report();
     }
 
     if cpu_profiling {
@@ -6,12 +6,13 @@ use crate::{
     metrics::NUM_TXNS, OverallMeasuring, TransactionCommitter, TransactionExecutor,
 };
 use aptos_block_partitioner::v2::config::PartitionerV2Config;
+use aptos_consensus::{ConflictAwareShuffler, TransactionShuffler};
 use aptos_crypto::HashValue;
 use aptos_executor::block_executor::{BlockExecutor, TransactionBlockExecutor};
 use aptos_executor_types::{state_checkpoint_output::StateCheckpointOutput, BlockExecutorTrait};
 use aptos_logger::info;
 use aptos_types::{
-    block_executor::partitioner::ExecutableBlock,
+    block_executor::{conflict_history::ConflictHistory, partitioner::ExecutableBlock},
     transaction::{Transaction, Version},
 };
 use derivative::Derivative;
@@ -39,6 +40,10 @@ pub struct PipelineConfig {
     pub use_global_executor: bool,
     #[derivative(Default(value = "4"))]
     pub num_generator_workers: usize,
+    pub conflict_aware_shuffling: bool,
+    /// Conflicts observed by the executor, used by the conflict aware shuffler if specified.
+    pub conflict_history: Option<Arc<ConflictHistory>>,
+    pub verify_against_unsharded: bool,
     pub partitioner_config: PartitionerV2Config,
 }
 
@@ -108,8 +113,19 @@ where
 
         let mut join_handles = vec![];
 
-        let mut partitioning_stage =
-            BlockPreparationStage::new(num_partitioner_shards, &config.partitioner_config);
+        let maybe_shuffler = config.conflict_aware_shuffling.then(|| {
+            Arc::new(match &config.conflict_history {
+                Some(conflict_history) => {
+                    ConflictAwareShuffler::with_conflict_history(conflict_history.clone())
+                },
+                None => ConflictAwareShuffler::new(),
+            }) as Arc<dyn TransactionShuffler>
+        });
+        let mut partitioning_stage = BlockPreparationStage::new(
+            num_partitioner_shards,
+            &config.partitioner_config,
+            maybe_shuffler,
+        );
 
         let mut exe = TransactionExecutor::new(
             executor_1,
@@ -118,6 +134,7 @@ where
             config.allow_aborts,
             config.allow_discards,
             config.allow_retries,
+            config.verify_against_unsharded,
         );
 
         let mut ledger_update_stage =
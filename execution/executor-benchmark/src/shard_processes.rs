@@ -0,0 +1,158 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_config::utils;
+use aptos_executor_service::metrics::REMOTE_EXECUTOR_CROSS_SHARD_MSG_COUNT;
+use aptos_logger::info;
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+const SHARD_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Executor shards running as separate `aptos-executor-service` processes on localhost,
+/// so that remote sharded execution can be benchmarked end-to-end on a single machine.
+/// The processes are killed when this is dropped.
+pub struct ShardProcesses {
+    children: Vec<Child>,
+    shard_addresses: Vec<SocketAddr>,
+    coordinator_address: SocketAddr,
+}
+
+impl ShardProcesses {
+    /// The executor service binary is expected next to the benchmark binary, unless specified.
+    pub fn default_executor_service_binary() -> PathBuf {
+        std::env::current_exe()
+            .expect("Failed to get the current executable")
+            .with_file_name("aptos-executor-service")
+    }
+
+    pub fn spawn(
+        executor_service_binary: &Path,
+        num_shards: usize,
+        num_threads_per_shard: usize,
+    ) -> Self {
+        assert!(
+            executor_service_binary.exists(),
+            "Executor service binary not found at {:?}, build it with `cargo build -p aptos-executor-service`",
+            executor_service_binary
+        );
+        let localhost = |port| SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+        let coordinator_address = localhost(utils::get_available_port());
+        let shard_addresses: Vec<_> = (0..num_shards)
+            .map(|_| localhost(utils::get_available_port()))
+            .collect();
+
+        let children = (0..num_shards)
+            .map(|shard_id| {
+                let mut command = Command::new(executor_service_binary);
+                command
+                    .arg("--shard-id")
+                    .arg(shard_id.to_string())
+                    .arg("--num-shards")
+                    .arg(num_shards.to_string())
+                    .arg("--num-executor-threads")
+                    .arg(num_threads_per_shard.to_string())
+                    .arg("--coordinator-address")
+                    .arg(coordinator_address.to_string())
+                    .arg("--remote-executor-addresses")
+                    .args(shard_addresses.iter().map(|address| address.to_string()))
+                    .stdin(Stdio::null());
+                let child = command.spawn().unwrap_or_else(|e| {
+                    panic!("Failed to spawn executor shard {}: {}", shard_id, e)
+                });
+                info!(
+                    "Spawned executor shard {} (pid {}) on {}",
+                    shard_id,
+                    child.id(),
+                    shard_addresses[shard_id]
+                );
+                child
+            })
+            .collect();
+
+        let shard_processes = Self {
+            children,
+            shard_addresses,
+            coordinator_address,
+        };
+        shard_processes.wait_until_listening();
+        shard_processes
+    }
+
+    fn wait_until_listening(&self) {
+        let start_time = Instant::now();
+        for (shard_id, address) in self.shard_addresses.iter().enumerate() {
+            while TcpStream::connect_timeout(address, Duration::from_millis(100)).is_err() {
+                assert!(
+                    start_time.elapsed() < SHARD_STARTUP_TIMEOUT,
+                    "Executor shard {} did not start listening on {} in time",
+                    shard_id,
+                    address
+                );
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+        info!(
+            "All {} executor shards are listening, after {:?}",
+            self.shard_addresses.len(),
+            start_time.elapsed()
+        );
+    }
+
+    pub fn shard_addresses(&self) -> Vec<SocketAddr> {
+        self.shard_addresses.clone()
+    }
+
+    pub fn coordinator_address(&self) -> SocketAddr {
+        self.coordinator_address
+    }
+
+    /// CPU time consumed so far by each shard process, if the platform exposes it.
+    pub fn cpu_times(&self) -> Vec<Option<Duration>> {
+        self.children
+            .iter()
+            .map(|child| {
+                // The first field of schedstat is the time spent on the CPU, in nanoseconds.
+                let schedstat =
+                    fs::read_to_string(format!("/proc/{}/schedstat", child.id())).ok()?;
+                let cpu_time_ns = schedstat.split_whitespace().next()?.parse().ok()?;
+                Some(Duration::from_nanos(cpu_time_ns))
+            })
+            .collect()
+    }
+
+    pub fn print_report(&self) {
+        let cross_shard_count = |shard_id: &str, name: &str| {
+            REMOTE_EXECUTOR_CROSS_SHARD_MSG_COUNT
+                .with_label_values(&[shard_id, name])
+                .get()
+        };
+        for (shard_id, cpu_time) in self.cpu_times().into_iter().enumerate() {
+            let shard_label = shard_id.to_string();
+            info!(
+                "Executor shard {}: cpu time: {}, cross-shard msgs sent: {} ({} bytes), received: {} ({} bytes)",
+                shard_id,
+                cpu_time.map_or("unavailable".to_string(), |t| format!("{:.3}s", t.as_secs_f64())),
+                cross_shard_count(&shard_label, "msgs_sent"),
+                cross_shard_count(&shard_label, "bytes_sent"),
+                cross_shard_count(&shard_label, "msgs_received"),
+                cross_shard_count(&shard_label, "bytes_received"),
+            );
+        }
+    }
+}
+
+impl Drop for ShardProcesses {
+    fn drop(&mut self) {
+        for child in self.children.iter_mut() {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
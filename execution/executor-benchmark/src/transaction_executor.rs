@@ -5,10 +5,11 @@
 use crate::pipeline::LedgerUpdateMessage;
 use aptos_crypto::hash::HashValue;
 use aptos_executor::block_executor::{BlockExecutor, TransactionBlockExecutor};
-use aptos_executor_types::BlockExecutorTrait;
+use aptos_executor_types::{state_checkpoint_output::StateCheckpointOutput, BlockExecutorTrait};
 use aptos_logger::info;
 use aptos_types::block_executor::{
-    config::BlockExecutorConfigFromOnchain, partitioner::ExecutableBlock,
+    config::BlockExecutorConfigFromOnchain,
+    partitioner::{ExecutableBlock, ExecutableTransactions},
 };
 use std::{
     sync::{mpsc, Arc},
@@ -27,6 +28,7 @@ pub struct TransactionExecutor<V> {
     allow_aborts: bool,
     allow_discards: bool,
     allow_retries: bool,
+    verify_against_unsharded: bool,
 }
 
 impl<V> TransactionExecutor<V>
@@ -40,6 +42,7 @@ where
         allow_aborts: bool,
         allow_discards: bool,
         allow_retries: bool,
+        verify_against_unsharded: bool,
     ) -> Self {
         Self {
             num_blocks_processed: 0,
@@ -50,6 +53,7 @@ where
             allow_aborts,
             allow_discards,
             allow_retries,
+            verify_against_unsharded,
         }
     }
 
@@ -69,7 +73,20 @@ where
             self.num_blocks_processed, block_id
         );
         let num_txns = executable_block.transactions.num_transactions();
-        let output = self
+        let maybe_unsharded_block = (self.verify_against_unsharded
+            && matches!(
+                executable_block.transactions,
+                ExecutableTransactions::Sharded(_)
+            ))
+        .then(|| {
+            ExecutableBlock::new(
+                HashValue::random(),
+                ExecutableTransactions::Unsharded(
+                    executable_block.transactions.clone().into_txns(),
+                ),
+            )
+        });
+        let mut output = self
             .executor
             .execute_and_state_checkpoint(
                 executable_block,
@@ -87,12 +104,17 @@ where
         if !self.allow_retries {
             assert_eq!(output.txns_to_commit_len(), num_txns + 1);
         }
+        let execution_time = Instant::now().duration_since(execution_start_time);
+
+        if let Some(unsharded_block) = maybe_unsharded_block {
+            self.verify_against_unsharded(block_id, &mut output, unsharded_block);
+        }
 
         let msg = LedgerUpdateMessage {
             current_block_start_time,
             first_block_start_time: *self.maybe_first_block_start_time.as_ref().unwrap(),
             partition_time,
-            execution_time: Instant::now().duration_since(execution_start_time),
+            execution_time,
             block_id,
             parent_block_id: self.parent_block_id,
             state_checkpoint_output: output,
@@ -101,4 +123,36 @@ where
         self.parent_block_id = block_id;
         self.num_blocks_processed += 1;
     }
+
+    /// Executes the same transactions unsharded, in the order they were executed by the shards,
+    /// as a sibling block that gets discarded when the sharded block is committed, and checks
+    /// that both executions result in the same state root.
+    fn verify_against_unsharded(
+        &self,
+        block_id: HashValue,
+        output: &mut StateCheckpointOutput,
+        unsharded_block: ExecutableBlock,
+    ) {
+        let unsharded_output = self
+            .executor
+            .execute_and_state_checkpoint(
+                unsharded_block,
+                self.parent_block_id,
+                BENCHMARKS_BLOCK_EXECUTOR_ONCHAIN_CONFIG,
+            )
+            .unwrap();
+        let (_, _, unsharded_state_checkpoint_hashes, _, _) = unsharded_output.into_inner();
+        output
+            .check_and_update_state_checkpoint_hashes(unsharded_state_checkpoint_hashes)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Sharded execution of block {} diverged from unsharded execution: {}",
+                    block_id, e
+                )
+            });
+        info!(
+            "Verified sharded execution of block {} against unsharded execution.",
+            block_id
+        );
+    }
 }
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 mod error;
 pub mod local_executor_helper;
-mod metrics;
+pub mod metrics;
 pub mod process_executor_service;
 mod remote_cordinator_client;
 mod remote_cross_shard_client;
@@ -31,14 +31,30 @@ mod thread_executor_service;
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RemoteExecutionResult {
     pub inner: Result<Vec<Vec<TransactionOutput>>, VMStatus>,
+    pub cross_shard_stats: CrossShardMessageStats,
 }
 
 impl RemoteExecutionResult {
-    pub fn new(inner: Result<Vec<Vec<TransactionOutput>>, VMStatus>) -> Self {
-        Self { inner }
+    pub fn new(
+        inner: Result<Vec<Vec<TransactionOutput>>, VMStatus>,
+        cross_shard_stats: CrossShardMessageStats,
+    ) -> Self {
+        Self {
+            inner,
+            cross_shard_stats,
+        }
     }
 }
 
+/// Volume of the cross-shard messages exchanged by a shard while executing a block.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct CrossShardMessageStats {
+    pub num_msgs_sent: u64,
+    pub num_bytes_sent: u64,
+    pub num_msgs_received: u64,
+    pub num_bytes_received: u64,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum RemoteExecutionRequest {
     ExecuteBlock(ExecuteBlockCommand),
@@ -30,6 +30,22 @@ pub static REMOTE_EXECUTOR_TIMER: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static REMOTE_EXECUTOR_CROSS_SHARD_MSG_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "remote_executor_cross_shard_msg_count",
+        // metric description
+        "Cross-shard messages exchanged by the remote shards, as reported to the coordinator: \
+         1. msgs_sent: the number of messages sent by a shard; \
+         2. bytes_sent: the number of bytes sent by a shard; \
+         3. msgs_received: the number of messages received by a shard; \
+         4. bytes_received: the number of bytes received by a shard; ",
+        // metric labels (dimensions)
+        &["shard_id", "name"],
+    )
+    .unwrap()
+});
+
 pub static REMOTE_EXECUTOR_REMOTE_KV_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         // metric name
@@ -1,8 +1,9 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    metrics::REMOTE_EXECUTOR_TIMER, remote_state_view::RemoteStateViewClient, ExecuteBlockCommand,
-    RemoteExecutionRequest, RemoteExecutionResult,
+    metrics::REMOTE_EXECUTOR_TIMER, remote_cross_shard_client::CrossShardMessageCounters,
+    remote_state_view::RemoteStateViewClient, ExecuteBlockCommand, RemoteExecutionRequest,
+    RemoteExecutionResult,
 };
 use aptos_secure_net::network_controller::{Message, NetworkController};
 use aptos_types::{
@@ -21,6 +22,7 @@ pub struct RemoteCoordinatorClient {
     command_rx: Receiver<Message>,
     result_tx: Sender<Message>,
     shard_id: ShardId,
+    cross_shard_counters: Arc<CrossShardMessageCounters>,
 }
 
 impl RemoteCoordinatorClient {
@@ -28,6 +30,7 @@ impl RemoteCoordinatorClient {
         shard_id: ShardId,
         controller: &mut NetworkController,
         coordinator_address: SocketAddr,
+        cross_shard_counters: Arc<CrossShardMessageCounters>,
     ) -> Self {
         let execute_command_type = format!("execute_command_{}", shard_id);
         let execute_result_type = format!("execute_result_{}", shard_id);
@@ -43,6 +46,7 @@ impl RemoteCoordinatorClient {
             command_rx,
             result_tx,
             shard_id,
+            cross_shard_counters,
         }
     }
 
@@ -113,7 +117,8 @@ impl CoordinatorClient<RemoteStateViewClient> for RemoteCoordinatorClient {
     }
 
     fn send_execution_result(&self, result: Result<Vec<Vec<TransactionOutput>>, VMStatus>) {
-        let remote_execution_result = RemoteExecutionResult::new(result);
+        let remote_execution_result =
+            RemoteExecutionResult::new(result, self.cross_shard_counters.take());
         let output_message = bcs::to_bytes(&remote_execution_result).unwrap();
         self.result_tx.send(Message::new(output_message)).unwrap();
     }
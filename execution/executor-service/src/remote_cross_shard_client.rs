@@ -1,5 +1,6 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
+use crate::CrossShardMessageStats;
 use aptos_secure_net::network_controller::{Message, NetworkController};
 use aptos_types::block_executor::partitioner::{RoundId, ShardId, MAX_ALLOWED_PARTITIONING_ROUNDS};
 use aptos_vm::sharded_block_executor::{
@@ -8,18 +9,59 @@ use aptos_vm::sharded_block_executor::{
 use crossbeam_channel::{Receiver, Sender};
 use std::{
     net::SocketAddr,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
+/// Counts the cross-shard messages of a shard, until taken when the block execution result is
+/// sent to the coordinator.
+#[derive(Default)]
+pub struct CrossShardMessageCounters {
+    num_msgs_sent: AtomicU64,
+    num_bytes_sent: AtomicU64,
+    num_msgs_received: AtomicU64,
+    num_bytes_received: AtomicU64,
+}
+
+impl CrossShardMessageCounters {
+    fn record_sent(&self, num_bytes: usize) {
+        self.num_msgs_sent.fetch_add(1, Ordering::Relaxed);
+        self.num_bytes_sent
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    fn record_received(&self, num_bytes: usize) {
+        self.num_msgs_received.fetch_add(1, Ordering::Relaxed);
+        self.num_bytes_received
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn take(&self) -> CrossShardMessageStats {
+        CrossShardMessageStats {
+            num_msgs_sent: self.num_msgs_sent.swap(0, Ordering::Relaxed),
+            num_bytes_sent: self.num_bytes_sent.swap(0, Ordering::Relaxed),
+            num_msgs_received: self.num_msgs_received.swap(0, Ordering::Relaxed),
+            num_bytes_received: self.num_bytes_received.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
 pub struct RemoteCrossShardClient {
     // The senders of cross-shard messages to other shards per round.
     message_txs: Arc<Vec<Vec<Mutex<Sender<Message>>>>>,
     // The receivers of cross shard messages from other shards per round.
     message_rxs: Arc<Vec<Mutex<Receiver<Message>>>>,
+    counters: Arc<CrossShardMessageCounters>,
 }
 
 impl RemoteCrossShardClient {
-    pub fn new(controller: &mut NetworkController, shard_addresses: Vec<SocketAddr>) -> Self {
+    pub fn new(
+        controller: &mut NetworkController,
+        shard_addresses: Vec<SocketAddr>,
+        counters: Arc<CrossShardMessageCounters>,
+    ) -> Self {
         let mut message_txs = vec![];
         let mut message_rxs = vec![];
         // Create outbound channels for each shard per round.
@@ -43,6 +85,7 @@ impl RemoteCrossShardClient {
         Self {
             message_txs: Arc::new(message_txs),
             message_rxs: Arc::new(message_rxs),
+            counters,
         }
     }
 }
@@ -54,14 +97,16 @@ impl CrossShardClient for RemoteCrossShardClient {
 
     fn send_cross_shard_msg(&self, shard_id: ShardId, round: RoundId, msg: CrossShardMsg) {
         let input_message = bcs::to_bytes(&msg).unwrap();
+        self.counters.record_sent(input_message.len());
         let tx = self.message_txs[shard_id][round].lock().unwrap();
         tx.send(Message::new(input_message)).unwrap();
     }
 
     fn receive_cross_shard_msg(&self, current_round: RoundId) -> CrossShardMsg {
         let rx = self.message_rxs[current_round].lock().unwrap();
-        let message = rx.recv().unwrap();
-        let msg: CrossShardMsg = bcs::from_bytes(&message.to_bytes()).unwrap();
+        let message = rx.recv().unwrap().to_bytes();
+        self.counters.record_received(message.len());
+        let msg: CrossShardMsg = bcs::from_bytes(&message).unwrap();
         msg
     }
 }
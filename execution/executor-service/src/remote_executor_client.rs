@@ -2,8 +2,9 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    remote_state_view_service::RemoteStateViewService, ExecuteBlockCommand, RemoteExecutionRequest,
-    RemoteExecutionResult,
+    metrics::REMOTE_EXECUTOR_CROSS_SHARD_MSG_COUNT,
+    remote_state_view_service::RemoteStateViewService, CrossShardMessageStats, ExecuteBlockCommand,
+    RemoteExecutionRequest, RemoteExecutionResult,
 };
 use aptos_logger::{info, trace};
 use aptos_secure_net::network_controller::{Message, NetworkController};
@@ -164,13 +165,28 @@ impl<S: StateView + Sync + Send + 'static> RemoteExecutorClient<S> {
     fn get_output_from_shards(&self) -> Result<Vec<Vec<Vec<TransactionOutput>>>, VMStatus> {
         trace!("RemoteExecutorClient Waiting for results");
         let mut results = vec![];
-        for rx in self.result_rxs.iter() {
+        for (shard_id, rx) in self.result_rxs.iter().enumerate() {
             let received_bytes = rx.recv().unwrap().to_bytes();
             let result: RemoteExecutionResult = bcs::from_bytes(&received_bytes).unwrap();
+            Self::record_cross_shard_stats(shard_id, &result.cross_shard_stats);
             results.push(result.inner?);
         }
         Ok(results)
     }
+
+    fn record_cross_shard_stats(shard_id: usize, stats: &CrossShardMessageStats) {
+        let shard_id = shard_id.to_string();
+        for (name, value) in [
+            ("msgs_sent", stats.num_msgs_sent),
+            ("bytes_sent", stats.num_bytes_sent),
+            ("msgs_received", stats.num_msgs_received),
+            ("bytes_received", stats.num_bytes_received),
+        ] {
+            REMOTE_EXECUTOR_CROSS_SHARD_MSG_COUNT
+                .with_label_values(&[&shard_id, name])
+                .inc_by(value);
+        }
+    }
 }
 
 impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for RemoteExecutorClient<S> {
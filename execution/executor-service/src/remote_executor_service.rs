@@ -3,7 +3,8 @@
 
 use crate::{
     remote_cordinator_client::RemoteCoordinatorClient,
-    remote_cross_shard_client::RemoteCrossShardClient, remote_state_view::RemoteStateViewClient,
+    remote_cross_shard_client::{CrossShardMessageCounters, RemoteCrossShardClient},
+    remote_state_view::RemoteStateViewClient,
 };
 use aptos_secure_net::network_controller::NetworkController;
 use aptos_types::block_executor::partitioner::ShardId;
@@ -29,14 +30,17 @@ impl ExecutorService {
     ) -> Self {
         let service_name = format!("executor_service-{}", shard_id);
         let mut controller = NetworkController::new(service_name, self_address, 5000);
+        let cross_shard_counters = Arc::new(CrossShardMessageCounters::default());
         let coordinator_client = Arc::new(RemoteCoordinatorClient::new(
             shard_id,
             &mut controller,
             coordinator_address,
+            cross_shard_counters.clone(),
         ));
         let cross_shard_client = Arc::new(RemoteCrossShardClient::new(
             &mut controller,
             remote_shard_addresses,
+            cross_shard_counters,
         ));
 
         let executor_service = Arc::new(ShardedExecutorService::new(
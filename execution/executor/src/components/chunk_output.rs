@@ -317,6 +317,21 @@ pub fn update_counters_for_processed_chunk<T, O>(
             TransactionStatus::Retry => ("retry", "", "".to_string()),
         };
 
+        if let Some(usage) = output.get_transaction_output().resource_usage() {
+            for (resource, value) in [
+                ("txns", 1),
+                ("bytes_read", usage.bytes_read),
+                ("bytes_written", usage.bytes_written),
+                ("state_keys_touched", usage.num_state_keys_touched),
+                ("native_calls", usage.num_native_calls),
+                ("execution_micros", usage.execution_time.as_micros() as u64),
+            ] {
+                metrics::APTOS_PROCESSED_TXNS_RESOURCE_USAGE
+                    .with_label_values(&[process_type, resource])
+                    .inc_by(value);
+            }
+        }
+
         let kind = match txn.get_transaction() {
             Some(Transaction::UserTransaction(_)) => "user_transaction",
             Some(Transaction::GenesisTransaction(_)) => "genesis",
@@ -219,3 +219,12 @@ pub static APTOS_PROCESSED_TXNS_OUTPUT_SIZE: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static APTOS_PROCESSED_TXNS_RESOURCE_USAGE: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_processed_txns_resource_usage",
+        "Counter of resources used by processed transactions, when reported by the VM",
+        &["process", "resource"]
+    )
+    .unwrap()
+});
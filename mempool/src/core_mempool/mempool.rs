@@ -21,7 +21,7 @@ use aptos_logger::prelude::*;
 use aptos_types::{
     account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
-    transaction::SignedTransaction,
+    transaction::{ReplayProtector, SignedTransaction},
     vm_status::DiscardedVMStatus,
 };
 use std::{
@@ -311,9 +311,12 @@ impl Mempool {
                     &upgraded,
                     &exclude_transactions,
                 );
-            // include transaction if it's "next" for given account or
-            // we've already sent its ancestor to Consensus.
-            if previous_txn_was_seen || account_sequence_number == Some(&tx_seq) {
+            // include transaction if it's "next" for given account, we've already sent its
+            // ancestor to Consensus, or it's orderless and has no ancestor.
+            if previous_txn_was_seen
+                || account_sequence_number == Some(&tx_seq)
+                || ReplayProtector::is_orderless(tx_seq)
+            {
                 let ptr = TxnPointer::from(txn);
                 seen.insert(ptr, txn.gas_ranking_score);
                 result.push(ptr);
@@ -321,6 +324,11 @@ impl Mempool {
                     break;
                 }
 
+                // orderless txns are never skipped, nor do they unblock skipped txns
+                if ReplayProtector::is_orderless(tx_seq) {
+                    continue;
+                }
+
                 // check if we can now include some transactions
                 // that were skipped before for given account
                 let mut skipped_txn = TxnPointer::new(txn.address, tx_seq + 1);
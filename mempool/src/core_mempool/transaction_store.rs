@@ -23,10 +23,10 @@ use aptos_logger::{prelude::*, Level};
 use aptos_types::{
     account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
-    transaction::SignedTransaction,
+    transaction::{ReplayProtector, SignedTransaction, ORDERLESS_SEQUENCE_NUMBER_START},
 };
 use std::{
-    cmp::max,
+    cmp::{max, min},
     collections::HashMap,
     mem::size_of,
     ops::Bound,
@@ -354,9 +354,12 @@ impl TransactionStore {
     /// (this handles both cases where, (1) txn is first possible txn for an account and (2) the
     /// previous txn is committed).
     /// 2. The txn before this is ready for broadcast but not yet committed.
+    /// Orderless transactions do not depend on any other transaction and are always ready.
     fn check_txn_ready(&self, txn: &MempoolTransaction, curr_sequence_number: u64) -> bool {
         let tx_sequence_number = txn.sequence_info.transaction_sequence_number;
-        if tx_sequence_number == curr_sequence_number {
+        if tx_sequence_number == curr_sequence_number
+            || ReplayProtector::is_orderless(tx_sequence_number)
+        {
             return true;
         } else if tx_sequence_number == 0 {
             // shouldn't really get here because filtering out old txn sequence numbers happens earlier in workflow
@@ -426,36 +429,34 @@ impl TransactionStore {
     ///   should be included in both the PriorityIndex (ordering for Consensus) and
     ///   TimelineIndex (txns for SharedMempool).
     /// - Other txns are considered to be "non-ready" and should be added to ParkingLotIndex.
+    /// - Orderless txns of a given account are always ready.
     fn process_ready_transactions(&mut self, address: &AccountAddress, sequence_num: u64) {
         if let Some(txns) = self.transactions.get_mut(address) {
             let mut min_seq = sequence_num;
 
             while let Some(txn) = txns.get_mut(&min_seq) {
-                let process_ready = !self.priority_index.contains(txn);
-                self.priority_index.insert(txn);
-
-                let process_broadcast_ready = txn.timeline_state == TimelineState::NotReady;
-                if process_broadcast_ready {
-                    self.timeline_index.insert(txn);
-                }
-
-                if process_ready {
-                    Self::log_ready_transaction(
-                        txn.ranking_score,
-                        self.timeline_index.get_bucket(txn.ranking_score),
-                        &txn.insertion_info,
-                        process_broadcast_ready,
-                    );
-                }
-
-                // Remove txn from parking lot after it has been promoted to
-                // priority_index / timeline_index, i.e., txn status is ready.
-                self.parking_lot_index.remove(txn);
+                Self::promote_ready_transaction(
+                    &mut self.priority_index,
+                    &mut self.timeline_index,
+                    &mut self.parking_lot_index,
+                    txn,
+                );
                 min_seq += 1;
             }
+            for (_, txn) in txns.range_mut(ORDERLESS_SEQUENCE_NUMBER_START..) {
+                Self::promote_ready_transaction(
+                    &mut self.priority_index,
+                    &mut self.timeline_index,
+                    &mut self.parking_lot_index,
+                    txn,
+                );
+            }
 
             let mut parking_lot_txns = 0;
-            for (_, txn) in txns.range_mut((Bound::Excluded(min_seq), Bound::Unbounded)) {
+            for (_, txn) in txns.range_mut((
+                Bound::Excluded(min_seq),
+                Bound::Excluded(ORDERLESS_SEQUENCE_NUMBER_START),
+            )) {
                 match txn.timeline_state {
                     TimelineState::Ready(_) => {},
                     _ => {
@@ -476,6 +477,34 @@ impl TransactionStore {
         }
     }
 
+    fn promote_ready_transaction(
+        priority_index: &mut PriorityIndex,
+        timeline_index: &mut MultiBucketTimelineIndex,
+        parking_lot_index: &mut ParkingLotIndex,
+        txn: &mut MempoolTransaction,
+    ) {
+        let process_ready = !priority_index.contains(txn);
+        priority_index.insert(txn);
+
+        let process_broadcast_ready = txn.timeline_state == TimelineState::NotReady;
+        if process_broadcast_ready {
+            timeline_index.insert(txn);
+        }
+
+        if process_ready {
+            Self::log_ready_transaction(
+                txn.ranking_score,
+                timeline_index.get_bucket(txn.ranking_score),
+                &txn.insertion_info,
+                process_broadcast_ready,
+            );
+        }
+
+        // Remove txn from parking lot after it has been promoted to
+        // priority_index / timeline_index, i.e., txn status is ready.
+        parking_lot_index.remove(txn);
+    }
+
     fn clean_committed_transactions(&mut self, address: &AccountAddress, sequence_number: u64) {
         // Remove all previous seq number transactions for this account.
         // This can happen if transactions are sent to multiple nodes and one of the
@@ -510,7 +539,19 @@ impl TransactionStore {
     /// Handles transaction commit.
     /// It includes deletion of all transactions with sequence number <= `account_sequence_number`
     /// and potential promotion of sequential txns to PriorityIndex/TimelineIndex.
+    /// Committing an orderless transaction only removes that transaction.
     pub fn commit_transaction(&mut self, account: &AccountAddress, sequence_number: u64) {
+        if ReplayProtector::is_orderless(sequence_number) {
+            if let Some(txn) = self
+                .transactions
+                .get_mut(account)
+                .and_then(|txns| txns.remove(&sequence_number))
+            {
+                self.index_remove(&txn);
+            }
+            return;
+        }
+
         let current_seq_number = self.get_sequence_number(account).map_or(0, |v| *v);
         let new_seq_number = max(current_seq_number, sequence_number + 1);
         self.sequence_numbers.insert(*account, new_seq_number);
@@ -708,21 +749,27 @@ impl TransactionStore {
         };
         while let Some(key) = gc_iter.next() {
             if let Some(txns) = self.transactions.get_mut(&key.address) {
-                let park_range_start = Bound::Excluded(key.sequence_number);
-                let park_range_end = gc_iter
-                    .peek()
-                    .filter(|next_key| key.address == next_key.address)
-                    .map_or(Bound::Unbounded, |next_key| {
-                        Bound::Excluded(next_key.sequence_number)
-                    });
-                // mark all following txns as non-ready, i.e. park them
-                for (_, t) in txns.range_mut((park_range_start, park_range_end)) {
-                    self.parking_lot_index.insert(t);
-                    t.was_parked = true;
-                    self.priority_index.remove(t);
-                    self.timeline_index.remove(t);
-                    if let TimelineState::Ready(_) = t.timeline_state {
-                        t.timeline_state = TimelineState::NotReady;
+                // orderless txns don't depend on the removed txn, and neither do the txns
+                // after a removed orderless txn
+                if !ReplayProtector::is_orderless(key.sequence_number) {
+                    let park_range_start = Bound::Excluded(key.sequence_number);
+                    let park_range_end = Bound::Excluded(
+                        gc_iter
+                            .peek()
+                            .filter(|next_key| key.address == next_key.address)
+                            .map_or(ORDERLESS_SEQUENCE_NUMBER_START, |next_key| {
+                                min(next_key.sequence_number, ORDERLESS_SEQUENCE_NUMBER_START)
+                            }),
+                    );
+                    // mark all following txns as non-ready, i.e. park them
+                    for (_, t) in txns.range_mut((park_range_start, park_range_end)) {
+                        self.parking_lot_index.insert(t);
+                        t.was_parked = true;
+                        self.priority_index.remove(t);
+                        self.timeline_index.remove(t);
+                        if let TimelineState::Ready(_) = t.timeline_state {
+                            t.timeline_state = TimelineState::NotReady;
+                        }
                     }
                 }
                 if let Some(txn) = txns.remove(&key.sequence_number) {
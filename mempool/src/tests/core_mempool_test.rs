@@ -13,7 +13,9 @@ use aptos_config::config::NodeConfig;
 use aptos_consensus_types::common::{TransactionInProgress, TransactionSummary};
 use aptos_crypto::HashValue;
 use aptos_types::{
-    mempool_status::MempoolStatusCode, transaction::SignedTransaction, vm_status::DiscardedVMStatus,
+    mempool_status::MempoolStatusCode,
+    transaction::{ReplayProtector, SignedTransaction},
+    vm_status::DiscardedVMStatus,
 };
 use itertools::Itertools;
 use maplit::btreemap;
@@ -200,6 +202,44 @@ fn test_commit_transaction() {
     );
 }
 
+#[test]
+fn test_orderless_transactions() {
+    let (mut pool, _) = setup_mempool();
+    let nonce = |nonce| ReplayProtector::Nonce(nonce).to_sequence_number_field();
+
+    // Sequence number 0 is missing, so sequence number 1 is parked, but orderless txns are ready.
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 1, 1),
+            TestTransaction::new(0, nonce(7), 1),
+            TestTransaction::new(0, nonce(3), 1),
+        ],
+    );
+    assert_eq!(pool.get_parking_lot_size(), 1);
+    let mut batch: Vec<_> = pool
+        .get_batch(10, 10240, true, false, btreemap![])
+        .iter()
+        .map(SignedTransaction::replay_protector)
+        .collect();
+    batch.sort_unstable();
+    assert_eq!(
+        batch,
+        vec![ReplayProtector::Nonce(3), ReplayProtector::Nonce(7)]
+    );
+
+    // Committing an orderless txn leaves the other txns of the account alone.
+    pool.commit_transaction(&txns[1].sender(), txns[1].sequence_number());
+    assert!(pool.get_by_hash(txns[1].clone().committed_hash()).is_none());
+    assert!(pool.get_by_hash(txns[0].clone().committed_hash()).is_some());
+    assert!(pool.get_by_hash(txns[2].clone().committed_hash()).is_some());
+
+    // Once sequence number 0 is committed, sequence number 1 becomes ready too.
+    pool.commit_transaction(&txns[0].sender(), 0);
+    assert_eq!(pool.get_parking_lot_size(), 0);
+    assert_eq!(pool.get_batch(10, 10240, true, false, btreemap![]).len(), 2);
+}
+
 #[test]
 fn test_reject_transaction() {
     let (mut pool, _) = setup_mempool();
@@ -45,7 +45,7 @@ use aptos_storage_service_types::{
     responses::{StorageServerSummary, StorageServiceResponse, TransactionOrOutputListWithProof},
     Epoch, StorageServiceMessage,
 };
-use aptos_time_service::TimeService;
+use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{
     epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
@@ -503,12 +503,77 @@ impl AptosDataClient {
         Ok(selected_peer)
     }
 
-    /// Chooses peers randomly weighted by latency from the given set of serviceable peers
+    /// Chooses peers randomly weighted by latency from the given set of serviceable peers.
+    /// If throughput-aware requests are enabled, peers with measured throughputs are
+    /// instead weighted by their throughputs (so that requests are distributed across
+    /// peers in proportion to their observed capacity). Some selection slots are reserved
+    /// for peers without measured throughputs, so that new peers are also measured.
     fn choose_random_peers_by_latency(
         &self,
         serviceable_peers: HashSet<PeerNetworkId>,
         num_peers_to_choose: usize,
     ) -> HashSet<PeerNetworkId> {
+        // Choose peers weighted by throughput (if enabled)
+        let throughput_config = &self.data_client_config.data_throughput_config;
+        if throughput_config.enable_throughput_aware_requests {
+            let (measured_peers, unmeasured_peers): (HashSet<_>, HashSet<_>) = serviceable_peers
+                .iter()
+                .cloned()
+                .partition(|peer| self.peer_states.get_measured_throughput(peer).is_some());
+            if !measured_peers.is_empty() {
+                // Choose the unmeasured peers for the exploration slots (weighted by latency)
+                let num_exploration_slots = if unmeasured_peers.is_empty() {
+                    0
+                } else {
+                    utils::calculate_num_exploration_slots(
+                        num_peers_to_choose,
+                        throughput_config.exploration_percentage,
+                    )
+                };
+                let selected_peers = utils::choose_peers_by_latency(
+                    self.data_client_config.clone(),
+                    num_exploration_slots as u64,
+                    unmeasured_peers.clone(),
+                    self.get_peers_and_metadata(),
+                    true,
+                );
+                let mut selected_peers = utils::extend_with_random_peers(
+                    selected_peers,
+                    unmeasured_peers,
+                    num_exploration_slots,
+                );
+
+                // Choose the measured peers for the remaining slots (weighted by throughput)
+                let num_remaining_peers = num_peers_to_choose.saturating_sub(selected_peers.len());
+                selected_peers.extend(utils::choose_peers_by_throughput(
+                    num_remaining_peers as u64,
+                    measured_peers,
+                    self.peer_states.clone(),
+                ));
+
+                // Extend the selected peers with latency weighted peers (if necessary)
+                let remaining_peers = serviceable_peers
+                    .difference(&selected_peers)
+                    .cloned()
+                    .collect::<HashSet<_>>();
+                let num_remaining_peers = num_peers_to_choose.saturating_sub(selected_peers.len());
+                selected_peers.extend(utils::choose_peers_by_latency(
+                    self.data_client_config.clone(),
+                    num_remaining_peers as u64,
+                    remaining_peers,
+                    self.get_peers_and_metadata(),
+                    true,
+                ));
+
+                // Extend the selected peers with random peers (if necessary)
+                return utils::extend_with_random_peers(
+                    selected_peers,
+                    serviceable_peers,
+                    num_peers_to_choose,
+                );
+            }
+        }
+
         // Choose peers weighted by latency
         let selected_peers = utils::choose_peers_by_latency(
             self.data_client_config.clone(),
@@ -700,6 +765,11 @@ impl AptosDataClient {
     {
         // Start the timer for the request
         let timer = start_request_timer(&metrics::REQUEST_LATENCIES, &request.get_label(), peer);
+        let request_start_time = self.time_service.now();
+        let measure_throughput = self
+            .data_client_config
+            .data_throughput_config
+            .enable_throughput_aware_requests;
 
         // Get the response from the peer
         let response = self
@@ -711,6 +781,18 @@ impl AptosDataClient {
         let storage_response = match response {
             Ok(storage_response) => {
                 timer.stop_and_record(); // Update the latency metrics
+
+                // Update the measured throughput for the peer (if enabled)
+                if measure_throughput {
+                    let response_time = self.time_service.now().duration_since(request_start_time);
+                    self.update_peer_throughput(
+                        peer,
+                        &request,
+                        &storage_response.payload,
+                        response_time,
+                    );
+                }
+
                 storage_response
             },
             Err(error) => {
@@ -849,6 +931,38 @@ impl AptosDataClient {
         }
     }
 
+    /// Updates the measured throughput of the peer using the given response
+    fn update_peer_throughput(
+        &self,
+        peer: PeerNetworkId,
+        request: &StorageServiceRequest,
+        storage_response: &StorageServiceResponse,
+        response_time: Duration,
+    ) {
+        // Calculate the number of bytes in the response. For compressed
+        // responses, this is (approximately) the number of bytes sent over the wire.
+        let num_bytes = match bcs::serialized_size(storage_response) {
+            Ok(num_bytes) => num_bytes as u64,
+            Err(error) => {
+                warn!(
+                    (LogSchema::new(LogEntry::StorageServiceResponse)
+                        .event(LogEvent::ResponseError)
+                        .request_type(&request.get_label())
+                        .peer(&peer)
+                        .message(&format!(
+                            "Unable to calculate the response size! Error: {:?}",
+                            error
+                        )))
+                );
+                return;
+            },
+        };
+
+        // Update the peer's throughput
+        self.peer_states
+            .update_throughput(peer, request, num_bytes, response_time);
+    }
+
     /// Updates the score of the peer who sent the response with the specified id
     fn notify_bad_response(
         &self,
@@ -9,6 +9,7 @@ use aptos_metrics_core::{
 };
 
 // Useful metric constants and labels
+pub const ADAPTIVE_CHUNK_SIZE_PERCENTAGE_LABEL: &str = "adaptive_chunk_size_percentage";
 pub const BLOCK_TIMESTAMP_LAG_LABEL: &str = "block_timestamp_lag";
 pub const PRIORITIZED_PEER: &str = "prioritized_peer";
 pub const PROPOSE_TO_SEEN_LATENCY_LABEL: &str = "propose_to_seen_latency";
@@ -141,6 +142,16 @@ pub static OPTIMAL_CHUNK_SIZES: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Gauge for the throughput-aware request scheduling state
+pub static THROUGHPUT_SCHEDULING: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_data_client_throughput_scheduling",
+        "Gauge related to the throughput-aware request scheduling",
+        &["label"]
+    )
+    .unwrap()
+});
+
 // Latency buckets for the sync latencies (seconds). Note: there are a
 // lot of buckets here because we really care about sync latencies.
 const SYNC_LATENCY_BUCKETS_SECS: &[f64] = &[
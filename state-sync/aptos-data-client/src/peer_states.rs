@@ -8,6 +8,7 @@ use crate::{
     metrics,
 };
 use aptos_config::{config::AptosDataClientConfig, network_id::PeerNetworkId};
+use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_storage_service_types::{
     requests::StorageServiceRequest, responses::StorageServerSummary,
@@ -15,7 +16,7 @@ use aptos_storage_service_types::{
 use aptos_time_service::TimeService;
 use dashmap::DashMap;
 use std::{
-    cmp::min,
+    cmp::{max, min},
     collections::{BTreeMap, HashSet},
     sync::Arc,
     time::Duration,
@@ -59,6 +60,66 @@ impl From<ResponseError> for ErrorType {
     }
 }
 
+/// Tracks the measured throughput and response times of a peer. These are
+/// maintained as exponential moving averages over all successful responses.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PeerThroughput {
+    /// The moving average of the response throughput (bytes per second)
+    average_bytes_per_sec: f64,
+    /// The moving average of the response time (seconds), normalized
+    /// to the response time of requests with unscaled chunk sizes.
+    average_response_time_secs: f64,
+    /// The number of samples that have been measured
+    num_samples: u64,
+}
+
+impl PeerThroughput {
+    /// Returns the moving average of the throughput (bytes per second)
+    pub fn get_average_bytes_per_sec(&self) -> f64 {
+        self.average_bytes_per_sec
+    }
+
+    /// Returns the moving average of the normalized response time (seconds)
+    pub fn get_average_response_time_secs(&self) -> f64 {
+        self.average_response_time_secs
+    }
+
+    /// Returns the number of measured samples
+    pub fn get_num_samples(&self) -> u64 {
+        self.num_samples
+    }
+
+    /// Updates the moving averages using the given response measurement.
+    /// The first sample is used directly (to avoid a slow warm-up period).
+    fn record_sample(
+        &mut self,
+        num_bytes: u64,
+        response_time: Duration,
+        normalized_response_time: Duration,
+        sample_weight: f64,
+    ) {
+        // Calculate the throughput of the response (ignoring zero response times)
+        let response_time_secs = response_time.as_secs_f64();
+        if response_time_secs <= 0.0 {
+            return;
+        }
+        let bytes_per_sec = num_bytes as f64 / response_time_secs;
+        let response_time_secs = normalized_response_time.as_secs_f64();
+
+        // Update the moving averages
+        if self.num_samples == 0 {
+            self.average_bytes_per_sec = bytes_per_sec;
+            self.average_response_time_secs = response_time_secs;
+        } else {
+            self.average_bytes_per_sec = (sample_weight * bytes_per_sec)
+                + ((1.0 - sample_weight) * self.average_bytes_per_sec);
+            self.average_response_time_secs = (sample_weight * response_time_secs)
+                + ((1.0 - sample_weight) * self.average_response_time_secs);
+        }
+        self.num_samples = self.num_samples.saturating_add(1);
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PeerState {
     /// The number of responses received from this peer (by data request label)
@@ -70,6 +131,8 @@ pub struct PeerState {
     storage_summary: Option<StorageServerSummary>,
     /// For now, a simplified port of the original state-sync v1 scoring system.
     score: f64,
+    /// The measured throughput and response times of the peer
+    throughput: PeerThroughput,
 }
 
 impl Default for PeerState {
@@ -79,6 +142,7 @@ impl Default for PeerState {
             sent_requests_by_type: Arc::new(DashMap::new()),
             storage_summary: None,
             score: STARTING_SCORE,
+            throughput: PeerThroughput::default(),
         }
     }
 }
@@ -105,6 +169,11 @@ impl PeerState {
         self.score
     }
 
+    /// Returns the measured throughput of the peer
+    pub fn get_throughput(&self) -> PeerThroughput {
+        self.throughput
+    }
+
     /// Returns the storage summary for the peer
     pub fn get_storage_summary(&self) -> Option<StorageServerSummary> {
         self.storage_summary.clone()
@@ -152,6 +221,22 @@ impl PeerState {
         self.score = f64::max(self.score * multiplier, MIN_SCORE);
     }
 
+    /// Updates the measured throughput of the peer
+    fn update_throughput(
+        &mut self,
+        num_bytes: u64,
+        response_time: Duration,
+        normalized_response_time: Duration,
+        sample_weight: f64,
+    ) {
+        self.throughput.record_sample(
+            num_bytes,
+            response_time,
+            normalized_response_time,
+            sample_weight,
+        );
+    }
+
     /// Updates the storage summary for the peer
     fn update_storage_summary(&mut self, storage_summary: StorageServerSummary) {
         self.storage_summary = Some(storage_summary);
@@ -164,6 +249,8 @@ impl PeerState {
 pub struct PeerStates {
     data_client_config: Arc<AptosDataClientConfig>,
     peer_to_state: Arc<DashMap<PeerNetworkId, PeerState>>,
+    /// The factor by which the optimal chunk sizes are currently scaled
+    chunk_size_scaling_factor: Arc<Mutex<f64>>,
 }
 
 impl PeerStates {
@@ -171,6 +258,7 @@ impl PeerStates {
         Self {
             data_client_config,
             peer_to_state: Arc::new(DashMap::new()),
+            chunk_size_scaling_factor: Arc::new(Mutex::new(1.0)),
         }
     }
 
@@ -294,6 +382,65 @@ impl PeerStates {
         }
     }
 
+    /// Updates the measured throughput for the given peer using
+    /// the size and response time of a successful data response.
+    pub fn update_throughput(
+        &self,
+        peer: PeerNetworkId,
+        request: &StorageServiceRequest,
+        num_bytes: u64,
+        response_time: Duration,
+    ) {
+        // Only measure requests that are served immediately. Summary and
+        // version polls are tiny, and optimistic fetches and subscriptions
+        // block on the server until new data arrives.
+        let data_request = &request.data_request;
+        if data_request.is_storage_summary_request()
+            || data_request.is_protocol_version_request()
+            || data_request.is_optimistic_fetch()
+            || data_request.is_subscription_request()
+        {
+            return;
+        }
+
+        // Normalize the response time to that of an unscaled chunk. Otherwise,
+        // scaling down the chunk sizes would make the peers appear faster, and
+        // the chunk sizes would be scaled back up (i.e., they would oscillate).
+        let chunk_size_scaling_factor = *self.chunk_size_scaling_factor.lock();
+        let normalized_response_time = response_time.div_f64(chunk_size_scaling_factor);
+
+        // Update the peer's throughput
+        if let Some(mut entry) = self.peer_to_state.get_mut(&peer) {
+            let throughput_config = &self.data_client_config.data_throughput_config;
+            let sample_weight = throughput_config.sample_weight_percentage.min(100) as f64 / 100.0;
+            entry.update_throughput(
+                num_bytes,
+                response_time,
+                normalized_response_time,
+                sample_weight,
+            );
+        }
+    }
+
+    /// Returns the measured throughput (bytes per second) of the given
+    /// peer, iff enough samples have been collected for the peer.
+    pub fn get_measured_throughput(&self, peer: &PeerNetworkId) -> Option<f64> {
+        let min_samples = self
+            .data_client_config
+            .data_throughput_config
+            .min_samples_for_throughput;
+        self.peer_to_state.get(peer).and_then(|peer_state| {
+            let throughput = peer_state.get_throughput();
+            if throughput.get_num_samples() >= min_samples
+                && throughput.get_average_bytes_per_sec() > 0.0
+            {
+                Some(throughput.get_average_bytes_per_sec())
+            } else {
+                None
+            }
+        })
+    }
+
     /// Updates the storage summary for the given peer
     pub fn update_summary(&self, peer: PeerNetworkId, storage_summary: StorageServerSummary) {
         self.peer_to_state
@@ -310,17 +457,23 @@ impl PeerStates {
 
     /// Calculates a global data summary using all known storage summaries
     pub fn calculate_global_data_summary(&self) -> GlobalDataSummary {
-        // Gather all storage summaries, but exclude peers that are ignored
-        let storage_summaries: Vec<StorageServerSummary> = self
-            .peer_to_state
-            .iter()
-            .filter_map(|peer_state| {
-                peer_state
-                    .value()
-                    .get_storage_summary_if_not_ignored()
-                    .cloned()
-            })
-            .collect();
+        // Gather all storage summaries and peer response times, but exclude peers that are ignored
+        let min_samples = self
+            .data_client_config
+            .data_throughput_config
+            .min_samples_for_throughput;
+        let mut storage_summaries = vec![];
+        let mut peer_response_times_secs = vec![];
+        for peer_state in self.peer_to_state.iter() {
+            if let Some(storage_summary) = peer_state.value().get_storage_summary_if_not_ignored() {
+                storage_summaries.push(storage_summary.clone());
+
+                let throughput = peer_state.value().get_throughput();
+                if throughput.get_num_samples() >= min_samples {
+                    peer_response_times_secs.push(throughput.get_average_response_time_secs());
+                }
+            }
+        }
 
         // If we have no peers, return an empty global summary
         if storage_summaries.is_empty() {
@@ -367,13 +520,31 @@ impl PeerStates {
         }
 
         // Calculate optimal chunk sizes based on the advertised data
-        let optimal_chunk_sizes = calculate_optimal_chunk_sizes(
+        let mut optimal_chunk_sizes = calculate_optimal_chunk_sizes(
             &self.data_client_config,
             max_epoch_chunk_sizes,
             max_state_chunk_sizes,
             max_transaction_chunk_sizes,
             max_transaction_output_chunk_sizes,
         );
+
+        // Adapt the chunk sizes to the measured peer response times (if enabled)
+        if self
+            .data_client_config
+            .data_throughput_config
+            .enable_throughput_aware_requests
+        {
+            let mut chunk_size_scaling_factor = self.chunk_size_scaling_factor.lock();
+            let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+                &self.data_client_config,
+                optimal_chunk_sizes,
+                *chunk_size_scaling_factor,
+                peer_response_times_secs,
+            );
+            optimal_chunk_sizes = adaptive_chunk_sizes;
+            *chunk_size_scaling_factor = scaling_factor;
+        }
+
         GlobalDataSummary {
             advertised_data,
             optimal_chunk_sizes,
@@ -415,6 +586,70 @@ pub(crate) fn calculate_optimal_chunk_sizes(
     }
 }
 
+/// Scales the given chunk sizes so that the median peer can respond to
+/// a single request within the configured target response time. The
+/// response times must be normalized to requests with unscaled chunk sizes.
+/// The chunk sizes are never increased (they are already bounded by the peer
+/// advertisements), and are never reduced below the configured minimum
+/// percentage. To avoid oscillations, the current scaling factor is kept
+/// unless the peers are too slow for it, or are fast enough for it to grow by
+/// more than the configured hysteresis. Returns the scaled chunk sizes and the
+/// new scaling factor.
+pub(crate) fn calculate_adaptive_chunk_sizes(
+    config: &AptosDataClientConfig,
+    optimal_chunk_sizes: OptimalChunkSizes,
+    current_scaling_factor: f64,
+    mut peer_response_times_secs: Vec<f64>,
+) -> (OptimalChunkSizes, f64) {
+    // Calculate the target scaling factor using the median response time of the peers
+    let throughput_config = &config.data_throughput_config;
+    peer_response_times_secs.retain(|response_time| *response_time > 0.0);
+    let scaling_factor = if peer_response_times_secs.is_empty() {
+        current_scaling_factor
+    } else {
+        peer_response_times_secs.sort_unstable_by(|a, b| a.total_cmp(b));
+        let median_response_time_secs =
+            peer_response_times_secs[peer_response_times_secs.len() / 2];
+
+        let target_response_time_secs = throughput_config.target_response_time_ms as f64 / 1000.0;
+        let min_scaling_factor =
+            throughput_config.min_chunk_size_percentage.clamp(1, 100) as f64 / 100.0;
+        let target_scaling_factor =
+            (target_response_time_secs / median_response_time_secs).clamp(min_scaling_factor, 1.0);
+
+        // Only change the scaling factor if the expected response time (using
+        // the current chunk sizes) is above the target, or well below it.
+        let expected_response_time_secs = median_response_time_secs * current_scaling_factor;
+        let hysteresis = 1.0 + (throughput_config.chunk_size_hysteresis_percentage as f64 / 100.0);
+        if expected_response_time_secs > target_response_time_secs
+            || expected_response_time_secs * hysteresis < target_response_time_secs
+        {
+            target_scaling_factor
+        } else {
+            current_scaling_factor
+        }
+    };
+
+    // Update the metrics
+    metrics::set_gauge(
+        &metrics::THROUGHPUT_SCHEDULING,
+        metrics::ADAPTIVE_CHUNK_SIZE_PERCENTAGE_LABEL,
+        (scaling_factor * 100.0) as u64,
+    );
+
+    // Scale the chunk sizes (ensuring that each chunk size is at least 1)
+    let scale_chunk_size = |chunk_size: u64| max(1, (chunk_size as f64 * scaling_factor) as u64);
+    let adaptive_chunk_sizes = OptimalChunkSizes {
+        epoch_chunk_size: scale_chunk_size(optimal_chunk_sizes.epoch_chunk_size),
+        state_chunk_size: scale_chunk_size(optimal_chunk_sizes.state_chunk_size),
+        transaction_chunk_size: scale_chunk_size(optimal_chunk_sizes.transaction_chunk_size),
+        transaction_output_chunk_size: scale_chunk_size(
+            optimal_chunk_sizes.transaction_output_chunk_size,
+        ),
+    };
+    (adaptive_chunk_sizes, scaling_factor)
+}
+
 /// Calculates the median of the given set of values (if it exists)
 /// and returns the median or the specified max value, whichever is
 /// lower.
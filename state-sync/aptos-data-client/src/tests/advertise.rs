@@ -4,19 +4,27 @@
 use crate::{
     client::AptosDataClient,
     error::Error,
+    global_summary::OptimalChunkSizes,
     interface::AptosDataClientInterface,
-    peer_states::calculate_optimal_chunk_sizes,
+    peer_states::{calculate_adaptive_chunk_sizes, calculate_optimal_chunk_sizes, PeerStates},
     poller,
     priority::PeerPriority,
     tests::{mock::MockNetwork, utils},
 };
-use aptos_config::{config::AptosDataClientConfig, network_id::PeerNetworkId};
+use aptos_config::{
+    config::{AptosDataClientConfig, AptosDataThroughputConfig},
+    network_id::{NetworkId, PeerNetworkId},
+};
 use aptos_storage_service_types::{
-    requests::{DataRequest, TransactionsWithProofRequest},
+    requests::{DataRequest, StorageServiceRequest, TransactionsWithProofRequest},
     responses::{CompleteDataRange, DataResponse, StorageServerSummary, StorageServiceResponse},
 };
-use aptos_types::transaction::{TransactionListWithProof, Version};
-use claims::assert_matches;
+use aptos_types::{
+    transaction::{TransactionListWithProof, Version},
+    PeerId,
+};
+use claims::{assert_matches, assert_none, assert_some_eq};
+use std::{sync::Arc, time::Duration};
 
 #[tokio::test]
 async fn request_works_only_when_data_available() {
@@ -318,6 +326,253 @@ async fn optimal_chunk_size_calculations() {
     assert_eq!(400, optimal_chunk_sizes.transaction_output_chunk_size);
 }
 
+#[tokio::test]
+async fn adaptive_chunk_size_calculations() {
+    // Create a data client config with throughput-aware requests
+    let data_client_config = AptosDataClientConfig {
+        data_throughput_config: AptosDataThroughputConfig {
+            enable_throughput_aware_requests: true,
+            min_chunk_size_percentage: 10,
+            target_response_time_ms: 2000,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // Create the optimal chunk sizes
+    let optimal_chunk_sizes = OptimalChunkSizes {
+        epoch_chunk_size: 100,
+        state_chunk_size: 1000,
+        transaction_chunk_size: 2000,
+        transaction_output_chunk_size: 3000,
+    };
+
+    // Verify that the chunk sizes are unchanged if there are no response times
+    let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+        &data_client_config,
+        optimal_chunk_sizes.clone(),
+        1.0,
+        vec![],
+    );
+    assert_eq!(adaptive_chunk_sizes, optimal_chunk_sizes);
+    assert_eq!(scaling_factor, 1.0);
+
+    // Verify that the chunk sizes are unchanged if the peers respond within the target
+    let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+        &data_client_config,
+        optimal_chunk_sizes.clone(),
+        1.0,
+        vec![0.5, 1.0, 1.5],
+    );
+    assert_eq!(adaptive_chunk_sizes, optimal_chunk_sizes);
+    assert_eq!(scaling_factor, 1.0);
+
+    // Verify that the chunk sizes are halved if the median response time is twice the target
+    let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+        &data_client_config,
+        optimal_chunk_sizes.clone(),
+        1.0,
+        vec![1.0, 4.0, 10.0],
+    );
+    assert_eq!(
+        adaptive_chunk_sizes,
+        create_chunk_sizes(50, 500, 1000, 1500)
+    );
+    assert_eq!(scaling_factor, 0.5);
+
+    // Verify that the chunk sizes are never reduced below the minimum percentage
+    let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+        &data_client_config,
+        optimal_chunk_sizes,
+        1.0,
+        vec![100.0, 200.0, 300.0],
+    );
+    assert_eq!(adaptive_chunk_sizes, create_chunk_sizes(10, 100, 200, 300));
+    assert_eq!(scaling_factor, 0.1);
+}
+
+#[tokio::test]
+async fn adaptive_chunk_size_hysteresis() {
+    // Create a data client config with throughput-aware requests
+    let data_client_config = AptosDataClientConfig {
+        data_throughput_config: AptosDataThroughputConfig {
+            enable_throughput_aware_requests: true,
+            chunk_size_hysteresis_percentage: 20,
+            min_chunk_size_percentage: 10,
+            target_response_time_ms: 2000,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let optimal_chunk_sizes = create_chunk_sizes(100, 1000, 2000, 3000);
+
+    // Verify that the chunk sizes are reduced if the peers are too slow for the current sizes
+    let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+        &data_client_config,
+        optimal_chunk_sizes.clone(),
+        0.5,
+        vec![5.0],
+    );
+    assert_eq!(adaptive_chunk_sizes, create_chunk_sizes(40, 400, 800, 1200));
+    assert_eq!(scaling_factor, 0.4);
+
+    // Verify that the chunk sizes are kept if the peers are only slightly faster
+    let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+        &data_client_config,
+        optimal_chunk_sizes.clone(),
+        0.4,
+        vec![4.5],
+    );
+    assert_eq!(adaptive_chunk_sizes, create_chunk_sizes(40, 400, 800, 1200));
+    assert_eq!(scaling_factor, 0.4);
+
+    // Verify that the chunk sizes are increased if the peers are much faster
+    let (adaptive_chunk_sizes, scaling_factor) = calculate_adaptive_chunk_sizes(
+        &data_client_config,
+        optimal_chunk_sizes.clone(),
+        0.4,
+        vec![2.5],
+    );
+    assert_eq!(
+        adaptive_chunk_sizes,
+        create_chunk_sizes(80, 800, 1600, 2400)
+    );
+    assert_eq!(scaling_factor, 0.8);
+
+    // Verify that the chunk sizes are kept if there are no response times
+    let (adaptive_chunk_sizes, scaling_factor) =
+        calculate_adaptive_chunk_sizes(&data_client_config, optimal_chunk_sizes, 0.8, vec![]);
+    assert_eq!(
+        adaptive_chunk_sizes,
+        create_chunk_sizes(80, 800, 1600, 2400)
+    );
+    assert_eq!(scaling_factor, 0.8);
+}
+
+#[tokio::test]
+async fn adaptive_chunk_sizes_are_stable() {
+    // Create the peer states with throughput-aware requests
+    let target_response_time_ms = 2000;
+    let data_client_config = AptosDataClientConfig {
+        data_throughput_config: AptosDataThroughputConfig {
+            enable_throughput_aware_requests: true,
+            min_samples_for_throughput: 1,
+            sample_weight_percentage: 100,
+            target_response_time_ms,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let peer_states = PeerStates::new(Arc::new(data_client_config));
+
+    // Add a peer to the peer states
+    let peer = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+    peer_states.update_summary(peer, utils::create_storage_summary(100));
+    let optimal_chunk_size = peer_states
+        .calculate_global_data_summary()
+        .optimal_chunk_sizes
+        .transaction_chunk_size;
+
+    // Repeatedly measure a peer that takes twice the target time for an optimal chunk
+    let data_request = create_transactions_request();
+    let mut chunk_sizes = vec![];
+    for _ in 0..10 {
+        let chunk_size = peer_states
+            .calculate_global_data_summary()
+            .optimal_chunk_sizes
+            .transaction_chunk_size;
+        let response_time_ms = 2 * target_response_time_ms * chunk_size / optimal_chunk_size;
+        peer_states.update_throughput(
+            peer,
+            &data_request,
+            chunk_size,
+            Duration::from_millis(response_time_ms),
+        );
+        chunk_sizes.push(chunk_size);
+    }
+
+    // Verify that the chunk sizes are halved once, and then never change
+    assert_eq!(chunk_sizes[0], optimal_chunk_size);
+    for chunk_size in &chunk_sizes[1..] {
+        assert_eq!(*chunk_size, optimal_chunk_size / 2);
+    }
+}
+
+#[tokio::test]
+async fn peer_throughput_measurements() {
+    // Create the peer states with a small number of required samples
+    let data_client_config = AptosDataClientConfig {
+        data_throughput_config: AptosDataThroughputConfig {
+            min_samples_for_throughput: 2,
+            sample_weight_percentage: 50,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let peer_states = PeerStates::new(Arc::new(data_client_config));
+
+    // Add a peer to the peer states
+    let peer = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+    peer_states.update_summary(peer, utils::create_storage_summary(100));
+
+    // Verify that summary requests are not measured
+    let summary_request = StorageServiceRequest::new(DataRequest::GetStorageServerSummary, true);
+    for _ in 0..10 {
+        peer_states.update_throughput(peer, &summary_request, 1000, Duration::from_secs(1));
+    }
+    assert_none!(peer_states.get_measured_throughput(&peer));
+
+    // Measure a single data response and verify the throughput is not yet used
+    let data_request = create_transactions_request();
+    peer_states.update_throughput(peer, &data_request, 1000, Duration::from_secs(1));
+    assert_none!(peer_states.get_measured_throughput(&peer));
+
+    // Measure another data response and verify the moving average is used
+    peer_states.update_throughput(peer, &data_request, 3000, Duration::from_secs(1));
+    assert_some_eq!(peer_states.get_measured_throughput(&peer), 2000.0);
+
+    // Verify the average response time of the peer
+    let peer_state = peer_states
+        .get_peer_to_states()
+        .get(&peer)
+        .unwrap()
+        .value()
+        .clone();
+    assert_eq!(
+        peer_state.get_throughput().get_average_response_time_secs(),
+        1.0
+    );
+    assert_eq!(peer_state.get_throughput().get_num_samples(), 2);
+}
+
+/// Creates optimal chunk sizes with the given values
+fn create_chunk_sizes(
+    epoch_chunk_size: u64,
+    state_chunk_size: u64,
+    transaction_chunk_size: u64,
+    transaction_output_chunk_size: u64,
+) -> OptimalChunkSizes {
+    OptimalChunkSizes {
+        epoch_chunk_size,
+        state_chunk_size,
+        transaction_chunk_size,
+        transaction_output_chunk_size,
+    }
+}
+
+/// Creates a request for transactions that is measured for throughput
+fn create_transactions_request() -> StorageServiceRequest {
+    StorageServiceRequest::new(
+        DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+            start_version: 0,
+            end_version: 100,
+            proof_version: 100,
+            include_events: false,
+        }),
+        true,
+    )
+}
+
 /// Requests transactions up to the specified version and verifies the request fails
 async fn fetch_transactions_and_verify_failure(
     data_client_config: &AptosDataClientConfig,
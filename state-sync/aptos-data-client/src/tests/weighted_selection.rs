@@ -5,17 +5,26 @@ use crate::{
     client::AptosDataClient,
     priority::PeerPriority,
     tests::{mock::MockNetwork, utils, utils::NUM_SELECTION_ITERATIONS},
+    utils::calculate_num_exploration_slots,
 };
 use aptos_config::{
-    config::{AptosDataClientConfig, AptosDataMultiFetchConfig, AptosLatencyFilteringConfig},
+    config::{
+        AptosDataClientConfig, AptosDataMultiFetchConfig, AptosDataThroughputConfig,
+        AptosLatencyFilteringConfig,
+    },
     network_id::{NetworkId, PeerNetworkId},
 };
-use aptos_storage_service_types::requests::{DataRequest, StorageServiceRequest};
+use aptos_storage_service_types::requests::{
+    DataRequest, StorageServiceRequest, TransactionsWithProofRequest,
+};
 use aptos_time_service::TimeServiceTrait;
 use maplit::hashset;
 use ordered_float::OrderedFloat;
 use rand::Rng;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 // Useful test constants
 const NUM_PEERS_TO_ADD: u64 = 50;
@@ -748,6 +757,97 @@ async fn request_no_latencies() {
     }
 }
 
+#[tokio::test]
+async fn request_throughput_exploration() {
+    // Create a base config for a validator
+    let base_config = utils::create_validator_base_config();
+
+    // Ensure unmeasured peers are only chosen for the exploration slots
+    for exploration_percentage in [0, 100] {
+        // Create the data client config with throughput-aware requests (and multi-fetch disabled)
+        let data_client_config = AptosDataClientConfig {
+            data_multi_fetch_config: AptosDataMultiFetchConfig {
+                enable_multi_fetch: false,
+                ..Default::default()
+            },
+            data_throughput_config: AptosDataThroughputConfig {
+                enable_throughput_aware_requests: true,
+                exploration_percentage,
+                min_samples_for_throughput: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Create the mock network, time service and client
+        let (mut mock_network, time_service, client, _) =
+            MockNetwork::new(Some(base_config.clone()), Some(data_client_config), None);
+
+        // Add several peers and advertise their data
+        let peers = utils::add_several_peers(
+            &mut mock_network,
+            NUM_PEERS_TO_ADD,
+            PeerPriority::HighPriority,
+        );
+        utils::update_storage_summaries_for_peers(
+            &client,
+            &peers,
+            100,
+            time_service.now_unix_time().as_micros(),
+        );
+
+        // Measure the throughput of a single peer
+        let measured_peer = *peers.iter().next().unwrap();
+        let transactions_request = StorageServiceRequest::new(
+            DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+                start_version: 0,
+                end_version: 100,
+                proof_version: 100,
+                include_events: false,
+            }),
+            true,
+        );
+        client.get_peer_states().update_throughput(
+            measured_peer,
+            &transactions_request,
+            1000,
+            Duration::from_secs(1),
+        );
+
+        // Select a peer to service a request multiple times
+        let storage_request =
+            StorageServiceRequest::new(DataRequest::GetStorageServerSummary, true);
+        for _ in 0..100 {
+            let selected_peers = client.choose_peers_for_request(&storage_request).unwrap();
+            assert_eq!(selected_peers.len(), 1);
+
+            // Verify the measured peer is only selected if there is no exploration
+            let measured_peer_selected = selected_peers.contains(&measured_peer);
+            assert_eq!(measured_peer_selected, exploration_percentage == 0);
+        }
+    }
+}
+
+#[tokio::test]
+async fn request_throughput_exploration_slots() {
+    // Verify that a single peer is explored with the given probability
+    assert_eq!(calculate_num_exploration_slots(1, 0), 0);
+    assert_eq!(calculate_num_exploration_slots(1, 100), 1);
+
+    // Verify that no slots are explored when exploration is disabled
+    assert_eq!(calculate_num_exploration_slots(2, 0), 0);
+    assert_eq!(calculate_num_exploration_slots(5, 0), 0);
+
+    // Verify that at least one slot is explored (and one is not) for multiple peers
+    assert_eq!(calculate_num_exploration_slots(2, 1), 1);
+    assert_eq!(calculate_num_exploration_slots(2, 100), 1);
+    assert_eq!(calculate_num_exploration_slots(5, 100), 4);
+    for _ in 0..100 {
+        let num_exploration_slots = calculate_num_exploration_slots(5, 50);
+        assert!((1..=4).contains(&num_exploration_slots));
+    }
+}
+
 #[tokio::test]
 async fn subscription_distance_latency_weights() {
     // Create a data client with a max lag of 500
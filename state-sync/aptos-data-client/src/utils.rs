@@ -4,13 +4,17 @@
 use crate::{
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
+    peer_states::PeerStates,
 };
 use aptos_config::{config::AptosDataClientConfig, network_id::PeerNetworkId};
 use aptos_logger::{sample, sample::SampleRate, warn};
 use aptos_network::application::{metadata::PeerMetadata, storage::PeersAndMetadata};
 use maplit::hashset;
 use ordered_float::OrderedFloat;
-use rand::seq::{IteratorRandom, SliceRandom};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
 use std::{
     collections::{BTreeMap, HashSet},
     sync::Arc,
@@ -120,6 +124,62 @@ pub fn choose_peers_by_latency(
     choose_random_peers_by_weight(num_peers_to_choose, potential_peers_and_latency_weights)
 }
 
+/// Selects the specified number of peers from the list of potential
+/// peers. Peer selection is weighted by the measured peer throughputs
+/// (i.e., the higher the throughput, the higher the probability of
+/// selection). Peers without enough throughput measurements are ignored,
+/// so the returned set may contain fewer peers than requested.
+pub fn choose_peers_by_throughput(
+    num_peers_to_choose: u64,
+    potential_peers: HashSet<PeerNetworkId>,
+    peer_states: Arc<PeerStates>,
+) -> HashSet<PeerNetworkId> {
+    // If no peers can be chosen, return an empty set
+    if num_peers_to_choose == 0 || potential_peers.is_empty() {
+        return hashset![];
+    }
+
+    // Gather the throughput weights for all potential peers
+    let potential_peers_and_throughput_weights = potential_peers
+        .into_iter()
+        .filter_map(|peer| {
+            peer_states
+                .get_measured_throughput(&peer)
+                .map(|throughput| (peer, throughput))
+        })
+        .collect::<Vec<_>>();
+
+    // Select the peers by throughput weights
+    choose_random_peers_by_weight(num_peers_to_choose, potential_peers_and_throughput_weights)
+}
+
+/// Calculates the number of peer selection slots to reserve for peers
+/// without throughput measurements. Each slot is reserved with the given
+/// probability (percentage). A percentage of zero disables exploration.
+/// Otherwise, when choosing multiple peers, at least one slot is always
+/// reserved, and at least one slot is never reserved (so that measured
+/// peers are still chosen).
+pub fn calculate_num_exploration_slots(
+    num_peers_to_choose: usize,
+    exploration_percentage: u64,
+) -> usize {
+    if exploration_percentage == 0 {
+        return 0;
+    }
+
+    let exploration_percentage = exploration_percentage.min(100) as u32;
+    let mut rng = rand::thread_rng();
+    let num_exploration_slots = (0..num_peers_to_choose)
+        .filter(|_| rng.gen_ratio(exploration_percentage, 100))
+        .count();
+
+    if num_peers_to_choose > 1 {
+        num_exploration_slots.clamp(1, num_peers_to_choose - 1)
+    } else {
+        num_exploration_slots
+    }
+}
+
 /// Selects a single peer randomly from the list of specified peers
 pub fn choose_random_peer(peers: HashSet<PeerNetworkId>) -> Option<PeerNetworkId> {
     peers.into_iter().choose(&mut rand::thread_rng())
@@ -14,7 +14,7 @@ use crate::{
 use aptos_crypto::hash::{CryptoHash, HashValue};
 use aptos_schemadb::{ReadOptions, SchemaBatch, DB};
 use aptos_storage_interface::{AptosDbError, Result};
-use aptos_types::transaction::{Transaction, TransactionToCommit, Version};
+use aptos_types::transaction::{ReplayProtector, Transaction, TransactionToCommit, Version};
 use rayon::prelude::*;
 use std::{path::Path, sync::Arc};
 
@@ -134,10 +134,14 @@ impl TransactionDb {
     ) -> Result<()> {
         if !skip_index {
             if let Some(txn) = transaction.try_as_signed_user_txn() {
-                batch.put::<TransactionByAccountSchema>(
-                    &(txn.sender(), txn.sequence_number()),
-                    &version,
-                )?;
+                // Orderless transactions don't have a sequence number to index by, and their
+                // nonces are neither contiguous nor unique over time.
+                if let ReplayProtector::SequenceNumber(sequence_number) = txn.replay_protector() {
+                    batch.put::<TransactionByAccountSchema>(
+                        &(txn.sender(), sequence_number),
+                        &version,
+                    )?;
+                }
             }
         }
         batch.put::<TransactionByHashSchema>(&transaction.hash(), &version)?;
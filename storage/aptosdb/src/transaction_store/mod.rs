@@ -12,7 +12,7 @@ use aptos_schemadb::{ReadOptions, SchemaBatch};
 use aptos_storage_interface::{AptosDbError, Result};
 use aptos_types::{
     account_address::AccountAddress,
-    transaction::{Transaction, Version},
+    transaction::{ReplayProtector, Transaction, Version},
 };
 use std::sync::Arc;
 
@@ -85,8 +85,11 @@ impl TransactionStore {
     ) -> Result<()> {
         for transaction in transactions {
             if let Some(txn) = transaction.try_as_signed_user_txn() {
-                db_batch
-                    .delete::<TransactionByAccountSchema>(&(txn.sender(), txn.sequence_number()))?;
+                // Orderless transactions are not indexed by account, see `put_transaction`.
+                if let ReplayProtector::SequenceNumber(sequence_number) = txn.replay_protector() {
+                    db_batch
+                        .delete::<TransactionByAccountSchema>(&(txn.sender(), sequence_number))?;
+                }
             }
         }
         Ok(())
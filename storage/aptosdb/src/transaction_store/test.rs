@@ -6,9 +6,14 @@
 
 use super::*;
 use crate::{ledger_db::transaction_db_test::init_db, AptosDB};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, PrivateKey, Uniform};
 use aptos_proptest_helpers::Index;
 use aptos_temppath::TempPath;
-use aptos_types::proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen};
+use aptos_types::{
+    chain_id::ChainId,
+    proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen},
+    transaction::{RawTransaction, Script, TransactionPayload, TransactionToCommit},
+};
 use proptest::{collection::vec, prelude::*};
 use std::collections::BTreeMap;
 
@@ -129,3 +134,117 @@ proptest! {
         prop_assert_eq!(&actual_scan, &expected_scan);
     }
 }
+
+#[test]
+fn test_orderless_transactions_are_not_indexed_by_account() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let store = &db.transaction_store;
+    let transaction_db = db.ledger_db.transaction_db();
+
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let sender = AccountAddress::random();
+    let payload = TransactionPayload::Script(Script::new(vec![], vec![], vec![]));
+    let sign = |raw_txn: RawTransaction| {
+        Transaction::UserTransaction(
+            raw_txn
+                .sign(&private_key, private_key.public_key())
+                .unwrap()
+                .into_inner(),
+        )
+    };
+    let txns = vec![
+        sign(RawTransaction::new(
+            sender,
+            0,
+            payload.clone(),
+            0,
+            0,
+            0,
+            ChainId::test(),
+        )),
+        sign(RawTransaction::new_orderless(
+            sender,
+            7,
+            payload.clone(),
+            0,
+            0,
+            0,
+            ChainId::test(),
+        )),
+        sign(RawTransaction::new(
+            sender,
+            1,
+            payload.clone(),
+            0,
+            0,
+            0,
+            ChainId::test(),
+        )),
+        // Nonces can be reused once the previous use has expired.
+        sign(RawTransaction::new_orderless(
+            sender,
+            7,
+            payload,
+            0,
+            0,
+            1,
+            ChainId::test(),
+        )),
+    ];
+    transaction_db
+        .commit_transactions(
+            &txns
+                .iter()
+                .map(|transaction| TransactionToCommit {
+                    transaction: transaction.clone(),
+                    ..TransactionToCommit::dummy()
+                })
+                .collect::<Vec<_>>(),
+            0,
+            /*skip_index=*/ false,
+        )
+        .unwrap();
+    let ledger_version = txns.len() as Version - 1;
+
+    // The orderless transactions are still reachable by version and hash.
+    for (version, txn) in txns.iter().enumerate() {
+        assert_eq!(
+            &transaction_db.get_transaction(version as Version).unwrap(),
+            txn
+        );
+        assert_eq!(
+            transaction_db
+                .get_transaction_version_by_hash(&txn.hash(), ledger_version)
+                .unwrap(),
+            Some(version as Version)
+        );
+    }
+
+    // But they don't show up in, or break the continuity of, the account index.
+    let orderless_sequence_number = ReplayProtector::Nonce(7).to_sequence_number_field();
+    assert_eq!(
+        store
+            .get_account_transaction_version(sender, orderless_sequence_number, ledger_version)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        store
+            .get_account_transaction_version_iter(sender, 0, 10, ledger_version)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap(),
+        vec![(0, 0), (1, 2)]
+    );
+
+    // Pruning doesn't trip over them either.
+    let batch = SchemaBatch::new();
+    store.prune_transaction_by_account(&txns, &batch).unwrap();
+    transaction_db.write_schemas(batch).unwrap();
+    assert!(store
+        .get_account_transaction_version_iter(sender, 0, 10, ledger_version)
+        .unwrap()
+        .next()
+        .is_none());
+}
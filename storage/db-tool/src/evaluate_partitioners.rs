@@ -0,0 +1,176 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, Result};
+use aptos_backup_cli::{
+    backup_types::transaction::manifest::TransactionBackup,
+    metadata::cache::{sync_and_load, MetadataCacheOpt},
+    storage::{BackupStorage, DBToolStorageOpt, FileHandleRef},
+    utils::{
+        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, ConcurrentDownloadsOpt,
+    },
+};
+use aptos_block_partitioner::evaluation::{
+    default_candidates, BlockSplitter, PartitionerEvaluator,
+};
+use aptos_config::config::{
+    RocksdbConfigs, StorageDirPaths, BUFFERED_STATE_TARGET_ITEMS,
+    DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD, NO_OP_STORAGE_PRUNER_CONFIG,
+};
+use aptos_db::AptosDB;
+use aptos_logger::info;
+use aptos_storage_interface::{DbReader, MAX_REQUEST_LIMIT};
+use aptos_types::{
+    contract_event::ContractEvent,
+    transaction::{Transaction, TransactionInfo, Version},
+    write_set::WriteSet,
+};
+use clap::Parser;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::io::BufReader;
+
+/// Partition the historical blocks in a range of versions with every candidate partitioner, and
+/// compare the resulting cross-shard edges, rounds, load imbalance and partitioning time.
+#[derive(Parser)]
+pub struct Opt {
+    #[clap(long)]
+    start_version: Version,
+    #[clap(long)]
+    end_version: Version,
+    #[clap(long, default_value_t = 8)]
+    num_shards: usize,
+    #[clap(subcommand)]
+    source: Source,
+}
+
+#[derive(clap::Subcommand)]
+enum Source {
+    /// Read the transactions from a DB.
+    Db {
+        #[clap(long, value_parser)]
+        db_dir: PathBuf,
+    },
+    /// Read the transactions from the backup storage.
+    Backup {
+        #[clap(flatten)]
+        metadata_cache_opt: MetadataCacheOpt,
+        #[clap(flatten)]
+        storage: DBToolStorageOpt,
+        #[clap(flatten)]
+        concurrent_downloads: ConcurrentDownloadsOpt,
+    },
+}
+
+impl Opt {
+    pub async fn run(self) -> Result<()> {
+        ensure!(
+            self.start_version <= self.end_version,
+            "start_version {} is larger than end_version {}",
+            self.start_version,
+            self.end_version,
+        );
+
+        let mut evaluator = PartitionerEvaluator::new(self.num_shards, default_candidates());
+        let mut splitter = BlockSplitter::default();
+        let mut on_txn = |txn, write_set: WriteSet| {
+            if let Some(block) = splitter.push(txn, &write_set) {
+                evaluator.evaluate_block(block);
+            }
+        };
+        match self.source {
+            Source::Db { ref db_dir } => self.load_from_db(db_dir, &mut on_txn)?,
+            Source::Backup {
+                ref metadata_cache_opt,
+                ref storage,
+                ref concurrent_downloads,
+            } => {
+                let storage = storage.clone().init_storage().await?;
+                let metadata_view = sync_and_load(
+                    metadata_cache_opt,
+                    Arc::clone(&storage),
+                    concurrent_downloads.get(),
+                )
+                .await?;
+                for backup in metadata_view
+                    .select_transaction_backups(self.start_version, self.end_version)?
+                {
+                    self.load_from_backup(&storage, &backup.manifest, &mut on_txn)
+                        .await?;
+                }
+            },
+        }
+        if let Some(block) = splitter.finish() {
+            evaluator.evaluate_block(block);
+        }
+
+        info!(
+            "Evaluated {} blocks between versions {} and {}.",
+            evaluator.num_blocks(),
+            self.start_version,
+            self.end_version,
+        );
+        println!("{}", evaluator.report());
+        Ok(())
+    }
+
+    fn load_from_db(
+        &self,
+        db_dir: &Path,
+        on_txn: &mut impl FnMut(Transaction, WriteSet),
+    ) -> Result<()> {
+        let db = AptosDB::open(
+            StorageDirPaths::from_path(db_dir),
+            true,                        /* read_only */
+            NO_OP_STORAGE_PRUNER_CONFIG, /* pruner */
+            RocksdbConfigs::default(),
+            false, /* indexer */
+            BUFFERED_STATE_TARGET_ITEMS,
+            DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+        )?;
+        let end_version = self.end_version.min(db.get_latest_version()?);
+
+        let mut version = self.start_version;
+        while version <= end_version {
+            let limit = (end_version - version + 1).min(MAX_REQUEST_LIMIT);
+            let txns = db.get_transaction_iterator(version, limit)?;
+            let write_sets = db.get_write_set_iterator(version, limit)?;
+            for (txn, write_set) in txns.zip(write_sets) {
+                on_txn(txn?, write_set?);
+            }
+            version += limit;
+        }
+        Ok(())
+    }
+
+    async fn load_from_backup(
+        &self,
+        storage: &Arc<dyn BackupStorage>,
+        manifest: &FileHandleRef,
+        on_txn: &mut impl FnMut(Transaction, WriteSet),
+    ) -> Result<()> {
+        let manifest: TransactionBackup = storage.load_json_file(manifest).await?;
+        for chunk in manifest.chunks {
+            if chunk.last_version < self.start_version || chunk.first_version > self.end_version {
+                continue;
+            }
+            let mut file = BufReader::new(storage.open_for_read(&chunk.transactions).await?);
+            let mut version = chunk.first_version;
+            while let Some(record_bytes) = file.read_record_bytes().await? {
+                let (txn, _, _, write_set): (
+                    Transaction,
+                    TransactionInfo,
+                    Vec<ContractEvent>,
+                    WriteSet,
+                ) = bcs::from_bytes(&record_bytes)?;
+                if (self.start_version..=self.end_version).contains(&version) {
+                    on_txn(txn, write_set);
+                }
+                version += 1;
+            }
+        }
+        Ok(())
+    }
+}
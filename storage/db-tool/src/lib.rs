@@ -6,6 +6,7 @@ extern crate core;
 mod backup;
 mod backup_maintenance;
 mod bootstrap;
+mod evaluate_partitioners;
 mod replay_verify;
 pub mod restore;
 #[cfg(test)]
@@ -31,6 +32,8 @@ pub enum DBTool {
     #[clap(subcommand)]
     Debug(db_debugger::Cmd),
 
+    EvaluatePartitioners(evaluate_partitioners::Opt),
+
     ReplayVerify(replay_verify::Opt),
 
     #[clap(subcommand)]
@@ -44,6 +47,7 @@ impl DBTool {
             DBTool::BackupMaintenance(cmd) => cmd.run().await,
             DBTool::Bootstrap(cmd) => cmd.run(),
             DBTool::Debug(cmd) => Ok(cmd.run()?),
+            DBTool::EvaluatePartitioners(cmd) => cmd.run().await,
             DBTool::ReplayVerify(cmd) => {
                 let ret = cmd.run().await;
                 info!("Replay verify result: {:?}", ret);
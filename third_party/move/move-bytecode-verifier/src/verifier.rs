@@ -20,7 +20,7 @@ use move_core_types::{state::VMState, vm_status::StatusCode};
 use serde::Serialize;
 use std::time::Instant;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize)]
 pub struct VerifierConfig {
     pub max_loop_depth: Option<usize>,
     pub max_function_parameters: Option<usize>,
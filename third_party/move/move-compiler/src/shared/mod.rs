@@ -638,6 +638,8 @@ pub mod known_attributes {
         Test,
         // This test is expected to fail
         ExpectedFailure,
+        // This test is expected to use less gas than the given budget
+        ExpectedGasBelow,
     }
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -683,6 +685,9 @@ pub mod known_attributes {
                 TestingAttribute::EXPECTED_FAILURE => {
                     Self::Testing(TestingAttribute::ExpectedFailure)
                 },
+                TestingAttribute::EXPECTED_GAS_BELOW => {
+                    Self::Testing(TestingAttribute::ExpectedGasBelow)
+                },
                 VerificationAttribute::VERIFY_ONLY => {
                     Self::Verification(VerificationAttribute::VerifyOnly)
                 },
@@ -736,11 +741,16 @@ pub mod known_attributes {
 
     impl TestingAttribute {
         pub const ABORT_CODE_NAME: &'static str = "abort_code";
-        const ALL_ATTRIBUTE_NAMES: [&'static str; 3] =
-            [Self::TEST, Self::TEST_ONLY, Self::EXPECTED_FAILURE];
+        const ALL_ATTRIBUTE_NAMES: [&'static str; 4] = [
+            Self::TEST,
+            Self::TEST_ONLY,
+            Self::EXPECTED_FAILURE,
+            Self::EXPECTED_GAS_BELOW,
+        ];
         pub const ARITHMETIC_ERROR_NAME: &'static str = "arithmetic_error";
         pub const ERROR_LOCATION: &'static str = "location";
         pub const EXPECTED_FAILURE: &'static str = "expected_failure";
+        pub const EXPECTED_GAS_BELOW: &'static str = "expected_gas_below";
         pub const MAJOR_STATUS_NAME: &'static str = "major_status";
         pub const MINOR_STATUS_NAME: &'static str = "minor_status";
         pub const OUT_OF_GAS_NAME: &'static str = "out_of_gas";
@@ -770,6 +780,7 @@ pub mod known_attributes {
                 Self::Test => Self::TEST,
                 Self::TestOnly => Self::TEST_ONLY,
                 Self::ExpectedFailure => Self::EXPECTED_FAILURE,
+                Self::ExpectedGasBelow => Self::EXPECTED_GAS_BELOW,
             }
         }
 
@@ -793,7 +804,9 @@ pub mod known_attributes {
             match self {
                 TestingAttribute::TestOnly => &TEST_ONLY_POSITIONS,
                 TestingAttribute::Test => &TEST_POSITIONS,
-                TestingAttribute::ExpectedFailure => &EXPECTED_FAILURE_POSITIONS,
+                TestingAttribute::ExpectedFailure | TestingAttribute::ExpectedGasBelow => {
+                    &EXPECTED_FAILURE_POSITIONS
+                },
             }
         }
     }
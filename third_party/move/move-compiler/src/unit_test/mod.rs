@@ -31,14 +31,16 @@ pub struct ModuleTestPlan {
     pub tests: BTreeMap<TestName, TestCase>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TestCase {
     pub test_name: TestName,
     pub arguments: Vec<MoveValue>,
     pub expected_failure: Option<ExpectedFailure>,
+    // gas budget of the test, only checked when the tests are run with a gas schedule
+    pub expected_gas_below: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExpectedFailure {
     // expected failure, but codes are not checked
     Expected,
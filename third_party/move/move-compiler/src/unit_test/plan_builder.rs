@@ -126,6 +126,7 @@ fn build_test_info<'func>(
     let test_attribute_opt = get_attrs(TestingAttribute::Test);
     let abort_attribute_opt = get_attrs(TestingAttribute::ExpectedFailure);
     let test_only_attribute_opt = get_attrs(TestingAttribute::TestOnly);
+    let gas_attribute_opt = get_attrs(TestingAttribute::ExpectedGasBelow);
 
     let test_attribute = match test_attribute_opt {
         None => {
@@ -140,6 +141,17 @@ fn build_test_info<'func>(
                     (abort_attribute.loc, abort_msg),
                 ))
             }
+            // neither can gas budgets
+            if let Some(gas_attribute) = gas_attribute_opt {
+                let fn_msg = "Only functions defined as a test with #[test] can also have an \
+                              #[expected_gas_below] attribute";
+                let gas_msg = "Attributed as #[expected_gas_below] here";
+                context.env.add_diag(diag!(
+                    Attributes::InvalidUsage,
+                    (fn_loc, fn_msg),
+                    (gas_attribute.loc, gas_msg),
+                ))
+            }
             return None;
         },
         Some(test_attribute) => test_attribute,
@@ -180,10 +192,16 @@ fn build_test_info<'func>(
         Some(abort_attribute) => parse_failure_attribute(context, abort_attribute),
     };
 
+    let expected_gas_below = match gas_attribute_opt {
+        None => None,
+        Some(gas_attribute) => parse_gas_attribute(context, gas_attribute),
+    };
+
     Some(TestCase {
         test_name: fn_name.to_string(),
         arguments,
         expected_failure,
+        expected_gas_below,
     })
 }
 
@@ -484,6 +502,30 @@ fn parse_failure_attribute(
     }
 }
 
+fn parse_gas_attribute(context: &mut Context, sp!(aloc, gas_attr): &E::Attribute) -> Option<u64> {
+    use E::Attribute_ as EA;
+    match gas_attr {
+        EA::Assigned(sp!(_, nm), value) => {
+            assert!(
+                nm.as_str() == TestingAttribute::ExpectedGasBelow.name(),
+                "ICE: gas budget attribute must have the right name"
+            );
+            let (_, budget) = convert_attribute_value_u64(context, *aloc, value)?;
+            Some(budget)
+        },
+        EA::Name(_) | EA::Parameterized(_, _) => {
+            let msg = format!(
+                "Expected a gas budget assigned to the attribute, e.g. '#[{}=1000]'",
+                TestingAttribute::EXPECTED_GAS_BELOW
+            );
+            context
+                .env
+                .add_diag(diag!(Attributes::InvalidValue, (*aloc, msg)));
+            None
+        },
+    }
+}
+
 fn check_attribute_unassigned(
     context: &mut Context,
     kind: &str,
@@ -0,0 +1,17 @@
+// gas budgets need a u64 value and can only be placed on #[test] functions
+module 0x1::A {
+    #[test]
+    #[expected_gas_below=1000]
+    fun ok() { }
+
+    #[expected_gas_below=1000]
+    fun not_test() { }
+
+    #[test]
+    #[expected_gas_below]
+    fun no_budget() { }
+
+    #[test]
+    #[expected_gas_below=@0x1]
+    fun bad_budget() { }
+}
@@ -576,6 +576,9 @@ pub enum StatusCode {
     RESERVED_VALIDATION_ERROR_3 = 38,
     RESERVED_VALIDATION_ERROR_4 = 39,
     RESERVED_VALIDATION_ERROR_5 = 40,
+    // The expiration time of an orderless transaction is beyond the window in which its nonce is
+    // remembered.
+    TRANSACTION_EXPIRATION_TOO_FAR_IN_FUTURE = 41,
 
     // When a code module/script is published it is verified. These are the
     // possible errors that can arise from the verification process.
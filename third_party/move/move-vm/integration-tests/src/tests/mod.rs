@@ -16,4 +16,5 @@ mod native_tests;
 mod nested_loop_tests;
 mod regression_tests;
 mod return_value_tests;
+mod verified_module_cache_tests;
 mod vm_arguments_tests;
@@ -0,0 +1,100 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::compiler::{as_module, compile_units};
+use move_binary_format::{
+    deserializer::DeserializerConfig,
+    file_format_common::{IDENTIFIER_SIZE_MAX, VERSION_MAX},
+};
+use move_bytecode_verifier::VerifierConfig;
+use move_core_types::{account_address::AccountAddress, ident_str, language_storage::ModuleId};
+use move_vm_runtime::{config::VMConfig, move_vm::MoveVM};
+use move_vm_test_utils::InMemoryStorage;
+use std::sync::Arc;
+
+// Not shared with other tests, so that the bytes of the module are only cached by this file.
+const TEST_ADDR: AccountAddress = AccountAddress::new([43; AccountAddress::LENGTH]);
+
+fn compile_module_with_nested_loops() -> (ModuleId, Vec<u8>) {
+    let code = r#"
+        module {{ADDR}}::M {
+            public fun foo() {
+                let i = 0;
+                while (i < 10) {
+                    let j = 0;
+                    while (j < 10) {
+                        j = j + 1;
+                    };
+                    i = i + 1;
+                };
+            }
+        }
+    "#;
+    let code = code.replace("{{ADDR}}", &format!("0x{}", TEST_ADDR.to_hex()));
+    let mut units = compile_units(&code).unwrap();
+
+    let m = as_module(units.pop().unwrap());
+    let mut m_blob = vec![];
+    m.serialize(&mut m_blob).unwrap();
+    (m.self_id(), m_blob)
+}
+
+fn vm_with_max_loop_depth(max_loop_depth: usize) -> MoveVM {
+    MoveVM::new_with_config(
+        move_stdlib::natives::all_natives(
+            AccountAddress::from_hex_literal("0x1").unwrap(),
+            move_stdlib::natives::GasParameters::zeros(),
+        ),
+        VMConfig {
+            verifier: VerifierConfig {
+                max_loop_depth: Some(max_loop_depth),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_cached_module_is_reverified_under_stricter_config() {
+    let (module_id, m_blob) = compile_module_with_nested_loops();
+    let mut storage = InMemoryStorage::new();
+    storage.publish_or_overwrite_module(module_id.clone(), m_blob);
+
+    // Loading with max_loop_depth = 2 verifies the module and caches it for the process.
+    {
+        let vm = vm_with_max_loop_depth(2);
+        let sess = vm.new_session(&storage);
+        sess.load_function(&module_id, ident_str!("foo"), &[])
+            .unwrap();
+    }
+
+    // A VM with max_loop_depth = 1 must not reuse the module verified under the other config.
+    {
+        let vm = vm_with_max_loop_depth(1);
+        let sess = vm.new_session(&storage);
+        sess.load_function(&module_id, ident_str!("foo"), &[])
+            .unwrap_err();
+    }
+
+    // While a VM with the original config still loads it.
+    {
+        let vm = vm_with_max_loop_depth(2);
+        let sess = vm.new_session(&storage);
+        sess.load_function(&module_id, ident_str!("foo"), &[])
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_module_metadata_is_cached() {
+    let (_, m_blob) = compile_module_with_nested_loops();
+    let config = DeserializerConfig::new(VERSION_MAX, IDENTIFIER_SIZE_MAX);
+
+    let metadata = MoveVM::get_module_metadata(&m_blob, &config).unwrap();
+    let cached = MoveVM::get_module_metadata(&m_blob, &config).unwrap();
+    assert!(Arc::ptr_eq(&metadata, &cached));
+
+    assert!(MoveVM::get_module_metadata(&[0xde, 0xad], &config).is_none());
+}
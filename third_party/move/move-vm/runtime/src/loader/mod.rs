@@ -6,7 +6,6 @@ use crate::{
     config::VMConfig, data_cache::TransactionDataCache, logging::expect_no_verification_errors,
     native_functions::NativeFunctions, session::LoadedFunctionInstantiation,
 };
-use lazy_static::lazy_static;
 use move_binary_format::{
     access::{ModuleAccess, ScriptAccess},
     errors::{verification_error, Location, PartialVMError, PartialVMResult, VMResult},
@@ -34,7 +33,7 @@ use move_vm_types::{
         AbilityInfo, DepthFormula, StructIdentifier, StructNameIndex, StructType, Type,
     },
 };
-use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
+use parking_lot::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
 use sha3::{Digest, Sha3_256};
 use std::{
     collections::{btree_map, BTreeMap, BTreeSet, HashMap},
@@ -47,11 +46,14 @@ mod function;
 mod modules;
 mod script;
 mod type_loader;
+mod verified_module_cache;
 
 pub(crate) use function::{Function, FunctionHandle, FunctionInstantiation, LoadedFunction, Scope};
 pub(crate) use modules::{Module, ModuleCache, ModuleStorage, ModuleStorageAdapter};
 pub(crate) use script::{Script, ScriptCache};
 use type_loader::intern_type;
+pub use verified_module_cache::VerifiedModuleCacheStats;
+pub(crate) use verified_module_cache::{module_hash, VERIFIED_MODULE_CACHE};
 
 type ScriptHash = [u8; 32];
 
@@ -90,15 +92,6 @@ where
     }
 }
 
-// Max number of modules that can skip re-verification.
-const VERIFIED_CACHE_SIZE: usize = 100_000;
-
-// Cache for already verified modules
-lazy_static! {
-    static ref VERIFIED_MODULES: Mutex<lru::LruCache<[u8; 32], ()>> =
-        Mutex::new(lru::LruCache::new(VERIFIED_CACHE_SIZE));
-}
-
 pub(crate) struct StructNameCache {
     data: RwLock<(
         BTreeMap<StructIdentifier, StructNameIndex>,
@@ -846,7 +839,7 @@ impl Loader {
         id: &ModuleId,
         data_store: &TransactionDataCache,
         allow_loading_failure: bool,
-    ) -> VMResult<CompiledModule> {
+    ) -> VMResult<Arc<CompiledModule>> {
         // bytes fetching, allow loading to fail if the flag is set
         let bytes = match data_store
             .load_module(id)
@@ -859,19 +852,46 @@ impl Loader {
             },
         };
 
-        // for bytes obtained from the data store, they should always deserialize and verify.
-        // It is an invariant violation if they don't.
-        let module =
-            CompiledModule::deserialize_with_config(&bytes, &self.vm_config.deserializer_config)
-                .map_err(|err| {
-                    let msg = format!("Deserialization error: {:?}", err);
-                    PartialVMError::new(StatusCode::CODE_DESERIALIZATION_ERROR)
-                        .with_message(msg)
-                        .finish(Location::Module(id.clone()))
-                })
-                .map_err(expect_no_verification_errors)?;
+        let hash_value = module_hash(&bytes);
+
+        // Skip deserialization and verification if the module has been loaded before with the
+        // same configs, possibly by another loader.
+        let deserializer_config = &self.vm_config.deserializer_config;
+        let verifier_config = &self.vm_config.verifier;
+        let cached =
+            VERIFIED_MODULE_CACHE
+                .read()
+                .get(&hash_value, deserializer_config, verifier_config);
+        let module = match cached {
+            Some(module) => module,
+            None => {
+                // for bytes obtained from the data store, they should always deserialize and verify.
+                // It is an invariant violation if they don't.
+                let module = CompiledModule::deserialize_with_config(&bytes, deserializer_config)
+                    .map_err(|err| {
+                        let msg = format!("Deserialization error: {:?}", err);
+                        PartialVMError::new(StatusCode::CODE_DESERIALIZATION_ERROR)
+                            .with_message(msg)
+                            .finish(Location::Module(id.clone()))
+                    })
+                    .map_err(expect_no_verification_errors)?;
 
-        fail::fail_point!("verifier-failpoint-2", |_| { Ok(module.clone()) });
+                fail::fail_point!("verifier-failpoint-2", |_| { Ok(Arc::new(module.clone())) });
+
+                move_bytecode_verifier::verify_module_with_config(verifier_config, &module)
+                    .map_err(expect_no_verification_errors)?;
+
+                let module = Arc::new(module);
+                VERIFIED_MODULE_CACHE.write().insert(
+                    hash_value,
+                    deserializer_config,
+                    verifier_config,
+                    module.clone(),
+                    bytes.len(),
+                );
+                module
+            },
+        };
 
         if self.vm_config.paranoid_type_checks && &module.self_id() != id {
             return Err(
@@ -881,18 +901,6 @@ impl Loader {
             );
         }
 
-        // Verify the module if it hasn't been verified before.
-        let mut sha3_256 = Sha3_256::new();
-        sha3_256.update(bytes);
-        let hash_value: [u8; 32] = sha3_256.finalize().into();
-
-        if VERIFIED_MODULES.lock().get(&hash_value).is_none() {
-            move_bytecode_verifier::verify_module_with_config(&self.vm_config.verifier, &module)
-                .map_err(expect_no_verification_errors)?;
-
-            VERIFIED_MODULES.lock().put(hash_value, ());
-        }
-
         self.check_natives(&module)
             .map_err(expect_no_verification_errors)?;
         Ok(module)
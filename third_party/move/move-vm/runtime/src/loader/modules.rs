@@ -93,7 +93,7 @@ impl ModuleStorageAdapter {
         &self,
         natives: &NativeFunctions,
         id: ModuleId,
-        module: CompiledModule,
+        module: Arc<CompiledModule>,
         name_cache: &StructNameCache,
     ) -> VMResult<Arc<Module>> {
         if let Some(cached) = self.module_at(&id) {
@@ -252,10 +252,10 @@ pub(crate) struct FieldInstantiation {
 impl Module {
     pub(crate) fn new(
         natives: &NativeFunctions,
-        module: CompiledModule,
+        module: Arc<CompiledModule>,
         cache: &ModuleStorageAdapter,
         name_cache: &StructNameCache,
-    ) -> Result<Self, (PartialVMError, CompiledModule)> {
+    ) -> Result<Self, (PartialVMError, Arc<CompiledModule>)> {
         let id = module.self_id();
 
         let mut structs = vec![];
@@ -440,7 +440,7 @@ impl Module {
         match create() {
             Ok(_) => Ok(Self {
                 id,
-                module: Arc::new(module),
+                module,
                 structs,
                 struct_instantiations,
                 function_refs,
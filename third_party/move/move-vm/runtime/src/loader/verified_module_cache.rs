@@ -0,0 +1,439 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cache of deserialized and verified modules, shared by all loaders and kept for the lifetime
+//! of the process.
+//!
+//! Unlike the module cache of a loader, which holds linked modules and must be flushed as a whole
+//! whenever any module is upgraded, entries here are keyed by the hash of the module bytes and by
+//! the configs they were deserialized and verified with, and only depend on the module itself. An
+//! upgraded module has a different hash, so stale entries are never returned. The adapter still
+//! invalidates the previous versions of a module once an upgrade is committed, so that they do not
+//! take up space until they are evicted.
+//!
+//! The metadata of modules is cached separately, since it is looked up by the adapter for modules
+//! that are not necessarily loaded, and does not depend on the verifier config.
+//!
+//! The cache is looked up on every module load, by all threads executing transactions, so lookups
+//! only take a read lock. As they can't reorder the LRU lists, lookups flag the entries they hit
+//! instead, and a flagged entry is moved to the front rather than evicted when it is the least
+//! recently used one (a second chance, as in the CLOCK approximation of LRU).
+
+use lazy_static::lazy_static;
+use lru::LruCache;
+use move_binary_format::{deserializer::DeserializerConfig, CompiledModule};
+use move_bytecode_verifier::VerifierConfig;
+use move_core_types::{language_storage::ModuleId, metadata::Metadata};
+use parking_lot::RwLock;
+use sha3::{Digest, Sha3_256};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Total size of the module bytes held by the cache, after which the least recently used modules
+/// are evicted.
+const VERIFIED_MODULE_CACHE_CAPACITY_BYTES: usize = 256 * 1024 * 1024;
+
+/// Number of modules whose metadata is cached.
+const MODULE_METADATA_CACHE_SIZE: usize = 100_000;
+
+pub(crate) type ModuleHash = [u8; 32];
+
+pub(crate) fn module_hash(bytes: &[u8]) -> ModuleHash {
+    let mut sha3_256 = Sha3_256::new();
+    sha3_256.update(bytes);
+    sha3_256.finalize().into()
+}
+
+#[derive(Clone, Eq, Hash, PartialEq)]
+struct CacheKey {
+    hash: ModuleHash,
+    // Bytes only deserialize the same way under the same config.
+    deserializer_config: DeserializerConfig,
+    // A module verified under a config is not necessarily valid under a stricter one.
+    verifier_config: VerifierConfig,
+}
+
+#[derive(Clone, Eq, Hash, PartialEq)]
+struct MetadataKey {
+    hash: ModuleHash,
+    deserializer_config: DeserializerConfig,
+}
+
+struct CacheEntry {
+    module: Arc<CompiledModule>,
+    size_bytes: usize,
+}
+
+/// A cached value, flagged when it is looked up so that it gets a second chance on eviction.
+struct Referenced<T> {
+    value: T,
+    referenced: AtomicBool,
+}
+
+impl<T> Referenced<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            referenced: AtomicBool::new(false),
+        }
+    }
+
+    fn touch(&self) -> &T {
+        // Hot modules are hit all the time, so only write the flag when it is not set yet.
+        if !self.referenced.load(Ordering::Relaxed) {
+            self.referenced.store(true, Ordering::Relaxed);
+        }
+        &self.value
+    }
+}
+
+/// Pops the least recently used entry that was not looked up since it was last moved to the
+/// front. Looked up entries are moved to the front instead, with their flag cleared.
+fn pop_lru<K: Eq + Hash, V>(cache: &mut LruCache<K, Referenced<V>>) -> Option<(K, V)> {
+    while let Some((key, mut entry)) = cache.pop_lru() {
+        if std::mem::take(entry.referenced.get_mut()) {
+            cache.put(key, entry);
+        } else {
+            return Some((key, entry.value));
+        }
+    }
+    None
+}
+
+/// Counters of the verified module cache, since the process started.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VerifiedModuleCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+    pub num_modules: usize,
+    pub size_bytes: usize,
+}
+
+pub(crate) struct VerifiedModuleCache {
+    modules: LruCache<CacheKey, Referenced<CacheEntry>>,
+    keys_by_id: HashMap<ModuleId, Vec<CacheKey>>,
+    metadata: LruCache<MetadataKey, Referenced<Arc<Vec<Metadata>>>>,
+    capacity_bytes: usize,
+    metadata_capacity: usize,
+    // Lookups only hold the read lock, so their counters are atomic.
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stats: VerifiedModuleCacheStats,
+}
+
+impl VerifiedModuleCache {
+    fn new(capacity_bytes: usize, metadata_capacity: usize) -> Self {
+        Self {
+            modules: LruCache::unbounded(),
+            keys_by_id: HashMap::new(),
+            metadata: LruCache::unbounded(),
+            capacity_bytes,
+            metadata_capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stats: VerifiedModuleCacheStats::default(),
+        }
+    }
+
+    pub(crate) fn get(
+        &self,
+        hash: &ModuleHash,
+        deserializer_config: &DeserializerConfig,
+        verifier_config: &VerifierConfig,
+    ) -> Option<Arc<CompiledModule>> {
+        let key = CacheKey {
+            hash: *hash,
+            deserializer_config: deserializer_config.clone(),
+            verifier_config: verifier_config.clone(),
+        };
+        match self.modules.peek(&key) {
+            Some(entry) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.touch().module.clone())
+            },
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            },
+        }
+    }
+
+    /// Inserts a module that was deserialized with the given config and successfully verified
+    /// with the given verifier config.
+    pub(crate) fn insert(
+        &mut self,
+        hash: ModuleHash,
+        deserializer_config: &DeserializerConfig,
+        verifier_config: &VerifierConfig,
+        module: Arc<CompiledModule>,
+        size_bytes: usize,
+    ) {
+        self.insert_metadata(hash, deserializer_config, module.metadata.clone());
+        if size_bytes > self.capacity_bytes {
+            return;
+        }
+        let key = CacheKey {
+            hash,
+            deserializer_config: deserializer_config.clone(),
+            verifier_config: verifier_config.clone(),
+        };
+        let id = module.self_id();
+        if let Some(previous) = self.modules.put(
+            key.clone(),
+            Referenced::new(CacheEntry { module, size_bytes }),
+        ) {
+            self.stats.size_bytes -= previous.value.size_bytes;
+        } else {
+            self.keys_by_id.entry(id).or_default().push(key);
+        }
+        self.stats.size_bytes += size_bytes;
+
+        while self.stats.size_bytes > self.capacity_bytes {
+            match pop_lru(&mut self.modules) {
+                Some((key, entry)) => {
+                    self.stats.evictions += 1;
+                    self.remove_key_of(&key, &entry);
+                },
+                None => break,
+            }
+        }
+    }
+
+    pub(crate) fn get_metadata(
+        &self,
+        hash: &ModuleHash,
+        deserializer_config: &DeserializerConfig,
+    ) -> Option<Arc<Vec<Metadata>>> {
+        self.metadata
+            .peek(&MetadataKey {
+                hash: *hash,
+                deserializer_config: deserializer_config.clone(),
+            })
+            .map(|metadata| metadata.touch().clone())
+    }
+
+    pub(crate) fn insert_metadata(
+        &mut self,
+        hash: ModuleHash,
+        deserializer_config: &DeserializerConfig,
+        metadata: Vec<Metadata>,
+    ) -> Arc<Vec<Metadata>> {
+        let metadata = Arc::new(metadata);
+        self.metadata.put(
+            MetadataKey {
+                hash,
+                deserializer_config: deserializer_config.clone(),
+            },
+            Referenced::new(metadata.clone()),
+        );
+        while self.metadata.len() > self.metadata_capacity {
+            if pop_lru(&mut self.metadata).is_none() {
+                break;
+            }
+        }
+        metadata
+    }
+
+    /// Removes the versions of the module with the given id that were replaced by a committed
+    /// publish, i.e., all versions but the published one (none if the module was deleted).
+    /// Versions that are still held by a loader are kept, since they may be used again by a VM
+    /// that has not observed the upgrade, e.g., when executing another fork of the chain.
+    pub(crate) fn invalidate(&mut self, id: &ModuleId, published: Option<&ModuleHash>) {
+        let stale_keys: Vec<CacheKey> = match self.keys_by_id.get(id) {
+            Some(keys) => keys
+                .iter()
+                .filter(|key| Some(&key.hash) != published)
+                .filter(|key| {
+                    self.modules
+                        .peek(*key)
+                        .is_some_and(|entry| Arc::strong_count(&entry.value.module) == 1)
+                })
+                .cloned()
+                .collect(),
+            None => return,
+        };
+        for key in stale_keys {
+            if let Some(entry) = self.modules.pop(&key) {
+                self.stats.invalidations += 1;
+                self.remove_key_of(&key, &entry.value);
+            }
+        }
+    }
+
+    pub(crate) fn flush(&mut self) {
+        self.modules.clear();
+        self.keys_by_id.clear();
+        self.metadata.clear();
+        self.stats.size_bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> VerifiedModuleCacheStats {
+        VerifiedModuleCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            num_modules: self.modules.len(),
+            ..self.stats
+        }
+    }
+
+    fn remove_key_of(&mut self, key: &CacheKey, entry: &CacheEntry) {
+        self.stats.size_bytes -= entry.size_bytes;
+        let id = entry.module.self_id();
+        if let Some(keys) = self.keys_by_id.get_mut(&id) {
+            keys.retain(|k| k != key);
+            if keys.is_empty() {
+                self.keys_by_id.remove(&id);
+            }
+        }
+    }
+}
+
+lazy_static! {
+    pub(crate) static ref VERIFIED_MODULE_CACHE: RwLock<VerifiedModuleCache> =
+        RwLock::new(VerifiedModuleCache::new(
+            VERIFIED_MODULE_CACHE_CAPACITY_BYTES,
+            MODULE_METADATA_CACHE_SIZE,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::empty_module;
+
+    fn config() -> DeserializerConfig {
+        DeserializerConfig::new(6, 255)
+    }
+
+    #[test]
+    fn evicts_and_invalidates_by_module_id() {
+        let config = config();
+        let verifier_config = VerifierConfig::default();
+        let module = empty_module();
+        let id = module.self_id();
+
+        let mut cache = VerifiedModuleCache::new(100, 10);
+        cache.insert(
+            [0; 32],
+            &config,
+            &verifier_config,
+            Arc::new(module.clone()),
+            40,
+        );
+        cache.insert(
+            [1; 32],
+            &config,
+            &verifier_config,
+            Arc::new(module.clone()),
+            40,
+        );
+        assert!(cache.get(&[0; 32], &config, &verifier_config).is_some());
+        assert!(cache
+            .get(&[1; 32], &DeserializerConfig::new(5, 255), &verifier_config)
+            .is_none());
+        assert_eq!(cache.stats().size_bytes, 80);
+
+        // The least recently used version is evicted.
+        cache.insert(
+            [2; 32],
+            &config,
+            &verifier_config,
+            Arc::new(module.clone()),
+            40,
+        );
+        assert!(cache.get(&[1; 32], &config, &verifier_config).is_none());
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().num_modules, 2);
+
+        cache.invalidate(&id, None);
+        assert!(cache.get(&[0; 32], &config, &verifier_config).is_none());
+        assert!(cache.get(&[2; 32], &config, &verifier_config).is_none());
+        assert_eq!(cache.stats().invalidations, 2);
+        assert_eq!(cache.stats().size_bytes, 0);
+        assert_eq!(cache.stats().num_modules, 0);
+    }
+
+    #[test]
+    fn keys_by_verifier_config() {
+        let config = config();
+        let lenient = VerifierConfig::default();
+        let strict = VerifierConfig {
+            max_loop_depth: Some(1),
+            ..VerifierConfig::default()
+        };
+
+        let mut cache = VerifiedModuleCache::new(100, 10);
+        cache.insert([0; 32], &config, &lenient, Arc::new(empty_module()), 40);
+        assert!(cache.get(&[0; 32], &config, &lenient).is_some());
+        assert!(cache.get(&[0; 32], &config, &strict).is_none());
+
+        // The metadata does not depend on the verifier.
+        assert!(cache.get_metadata(&[0; 32], &config).is_some());
+        assert!(cache
+            .get_metadata(&[0; 32], &DeserializerConfig::new(5, 255))
+            .is_none());
+    }
+
+    #[test]
+    fn evicts_metadata_not_looked_up() {
+        let config = config();
+        let mut cache = VerifiedModuleCache::new(100, 2);
+        cache.insert_metadata([0; 32], &config, vec![]);
+        cache.insert_metadata([1; 32], &config, vec![]);
+
+        // The least recently inserted metadata was looked up, so the other one is evicted.
+        assert!(cache.get_metadata(&[0; 32], &config).is_some());
+        cache.insert_metadata([2; 32], &config, vec![]);
+        assert!(cache.get_metadata(&[0; 32], &config).is_some());
+        assert!(cache.get_metadata(&[1; 32], &config).is_none());
+        assert!(cache.get_metadata(&[2; 32], &config).is_some());
+    }
+
+    #[test]
+    fn invalidation_keeps_published_and_used_versions() {
+        let config = config();
+        let verifier_config = VerifierConfig::default();
+        let module = empty_module();
+        let id = module.self_id();
+
+        let mut cache = VerifiedModuleCache::new(1000, 10);
+        let in_use = Arc::new(module.clone());
+        cache.insert([0; 32], &config, &verifier_config, in_use.clone(), 40);
+        cache.insert(
+            [1; 32],
+            &config,
+            &verifier_config,
+            Arc::new(module.clone()),
+            40,
+        );
+        cache.insert(
+            [2; 32],
+            &config,
+            &verifier_config,
+            Arc::new(module.clone()),
+            40,
+        );
+
+        // Version 2 is published: version 1 is stale, version 0 is still held by a loader.
+        cache.invalidate(&id, Some(&[2; 32]));
+        assert_eq!(cache.stats().invalidations, 1);
+        assert!(cache.get(&[1; 32], &config, &verifier_config).is_none());
+        assert!(cache.get(&[2; 32], &config, &verifier_config).is_some());
+        assert!(cache.get(&[0; 32], &config, &verifier_config).is_some());
+
+        // Once released, version 0 is invalidated by the next publish.
+        drop(in_use);
+        cache.invalidate(&id, Some(&[2; 32]));
+        assert_eq!(cache.stats().invalidations, 2);
+        assert!(cache.get(&[0; 32], &config, &verifier_config).is_none());
+        assert_eq!(cache.stats().num_modules, 1);
+    }
+}
@@ -5,13 +5,14 @@
 use crate::{
     config::VMConfig,
     data_cache::TransactionDataCache,
-    loader::{ModuleStorage, ModuleStorageAdapter},
+    loader::{module_hash, ModuleStorage, ModuleStorageAdapter, VERIFIED_MODULE_CACHE},
     native_extensions::NativeContextExtensions,
     native_functions::NativeFunction,
     runtime::VMRuntime,
     session::Session,
 };
 use move_binary_format::{
+    deserializer::DeserializerConfig,
     errors::{Location, PartialVMError, VMResult},
     CompiledModule,
 };
@@ -21,6 +22,8 @@ use move_core_types::{
 };
 use std::sync::Arc;
 
+pub use crate::loader::VerifiedModuleCacheStats;
+
 #[derive(Clone)]
 pub struct MoveVM {
     pub(crate) runtime: VMRuntime,
@@ -118,6 +121,50 @@ impl MoveVM {
         self.runtime.loader().mark_as_invalid()
     }
 
+    /// Removes the versions of the module replaced by a committed publish of the given bytes
+    /// (`None` if the module was deleted) from the cache of deserialized and verified modules,
+    /// which is shared by all VMs in the process. This is not needed for correctness, since the
+    /// cache is keyed by the hash of the module bytes, but frees the space of upgraded modules.
+    /// Versions still held by a loader are kept.
+    pub fn invalidate_verified_module(module_id: &ModuleId, published_bytes: Option<&[u8]>) {
+        let published = published_bytes.map(module_hash);
+        VERIFIED_MODULE_CACHE
+            .write()
+            .invalidate(module_id, published.as_ref())
+    }
+
+    /// Returns the metadata of the module with the given bytes, deserializing the module only if
+    /// no VM in the process has seen it before with the given config. Returns `None` if the
+    /// module cannot be deserialized.
+    pub fn get_module_metadata(
+        module_bytes: &[u8],
+        deserializer_config: &DeserializerConfig,
+    ) -> Option<Arc<Vec<Metadata>>> {
+        let hash = module_hash(module_bytes);
+        if let Some(metadata) = VERIFIED_MODULE_CACHE
+            .read()
+            .get_metadata(&hash, deserializer_config)
+        {
+            return Some(metadata);
+        }
+        let module =
+            CompiledModule::deserialize_with_config(module_bytes, deserializer_config).ok()?;
+        Some(VERIFIED_MODULE_CACHE.write().insert_metadata(
+            hash,
+            deserializer_config,
+            module.metadata,
+        ))
+    }
+
+    /// Empties the cache of deserialized and verified modules shared by all VMs in the process.
+    pub fn flush_verified_module_cache() {
+        VERIFIED_MODULE_CACHE.write().flush()
+    }
+
+    pub fn verified_module_cache_stats() -> VerifiedModuleCacheStats {
+        VERIFIED_MODULE_CACHE.read().stats()
+    }
+
     /// Returns true if the loader cache has been invalidated (either by explicit call above
     /// or by the runtime)
     pub fn is_loader_cache_invalidated(&self) -> bool {
@@ -5,9 +5,10 @@
 use super::package_layout::CompiledPackageLayout;
 use crate::{
     compilation::compiled_package::{
-        build_and_report_no_exit_v2_driver, build_and_report_v2_driver, CompiledPackage,
+        build_and_report_no_exit_v2_driver, build_and_report_v2_driver,
+        make_source_and_deps_for_compiler, CompiledPackage,
     },
-    resolution::resolution_graph::ResolvedGraph,
+    resolution::resolution_graph::{ResolvedGraph, ResolvedTable},
     source_package::parsed_manifest::PackageName,
     CompilerConfig,
 };
@@ -15,9 +16,11 @@ use anyhow::Result;
 use move_compiler::{
     compiled_unit::AnnotatedCompiledUnit,
     diagnostics::{report_diagnostics_to_color_buffer, report_warnings, FilesSourceText},
+    shared::PackagePaths,
     Compiler,
 };
 use move_model::model;
+use move_symbol_pool::Symbol;
 use petgraph::algo::toposort;
 use std::{collections::BTreeSet, io::Write, path::Path};
 #[cfg(feature = "evm-backend")]
@@ -164,9 +167,58 @@ impl BuildPlan {
             Some(under_path) => under_path.clone(),
             None => self.resolution_graph.root_package_path.clone(),
         };
+
+        let (compiled, model) = CompiledPackage::build_all(
+            writer,
+            &project_root,
+            root_package.clone(),
+            self.transitive_dependencies(),
+            config,
+            &self.resolution_graph,
+            compiler_driver_v1,
+            compiler_driver_v2,
+        )?;
+
+        Self::clean(
+            &project_root.join(CompiledPackageLayout::Root.path()),
+            self.sorted_deps.iter().copied().collect(),
+        )?;
+        Ok((compiled, model))
+    }
+
+    /// The sources of the root package and of each of its dependencies, with the named addresses
+    /// they are compiled with. Dependencies are paired with whether their sources are available,
+    /// otherwise their bytecode is listed instead.
+    pub fn compiler_package_paths(&self) -> Result<(PackagePaths, Vec<(PackagePaths, bool)>)> {
+        let transitive_dependencies = self
+            .transitive_dependencies()
+            .into_iter()
+            .map(
+                |(name, _, source_paths, resolution_table, source_available)| {
+                    (name, source_paths, resolution_table, source_available)
+                },
+            )
+            .collect();
+        make_source_and_deps_for_compiler(
+            &self.resolution_graph,
+            &self.resolution_graph.package_table[&self.root],
+            transitive_dependencies,
+        )
+    }
+
+    fn transitive_dependencies(
+        &self,
+    ) -> Vec<(
+        /* name */ PackageName,
+        /* is immediate */ bool,
+        /* source paths */ Vec<Symbol>,
+        /* address mapping */ &ResolvedTable,
+        /* whether source is available */ bool,
+    )> {
+        let root_package = &self.resolution_graph.package_table[&self.root];
         let immediate_dependencies_names =
             root_package.immediate_dependencies(&self.resolution_graph);
-        let transitive_dependencies = root_package
+        root_package
             .transitive_dependencies(&self.resolution_graph)
             .into_iter()
             .map(|package_name| {
@@ -192,24 +244,7 @@ impl BuildPlan {
                     source_available,
                 )
             })
-            .collect();
-
-        let (compiled, model) = CompiledPackage::build_all(
-            writer,
-            &project_root,
-            root_package.clone(),
-            transitive_dependencies,
-            config,
-            &self.resolution_graph,
-            compiler_driver_v1,
-            compiler_driver_v2,
-        )?;
-
-        Self::clean(
-            &project_root.join(CompiledPackageLayout::Root.path()),
-            self.sorted_deps.iter().copied().collect(),
-        )?;
-        Ok((compiled, model))
+            .collect()
     }
 
     #[cfg(feature = "evm-backend")]
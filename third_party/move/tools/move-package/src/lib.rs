@@ -14,7 +14,10 @@ use crate::{
         build_plan::BuildPlan, compiled_package::CompiledPackage, model_builder::ModelBuilder,
     },
     package_lock::PackageLock,
-    resolution::resolution_graph::{ResolutionGraph, ResolvedGraph},
+    resolution::{
+        lock_file::{copy_package, LockFile, VENDOR_DIR},
+        resolution_graph::{ResolutionGraph, ResolvedGraph},
+    },
     source_package::manifest_parser,
 };
 use anyhow::{bail, Result};
@@ -287,6 +290,66 @@ impl BuildConfig {
         ret
     }
 
+    /// Resolves the dependencies of the package at `path` again, ignoring its current `Move.lock`,
+    /// and writes a new `Move.lock` pinning them. Dev dependencies are always included.
+    pub fn update_lock_file<W: Write>(mut self, path: &Path, writer: &mut W) -> Result<LockFile> {
+        self.dev_mode = true;
+        let path = SourcePackageLayout::try_find_root(path)?;
+        let toml_manifest =
+            self.parse_toml_manifest(path.join(SourcePackageLayout::Manifest.path()))?;
+        let mutx = PackageLock::lock();
+        let manifest = manifest_parser::parse_source_manifest(toml_manifest)?;
+        let previous = LockFile::read(&path)?;
+        let resolution_graph =
+            ResolutionGraph::new_with_lock_file(manifest, path.clone(), self, None, writer)?;
+        let lock_file = LockFile::from_graph(&resolution_graph, previous.as_ref())?;
+        lock_file.write(&path)?;
+        mutx.unlock();
+        Ok(lock_file)
+    }
+
+    /// Copies all the remote dependencies of the package at `path` into its `vendor` directory,
+    /// from where they are used instead of being downloaded. Dev dependencies are always included.
+    /// The package is locked first if it has no `Move.lock` yet, and returns the vendored packages.
+    pub fn vendor_dependencies<W: Write>(
+        mut self,
+        path: &Path,
+        writer: &mut W,
+    ) -> Result<Vec<PathBuf>> {
+        self.dev_mode = true;
+        let path = SourcePackageLayout::try_find_root(path)?;
+        let toml_manifest =
+            self.parse_toml_manifest(path.join(SourcePackageLayout::Manifest.path()))?;
+        let mutx = PackageLock::lock();
+        let manifest = manifest_parser::parse_source_manifest(toml_manifest)?;
+        let resolution_graph = ResolutionGraph::new(manifest, path.clone(), self, writer)?;
+        if resolution_graph.lock_file.is_none() {
+            LockFile::from_graph(&resolution_graph, None)?.write(&path)?;
+        }
+
+        // Vendored packages may be copied from the current vendor directory, so the new one is
+        // staged next to it and only replaces it once complete
+        let vendor_dir = path.join(VENDOR_DIR);
+        let staging_dir = path.join(format!("{}.new", VENDOR_DIR));
+        if staging_dir.exists() {
+            std::fs::remove_dir_all(&staging_dir)?;
+        }
+        let mut vendored = vec![];
+        for (name, package) in &resolution_graph.package_table {
+            if resolution_graph.is_remote_dependency(name, &package.package_path) {
+                copy_package(&package.package_path, &staging_dir.join(name.as_str()))?;
+                vendored.push(vendor_dir.join(name.as_str()));
+            }
+        }
+        std::fs::create_dir_all(&staging_dir)?;
+        if vendor_dir.exists() {
+            std::fs::remove_dir_all(&vendor_dir)?;
+        }
+        std::fs::rename(&staging_dir, &vendor_dir)?;
+        mutx.unlock();
+        Ok(vendored)
+    }
+
     fn parse_toml_manifest(&self, path: PathBuf) -> Result<toml::Value> {
         let manifest_string = std::fs::read_to_string(path)?;
         manifest_parser::parse_move_manifest_string(manifest_string)
@@ -0,0 +1,319 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `Move.lock` file of a package, which pins every remote dependency of the package.
+//!
+//! Git dependencies are pinned to the commit they were checked out at, and all remote
+//! dependencies, including on-chain ones, to the digest of their sources and manifest. A package
+//! with a `Move.lock` is always resolved against it: git dependencies are checked out at the
+//! locked commit, without fetching if the commit is already available, and resolution fails if a
+//! dependency is not locked or its digest differs from the locked one.
+//!
+//! Dependencies can also be vendored, i.e. copied into the `vendor` directory of the package, in
+//! which case they are used from there instead of being downloaded.
+
+use crate::{
+    resolution::{digest::compute_digest, resolution_graph::ResolutionGraph},
+    source_package::{
+        layout::SourcePackageLayout,
+        parsed_manifest::{Dependency, PackageDigest, PackageName},
+    },
+};
+use anyhow::{bail, Context, Result};
+use move_command_line_common::env::MOVE_HOME;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub const LOCK_FILE_NAME: &str = "Move.lock";
+pub const VENDOR_DIR: &str = "vendor";
+
+const LOCK_FILE_VERSION: u64 = 1;
+const LOCK_FILE_HEADER: &str =
+    "# This file is generated by the Move package system, it should not be edited by hand.\n\n";
+
+/// The parts of a package that are locked and vendored
+const PACKAGE_CONTENTS: [SourcePackageLayout; 5] = [
+    SourcePackageLayout::Manifest,
+    SourcePackageLayout::Sources,
+    SourcePackageLayout::Scripts,
+    SourcePackageLayout::Examples,
+    SourcePackageLayout::Tests,
+];
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LockFile {
+    pub version: u64,
+    #[serde(rename = "package", default)]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A remote dependency, as it was when locked
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    /// Git url, revision and subdirectory of a git dependency, as given in the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
+    /// The commit the git revision pointed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Node url and address of an on-chain dependency, as given in the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Digest of the sources and manifest of the package
+    pub digest: String,
+}
+
+impl LockFile {
+    /// Reads the lock file of the package at `root_path`, if it has one.
+    pub fn read(root_path: &Path) -> Result<Option<LockFile>> {
+        let path = root_path.join(LOCK_FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read {}", path.display()))?;
+        let lock_file: LockFile = toml::from_str(&contents)
+            .with_context(|| format!("Unable to parse {}", path.display()))?;
+        if lock_file.version != LOCK_FILE_VERSION {
+            bail!(
+                "Unsupported version {} of {}, expected version {}",
+                lock_file.version,
+                path.display(),
+                LOCK_FILE_VERSION
+            );
+        }
+        Ok(Some(lock_file))
+    }
+
+    pub fn write(&self, root_path: &Path) -> Result<()> {
+        let contents = toml::to_string(self)?;
+        fs::write(
+            root_path.join(LOCK_FILE_NAME),
+            format!("{}{}", LOCK_FILE_HEADER, contents),
+        )?;
+        Ok(())
+    }
+
+    /// Locks the remote dependencies of a resolution graph. The commits of vendored git
+    /// dependencies can't be read from their checkout, so they are taken from `previous`.
+    pub fn from_graph<T>(graph: &ResolutionGraph<T>, previous: Option<&LockFile>) -> Result<Self> {
+        let dependencies = graph.dependency_declarations();
+        let mut packages = vec![];
+        for (name, package) in &graph.package_table {
+            if !graph.is_remote_dependency(name, &package.package_path) {
+                continue;
+            }
+            let mut locked = LockedPackage {
+                name: name.to_string(),
+                git: None,
+                rev: None,
+                subdir: None,
+                commit: None,
+                node: None,
+                address: None,
+                digest: package_digest(&package.package_path)?.to_string(),
+            };
+            match dependencies.get(name) {
+                Some(Dependency {
+                    git_info: Some(git_info),
+                    ..
+                }) => {
+                    locked.git = Some(git_info.git_url.to_string());
+                    locked.rev = Some(git_info.git_rev.to_string());
+                    locked.subdir = Some(git_info.subdir.to_string_lossy().to_string())
+                        .filter(|subdir| !subdir.is_empty());
+                    locked.commit = if graph.is_vendored_package(&package.package_path) {
+                        previous
+                            .and_then(|previous| previous.get(name))
+                            .filter(|previous| previous.matches(dependencies[name]))
+                            .and_then(|previous| previous.commit.clone())
+                    } else {
+                        Some(head_commit(&package.package_path)?)
+                    };
+                    if locked.commit.is_none() {
+                        bail!(
+                            "Unable to lock vendored dependency '{}', as its git commit is unknown. \
+                            Remove the '{}' directory to lock it",
+                            name,
+                            VENDOR_DIR
+                        );
+                    }
+                },
+                Some(Dependency {
+                    node_info: Some(node_info),
+                    ..
+                }) => {
+                    locked.node = Some(node_info.node_url.to_string());
+                    locked.address = Some(node_info.package_address.to_string());
+                },
+                // Local dependencies of remote packages are pinned along with those
+                _ => (),
+            }
+            packages.push(locked);
+        }
+        Ok(Self {
+            version: LOCK_FILE_VERSION,
+            packages,
+        })
+    }
+
+    pub fn get(&self, name: &PackageName) -> Option<&LockedPackage> {
+        self.packages
+            .iter()
+            .find(|package| package.name == name.as_str())
+    }
+
+    /// Fails if a remote dependency of the graph is not locked, or differs from the locked one.
+    pub fn check_graph<T>(&self, graph: &ResolutionGraph<T>) -> Result<()> {
+        let dependencies = graph.dependency_declarations();
+        for (name, package) in &graph.package_table {
+            if !graph.is_remote_dependency(name, &package.package_path) {
+                continue;
+            }
+            let locked = match self.get(name) {
+                Some(locked) => locked,
+                None => bail!(
+                    "Dependency '{}' is not in {}, the lock file needs to be updated",
+                    name,
+                    LOCK_FILE_NAME
+                ),
+            };
+            if let Some(dependency) = dependencies.get(name) {
+                if !locked.matches(dependency) {
+                    bail!(
+                        "Dependency '{}' changed since {} was written, the lock file needs to be updated",
+                        name,
+                        LOCK_FILE_NAME
+                    );
+                }
+            }
+            let digest = package_digest(&package.package_path)?;
+            if locked.digest != digest.as_str() {
+                bail!(
+                    "Source digest mismatch in dependency '{}'. {} expects '{}' but got '{}'.",
+                    name,
+                    LOCK_FILE_NAME,
+                    locked.digest,
+                    digest
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LockedPackage {
+    /// Whether this is the lock of the dependency, as currently declared
+    pub fn matches(&self, dependency: &Dependency) -> bool {
+        if let Some(git_info) = &dependency.git_info {
+            self.git.as_deref() == Some(git_info.git_url.as_str())
+                && self.rev.as_deref() == Some(git_info.git_rev.as_str())
+                && self.subdir.as_deref().unwrap_or_default() == git_info.subdir.to_string_lossy()
+        } else if let Some(node_info) = &dependency.node_info {
+            self.node.as_deref() == Some(node_info.node_url.as_str())
+                && self.address.as_deref() == Some(node_info.package_address.as_str())
+        } else {
+            self.git.is_none() && self.node.is_none()
+        }
+    }
+}
+
+impl<T> ResolutionGraph<T> {
+    /// Whether the package is a dependency that was downloaded or vendored, rather than part of
+    /// the local sources
+    pub fn is_remote_dependency(&self, name: &PackageName, package_path: &Path) -> bool {
+        *name != self.root_package.package.name
+            && (package_path.starts_with(MOVE_HOME.as_str())
+                || self.is_vendored_package(package_path))
+    }
+
+    pub fn is_vendored_package(&self, package_path: &Path) -> bool {
+        package_path.starts_with(self.root_package_path.join(VENDOR_DIR))
+    }
+
+    /// The dependencies as declared in the manifests of the packages in the graph, by name
+    fn dependency_declarations(&self) -> BTreeMap<PackageName, &Dependency> {
+        self.package_table
+            .values()
+            .flat_map(|package| {
+                package
+                    .source_package
+                    .dependencies
+                    .iter()
+                    .chain(package.source_package.dev_dependencies.iter())
+            })
+            .map(|(name, dependency)| (*name, dependency))
+            .collect()
+    }
+}
+
+/// Where the package would be vendored in the package at `root_path`, if it is
+pub fn vendored_package_path(root_path: &Path, name: &PackageName) -> Option<PathBuf> {
+    let path = root_path.join(VENDOR_DIR).join(name.as_str());
+    path.join(SourcePackageLayout::Manifest.path())
+        .is_file()
+        .then_some(path)
+}
+
+/// Digest of all the sources and the manifest of a package, regardless of the build mode
+pub fn package_digest(package_path: &Path) -> Result<PackageDigest> {
+    let paths: Vec<PathBuf> = PACKAGE_CONTENTS
+        .iter()
+        .map(|layout| package_path.join(layout.path()))
+        .filter(|path| path.exists())
+        .collect();
+    compute_digest(&paths)
+}
+
+/// Copies the sources and the manifest of a package.
+pub fn copy_package(from: &Path, to: &Path) -> Result<()> {
+    for layout in PACKAGE_CONTENTS.iter() {
+        let source = from.join(layout.path());
+        if !source.exists() {
+            continue;
+        }
+        let target = to.join(layout.path());
+        if source.is_file() {
+            fs::create_dir_all(to)?;
+            fs::copy(&source, &target)?;
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&source).follow_links(true) {
+            let entry = entry?;
+            let target = target.join(entry.path().strip_prefix(&source)?);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)?;
+            } else {
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn head_commit(repo_path: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["-C", &repo_path.to_string_lossy(), "rev-parse", "HEAD"])
+        .output()
+        .with_context(|| format!("Failed to read the commit of {}", repo_path.display()))?;
+    if !output.status.success() {
+        bail!(
+            "Failed to read the commit of {}: {}",
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
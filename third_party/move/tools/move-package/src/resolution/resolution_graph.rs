@@ -4,10 +4,13 @@
 
 use crate::{
     package_hooks,
-    resolution::digest::compute_digest,
+    resolution::{
+        digest::compute_digest,
+        lock_file::{vendored_package_path, LockFile},
+    },
     source_package::{
         layout::SourcePackageLayout,
-        manifest_parser::{parse_move_manifest_string, parse_source_manifest},
+        manifest_parser::{parse_move_manifest_string, parse_source_manifest, url_to_file_name},
         parsed_manifest::{
             Dependencies, Dependency, FileName, NamedAddress, PackageDigest, PackageName,
             SourceManifest, SubstOrRename,
@@ -75,6 +78,8 @@ pub struct ResolutionGraph<T> {
     pub graph: DiGraphMap<PackageName, ()>,
     /// A mapping of package name to its resolution
     pub package_table: BTreeMap<PackageName, ResolutionPackage<T>>,
+    /// The lock file remote dependencies are resolved against, if any
+    pub lock_file: Option<LockFile>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -94,10 +99,29 @@ pub struct ResolutionPackage<T> {
 }
 
 impl ResolvingGraph {
+    /// Resolves the package graph, against the lock file of the root package if it has one.
     pub fn new<W: Write>(
+        root_package: SourceManifest,
+        root_package_path: PathBuf,
+        build_options: BuildConfig,
+        writer: &mut W,
+    ) -> Result<ResolvingGraph> {
+        let lock_file = LockFile::read(&root_package_path)?;
+        Self::new_with_lock_file(
+            root_package,
+            root_package_path,
+            build_options,
+            lock_file,
+            writer,
+        )
+    }
+
+    /// Resolves the package graph, against the given lock file, if any.
+    pub fn new_with_lock_file<W: Write>(
         root_package: SourceManifest,
         root_package_path: PathBuf,
         mut build_options: BuildConfig,
+        lock_file: Option<LockFile>,
         writer: &mut W,
     ) -> Result<ResolvingGraph> {
         if build_options.architecture.is_none() {
@@ -111,6 +135,7 @@ impl ResolvingGraph {
             root_package: root_package.clone(),
             graph: DiGraphMap::new(),
             package_table: BTreeMap::new(),
+            lock_file,
         };
 
         resolution_graph
@@ -121,6 +146,9 @@ impl ResolvingGraph {
                     root_package.package.name
                 )
             })?;
+        if let Some(lock_file) = &resolution_graph.lock_file {
+            lock_file.check_graph(&resolution_graph)?;
+        }
         Ok(resolution_graph)
     }
 
@@ -131,6 +159,7 @@ impl ResolvingGraph {
             root_package,
             graph,
             package_table,
+            lock_file,
         } = self;
 
         let mut unresolved_addresses = Vec::new();
@@ -191,6 +220,7 @@ impl ResolvingGraph {
             root_package,
             graph,
             package_table: resolved_package_table,
+            lock_file,
         })
     }
 
@@ -395,15 +425,24 @@ impl ResolvingGraph {
         root_path: PathBuf,
         writer: &mut W,
     ) -> Result<(Renaming, ResolvingTable)> {
-        Self::download_and_update_if_remote(
-            dep_name_in_pkg,
-            &dep,
-            self.build_options.skip_fetch_latest_git_deps,
-            writer,
-        )?;
         let (dep_package, dep_package_dir) =
-            Self::parse_package_manifest(&dep, &dep_name_in_pkg, root_path)
-                .with_context(|| format!("While processing dependency '{}'", dep_name_in_pkg))?;
+            match vendored_package_path(&self.root_package_path, &dep_name_in_pkg) {
+                Some(vendored_path) => Self::read_package_manifest(&dep_name_in_pkg, vendored_path),
+                None => {
+                    let locked_commit =
+                        Self::locked_commit(self.lock_file.as_ref(), &dep_name_in_pkg, &dep);
+                    let dep = Self::checkout_for_commit(&dep, locked_commit);
+                    Self::download_and_update_if_remote(
+                        dep_name_in_pkg,
+                        &dep,
+                        self.build_options.skip_fetch_latest_git_deps,
+                        locked_commit,
+                        writer,
+                    )?;
+                    Self::parse_package_manifest(&dep, &dep_name_in_pkg, root_path)
+                },
+            }
+            .with_context(|| format!("While processing dependency '{}'", dep_name_in_pkg))?;
         self.build_resolution_graph(dep_package.clone(), dep_package_dir, false, writer)
             .with_context(|| {
                 format!("Unable to resolve package dependency '{}'", dep_name_in_pkg)
@@ -509,25 +548,79 @@ impl ResolvingGraph {
         mut root_path: PathBuf,
     ) -> Result<(SourceManifest, PathBuf)> {
         root_path.push(&dep.local);
-        match fs::read_to_string(root_path.join(SourcePackageLayout::Manifest.path())) {
+        Self::read_package_manifest(dep_name, root_path)
+    }
+
+    fn read_package_manifest(
+        dep_name: &PackageName,
+        package_path: PathBuf,
+    ) -> Result<(SourceManifest, PathBuf)> {
+        match fs::read_to_string(package_path.join(SourcePackageLayout::Manifest.path())) {
             Ok(contents) => {
                 let source_package: SourceManifest =
                     parse_move_manifest_string(contents).and_then(parse_source_manifest)?;
-                Ok((source_package, root_path))
+                Ok((source_package, package_path))
             },
             Err(_) => Err(anyhow::format_err!(
                 "Unable to find package manifest for '{}' at {:?}",
                 dep_name,
-                SourcePackageLayout::Manifest.path().join(root_path),
+                SourcePackageLayout::Manifest.path().join(package_path),
             )),
         }
     }
 
+    /// The commit a git dependency is locked at, unless it changed since it was locked
+    fn locked_commit<'a>(
+        lock_file: Option<&'a LockFile>,
+        dep_name: &PackageName,
+        dep: &Dependency,
+    ) -> Option<&'a str> {
+        lock_file?
+            .get(dep_name)
+            .filter(|locked| locked.matches(dep))?
+            .commit
+            .as_deref()
+    }
+
+    /// The dependency, downloaded to a directory of its own if it is locked at a commit. The
+    /// directory shared by all packages depending on the same git revision is left as is, as
+    /// other packages may need it at the latest commit of the revision.
+    fn checkout_for_commit(dep: &Dependency, locked_commit: Option<&str>) -> Dependency {
+        let mut dep = dep.clone();
+        if let (Some(git_info), Some(commit)) = (&mut dep.git_info, locked_commit) {
+            // Downloaded packages locked at a commit are of the form <sanitized_git_url>_<commit>
+            git_info.download_to = git_info.download_to.with_file_name(format!(
+                "{}_{}",
+                url_to_file_name(git_info.git_url.as_str()),
+                commit
+            ));
+            dep.local = git_info.download_to.join(&git_info.subdir);
+        }
+        dep
+    }
+
     pub fn download_dependency_repos<W: Write>(
         manifest: &SourceManifest,
         build_options: &BuildConfig,
         root_path: &Path,
         writer: &mut W,
+    ) -> Result<()> {
+        let lock_file = LockFile::read(root_path)?;
+        Self::download_locked_dependency_repos(
+            manifest,
+            build_options,
+            root_path,
+            lock_file.as_ref(),
+            writer,
+        )
+    }
+
+    fn download_locked_dependency_repos<W: Write>(
+        manifest: &SourceManifest,
+        build_options: &BuildConfig,
+        root_path: &Path,
+        lock_file: Option<&LockFile>,
+        writer: &mut W,
     ) -> Result<()> {
         // include dev dependencies if in dev mode
         let empty_deps;
@@ -539,18 +632,30 @@ impl ResolvingGraph {
         };
 
         for (dep_name, dep) in manifest.dependencies.iter().chain(additional_deps.iter()) {
-            Self::download_and_update_if_remote(
-                *dep_name,
-                dep,
-                build_options.skip_fetch_latest_git_deps,
+            let (dep_manifest, _) = match vendored_package_path(root_path, dep_name) {
+                Some(vendored_path) => Self::read_package_manifest(dep_name, vendored_path),
+                None => {
+                    let locked_commit = Self::locked_commit(lock_file, dep_name, dep);
+                    let dep = Self::checkout_for_commit(dep, locked_commit);
+                    Self::download_and_update_if_remote(
+                        *dep_name,
+                        &dep,
+                        build_options.skip_fetch_latest_git_deps,
+                        locked_commit,
+                        writer,
+                    )?;
+                    Self::parse_package_manifest(&dep, dep_name, root_path.to_path_buf())
+                },
+            }
+            .with_context(|| format!("While processing dependency '{}'", *dep_name))?;
+            // download dependencies of dependencies
+            Self::download_locked_dependency_repos(
+                &dep_manifest,
+                build_options,
+                root_path,
+                lock_file,
                 writer,
             )?;
-
-            let (dep_manifest, _) =
-                Self::parse_package_manifest(dep, dep_name, root_path.to_path_buf())
-                    .with_context(|| format!("While processing dependency '{}'", *dep_name))?;
-            // download dependencies of dependencies
-            Self::download_dependency_repos(&dep_manifest, build_options, root_path, writer)?;
         }
         Ok(())
     }
@@ -559,6 +664,7 @@ impl ResolvingGraph {
         dep_name: PackageName,
         dep: &Dependency,
         skip_fetch_latest_git_deps: bool,
+        locked_commit: Option<&str>,
         writer: &mut W,
     ) -> Result<()> {
         if let Some(git_info) = &dep.git_info {
@@ -566,8 +672,10 @@ impl ResolvingGraph {
             let git_rev = git_info.git_rev.as_str();
             let git_path = &git_info.download_to.display().to_string();
 
-            // If there is no cached dependency, download it
-            if !git_info.download_to.exists() {
+            if let Some(commit) = locked_commit {
+                Self::checkout_locked_commit(dep_name, git_url, git_path, commit, writer)?;
+            } else if !git_info.download_to.exists() {
+                // If there is no cached dependency, download it
                 writeln!(
                     writer,
                     "{} {}",
@@ -694,6 +802,86 @@ impl ResolvingGraph {
         }
         Ok(())
     }
+
+    /// Checks out the commit a git dependency is locked at, in the directory of that commit, only
+    /// fetching from the remote if the commit isn't in the cached repository yet.
+    fn checkout_locked_commit<W: Write>(
+        dep_name: PackageName,
+        git_url: &str,
+        git_path: &str,
+        commit: &str,
+        writer: &mut W,
+    ) -> Result<()> {
+        let git = |args: &[&str]| -> Result<bool> {
+            Ok(Command::new("git")
+                .args(["-C", git_path])
+                .args(args)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()?
+                .success())
+        };
+
+        if !Path::new(git_path).exists() {
+            writeln!(
+                writer,
+                "{} {}",
+                "FETCHING GIT DEPENDENCY".bold().green(),
+                git_url,
+            )?;
+            confirm_git_available()?;
+            let status = Command::new("git")
+                .args(["clone", git_url, git_path])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .map_err(|_| {
+                    anyhow::anyhow!("Failed to clone Git repository for package '{}'", dep_name)
+                })?;
+            if !status.success() {
+                bail!(
+                    "Failed to clone Git repository for package '{}' | Exit status: {}",
+                    dep_name,
+                    status
+                );
+            }
+        } else {
+            confirm_git_available()?;
+            if let Ok(head) = Command::new("git")
+                .args(["-C", git_path, "rev-parse", "HEAD"])
+                .output()
+            {
+                if String::from_utf8_lossy(&head.stdout).trim() == commit {
+                    return Ok(());
+                }
+            }
+        }
+
+        let commit_object = format!("{}^{{commit}}", commit);
+        if !git(&["cat-file", "-e", &commit_object])? {
+            writeln!(
+                writer,
+                "{} {}",
+                "UPDATING GIT DEPENDENCY".bold().green(),
+                git_url,
+            )?;
+            if !git(&["fetch", "origin"])? {
+                bail!(
+                    "Failed to fetch commit '{}' locked for package '{}'",
+                    commit,
+                    dep_name
+                );
+            }
+        }
+        if !git(&["checkout", "--force", "--detach", commit])? {
+            bail!(
+                "Failed to checkout commit '{}' locked for package '{}'",
+                commit,
+                dep_name
+            );
+        }
+        Ok(())
+    }
 }
 
 impl ResolvingPackage {
@@ -449,7 +449,7 @@ fn parse_dependency(dep_name: &str, tval: TV) -> Result<PM::Dependency> {
     }
 }
 
-fn url_to_file_name(url: &str) -> String {
+pub(crate) fn url_to_file_name(url: &str) -> String {
     regex::Regex::new(r"/|:|\.|@")
         .unwrap()
         .replace_all(url, "_")
@@ -0,0 +1,177 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_package::{
+    resolution::lock_file::{package_digest, LockFile, LockedPackage, VENDOR_DIR},
+    BuildConfig,
+};
+use move_symbol_pool::Symbol;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+/// Writes a package depending on a git package that is only available vendored.
+fn write_package_with_vendored_dep(root: &Path) {
+    fs::create_dir_all(root.join("sources")).unwrap();
+    fs::write(
+        root.join("Move.toml"),
+        r#"
+[package]
+name = "Root"
+version = "0.0.0"
+
+[dependencies]
+Dep = { git = "https://example.invalid/dep.git", rev = "main", subdir = "dep" }
+"#,
+    )
+    .unwrap();
+    fs::write(
+        root.join("sources/root.move"),
+        "module 0x2::root { public fun f(): u64 { 0x3::dep::f() } }",
+    )
+    .unwrap();
+
+    let dep = root.join(VENDOR_DIR).join("Dep");
+    fs::create_dir_all(dep.join("sources")).unwrap();
+    fs::write(
+        dep.join("Move.toml"),
+        "[package]\nname = \"Dep\"\nversion = \"0.0.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dep.join("sources/dep.move"),
+        "module 0x3::dep { public fun f(): u64 { 1 } }",
+    )
+    .unwrap();
+}
+
+fn resolve(root: &Path) -> anyhow::Result<()> {
+    BuildConfig {
+        install_dir: Some(root.join("build")),
+        ..Default::default()
+    }
+    .resolution_graph_for_package(root, &mut Vec::new())
+    .map(|_| ())
+}
+
+#[test]
+fn vendored_dependencies_are_not_downloaded() {
+    let dir = tempdir().unwrap();
+    write_package_with_vendored_dep(dir.path());
+
+    let graph = BuildConfig {
+        install_dir: Some(dir.path().join("build")),
+        ..Default::default()
+    }
+    .resolution_graph_for_package(dir.path(), &mut Vec::new())
+    .unwrap();
+    let name = Symbol::from("Dep");
+    let dep = &graph.package_table[&name];
+    assert_eq!(dep.package_path, dir.path().join(VENDOR_DIR).join("Dep"));
+    assert!(graph.is_remote_dependency(&name, &dep.package_path));
+    assert!(graph.lock_file.is_none());
+}
+
+#[test]
+fn lock_file_is_checked() {
+    let dir = tempdir().unwrap();
+    write_package_with_vendored_dep(dir.path());
+    let dep_path = dir.path().join(VENDOR_DIR).join("Dep");
+
+    // The commit of a vendored dependency is only known from a previous lock file
+    let error = BuildConfig::default()
+        .update_lock_file(dir.path(), &mut Vec::new())
+        .unwrap_err();
+    assert!(error.to_string().contains("git commit is unknown"));
+
+    let mut locked = LockedPackage {
+        name: "Dep".to_string(),
+        git: Some("https://example.invalid/dep.git".to_string()),
+        rev: Some("main".to_string()),
+        subdir: Some("dep".to_string()),
+        commit: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+        node: None,
+        address: None,
+        digest: package_digest(&dep_path).unwrap().to_string(),
+    };
+    let lock_file = LockFile {
+        version: 1,
+        packages: vec![locked.clone()],
+    };
+    lock_file.write(dir.path()).unwrap();
+    assert_eq!(LockFile::read(dir.path()).unwrap(), Some(lock_file));
+    resolve(dir.path()).unwrap();
+
+    // Updating keeps the commit of the vendored dependency
+    let updated = BuildConfig::default()
+        .update_lock_file(dir.path(), &mut Vec::new())
+        .unwrap();
+    assert_eq!(updated.packages, vec![locked.clone()]);
+
+    // A different revision in the manifest needs the lock file to be updated
+    locked.rev = Some("other".to_string());
+    LockFile {
+        version: 1,
+        packages: vec![locked.clone()],
+    }
+    .write(dir.path())
+    .unwrap();
+    let error = resolve(dir.path()).unwrap_err();
+    assert!(format!("{:#}", error).contains("changed since Move.lock was written"));
+
+    // So does a dependency that is not locked
+    LockFile {
+        version: 1,
+        packages: vec![],
+    }
+    .write(dir.path())
+    .unwrap();
+    let error = resolve(dir.path()).unwrap_err();
+    assert!(format!("{:#}", error).contains("is not in Move.lock"));
+
+    // And changes to the sources of a dependency are detected
+    locked.rev = Some("main".to_string());
+    LockFile {
+        version: 1,
+        packages: vec![locked],
+    }
+    .write(dir.path())
+    .unwrap();
+    fs::write(
+        dep_path.join("sources/dep.move"),
+        "module 0x3::dep { public fun f(): u64 { 2 } }",
+    )
+    .unwrap();
+    let error = resolve(dir.path()).unwrap_err();
+    assert!(format!("{:#}", error).contains("Source digest mismatch in dependency 'Dep'"));
+}
+
+#[test]
+fn vendoring_copies_dependencies() {
+    let dir = tempdir().unwrap();
+    write_package_with_vendored_dep(dir.path());
+    let dep_path = dir.path().join(VENDOR_DIR).join("Dep");
+    LockFile {
+        version: 1,
+        packages: vec![LockedPackage {
+            name: "Dep".to_string(),
+            git: Some("https://example.invalid/dep.git".to_string()),
+            rev: Some("main".to_string()),
+            subdir: Some("dep".to_string()),
+            commit: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+            node: None,
+            address: None,
+            digest: package_digest(&dep_path).unwrap().to_string(),
+        }],
+    }
+    .write(dir.path())
+    .unwrap();
+
+    // Vendoring again copies the vendored packages over themselves
+    let vendored = BuildConfig::default()
+        .vendor_dependencies(dir.path(), &mut Vec::new())
+        .unwrap();
+    assert_eq!(vendored, vec![dep_path.clone()]);
+    assert!(dep_path.join("sources/dep.move").is_file());
+    assert!(!dir.path().join(format!("{}.new", VENDOR_DIR)).exists());
+    resolve(dir.path()).unwrap();
+}
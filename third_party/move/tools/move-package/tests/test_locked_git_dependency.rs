@@ -0,0 +1,102 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_command_line_common::env::MOVE_HOME;
+use move_package::BuildConfig;
+use move_symbol_pool::Symbol;
+use std::{fs, path::Path, process::Command};
+use tempfile::tempdir;
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-C", &repo.to_string_lossy()])
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// Commits a version of the `Dep` package, and returns the commit.
+fn commit_dep(repo: &Path, value: u64) -> String {
+    let dep = repo.join("dep");
+    fs::create_dir_all(dep.join("sources")).unwrap();
+    fs::write(
+        dep.join("Move.toml"),
+        "[package]\nname = \"Dep\"\nversion = \"0.0.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dep.join("sources/dep.move"),
+        format!("module 0x3::dep {{ public fun f(): u64 {{ {} }} }}", value),
+    )
+    .unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-m", &format!("Version {}", value)]);
+    git(repo, &["rev-parse", "HEAD"])
+}
+
+fn write_package(root: &Path, name: &str, repo: &Path) {
+    fs::create_dir_all(root.join("sources")).unwrap();
+    fs::write(
+        root.join("Move.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.0.0\"\n\n[dependencies]\n\
+            Dep = {{ git = \"{}\", rev = \"main\", subdir = \"dep\" }}\n",
+            name,
+            repo.display()
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn locked_commits_are_checked_out_apart() {
+    let dir = tempdir().unwrap();
+    // Must be set before the Move home is first read
+    std::env::set_var("MOVE_HOME", dir.path().join("move_home"));
+    assert!(MOVE_HOME.starts_with(&dir.path().to_string_lossy().to_string()));
+
+    let repo = dir.path().join("repo");
+    fs::create_dir_all(&repo).unwrap();
+    git(&repo, &["init"]);
+    let locked_commit = commit_dep(&repo, 1);
+    git(&repo, &["branch", "-M", "main"]);
+
+    // Locking downloads the revision to the directory shared by all packages
+    let root = dir.path().join("root");
+    write_package(&root, "Root", &repo);
+    let lock_file = BuildConfig::default()
+        .update_lock_file(&root, &mut Vec::new())
+        .unwrap();
+    assert_eq!(
+        lock_file.packages[0].commit.as_deref(),
+        Some(locked_commit.as_str())
+    );
+
+    // Another package moves the shared directory to the latest commit of the revision
+    let latest_commit = commit_dep(&repo, 2);
+    let other = dir.path().join("other");
+    write_package(&other, "Other", &repo);
+    let graph = BuildConfig::default()
+        .resolution_graph_for_package(&other, &mut Vec::new())
+        .unwrap();
+    let shared_path = graph.package_table[&Symbol::from("Dep")]
+        .package_path
+        .clone();
+    assert_eq!(git(&shared_path, &["rev-parse", "HEAD"]), latest_commit);
+
+    // The locked package gets the locked commit in its own directory, and leaves the shared
+    // directory at the latest commit
+    let graph = BuildConfig::default()
+        .resolution_graph_for_package(&root, &mut Vec::new())
+        .unwrap();
+    let locked_path = &graph.package_table[&Symbol::from("Dep")].package_path;
+    assert_ne!(locked_path, &shared_path);
+    assert!(locked_path
+        .to_string_lossy()
+        .contains(locked_commit.as_str()));
+    assert_eq!(git(locked_path, &["rev-parse", "HEAD"]), locked_commit);
+    assert_eq!(git(&shared_path, &["rev-parse", "HEAD"]), latest_commit);
+}
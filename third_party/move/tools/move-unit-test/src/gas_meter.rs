@@ -0,0 +1,64 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module allows tests to be metered with the gas schedule of a custom Move environment.
+//!
+//! By default, the execution of a test is only bounded by the number of instructions it executes.
+//! With a `TestGasSchedule`, every test is instead charged like a transaction running it would be
+//! in the environment, which gives the gas used by the test and allows checking it against the
+//! budget given in its `#[expected_gas_below=...]` attribute.
+
+use move_binary_format::errors::VMResult;
+use move_core_types::{effects::ChangeSet, identifier::IdentStr, language_storage::ModuleId};
+use move_vm_runtime::{
+    move_vm::MoveVM,
+    native_extensions::NativeContextExtensions,
+    session::{SerializedReturnValues, Session},
+};
+use std::fmt::Debug;
+
+/// Gas used by a test, in the gas units of the environment
+#[derive(Debug, Default, Clone, Copy, Ord, PartialOrd, PartialEq, Eq)]
+pub struct GasUsage {
+    pub execution: u64,
+    pub io: u64,
+    pub storage: u64,
+}
+
+impl GasUsage {
+    pub fn total(&self) -> u64 {
+        self.execution + self.io + self.storage
+    }
+}
+
+/// The gas schedule of a Move environment, which creates a fresh meter for every test.
+pub trait TestGasSchedule: Debug + Send + Sync {
+    fn new_meter(&self) -> Box<dyn TestGasMeter>;
+}
+
+/// Meters a single test.
+///
+/// `GasMeter` can't be used as a trait object, so the meter executes the test function itself,
+/// with the `GasMeter` of the environment.
+pub trait TestGasMeter {
+    fn execute_function_bypass_visibility(
+        &mut self,
+        session: &mut Session,
+        module_id: &ModuleId,
+        function_name: &IdentStr,
+        args: Vec<Vec<u8>>,
+    ) -> VMResult<SerializedReturnValues>;
+
+    /// Charges for the changes made by the test, like they would be charged for a transaction.
+    ///
+    /// Changes kept by native extensions of the environment, e.g. to tables or events, can be
+    /// taken out of `extensions` to charge them.
+    fn charge_change_set(
+        &mut self,
+        move_vm: &MoveVM,
+        change_set: &ChangeSet,
+        extensions: &mut NativeContextExtensions,
+    ) -> VMResult<()>;
+
+    fn gas_used(&self) -> GasUsage;
+}
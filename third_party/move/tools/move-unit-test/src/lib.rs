@@ -4,10 +4,11 @@
 
 pub mod cargo_runner;
 pub mod extensions;
+pub mod gas_meter;
 pub mod test_reporter;
 pub mod test_runner;
 
-use crate::test_runner::TestRunner;
+use crate::{gas_meter::TestGasSchedule, test_reporter::TestResults, test_runner::TestRunner};
 use clap::*;
 use move_command_line_common::files::verify_and_create_named_address_mapping;
 use move_compiler::{
@@ -21,10 +22,10 @@ use move_core_types::language_storage::ModuleId;
 use move_vm_runtime::native_functions::NativeFunctionTable;
 use move_vm_test_utils::gas_schedule::CostTable;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     io::{Result, Write},
     marker::Send,
-    sync::Mutex,
+    sync::{Arc, Mutex},
 };
 
 /// The default value bounding the amount of gas consumed in a test.
@@ -41,6 +42,15 @@ pub struct UnitTestingConfig {
     #[clap(name = "filter", short = 'f', long = "filter")]
     pub filter: Option<String>,
 
+    /// Only run the tests of these modules
+    #[clap(skip)]
+    pub module_filter: Option<BTreeSet<ModuleId>>,
+
+    /// Meter the tests with this gas schedule, instead of bounding the number of instructions
+    /// they execute, and report the gas they use
+    #[clap(skip)]
+    pub gas_schedule: Option<Arc<dyn TestGasSchedule>>,
+
     /// List all tests
     #[clap(name = "list", short = 'l', long = "list")]
     pub list: bool,
@@ -128,6 +138,8 @@ impl UnitTestingConfig {
         Self {
             gas_limit: bound.or(Some(DEFAULT_EXECUTION_BOUND)),
             filter: None,
+            module_filter: None,
+            gas_schedule: None,
             num_threads: 8,
             report_statistics: false,
             report_storage_on_error: false,
@@ -218,6 +230,25 @@ impl UnitTestingConfig {
         cost_table: Option<CostTable>,
         writer: W,
     ) -> Result<(W, bool)> {
+        let (writer, test_results) = self.run_and_report_unit_tests_with_results(
+            test_plan,
+            native_function_table,
+            cost_table,
+            writer,
+        )?;
+        let ok = test_results.map_or(true, |test_results| test_results.failed().is_empty());
+        Ok((writer, ok))
+    }
+
+    /// Like `run_and_report_unit_tests`, but returns the results of the tests, for tools to
+    /// inspect them. There are no results if the tests were only listed.
+    pub fn run_and_report_unit_tests_with_results<W: Write + Send>(
+        &self,
+        test_plan: TestPlan,
+        native_function_table: Option<NativeFunctionTable>,
+        cost_table: Option<CostTable>,
+        writer: W,
+    ) -> Result<(W, Option<TestResults>)> {
         let shared_writer = Mutex::new(writer);
 
         if self.list {
@@ -231,7 +262,7 @@ impl UnitTestingConfig {
                     )?;
                 }
             }
-            return Ok((shared_writer.into_inner().unwrap(), true));
+            return Ok((shared_writer.into_inner().unwrap(), None));
         }
 
         writeln!(shared_writer.lock().unwrap(), "Running Move unit tests")?;
@@ -243,6 +274,7 @@ impl UnitTestingConfig {
             test_plan,
             native_function_table,
             cost_table,
+            self.gas_schedule.clone(),
             self.verbose,
             #[cfg(feature = "evm-backend")]
             self.evm,
@@ -253,19 +285,27 @@ impl UnitTestingConfig {
             test_runner.filter(filter_str)
         }
 
+        if let Some(modules) = &self.module_filter {
+            test_runner.filter_modules(modules)
+        }
+
         let test_results = test_runner.run(&shared_writer).unwrap();
         if self.report_statistics {
             test_results.report_statistics(&shared_writer)?;
         }
 
+        if self.gas_schedule.is_some() {
+            test_results.report_gas_usage(&shared_writer)?;
+        }
+
         if self.verbose {
             test_results.report_goldens(&shared_writer)?;
         }
 
-        let ok = test_results.summarize(&shared_writer)?;
+        test_results.summarize(&shared_writer)?;
 
         let writer = shared_writer.into_inner().unwrap();
-        Ok((writer, ok))
+        Ok((writer, Some(test_results)))
     }
 }
 
@@ -2,7 +2,7 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::format_module_id;
+use crate::{format_module_id, gas_meter::GasUsage};
 use codespan_reporting::files::{Files, SimpleFiles};
 use colored::{control, Colorize};
 use move_binary_format::{
@@ -38,6 +38,8 @@ pub enum FailureReason {
     UnexpectedError(String, MoveError),
     // Test timed out
     Timeout(String),
+    // Used at least as much gas as its budget
+    GasBudgetExceeded(String, u64, u64),
     // The execution results of the Move VM and stackless VM does not match
     Mismatch {
         move_vm_return_values: Box<VMResult<Vec<Vec<u8>>>>,
@@ -66,6 +68,7 @@ pub struct TestRunInfo {
     pub function_ident: String,
     pub elapsed_time: Duration,
     pub instructions_executed: u64,
+    pub gas_used: Option<GasUsage>,
 }
 
 #[derive(Debug, Clone)]
@@ -87,6 +90,14 @@ impl TestRunInfo {
             function_ident,
             elapsed_time,
             instructions_executed,
+            gas_used: None,
+        }
+    }
+
+    pub fn with_gas_used(self, gas_used: GasUsage) -> Self {
+        Self {
+            gas_used: Some(gas_used),
+            ..self
         }
     }
 }
@@ -120,6 +131,14 @@ impl FailureReason {
         FailureReason::Timeout("Test timed out".to_string())
     }
 
+    pub fn gas_budget_exceeded(budget: u64, gas_used: u64) -> Self {
+        FailureReason::GasBudgetExceeded(
+            "Test did not stay below its gas budget".to_string(),
+            budget,
+            gas_used,
+        )
+    }
+
     pub fn mismatch(
         move_vm_return_values: VMResult<Vec<Vec<u8>>>,
         move_vm_change_set: VMResult<ChangeSet>,
@@ -163,6 +182,9 @@ impl TestFailure {
         let error_string = match &self.failure_reason {
             FailureReason::NoError(message) => message.to_string(),
             FailureReason::Timeout(message) => message.to_string(),
+            FailureReason::GasBudgetExceeded(message, budget, gas_used) => format!(
+                "{message}. Expected test to use less than {budget} gas, but it used {gas_used}"
+            ),
             FailureReason::WrongError(message, expected, actual) => {
                 let base_message = format!(
                     "{message}. Expected test {} but instead it {} rooted here",
@@ -557,8 +579,121 @@ impl TestResults {
         writeln!(writer.lock().unwrap())
     }
 
+    pub fn report_gas_usage<W: Write>(&self, writer: &Mutex<W>) -> Result<()> {
+        writeln!(writer.lock().unwrap(), "\nGas Usage:\n")?;
+
+        let passed = self
+            .final_statistics
+            .passed
+            .iter()
+            .flat_map(|(module_id, test_results)| {
+                test_results
+                    .iter()
+                    .map(move |test_result| (module_id, test_result))
+            });
+        let failed = self
+            .final_statistics
+            .failed
+            .iter()
+            .flat_map(|(module_id, test_failures)| {
+                test_failures
+                    .iter()
+                    .map(move |test_failure| (module_id, &test_failure.test_run_info))
+            });
+        let mut stats: Vec<_> = passed
+            .chain(failed)
+            .filter_map(|(module_id, test_run_info)| {
+                let qualified_function_name = format!(
+                    "{}::{}",
+                    format_module_id(module_id),
+                    test_run_info.function_ident
+                );
+                Some((qualified_function_name, test_run_info.gas_used?))
+            })
+            .collect();
+        stats.sort();
+
+        if !stats.is_empty() {
+            let max_function_name_size = stats
+                .iter()
+                .map(|(name, _)| name.len())
+                .max()
+                .unwrap_or_default();
+            writeln!(
+                writer.lock().unwrap(),
+                "┌─{:─^width$}─┬─{:─^12}─┬─{:─^12}─┬─{:─^12}─┬─{:─^12}─┐",
+                "",
+                "",
+                "",
+                "",
+                "",
+                width = max_function_name_size,
+            )?;
+            writeln!(
+                writer.lock().unwrap(),
+                "│ {name:^width$} │ {execution:^12} │ {io:^12} │ {storage:^12} │ {total:^12} │",
+                width = max_function_name_size,
+                name = "Test Name",
+                execution = "Execution",
+                io = "IO",
+                storage = "Storage",
+                total = "Total",
+            )?;
+
+            for (qualified_function_name, gas_used) in stats {
+                writeln!(
+                    writer.lock().unwrap(),
+                    "├─{:─^width$}─┼─{:─^12}─┼─{:─^12}─┼─{:─^12}─┼─{:─^12}─┤",
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                    width = max_function_name_size,
+                )?;
+                writeln!(
+                    writer.lock().unwrap(),
+                    "│ {name:<width$} │ {execution:>12} │ {io:>12} │ {storage:>12} │ {total:>12} │",
+                    name = qualified_function_name,
+                    width = max_function_name_size,
+                    execution = gas_used.execution,
+                    io = gas_used.io,
+                    storage = gas_used.storage,
+                    total = gas_used.total(),
+                )?;
+            }
+
+            writeln!(
+                writer.lock().unwrap(),
+                "└─{:─^width$}─┴─{:─^12}─┴─{:─^12}─┴─{:─^12}─┴─{:─^12}─┘",
+                "",
+                "",
+                "",
+                "",
+                "",
+                width = max_function_name_size,
+            )?;
+        }
+
+        writeln!(writer.lock().unwrap())
+    }
+
+    /// The tests that passed, by module
+    pub fn passed(&self) -> &BTreeMap<ModuleId, BTreeSet<TestRunInfo>> {
+        &self.final_statistics.passed
+    }
+
+    /// The tests that failed or timed out, by module
+    pub fn failed(&self) -> &BTreeMap<ModuleId, BTreeSet<TestFailure>> {
+        &self.final_statistics.failed
+    }
+
+    pub fn test_plan(&self) -> &TestPlan {
+        &self.test_plan
+    }
+
     /// Returns `true` if all tests passed, `false` if there was a test failure/timeout
-    pub fn summarize<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
+    pub fn summarize<W: Write>(&self, writer: &Mutex<W>) -> Result<bool> {
         let num_failed_tests = self
             .final_statistics
             .failed
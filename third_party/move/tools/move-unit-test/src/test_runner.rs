@@ -4,6 +4,7 @@
 
 use crate::{
     extensions, format_module_id,
+    gas_meter::TestGasSchedule,
     test_reporter::{
         FailureReason, MoveError, TestFailure, TestResults, TestRunInfo, TestStatistics,
     },
@@ -17,6 +18,7 @@ use move_core_types::{
     account_address::AccountAddress,
     effects::{ChangeSet, Op},
     identifier::IdentStr,
+    language_storage::ModuleId,
     value::serialize_values,
     vm_status::StatusCode,
 };
@@ -30,7 +32,13 @@ use move_vm_test_utils::{
     InMemoryStorage,
 };
 use rayon::prelude::*;
-use std::{io::Write, marker::Send, sync::Mutex, time::Instant};
+use std::{
+    collections::BTreeSet,
+    io::Write,
+    marker::Send,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 #[cfg(feature = "evm-backend")]
 use {
     evm::{backend::MemoryVicinity, ExitReason},
@@ -47,6 +55,7 @@ pub struct SharedTestingConfig {
     report_stacktrace_on_abort: bool,
     execution_bound: u64,
     cost_table: CostTable,
+    gas_schedule: Option<Arc<dyn TestGasSchedule>>,
     native_function_table: NativeFunctionTable,
     starting_storage_state: InMemoryStorage,
     #[allow(dead_code)] // used by some features
@@ -131,6 +140,7 @@ impl TestRunner {
         // we don't have to make assumptions about their gas parameters.
         native_function_table: Option<NativeFunctionTable>,
         cost_table: Option<CostTable>,
+        gas_schedule: Option<Arc<dyn TestGasSchedule>>,
         record_writeset: bool,
         #[cfg(feature = "evm-backend")] evm: bool,
     ) -> Result<Self> {
@@ -160,6 +170,7 @@ impl TestRunner {
                 //
                 // From the API standpoint, we should let the client specify the cost table.
                 cost_table: cost_table.unwrap_or_else(unit_cost_table),
+                gas_schedule,
                 source_files,
                 record_writeset,
                 #[cfg(feature = "evm-backend")]
@@ -187,6 +198,12 @@ impl TestRunner {
             })
     }
 
+    pub fn filter_modules(&mut self, modules: &BTreeSet<ModuleId>) {
+        self.tests
+            .module_tests
+            .retain(|module_id, _| modules.contains(module_id));
+    }
+
     pub fn filter(&mut self, test_name_slice: &str) {
         for (module_id, module_test) in self.tests.module_tests.iter_mut() {
             if module_id.name().as_str().contains(test_name_slice) {
@@ -264,16 +281,29 @@ impl SharedTestingConfig {
         let mut session =
             move_vm.new_session_with_extensions(&self.starting_storage_state, extensions);
         let mut gas_meter = GasStatus::new(&self.cost_table, Gas::new(self.execution_bound));
+        let mut test_gas_meter = self
+            .gas_schedule
+            .as_ref()
+            .map(|gas_schedule| gas_schedule.new_meter());
         // TODO: collect VM logs if the verbose flag (i.e, `self.verbose`) is set
 
         let now = Instant::now();
-        let serialized_return_values_result = session.execute_function_bypass_visibility(
-            &test_plan.module_id,
-            IdentStr::new(function_name).unwrap(),
-            vec![], // no ty args, at least for now
-            serialize_values(test_info.arguments.iter()),
-            &mut gas_meter,
-        );
+        let args = serialize_values(test_info.arguments.iter());
+        let serialized_return_values_result = match &mut test_gas_meter {
+            Some(test_gas_meter) => test_gas_meter.execute_function_bypass_visibility(
+                &mut session,
+                &test_plan.module_id,
+                IdentStr::new(function_name).unwrap(),
+                args,
+            ),
+            None => session.execute_function_bypass_visibility(
+                &test_plan.module_id,
+                IdentStr::new(function_name).unwrap(),
+                vec![], // no ty args, at least for now
+                args,
+                &mut gas_meter,
+            ),
+        };
         let mut return_result = serialized_return_values_result.map(|res| {
             res.return_values
                 .into_iter()
@@ -285,20 +315,39 @@ impl SharedTestingConfig {
                 err.remove_exec_state();
             }
         }
-        let test_run_info = TestRunInfo::new(
-            function_name.to_string(),
-            now.elapsed(),
-            // TODO(Gas): This doesn't look quite right...
-            //            We're not computing the number of instructions executed even with a unit gas schedule.
-            Gas::new(self.execution_bound)
-                .checked_sub(gas_meter.remaining_gas())
-                .unwrap()
-                .into(),
-        );
-        match session.finish_with_extensions() {
-            Ok((cs, extensions)) => (Ok(cs), Ok(extensions), return_result, test_run_info),
-            Err(err) => (Err(err.clone()), Err(err), return_result, test_run_info),
-        }
+        let elapsed_time = now.elapsed();
+        let (cs_result, mut ext_result) = match session.finish_with_extensions() {
+            Ok((cs, extensions)) => (Ok(cs), Ok(extensions)),
+            Err(err) => (Err(err.clone()), Err(err)),
+        };
+        let test_run_info = match &mut test_gas_meter {
+            Some(test_gas_meter) => {
+                // Writes are charged after execution, like they are for a transaction
+                let charged = match (&cs_result, &mut ext_result) {
+                    (Ok(cs), Ok(extensions)) if return_result.is_ok() => {
+                        test_gas_meter.charge_change_set(&move_vm, cs, extensions)
+                    },
+                    _ => Ok(()),
+                };
+                if let Err(err) = charged {
+                    return_result = Err(err);
+                }
+                let gas_used = test_gas_meter.gas_used();
+                TestRunInfo::new(function_name.to_string(), elapsed_time, gas_used.total())
+                    .with_gas_used(gas_used)
+            },
+            None => TestRunInfo::new(
+                function_name.to_string(),
+                elapsed_time,
+                // TODO(Gas): This doesn't look quite right...
+                //            We're not computing the number of instructions executed even with a unit gas schedule.
+                Gas::new(self.execution_bound)
+                    .checked_sub(gas_meter.remaining_gas())
+                    .unwrap()
+                    .into(),
+            ),
+        };
+        (cs_result, ext_result, return_result, test_run_info)
     }
 
     fn exec_module_tests_move_vm_and_stackless_vm(
@@ -336,6 +385,29 @@ impl SharedTestingConfig {
                     None
                 }
             };
+            // A test that behaves as expected still fails if it uses more gas than its budget.
+            // Budgets are skipped without a gas schedule, as the gas used isn't measured then.
+            let pass = |stats: &mut TestStatistics, test_run_info: TestRunInfo| match (
+                test_info.expected_gas_below,
+                test_run_info.gas_used,
+            ) {
+                (Some(budget), Some(gas_used)) if gas_used.total() >= budget => {
+                    output.fail(function_name);
+                    stats.test_failure(
+                        TestFailure::new(
+                            FailureReason::gas_budget_exceeded(budget, gas_used.total()),
+                            test_run_info,
+                            None,
+                            None,
+                        ),
+                        test_plan,
+                    )
+                },
+                _ => {
+                    output.pass(function_name);
+                    stats.test_success(test_run_info, test_plan);
+                },
+            };
             match exec_result {
                 Err(err) => {
                     let actual_err =
@@ -343,22 +415,19 @@ impl SharedTestingConfig {
                     assert!(err.major_status() != StatusCode::EXECUTED);
                     match test_info.expected_failure.as_ref() {
                         Some(ExpectedFailure::Expected) => {
-                            output.pass(function_name);
-                            stats.test_success(test_run_info, test_plan);
+                            pass(&mut stats, test_run_info);
                         },
                         Some(ExpectedFailure::ExpectedWithError(expected_err))
                             if expected_err == &actual_err =>
                         {
-                            output.pass(function_name);
-                            stats.test_success(test_run_info, test_plan);
+                            pass(&mut stats, test_run_info);
                         },
                         Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(code))
                             if actual_err.0 == StatusCode::ABORTED
                                 && actual_err.1.is_some()
                                 && actual_err.1.unwrap() == *code =>
                         {
-                            output.pass(function_name);
-                            stats.test_success(test_run_info, test_plan);
+                            pass(&mut stats, test_run_info);
                         },
                         // incorrect cases
                         Some(ExpectedFailure::ExpectedWithError(expected_err)) => {
@@ -430,8 +499,7 @@ impl SharedTestingConfig {
                         )
                     } else {
                         // Expected the test to execute fully and it did
-                        output.pass(function_name);
-                        stats.test_success(test_run_info, test_plan);
+                        pass(&mut stats, test_run_info);
                     }
                 },
             }
@@ -2,20 +2,38 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use move_binary_format::errors::VMResult;
 use move_command_line_common::testing::{
     add_update_baseline_fix, format_diff, read_env_update_baseline, EXP_EXT,
 };
-use move_unit_test::{self, UnitTestingConfig};
+use move_core_types::{
+    effects::{ChangeSet, Op},
+    identifier::IdentStr,
+    language_storage::ModuleId,
+};
+use move_unit_test::{
+    self,
+    gas_meter::{GasUsage, TestGasMeter, TestGasSchedule},
+    UnitTestingConfig,
+};
+use move_vm_runtime::{
+    move_vm::MoveVM,
+    native_extensions::NativeContextExtensions,
+    session::{SerializedReturnValues, Session},
+};
+use move_vm_types::gas::UnmeteredGasMeter;
 use regex::RegexBuilder;
 use std::{
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 // We don't support statistics tests as that includes times which are variable and will make these
 // tests flaky.
 const TEST_MODIFIER_STRS: &[&str] = &[
     "storage",
+    "gas",
     #[cfg(feature = "evm-backend")]
     "evm",
 ];
@@ -24,6 +42,7 @@ pub fn modify(mut base_config: UnitTestingConfig, modifier_str: &str) -> Option<
     // Add future test modifiers here
     match modifier_str {
         "storage" => base_config.report_storage_on_error = true,
+        "gas" => base_config.gas_schedule = Some(Arc::new(WriteCountingGasSchedule)),
         #[cfg(feature = "evm-backend")]
         "evm" => base_config.evm = true,
         _ => return None,
@@ -31,6 +50,56 @@ pub fn modify(mut base_config: UnitTestingConfig, modifier_str: &str) -> Option<
     Some(base_config)
 }
 
+/// A gas schedule charging one unit of IO gas per write, and one unit of storage gas per byte
+/// written. Execution is free, so that the gas used doesn't change with the compiled code.
+#[derive(Debug)]
+struct WriteCountingGasSchedule;
+
+struct WriteCountingGasMeter(GasUsage);
+
+impl TestGasSchedule for WriteCountingGasSchedule {
+    fn new_meter(&self) -> Box<dyn TestGasMeter> {
+        Box::new(WriteCountingGasMeter(GasUsage::default()))
+    }
+}
+
+impl TestGasMeter for WriteCountingGasMeter {
+    fn execute_function_bypass_visibility(
+        &mut self,
+        session: &mut Session,
+        module_id: &ModuleId,
+        function_name: &IdentStr,
+        args: Vec<Vec<u8>>,
+    ) -> VMResult<SerializedReturnValues> {
+        session.execute_function_bypass_visibility(
+            module_id,
+            function_name,
+            vec![],
+            args,
+            &mut UnmeteredGasMeter,
+        )
+    }
+
+    fn charge_change_set(
+        &mut self,
+        _move_vm: &MoveVM,
+        change_set: &ChangeSet,
+        _extensions: &mut NativeContextExtensions,
+    ) -> VMResult<()> {
+        for (_, _, op) in change_set.resources() {
+            self.0.io += 1;
+            if let Op::New(bytes) | Op::Modify(bytes) = op {
+                self.0.storage += bytes.len() as u64;
+            }
+        }
+        Ok(())
+    }
+
+    fn gas_used(&self) -> GasUsage {
+        self.0
+    }
+}
+
 fn run_test_with_modifiers(
     unit_test_config: UnitTestingConfig,
     path: &Path,
@@ -0,0 +1,22 @@
+address 0x1 {
+module M {
+    struct R has key { v: u64 }
+
+    #[test]
+    #[expected_gas_below=1]
+    fun no_writes() { }
+
+    #[test(a=@0x1)]
+    #[expected_gas_below=10]
+    fun within_budget(a: signer) {
+        move_to(&a, R { v: 0 });
+    }
+
+    // budgets are only checked when running with a gas schedule
+    #[test(a=@0x1)]
+    #[expected_gas_below=9]
+    fun over_budget(a: signer) {
+        move_to(&a, R { v: 0 });
+    }
+}
+}
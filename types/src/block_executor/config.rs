@@ -1,7 +1,10 @@
 // Copyright © Aptos Foundation
 
-use crate::on_chain_config::BlockGasLimitType;
+use crate::{
+    block_executor::conflict_history::ConflictHistory, on_chain_config::BlockGasLimitType,
+};
 use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc};
 
 /// Local, per-node configuration.
 #[derive(Clone, Debug)]
@@ -13,6 +16,10 @@ pub struct BlockExecutorLocalConfig {
     // If true, we will discard the failed blocks and continue with the next block.
     // (allow_fallback needs to be set)
     pub discard_failed_blocks: bool,
+    // If specified, parallel execution records a trace of every block, written to this directory.
+    pub execution_trace_dir: Option<PathBuf>,
+    // If specified, the conflicts observed in every block are recorded into this history.
+    pub conflict_history: Option<Arc<ConflictHistory>>,
 }
 
 /// Configuration from on-chain configuration, that is
@@ -72,6 +79,8 @@ impl BlockExecutorConfig {
                 concurrency_level,
                 allow_fallback: true,
                 discard_failed_blocks: false,
+                execution_trace_dir: None,
+                conflict_history: None,
             },
             onchain: BlockExecutorConfigFromOnchain::new_no_block_limit(),
         }
@@ -86,6 +95,8 @@ impl BlockExecutorConfig {
                 concurrency_level,
                 allow_fallback: true,
                 discard_failed_blocks: false,
+                execution_trace_dir: None,
+                conflict_history: None,
             },
             onchain: BlockExecutorConfigFromOnchain::new_maybe_block_limit(maybe_block_gas_limit),
         }
@@ -0,0 +1,110 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::Mutex;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+/// Weight of the previous score kept when a new block is recorded.
+const DECAY_FACTOR: f64 = 0.8;
+/// Scores below this threshold are dropped from the history.
+const MIN_TRACKED_SCORE: f64 = 0.05;
+/// Upper bound on the number of keys kept in the history.
+const MAX_TRACKED_KEYS: usize = 100_000;
+
+/// Keeps exponentially decayed counts of the read/write conflicts observed per key in the
+/// recently executed blocks, so that transactions can be ordered ahead of execution based
+/// on which keys tend to be contended.
+///
+/// The block executor records into the history passed in its local config, if any.
+/// Keys are type-erased by hashing, as the block executor is generic over its key type.
+/// The history depends on the blocks executed locally, so it must not influence anything
+/// that is required to be deterministic across nodes.
+#[derive(Debug)]
+pub struct ConflictHistory {
+    scores: Mutex<HashMap<u64, f64>>,
+}
+
+impl ConflictHistory {
+    pub fn new() -> Self {
+        Self {
+            scores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn key_hash<K: Hash>(key: &K) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Records the keys that caused conflicts in a block, one entry per observed conflict.
+    pub fn record_block(&self, conflicting_key_hashes: &[u64]) {
+        let mut scores = self.scores.lock();
+        scores.retain(|_, score| {
+            *score *= DECAY_FACTOR;
+            *score >= MIN_TRACKED_SCORE
+        });
+        for key_hash in conflicting_key_hashes {
+            *scores.entry(*key_hash).or_insert(0.0) += 1.0;
+        }
+
+        if scores.len() > MAX_TRACKED_KEYS {
+            let mut sorted_scores: Vec<f64> = scores.values().copied().collect();
+            let num_to_drop = scores.len() - MAX_TRACKED_KEYS;
+            let (_, threshold, _) =
+                sorted_scores.select_nth_unstable_by(num_to_drop, |a, b| a.total_cmp(b));
+            let threshold = *threshold;
+            scores.retain(|_, score| *score >= threshold);
+        }
+    }
+
+    /// Returns the decayed number of conflicts per block observed on the key.
+    pub fn conflict_score(&self, key_hash: u64) -> f64 {
+        self.scores.lock().get(&key_hash).copied().unwrap_or(0.0)
+    }
+
+    pub fn num_tracked_keys(&self) -> usize {
+        self.scores.lock().len()
+    }
+
+    pub fn clear(&self) {
+        self.scores.lock().clear();
+    }
+}
+
+impl Default for ConflictHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflict_scores_decay() {
+        let history = ConflictHistory::new();
+        let hot_key = ConflictHistory::key_hash(&"hot");
+        let cold_key = ConflictHistory::key_hash(&"cold");
+
+        history.record_block(&[hot_key, hot_key, cold_key]);
+        assert_eq!(history.conflict_score(hot_key), 2.0);
+        assert_eq!(history.conflict_score(cold_key), 1.0);
+
+        history.record_block(&[hot_key]);
+        assert!((history.conflict_score(hot_key) - 2.6).abs() < 1e-9);
+        assert!((history.conflict_score(cold_key) - 0.8).abs() < 1e-9);
+
+        // The cold key is eventually dropped from the history
+        for _ in 0..20 {
+            history.record_block(&[hot_key]);
+        }
+        assert!(history.conflict_score(hot_key) > 4.0);
+        assert_eq!(history.conflict_score(cold_key), 0.0);
+        assert_eq!(history.num_tracked_keys(), 1);
+    }
+}
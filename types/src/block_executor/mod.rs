@@ -3,4 +3,5 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod config;
+pub mod conflict_history;
 pub mod partitioner;
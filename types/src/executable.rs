@@ -8,7 +8,7 @@ use crate::{
 use aptos_crypto::HashValue;
 use std::sync::Arc;
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum ExecutableDescriptor {
     /// Possibly speculative, based on code published during the block.
     Published(HashValue),
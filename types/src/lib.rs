@@ -34,6 +34,7 @@ pub mod proof;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod proptest_types;
 pub mod randomness;
+pub mod resource_usage;
 pub mod serde_helper;
 pub mod stake_pool;
 pub mod staking_contract;
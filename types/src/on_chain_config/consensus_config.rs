@@ -423,11 +423,29 @@ pub struct ProposerAndVoterConfig {
     pub use_history_from_previous_epoch_max_count: u32,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FastestRespondersConfig {
+    // Window into history considered for anchor latencies, multiplier
+    // on top of number of validators
+    pub window_num_validators_multiplier: usize,
+    // Percentage of the total voting power, taken from the validators with
+    // the lowest anchor latency first, that is eligible to be elected as anchors,
+    // i.e. 67 is 67%.
+    pub eligible_voting_power_percent: u64,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AnchorElectionMode {
     RoundRobin,
     LeaderReputation(LeaderReputationType),
+    // Anchors are chosen pseudo-randomly per round, proportional to voting power
+    StakeWeighted,
+    // Anchors are chosen, proportional to voting power, among the validators
+    // ranked by their average anchor latency (the time the DAG took to commit
+    // their recent anchors, with failed anchors charged the whole wait), from
+    // the lowest. Validators without a measured latency are always eligible.
+    FastestResponders(FastestRespondersConfig),
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
@@ -496,6 +514,34 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_dag_anchor_election_mode_serialization() {
+        for anchor_election_mode in [
+            AnchorElectionMode::RoundRobin,
+            AnchorElectionMode::StakeWeighted,
+            AnchorElectionMode::FastestResponders(FastestRespondersConfig {
+                window_num_validators_multiplier: 2,
+                eligible_voting_power_percent: 67,
+            }),
+        ] {
+            let config = OnChainConsensusConfig::V3 {
+                alg: ConsensusAlgorithmConfig::DAG(DagConsensusConfigV1 {
+                    anchor_election_mode: anchor_election_mode.clone(),
+                    ..DagConsensusConfigV1::default()
+                }),
+                vtxn: ValidatorTxnConfig::default_disabled(),
+            };
+
+            let s = bcs::to_bytes(&config).unwrap();
+            let result = bcs::from_bytes::<OnChainConsensusConfig>(&s).unwrap();
+            assert_eq!(result, config);
+
+            let s = serde_yaml::to_string(&config).unwrap();
+            let result = serde_yaml::from_str::<OnChainConsensusConfig>(&s).unwrap();
+            assert_eq!(result, config);
+        }
+    }
+
     #[test]
     fn test_config_onchain_payload() {
         let consensus_config = OnChainConsensusConfig::V1(ConsensusConfigV1 {
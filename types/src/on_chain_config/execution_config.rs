@@ -151,6 +151,7 @@ pub enum TransactionShufflerType {
         module_conflict_window_size: u32,
         entry_fun_conflict_window_size: u32,
     },
+    ConflictAware,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -39,8 +39,8 @@ pub use self::{
     },
     consensus_config::{
         AnchorElectionMode, ConsensusAlgorithmConfig, ConsensusConfigV1, DagConsensusConfigV1,
-        LeaderReputationType, OnChainConsensusConfig, ProposerAndVoterConfig, ProposerElectionType,
-        ValidatorTxnConfig,
+        FastestRespondersConfig, LeaderReputationType, OnChainConsensusConfig,
+        ProposerAndVoterConfig, ProposerElectionType, ValidatorTxnConfig,
     },
     execution_config::{
         BlockGasLimitType, ExecutionConfigV1, ExecutionConfigV2, ExecutionConfigV4,
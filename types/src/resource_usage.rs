@@ -0,0 +1,33 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Resources used by the VM to execute a transaction, reported for capacity planning when
+/// enabled on the VM. Unlike the `FeeStatement`, these are raw quantities that are not
+/// weighted by the gas schedule, and they are not part of the consensus-critical output.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TransactionResourceUsage {
+    /// Total size of the resources loaded by the Move VM.
+    pub bytes_read: u64,
+    /// Total size of the keys and values of the resources written by the transaction.
+    pub bytes_written: u64,
+    /// Number of distinct resources read or written by the transaction.
+    pub num_state_keys_touched: u64,
+    /// Number of native function calls.
+    pub num_native_calls: u64,
+    /// Wall-clock time spent executing the transaction in the VM.
+    pub execution_time: Duration,
+}
+
+impl TransactionResourceUsage {
+    /// Adds up the usage of several transactions.
+    pub fn accumulate(&mut self, other: &Self) {
+        self.bytes_read += other.bytes_read;
+        self.bytes_written += other.bytes_written;
+        self.num_state_keys_touched += other.num_state_keys_touched;
+        self.num_native_calls += other.num_native_calls;
+        self.execution_time += other.execution_time;
+    }
+}
@@ -8,8 +8,8 @@ use crate::{
     on_chain_config::{CurrentTimeMicroseconds, Features, OnChainConfig, TransactionFeeBurnCap},
     state_store::{state_key::StateKey, table::TableHandle},
     transaction::{
-        signature_verified_transaction::SignatureVerifiedTransaction, Transaction,
-        TransactionPayload,
+        signature_verified_transaction::SignatureVerifiedTransaction, SignedTransaction,
+        Transaction, TransactionPayload,
     },
 };
 use aptos_crypto::HashValue;
@@ -69,6 +69,16 @@ impl StorageLocation {
 impl AnalyzedTransaction {
     pub fn new(transaction: SignatureVerifiedTransaction) -> Self {
         let (read_hints, write_hints) = transaction.get_read_write_hints();
+        Self::new_with_hints(transaction, read_hints, write_hints)
+    }
+
+    /// Creates an `AnalyzedTransaction` with hints that were derived externally, e.g. from the
+    /// write set of an already committed transaction.
+    pub fn new_with_hints(
+        transaction: SignatureVerifiedTransaction,
+        read_hints: Vec<StorageLocation>,
+        write_hints: Vec<StorageLocation>,
+    ) -> Self {
         let hints_contain_wildcard = read_hints
             .iter()
             .chain(write_hints.iter())
@@ -245,6 +255,39 @@ pub fn empty_rw_set() -> (Vec<StorageLocation>, Vec<StorageLocation>) {
     (vec![], vec![])
 }
 
+/// Returns the read/write hints of a user transaction, or `None` if they cannot be derived
+/// from its payload. Only coin transfer and create account transactions are supported for now.
+pub fn try_get_read_write_hints(
+    signed_txn: &SignedTransaction,
+) -> Option<(Vec<StorageLocation>, Vec<StorageLocation>)> {
+    let func = match signed_txn.payload() {
+        TransactionPayload::EntryFunction(func) => func,
+        _ => return None,
+    };
+    let sender_address = signed_txn.sender();
+    let receiver_address = || bcs::from_bytes(func.args().first()?).ok();
+    match (
+        *func.module().address(),
+        func.module().name().as_str(),
+        func.function().as_str(),
+    ) {
+        (AccountAddress::ONE, "coin", "transfer") => Some(rw_set_for_coin_transfer(
+            sender_address,
+            receiver_address()?,
+            true,
+        )),
+        (AccountAddress::ONE, "aptos_account", "transfer") => Some(rw_set_for_coin_transfer(
+            sender_address,
+            receiver_address()?,
+            false,
+        )),
+        (AccountAddress::ONE, "aptos_account", "create_account") => Some(
+            rw_set_for_create_account(sender_address, receiver_address()?),
+        ),
+        _ => None,
+    }
+}
+
 trait AnalyzedTransactionProvider {
     fn get_read_write_hints(&self) -> (Vec<StorageLocation>, Vec<StorageLocation>);
 }
@@ -252,44 +295,12 @@ trait AnalyzedTransactionProvider {
 impl AnalyzedTransactionProvider for Transaction {
     fn get_read_write_hints(&self) -> (Vec<StorageLocation>, Vec<StorageLocation>) {
         match self {
-            Transaction::UserTransaction(signed_txn) => match signed_txn.payload() {
-                TransactionPayload::EntryFunction(func) => {
-                    match (
-                        *func.module().address(),
-                        func.module().name().as_str(),
-                        func.function().as_str(),
-                    ) {
-                        (AccountAddress::ONE, "coin", "transfer") => {
-                            let sender_address = signed_txn.sender();
-                            let receiver_address = bcs::from_bytes(&func.args()[0]).unwrap();
-                            rw_set_for_coin_transfer(
-                                sender_address,
-                                receiver_address,
-                                true,
-                            )
-                        },
-                        (AccountAddress::ONE, "aptos_account", "transfer") => {
-                            let sender_address = signed_txn.sender();
-                            let receiver_address = bcs::from_bytes(&func.args()[0]).unwrap();
-                            rw_set_for_coin_transfer(
-                                sender_address,
-                                receiver_address,
-                                false,
-                            )
-                        },
-                        (AccountAddress::ONE, "aptos_account", "create_account") => {
-                            let sender_address = signed_txn.sender();
-                            let receiver_address = bcs::from_bytes(&func.args()[0]).unwrap();
-                            rw_set_for_create_account(
-                                sender_address,
-                                receiver_address,
-                            )
-                        },
-                        _ => todo!("Only coin transfer and create account transactions are supported for now")
-                    }
-                },
-                _ => todo!("Only entry function transactions are supported for now"),
-            },
+            Transaction::UserTransaction(signed_txn) => try_get_read_write_hints(signed_txn)
+                .unwrap_or_else(|| {
+                    todo!(
+                        "Only coin transfer and create account transactions are supported for now"
+                    )
+                }),
             _ => empty_rw_set(),
         }
     }
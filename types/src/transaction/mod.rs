@@ -55,7 +55,8 @@ use crate::state_store::create_empty_sharded_state_updates;
 use crate::{
     block_metadata_ext::BlockMetadataExt, contract_event::TransactionEvent, executable::ModulePath,
     fee_statement::FeeStatement, proof::accumulator::InMemoryEventAccumulator,
-    validator_txn::ValidatorTransaction, write_set::TransactionWrite,
+    resource_usage::TransactionResourceUsage, validator_txn::ValidatorTransaction,
+    write_set::TransactionWrite,
 };
 pub use block_output::BlockOutput;
 pub use change_set::ChangeSet;
@@ -77,6 +78,59 @@ use std::{collections::BTreeSet, hash::Hash, ops::Deref, sync::atomic::AtomicU64
 pub type Version = u64; // Height - also used for MVCC in StateDB
 pub type AtomicVersion = AtomicU64;
 
+/// Sequence numbers at or above this value do not order a transaction within its sender's
+/// sequence, but carry the nonce of an orderless transaction in their lower 63 bits. The
+/// prologue always rejected them as too big, so no committed transaction uses them.
+pub const ORDERLESS_SEQUENCE_NUMBER_START: u64 = 1 << 63;
+
+/// How a user transaction is protected against being executed more than once.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ReplayProtector {
+    /// The transaction must match the sender's next sequence number, which it then increments.
+    SequenceNumber(u64),
+    /// The transaction can execute in any order, as long as the nonce was not used by another
+    /// transaction of the same sender that has not expired yet.
+    Nonce(u64),
+}
+
+impl ReplayProtector {
+    /// Decodes the replay protector from the `sequence_number` field of a raw transaction.
+    pub fn from_sequence_number_field(sequence_number: u64) -> Self {
+        if Self::is_orderless(sequence_number) {
+            ReplayProtector::Nonce(sequence_number - ORDERLESS_SEQUENCE_NUMBER_START)
+        } else {
+            ReplayProtector::SequenceNumber(sequence_number)
+        }
+    }
+
+    /// Encodes the replay protector into the `sequence_number` field of a raw transaction.
+    pub fn to_sequence_number_field(self) -> u64 {
+        match self {
+            ReplayProtector::SequenceNumber(sequence_number) => {
+                assert!(
+                    !Self::is_orderless(sequence_number),
+                    "Sequence number {} overlaps with orderless nonces",
+                    sequence_number
+                );
+                sequence_number
+            },
+            ReplayProtector::Nonce(nonce) => {
+                assert!(
+                    !Self::is_orderless(nonce),
+                    "Nonce {} does not fit into 63 bits",
+                    nonce
+                );
+                ORDERLESS_SEQUENCE_NUMBER_START + nonce
+            },
+        }
+    }
+
+    /// Returns whether the `sequence_number` field of a raw transaction holds a nonce.
+    pub fn is_orderless(sequence_number: u64) -> bool {
+        sequence_number >= ORDERLESS_SEQUENCE_NUMBER_START
+    }
+}
+
 /// RawTransaction is the portion of a transaction that a client signs.
 #[derive(
     Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, CryptoHasher, BCSCryptoHash,
@@ -134,6 +188,29 @@ impl RawTransaction {
         }
     }
 
+    /// Create a new orderless `RawTransaction` with a payload. Instead of a sequence number, it
+    /// is protected against replays by a nonce that the sender must not reuse before
+    /// `expiration_timestamp_secs`.
+    pub fn new_orderless(
+        sender: AccountAddress,
+        nonce: u64,
+        payload: TransactionPayload,
+        max_gas_amount: u64,
+        gas_unit_price: u64,
+        expiration_timestamp_secs: u64,
+        chain_id: ChainId,
+    ) -> Self {
+        RawTransaction {
+            sender,
+            sequence_number: ReplayProtector::Nonce(nonce).to_sequence_number_field(),
+            payload,
+            max_gas_amount,
+            gas_unit_price,
+            expiration_timestamp_secs,
+            chain_id,
+        }
+    }
+
     /// Create a new `RawTransaction` with a script.
     ///
     /// A script transaction contains only code to execute. No publishing is allowed in scripts.
@@ -351,6 +428,41 @@ impl RawTransaction {
         self.sender
     }
 
+    /// Return how this transaction is protected against replays.
+    pub fn replay_protector(&self) -> ReplayProtector {
+        ReplayProtector::from_sequence_number_field(self.sequence_number)
+    }
+
+    /// Return the sequence number field of this transaction.
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+
+    /// Return the payload of this transaction.
+    pub fn payload(&self) -> &TransactionPayload {
+        &self.payload
+    }
+
+    /// Return the maximal total gas to spend for this transaction.
+    pub fn max_gas_amount(&self) -> u64 {
+        self.max_gas_amount
+    }
+
+    /// Return the price to be paid per gas unit.
+    pub fn gas_unit_price(&self) -> u64 {
+        self.gas_unit_price
+    }
+
+    /// Return the expiration timestamp of this transaction, in seconds from the Unix Epoch.
+    pub fn expiration_timestamp_secs(&self) -> u64 {
+        self.expiration_timestamp_secs
+    }
+
+    /// Return the chain ID this transaction is intended for.
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
+    }
+
     /// Return the signing message for creating transaction signature.
     pub fn signing_message(&self) -> Result<Vec<u8>, CryptoMaterialError> {
         signing_message(self)
@@ -691,6 +803,10 @@ impl SignedTransaction {
         self.raw_txn.sequence_number
     }
 
+    pub fn replay_protector(&self) -> ReplayProtector {
+        self.raw_txn.replay_protector()
+    }
+
     pub fn chain_id(&self) -> ChainId {
         self.raw_txn.chain_id
     }
@@ -1037,7 +1153,7 @@ impl VMValidatorResult {
 }
 
 /// The output of executing a transaction.
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TransactionOutput {
     /// The list of writes this transaction intends to do.
     write_set: WriteSet,
@@ -1050,8 +1166,26 @@ pub struct TransactionOutput {
 
     /// The execution status.
     status: TransactionStatus,
+
+    /// The resources used by the VM, if it was asked to report them. This is only informational,
+    /// and is never persisted or sent over the network.
+    #[serde(skip)]
+    resource_usage: Option<TransactionResourceUsage>,
 }
 
+// Resource usage is informational only, and depends on the node and on timing, so two outputs of
+// the same transaction are equal regardless of it.
+impl PartialEq for TransactionOutput {
+    fn eq(&self, other: &Self) -> bool {
+        self.write_set == other.write_set
+            && self.events == other.events
+            && self.gas_used == other.gas_used
+            && self.status == other.status
+    }
+}
+
+impl Eq for TransactionOutput {}
+
 impl TransactionOutput {
     pub fn new(
         write_set: WriteSet,
@@ -1064,9 +1198,19 @@ impl TransactionOutput {
             events,
             gas_used,
             status,
+            resource_usage: None,
         }
     }
 
+    pub fn with_resource_usage(mut self, resource_usage: Option<TransactionResourceUsage>) -> Self {
+        self.resource_usage = resource_usage;
+        self
+    }
+
+    pub fn resource_usage(&self) -> Option<&TransactionResourceUsage> {
+        self.resource_usage.as_ref()
+    }
+
     pub fn into(self) -> (WriteSet, Vec<ContractEvent>) {
         (self.write_set, self.events)
     }
@@ -1102,6 +1246,7 @@ impl TransactionOutput {
             events,
             gas_used,
             status,
+            resource_usage: _,
         } = self;
         (write_set, events, gas_used, status)
     }
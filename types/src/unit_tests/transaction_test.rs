@@ -5,10 +5,14 @@
 use crate::{
     account_address::AccountAddress,
     chain_id::ChainId,
+    resource_usage::TransactionResourceUsage,
     transaction::{
-        AccountTransactionsWithProof, RawTransaction, Script, SignedTransaction, Transaction,
-        TransactionInfo, TransactionListWithProof, TransactionPayload, TransactionWithProof,
+        AccountTransactionsWithProof, ExecutionStatus, RawTransaction, ReplayProtector, Script,
+        SignedTransaction, Transaction, TransactionInfo, TransactionListWithProof,
+        TransactionOutput, TransactionPayload, TransactionStatus, TransactionWithProof,
+        ORDERLESS_SEQUENCE_NUMBER_START,
     },
+    write_set::WriteSet,
 };
 use aptos_crypto::{
     ed25519::{self, Ed25519PrivateKey, Ed25519Signature},
@@ -16,7 +20,31 @@ use aptos_crypto::{
 };
 use bcs::test_helpers::assert_canonical_encode_decode;
 use proptest::prelude::*;
-use std::convert::TryFrom;
+use std::{convert::TryFrom, time::Duration};
+
+#[test]
+fn test_replay_protector_encoding() {
+    let txn = RawTransaction::new_orderless(
+        AccountAddress::random(),
+        42,
+        TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+        0,
+        0,
+        0,
+        ChainId::test(),
+    );
+    assert_eq!(txn.replay_protector(), ReplayProtector::Nonce(42));
+
+    for sequence_number in [0, 7, ORDERLESS_SEQUENCE_NUMBER_START - 1] {
+        let protector = ReplayProtector::from_sequence_number_field(sequence_number);
+        assert_eq!(protector, ReplayProtector::SequenceNumber(sequence_number));
+        assert_eq!(protector.to_sequence_number_field(), sequence_number);
+    }
+    assert_eq!(
+        ReplayProtector::from_sequence_number_field(u64::MAX),
+        ReplayProtector::Nonce(ORDERLESS_SEQUENCE_NUMBER_START - 1)
+    );
+}
 
 #[test]
 fn test_invalid_signature() {
@@ -39,6 +67,47 @@ fn test_invalid_signature() {
     )
 }
 
+#[test]
+fn test_output_equality_ignores_resource_usage() {
+    let output = TransactionOutput::new(
+        WriteSet::default(),
+        vec![],
+        7,
+        TransactionStatus::Keep(ExecutionStatus::Success),
+    );
+    let usage = TransactionResourceUsage {
+        bytes_read: 100,
+        bytes_written: 50,
+        num_state_keys_touched: 3,
+        num_native_calls: 2,
+        execution_time: Duration::from_micros(10),
+    };
+    let tracked = output.clone().with_resource_usage(Some(usage));
+    assert_eq!(tracked.resource_usage(), Some(&usage));
+    assert_eq!(output, tracked);
+
+    let mut other_usage = usage;
+    other_usage.execution_time = Duration::from_micros(20);
+    assert_eq!(
+        tracked,
+        output.clone().with_resource_usage(Some(other_usage))
+    );
+
+    let other_gas = TransactionOutput::new(
+        WriteSet::default(),
+        vec![],
+        8,
+        TransactionStatus::Keep(ExecutionStatus::Success),
+    )
+    .with_resource_usage(Some(usage));
+    assert_ne!(tracked, other_gas);
+
+    // Resource usage is never serialized.
+    let decoded: TransactionOutput = bcs::from_bytes(&bcs::to_bytes(&tracked).unwrap()).unwrap();
+    assert_eq!(decoded.resource_usage(), None);
+    assert_eq!(decoded, tracked);
+}
+
 proptest! {
     #[test]
     fn test_sign_raw_transaction(raw_txn in any::<RawTransaction>(), keypair in ed25519::keypair_strategy()) {